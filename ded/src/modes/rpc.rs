@@ -51,6 +51,7 @@ impl Rpc {
             request_id,
             user_id,
             data,
+            session_token: Vec::new(),
         };
 
         // encode message
@@ -66,6 +66,8 @@ fn main() {
         &[
             "rpc/qaul_rpc.proto",
             "rpc/debug.proto",
+            "rpc/configuration.proto",
+            "rpc/encryption.proto",
             "connections/connections.proto",
             "node/node.proto",
             "node/user_accounts.proto",
@@ -107,6 +109,16 @@ fn main() {
                 to.join("qaul.rpc.debug.rs"),
             )
             .unwrap();
+            fs::copy(
+                Path::new(&out_dir).join("qaul.rpc.configuration.rs"),
+                to.join("qaul.rpc.configuration.rs"),
+            )
+            .unwrap();
+            fs::copy(
+                Path::new(&out_dir).join("qaul.rpc.encryption.rs"),
+                to.join("qaul.rpc.encryption.rs"),
+            )
+            .unwrap();
             fs::copy(
                 Path::new(&out_dir).join("qaul.rpc.connections.rs"),
                 to.join("qaul.rpc.connections.rs"),
@@ -227,6 +239,16 @@ fn main() {
                 Path::new("src/rpc/qaul.rpc.debug.rs"),
             )
             .unwrap();
+            fs::copy(
+                &Path::new(&out_dir).join("qaul.rpc.configuration.rs"),
+                Path::new("src/rpc/qaul.rpc.configuration.rs"),
+            )
+            .unwrap();
+            fs::copy(
+                &Path::new(&out_dir).join("qaul.rpc.encryption.rs"),
+                Path::new("src/rpc/qaul.rpc.encryption.rs"),
+            )
+            .unwrap();
             fs::copy(
                 &Path::new(&out_dir).join("qaul.rpc.connections.rs"),
                 Path::new("src/connections/qaul.rpc.connections.rs"),
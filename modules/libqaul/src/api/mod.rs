@@ -110,6 +110,15 @@ pub fn initialization_finished() -> bool {
     false
 }
 
+/// Check if libqaul waits for the storage to be unlocked
+///
+/// When encryption at rest is enabled, libqaul only finishes
+/// initializing after it was unlocked with the storage passphrase
+/// via an RPC message of the encryption module.
+pub fn storage_locked() -> bool {
+    crate::storage::encryption::Encryption::is_locked()
+}
+
 /// send an RPC message to libqaul
 pub fn send_rpc(binary_message: Vec<u8>) {
     Rpc::send_to_libqaul(binary_message);
@@ -139,3 +148,11 @@ pub fn send_sys(binary_message: Vec<u8>) {
 pub fn receive_sys() -> Result<Vec<u8>, TryRecvError> {
     Sys::receive_from_libqaul()
 }
+
+/// attach a host polling the SYS messages via `receive_sys`
+///
+/// SYS messages are only queued for polling
+/// after a host has been attached.
+pub fn attach_sys() {
+    Sys::attach_host();
+}
@@ -0,0 +1,667 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Loopback BLE Host
+//!
+//! A software stand-in for the BLE module of the host system.
+//! It implements the `qaul.sys.ble` protocol in-process, so that
+//! the BLE connection module can be exercised without radios.
+//!
+//! All hosts created from a `LoopbackRadio` share a simulated medium:
+//!
+//! * Started hosts discover each other when they are in range.
+//!   All hosts are in range by default.
+//! * Moving hosts out of range reports them as unavailable.
+//! * Direct messages are split into fragments fitting the ATT MTU,
+//!   and reassembled by the receiving host.
+//! * Fragments can be dropped with a configurable rate.
+//!   A message with a lost fragment is discarded by the receiver
+//!   and reported as failed to the sender.
+//!
+//! A host is either driven directly by `send` and `receive`,
+//! or attached to the running libqaul node via `attach`.
+
+use prost::Message;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::proto;
+use crate::rpc::sys::Sys;
+
+/// ATT protocol overhead of a GATT write in bytes
+const ATT_OVERHEAD: usize = 3;
+/// fragment header: index and count as big endian u16
+const FRAGMENT_HEADER: usize = 4;
+/// polling interval of an attached host
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Options of the simulated medium
+#[derive(Debug, Clone)]
+pub struct LoopbackOptions {
+    /// ATT MTU in bytes
+    ///
+    /// 23 bytes is the BLE 4.0 default,
+    /// newer devices negotiate up to 517 bytes.
+    pub mtu: usize,
+    /// probability that a fragment is lost, from 0.0 to 1.0
+    pub drop_rate: f64,
+    /// signal strength reported on discovery
+    pub rssi: i32,
+    /// seed of the random number generator, for reproducible drops
+    pub seed: u64,
+}
+
+impl Default for LoopbackOptions {
+    fn default() -> Self {
+        LoopbackOptions {
+            mtu: 185,
+            drop_rate: 0.0,
+            rssi: -60,
+            seed: 0,
+        }
+    }
+}
+
+/// Simulated BLE medium shared by loopback hosts
+#[derive(Clone)]
+pub struct LoopbackRadio {
+    medium: Arc<Mutex<Medium>>,
+}
+
+impl LoopbackRadio {
+    /// create a new simulated medium
+    pub fn new(options: LoopbackOptions) -> Self {
+        let rng = StdRng::seed_from_u64(options.seed);
+        LoopbackRadio {
+            medium: Arc::new(Mutex::new(Medium {
+                options,
+                rng,
+                hosts: BTreeMap::new(),
+                out_of_range: BTreeSet::new(),
+                next_host: 0,
+            })),
+        }
+    }
+
+    /// create a new host on this medium
+    pub fn host(&self) -> LoopbackHost {
+        let mut medium = self.medium.lock().unwrap();
+        let id = medium.next_host;
+        medium.next_host += 1;
+        medium.hosts.insert(id, HostState::default());
+
+        LoopbackHost {
+            id,
+            medium: self.medium.clone(),
+        }
+    }
+
+    /// move two hosts in or out of each others range
+    pub fn set_in_range(&self, a: &LoopbackHost, b: &LoopbackHost, in_range: bool) {
+        let mut medium = self.medium.lock().unwrap();
+        medium.set_in_range(a.id, b.id, in_range);
+    }
+
+    /// change the probability that a fragment is lost
+    pub fn set_drop_rate(&self, drop_rate: f64) {
+        let mut medium = self.medium.lock().unwrap();
+        medium.options.drop_rate = drop_rate.clamp(0.0, 1.0);
+    }
+}
+
+/// Loopback BLE host of a single node
+pub struct LoopbackHost {
+    id: u32,
+    medium: Arc<Mutex<Medium>>,
+}
+
+impl LoopbackHost {
+    /// process a sys message from the node
+    pub fn send(&self, data: &[u8]) {
+        let mut medium = self.medium.lock().unwrap();
+        medium.process(self.id, data);
+    }
+
+    /// get the next sys message for the node
+    pub fn receive(&self) -> Option<Vec<u8>> {
+        let mut medium = self.medium.lock().unwrap();
+        medium.receive(self.id)
+    }
+
+    /// Attach this host to the running libqaul node
+    ///
+    /// The sys messages of libqaul are relayed to this host
+    /// and its answers back to libqaul on a background thread.
+    /// libqaul needs to be started before.
+    pub fn attach(self) -> thread::JoinHandle<()> {
+        Sys::attach_host();
+        thread::spawn(move || loop {
+            while let Ok(message) = Sys::receive_from_libqaul() {
+                self.send(&message);
+            }
+            while let Some(message) = self.receive() {
+                Sys::send_to_libqaul(message);
+            }
+            thread::sleep(POLL_INTERVAL);
+        })
+    }
+}
+
+/// State of a host in the medium
+#[derive(Default)]
+struct HostState {
+    /// small qaul id, set when the host is started
+    qaul_id: Option<Vec<u8>>,
+    /// sys messages to the node
+    outbox: VecDeque<Vec<u8>>,
+    /// received fragments with the small id of the sender
+    fragments: VecDeque<(Vec<u8>, Vec<u8>)>,
+    /// messages in reassembly, by small id of the sender
+    partial: BTreeMap<Vec<u8>, Partial>,
+}
+
+/// Message in reassembly
+struct Partial {
+    count: u16,
+    next: u16,
+    data: Vec<u8>,
+}
+
+impl HostState {
+    /// queue a sys message to the node
+    fn push(&mut self, message: proto::ble::Message) {
+        let message = proto::Ble {
+            message: Some(message),
+        };
+        self.outbox.push_back(message.encode_to_vec());
+    }
+
+    /// add a fragment to the reassembly
+    ///
+    /// Returns the message when the last fragment was added.
+    /// On a missing fragment the partial message is discarded.
+    fn reassemble(&mut self, from: Vec<u8>, frame: Vec<u8>) -> Option<Vec<u8>> {
+        if frame.len() < FRAGMENT_HEADER {
+            return None;
+        }
+        let index = u16::from_be_bytes([frame[0], frame[1]]);
+        let count = u16::from_be_bytes([frame[2], frame[3]]);
+
+        if index == 0 {
+            self.partial.insert(
+                from.clone(),
+                Partial {
+                    count,
+                    next: 0,
+                    data: Vec::new(),
+                },
+            );
+        }
+        match self.partial.get_mut(&from) {
+            Some(partial) if partial.next == index && partial.count == count => {
+                partial.data.extend_from_slice(&frame[FRAGMENT_HEADER..]);
+                partial.next += 1;
+                if partial.next < partial.count {
+                    return None;
+                }
+            }
+            _ => {
+                log::debug!("loopback BLE fragment missing, message discarded");
+                self.partial.remove(&from);
+                return None;
+            }
+        }
+        self.partial.remove(&from).map(|partial| partial.data)
+    }
+}
+
+/// Simulated medium
+struct Medium {
+    options: LoopbackOptions,
+    rng: StdRng,
+    hosts: BTreeMap<u32, HostState>,
+    /// pairs of hosts which are out of range
+    out_of_range: BTreeSet<(u32, u32)>,
+    next_host: u32,
+}
+
+impl Medium {
+    /// process a sys message from the node of a host
+    fn process(&mut self, id: u32, data: &[u8]) {
+        let message = match proto::Ble::decode(data) {
+            Ok(proto::Ble {
+                message: Some(message),
+            }) => message,
+            _ => {
+                log::error!("loopback BLE host received an invalid sys message");
+                return;
+            }
+        };
+
+        match message {
+            proto::ble::Message::InfoRequest(_) => {
+                let device = proto::BleDeviceInfo {
+                    ble_support: true,
+                    id: format!("loopback-{}", id),
+                    name: "qaul loopback BLE".to_string(),
+                    bluetooth_on: true,
+                    adv_extended: true,
+                    adv_extended_bytes: 251,
+                    le_2m: true,
+                    ..Default::default()
+                };
+                self.push(
+                    id,
+                    proto::ble::Message::InfoResponse(proto::BleInfoResponse {
+                        device: Some(device),
+                    }),
+                );
+            }
+            proto::ble::Message::StartRequest(request) => self.start(id, request.qaul_id),
+            proto::ble::Message::StopRequest(_) => self.stop(id),
+            proto::ble::Message::DirectSend(send) => self.direct_send(id, send),
+            _ => log::warn!("loopback BLE host: unexpected sys message"),
+        }
+    }
+
+    /// get the next sys message for the node of a host
+    fn receive(&mut self, id: u32) -> Option<Vec<u8>> {
+        let host = self.hosts.get_mut(&id)?;
+        while let Some((from, frame)) = host.fragments.pop_front() {
+            if let Some(data) = host.reassemble(from.clone(), frame) {
+                host.push(proto::ble::Message::DirectReceived(
+                    proto::BleDirectReceived { from, data },
+                ));
+            }
+        }
+        host.outbox.pop_front()
+    }
+
+    /// start advertising and scanning
+    fn start(&mut self, id: u32, qaul_id: Vec<u8>) {
+        if qaul_id.is_empty() {
+            self.push(
+                id,
+                proto::ble::Message::StartResult(proto::BleStartResult {
+                    success: false,
+                    error_reason: proto::BleError::UnknownError.into(),
+                    error_message: "qaul id missing".to_string(),
+                }),
+            );
+            return;
+        }
+        if let Some(host) = self.hosts.get_mut(&id) {
+            host.qaul_id = Some(qaul_id);
+        }
+        self.push(
+            id,
+            proto::ble::Message::StartResult(proto::BleStartResult {
+                success: true,
+                ..Default::default()
+            }),
+        );
+
+        // discover the started hosts in range
+        let others: Vec<u32> = self.hosts.keys().copied().filter(|o| *o != id).collect();
+        for other in others {
+            self.link_changed(id, other, true);
+        }
+    }
+
+    /// stop advertising and scanning
+    fn stop(&mut self, id: u32) {
+        let others: Vec<u32> = self.hosts.keys().copied().filter(|o| *o != id).collect();
+        for other in others {
+            self.link_changed(id, other, false);
+        }
+        if let Some(host) = self.hosts.get_mut(&id) {
+            host.qaul_id = None;
+            host.fragments.clear();
+            host.partial.clear();
+        }
+        self.push(
+            id,
+            proto::ble::Message::StopResult(proto::BleStopResult {
+                success: true,
+                ..Default::default()
+            }),
+        );
+    }
+
+    /// send a direct message in fragments
+    fn direct_send(&mut self, id: u32, send: proto::BleDirectSend) {
+        let result = self.transmit(id, &send);
+        self.push(
+            id,
+            proto::ble::Message::DirectSendResult(proto::BleDirectSendResult {
+                id: send.message_id,
+                success: result.is_ok(),
+                error_message: result.err().unwrap_or_default(),
+            }),
+        );
+    }
+
+    /// transmit the fragments of a message to the receiving host
+    fn transmit(&mut self, id: u32, send: &proto::BleDirectSend) -> Result<(), String> {
+        let sender_id = self
+            .qaul_id(id)
+            .ok_or_else(|| "module not started".to_string())?;
+        let receiver = self
+            .hosts
+            .iter()
+            .find(|(other, host)| **other != id && host.qaul_id.as_ref() == Some(&send.receiver_id))
+            .map(|(other, _)| *other)
+            .filter(|other| self.in_range(id, *other))
+            .ok_or_else(|| "device not available".to_string())?;
+
+        let frames = fragment(&send.data, self.options.mtu)?;
+        let mut lost = false;
+        for frame in frames {
+            if self.rng.random::<f64>() < self.options.drop_rate {
+                lost = true;
+                continue;
+            }
+            if let Some(host) = self.hosts.get_mut(&receiver) {
+                host.fragments.push_back((sender_id.clone(), frame));
+            }
+        }
+
+        match lost {
+            true => Err("fragment lost".to_string()),
+            false => Ok(()),
+        }
+    }
+
+    /// move two hosts in or out of each others range
+    fn set_in_range(&mut self, a: u32, b: u32, in_range: bool) {
+        if a == b || self.in_range(a, b) == in_range {
+            return;
+        }
+        // hosts are only informed while in range
+        if in_range {
+            self.out_of_range.remove(&pair(a, b));
+            self.link_changed(a, b, true);
+        } else {
+            self.link_changed(a, b, false);
+            self.out_of_range.insert(pair(a, b));
+        }
+    }
+
+    /// inform two started hosts in range about each other
+    fn link_changed(&mut self, a: u32, b: u32, available: bool) {
+        if !self.in_range(a, b) {
+            return;
+        }
+        let (a_id, b_id) = match (self.qaul_id(a), self.qaul_id(b)) {
+            (Some(a_id), Some(b_id)) => (a_id, b_id),
+            _ => return,
+        };
+        let rssi = self.options.rssi;
+
+        for (host, other_id) in [(a, b_id), (b, a_id)] {
+            let message = match available {
+                true => proto::ble::Message::DeviceDiscovered(proto::BleDeviceDiscovered {
+                    qaul_id: other_id,
+                    rssi,
+                }),
+                false => {
+                    if let Some(state) = self.hosts.get_mut(&host) {
+                        state.partial.remove(&other_id);
+                        state.fragments.retain(|(from, _)| *from != other_id);
+                    }
+                    proto::ble::Message::DeviceUnavailable(proto::BleDeviceUnavailable {
+                        qaul_id: other_id,
+                    })
+                }
+            };
+            self.push(host, message);
+        }
+    }
+
+    fn in_range(&self, a: u32, b: u32) -> bool {
+        !self.out_of_range.contains(&pair(a, b))
+    }
+
+    fn qaul_id(&self, id: u32) -> Option<Vec<u8>> {
+        self.hosts.get(&id).and_then(|host| host.qaul_id.clone())
+    }
+
+    fn push(&mut self, id: u32, message: proto::ble::Message) {
+        if let Some(host) = self.hosts.get_mut(&id) {
+            host.push(message);
+        }
+    }
+}
+
+/// ordered key of a pair of hosts
+fn pair(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
+}
+
+/// split a message into fragments fitting the ATT MTU
+fn fragment(data: &[u8], mtu: usize) -> Result<Vec<Vec<u8>>, String> {
+    let size = mtu.saturating_sub(ATT_OVERHEAD + FRAGMENT_HEADER).max(1);
+    let chunks: Vec<&[u8]> = match data.is_empty() {
+        true => vec![data],
+        false => data.chunks(size).collect(),
+    };
+    let count = u16::try_from(chunks.len()).map_err(|_| "message too large".to_string())?;
+
+    Ok(chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut frame = Vec::with_capacity(FRAGMENT_HEADER + chunk.len());
+            frame.extend_from_slice(&(index as u16).to_be_bytes());
+            frame.extend_from_slice(&count.to_be_bytes());
+            frame.extend_from_slice(chunk);
+            frame
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sys(message: proto::ble::Message) -> Vec<u8> {
+        proto::Ble {
+            message: Some(message),
+        }
+        .encode_to_vec()
+    }
+
+    fn start(host: &LoopbackHost, qaul_id: &[u8]) {
+        host.send(&sys(proto::ble::Message::StartRequest(
+            proto::BleStartRequest {
+                qaul_id: qaul_id.to_vec(),
+                power_setting: 0,
+            },
+        )));
+    }
+
+    fn direct_send(host: &LoopbackHost, receiver_id: &[u8], data: Vec<u8>) {
+        host.send(&sys(proto::ble::Message::DirectSend(
+            proto::BleDirectSend {
+                message_id: vec![1],
+                receiver_id: receiver_id.to_vec(),
+                sender_id: Vec::new(),
+                data,
+            },
+        )));
+    }
+
+    fn messages(host: &LoopbackHost) -> Vec<proto::ble::Message> {
+        std::iter::from_fn(|| host.receive())
+            .filter_map(|data| proto::Ble::decode(&data[..]).unwrap().message)
+            .collect()
+    }
+
+    #[test]
+    fn test_discovery_and_fragmented_send() {
+        let radio = LoopbackRadio::new(LoopbackOptions {
+            mtu: 23,
+            ..Default::default()
+        });
+        let a = radio.host();
+        let b = radio.host();
+        start(&a, b"node-a");
+        start(&b, b"node-b");
+
+        assert!(messages(&a).iter().any(|message| matches!(
+            message,
+            proto::ble::Message::DeviceDiscovered(device) if device.qaul_id == b"node-b"
+        )));
+        messages(&b);
+
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        direct_send(&a, b"node-b", data.clone());
+        assert!(matches!(
+            messages(&a).as_slice(),
+            [proto::ble::Message::DirectSendResult(result)] if result.success
+        ));
+        assert!(matches!(
+            messages(&b).as_slice(),
+            [proto::ble::Message::DirectReceived(received)]
+                if received.from == b"node-a" && received.data == data
+        ));
+    }
+
+    #[test]
+    fn test_drops_and_out_of_range() {
+        let radio = LoopbackRadio::new(LoopbackOptions::default());
+        let a = radio.host();
+        let b = radio.host();
+        start(&a, b"node-a");
+        start(&b, b"node-b");
+        messages(&a);
+        messages(&b);
+
+        // a lost fragment discards the message
+        radio.set_drop_rate(1.0);
+        direct_send(&a, b"node-b", vec![0; 500]);
+        assert!(matches!(
+            messages(&a).as_slice(),
+            [proto::ble::Message::DirectSendResult(result)] if !result.success
+        ));
+        assert!(messages(&b).is_empty());
+
+        // out of range
+        radio.set_drop_rate(0.0);
+        radio.set_in_range(&a, &b, false);
+        assert!(matches!(
+            messages(&b).as_slice(),
+            [proto::ble::Message::DeviceUnavailable(device)] if device.qaul_id == b"node-a"
+        ));
+        messages(&a);
+        direct_send(&a, b"node-b", vec![1, 2, 3]);
+        assert!(matches!(
+            messages(&a).as_slice(),
+            [proto::ble::Message::DirectSendResult(result)]
+                if result.error_message == "device not available"
+        ));
+    }
+
+    #[test]
+    fn test_reassembly_discards_gaps() {
+        let mut host = HostState::default();
+        let frames = fragment(&[7; 100], 23).unwrap();
+        assert_eq!(frames.len(), 7);
+
+        assert_eq!(host.reassemble(b"a".to_vec(), frames[0].clone()), None);
+        assert_eq!(host.reassemble(b"a".to_vec(), frames[2].clone()), None);
+        assert!(host.partial.is_empty());
+
+        let mut result = None;
+        for frame in frames {
+            result = host.reassemble(b"a".to_vec(), frame);
+        }
+        assert_eq!(result, Some(vec![7; 100]));
+    }
+
+    /// poll `condition` until it is true or the timeout elapsed
+    fn wait_for(mut condition: impl FnMut() -> bool) -> bool {
+        for _ in 0..3000 {
+            if condition() {
+                return true;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        false
+    }
+
+    #[test]
+    fn test_attach_confirms_node() {
+        use crate::connections::{ble::proto_net, ConnectionModule};
+        use crate::node::Node;
+        use crate::router::neighbours::Neighbours;
+        use crate::utilities::qaul_id::QaulId;
+
+        let path = std::env::temp_dir().join(format!("qaul-loopback-{}", std::process::id()));
+        crate::api::start(path.to_string_lossy().to_string());
+        assert!(wait_for(crate::api::initialization_finished));
+
+        // the host of the node and a remote node driven by the test
+        let radio = LoopbackRadio::new(LoopbackOptions::default());
+        let host = radio.host();
+        let remote = radio.host();
+        let remote_id = libp2p::identity::Keypair::generate_ed25519()
+            .public()
+            .to_peer_id();
+        let remote_small_id = QaulId::to_small(remote_id);
+        start(&remote, &remote_small_id);
+        host.attach();
+
+        // the node starts BLE, discovers the unknown remote
+        // node and asks it for its id
+        let mut requested = false;
+        assert!(wait_for(|| {
+            requested |= messages(&remote).iter().any(|message| match message {
+                proto::ble::Message::DirectReceived(received) => {
+                    received.from == Node::get_small_id()
+                        && matches!(
+                            proto_net::BleMessage::decode(&received.data[..]).unwrap().message,
+                            Some(proto_net::ble_message::Message::Identification(
+                                proto_net::Identification { request: true, .. }
+                            ))
+                        )
+                }
+                _ => false,
+            });
+            requested
+        }));
+        assert!(super::TO_CONFIRM
+            .get()
+            .read()
+            .unwrap()
+            .contains_key(&remote_small_id));
+        assert_eq!(Neighbours::is_neighbour(&remote_id), ConnectionModule::None);
+
+        // the identification confirms the remote node
+        let identification = proto_net::BleMessage {
+            message: Some(proto_net::ble_message::Message::Identification(
+                proto_net::Identification {
+                    request: false,
+                    node: Some(proto_net::NodeIdentification {
+                        id: remote_id.to_bytes(),
+                    }),
+                },
+            )),
+        };
+        direct_send(&remote, &Node::get_small_id(), identification.encode_to_vec());
+
+        assert!(wait_for(|| {
+            Neighbours::is_neighbour(&remote_id) == ConnectionModule::Ble
+        }));
+        assert!(!super::TO_CONFIRM
+            .get()
+            .read()
+            .unwrap()
+            .contains_key(&remote_small_id));
+
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
@@ -9,6 +9,9 @@
 //!
 //! * Android
 //! * iOS
+//!
+//! The `loopback` module provides a software BLE host,
+//! to run the module without radios.
 
 use libp2p::{floodsub::Topic, PeerId};
 use prost::Message;
@@ -39,6 +42,8 @@ pub mod proto_rpc {
     include!("qaul.rpc.ble.rs");
 }
 
+pub mod loopback;
+
 /// Module State
 static BLE: InitCell<RwLock<Ble>> = InitCell::new();
 /// List of detected BLE nodes needing ID confirmation
@@ -132,6 +137,16 @@ impl Ble {
         Self::info_send_request();
     }
 
+    /// a host polling the SYS messages got attached
+    ///
+    /// If the module is already initialized, the info request
+    /// was discarded and is sent again.
+    pub fn host_attached() {
+        if BLE.try_get().is_some() {
+            Self::info_send_request();
+        }
+    }
+
     /// set module status
     fn status_set(status: ModuleStatus) {
         // get module state
@@ -307,7 +322,9 @@ impl Ble {
         log::info!("BLE send stop request");
 
         // create stop message
-        let message = proto::BleStopRequest {};
+        let message = proto::Ble {
+            message: Some(proto::ble::Message::StopRequest(proto::BleStopRequest {})),
+        };
 
         // encode message
         let mut buf = Vec::with_capacity(message.encoded_len());
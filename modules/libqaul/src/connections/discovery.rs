@@ -0,0 +1,713 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Peer Discovery
+//!
+//! **Find qaul nodes beyond the local mDNS discovery.**
+//!
+//! On networks with client isolation mDNS can't find any neighbours.
+//! This module therefore provides two additional ways to find other
+//! qaul nodes:
+//!
+//! * Rendezvous: the node registers itself at a rendezvous server
+//!   and discovers all nodes that registered under the same namespace.
+//! * Peer exchange: neighbours share the addresses of the peers they
+//!   have recently seen via the qaul router info service.
+//!
+//! The Internet connection module dials a bounded number of the
+//! discovered peers and saves them in the peers list of the
+//! configuration file. Only the connected discovered peers take up
+//! one of the `max_auto_peers` slots. When all slots are taken, a
+//! newly connected peer replaces a saved peer which is not connected.
+//!
+//! ```yaml
+//! internet:
+//!   rendezvous:
+//!     enabled: true
+//!     server: /ip4/144.91.74.192/udp/9229/quic-v1/p2p/12D3KooW...
+//!     namespace: qaul
+//!     ttl: 7200
+//!     discover_interval: 60
+//!   peer_exchange:
+//!     enabled: true
+//!     exchange_interval: 60
+//!     max_shared: 20
+//!     max_auto_peers: 5
+//!     seen_period_limit: 3600
+//! ```
+
+use libp2p::{multiaddr::Protocol, rendezvous, swarm::Swarm, Multiaddr, PeerId};
+use state::InitCell;
+use std::{collections::HashMap, sync::RwLock};
+
+use crate::connections::{internet::QaulInternetBehaviour, ConnectionModule};
+use crate::node::Node;
+use crate::router::{info::RouterInfo, neighbours::Neighbours, router_net_proto};
+use crate::storage::configuration::{Configuration, InternetPeer};
+use crate::utilities::timestamp::Timestamp;
+
+/// mutable state of the peer discovery
+static DISCOVERY: InitCell<RwLock<Discovery>> = InitCell::new();
+
+/// time after which an unanswered dial is given up, in milli seconds
+const DIAL_TIMEOUT: u64 = 30 * 1000;
+
+/// time after which an unanswered registration is sent again, in milli seconds
+const RENDEZVOUS_REGISTER_TIMEOUT: u64 = 30 * 1000;
+
+/// share of the registration TTL after which the registration is renewed
+const RENDEZVOUS_RENEW_PERCENT: u64 = 75;
+
+/// A peer and the addresses it was seen on
+#[derive(Clone, Debug)]
+pub struct SeenPeer {
+    /// dialable addresses of the peer
+    pub addresses: Vec<Multiaddr>,
+    /// last time the peer was seen, in milli seconds
+    pub last_seen: u64,
+}
+
+/// Peer Discovery Module
+pub struct Discovery {
+    /// recently seen peers, shared with the neighbours
+    seen: HashMap<PeerId, SeenPeer>,
+    /// peers learned via peer exchange or rendezvous,
+    /// which can be dialed by the Internet module
+    candidates: HashMap<PeerId, SeenPeer>,
+    /// addresses of discovered peers that are currently dialed,
+    /// with the peer id and the time of the dial
+    dialing: HashMap<String, (PeerId, u64)>,
+    /// addresses under which other nodes observe this node
+    observed: Vec<Multiaddr>,
+    /// time of the last peer exchange
+    last_exchange: u64,
+    /// time of the last rendezvous discovery request
+    last_rendezvous_discover: u64,
+    /// time of the last dial attempt to the rendezvous server
+    last_rendezvous_dial: u64,
+    /// time at which the registration at the rendezvous server
+    /// needs to be renewed, 0 if the node isn't registered
+    rendezvous_renew_at: u64,
+    /// rendezvous discovery cookie, to only receive new registrations
+    rendezvous_cookie: Option<rendezvous::Cookie>,
+    /// server and namespace of the current registration
+    rendezvous_registration: Option<(PeerId, rendezvous::Namespace)>,
+}
+
+impl Discovery {
+    /// Initialize peer discovery state
+    pub fn init() {
+        let discovery = Discovery {
+            seen: HashMap::new(),
+            candidates: HashMap::new(),
+            dialing: HashMap::new(),
+            observed: Vec::new(),
+            last_exchange: Timestamp::get_timestamp(),
+            last_rendezvous_discover: 0,
+            last_rendezvous_dial: 0,
+            rendezvous_renew_at: 0,
+            rendezvous_cookie: None,
+            rendezvous_registration: None,
+        };
+        DISCOVERY.set(RwLock::new(discovery));
+    }
+
+    /// Apply changed discovery options
+    ///
+    /// When the rendezvous server or namespace changed, or
+    /// rendezvous was disabled, the node unregisters from the
+    /// previous server. The new server is registered at the
+    /// next `check()`.
+    pub fn apply_configuration(swarm: &mut Swarm<QaulInternetBehaviour>) {
+        let server = Self::rendezvous_server();
+        let namespace = Configuration::get().internet.rendezvous.namespace.clone();
+
+        let previous = {
+            let mut discovery = DISCOVERY.get().write().unwrap();
+            let unchanged = match (&discovery.rendezvous_registration, &server) {
+                (Some((previous_id, previous_namespace)), Some((server_id, _))) => {
+                    previous_id == server_id && previous_namespace.to_string() == namespace
+                }
+                (None, _) => true,
+                _ => false,
+            };
+            if unchanged {
+                return;
+            }
+            discovery.rendezvous_renew_at = 0;
+            discovery.rendezvous_cookie = None;
+            discovery.last_rendezvous_discover = 0;
+            discovery.last_rendezvous_dial = 0;
+            discovery.rendezvous_registration.take()
+        };
+
+        if let Some((previous_id, previous_namespace)) = previous {
+            log::info!("unregister from rendezvous node {}", previous_id);
+            swarm
+                .behaviour_mut()
+                .rendezvous
+                .unregister(previous_namespace, previous_id);
+        }
+    }
+
+    /// Get the configured rendezvous server
+    ///
+    /// Returns the peer id and the address of the server,
+    /// if rendezvous is enabled and the address is valid.
+    pub fn rendezvous_server() -> Option<(PeerId, Multiaddr)> {
+        let config = Configuration::get();
+        if !config.internet.rendezvous.enabled {
+            return None;
+        }
+
+        let address: Multiaddr = match config.internet.rendezvous.server.parse() {
+            Ok(address) => address,
+            Err(e) => {
+                log::error!(
+                    "invalid rendezvous server address {}: {:?}",
+                    config.internet.rendezvous.server,
+                    e
+                );
+                return None;
+            }
+        };
+
+        match address.iter().last() {
+            Some(Protocol::P2p(peer_id)) => Some((peer_id, address)),
+            _ => {
+                log::error!("rendezvous server address needs to end with /p2p/<peer id>");
+                None
+            }
+        }
+    }
+
+    /// Check if an address can be shared with other nodes
+    ///
+    /// Loopback, unspecified and relayed addresses are not dialable
+    /// from other nodes.
+    fn is_shareable(address: &Multiaddr) -> bool {
+        for protocol in address.iter() {
+            match protocol {
+                Protocol::Ip4(ip) => {
+                    if ip.is_loopback() || ip.is_unspecified() || ip.is_link_local() {
+                        return false;
+                    }
+                }
+                Protocol::Ip6(ip) => {
+                    if ip.is_loopback() || ip.is_unspecified() {
+                        return false;
+                    }
+                    // link local addresses fe80::/10
+                    if (ip.segments()[0] & 0xffc0) == 0xfe80 {
+                        return false;
+                    }
+                }
+                Protocol::P2pCircuit => return false,
+                _ => {}
+            }
+        }
+        true
+    }
+
+    /// Remove the `/p2p/` part of an address
+    fn strip_peer_id(address: &Multiaddr) -> Multiaddr {
+        address
+            .iter()
+            .filter(|protocol| !matches!(protocol, Protocol::P2p(_)))
+            .collect()
+    }
+
+    /// Add or update the entry of a peer in a peer list
+    fn update_peer(
+        peers: &mut HashMap<PeerId, SeenPeer>,
+        peer_id: PeerId,
+        addresses: Vec<Multiaddr>,
+        last_seen: u64,
+    ) {
+        let entry = peers.entry(peer_id).or_insert(SeenPeer {
+            addresses: Vec::new(),
+            last_seen,
+        });
+        if last_seen > entry.last_seen {
+            entry.last_seen = last_seen;
+        }
+        for address in addresses {
+            let address = Self::strip_peer_id(&address);
+            if Self::is_shareable(&address) && !entry.addresses.contains(&address) {
+                entry.addresses.push(address);
+            }
+        }
+    }
+
+    /// Add a peer this node is connected to, or has discovered
+    /// in the local network.
+    pub fn add_seen(peer_id: PeerId, addresses: Vec<Multiaddr>) {
+        if peer_id == Node::get_id() {
+            return;
+        }
+
+        let mut discovery = DISCOVERY.get().write().unwrap();
+        Self::update_peer(
+            &mut discovery.seen,
+            peer_id,
+            addresses,
+            Timestamp::get_timestamp(),
+        );
+    }
+
+    /// Add an address under which another node observed this node
+    pub fn add_observed_address(address: Multiaddr) {
+        if !Self::is_shareable(&address) {
+            return;
+        }
+
+        let mut discovery = DISCOVERY.get().write().unwrap();
+        if !discovery.observed.contains(&address) {
+            discovery.observed.push(address);
+        }
+    }
+
+    /// Add peers that can be dialed by the Internet module
+    fn add_candidates(peers: Vec<(PeerId, Vec<Multiaddr>, u64)>) {
+        let node_id = Node::get_id();
+        let mut discovery = DISCOVERY.get().write().unwrap();
+
+        for (peer_id, addresses, last_seen) in peers {
+            if peer_id == node_id {
+                continue;
+            }
+            Self::update_peer(&mut discovery.candidates, peer_id, addresses, last_seen);
+        }
+
+        // remove candidates without any dialable address
+        discovery
+            .candidates
+            .retain(|_id, peer| !peer.addresses.is_empty());
+    }
+
+    /// Create the peer exchange message for the neighbours
+    ///
+    /// Returns None when peer exchange is disabled, or it is not
+    /// yet time to send the next exchange.
+    pub fn create_peer_exchange() -> Option<Vec<u8>> {
+        let options = Configuration::get().internet.peer_exchange.clone();
+        if !options.enabled {
+            return None;
+        }
+
+        let now = Timestamp::get_timestamp();
+        let mut discovery = DISCOVERY.get().write().unwrap();
+        if now < discovery.last_exchange + options.exchange_interval * 1000 {
+            return None;
+        }
+        discovery.last_exchange = now;
+
+        // forget peers that were not seen for a long time
+        let limit = now.saturating_sub(options.seen_period_limit * 1000);
+        discovery.seen.retain(|_id, peer| peer.last_seen >= limit);
+        discovery.candidates.retain(|_id, peer| peer.last_seen >= limit);
+
+        // share the most recently seen peers
+        let mut peers: Vec<(&PeerId, &SeenPeer)> = discovery
+            .seen
+            .iter()
+            .filter(|(_id, peer)| !peer.addresses.is_empty())
+            .collect();
+        if peers.is_empty() {
+            return None;
+        }
+        peers.sort_by(|a, b| b.1.last_seen.cmp(&a.1.last_seen));
+
+        let entries: Vec<router_net_proto::PeerExchangeEntry> = peers
+            .iter()
+            .take(options.max_shared as usize)
+            .map(|(peer_id, peer)| router_net_proto::PeerExchangeEntry {
+                peer_id: peer_id.to_bytes(),
+                addresses: peer.addresses.iter().map(|a| a.to_string()).collect(),
+                last_seen: peer.last_seen,
+            })
+            .collect();
+
+        Some(RouterInfo::create_peer_exchange(&entries))
+    }
+
+    /// Process a received peer exchange message
+    pub fn process_peer_exchange(
+        received_from: &PeerId,
+        entries: Vec<router_net_proto::PeerExchangeEntry>,
+    ) {
+        if !Configuration::get().internet.peer_exchange.enabled {
+            return;
+        }
+
+        log::trace!(
+            "received {} peer exchange entries from {:?}",
+            entries.len(),
+            received_from
+        );
+
+        let mut peers: Vec<(PeerId, Vec<Multiaddr>, u64)> = Vec::new();
+        for entry in entries {
+            let peer_id = match PeerId::from_bytes(&entry.peer_id) {
+                Ok(peer_id) => peer_id,
+                Err(_) => continue,
+            };
+            if peer_id == *received_from {
+                continue;
+            }
+            let addresses: Vec<Multiaddr> = entry
+                .addresses
+                .iter()
+                .filter_map(|address| address.parse().ok())
+                .collect();
+            peers.push((peer_id, addresses, entry.last_seen));
+        }
+
+        Self::add_candidates(peers);
+    }
+
+    /// Process events of the rendezvous client behaviour
+    pub fn rendezvous_event(event: rendezvous::client::Event) {
+        match event {
+            rendezvous::client::Event::Registered {
+                rendezvous_node,
+                ttl,
+                namespace,
+            } => {
+                log::info!(
+                    "registered at rendezvous node {} in namespace {:?} for {}s",
+                    rendezvous_node,
+                    namespace,
+                    ttl
+                );
+                // renew the registration before it runs out
+                let mut discovery = DISCOVERY.get().write().unwrap();
+                discovery.rendezvous_renew_at =
+                    Timestamp::get_timestamp() + ttl * 1000 * RENDEZVOUS_RENEW_PERCENT / 100;
+            }
+            rendezvous::client::Event::RegisterFailed {
+                rendezvous_node,
+                namespace,
+                error,
+            } => {
+                log::error!(
+                    "rendezvous registration at {} in namespace {:?} failed: {:?}",
+                    rendezvous_node,
+                    namespace,
+                    error
+                );
+                let mut discovery = DISCOVERY.get().write().unwrap();
+                discovery.rendezvous_renew_at = 0;
+            }
+            rendezvous::client::Event::Discovered {
+                rendezvous_node,
+                registrations,
+                cookie,
+            } => {
+                log::trace!(
+                    "discovered {} peers at rendezvous node {}",
+                    registrations.len(),
+                    rendezvous_node
+                );
+                let now = Timestamp::get_timestamp();
+                let peers: Vec<(PeerId, Vec<Multiaddr>, u64)> = registrations
+                    .iter()
+                    .map(|registration| {
+                        (
+                            registration.record.peer_id(),
+                            registration.record.addresses().to_vec(),
+                            now,
+                        )
+                    })
+                    .collect();
+                Self::add_candidates(peers);
+
+                let mut discovery = DISCOVERY.get().write().unwrap();
+                discovery.rendezvous_cookie = Some(cookie);
+            }
+            rendezvous::client::Event::DiscoverFailed {
+                rendezvous_node,
+                namespace,
+                error,
+            } => {
+                log::error!(
+                    "rendezvous discovery at {} in namespace {:?} failed: {:?}",
+                    rendezvous_node,
+                    namespace,
+                    error
+                );
+                let mut discovery = DISCOVERY.get().write().unwrap();
+                discovery.rendezvous_cookie = None;
+            }
+            rendezvous::client::Event::Expired { peer } => {
+                log::trace!("rendezvous registration of {} expired", peer);
+                let mut discovery = DISCOVERY.get().write().unwrap();
+                discovery.candidates.remove(&peer);
+            }
+        }
+    }
+
+    /// An Internet connection was established
+    ///
+    /// If the connection was dialed by the discovery, the peer is
+    /// saved to the configuration.
+    pub fn connection_established(peer_id: PeerId, address: &Multiaddr) {
+        let address_string = address.to_string();
+        let dialed;
+        {
+            let mut discovery = DISCOVERY.get().write().unwrap();
+            dialed = discovery.dialing.remove(&address_string).is_some();
+            if dialed {
+                discovery.candidates.remove(&peer_id);
+            }
+        }
+
+        // save discovered peer to configuration
+        if dialed {
+            let result = Configuration::update(|config| {
+                let max_auto_peers = config.internet.peer_exchange.max_auto_peers as usize;
+                let exists = config
+                    .internet
+                    .peers
+                    .iter()
+                    .any(|peer| peer.address == address_string);
+                if exists {
+                    return;
+                }
+
+                // replace the saved peers which are not connected
+                let mut discovered_count = config
+                    .internet
+                    .peers
+                    .iter()
+                    .filter(|peer| peer.discovered)
+                    .count();
+                if discovered_count >= max_auto_peers {
+                    config.internet.peers.retain(|peer| {
+                        if discovered_count < max_auto_peers
+                            || !peer.discovered
+                            || Self::is_connected_peer(peer)
+                        {
+                            return true;
+                        }
+                        log::info!("remove unconnected discovered peer {}", peer.address);
+                        discovered_count -= 1;
+                        false
+                    });
+                }
+
+                if discovered_count < max_auto_peers {
+                    log::info!("save discovered peer {} to configuration", address_string);
+                    config.internet.peers.push(InternetPeer {
+                        address: address_string.clone(),
+                        name: format!("Discovered Node {}", peer_id.to_base58()),
+                        enabled: true,
+                        discovered: true,
+                    });
+                }
+            });
+            if let Err(errors) = result {
+                log::error!(
+                    "discovered peer {} not saved to configuration: {:?}",
+                    address_string,
+                    errors
+                );
+            }
+        }
+
+        Self::add_seen(peer_id, vec![address.clone()]);
+    }
+
+    /// An Internet connection was closed
+    pub fn connection_closed(peer_id: PeerId) {
+        if let Some((server_id, _address)) = Self::rendezvous_server() {
+            if peer_id == server_id {
+                let mut discovery = DISCOVERY.get().write().unwrap();
+                discovery.rendezvous_renew_at = 0;
+            }
+        }
+    }
+
+    /// Dialing a discovered address failed
+    pub fn dial_failed(address: &Multiaddr) {
+        let mut discovery = DISCOVERY.get().write().unwrap();
+        if let Some((peer_id, _dialed_at)) = discovery.dialing.remove(&address.to_string()) {
+            // remove the unreachable address from the candidate
+            let stripped = Self::strip_peer_id(address);
+            if let Some(candidate) = discovery.candidates.get_mut(&peer_id) {
+                candidate.addresses.retain(|a| *a != stripped);
+                if candidate.addresses.is_empty() {
+                    discovery.candidates.remove(&peer_id);
+                }
+            }
+        }
+    }
+
+    /// Process the periodic discovery tasks of the Internet module
+    ///
+    /// * announce observed addresses as external addresses
+    /// * register at and discover from the rendezvous server
+    /// * dial discovered peers, if there are free slots
+    pub fn check(swarm: &mut Swarm<QaulInternetBehaviour>) {
+        // announce observed addresses
+        let observed: Vec<Multiaddr> = {
+            let mut discovery = DISCOVERY.get().write().unwrap();
+            discovery.observed.drain(..).collect()
+        };
+        for address in observed {
+            swarm.add_external_address(address);
+        }
+
+        Self::check_rendezvous(swarm);
+        Self::check_auto_dial(swarm);
+    }
+
+    /// register and discover at the rendezvous server
+    fn check_rendezvous(swarm: &mut Swarm<QaulInternetBehaviour>) {
+        let (server_id, server_address) = match Self::rendezvous_server() {
+            Some(server) => server,
+            None => return,
+        };
+        let (namespace, ttl, interval) = {
+            let config = Configuration::get();
+            (
+                config.internet.rendezvous.namespace.clone(),
+                config.internet.rendezvous.ttl,
+                config.internet.rendezvous.discover_interval,
+            )
+        };
+        let namespace = match rendezvous::Namespace::new(namespace) {
+            Ok(namespace) => namespace,
+            Err(e) => {
+                log::error!("invalid rendezvous namespace: {:?}", e);
+                return;
+            }
+        };
+
+        let now = Timestamp::get_timestamp();
+
+        // connect to rendezvous server
+        if !swarm.is_connected(&server_id) {
+            let mut discovery = DISCOVERY.get().write().unwrap();
+            discovery.rendezvous_renew_at = 0;
+            if now >= discovery.last_rendezvous_dial + interval * 1000 {
+                discovery.last_rendezvous_dial = now;
+                super::internet::Internet::peer_dial(server_address, swarm);
+            }
+            return;
+        }
+
+        let (register, discover, cookie) = {
+            let discovery = DISCOVERY.get().read().unwrap();
+            (
+                now >= discovery.rendezvous_renew_at,
+                now >= discovery.last_rendezvous_discover + interval * 1000,
+                discovery.rendezvous_cookie.clone(),
+            )
+        };
+
+        let rendezvous = &mut swarm.behaviour_mut().rendezvous;
+
+        // register this node, or renew the registration
+        if register {
+            match rendezvous.register(namespace.clone(), server_id, Some(ttl)) {
+                Ok(_) => {
+                    // wait for the answer before registering again
+                    let mut discovery = DISCOVERY.get().write().unwrap();
+                    discovery.rendezvous_renew_at = now + RENDEZVOUS_REGISTER_TIMEOUT;
+                    discovery.rendezvous_registration = Some((server_id, namespace.clone()));
+                }
+                Err(e) => log::trace!("rendezvous register error: {:?}", e),
+            }
+        }
+
+        // discover other nodes
+        if discover {
+            rendezvous.discover(Some(namespace), cookie, None, server_id);
+            let mut discovery = DISCOVERY.get().write().unwrap();
+            discovery.last_rendezvous_discover = now;
+        }
+    }
+
+    /// peer id of a peer of the configuration
+    fn peer_id_of(peer: &InternetPeer) -> Option<PeerId> {
+        let address: Multiaddr = peer.address.parse().ok()?;
+        match address.iter().last() {
+            Some(Protocol::P2p(peer_id)) => Some(peer_id),
+            _ => None,
+        }
+    }
+
+    /// Is the configured peer a connected neighbour
+    fn is_connected_peer(peer: &InternetPeer) -> bool {
+        match Self::peer_id_of(peer) {
+            Some(peer_id) => Neighbours::is_neighbour(&peer_id) == ConnectionModule::Internet,
+            None => false,
+        }
+    }
+
+    /// dial discovered peers, as long as there are free slots
+    ///
+    /// Only the connected discovered peers take up a slot.
+    fn check_auto_dial(swarm: &mut Swarm<QaulInternetBehaviour>) {
+        let free_slots = {
+            let config = Configuration::get();
+            let connected_count = config
+                .internet
+                .peers
+                .iter()
+                .filter(|peer| peer.discovered)
+                .filter_map(Self::peer_id_of)
+                .filter(|peer_id| swarm.is_connected(peer_id))
+                .count();
+            (config.internet.peer_exchange.max_auto_peers as usize).saturating_sub(connected_count)
+        };
+
+        let now = Timestamp::get_timestamp();
+        let mut discovery = DISCOVERY.get().write().unwrap();
+        discovery
+            .dialing
+            .retain(|_address, (_id, dialed_at)| now < *dialed_at + DIAL_TIMEOUT);
+        let free_slots = free_slots.saturating_sub(discovery.dialing.len());
+        if free_slots == 0 {
+            return;
+        }
+
+        // find candidates that are not yet connected or configured
+        let config = Configuration::get();
+        let mut to_dial: Vec<(PeerId, Multiaddr)> = Vec::new();
+        for (peer_id, candidate) in discovery.candidates.iter() {
+            if to_dial.len() >= free_slots {
+                break;
+            }
+            if swarm.is_connected(peer_id)
+                || Neighbours::is_neighbour(peer_id) != ConnectionModule::None
+                || discovery.dialing.values().any(|(id, _)| id == peer_id)
+            {
+                continue;
+            }
+            if let Some(address) = candidate.addresses.first() {
+                let address = match address.clone().with_p2p(*peer_id) {
+                    Ok(address) => address,
+                    Err(_) => continue,
+                };
+                let address_string = address.to_string();
+                if config
+                    .internet
+                    .peers
+                    .iter()
+                    .any(|peer| peer.address == address_string)
+                {
+                    continue;
+                }
+                to_dial.push((*peer_id, address));
+            }
+        }
+
+        for (peer_id, address) in to_dial {
+            log::trace!("dial discovered peer {}", address);
+            discovery.dialing.insert(address.to_string(), (peer_id, now));
+            super::internet::Internet::peer_dial(address, swarm);
+        }
+    }
+}
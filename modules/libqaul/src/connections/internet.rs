@@ -26,12 +26,16 @@
 //!   - /ip6/::/udp/9229/quic-v1
 //!   - /ip6/::/tcp/9229
 //! ```
+//!
+//! Further peers can be discovered via a rendezvous server
+//! or peer exchange, see the [`super::discovery`] module.
 
 use libp2p::{
+    core::transport::ListenerId,
     floodsub::{Floodsub, FloodsubEvent},
     identify,
     identity::Keypair,
-    noise, ping,
+    noise, ping, rendezvous,
     swarm::{NetworkBehaviour, Swarm},
     tcp, yamux, Multiaddr, PeerId, SwarmBuilder,
 };
@@ -43,11 +47,11 @@ use std::{
     sync::RwLock,
 };
 
-use crate::connections::{events, ConnectionModule};
+use crate::connections::{discovery::Discovery, events, ConnectionModule, Connections};
 use crate::node::Node;
 use crate::services::feed::proto_net;
 use crate::services::feed::Feed;
-use crate::storage::configuration::Configuration;
+use crate::storage::configuration::{Configuration, InternetPeer};
 use crate::utilities::timestamp::Timestamp;
 use qaul_info::{QaulInfo, QaulInfoEvent};
 use qaul_messaging::{QaulMessaging, QaulMessagingEvent};
@@ -60,6 +64,7 @@ pub struct QaulInternetBehaviour {
     pub ping: ping::Behaviour,
     pub qaul_info: QaulInfo,
     pub qaul_messaging: QaulMessaging,
+    pub rendezvous: rendezvous::client::Behaviour,
 }
 
 impl QaulInternetBehaviour {
@@ -80,6 +85,9 @@ impl QaulInternetBehaviour {
             QaulInternetEvent::Floodsub(ev) => {
                 self.floodsub_event(ev);
             }
+            QaulInternetEvent::Rendezvous(ev) => {
+                Discovery::rendezvous_event(ev);
+            }
         }
     }
 
@@ -103,6 +111,10 @@ impl QaulInternetBehaviour {
                 // add node to floodsub
                 self.floodsub.add_node_to_partial_view(peer_id);
 
+                // remember the peer and our observed address for peer discovery
+                Discovery::add_seen(peer_id, info.listen_addrs.clone());
+                Discovery::add_observed_address(info.observed_addr.clone());
+
                 // print received information
                 log::trace!("IdentifyEvent::Received from {:?}", peer_id);
                 log::trace!("  added peer_id {:?} to floodsub", peer_id);
@@ -183,6 +195,7 @@ pub enum QaulInternetEvent {
     Ping(ping::Event),
     QaulInfo(QaulInfoEvent),
     QaulMessaging(QaulMessagingEvent),
+    Rendezvous(rendezvous::client::Event),
 }
 
 impl From<FloodsubEvent> for QaulInternetEvent {
@@ -215,11 +228,21 @@ impl From<QaulMessagingEvent> for QaulInternetEvent {
     }
 }
 
+impl From<rendezvous::client::Event> for QaulInternetEvent {
+    fn from(event: rendezvous::client::Event) -> Self {
+        Self::Rendezvous(event)
+    }
+}
+
 /// Internet Connection Module of libqaul
 ///
 /// it creates a libp2p swarm
 pub struct Internet {
     pub swarm: Swarm<QaulInternetBehaviour>,
+    /// active listeners by their configured address
+    pub listeners: BTreeMap<String, ListenerId>,
+    /// the peers configuration that was last applied
+    peers: Vec<InternetPeer>,
 }
 
 impl Internet {
@@ -251,6 +274,9 @@ impl Internet {
             ping: ping::Behaviour::new(ping_config),
             qaul_info: QaulInfo::new(Node::get_id()),
             qaul_messaging: QaulMessaging::new(Node::get_id()),
+            // the client is only active when a rendezvous server
+            // is configured, which can change at runtime
+            rendezvous: rendezvous::client::Behaviour::new(node_keys.to_owned()),
         };
         behaviour.floodsub.subscribe(Node::get_topic());
 
@@ -277,35 +303,90 @@ impl Internet {
 
         // connect swarm to the listening interfaces defined in
         // the configuration array config.internet.listen
-        let config = Configuration::get();
-
-        for listen in &config.internet.listen {
-            match Swarm::listen_on(&mut swarm, listen.parse().expect("can get a local socket")) {
-                Ok(listener_id) => {
-                    log::info!(
-                        "INTERNET listening on `{}` with ID {:?}",
-                        listen,
-                        listener_id
-                    );
-                }
-                Err(e) => {
-                    log::error!("Error INTERNET start listening on `{}`: {}", listen, e);
-                }
-            }
-        }
+        let mut listeners = BTreeMap::new();
+        let listen = Configuration::get().internet.listen.clone();
+        Connections::update_listeners(&mut swarm, &mut listeners, &listen, "INTERNET");
 
         // connect to remote peers that are specified in
         // the configuration config.internet.peers
+        let config = Configuration::get();
         Self::peer_connect(&config, &mut swarm);
+        let peers = config.internet.peers.clone();
 
         log::trace!("Internet.init() peer_connect");
 
         // construct internet object
-        let internet = Internet { swarm };
+        let internet = Internet {
+            swarm,
+            listeners,
+            peers,
+        };
 
         internet
     }
 
+    /// Apply a changed configuration to the running swarm
+    ///
+    /// The listeners are reconciled with `config.internet.listen`,
+    /// enabled peers are dialed and the connections to disabled
+    /// or removed peers are closed.
+    /// Changed discovery options are applied by the discovery module.
+    pub fn apply_configuration(&mut self) {
+        let internet = Configuration::get().internet.clone();
+
+        // listeners
+        Connections::update_listeners(
+            &mut self.swarm,
+            &mut self.listeners,
+            &internet.listen,
+            "INTERNET",
+        );
+
+        // disconnect peers that were removed or disabled
+        for peer in &self.peers {
+            let still_enabled = internet
+                .peers
+                .iter()
+                .any(|new_peer| new_peer.address == peer.address && new_peer.enabled);
+            if still_enabled {
+                continue;
+            }
+            if let Some(peer_id) = Self::peerid_from_address(peer.address.clone()) {
+                if self.swarm.is_connected(&peer_id) {
+                    log::info!("disconnect internet peer {}", peer.address);
+                    if self.swarm.disconnect_peer_id(peer_id).is_err() {
+                        log::warn!("internet peer {} was not connected", peer.address);
+                    }
+                }
+            }
+            if let Ok(address) = peer.address.parse() {
+                Self::remove_reconnection(address);
+            }
+        }
+
+        // dial enabled peers that are not connected
+        for peer in &internet.peers {
+            if !peer.enabled {
+                continue;
+            }
+            if let Some(peer_id) = Self::peerid_from_address(peer.address.clone()) {
+                if self.swarm.is_connected(&peer_id) {
+                    continue;
+                }
+            }
+            match peer.address.parse() {
+                Ok(address) => Self::peer_dial(address, &mut self.swarm),
+                Err(error) => {
+                    log::trace!("peer address {} parse error: {:?}", peer.address, error)
+                }
+            }
+        }
+        self.peers = internet.peers;
+
+        // discovery
+        Discovery::apply_configuration(&mut self.swarm);
+    }
+
     // check if connection is active
     pub fn is_active_connection(address: &Multiaddr) -> bool {
         let config = Configuration::get();
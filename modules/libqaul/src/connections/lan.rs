@@ -8,6 +8,10 @@
 //! This module advertises the node via mdns in the local network.
 //! By default it listens to all interfaces and connects to a random port.
 //!
+//! On networks where mdns is blocked, nodes can still be found via
+//! the rendezvous and peer exchange mechanisms of the
+//! [`super::discovery`] module.
+//!
 //! The module is configured in the configuration file:
 //!
 //! ```yaml
@@ -21,6 +25,7 @@
 //! ```
 
 use libp2p::{
+    core::transport::ListenerId,
     floodsub::{Floodsub, FloodsubEvent},
     identity::Keypair,
     mdns, noise, ping,
@@ -28,9 +33,10 @@ use libp2p::{
     tcp, yamux, SwarmBuilder,
 };
 use prost::Message;
+use std::collections::BTreeMap;
 use std::time::Duration;
 
-use crate::connections::{events, ConnectionModule};
+use crate::connections::{discovery::Discovery, events, ConnectionModule, Connections};
 use crate::node::Node;
 use crate::services::feed::proto_net;
 use crate::services::feed::Feed;
@@ -80,12 +86,15 @@ impl QaulLanBehaviour {
     fn mdsn_event(&mut self, event: mdns::Event) {
         match event {
             mdns::Event::Discovered(discovered_list) => {
-                for (peer_id, _addr) in discovered_list {
+                for (peer_id, addr) in discovered_list {
                     log::trace!(
                         "MdnsEvent::Discovered, peer {:?} to floodsub added",
                         peer_id.clone()
                     );
                     self.floodsub.add_node_to_partial_view(peer_id);
+
+                    // share the peer with our neighbours
+                    Discovery::add_seen(peer_id, vec![addr]);
                 }
             }
             mdns::Event::Expired(expired_list) => {
@@ -157,6 +166,8 @@ impl From<QaulMessagingEvent> for QaulLanEvent {
 
 pub struct Lan {
     pub swarm: Swarm<QaulLanBehaviour>,
+    /// active listeners by their configured address
+    pub listeners: BTreeMap<String, ListenerId>,
 }
 
 impl Lan {
@@ -210,15 +221,25 @@ impl Lan {
 
         // connect swarm to the defined listening interfaces in
         // the configuration array config.lan.listen
-        let config = Configuration::get();
-
-        for listen in &config.lan.listen {
-            Swarm::listen_on(&mut swarm, listen.parse().expect("can get a local socket"))
-                .expect("swarm can be started");
+        let mut listeners = BTreeMap::new();
+        let listen = Configuration::get().lan.listen.clone();
+        for address in listen {
+            let listener_id =
+                Swarm::listen_on(&mut swarm, address.parse().expect("can get a local socket"))
+                    .expect("swarm can be started");
+            listeners.insert(address, listener_id);
         }
 
-        let lan = Lan { swarm };
+        let lan = Lan { swarm, listeners };
 
         lan
     }
+
+    /// Apply a changed configuration to the running swarm
+    ///
+    /// The listeners are reconciled with `config.lan.listen`.
+    pub fn apply_configuration(&mut self) {
+        let listen = Configuration::get().lan.listen.clone();
+        Connections::update_listeners(&mut self.swarm, &mut self.listeners, &listen, "LAN");
+    }
 }
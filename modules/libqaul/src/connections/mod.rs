@@ -6,19 +6,26 @@
 //! The modules define how and where to connect to network interfaces.
 
 pub mod ble;
+pub mod discovery;
 pub mod events;
 pub mod internet;
 pub mod lan;
 
-use libp2p::Multiaddr;
+use libp2p::{
+    core::transport::ListenerId,
+    swarm::{NetworkBehaviour, Swarm},
+    Multiaddr,
+};
 use prost::Message;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use crate::node::Node;
 use crate::rpc::Rpc;
 use crate::storage::configuration::Configuration;
 use crate::storage::configuration::InternetPeer;
 use ble::Ble;
+use discovery::Discovery;
 use internet::Internet;
 use lan::Lan;
 
@@ -86,6 +93,9 @@ impl Connections {
         // get node keys
         let node_keys = Node::get_keys();
 
+        // initialize peer discovery
+        Discovery::init();
+
         // initialize Lan module
         let lan = Lan::init(&node_keys).await;
 
@@ -103,8 +113,61 @@ impl Connections {
         conn
     }
 
+    /// Reconcile the listeners of a swarm with the configured addresses
+    ///
+    /// Listeners of addresses that were removed are closed,
+    /// new addresses are listened on.
+    pub fn update_listeners<B: NetworkBehaviour>(
+        swarm: &mut Swarm<B>,
+        listeners: &mut BTreeMap<String, ListenerId>,
+        listen: &Vec<String>,
+        module: &str,
+    ) {
+        // remove listeners that are not configured anymore
+        listeners.retain(|address, listener_id| {
+            if listen.contains(address) {
+                return true;
+            }
+            log::info!("{} stop listening on `{}`", module, address);
+            swarm.remove_listener(*listener_id);
+            false
+        });
+
+        // listen on new addresses
+        for address in listen {
+            if listeners.contains_key(address) {
+                continue;
+            }
+            let multiaddr: Multiaddr = match address.parse() {
+                Ok(multiaddr) => multiaddr,
+                Err(e) => {
+                    log::error!("{} invalid listening address `{}`: {}", module, address, e);
+                    continue;
+                }
+            };
+            match swarm.listen_on(multiaddr) {
+                Ok(listener_id) => {
+                    log::info!(
+                        "{} listening on `{}` with ID {:?}",
+                        module,
+                        address,
+                        listener_id
+                    );
+                    listeners.insert(address.clone(), listener_id);
+                }
+                Err(e) => {
+                    log::error!("Error {} start listening on `{}`: {}", module, address, e);
+                }
+            }
+        }
+    }
+
     /// Process incoming RPC request messages
-    pub fn rpc(data: Vec<u8>, internet_opt: Option<&mut Internet>) {
+    ///
+    /// Changes of the internet peers are applied via the
+    /// configuration. The peers are dialed or disconnected,
+    /// when the changed configuration is applied.
+    pub fn rpc(data: Vec<u8>) {
         match proto::Connections::decode(&data[..]) {
             Ok(connections) => {
                 match connections.message {
@@ -114,52 +177,21 @@ impl Connections {
                         Self::rpc_send_node_list(proto::Info::Request);
                     }
                     Some(proto::connections::Message::InternetNodesAdd(nodes_entry)) => {
-                        // check if we have a valid address
-                        let mut valid = false;
                         let mut info = proto::Info::AddSuccess;
 
-                        {
-                            // get config
-                            let mut config = Configuration::get_mut();
-
-                            // add the node to config if the address is valid
-                            let address_result: Result<Multiaddr, libp2p::multiaddr::Error> =
-                                nodes_entry.address.clone().parse();
-                            match address_result {
-                                Ok(address) => {
-                                    valid = true;
-
-                                    // add to config
-                                    config.internet.peers.push(InternetPeer {
-                                        address: nodes_entry.address.clone(),
-                                        name: nodes_entry.name.clone(),
-                                        enabled: true,
-                                    });
-
-                                    // connect to node
-                                    if let Some(internet) = internet_opt {
-                                        let mut connected = false;
-                                        // if we already have connection history, check if there is connected
-                                        if let Some(peer_id) = Internet::peerid_from_address(
-                                            nodes_entry.address.clone(),
-                                        ) {
-                                            connected = internet.swarm.is_connected(&peer_id);
-                                        }
-                                        if connected == false {
-                                            Internet::peer_dial(address, &mut internet.swarm);
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    log::error!("Not a valid address: {:?}", e);
-                                    info = proto::Info::AddErrorInvalid;
-                                }
-                            }
-                        }
-
-                        // save configuration
-                        if valid {
-                            Configuration::save();
+                        // add the node to config if the address is valid
+                        if let Err(e) = nodes_entry.address.parse::<Multiaddr>() {
+                            log::error!("Not a valid address: {:?}", e);
+                            info = proto::Info::AddErrorInvalid;
+                        } else if !Self::update_peers(|peers| {
+                            peers.push(InternetPeer {
+                                address: nodes_entry.address.clone(),
+                                name: nodes_entry.name.clone(),
+                                enabled: true,
+                                discovered: false,
+                            })
+                        }) {
+                            info = proto::Info::AddErrorInvalid;
                         }
 
                         // send response message
@@ -168,77 +200,33 @@ impl Connections {
 
                     Some(proto::connections::Message::InternetNodesRename(nodes_entry)) => {
                         let mut info = proto::Info::RemoveErrorNotFound;
-                        {
-                            let mut nodes: Vec<InternetPeer> = Vec::new();
-
-                            // get config
-                            let mut config = Configuration::get_mut();
-
-                            // loop through addresses and remove the equal
-                            for peer in &config.internet.peers {
+                        if !Self::update_peers(|peers| {
+                            for peer in peers.iter_mut() {
                                 if peer.address == nodes_entry.address {
-                                    // address has been found and is
-                                    // therefore removed.
-                                    nodes.push(InternetPeer {
-                                        address: peer.address.clone(),
-                                        name: nodes_entry.name.clone(),
-                                        enabled: peer.enabled,
-                                    });
+                                    peer.name = nodes_entry.name.clone();
                                     info = proto::Info::StateSuccess;
-                                } else {
-                                    // addresses do not match.
-                                    // add this address to the new vector.
-                                    nodes.push(peer.clone());
                                 }
                             }
-                            // add new nodes list to configuration
-                            config.internet.peers = nodes;
+                        }) {
+                            info = proto::Info::AddErrorInvalid;
                         }
 
-                        // save configuration
-                        if info == proto::Info::StateSuccess {
-                            Configuration::save();
-                        }
                         // send response
                         Self::rpc_send_node_list(info);
                     }
 
                     Some(proto::connections::Message::InternetNodesRemove(nodes_entry)) => {
                         let mut info = proto::Info::RemoveErrorNotFound;
-
-                        {
-                            let mut nodes: Vec<InternetPeer> = Vec::new();
-
-                            // get config
-                            let mut config = Configuration::get_mut();
-
-                            // loop through addresses and remove the equal
-                            for peer in &config.internet.peers {
+                        if !Self::update_peers(|peers| {
+                            peers.retain(|peer| {
                                 if peer.address == nodes_entry.address {
-                                    // address has been found and is
-                                    // therefore removed.
                                     info = proto::Info::RemoveSuccess;
-                                } else {
-                                    // addresses do not match.
-                                    // add this address to the new vector.
-                                    nodes.push(peer.clone());
+                                    return false;
                                 }
-                            }
-                            // add new nodes list to configuration
-                            config.internet.peers = nodes;
-                        }
-
-                        // save configuration
-                        Configuration::save();
-
-                        // check connection and disconnect node
-                        if let Some(peer_id) =
-                            Internet::peerid_from_address(nodes_entry.address.clone())
-                        {
-                            let internet = internet_opt.unwrap();
-                            if internet.swarm.is_connected(&peer_id) {
-                                if let Err(_) = internet.swarm.disconnect_peer_id(peer_id) {}
-                            }
+                                true
+                            })
+                        }) {
+                            info = proto::Info::AddErrorInvalid;
                         }
 
                         // send response
@@ -246,70 +234,15 @@ impl Connections {
                     }
                     Some(proto::connections::Message::InternetNodesState(nodes_entry)) => {
                         let mut info = proto::Info::RemoveErrorNotFound;
-                        let mut changed_state = false;
-
-                        {
-                            let mut nodes: Vec<InternetPeer> = Vec::new();
-
-                            // get config
-                            let mut config = Configuration::get_mut();
-
-                            // loop through addresses and remove the equal
-                            for peer in &config.internet.peers {
+                        if !Self::update_peers(|peers| {
+                            for peer in peers.iter_mut() {
                                 if peer.address == nodes_entry.address {
-                                    // address has been found and is
-                                    // therefore removed.
-                                    nodes.push(InternetPeer {
-                                        address: peer.address.clone(),
-                                        name: peer.name.clone(),
-                                        enabled: nodes_entry.enabled,
-                                    });
+                                    peer.enabled = nodes_entry.enabled;
                                     info = proto::Info::StateSuccess;
-
-                                    if peer.enabled != nodes_entry.enabled {
-                                        changed_state = true;
-                                    }
-                                } else {
-                                    // addresses do not match.
-                                    // add this address to the new vector.
-                                    nodes.push(peer.clone());
-                                }
-                            }
-                            // add new nodes list to configuration
-                            config.internet.peers = nodes;
-                        }
-
-                        // save configuration
-                        Configuration::save();
-
-                        if info == proto::Info::StateSuccess && changed_state == true {
-                            let internet = internet_opt.unwrap();
-                            // already has connection history, we simply handle banned peer list
-                            if nodes_entry.enabled == false {
-                                if let Some(peer_id) =
-                                    Internet::peerid_from_address(nodes_entry.address.clone())
-                                {
-                                    if internet.swarm.is_connected(&peer_id) {
-                                        if let Err(_) = internet.swarm.disconnect_peer_id(peer_id) {
-                                        }
-                                    }
-                                }
-                            } else {
-                                let mut connected = false;
-                                if let Some(peer_id) =
-                                    Internet::peerid_from_address(nodes_entry.address.clone())
-                                {
-                                    connected = internet.swarm.is_connected(&peer_id);
-                                }
-                                if connected == false {
-                                    let address_result: Result<
-                                        Multiaddr,
-                                        libp2p::multiaddr::Error,
-                                    > = nodes_entry.address.clone().parse();
-                                    let address = address_result.unwrap();
-                                    Internet::peer_dial(address, &mut internet.swarm);
                                 }
                             }
+                        }) {
+                            info = proto::Info::AddErrorInvalid;
                         }
 
                         // send response
@@ -324,6 +257,24 @@ impl Connections {
         }
     }
 
+    /// change the internet peers of the configuration
+    ///
+    /// Returns false if the changed configuration is invalid.
+    fn update_peers<F>(change: F) -> bool
+    where
+        F: FnOnce(&mut Vec<InternetPeer>),
+    {
+        match Configuration::update(|config| change(&mut config.internet.peers)) {
+            Ok(_) => true,
+            Err(errors) => {
+                for error in errors {
+                    log::error!("invalid internet peer {}: {}", error.field, error.message);
+                }
+                false
+            }
+        }
+    }
+
     /// create and send a node list message
     fn rpc_send_node_list(info: proto::Info) {
         let mut nodes: Vec<proto::InternetNodesEntry> = Vec::new();
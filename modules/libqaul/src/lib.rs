@@ -25,15 +25,21 @@ pub mod services;
 pub mod storage;
 pub mod utilities;
 
-use connections::{ble::Ble, internet::Internet, ConnectionModule, Connections};
+use connections::{
+    ble::Ble, discovery::Discovery, internet::Internet, ConnectionModule, Connections,
+};
 use node::Node;
 use router::{
     feed_requester, flooder, info::RouterInfo, neighbours::Neighbours, user_requester, Router,
 };
 use rpc::sys::Sys;
 use rpc::Rpc;
+use services::dtn::Dtn;
+use services::feed::Feed;
 use services::messaging::Messaging;
 use services::Services;
+use storage::configuration::{Configuration, ConfigurationSection};
+use storage::encryption::Encryption;
 use utilities::filelogger::FileLogger;
 use utilities::timestamp::Timestamp;
 use utilities::upgrade;
@@ -78,6 +84,9 @@ enum EventType {
     Retransmit,
     RoutingTableLog,
     UserUpdate,
+    Discovery,
+    Configuration,
+    FeedMaintenance,
 }
 
 /// initialize and start libqaul with a optional custom configuration options
@@ -226,6 +235,19 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
 
     log::trace!("test log to ensure that logging is working");
 
+    // wait until the encrypted storage is unlocked
+    if Encryption::is_locked() {
+        log::info!("storage is encrypted, waiting to be unlocked");
+        while Encryption::is_locked() {
+            if let Ok(rpc_message) = libqaul_rpc_receive.try_recv() {
+                Rpc::process_locked_message(rpc_message);
+            } else {
+                async_std::task::sleep(Duration::from_millis(10)).await;
+            }
+        }
+        log::info!("storage unlocked");
+    }
+
     // initialize node & user accounts
     Node::init();
 
@@ -294,6 +316,16 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
     // check for user config updates every 5 seconds
     let mut user_update_ticker = Ticker::new(Duration::from_millis(5000));
 
+    // rendezvous, peer exchange and dialing of discovered peers
+    let mut discovery_ticker = Ticker::new(Duration::from_millis(1000));
+
+    // remove outdated feed messages
+    let mut feed_maintenance_ticker = Ticker::new(Duration::from_millis(60000));
+
+    // re-apply changed configuration sections
+    let configuration_receive = Configuration::subscribe();
+    let mut configuration_ticker = Ticker::new(Duration::from_millis(100));
+
     // set initialized flag
     INITIALIZED.set(true);
 
@@ -317,6 +349,9 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
             let retransmit_fut = retransmit_ticker.next().fuse();
             let routing_table_log_fut = routing_table_log_ticker.next().fuse();
             let user_update_fut = user_update_ticker.next().fuse();
+            let discovery_fut = discovery_ticker.next().fuse();
+            let configuration_fut = configuration_ticker.next().fuse();
+            let feed_maintenance_fut = feed_maintenance_ticker.next().fuse();
 
             // This Macro is shown wrong by Rust-Language-Server > 0.2.400
             // You need to downgrade to version 0.2.400 if this happens to you
@@ -337,6 +372,7 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                 retransmit_fut,
                 routing_table_log_fut,
                 user_update_fut,
+                discovery_fut,
             );
 
             select! {
@@ -376,9 +412,11 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
 
                                         // check if address is active
                                         if Internet::is_active_connection(&addr){
-                                            Internet::add_reconnection(addr);
+                                            Internet::add_reconnection(addr.clone());
                                         }
 
+                                        // forget unreachable discovered address
+                                        Discovery::dial_failed(&addr);
                                     }
                                 },
                                 _ => {
@@ -393,6 +431,7 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                                     log::info!("connection established! peer={}, endpoint={}", peer_id.to_base58(), address.to_string());
                                     Internet::remove_reconnection(address.clone());
                                     Internet::add_connection(address.to_string(), &peer_id);
+                                    Discovery::connection_established(peer_id, &address);
                                 }
                                 _ => {}
                             }
@@ -401,6 +440,7 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                             // remove from neighbour table, after then scheduler will auto remove this neighbour
                             log::trace!("internet connection closed: {:?}", peer_id);
                             Neighbours::delete(ConnectionModule::Internet, peer_id);
+                            Discovery::connection_closed(peer_id);
 
                             // add new reconnection
                             match endpoint {
@@ -439,6 +479,9 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                 _retransmit_event = retransmit_fut => Some(EventType::Retransmit),
                 _routing_table_log_event = routing_table_log_fut => Some(EventType::RoutingTableLog),
                 _user_update_event = user_update_fut => Some(EventType::UserUpdate),
+                _discovery_event = discovery_fut => Some(EventType::Discovery),
+                _configuration_event = configuration_fut => Some(EventType::Configuration),
+                _feed_maintenance_event = feed_maintenance_fut => Some(EventType::FeedMaintenance),
             }
         };
 
@@ -724,6 +767,10 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                     // check if there are messages to retransmit
                     services::messaging::retransmit::MessagingRetransmit::process();
                 }
+                EventType::FeedMaintenance => {
+                    // remove expired and old feed messages
+                    Feed::maintain();
+                }
                 EventType::RoutingTableLog => {
                     // log routing table every second
                     router::table::RoutingTable::log_routing_table();
@@ -761,6 +808,68 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                         }
                     }
                 }
+                EventType::Discovery => {
+                    // rendezvous and dialing of discovered peers
+                    Discovery::check(&mut internet.swarm);
+
+                    // share recently seen peers with all neighbours
+                    if let Some(data) = Discovery::create_peer_exchange() {
+                        let mut neighbours = Neighbours::get_all_neighbours();
+                        neighbours.sort();
+                        neighbours.dedup();
+                        for neighbour_id in neighbours {
+                            match Neighbours::is_neighbour(&neighbour_id) {
+                                ConnectionModule::Lan => lan
+                                    .swarm
+                                    .behaviour_mut()
+                                    .qaul_info
+                                    .send_qaul_info_message(neighbour_id, data.clone()),
+                                ConnectionModule::Internet => internet
+                                    .swarm
+                                    .behaviour_mut()
+                                    .qaul_info
+                                    .send_qaul_info_message(neighbour_id, data.clone()),
+                                ConnectionModule::Ble => {
+                                    Ble::send_routing_info(neighbour_id, data.clone());
+                                }
+                                ConnectionModule::Local => {}
+                                ConnectionModule::None => {}
+                            }
+                        }
+                    }
+                }
+                EventType::Configuration => {
+                    let mut changed: Vec<ConfigurationSection> = Vec::new();
+                    while let Ok(section) = configuration_receive.try_recv() {
+                        if !changed.contains(&section) {
+                            changed.push(section);
+                        }
+                    }
+                    if changed.is_empty() {
+                        continue;
+                    }
+
+                    for section in &changed {
+                        log::info!("apply changed configuration {:?}", section);
+                        match section {
+                            ConfigurationSection::Lan => lan.apply_configuration(),
+                            ConfigurationSection::Internet => internet.apply_configuration(),
+                            ConfigurationSection::Routing => Router::apply_configuration(),
+                            ConfigurationSection::Debug => {
+                                FileLogger::enable(Configuration::get_debug_log())
+                            }
+                            ConfigurationSection::UserAccounts => Dtn::apply_configuration(),
+                            ConfigurationSection::Feed => Feed::maintain(),
+                        }
+                    }
+
+                    // inform the UI
+                    rpc::configuration::Configuration::send_changed(&changed);
+                    connections::events::emit_tauri_event(
+                        "qaul-configuration-changed",
+                        serde_json::json!({ "sections": changed }),
+                    );
+                }
             }
         }
     }
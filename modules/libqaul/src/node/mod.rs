@@ -22,6 +22,7 @@ use state;
 use crate::connections::{internet::Internet, lan::Lan};
 use crate::rpc::Rpc;
 use crate::storage::configuration::Configuration;
+use crate::storage::encryption::Encryption;
 use crate::utilities::qaul_id::QaulId;
 
 /// central state of this instances Node struct
@@ -75,8 +76,10 @@ impl Node {
         // save node to configuration file
         {
             let mut config = Configuration::get_mut();
-            config.node.keys = base64::engine::general_purpose::STANDARD
-                .encode(keys_ed25519.clone().try_into_ed25519().unwrap().to_bytes());
+            config.node.keys = Encryption::encrypt_key_string(
+                base64::engine::general_purpose::STANDARD
+                    .encode(keys_ed25519.clone().try_into_ed25519().unwrap().to_bytes()),
+            );
             config.node.id = id.to_string();
             config.node.initialized = 1;
         }
@@ -92,8 +95,10 @@ impl Node {
     /// start an existing node from the config parameters
     fn from_config() {
         let config = Configuration::get();
+        let keys_config = Encryption::decrypt_key_string(&config.node.keys)
+            .expect("node keys can't be decrypted");
         let mut basedecode = base64::engine::general_purpose::STANDARD
-            .decode(&config.node.keys)
+            .decode(&keys_config)
             .unwrap();
         let ed25519_keys = ed25519::Keypair::try_from_bytes(&mut basedecode).unwrap();
         let keys = Keypair::from(ed25519_keys);
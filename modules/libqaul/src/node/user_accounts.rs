@@ -8,7 +8,20 @@
 //! * user ID (hash of the public key)
 //! * Public / private key
 //! * user name (optional)
+//!
+//! Several user accounts can be logged in on one node at the same time.
+//! An account can be protected by a passphrase, it then needs to be
+//! logged in before it can be used via RPC.
+//! One of the logged in accounts is the current account, which is
+//! returned by `get_default_user()`.
+//!
+//! Logging in via RPC returns a session token. RPC messages can only
+//! act on behalf of the account their session token was issued for.
 
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
 use base64::Engine;
 use libp2p::{
     identity::{ed25519, Keypair, PublicKey},
@@ -16,12 +29,14 @@ use libp2p::{
 };
 use prost::Message;
 use state::InitCell;
+use std::collections::BTreeMap;
 use std::sync::RwLock;
 
 use crate::router;
 use crate::rpc::Rpc;
 use crate::storage::configuration;
 use crate::storage::configuration::Configuration;
+use crate::storage::encryption::Encryption;
 
 /// Import protobuf message definition generated by
 /// the rust module prost-build.
@@ -46,11 +61,23 @@ pub struct UserAccount {
 
 pub struct UserAccounts {
     pub users: Vec<UserAccount>,
+    /// user accounts that are logged in
+    pub sessions: Vec<PeerId>,
+    /// the currently selected user account
+    pub current: Option<PeerId>,
+    /// session tokens of the RPC clients,
+    /// with the user account they were issued for
+    pub tokens: BTreeMap<Vec<u8>, PeerId>,
 }
 
 impl UserAccounts {
     pub fn init() {
-        let mut accounts = UserAccounts { users: Vec::new() };
+        let mut accounts = UserAccounts {
+            users: Vec::new(),
+            sessions: Vec::new(),
+            current: None,
+            tokens: BTreeMap::new(),
+        };
 
         // check if there are users defined in configuration
         let config = Configuration::get();
@@ -58,8 +85,10 @@ impl UserAccounts {
         let mut iter = IntoIterator::into_iter(config_users);
 
         while let Some(user) = iter.next() {
+            let keys_config = Encryption::decrypt_key_string(&user.keys)
+                .expect("user account keys can't be decrypted");
             let mut basedecode = base64::engine::general_purpose::STANDARD
-                .decode(&user.keys)
+                .decode(&keys_config)
                 .unwrap();
             let ed25519_keys = ed25519::Keypair::try_from_bytes(&mut basedecode).unwrap();
             let keys = Keypair::from(ed25519_keys);
@@ -75,6 +104,14 @@ impl UserAccounts {
                 log::error!("------------------------------------");
             }
 
+            // accounts without passphrase are logged in
+            if user.passphrase.is_none() {
+                accounts.sessions.push(id);
+                if accounts.current.is_none() {
+                    accounts.current = Some(id);
+                }
+            }
+
             // push to user accounts table
             accounts.users.push(UserAccount {
                 name: user.name.clone(),
@@ -130,6 +167,27 @@ impl UserAccounts {
         reg_no: Option<String>,
         college: Option<String>,
     ) -> UserAccount {
+        Self::create_with_passphrase(name, profile_pic, about, reg_no, college, None)
+    }
+
+    /// create a new user account, protected by an optional passphrase
+    ///
+    /// The new account is logged in. It becomes the current
+    /// account if no other account is selected yet.
+    pub fn create_with_passphrase(
+        name: String,
+        profile_pic: Option<String>,
+        about: Option<String>,
+        reg_no: Option<String>,
+        college: Option<String>,
+        passphrase: Option<String>,
+    ) -> UserAccount {
+        // hash passphrase
+        let passphrase_hash = match passphrase {
+            Some(passphrase) if !passphrase.is_empty() => Self::hash_passphrase(&passphrase),
+            _ => None,
+        };
+
         // create user
         let keys_ed25519 = Keypair::generate_ed25519();
         let keys_config = base64::engine::general_purpose::STANDARD
@@ -182,25 +240,28 @@ impl UserAccounts {
         };
 
         // save it to state
-        let mut users = USERACCOUNTS.get().write().unwrap();
-        users.users.push(user.clone());
-
-        // save it to config
         {
-            let mut config = Configuration::get_mut();
-            config.user_accounts.push(configuration::UserAccount {
-                name: name.clone(),
-                id: id.to_string(),
-                keys: keys_config,
-                storage: configuration::StorageOptions::default(),
-                active: true,
-                profile_pic: profile_pic.clone(),
-                about: about.clone(),
-                reg_no: reg_no.clone(),
-                college: college.clone(),
-            });
+            let mut users = USERACCOUNTS.get().write().unwrap();
+            users.users.push(user.clone());
+            users.sessions.push(id);
+            if users.current.is_none() {
+                users.current = Some(id);
+            }
         }
-        Configuration::save();
+
+        // save it to config
+        Configuration::add_user_account(configuration::UserAccount {
+            name: name.clone(),
+            id: id.to_string(),
+            keys: Encryption::encrypt_key_string(keys_config),
+            storage: configuration::StorageOptions::default(),
+            active: true,
+            profile_pic: profile_pic.clone(),
+            about: about.clone(),
+            reg_no: reg_no.clone(),
+            college: college.clone(),
+            passphrase: passphrase_hash,
+        });
 
         // add it to users list
         crate::router::users::Users::add(
@@ -249,24 +310,191 @@ impl UserAccounts {
     }
 
     /// Return the default user.
-    /// The first registered user account is returned.
+    /// The currently selected, logged in user account is returned.
     pub fn get_default_user() -> Option<UserAccount> {
         // get state
         let users = USERACCOUNTS.get().read().unwrap();
 
-        // check if a user exists
-        if users.users.len() == 0 {
+        // get current user account
+        let current = users.current?;
+        users.users.iter().find(|user| user.id == current).cloned()
+    }
+
+    /// Return all user accounts of this node
+    pub fn get_all() -> Vec<UserAccount> {
+        let users = USERACCOUNTS.get().read().unwrap();
+        users.users.clone()
+    }
+
+    /// checks if a user account is logged in
+    pub fn is_logged_in(user_id: &PeerId) -> bool {
+        let users = USERACCOUNTS.get().read().unwrap();
+        users.sessions.contains(user_id)
+    }
+
+    /// checks if a user account is protected by a passphrase
+    pub fn has_passphrase(user_id: &PeerId) -> bool {
+        match Configuration::get_user(user_id.to_string()) {
+            Some(user) => user.passphrase.is_some(),
+            None => false,
+        }
+    }
+
+    /// Check whether an RPC message may act on behalf of this user
+    ///
+    /// The session token of the message needs to be issued
+    /// for this logged in account. An empty or invalid user id
+    /// is rejected.
+    pub fn is_authorized(user_id: &Vec<u8>, session_token: &Vec<u8>) -> bool {
+        match PeerId::from_bytes(user_id) {
+            Ok(id) => Self::get_session_account(session_token) == Some(id),
+            Err(_) => false,
+        }
+    }
+
+    /// Get the logged in user account a session token was issued for
+    pub fn get_session_account(session_token: &Vec<u8>) -> Option<PeerId> {
+        if session_token.is_empty() {
             return None;
         }
+        let users = USERACCOUNTS.get().read().unwrap();
+        let user_id = users.tokens.get(session_token)?;
+        match users.sessions.contains(user_id) {
+            true => Some(*user_id),
+            false => None,
+        }
+    }
+
+    /// Create a session token for a logged in user account
+    fn create_session_token(user_id: PeerId) -> Result<Vec<u8>, String> {
+        let mut users = USERACCOUNTS.get().write().unwrap();
+        if !users.sessions.contains(&user_id) {
+            return Err("user account is not logged in".to_string());
+        }
+        let token: [u8; 32] = rand::random();
+        users.tokens.insert(token.to_vec(), user_id);
+        Ok(token.to_vec())
+    }
+
+    /// Log in a user account
+    ///
+    /// The account becomes the current account.
+    pub fn login(user_id: PeerId, passphrase: Option<String>) -> Result<UserAccount, String> {
+        let account = Self::get_by_id(user_id).ok_or("user account not found".to_string())?;
+        Self::check_passphrase(user_id, passphrase)?;
+
+        let mut users = USERACCOUNTS.get().write().unwrap();
+        if !users.sessions.contains(&user_id) {
+            users.sessions.push(user_id);
+        }
+        users.current = Some(user_id);
+        log::info!("user account '{}' logged in", account.name);
+
+        Ok(account)
+    }
+
+    /// Log out a user account
+    ///
+    /// The passphrase of the account is required.
+    /// If it was the current account, the next logged in
+    /// account becomes the current account.
+    pub fn logout(user_id: PeerId, passphrase: Option<String>) -> Result<(), String> {
+        Self::get_by_id(user_id).ok_or("user account not found".to_string())?;
+        Self::check_passphrase(user_id, passphrase)?;
+
+        let mut users = USERACCOUNTS.get().write().unwrap();
+        users.sessions.retain(|id| *id != user_id);
+        users.tokens.retain(|_token, id| *id != user_id);
+        if users.current == Some(user_id) {
+            users.current = users.sessions.first().cloned();
+        }
+
+        Ok(())
+    }
+
+    /// Switch the current user account to another logged in account
+    ///
+    /// The passphrase of the account is required.
+    pub fn switch(user_id: PeerId, passphrase: Option<String>) -> Result<UserAccount, String> {
+        let account = Self::get_by_id(user_id).ok_or("user account not found".to_string())?;
+        Self::check_passphrase(user_id, passphrase)?;
+
+        let mut users = USERACCOUNTS.get().write().unwrap();
+        if !users.sessions.contains(&user_id) {
+            return Err("user account is not logged in".to_string());
+        }
+        users.current = Some(user_id);
+        Ok(account)
+    }
+
+    /// Set, change or remove the passphrase of a user account
+    ///
+    /// If the account has a passphrase, the old passphrase
+    /// needs to be provided.
+    /// An empty or missing new passphrase removes the protection.
+    pub fn set_passphrase(
+        user_id: PeerId,
+        old_passphrase: Option<String>,
+        new_passphrase: Option<String>,
+    ) -> Result<(), String> {
+        let user =
+            Configuration::get_user(user_id.to_string()).ok_or("user account not found")?;
+        if let Some(hash) = user.passphrase {
+            if !Self::verify_passphrase(&old_passphrase.unwrap_or_default(), &hash) {
+                return Err("wrong passphrase".to_string());
+            }
+        }
+
+        let new_hash = match new_passphrase {
+            Some(passphrase) if !passphrase.is_empty() => Some(
+                Self::hash_passphrase(&passphrase).ok_or("passphrase hashing failed")?,
+            ),
+            _ => None,
+        };
+        Configuration::set_user_passphrase(user_id.to_string(), new_hash);
+
+        Ok(())
+    }
+
+    /// check the passphrase of a protected user account
+    ///
+    /// Accounts without passphrase accept any request.
+    fn check_passphrase(user_id: PeerId, passphrase: Option<String>) -> Result<(), String> {
+        if let Some(user) = Configuration::get_user(user_id.to_string()) {
+            if let Some(hash) = user.passphrase {
+                if !Self::verify_passphrase(&passphrase.unwrap_or_default(), &hash) {
+                    log::warn!("wrong passphrase for user account {}", user_id);
+                    return Err("wrong passphrase".to_string());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// create an Argon2 hash of a passphrase in PHC string format
+    fn hash_passphrase(passphrase: &str) -> Option<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        match Argon2::default().hash_password(passphrase.as_bytes(), &salt) {
+            Ok(hash) => Some(hash.to_string()),
+            Err(e) => {
+                log::error!("passphrase hashing error: {}", e);
+                None
+            }
+        }
+    }
 
-        // get user account
-        let user = users.users.first().unwrap();
-        // Some(UserAccount {
-        //     id: user.id.clone(),
-        //     keys: user.keys.clone(),
-        //     name: user.name.clone(),
-        // });
-        Some(user.clone())
+    /// verify a passphrase against its stored Argon2 hash
+    fn verify_passphrase(passphrase: &str, hash: &str) -> bool {
+        match PasswordHash::new(hash) {
+            Ok(parsed) => Argon2::default()
+                .verify_password(passphrase.as_bytes(), &parsed)
+                .is_ok(),
+            Err(e) => {
+                log::error!("invalid passphrase hash: {}", e);
+                false
+            }
+        }
     }
 
     /// to fill the routing table get all users
@@ -310,7 +538,9 @@ impl UserAccounts {
     }
 
     /// Process incoming RPC request messages for user accounts
-    pub fn rpc(data: Vec<u8>) {
+    ///
+    /// `session_token` is the session token of the RPC message.
+    pub fn rpc(data: Vec<u8>, session_token: Vec<u8>) {
         match proto::UserAccounts::decode(&data[..]) {
             Ok(user_accounts) => {
                 match user_accounts.message {
@@ -380,12 +610,13 @@ impl UserAccounts {
                     }
                     Some(proto::user_accounts::Message::CreateUserAccount(create_user_account)) => {
                         // create user account
-                        let user_account = Self::create(
+                        let user_account = Self::create_with_passphrase(
                             create_user_account.name,
                             create_user_account.profile_pic,
                             create_user_account.about,
                             create_user_account.reg_no,
                             create_user_account.college,
+                            create_user_account.passphrase,
                         );
 
                         // get RPC key values
@@ -423,6 +654,58 @@ impl UserAccounts {
                             "".to_string(),
                             Vec::new(),
                         );
+
+                        // the new account is logged in
+                        let user_id = user_account.id;
+                        Self::rpc_send_session_result(
+                            user_id.to_bytes(),
+                            Self::create_session_token(user_id),
+                        );
+                    }
+                    Some(proto::user_accounts::Message::GetUserAccounts(_)) => {
+                        Self::rpc_send_user_account_list();
+                    }
+                    Some(proto::user_accounts::Message::LoginUserAccount(login)) => {
+                        let result = PeerId::from_bytes(&login.user_id)
+                            .map_err(|e| e.to_string())
+                            .and_then(|user_id| {
+                                Self::login(user_id, login.passphrase)?;
+                                Self::create_session_token(user_id)
+                            });
+                        Self::rpc_send_session_result(login.user_id, result);
+                    }
+                    Some(proto::user_accounts::Message::LogoutUserAccount(logout)) => {
+                        let result = PeerId::from_bytes(&logout.user_id)
+                            .map_err(|e| e.to_string())
+                            .and_then(|user_id| Self::logout(user_id, logout.passphrase));
+                        Self::rpc_send_session_result(logout.user_id, result.map(|_| Vec::new()));
+                    }
+                    Some(proto::user_accounts::Message::SwitchUserAccount(switch)) => {
+                        let result = PeerId::from_bytes(&switch.user_id)
+                            .map_err(|e| e.to_string())
+                            .and_then(|user_id| {
+                                Self::switch(user_id, switch.passphrase)?;
+                                Self::create_session_token(user_id)
+                            });
+                        Self::rpc_send_session_result(switch.user_id, result);
+                    }
+                    Some(proto::user_accounts::Message::SetPassphrase(set_passphrase)) => {
+                        let result = PeerId::from_bytes(&set_passphrase.user_id)
+                            .map_err(|e| e.to_string())
+                            .and_then(|user_id| {
+                                if Self::get_session_account(&session_token) != Some(user_id) {
+                                    return Err("user account is not logged in".to_string());
+                                }
+                                Self::set_passphrase(
+                                    user_id,
+                                    set_passphrase.old_passphrase,
+                                    set_passphrase.new_passphrase,
+                                )
+                            });
+                        Self::rpc_send_session_result(
+                            set_passphrase.user_id,
+                            result.map(|_| Vec::new()),
+                        );
                     }
                     _ => {}
                 }
@@ -433,6 +716,76 @@ impl UserAccounts {
         }
     }
 
+    /// send the list of all user accounts
+    fn rpc_send_user_account_list() {
+        let (accounts, sessions, current) = {
+            let users = USERACCOUNTS.get().read().unwrap();
+            (users.users.clone(), users.sessions.clone(), users.current)
+        };
+
+        let mut user_accounts = Vec::new();
+        for account in accounts {
+            let (key_type, key_base58) = Self::get_protobuf_public_key(account.keys.public());
+            user_accounts.push(proto::UserAccountEntry {
+                logged_in: sessions.contains(&account.id),
+                has_passphrase: Self::has_passphrase(&account.id),
+                my_user_account: Some(proto::MyUserAccount {
+                    name: account.name,
+                    id: account.id.to_bytes(),
+                    id_base58: account.id.to_base58(),
+                    key: account.keys.public().encode_protobuf(),
+                    key_type,
+                    key_base58,
+                    profile_pic: account.profile_pic,
+                    about: account.about,
+                    reg_no: account.reg_no,
+                    college: account.college,
+                }),
+            });
+        }
+
+        Self::rpc_send(proto::user_accounts::Message::UserAccountList(
+            proto::UserAccountList {
+                user_accounts,
+                current: current.map(|id| id.to_bytes()).unwrap_or_default(),
+            },
+        ));
+    }
+
+    /// send the result of a session request
+    ///
+    /// A successful result contains the new session token,
+    /// or an empty vector if no token was issued.
+    fn rpc_send_session_result(user_id: Vec<u8>, result: Result<Vec<u8>, String>) {
+        let (success, message, session_token) = match result {
+            Ok(session_token) => (true, "".to_string(), session_token),
+            Err(e) => (false, e, Vec::new()),
+        };
+        Self::rpc_send(proto::user_accounts::Message::SessionResult(
+            proto::SessionResult {
+                success,
+                message,
+                user_id,
+                session_token,
+            },
+        ));
+    }
+
+    /// encode and send a user accounts RPC message
+    fn rpc_send(message: proto::user_accounts::Message) {
+        let proto_message = proto::UserAccounts {
+            message: Some(message),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            crate::rpc::proto::Modules::Useraccounts.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+
     /// create the qaul RPC definitions of a public key
     ///
     /// Returns a tuple with the key type & the base58 encoded
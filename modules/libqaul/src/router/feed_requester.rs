@@ -5,11 +5,14 @@
 //!
 //! As feed messages flooded in the entire network.
 //! In case a user joins the network newly or a feed message
-//! was missed, the latest feed id's are synchronized via the
+//! was missed, the feeds are reconciled via the
 //! qaul router info service.
 //!
-//! With each routing information the last feed messages are
-//! advertised and can be requested from the sending node.
+//! Every feed sync interval, the routing information contains
+//! a bloom filter of the feed messages. The receiving node
+//! sends the missing messages via the feed responser.
+//!
+//! Feed messages can still be requested by their ids.
 
 use libp2p::PeerId;
 use state::InitCell;
@@ -15,6 +15,10 @@
 //! of them to make sure, each node is only sent the routing
 //! table over one of the interfaces.
 //! The timer needs to be polled manually.
+//!
+//! Every feed sync interval, the routing information contains
+//! a bloom filter of the feed messages, with which the
+//! neighbours reconcile their feeds.
 
 use crate::utilities::qaul_id::QaulId;
 use libp2p::PeerId;
@@ -28,22 +32,33 @@ use std::{
 };
 
 use crate::{
-    connections::ConnectionModule,
+    connections::{discovery::Discovery, ConnectionModule},
     node::Node,
     router::{
         connections::ConnectionTable, neighbours::Neighbours, router_net_proto,
         table::RoutingTable, users::Users,
     },
+    storage::configuration::Configuration,
     utilities::timestamp::Timestamp,
 };
 
-use crate::feed_requester::FeedRequester;
-use crate::feed_requester::FeedResponser;
-use crate::services::feed::Feed;
+use crate::feed_requester::{FeedRequester, FeedResponser};
+use crate::services::feed::{bloom, bloom::BloomFilter, Feed};
 
 use crate::router::user_requester::UserRequester;
 use crate::router::user_requester::UserResponser;
 
+/// maximal number of feed messages per feed response
+const FEED_RESPONSE_MESSAGES: usize = 16;
+
+/// number of latest feed ids advertised to neighbours
+/// without feed filter support
+const FEED_IDS_COUNT: usize = 5;
+
+/// maximal size of the feed filter in bytes on BLE,
+/// where the router info has to fit into a few BLE messages
+const FEED_FILTER_BLE_BYTES: usize = 256;
+
 /// mutable state of Neighbours table per ConnectionModule
 static SCHEDULER: InitCell<RwLock<Scheduler>> = InitCell::new();
 
@@ -78,7 +93,10 @@ pub struct Scheduler {
 struct SchedulerEntry {
     /// time of the last send
     timestamp: SystemTime,
-    is_first: bool,
+    /// time of the last feed synchronisation
+    ///
+    /// `None` if the feed was not synchronised yet.
+    feed_sync: Option<SystemTime>,
 }
 
 /// RouterInfo Module
@@ -101,14 +119,22 @@ impl RouterInfo {
         SCHEDULER.set(RwLock::new(scheduler));
     }
 
+    /// Change the interval in which the routing
+    /// information is sent to the neighbours
+    pub fn set_interval(interval_seconds: u64) {
+        let mut scheduler = SCHEDULER.get().write().unwrap();
+        scheduler.interval = Duration::from_secs(interval_seconds);
+    }
+
     /// This loops over all neighbours
     /// and checks if there is any timeout.
     /// If it finds a timeout it returns the node id
     /// to send a routing information to.
     pub fn check_scheduler() -> Option<(PeerId, ConnectionModule, Vec<u8>)> {
+        let feed_sync_interval = Duration::from_secs(Configuration::get().feed.sync_interval);
         let mut found_neighbour: Option<PeerId> = None;
         let mut neighbour_last_sent: u64 = 0;
-        let mut neighbour_is_first: bool = false;
+        let mut neighbour_feed_sync: bool = false;
         let mut propagation_id: u32;
         let mut propagation_timestamp: u64;
 
@@ -121,7 +147,10 @@ impl RouterInfo {
                 if ctx.timestamp + scheduler.interval < SystemTime::now() {
                     found_neighbour = Some(id.clone());
                     neighbour_last_sent = Timestamp::get_timestamp_by(&ctx.timestamp);
-                    neighbour_is_first = ctx.is_first;
+                    neighbour_feed_sync = match ctx.feed_sync {
+                        Some(feed_sync) => feed_sync + feed_sync_interval < SystemTime::now(),
+                        None => true,
+                    };
                     break;
                 }
             }
@@ -163,11 +192,18 @@ impl RouterInfo {
                 // update timer
                 if let Some(entry) = scheduler.neighbours.get_mut(&node_id) {
                     entry.timestamp = SystemTime::now();
-                    entry.is_first = false;
+                    if neighbour_feed_sync {
+                        entry.feed_sync = Some(SystemTime::now());
+                    }
                 }
 
                 // create routing information
-                let data = Self::create(node_id.clone(), neighbour_last_sent, neighbour_is_first);
+                let data = Self::create(
+                    node_id.clone(),
+                    module,
+                    neighbour_last_sent,
+                    neighbour_feed_sync,
+                );
 
                 // create result
                 return Some((node_id, module, data));
@@ -195,7 +231,7 @@ impl RouterInfo {
                 node_id,
                 SchedulerEntry {
                     timestamp: SystemTime::now() - interval,
-                    is_first: true,
+                    feed_sync: None,
                 },
             );
         }
@@ -203,20 +239,40 @@ impl RouterInfo {
 
     /// Create routing information for a neighbour node,
     /// encode the information and return the byte code.
-    pub fn create(neighbour: PeerId, last_sent: u64, is_first: bool) -> Vec<u8> {
+    ///
+    /// When feed synchronisation is due, the latest feed ids for
+    /// nodes without feed filter support and the feed filter are
+    /// added. On BLE the size of the feed filter is capped.
+    pub fn create(
+        neighbour: PeerId,
+        module: ConnectionModule,
+        last_sent: u64,
+        feed_sync: bool,
+    ) -> Vec<u8> {
         let node_id = Node::get_id();
 
         // create routing table
         let routes = RoutingTable::create_routing_info(neighbour, last_sent);
 
-        // create latest Feed ids table
-        let mut feeds = router_net_proto::FeedIdsTable { ids: Vec::new() };
-
-        if is_first == true {
-            let ids = Feed::get_latest_message_ids(5);
-            for id in ids {
-                feeds.ids.push(id.clone());
-            }
+        // create feed synchronisation filter
+        let mut feeds = None;
+        let mut feed_filter = None;
+        if feed_sync {
+            feeds = Some(router_net_proto::FeedIdsTable {
+                ids: Feed::get_latest_message_ids(FEED_IDS_COUNT),
+            });
+
+            let max_bytes = match module {
+                ConnectionModule::Ble => FEED_FILTER_BLE_BYTES,
+                _ => bloom::MAX_BYTES,
+            };
+            let (since, filter) = Feed::create_sync_filter(max_bytes);
+            feed_filter = Some(router_net_proto::FeedSyncFilter {
+                since,
+                bits: filter.bits,
+                hashes: filter.hashes,
+                seed: filter.seed,
+            });
         }
 
         // create router info protobuf message
@@ -225,8 +281,9 @@ impl RouterInfo {
             node: node_id.clone().to_bytes(),
             routes: Some(routes),
             //users: Some(users),
-            feeds: Some(feeds),
+            feeds,
             timestamp,
+            feed_filter,
         };
 
         // encode router info message
@@ -268,6 +325,29 @@ impl RouterInfo {
         buf
     }
 
+    /// Send the feed messages the neighbour is missing
+    ///
+    /// The messages are sent in several responses,
+    /// to keep the messages small.
+    fn process_feed_filter(neighbour_id: &PeerId, filter: router_net_proto::FeedSyncFilter) {
+        let bloom = BloomFilter {
+            bits: filter.bits,
+            hashes: filter.hashes,
+            seed: filter.seed,
+        };
+        let feeds = Feed::reconcile(filter.since, &bloom);
+        if feeds.len() > 0 {
+            log::trace!(
+                "sending {} missing feed messages to {:?}",
+                feeds.len(),
+                neighbour_id
+            );
+            for chunk in feeds.chunks(FEED_RESPONSE_MESSAGES) {
+                FeedResponser::add(neighbour_id, &chunk.to_vec());
+            }
+        }
+    }
+
     /// creating feed request message
     pub fn create_feed_request(ids: &Vec<Vec<u8>>) -> Vec<u8> {
         let node_id = Node::get_id();
@@ -327,6 +407,7 @@ impl RouterInfo {
                 sender_id: sender_id.clone(),
                 content: content.clone(),
                 time: *time,
+                message: Feed::get_message_bytes(message_id),
             };
             feeds.messages.push(feed);
         }
@@ -458,6 +539,52 @@ impl RouterInfo {
         buf
     }
 
+    /// create peer exchange message
+    pub fn create_peer_exchange(peers: &Vec<router_net_proto::PeerExchangeEntry>) -> Vec<u8> {
+        let node_id = Node::get_id();
+        let timestamp = Timestamp::get_timestamp();
+
+        let peer_exchange = router_net_proto::PeerExchangeMessage {
+            peers: peers.clone(),
+        };
+
+        let mut buf = Vec::with_capacity(peer_exchange.encoded_len());
+        peer_exchange
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        let router_info_proto = router_net_proto::RouterInfoContent {
+            id: node_id.to_bytes(),
+            router_info_module: router_net_proto::RouterInfoModule::PeerExchange as i32,
+            content: buf,
+            time: timestamp,
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(router_info_proto.encoded_len());
+        router_info_proto
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // sign data
+        let keys = Node::get_keys();
+        let signature = keys.sign(&buf).unwrap();
+
+        // create signed container
+        let router_info_container = router_net_proto::RouterInfoContainer {
+            signature,
+            message: buf,
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(router_info_container.encoded_len());
+        router_info_container
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        buf
+    }
+
     /// process received qaul_info message
     pub fn received(received: QaulInfoReceived) {
         // validate received data before decoding
@@ -533,6 +660,7 @@ impl RouterInfo {
                                     //let users = messages.users;
                                     let routes = messages.routes;
                                     let feeds = messages.feeds;
+                                    let feed_filter = messages.feed_filter;
 
                                     match routes {
                                         Some(router_net_proto::RoutingInfoTable { entry }) => {
@@ -557,8 +685,16 @@ impl RouterInfo {
                                         }
                                         _ => {}
                                     }
-                                    match feeds {
-                                        Some(router_net_proto::FeedIdsTable { ids }) => {
+                                    // nodes without feed filter support
+                                    // only advertise their latest feed ids
+                                    match (feed_filter, feeds) {
+                                        (Some(filter), _) => {
+                                            Self::process_feed_filter(
+                                                &received.received_from,
+                                                filter,
+                                            );
+                                        }
+                                        (None, Some(router_net_proto::FeedIdsTable { ids })) => {
                                             let missing_ids = Feed::process_received_feed_ids(&ids);
                                             if missing_ids.len() > 0 {
                                                 FeedRequester::add(
@@ -567,7 +703,7 @@ impl RouterInfo {
                                                 );
                                             }
                                         }
-                                        _ => {}
+                                        (None, None) => {}
                                     }
                                 }
                             }
@@ -604,6 +740,7 @@ impl RouterInfo {
                                                     &feed.sender_id,
                                                     feed.content,
                                                     feed.time,
+                                                    &feed.message,
                                                 );
                                             }
                                             // check missed users
@@ -641,6 +778,17 @@ impl RouterInfo {
                                     received.received_from,
                                 );
                             }
+                            Ok(router_net_proto::RouterInfoModule::PeerExchange) => {
+                                let message_info = router_net_proto::PeerExchangeMessage::decode(
+                                    &content.content[..],
+                                );
+                                if let Ok(message) = message_info {
+                                    Discovery::process_peer_exchange(
+                                        &received.received_from,
+                                        message.peers,
+                                    );
+                                }
+                            }
                             Err(_) => {}
                         }
                     }
@@ -101,6 +101,26 @@ impl Router {
         user_update::UserUpdateSendRequester::init();
     }
 
+    /// Apply a changed routing configuration
+    ///
+    /// The routing table timers are used from the next
+    /// scheduler run on. The ping interval of the connection
+    /// modules is set when their swarms are created and
+    /// applies after a restart.
+    pub fn apply_configuration() {
+        let config = Configuration::get().routing.clone();
+        let previous = {
+            let mut router = ROUTER.get().write().unwrap();
+            std::mem::replace(&mut router.configuration, config.clone())
+        };
+
+        RouterInfo::set_interval(config.sending_table_period);
+
+        if previous.ping_neighbour_period != config.ping_neighbour_period {
+            log::warn!("the changed ping_neighbour_period applies after a restart");
+        }
+    }
+
     /// Get router configuration from state
     pub fn get_configuration() -> RoutingOptions {
         let router = ROUTER.get().read().unwrap();
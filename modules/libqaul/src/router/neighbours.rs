@@ -8,7 +8,6 @@
 use libp2p::PeerId;
 use prost::Message;
 use serde::{Deserialize, Serialize};
-use sled::Tree;
 use state::InitCell;
 use std::{collections::HashMap, sync::RwLock};
 
@@ -17,6 +16,7 @@ use super::proto;
 use crate::connections::ConnectionModule;
 use crate::rpc::Rpc;
 use crate::storage::database::DataBase;
+use crate::storage::encryption::EncryptedTree;
 use crate::utilities::{qaul_id::QaulId, timestamp::Timestamp};
 
 /// mutable state of Internet neighbour node table
@@ -32,7 +32,7 @@ static BLE: InitCell<RwLock<Neighbours>> = InitCell::new();
 /// used by the BLE module.
 ///
 /// The DB saves the serialized bincode of `Node`
-static NODES: InitCell<Tree> = InitCell::new();
+static NODES: InitCell<EncryptedTree> = InitCell::new();
 
 /// Node entry in the data base
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -82,7 +82,7 @@ impl Neighbours {
 
         // get nodes tree from data base and set it to state
         let db = DataBase::get_node_db();
-        let tree = db.open_tree("nodes").unwrap();
+        let tree = EncryptedTree::open(&db, "nodes");
         NODES.set(tree);
     }
 
@@ -2,7 +2,7 @@
 /// users rpc message container
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Users {
-    #[prost(oneof = "users::Message", tags = "1, 2, 3, 4, 5, 6")]
+    #[prost(oneof = "users::Message", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12")]
     pub message: ::core::option::Option<users::Message>,
 }
 /// Nested message and enum types in `Users`.
@@ -53,6 +53,44 @@ pub mod users {
         /// 12142 31101 09874 34545
         #[prost(message, tag = "6")]
         SecurityNumberResponse(super::SecurityNumberResponse),
+        /// Verification QR Request
+        ///
+        /// Requests the QR code payload for the in-person
+        /// verification with this user.
+        #[prost(message, tag = "7")]
+        VerificationQrRequest(super::VerificationQrRequest),
+        /// Verification QR Response
+        ///
+        /// Libqaul's response containing the QR code payload.
+        /// The UI renders it as QR code, to be scanned by the
+        /// remote user.
+        #[prost(message, tag = "8")]
+        VerificationQrResponse(super::VerificationQrResponse),
+        /// Verify QR Request
+        ///
+        /// Sends a scanned QR code payload to libqaul.
+        /// If the payload matches, the user is set to verified.
+        #[prost(message, tag = "9")]
+        VerifyQrRequest(super::VerifyQrRequest),
+        /// Set Trust Request
+        ///
+        /// Manually set a user to verified, after the
+        /// security numbers have been compared, or back to
+        /// unverified.
+        #[prost(message, tag = "10")]
+        SetTrustRequest(super::SetTrustRequest),
+        /// Verification Result
+        ///
+        /// Libqaul's response to 'VerifyQrRequest' and
+        /// 'SetTrustRequest'.
+        #[prost(message, tag = "11")]
+        VerificationResult(super::VerificationResult),
+        /// Key Changed Warning
+        ///
+        /// Sent by libqaul when the public key of a
+        /// known user has changed.
+        #[prost(message, tag = "12")]
+        KeyChanged(super::KeyChangedWarning),
     }
 }
 /// UI request for some users
@@ -109,6 +147,9 @@ pub struct UserEntry {
     /// optional college
     #[prost(string, optional, tag = "15")]
     pub college: ::core::option::Option<::prost::alloc::string::String>,
+    /// trust level of the user's public key
+    #[prost(enumeration = "TrustLevel", tag = "16")]
+    pub trust: i32,
 }
 /// Routing table connection entry.
 /// This message contains a connection to a specific user.
@@ -149,6 +190,105 @@ pub struct SecurityNumberResponse {
     #[prost(uint32, repeated, tag = "3")]
     pub security_number_blocks: ::prost::alloc::vec::Vec<u32>,
 }
+/// verification QR code request
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct VerificationQrRequest {
+    /// user id of the remote user
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// verification QR code response
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct VerificationQrResponse {
+    /// user id of the remote user
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// QR code payload
+    #[prost(string, tag = "2")]
+    pub qr_payload: ::prost::alloc::string::String,
+}
+/// scanned verification QR code
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct VerifyQrRequest {
+    /// QR code payload
+    #[prost(string, tag = "1")]
+    pub qr_payload: ::prost::alloc::string::String,
+}
+/// set the trust level of a user
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SetTrustRequest {
+    /// user id
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// set user to verified or unverified
+    #[prost(bool, tag = "2")]
+    pub verified: bool,
+}
+/// result of a verification
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct VerificationResult {
+    /// user id of the verified user
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// verification was successful
+    #[prost(bool, tag = "2")]
+    pub success: bool,
+    /// the new trust level
+    #[prost(enumeration = "TrustLevel", tag = "3")]
+    pub trust: i32,
+    /// error message
+    #[prost(string, tag = "4")]
+    pub error_message: ::prost::alloc::string::String,
+}
+/// warning about a changed key of a known user
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct KeyChangedWarning {
+    /// the new user id
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// the previously known user id
+    #[prost(bytes = "vec", tag = "2")]
+    pub previous_user_id: ::prost::alloc::vec::Vec<u8>,
+    /// user name
+    #[prost(string, tag = "3")]
+    pub name: ::prost::alloc::string::String,
+    /// the previous key was verified
+    #[prost(bool, tag = "4")]
+    pub was_verified: bool,
+}
+/// trust level of a user's public key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum TrustLevel {
+    /// the key has not been verified
+    Unverified = 0,
+    /// the key has been verified
+    Verified = 1,
+    /// the key of this user has changed
+    KeyChanged = 2,
+}
+impl TrustLevel {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unverified => "UNVERIFIED",
+            Self::Verified => "VERIFIED",
+            Self::KeyChanged => "KEY_CHANGED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "UNVERIFIED" => Some(Self::Unverified),
+            "VERIFIED" => Some(Self::Verified),
+            "KEY_CHANGED" => Some(Self::KeyChanged),
+            _ => None,
+        }
+    }
+}
 /// Connection modules
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -0,0 +1,273 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # RPC Configuration Messages
+//!
+//! Read, validate and change the runtime configuration of libqaul.
+
+use super::Rpc;
+use crate::storage::configuration::{
+    self, Configuration as Config, ConfigurationError, ConfigurationSection, ConfigurationUpdate,
+};
+use prost::Message;
+
+/// Import protobuf message definition generated by
+/// the rust module prost-build.
+pub mod proto {
+    include!("qaul.rpc.configuration.rs");
+}
+
+/// RPC Configuration Module
+pub struct Configuration {}
+
+impl Configuration {
+    /// Process incoming RPC request messages for configuration module
+    pub fn rpc(data: Vec<u8>, request_id: String) {
+        match proto::Configuration::decode(&data[..]) {
+            Ok(configuration) => match configuration.message {
+                Some(proto::configuration::Message::ConfigurationRequest(_request)) => {
+                    let settings = Config::get_settings();
+                    Self::send(
+                        proto::configuration::Message::ConfigurationInfo(Self::info_to_proto(
+                            settings,
+                        )),
+                        request_id,
+                    );
+                }
+                Some(proto::configuration::Message::ValidateRequest(update)) => {
+                    let result = match Self::update_from_proto(update) {
+                        Ok(update) => Config::validate_update(&update).map(|_| Vec::new()),
+                        Err(errors) => Err(errors),
+                    };
+                    Self::send_result(result, request_id);
+                }
+                Some(proto::configuration::Message::UpdateRequest(update)) => {
+                    let result = match Self::update_from_proto(update) {
+                        Ok(update) => Config::apply_update(update),
+                        Err(errors) => Err(errors),
+                    };
+                    Self::send_result(result, request_id);
+                }
+                _ => {
+                    log::error!("Unhandled RPC Configuration Message");
+                }
+            },
+            Err(e) => {
+                log::error!("{:?}", e);
+            }
+        }
+    }
+
+    /// Inform the UI about changed configuration sections
+    pub fn send_changed(sections: &Vec<ConfigurationSection>) {
+        let sections = sections
+            .iter()
+            .map(|section| Self::section_to_proto(*section) as i32)
+            .collect();
+        Self::send(
+            proto::configuration::Message::ConfigurationChanged(proto::ConfigurationChanged {
+                sections,
+            }),
+            "".to_string(),
+        );
+    }
+
+    /// send the result of a validate or update request
+    fn send_result(
+        result: Result<Vec<ConfigurationSection>, Vec<ConfigurationError>>,
+        request_id: String,
+    ) {
+        let proto_result = match result {
+            Ok(changed) => proto::ConfigurationResult {
+                success: true,
+                errors: Vec::new(),
+                changed: changed
+                    .into_iter()
+                    .map(|section| Self::section_to_proto(section) as i32)
+                    .collect(),
+            },
+            Err(errors) => proto::ConfigurationResult {
+                success: false,
+                errors: errors
+                    .into_iter()
+                    .map(|error| proto::ConfigurationError {
+                        field: error.field,
+                        message: error.message,
+                    })
+                    .collect(),
+                changed: Vec::new(),
+            },
+        };
+        Self::send(
+            proto::configuration::Message::ConfigurationResult(proto_result),
+            request_id,
+        );
+    }
+
+    /// encode and send a configuration message
+    fn send(message: proto::configuration::Message, request_id: String) {
+        let proto_message = proto::Configuration {
+            message: Some(message),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Configuration.into(),
+            request_id,
+            Vec::new(),
+        );
+    }
+
+    /// translate configuration section to protobuf enum
+    fn section_to_proto(section: ConfigurationSection) -> proto::ConfigurationSection {
+        match section {
+            ConfigurationSection::Lan => proto::ConfigurationSection::Lan,
+            ConfigurationSection::Internet => proto::ConfigurationSection::Internet,
+            ConfigurationSection::Routing => proto::ConfigurationSection::Routing,
+            ConfigurationSection::Debug => proto::ConfigurationSection::Debug,
+            ConfigurationSection::UserAccounts => proto::ConfigurationSection::UserAccounts,
+            ConfigurationSection::Feed => proto::ConfigurationSection::Feed,
+        }
+    }
+
+    /// translate the runtime configuration to protobuf
+    fn info_to_proto(settings: ConfigurationUpdate) -> proto::ConfigurationInfo {
+        proto::ConfigurationInfo {
+            lan: settings.lan.map(|lan| proto::LanConfiguration {
+                active: lan.active,
+                listen: lan.listen,
+            }),
+            internet: settings
+                .internet
+                .map(|internet| proto::InternetConfiguration {
+                    active: internet.active,
+                    peers: internet
+                        .peers
+                        .into_iter()
+                        .map(|peer| proto::InternetPeer {
+                            address: peer.address,
+                            name: peer.name,
+                            enabled: peer.enabled,
+                            discovered: peer.discovered,
+                        })
+                        .collect(),
+                    do_listen: internet.do_listen,
+                    listen: internet.listen,
+                    rendezvous: Some(proto::RendezvousConfiguration {
+                        enabled: internet.rendezvous.enabled,
+                        server: internet.rendezvous.server,
+                        namespace: internet.rendezvous.namespace,
+                        ttl: internet.rendezvous.ttl,
+                        discover_interval: internet.rendezvous.discover_interval,
+                    }),
+                    peer_exchange: Some(proto::PeerExchangeConfiguration {
+                        enabled: internet.peer_exchange.enabled,
+                        exchange_interval: internet.peer_exchange.exchange_interval,
+                        max_shared: internet.peer_exchange.max_shared,
+                        max_auto_peers: internet.peer_exchange.max_auto_peers,
+                        seen_period_limit: internet.peer_exchange.seen_period_limit,
+                    }),
+                }),
+            routing: settings.routing.map(|routing| proto::RoutingConfiguration {
+                sending_table_period: routing.sending_table_period,
+                ping_neighbour_period: routing.ping_neighbour_period,
+                hop_count_penalty: routing.hop_count_penalty,
+                maintain_period_limit: routing.maintain_period_limit,
+            }),
+            debug: settings
+                .debug
+                .map(|debug| proto::DebugConfiguration { log: debug.log }),
+            feed: settings.feed.map(|feed| proto::FeedConfiguration {
+                max_age: feed.max_age,
+                max_size: feed.max_size,
+                sync_window: feed.sync_window,
+                sync_interval: feed.sync_interval,
+            }),
+        }
+    }
+
+    /// translate a protobuf update to the typed configuration update
+    ///
+    /// The rendezvous and peer exchange options are required
+    /// when the internet section is sent.
+    fn update_from_proto(
+        update: proto::ConfigurationUpdate,
+    ) -> Result<ConfigurationUpdate, Vec<ConfigurationError>> {
+        let internet = match update.internet {
+            Some(internet) => {
+                let rendezvous = internet.rendezvous.ok_or_else(|| {
+                    vec![ConfigurationError {
+                        field: "internet.rendezvous".to_string(),
+                        message: "missing".to_string(),
+                    }]
+                })?;
+                let peer_exchange = internet.peer_exchange.ok_or_else(|| {
+                    vec![ConfigurationError {
+                        field: "internet.peer_exchange".to_string(),
+                        message: "missing".to_string(),
+                    }]
+                })?;
+                Some(configuration::Internet {
+                    active: internet.active,
+                    peers: internet
+                        .peers
+                        .into_iter()
+                        .map(|peer| configuration::InternetPeer {
+                            address: peer.address,
+                            name: peer.name,
+                            enabled: peer.enabled,
+                            discovered: peer.discovered,
+                        })
+                        .collect(),
+                    do_listen: internet.do_listen,
+                    listen: internet.listen,
+                    rendezvous: configuration::RendezvousOptions {
+                        enabled: rendezvous.enabled,
+                        server: rendezvous.server,
+                        namespace: rendezvous.namespace,
+                        ttl: rendezvous.ttl,
+                        discover_interval: rendezvous.discover_interval,
+                    },
+                    peer_exchange: configuration::PeerExchangeOptions {
+                        enabled: peer_exchange.enabled,
+                        exchange_interval: peer_exchange.exchange_interval,
+                        max_shared: peer_exchange.max_shared,
+                        max_auto_peers: peer_exchange.max_auto_peers,
+                        seen_period_limit: peer_exchange.seen_period_limit,
+                    },
+                })
+            }
+            None => None,
+        };
+
+        Ok(ConfigurationUpdate {
+            lan: update.lan.map(|lan| configuration::Lan {
+                active: lan.active,
+                listen: lan.listen,
+            }),
+            internet,
+            routing: update.routing.map(|routing| configuration::RoutingOptions {
+                sending_table_period: routing.sending_table_period,
+                ping_neighbour_period: routing.ping_neighbour_period,
+                hop_count_penalty: routing.hop_count_penalty,
+                maintain_period_limit: routing.maintain_period_limit,
+            }),
+            debug: update
+                .debug
+                .map(|debug| configuration::DebugOption { log: debug.log }),
+            feed: update.feed.map(|feed| configuration::FeedOptions {
+                max_age: feed.max_age,
+                max_size: feed.max_size,
+                sync_window: feed.sync_window,
+                sync_interval: feed.sync_interval,
+            }),
+        })
+    }
+}
@@ -6,7 +6,7 @@
 //! Messages to debug libqaul
 
 use super::Rpc;
-use crate::storage::configuration::Configuration;
+use crate::storage::configuration::{Configuration, DebugOption};
 use crate::storage::Storage;
 use crate::utilities::filelogger::FileLogger;
 use prost::Message;
@@ -54,23 +54,15 @@ impl Debug {
                         panic!("Libqaul panics for debugging reasons");
                     }
                     Some(proto::debug::Message::LogToFile(log_to_file)) => {
-                        if log_to_file.enable {
-                            // start log
-                            FileLogger::enable(true);
-                            if Configuration::get_debug_log() == false {
-                                Configuration::enable_debug_log(true);
-                                Configuration::save();
-                                log::info!("starting debug log..");
+                        if Configuration::get_debug_log() != log_to_file.enable {
+                            match Configuration::set_debug(DebugOption {
+                                log: log_to_file.enable,
+                            }) {
+                                Ok(_) => log::info!("debug log enabled: {}", log_to_file.enable),
+                                Err(errors) => log::error!("{:?}", errors),
                             }
-                        } else {
-                            // stop log
-                            if Configuration::get_debug_log() == true {
-                                Configuration::enable_debug_log(false);
-                                Configuration::save();
-                                log::info!("stop debug log..");
-                            }
-                            FileLogger::enable(false);
                         }
+                        FileLogger::enable(log_to_file.enable);
                     }
                     Some(proto::debug::Message::StoragePathRequest(_storage_path_request)) => {
                         // create and return storage path response message
@@ -0,0 +1,126 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # RPC Storage Encryption Messages
+//!
+//! Unlock the encrypted storage, enable encryption at rest
+//! and change the storage passphrase.
+
+use super::Rpc;
+use crate::storage::encryption::{Encryption as StorageEncryption, EncryptionStatus};
+use prost::Message;
+
+/// Import protobuf message definition generated by
+/// the rust module prost-build.
+pub mod proto {
+    include!("qaul.rpc.encryption.rs");
+}
+
+/// RPC Storage Encryption Module
+pub struct Encryption {}
+
+impl Encryption {
+    /// Process incoming RPC request messages for the encryption module
+    pub fn rpc(data: Vec<u8>, request_id: String) {
+        match proto::Encryption::decode(&data[..]) {
+            Ok(encryption) => match encryption.message {
+                Some(proto::encryption::Message::StateRequest(_request)) => {
+                    Self::send(
+                        proto::encryption::Message::State(Self::state_to_proto(
+                            StorageEncryption::status(),
+                        )),
+                        request_id,
+                    );
+                }
+                Some(proto::encryption::Message::Unlock(unlock)) => {
+                    let result = StorageEncryption::unlock(&unlock.passphrase);
+                    Self::send_result(result, request_id);
+                }
+                Some(proto::encryption::Message::Enable(enable)) => {
+                    let result = StorageEncryption::enable(&enable.passphrase);
+                    Self::send_result(result, request_id);
+                }
+                Some(proto::encryption::Message::ChangePassphrase(change)) => {
+                    let result = StorageEncryption::change_passphrase(
+                        &change.old_passphrase,
+                        &change.new_passphrase,
+                    );
+                    Self::send_result(result, request_id);
+                }
+                _ => {
+                    log::error!("Unhandled RPC Encryption Message");
+                }
+            },
+            Err(e) => {
+                log::error!("{:?}", e);
+            }
+        }
+    }
+
+    /// Process incoming RPC messages while the storage is locked
+    ///
+    /// Only the state request and the unlock message are accepted.
+    pub fn rpc_locked(data: Vec<u8>, request_id: String) {
+        match proto::Encryption::decode(&data[..]) {
+            Ok(proto::Encryption {
+                message:
+                    Some(
+                        proto::encryption::Message::StateRequest(_)
+                        | proto::encryption::Message::Unlock(_),
+                    ),
+            }) => Self::rpc(data, request_id),
+            Ok(_) => {
+                Self::send_result(Err("storage is locked".to_string()), request_id);
+            }
+            Err(e) => {
+                log::error!("{:?}", e);
+            }
+        }
+    }
+
+    /// send the result of a request
+    fn send_result(result: Result<(), String>, request_id: String) {
+        let (success, error_message) = match result {
+            Ok(()) => (true, String::new()),
+            Err(e) => (false, e),
+        };
+        Self::send(
+            proto::encryption::Message::Result(proto::EncryptionResult {
+                success,
+                error_message,
+                state: Some(Self::state_to_proto(StorageEncryption::status())),
+            }),
+            request_id,
+        );
+    }
+
+    /// encode and send an encryption message
+    fn send(message: proto::encryption::Message, request_id: String) {
+        let proto_message = proto::Encryption {
+            message: Some(message),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Encryption.into(),
+            request_id,
+            Vec::new(),
+        );
+    }
+
+    /// translate the encryption status to protobuf
+    fn state_to_proto(status: EncryptionStatus) -> proto::EncryptionState {
+        proto::EncryptionState {
+            enabled: status.enabled,
+            locked: status.locked,
+            migrated: status.migrated,
+        }
+    }
+}
@@ -6,7 +6,9 @@
 //! The RPC messages are defined in the protobuf format.
 //! The format is then translated to rust program code.
 
+pub mod configuration;
 pub mod debug;
+pub mod encryption;
 pub mod sys;
 
 use crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError};
@@ -28,7 +30,9 @@ use crate::services::dtn::Dtn;
 use crate::services::feed::Feed;
 use crate::services::group::Group;
 use crate::services::rtc::Rtc;
+use configuration::Configuration;
 use debug::Debug;
+use encryption::Encryption;
 
 /// Import protobuf message definition generated by
 /// the rust module prost-build.
@@ -135,7 +139,38 @@ impl Rpc {
         Self::increase_message_counter();
 
         match QaulRpc::decode(&data[..]) {
-            Ok(message) => {
+            Ok(mut message) => {
+                // services acting on behalf of a user account need one,
+                // messages without user id use the account of the session
+                let needs_account = matches!(
+                    Modules::try_from(message.module),
+                    Ok(Modules::Users
+                        | Modules::Feed
+                        | Modules::Chat
+                        | Modules::Chatfile
+                        | Modules::Group
+                        | Modules::Rtc
+                        | Modules::Dtn)
+                );
+                if needs_account && message.user_id.is_empty() {
+                    if let Some(account_id) =
+                        UserAccounts::get_session_account(&message.session_token)
+                    {
+                        message.user_id = account_id.to_bytes();
+                    }
+                }
+
+                // only the session of a logged in user account can act
+                // on its behalf, the user accounts module handles the
+                // login itself
+                if message.module != Modules::Useraccounts as i32
+                    && (needs_account || !message.user_id.is_empty())
+                    && !UserAccounts::is_authorized(&message.user_id, &message.session_token)
+                {
+                    log::error!("RPC message without a valid session of the user account");
+                    return;
+                }
+
                 match Modules::try_from(message.module) {
                     Ok(Modules::Node) => {
                         Self::increase_message_counter();
@@ -146,7 +181,7 @@ impl Rpc {
                         // TODO: authorisation
                     }
                     Ok(Modules::Useraccounts) => {
-                        UserAccounts::rpc(message.data);
+                        UserAccounts::rpc(message.data, message.session_token);
                     }
                     Ok(Modules::Users) => {
                         Users::rpc(message.data, message.user_id);
@@ -158,7 +193,7 @@ impl Rpc {
                         Feed::rpc(message.data, message.user_id, lan, internet);
                     }
                     Ok(Modules::Connections) => {
-                        Connections::rpc(message.data, internet);
+                        Connections::rpc(message.data);
                     }
                     Ok(Modules::Ble) => {
                         Ble::rpc(message.data);
@@ -185,6 +220,14 @@ impl Rpc {
                         log::trace!("Message Modules::Group received");
                         Dtn::rpc(message.data, message.user_id);
                     }
+                    Ok(Modules::Configuration) => {
+                        log::trace!("Message Modules::Configuration received");
+                        Configuration::rpc(message.data, message.request_id);
+                    }
+                    Ok(Modules::Encryption) => {
+                        log::trace!("Message Modules::Encryption received");
+                        Encryption::rpc(message.data, message.request_id);
+                    }
                     Ok(Modules::None) => {
                         log::error!("Message Modules::None received");
                     }
@@ -199,6 +242,26 @@ impl Rpc {
         }
     }
 
+    /// Process received RPC message while the storage is locked
+    ///
+    /// The node is not initialized yet, only messages of the
+    /// encryption module are processed to unlock the storage.
+    pub fn process_locked_message(data: Vec<u8>) {
+        match QaulRpc::decode(&data[..]) {
+            Ok(message) => match Modules::try_from(message.module) {
+                Ok(Modules::Encryption) => {
+                    Encryption::rpc_locked(message.data, message.request_id);
+                }
+                _ => {
+                    log::error!("RPC message received while the storage is locked");
+                }
+            },
+            Err(error) => {
+                log::error!("{:?}", error);
+            }
+        }
+    }
+
     /// sends an RPC message to the outside
     pub fn send_message(data: Vec<u8>, module: i32, request_id: String, user_id: Vec<u8>) {
         // Create RPC message container
@@ -207,6 +270,7 @@ impl Rpc {
             request_id,
             user_id,
             data,
+            session_token: Vec::new(),
         };
 
         // encode message
@@ -10,7 +10,7 @@ pub struct FeedContainer {
     pub message: ::prost::alloc::vec::Vec<u8>,
 }
 /// Feed Message Content
-#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct FeedMessageContent {
     /// sender id
     #[prost(bytes = "vec", tag = "1")]
@@ -21,4 +21,36 @@ pub struct FeedMessageContent {
     /// timestamp in milliseconds
     #[prost(uint64, tag = "3")]
     pub time: u64,
+    /// attached files
+    #[prost(message, repeated, tag = "4")]
+    pub attachments: ::prost::alloc::vec::Vec<FeedAttachment>,
+    /// hashtags without the leading '#', in lower case
+    #[prost(string, repeated, tag = "5")]
+    pub hashtags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// message id of the feed message this is a reply to
+    #[prost(bytes = "vec", tag = "6")]
+    pub reply_to: ::prost::alloc::vec::Vec<u8>,
+    /// expiry timestamp in milliseconds
+    ///
+    /// 0 means the message does not expire.
+    #[prost(uint64, tag = "7")]
+    pub expires: u64,
+}
+/// Reference to a file attached to a feed message
+///
+/// The file itself is not contained in the feed message.
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FeedAttachment {
+    /// file name
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// mime type of the file
+    #[prost(string, tag = "2")]
+    pub mime_type: ::prost::alloc::string::String,
+    /// file size in bytes
+    #[prost(uint64, tag = "3")]
+    pub size: u64,
+    /// where to retrieve the file, e.g. a URL or a file ID
+    #[prost(string, tag = "4")]
+    pub reference: ::prost::alloc::string::String,
 }
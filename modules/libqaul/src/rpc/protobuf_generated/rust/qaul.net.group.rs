@@ -2,7 +2,7 @@
 /// Group network message container
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GroupContainer {
-    #[prost(oneof = "group_container::Message", tags = "1, 2, 3, 4")]
+    #[prost(oneof = "group_container::Message", tags = "1, 2, 3, 4, 5")]
     pub message: ::core::option::Option<group_container::Message>,
 }
 /// Nested message and enum types in `GroupContainer`.
@@ -21,6 +21,9 @@ pub mod group_container {
         /// member removed
         #[prost(message, tag = "4")]
         Removed(super::RemovedMember),
+        /// sender key of a group member
+        #[prost(message, tag = "5")]
+        SenderKey(super::SenderKeyDistribution),
     }
 }
 /// Invite member
@@ -104,6 +107,27 @@ pub struct RemovedMember {
     #[prost(bytes = "vec", tag = "1")]
     pub group_id: ::prost::alloc::vec::Vec<u8>,
 }
+/// Sender key distribution
+///
+/// Each group member encrypts its group messages with
+/// its own symmetric sender key chain.
+/// The chain is sent to every group member via the
+/// pairwise encrypted messaging sessions.
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SenderKeyDistribution {
+    /// group id
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// sender key id
+    #[prost(uint32, tag = "2")]
+    pub key_id: u32,
+    /// chain key at the given iteration
+    #[prost(bytes = "vec", tag = "3")]
+    pub chain_key: ::prost::alloc::vec::Vec<u8>,
+    /// chain iteration
+    #[prost(uint32, tag = "4")]
+    pub iteration: u32,
+}
 /// Group member state
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
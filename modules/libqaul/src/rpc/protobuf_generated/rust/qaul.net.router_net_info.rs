@@ -35,11 +35,42 @@ pub struct RouterInfoMessage {
     #[prost(message, optional, tag = "2")]
     pub routes: ::core::option::Option<RoutingInfoTable>,
     /// Latest Feed ids table
+    ///
+    /// Only sent together with the feed filter,
+    /// for nodes without feed filter support.
     #[prost(message, optional, tag = "4")]
     pub feeds: ::core::option::Option<FeedIdsTable>,
     /// timestamp
     #[prost(uint64, tag = "5")]
     pub timestamp: u64,
+    /// Feed synchronisation filter
+    ///
+    /// Only sent every feed sync interval.
+    #[prost(message, optional, tag = "6")]
+    pub feed_filter: ::core::option::Option<FeedSyncFilter>,
+}
+/// Feed synchronisation filter
+///
+/// Bloom filter over the ids of all feed messages
+/// sent within the time window.
+/// The receiver answers with a FeedResponseMessage
+/// containing the messages of the time window
+/// which are not in the filter.
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FeedSyncFilter {
+    /// start of the time window
+    /// timestamp in milli seconds
+    #[prost(uint64, tag = "1")]
+    pub since: u64,
+    /// filter bits
+    #[prost(bytes = "vec", tag = "2")]
+    pub bits: ::prost::alloc::vec::Vec<u8>,
+    /// number of hash functions
+    #[prost(uint32, tag = "3")]
+    pub hashes: u32,
+    /// seed of the hash functions
+    #[prost(uint32, tag = "4")]
+    pub seed: u32,
 }
 /// Routing information to send to neighbours
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -134,6 +165,13 @@ pub struct FeedMessage {
     /// timestamp in milli seconds
     #[prost(uint64, tag = "4")]
     pub time: u64,
+    /// signed message
+    ///
+    /// The encoded `qaul.net.feed.FeedMessageContent`,
+    /// the message id is its signature.
+    /// Empty for messages stored before it was added.
+    #[prost(bytes = "vec", tag = "5")]
+    pub message: ::prost::alloc::vec::Vec<u8>,
 }
 /// User update message
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -159,6 +197,29 @@ pub struct UserResponseMessage {
     #[prost(message, optional, tag = "1")]
     pub users: ::core::option::Option<UserInfoTable>,
 }
+/// Peer exchange message
+///
+/// shares the addresses of recently seen
+/// peers with the neighbours
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PeerExchangeMessage {
+    /// peer entries
+    #[prost(message, repeated, tag = "1")]
+    pub peers: ::prost::alloc::vec::Vec<PeerExchangeEntry>,
+}
+/// Peer exchange entry
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct PeerExchangeEntry {
+    /// libp2p peer id of the node
+    #[prost(bytes = "vec", tag = "1")]
+    pub peer_id: ::prost::alloc::vec::Vec<u8>,
+    /// multiaddresses the node was seen on
+    #[prost(string, repeated, tag = "2")]
+    pub addresses: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// last time the node was seen, in milli seconds
+    #[prost(uint64, tag = "3")]
+    pub last_seen: u64,
+}
 /// RouterInfoModule
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -175,6 +236,8 @@ pub enum RouterInfoModule {
     UserResponse = 4,
     /// Message is a UserUpdateMessage
     UserUpdate = 5,
+    /// Message is a PeerExchangeMessage
+    PeerExchange = 6,
 }
 impl RouterInfoModule {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -189,6 +252,7 @@ impl RouterInfoModule {
             Self::UserRequest => "USER_REQUEST",
             Self::UserResponse => "USER_RESPONSE",
             Self::UserUpdate => "USER_UPDATE",
+            Self::PeerExchange => "PEER_EXCHANGE",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -200,6 +264,7 @@ impl RouterInfoModule {
             "USER_REQUEST" => Some(Self::UserRequest),
             "USER_RESPONSE" => Some(Self::UserResponse),
             "USER_UPDATE" => Some(Self::UserUpdate),
+            "PEER_EXCHANGE" => Some(Self::PeerExchange),
             _ => None,
         }
     }
@@ -1,13 +1,13 @@
 // This file is @generated by prost-build.
 /// RTC network message container
-#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RtcContainer {
-    #[prost(oneof = "rtc_container::Message", tags = "1, 2, 3")]
+    #[prost(oneof = "rtc_container::Message", tags = "1, 2, 3, 4")]
     pub message: ::core::option::Option<rtc_container::Message>,
 }
 /// Nested message and enum types in `RtcContainer`.
 pub mod rtc_container {
-    #[derive(Clone, PartialEq, Eq, Hash, ::prost::Oneof)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum Message {
         /// rtc session request (with SDP offer)
         #[prost(message, tag = "1")]
@@ -18,6 +18,9 @@ pub mod rtc_container {
         /// ICE candidate exchange
         #[prost(message, tag = "3")]
         RtcIceCandidate(super::RtcIceCandidate),
+        /// group call state, sent by the host
+        #[prost(message, tag = "4")]
+        RtcGroupState(super::RtcGroupState),
     }
 }
 /// rtc session request (caller sends this with SDP offer)
@@ -29,16 +32,80 @@ pub struct RtcSessionRequest {
     /// SDP offer from caller (JSON serialized str0m SdpOffer)
     #[prost(string, tag = "2")]
     pub sdp_offer: ::prost::alloc::string::String,
+    /// qaul group id of a group call
+    ///
+    /// empty for direct calls
+    #[prost(bytes = "vec", tag = "3")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// user id of the group call host, running the SFU
+    #[prost(bytes = "vec", tag = "4")]
+    pub host_id: ::prost::alloc::vec::Vec<u8>,
 }
 /// rtc session management (callee responds with SDP answer when accepting)
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct RtcSessionManagement {
-    /// option (1: accept, 2: deny, 3: end)
+    /// option (1: accept, 2: deny, 3: end, 4: join group call, 5: mute state,
+    /// 6: ICE restart offer, 7: ICE restart answer,
+    /// 8: share offer, 9: share answer, 10: share stop,
+    /// 11: recording started, 12: recording stopped)
     #[prost(uint32, tag = "1")]
     pub option: u32,
-    /// SDP answer from callee (JSON serialized str0m SdpAnswer, only when option=1)
+    /// SDP answer from callee (JSON serialized str0m SdpAnswer, only when option=1, 7 or 9)
     #[prost(string, tag = "2")]
     pub sdp_answer: ::prost::alloc::string::String,
+    /// qaul group id of a group call
+    ///
+    /// empty for direct calls
+    #[prost(bytes = "vec", tag = "3")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// SDP offer of a participant joining a group call (only when option=4),
+    /// of an ICE restart (only when option=6) or of a share (only when option=8)
+    #[prost(string, tag = "4")]
+    pub sdp_offer: ::prost::alloc::string::String,
+    /// audio of the participant is muted
+    #[prost(bool, tag = "5")]
+    pub audio_muted: bool,
+    /// video of the participant is muted
+    #[prost(bool, tag = "6")]
+    pub video_muted: bool,
+    /// id of a screen or file share within the session (only when option=8, 9 or 10)
+    #[prost(string, tag = "7")]
+    pub share_id: ::prost::alloc::string::String,
+}
+/// group call state
+///
+/// The host sends it to all group members,
+/// whenever the participants change.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RtcGroupState {
+    /// qaul group id
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// user id of the host
+    #[prost(bytes = "vec", tag = "2")]
+    pub host_id: ::prost::alloc::vec::Vec<u8>,
+    /// session type (1: audio, 2: video, 3: screen share)
+    #[prost(uint32, tag = "3")]
+    pub session_type: u32,
+    /// participants of the call
+    #[prost(message, repeated, tag = "4")]
+    pub participants: ::prost::alloc::vec::Vec<RtcParticipant>,
+}
+/// group call participant
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RtcParticipant {
+    /// user id
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// audio is muted
+    #[prost(bool, tag = "2")]
+    pub audio_muted: bool,
+    /// video is muted
+    #[prost(bool, tag = "3")]
+    pub video_muted: bool,
+    /// joined at
+    #[prost(uint64, tag = "4")]
+    pub joined_at: u64,
 }
 /// ICE candidate message for trickle ICE
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -52,6 +119,19 @@ pub struct RtcIceCandidate {
     /// SDP m-line index
     #[prost(uint32, tag = "3")]
     pub sdp_mline_index: u32,
+    /// qaul group id of a group call
+    ///
+    /// empty for direct calls
+    #[prost(bytes = "vec", tag = "4")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// ICE username fragment of the session the candidate belongs to
+    ///
+    /// Candidates of a former ICE session are dropped after an ICE restart.
+    #[prost(string, tag = "5")]
+    pub ufrag: ::prost::alloc::string::String,
+    /// all candidates have been sent
+    #[prost(bool, tag = "6")]
+    pub end_of_candidates: bool,
 }
 /// Rtc message
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -233,6 +233,9 @@ pub struct GroupMember {
     /// college
     #[prost(string, tag = "10")]
     pub college: ::prost::alloc::string::String,
+    /// trust level of the user's public key
+    #[prost(enumeration = "TrustLevel", tag = "11")]
+    pub trust: i32,
 }
 /// Group info response
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -379,6 +382,39 @@ impl GroupMemberRole {
         }
     }
 }
+/// trust level of a user's public key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum TrustLevel {
+    /// the key has not been verified
+    Unverified = 0,
+    /// the key has been verified
+    Verified = 1,
+    /// the key of this user has changed
+    KeyChanged = 2,
+}
+impl TrustLevel {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unverified => "UNVERIFIED",
+            Self::Verified => "VERIFIED",
+            Self::KeyChanged => "KEY_CHANGED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "UNVERIFIED" => Some(Self::Unverified),
+            "VERIFIED" => Some(Self::Verified),
+            "KEY_CHANGED" => Some(Self::KeyChanged),
+            _ => None,
+        }
+    }
+}
 /// Group Status
 ///
 /// Indicates the working status of a group.
@@ -1,13 +1,16 @@
 // This file is @generated by prost-build.
 /// user account rpc message container
-#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UserAccounts {
-    #[prost(oneof = "user_accounts::Message", tags = "1, 2, 3, 4")]
+    #[prost(
+        oneof = "user_accounts::Message",
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11"
+    )]
     pub message: ::core::option::Option<user_accounts::Message>,
 }
 /// Nested message and enum types in `UserAccounts`.
 pub mod user_accounts {
-    #[derive(Clone, PartialEq, Eq, Hash, ::prost::Oneof)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum Message {
         #[prost(bool, tag = "1")]
         GetDefaultUserAccount(bool),
@@ -17,6 +20,27 @@ pub mod user_accounts {
         DefaultUserAccount(super::DefaultUserAccount),
         #[prost(message, tag = "4")]
         MyUserAccount(super::MyUserAccount),
+        /// request all user accounts of this node
+        #[prost(bool, tag = "5")]
+        GetUserAccounts(bool),
+        /// list of all user accounts of this node
+        #[prost(message, tag = "6")]
+        UserAccountList(super::UserAccountList),
+        /// log in a user account
+        #[prost(message, tag = "7")]
+        LoginUserAccount(super::LoginUserAccount),
+        /// log out a user account
+        #[prost(message, tag = "8")]
+        LogoutUserAccount(super::LogoutUserAccount),
+        /// switch the current user account
+        #[prost(message, tag = "9")]
+        SwitchUserAccount(super::SwitchUserAccount),
+        /// set, change or remove the passphrase
+        #[prost(message, tag = "10")]
+        SetPassphrase(super::SetPassphrase),
+        /// result of a session request
+        #[prost(message, tag = "11")]
+        SessionResult(super::SessionResult),
     }
 }
 /// create a new user on this node
@@ -32,6 +56,9 @@ pub struct CreateUserAccount {
     pub reg_no: ::core::option::Option<::prost::alloc::string::String>,
     #[prost(string, optional, tag = "5")]
     pub college: ::core::option::Option<::prost::alloc::string::String>,
+    /// protect the account with a passphrase
+    #[prost(string, optional, tag = "6")]
+    pub passphrase: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// Session Information
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -65,3 +92,75 @@ pub struct MyUserAccount {
     #[prost(string, optional, tag = "10")]
     pub college: ::core::option::Option<::prost::alloc::string::String>,
 }
+/// List of all user accounts
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserAccountList {
+    #[prost(message, repeated, tag = "1")]
+    pub user_accounts: ::prost::alloc::vec::Vec<UserAccountEntry>,
+    /// id of the current user account
+    #[prost(bytes = "vec", tag = "2")]
+    pub current: ::prost::alloc::vec::Vec<u8>,
+}
+/// User account entry
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct UserAccountEntry {
+    #[prost(message, optional, tag = "1")]
+    pub my_user_account: ::core::option::Option<MyUserAccount>,
+    /// account is logged in
+    #[prost(bool, tag = "2")]
+    pub logged_in: bool,
+    /// account is protected by a passphrase
+    #[prost(bool, tag = "3")]
+    pub has_passphrase: bool,
+}
+/// Log in a user account
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LoginUserAccount {
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(string, optional, tag = "2")]
+    pub passphrase: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Log out a user account
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LogoutUserAccount {
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// required if the account is protected by a passphrase
+    #[prost(string, optional, tag = "2")]
+    pub passphrase: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Switch the current user account
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SwitchUserAccount {
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// required if the account is protected by a passphrase
+    #[prost(string, optional, tag = "2")]
+    pub passphrase: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Set, change or remove the passphrase of a user account
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SetPassphrase {
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(string, optional, tag = "2")]
+    pub old_passphrase: ::core::option::Option<::prost::alloc::string::String>,
+    /// no or empty passphrase removes the protection
+    #[prost(string, optional, tag = "3")]
+    pub new_passphrase: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Result of a session request
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SessionResult {
+    #[prost(bool, tag = "1")]
+    pub success: bool,
+    #[prost(string, tag = "2")]
+    pub message: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "3")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// session token for the RPC messages of this account,
+    /// sent when an account was created, logged in or switched to
+    #[prost(bytes = "vec", tag = "4")]
+    pub session_token: ::prost::alloc::vec::Vec<u8>,
+}
@@ -0,0 +1,275 @@
+// This file is @generated by prost-build.
+/// Libqaul RPC Configuration Messages
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Configuration {
+    /// message type
+    #[prost(oneof = "configuration::Message", tags = "1, 2, 3, 4, 5, 6")]
+    pub message: ::core::option::Option<configuration::Message>,
+}
+/// Nested message and enum types in `Configuration`.
+pub mod configuration {
+    /// message type
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Message {
+        /// request the current configuration
+        #[prost(message, tag = "1")]
+        ConfigurationRequest(super::ConfigurationRequest),
+        /// current configuration
+        #[prost(message, tag = "2")]
+        ConfigurationInfo(super::ConfigurationInfo),
+        /// validate a configuration update
+        /// without applying it
+        #[prost(message, tag = "3")]
+        ValidateRequest(super::ConfigurationUpdate),
+        /// validate and apply a configuration update
+        #[prost(message, tag = "4")]
+        UpdateRequest(super::ConfigurationUpdate),
+        /// result of a validate or update request
+        #[prost(message, tag = "5")]
+        ConfigurationResult(super::ConfigurationResult),
+        /// the configuration was changed
+        ///
+        /// this message is sent to the UI
+        /// after every applied change
+        #[prost(message, tag = "6")]
+        ConfigurationChanged(super::ConfigurationChanged),
+    }
+}
+/// Request the current configuration
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ConfigurationRequest {}
+/// The runtime changeable configuration of libqaul
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfigurationInfo {
+    /// LAN connection module
+    #[prost(message, optional, tag = "1")]
+    pub lan: ::core::option::Option<LanConfiguration>,
+    /// Internet overlay connection module
+    #[prost(message, optional, tag = "2")]
+    pub internet: ::core::option::Option<InternetConfiguration>,
+    /// routing options
+    #[prost(message, optional, tag = "3")]
+    pub routing: ::core::option::Option<RoutingConfiguration>,
+    /// debugging options
+    #[prost(message, optional, tag = "4")]
+    pub debug: ::core::option::Option<DebugConfiguration>,
+    /// feed options
+    #[prost(message, optional, tag = "5")]
+    pub feed: ::core::option::Option<FeedConfiguration>,
+}
+/// Configuration Update
+///
+/// Only the sections that are set are changed.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfigurationUpdate {
+    /// LAN connection module
+    #[prost(message, optional, tag = "1")]
+    pub lan: ::core::option::Option<LanConfiguration>,
+    /// Internet overlay connection module
+    #[prost(message, optional, tag = "2")]
+    pub internet: ::core::option::Option<InternetConfiguration>,
+    /// routing options
+    #[prost(message, optional, tag = "3")]
+    pub routing: ::core::option::Option<RoutingConfiguration>,
+    /// debugging options
+    #[prost(message, optional, tag = "4")]
+    pub debug: ::core::option::Option<DebugConfiguration>,
+    /// feed options
+    #[prost(message, optional, tag = "5")]
+    pub feed: ::core::option::Option<FeedConfiguration>,
+}
+/// LAN connection module configuration
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LanConfiguration {
+    /// module is active
+    #[prost(bool, tag = "1")]
+    pub active: bool,
+    /// listening multiaddresses
+    #[prost(string, repeated, tag = "2")]
+    pub listen: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Internet overlay connection module configuration
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InternetConfiguration {
+    /// module is active
+    #[prost(bool, tag = "1")]
+    pub active: bool,
+    /// statically configured and discovered peers
+    #[prost(message, repeated, tag = "2")]
+    pub peers: ::prost::alloc::vec::Vec<InternetPeer>,
+    /// listen for incoming connections
+    #[prost(bool, tag = "3")]
+    pub do_listen: bool,
+    /// listening multiaddresses
+    #[prost(string, repeated, tag = "4")]
+    pub listen: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// rendezvous discovery options
+    #[prost(message, optional, tag = "5")]
+    pub rendezvous: ::core::option::Option<RendezvousConfiguration>,
+    /// peer exchange options
+    #[prost(message, optional, tag = "6")]
+    pub peer_exchange: ::core::option::Option<PeerExchangeConfiguration>,
+}
+/// Internet peer
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct InternetPeer {
+    /// multiaddress of the peer
+    #[prost(string, tag = "1")]
+    pub address: ::prost::alloc::string::String,
+    /// name of the peer
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    /// connect to this peer
+    #[prost(bool, tag = "3")]
+    pub enabled: bool,
+    /// peer was found via rendezvous or peer exchange
+    #[prost(bool, tag = "4")]
+    pub discovered: bool,
+}
+/// Rendezvous discovery configuration
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RendezvousConfiguration {
+    /// discovery is enabled
+    #[prost(bool, tag = "1")]
+    pub enabled: bool,
+    /// multiaddress of the rendezvous server,
+    /// including the /p2p/ peer id
+    #[prost(string, tag = "2")]
+    pub server: ::prost::alloc::string::String,
+    /// registration namespace
+    #[prost(string, tag = "3")]
+    pub namespace: ::prost::alloc::string::String,
+    /// registration time to live in seconds
+    #[prost(uint64, tag = "4")]
+    pub ttl: u64,
+    /// discover interval in seconds
+    #[prost(uint64, tag = "5")]
+    pub discover_interval: u64,
+}
+/// Peer exchange configuration
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct PeerExchangeConfiguration {
+    /// peer exchange is enabled
+    #[prost(bool, tag = "1")]
+    pub enabled: bool,
+    /// exchange interval in seconds
+    #[prost(uint64, tag = "2")]
+    pub exchange_interval: u64,
+    /// maximal number of shared addresses per message
+    #[prost(uint32, tag = "3")]
+    pub max_shared: u32,
+    /// maximal number of discovered peers kept
+    #[prost(uint32, tag = "4")]
+    pub max_auto_peers: u32,
+    /// how long a seen peer is shared in seconds
+    #[prost(uint64, tag = "5")]
+    pub seen_period_limit: u64,
+}
+/// Routing configuration
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RoutingConfiguration {
+    /// send the routing table every x seconds
+    #[prost(uint64, tag = "1")]
+    pub sending_table_period: u64,
+    /// ping the neighbours every x seconds
+    #[prost(uint64, tag = "2")]
+    pub ping_neighbour_period: u64,
+    /// hop count penalty in seconds
+    #[prost(uint64, tag = "3")]
+    pub hop_count_penalty: u64,
+    /// how long a route is kept in seconds
+    #[prost(uint64, tag = "4")]
+    pub maintain_period_limit: u64,
+}
+/// Feed configuration
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FeedConfiguration {
+    /// how long feed messages are kept in seconds, 0 keeps them forever
+    #[prost(uint64, tag = "1")]
+    pub max_age: u64,
+    /// maximal size of the stored feed in MB, 0 for no limit
+    #[prost(uint32, tag = "2")]
+    pub max_size: u32,
+    /// time window of the feed synchronisation in seconds
+    #[prost(uint64, tag = "3")]
+    pub sync_window: u64,
+    /// synchronise the feed with the neighbours every x seconds
+    #[prost(uint64, tag = "4")]
+    pub sync_interval: u64,
+}
+/// Debugging configuration
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct DebugConfiguration {
+    /// log to file
+    #[prost(bool, tag = "1")]
+    pub log: bool,
+}
+/// Result of a validate or update request
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfigurationResult {
+    /// the update is valid (and was applied)
+    #[prost(bool, tag = "1")]
+    pub success: bool,
+    /// validation errors
+    #[prost(message, repeated, tag = "2")]
+    pub errors: ::prost::alloc::vec::Vec<ConfigurationError>,
+    /// sections that were changed
+    #[prost(enumeration = "ConfigurationSection", repeated, tag = "3")]
+    pub changed: ::prost::alloc::vec::Vec<i32>,
+}
+/// Validation Error
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ConfigurationError {
+    /// path of the invalid field
+    #[prost(string, tag = "1")]
+    pub field: ::prost::alloc::string::String,
+    /// description of the problem
+    #[prost(string, tag = "2")]
+    pub message: ::prost::alloc::string::String,
+}
+/// The configuration has changed
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ConfigurationChanged {
+    /// changed sections
+    #[prost(enumeration = "ConfigurationSection", repeated, tag = "1")]
+    pub sections: ::prost::alloc::vec::Vec<i32>,
+}
+/// Configuration Sections
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ConfigurationSection {
+    Lan = 0,
+    Internet = 1,
+    Routing = 2,
+    Debug = 3,
+    UserAccounts = 4,
+    Feed = 5,
+}
+impl ConfigurationSection {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Lan => "LAN",
+            Self::Internet => "INTERNET",
+            Self::Routing => "ROUTING",
+            Self::Debug => "DEBUG",
+            Self::UserAccounts => "USER_ACCOUNTS",
+            Self::Feed => "FEED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "LAN" => Some(Self::Lan),
+            "INTERNET" => Some(Self::Internet),
+            "ROUTING" => Some(Self::Routing),
+            "DEBUG" => Some(Self::Debug),
+            "USER_ACCOUNTS" => Some(Self::UserAccounts),
+            "FEED" => Some(Self::Feed),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,94 @@
+// This file is @generated by prost-build.
+/// Libqaul RPC Storage Encryption Messages
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct Encryption {
+    /// message type
+    #[prost(oneof = "encryption::Message", tags = "1, 2, 3, 4, 5, 6")]
+    pub message: ::core::option::Option<encryption::Message>,
+}
+/// Nested message and enum types in `Encryption`.
+pub mod encryption {
+    /// message type
+    #[derive(Clone, PartialEq, Eq, Hash, ::prost::Oneof)]
+    pub enum Message {
+        /// request the encryption state
+        #[prost(message, tag = "1")]
+        StateRequest(super::EncryptionStateRequest),
+        /// encryption state of the storage
+        #[prost(message, tag = "2")]
+        State(super::EncryptionState),
+        /// unlock the encrypted storage
+        ///
+        /// This is the only message libqaul accepts
+        /// while it is locked.
+        #[prost(message, tag = "3")]
+        Unlock(super::UnlockStorage),
+        /// enable encryption at rest
+        #[prost(message, tag = "4")]
+        Enable(super::EnableEncryption),
+        /// change the storage passphrase
+        #[prost(message, tag = "5")]
+        ChangePassphrase(super::ChangeStoragePassphrase),
+        /// result of an unlock, enable or
+        /// change passphrase request
+        #[prost(message, tag = "6")]
+        Result(super::EncryptionResult),
+    }
+}
+/// Request the encryption state
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct EncryptionStateRequest {}
+/// Encryption state of the storage
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct EncryptionState {
+    /// encryption at rest is enabled
+    #[prost(bool, tag = "1")]
+    pub enabled: bool,
+    /// the storage is locked and
+    /// waits for the passphrase
+    #[prost(bool, tag = "2")]
+    pub locked: bool,
+    /// all records have been encrypted
+    #[prost(bool, tag = "3")]
+    pub migrated: bool,
+}
+/// Unlock the encrypted storage
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct UnlockStorage {
+    /// storage passphrase
+    #[prost(string, tag = "1")]
+    pub passphrase: ::prost::alloc::string::String,
+}
+/// Enable encryption at rest
+///
+/// All existing data base records and keys
+/// are encrypted with the new passphrase.
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct EnableEncryption {
+    /// storage passphrase
+    #[prost(string, tag = "1")]
+    pub passphrase: ::prost::alloc::string::String,
+}
+/// Change the storage passphrase
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ChangeStoragePassphrase {
+    /// current storage passphrase
+    #[prost(string, tag = "1")]
+    pub old_passphrase: ::prost::alloc::string::String,
+    /// new storage passphrase
+    #[prost(string, tag = "2")]
+    pub new_passphrase: ::prost::alloc::string::String,
+}
+/// Result of an encryption request
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct EncryptionResult {
+    /// request was successful
+    #[prost(bool, tag = "1")]
+    pub success: bool,
+    /// error message
+    #[prost(string, tag = "2")]
+    pub error_message: ::prost::alloc::string::String,
+    /// the encryption state after the request
+    #[prost(message, optional, tag = "3")]
+    pub state: ::core::option::Option<EncryptionState>,
+}
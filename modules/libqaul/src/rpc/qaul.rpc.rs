@@ -18,6 +18,10 @@ pub struct QaulRpc {
     /// which is passed to the module.
     #[prost(bytes = "vec", tag = "4")]
     pub data: ::prost::alloc::vec::Vec<u8>,
+    /// session token of the user account,
+    /// returned when the account was logged in
+    #[prost(bytes = "vec", tag = "5")]
+    pub session_token: ::prost::alloc::vec::Vec<u8>,
 }
 /// Identification to which module the message shall be
 /// handed to.
@@ -64,6 +68,10 @@ pub enum Modules {
     Rtc = 13,
     /// Delay Tolerant Networking
     Dtn = 14,
+    /// runtime configuration of libqaul
+    Configuration = 15,
+    /// encryption at rest of the node storage
+    Encryption = 16,
 }
 impl Modules {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -87,6 +95,8 @@ impl Modules {
             Self::Ble => "BLE",
             Self::Rtc => "RTC",
             Self::Dtn => "DTN",
+            Self::Configuration => "CONFIGURATION",
+            Self::Encryption => "ENCRYPTION",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -107,6 +117,8 @@ impl Modules {
             "BLE" => Some(Self::Ble),
             "RTC" => Some(Self::Rtc),
             "DTN" => Some(Self::Dtn),
+            "CONFIGURATION" => Some(Self::Configuration),
+            "ENCRYPTION" => Some(Self::Encryption),
             _ => None,
         }
     }
@@ -16,6 +16,7 @@ use crate::connections::ble::Ble;
 use crate::connections::{internet::Internet, lan::Lan};
 use crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError};
 use state::InitCell;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[cfg(target_os = "android")]
 use crate::api::android::Android;
@@ -26,6 +27,8 @@ static EXTERN_RECEIVE: InitCell<Receiver<Vec<u8>>> = InitCell::new();
 static EXTERN_SEND: InitCell<Sender<Vec<u8>>> = InitCell::new();
 /// sending end of th mpsc channel for libqaul to send
 static LIBQAUL_SEND: InitCell<Sender<Vec<u8>>> = InitCell::new();
+/// a host polls the SYS messages via `api::receive_sys`
+static HOST_ATTACHED: AtomicBool = AtomicBool::new(false);
 
 /// Handling of SYS messages of libqaul
 pub struct Sys {}
@@ -61,6 +64,17 @@ impl Sys {
         }
     }
 
+    /// Attach a host polling the SYS messages
+    ///
+    /// Until a host is attached, SYS messages are discarded
+    /// on all systems but Android. When libqaul is already
+    /// running, the BLE module requests the host info again.
+    pub fn attach_host() {
+        if !HOST_ATTACHED.swap(true, Ordering::SeqCst) {
+            Ble::host_attached();
+        }
+    }
+
     /// check the receiving sys channel if there
     /// are new messages from inside libqaul for
     /// the outside.
@@ -71,7 +85,6 @@ impl Sys {
 
     /// send an rpc message from inside libqaul thread
     /// to the extern.
-    #[allow(dead_code)]
     pub fn send_to_extern(message: Vec<u8>) {
         let sender = LIBQAUL_SEND.get().clone();
         match sender.send(message) {
@@ -98,10 +111,20 @@ impl Sys {
     }
 
     /// sends a SYS message to the outside
-    #[allow(unused_variables)]
+    ///
+    /// On Android the message is handed to the Android host,
+    /// all other systems poll it via `api::receive_sys`,
+    /// once a host is attached.
     pub fn send_message(data: Vec<u8>) {
         // send the message
         #[cfg(target_os = "android")]
         Android::send_to_android(data);
+
+        #[cfg(not(target_os = "android"))]
+        if HOST_ATTACHED.load(Ordering::SeqCst) {
+            Self::send_to_extern(data);
+        } else {
+            log::trace!("no SYS host attached, message discarded");
+        }
     }
 }
@@ -22,8 +22,9 @@ use std::{
 };
 
 use super::ChatStorage;
-use crate::services::messaging::{self, Messaging, MessagingServiceType};
+use crate::services::messaging::{self, MessagingServiceType};
 use crate::storage::database::DataBase;
+use crate::storage::encryption::{self, EncryptedTree};
 use crate::utilities::timestamp;
 use crate::utilities::timestamp::Timestamp;
 use crate::{
@@ -60,14 +61,14 @@ pub struct UserFiles {
     ///
     /// key: file ID
     /// value: bincode of `FileHistory`
-    pub histories: sled::Tree,
+    pub histories: EncryptedTree,
     /// file data chunks
     ///
     /// Storage of incoming chunks until receiving is completed.
     ///
     /// key: file_id & chunk_index
     /// value: `Vec<u8>`
-    pub file_chunks: sled::Tree,
+    pub file_chunks: EncryptedTree,
 }
 
 impl UserFiles {
@@ -87,7 +88,7 @@ impl UserFiles {
     }
 
     /// get file history iterator
-    pub fn get_filehistory_iterator(&self) -> sled::Iter {
+    pub fn get_filehistory_iterator(&self) -> encryption::Iter {
         // get key range
         let first_key: u64 = 0;
         let last_key: u64 = u64::MAX;
@@ -170,7 +171,7 @@ impl UserFiles {
     }
 
     /// get all file chunks for a specific id
-    pub fn get_file_chunks(&self, file_id: &Vec<u8>) -> sled::Iter {
+    pub fn get_file_chunks(&self, file_id: &Vec<u8>) -> encryption::Iter {
         // get key range
         let (first_key, last_key) = Self::get_chunk_key_range(file_id);
 
@@ -323,8 +324,8 @@ impl ChatFile {
         let db = DataBase::get_user_db(user_id.clone());
 
         // open trees
-        let histories: sled::Tree = db.open_tree("chat_file").unwrap();
-        let file_chunks: sled::Tree = db.open_tree("file_chunks").unwrap();
+        let histories = EncryptedTree::open(&db, "chat_file");
+        let file_chunks = EncryptedTree::open(&db, "file_chunks");
 
         let user_files = UserFiles {
             histories,
@@ -400,6 +401,32 @@ impl ChatFile {
         files_storage_path.join(file_name.clone())
     }
 
+    /// Get the path of a received file
+    ///
+    /// Returns `None` as long as the file has not been
+    /// received completely. Files sent by this account
+    /// are always available.
+    pub fn received_file_path(account_id: PeerId, file_id: u64) -> Option<PathBuf> {
+        let user_files = Self::get_db_ref(&account_id);
+        let file_history_bytes = user_files
+            .histories
+            .get(file_id.to_be_bytes().to_vec())
+            .ok()??;
+        let file_history: FileHistory = bincode::deserialize(&file_history_bytes).ok()?;
+
+        let received = file_history.sender_id == account_id.to_bytes()
+            || ChatStorage::get_status(&account_id, &file_history.message_id)
+                == Some(super::rpc_proto::MessageStatus::Received);
+        match received {
+            true => Some(Self::create_file_path(
+                account_id,
+                file_id,
+                &file_history.file_extension,
+            )),
+            false => None,
+        }
+    }
+
     /// Getting file histories from table.
     /// This function is called from RPC command (file history [offset limit])
     pub fn file_history(
@@ -722,23 +749,13 @@ impl ChatFile {
         };
 
         // send to all members
-        for user_id in group.members.keys() {
-            let receiver = PeerId::from_bytes(&user_id.clone()).unwrap();
-            if receiver == user_account.id {
-                continue;
-            }
-
-            if let Err(error) = Messaging::pack_and_send_message(
-                user_account,
-                &receiver,
-                message.encode_to_vec(),
-                MessagingServiceType::ChatFile,
-                message_id,
-                true,
-            ) {
-                log::error!("sending file message error {}", error);
-            }
-        }
+        Group::send_to_members(
+            user_account,
+            group,
+            message.encode_to_vec(),
+            MessagingServiceType::ChatFile,
+            message_id,
+        );
     }
 
     /// Generate File id
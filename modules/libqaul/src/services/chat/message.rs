@@ -128,21 +128,18 @@ impl ChatMessage {
 
         // send to all group members
         if let Some(user_account) = UserAccounts::get_by_id(account_id.clone()) {
-            for user_id in group.members.keys() {
-                let receiver = PeerId::from_bytes(&user_id.clone()).unwrap();
-                if receiver != *account_id {
-                    log::trace!("send message to {}", receiver.to_base58());
-                    if let Err(error) = Self::send(&user_account, &receiver, &common_message) {
-                        log::error!("chat message send error - Details: sender: {}, receiver: {}, group_id: {}, message_id: {}, error: {}", 
-                            account_id.to_base58(),
-                            receiver.to_base58(),
-                            bs58::encode(group_id.as_slice()).into_string(),
-                            bs58::encode(&common_message.message_id).into_string(),
-                            error
-                        );
-                    }
-                }
-            }
+            let send_message = proto::Messaging {
+                message: Some(proto::messaging::Message::CommonMessage(
+                    common_message.clone(),
+                )),
+            };
+            Group::send_to_members(
+                &user_account,
+                &group,
+                send_message.encode_to_vec(),
+                MessagingServiceType::Chat,
+                &common_message.message_id,
+            );
         }
 
         // update member state
@@ -41,6 +41,19 @@ pub struct ChatConversationList {
     /// several messages
     #[prost(message, repeated, tag = "2")]
     pub message_list: ::prost::alloc::vec::Vec<ChatMessage>,
+    /// trust levels of the conversation participants
+    #[prost(message, repeated, tag = "3")]
+    pub participants: ::prost::alloc::vec::Vec<ChatParticipantTrust>,
+}
+/// trust level of a conversation participant
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ChatParticipantTrust {
+    /// user id
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// trust level
+    #[prost(enumeration = "TrustLevel", tag = "2")]
+    pub trust: i32,
 }
 /// a single chat message
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -158,6 +171,39 @@ pub struct ChatMessageSend {
     #[prost(string, tag = "2")]
     pub content: ::prost::alloc::string::String,
 }
+/// trust level of a user's public key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum TrustLevel {
+    /// the key has not been verified
+    Unverified = 0,
+    /// the key has been verified
+    Verified = 1,
+    /// the key of this user has changed
+    KeyChanged = 2,
+}
+impl TrustLevel {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unverified => "UNVERIFIED",
+            Self::Verified => "VERIFIED",
+            Self::KeyChanged => "KEY_CHANGED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "UNVERIFIED" => Some(Self::Unverified),
+            "VERIFIED" => Some(Self::Verified),
+            "KEY_CHANGED" => Some(Self::KeyChanged),
+            _ => None,
+        }
+    }
+}
 /// Sending status of sent messages
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
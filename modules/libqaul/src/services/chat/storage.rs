@@ -13,8 +13,10 @@ use std::collections::BTreeMap;
 use std::sync::RwLock;
 
 use super::rpc_proto;
+use crate::router::users::Users;
 use crate::services::group::{group_id::GroupId, GroupStorage};
 use crate::storage::database::DataBase;
+use crate::storage::encryption::EncryptedTree;
 use crate::utilities::timestamp::Timestamp;
 use prost::Message;
 
@@ -27,11 +29,11 @@ pub struct ChatAccountDb {
     /// messages sled data base tree
     ///
     /// value: Vec<u8> bincode of rpc_proto::ChatMessage
-    pub messages: sled::Tree,
+    pub messages: EncryptedTree,
     /// message id => db key
     ///
     /// value: Vec<u8> of db key
-    pub message_ids: sled::Tree,
+    pub message_ids: EncryptedTree,
 }
 
 /// qaul Chat Conversation Storage
@@ -290,6 +292,20 @@ impl ChatStorage {
         }
     }
 
+    /// get message status
+    pub fn get_status(
+        account_id: &PeerId,
+        message_id: &Vec<u8>,
+    ) -> Option<super::rpc_proto::MessageStatus> {
+        // get data base of user account
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+        let key = db_ref.message_ids.get(message_id).ok()??;
+        let chat_msg_fromdb = db_ref.messages.get(&key).ok()??;
+        let chat_msg: rpc_proto::ChatMessage = bincode::deserialize(&chat_msg_fromdb).ok()?;
+
+        super::rpc_proto::MessageStatus::try_from(chat_msg.status).ok()
+    }
+
     /// update message status
     pub fn udate_status(
         account_id: &PeerId,
@@ -350,9 +366,26 @@ impl ChatStorage {
             GroupStorage::group_clear_unread(account_id, group_id.clone());
         }
 
+        // get the trust levels of the participants
+        let mut participants: Vec<rpc_proto::ChatParticipantTrust> = Vec::new();
+        if let Some(group) = GroupStorage::get_group(account_id, group_id.clone()) {
+            for user_id in group.members.keys() {
+                if let Ok(id) = PeerId::from_bytes(user_id) {
+                    if id == account_id {
+                        continue;
+                    }
+                    participants.push(rpc_proto::ChatParticipantTrust {
+                        user_id: user_id.clone(),
+                        trust: Users::get_trust(&account_id, &id).as_proto(),
+                    });
+                }
+            }
+        }
+
         rpc_proto::ChatConversationList {
             group_id,
             message_list,
+            participants,
         }
     }
 
@@ -424,8 +457,8 @@ impl ChatStorage {
         let db = DataBase::get_user_db(account_id);
 
         // open trees
-        let messages: sled::Tree = db.open_tree("chat_messages").unwrap();
-        let message_ids: sled::Tree = db.open_tree("chat_message_ids").unwrap();
+        let messages = EncryptedTree::open(&db, "chat_messages");
+        let message_ids = EncryptedTree::open(&db, "chat_message_ids");
 
         let chat_user = ChatAccountDb {
             messages,
@@ -9,6 +9,9 @@
 //! The cryptography is based on the Noise protocol.
 //! qaul uses the `Noise_KK_25519_ChaChaPoly_SHA256` pattern.
 //!
+//! The transport keys of a session are ratcheted forward,
+//! see `ratchet.rs`, if both users support it.
+//!
 //! This file manages the encryption session.
 
 use libp2p::PeerId;
@@ -17,6 +20,7 @@ use serde::{Deserialize, Serialize};
 
 mod crypto25519;
 mod noise;
+mod ratchet;
 pub mod sessionmanager;
 mod storage;
 
@@ -25,6 +29,7 @@ use crate::node::user_accounts::UserAccount;
 use crate::services::crypto::sessionmanager::CryptoSessionManager;
 pub use crypto25519::Crypto25519;
 pub use noise::CryptoNoise;
+pub use ratchet::CryptoRatchet;
 pub use storage::CryptoAccount;
 pub use storage::CryptoStorage;
 
@@ -119,7 +124,8 @@ impl Crypto {
         let process_state: messaging::proto::CryptoState;
 
         // get data base object
-        let crypto_account = CryptoStorage::get_db_ref(user_account.id.clone());
+        let account_id = user_account.id.clone();
+        let crypto_account = CryptoStorage::get_db_ref(account_id);
 
         // check if there is a handshake state?
         match crypto_account.get_state(remote_id) {
@@ -154,11 +160,13 @@ impl Crypto {
 
         // create and return encrypted message
         if let Some(encrypted_data) = encrypted_option {
+            let ratchet = Self::use_ratchet(&account_id, remote_id, session_id, process_state);
             return Some(Self::create_encrypted_protobuf(
                 nonce,
                 session_id,
                 encrypted_data,
                 process_state,
+                ratchet,
             ));
         }
 
@@ -231,12 +239,54 @@ impl Crypto {
         return Some((encrypted_option, nonce, session_id, process_state));
     }
 
+    /// Check if the ratchet flag is set on an outgoing message
+    ///
+    /// Handshake messages announce the support of the key ratchet,
+    /// transport messages are flagged if the session uses it.
+    fn use_ratchet(
+        account_id: &PeerId,
+        remote_id: PeerId,
+        session_id: u32,
+        process_state: messaging::proto::CryptoState,
+    ) -> bool {
+        if process_state == messaging::proto::CryptoState::Handshake {
+            return true;
+        }
+
+        CryptoStorage::get_db_ref(account_id.to_owned())
+            .get_ratchet(remote_id, session_id)
+            .is_some()
+    }
+
+    /// Enable the key ratchet for a session in transport state
+    ///
+    /// This is done after the handshake, if the remote user
+    /// announced the support of the key ratchet.
+    fn enable_ratchet(crypto_account: &CryptoAccount, remote_id: PeerId, session_id: u32) {
+        if crypto_account.get_ratchet(remote_id, session_id).is_some() {
+            return;
+        }
+
+        if let Some(state) = crypto_account.get_state_by_id(remote_id, session_id) {
+            if let CryptoProcessState::Transport = state.state {
+                log::trace!("enable key ratchet for session {}", session_id);
+                crypto_account.save_state_ratchet(
+                    remote_id,
+                    session_id,
+                    state,
+                    &CryptoRatchet::new(),
+                );
+            }
+        }
+    }
+
     /// Create encrypted protobuf message
     fn create_encrypted_protobuf(
         nonce: u64,
         session_id: u32,
         encrypted_data: Vec<u8>,
         process_state: messaging::proto::CryptoState,
+        ratchet: bool,
     ) -> messaging::proto::Encrypted {
         let mut data_messages: Vec<messaging::proto::Data> = Vec::new();
         data_messages.push(messaging::proto::Data {
@@ -248,6 +298,7 @@ impl Crypto {
             state: process_state.into(),
             session_id,
             data: data_messages,
+            ratchet,
         };
     }
 
@@ -297,18 +348,30 @@ impl Crypto {
 
                         // decrypt second handshake message
                         for data in message.data {
-                            let message = CryptoNoise::decrypt_noise_kk_handshake_2::<
+                            let decrypted = CryptoNoise::decrypt_noise_kk_handshake_2::<
                                 X25519,
                                 ChaCha20Poly1305,
                                 Sha256,
                                 &[u8],
                             >(
-                                data.data, session, crypto_account, remote_id
+                                data.data,
+                                session,
+                                crypto_account.clone(),
+                                remote_id,
                             );
 
+                            // the remote user supports the key ratchet
+                            if decrypted.is_some() && message.ratchet {
+                                Self::enable_ratchet(
+                                    &crypto_account,
+                                    remote_id,
+                                    message.session_id,
+                                );
+                            }
+
                             // return second handshake confirmation message
 
-                            return message;
+                            return decrypted;
                         }
                     }
                     (
@@ -396,22 +459,32 @@ impl Crypto {
                                     );
 
                                 // encrypt confirmation message
-                                if let Some((Some(message), nonce, session_id, crypto_state)) =
+                                if let Some((Some(encrypted), nonce, session_id, crypto_state)) =
                                     Self::encrypt_with_state(
                                         messaging_message,
                                         remote_id,
-                                        crypto_account,
+                                        crypto_account.clone(),
                                         crypto_state,
                                     )
                                 {
+                                    // the remote user supports the key ratchet
+                                    if message.ratchet {
+                                        Self::enable_ratchet(
+                                            &crypto_account,
+                                            remote_id,
+                                            session_id,
+                                        );
+                                    }
+
                                     log::trace!(
                                         "create first handshake cryptoservice confirmation message"
                                     );
                                     let encrypted_message = Self::create_encrypted_protobuf(
                                         nonce,
                                         session_id,
-                                        message,
+                                        encrypted,
                                         crypto_state,
+                                        true,
                                     );
 
                                     // pack and send encrypted message
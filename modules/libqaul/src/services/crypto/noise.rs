@@ -177,9 +177,22 @@ impl CryptoNoise {
         // the handshake has been done, we can encrypt messages
         nonce = state.index_nonce_out;
 
+        // get the key of this message
+        let mut ratchet = storage.get_ratchet(remote_id, state.session_id);
+        let key = match ratchet.as_mut() {
+            Some(ratchet) => ratchet.key_out(&mut state, nonce),
+            None => state.cipher_out.clone(),
+        };
+        let key = match key {
+            Some(key) => key,
+            None => {
+                log::error!("no outgoing cipher key");
+                return (None, nonce);
+            }
+        };
+
         // create cipher
-        let mut cipher: CipherState<C> =
-            CipherState::new(state.clone().cipher_out.unwrap().as_slice(), nonce);
+        let mut cipher: CipherState<C> = CipherState::new(key.as_slice(), nonce);
 
         // encrypt message
         message = Some(cipher.encrypt_vec(data.as_slice()));
@@ -188,7 +201,12 @@ impl CryptoNoise {
         state.index_nonce_out = nonce + 1;
 
         // save state
-        storage.save_state(remote_id, state.session_id, state);
+        match ratchet {
+            Some(ratchet) => {
+                storage.save_state_ratchet(remote_id, state.session_id, state, &ratchet)
+            }
+            None => storage.save_state(remote_id, state.session_id, state),
+        }
 
         (message, nonce)
     }
@@ -375,17 +393,39 @@ impl CryptoNoise {
         // we had a successful handshake and are in transport state
         log::trace!("Decrypting with full encryption");
 
+        // get the key of this message
+        let mut ratchet = storage.get_ratchet(remote_id, state.session_id);
+        let key = match ratchet.as_mut() {
+            Some(ratchet) => ratchet.key_in(&mut state, nonce),
+            None => state.cipher_in.clone(),
+        };
+        let key = match key {
+            Some(key) => key,
+            None => {
+                log::error!("no incoming cipher key for nonce {}", nonce);
+                return None;
+            }
+        };
+
         // create cipher
-        let mut cipher: CipherState<C> =
-            CipherState::new(state.cipher_in.clone().unwrap().as_slice(), nonce);
+        let mut cipher: CipherState<C> = CipherState::new(key.as_slice(), nonce);
 
         // decrypt message
         match cipher.decrypt_vec(data.as_slice()) {
             Ok(decrypted) => {
                 message = Some(decrypted);
 
-                state.highest_index_nonce_in = nonce;
-                storage.save_state(remote_id, state.session_id, state);
+                // only save the moved key chain after successful decryption
+                match ratchet {
+                    Some(mut ratchet) => {
+                        ratchet.on_received(&mut state, nonce);
+                        storage.save_state_ratchet(remote_id, state.session_id, state, &ratchet);
+                    }
+                    None => {
+                        state.highest_index_nonce_in = nonce;
+                        storage.save_state(remote_id, state.session_id, state);
+                    }
+                }
             }
             Err(_) => {
                 log::error!("decryption error");
@@ -0,0 +1,277 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Symmetric Key Ratchet
+//!
+//! Provides forward secrecy for long lived Noise sessions.
+//!
+//! The message nonces of a session are divided into epochs of
+//! `EPOCH_LENGTH` messages.
+//! Every epoch has its own key, which is derived from the key
+//! of the previous epoch.
+//! The key of the first epoch is the cipher key of the Noise
+//! handshake.
+//!
+//! The `cipher_out` and `cipher_in` keys of the `CryptoState`
+//! always contain the key of the current epoch and are
+//! overwritten when the chain moves forward.
+//! The keys of incoming epochs, which still have missing
+//! messages, are kept in the `CryptoRatchet` state, until all
+//! messages of the epoch have arrived, or the epoch is too old.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use super::CryptoState;
+
+/// Number of messages encrypted with the same key
+pub const EPOCH_LENGTH: u64 = 64;
+
+/// Maximal number of incoming epoch keys that are kept
+/// for out of order messages
+const MAX_EPOCHS_IN: usize = 32;
+
+/// Maximal number of epochs the incoming chain
+/// is moved forward at once
+const MAX_SKIP_EPOCHS: u64 = 1024;
+
+/// Ratchet State of a Crypto Session
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct CryptoRatchet {
+    /// epoch of the outgoing key in `CryptoState::cipher_out`
+    pub epoch_out: u64,
+    /// epoch of the next incoming key in `CryptoState::cipher_in`
+    pub epoch_in: u64,
+    /// keys of the incoming epochs with missing messages
+    pub keys_in: BTreeMap<u64, CryptoRatchetKey>,
+}
+
+/// Key of an Incoming Epoch
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CryptoRatchetKey {
+    /// cipher key
+    pub key: Vec<u8>,
+    /// bit field of the received messages of this epoch
+    pub received: u64,
+}
+
+impl CryptoRatchet {
+    /// create a new ratchet state for a session in transport state
+    pub fn new() -> CryptoRatchet {
+        CryptoRatchet::default()
+    }
+
+    /// derive the key of the next epoch
+    fn next_key(key: &[u8]) -> Vec<u8> {
+        Sha256::new()
+            .chain_update(b"qaul ratchet")
+            .chain_update(key)
+            .finalize()
+            .to_vec()
+    }
+
+    /// get the key to encrypt an outgoing message
+    ///
+    /// Moves the outgoing chain forward to the epoch of the nonce,
+    /// the keys of the former epochs are overwritten.
+    pub fn key_out(&mut self, state: &mut CryptoState, nonce: u64) -> Option<Vec<u8>> {
+        let epoch = nonce / EPOCH_LENGTH;
+        let mut key = state.cipher_out.clone()?;
+
+        while self.epoch_out < epoch {
+            key = Self::next_key(&key);
+            self.epoch_out += 1;
+        }
+        state.cipher_out = Some(key.clone());
+
+        Some(key)
+    }
+
+    /// get the key to decrypt an incoming message
+    ///
+    /// Moves the incoming chain forward to the epoch of the nonce,
+    /// and keeps the keys of the skipped epochs.
+    /// Returns `None` if the message has already been received,
+    /// or if the key of its epoch has been deleted.
+    pub fn key_in(&mut self, state: &mut CryptoState, nonce: u64) -> Option<Vec<u8>> {
+        let epoch = nonce / EPOCH_LENGTH;
+
+        if epoch >= self.epoch_in {
+            if epoch - self.epoch_in > MAX_SKIP_EPOCHS {
+                log::error!("ratchet: too many skipped epochs");
+                return None;
+            }
+
+            let mut key = state.cipher_in.clone()?;
+            while self.epoch_in <= epoch {
+                self.keys_in.insert(
+                    self.epoch_in,
+                    CryptoRatchetKey {
+                        key: key.clone(),
+                        received: 0,
+                    },
+                );
+                key = Self::next_key(&key);
+                self.epoch_in += 1;
+            }
+            state.cipher_in = Some(key);
+        }
+
+        let epoch_key = self.keys_in.get(&epoch)?;
+        if epoch_key.received & Self::message_bit(nonce) != 0 {
+            log::warn!("ratchet: message {} already received", nonce);
+            return None;
+        }
+
+        Some(epoch_key.key.clone())
+    }
+
+    /// mark an incoming message as received
+    ///
+    /// Deletes the keys of complete epochs and of the
+    /// oldest epochs, if there are too many.
+    pub fn on_received(&mut self, state: &mut CryptoState, nonce: u64) {
+        let epoch = nonce / EPOCH_LENGTH;
+
+        if let Some(epoch_key) = self.keys_in.get_mut(&epoch) {
+            epoch_key.received |= Self::message_bit(nonce);
+            if epoch_key.received == u64::MAX {
+                self.keys_in.remove(&epoch);
+            }
+        }
+        while self.keys_in.len() > MAX_EPOCHS_IN {
+            self.keys_in.pop_first();
+        }
+
+        if nonce > state.highest_index_nonce_in {
+            state.highest_index_nonce_in = nonce;
+        }
+        let highest_epoch = state.highest_index_nonce_in / EPOCH_LENGTH;
+        state.out_of_order_indexes = self.keys_in.range(..highest_epoch).next().is_some();
+    }
+
+    /// bit of a message in the received bit field of its epoch
+    fn message_bit(nonce: u64) -> u64 {
+        1 << (nonce % EPOCH_LENGTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::crypto::CryptoProcessState;
+
+    /// a session in transport state, the keys of both directions are equal
+    fn session(key: &[u8]) -> CryptoState {
+        CryptoState {
+            session_id: 1,
+            state: CryptoProcessState::Transport,
+            initiator: true,
+            s: Vec::new(),
+            rs: Vec::new(),
+            e: Vec::new(),
+            re: None,
+            cipher_out: Some(key.to_vec()),
+            index_nonce_out: 0,
+            cipher_in: Some(key.to_vec()),
+            highest_index_nonce_in: 0,
+            out_of_order_indexes: false,
+        }
+    }
+
+    /// receive a message and mark it as received
+    fn receive(ratchet: &mut CryptoRatchet, state: &mut CryptoState, nonce: u64) -> Vec<u8> {
+        let key = ratchet.key_in(state, nonce).unwrap();
+        ratchet.on_received(state, nonce);
+        key
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut sender = session(&[7; 32]);
+        let mut receiver = session(&[7; 32]);
+        let mut ratchet_out = CryptoRatchet::new();
+        let mut ratchet_in = CryptoRatchet::new();
+
+        for nonce in 0..3 * EPOCH_LENGTH {
+            let key = ratchet_out.key_out(&mut sender, nonce).unwrap();
+            assert_eq!(receive(&mut ratchet_in, &mut receiver, nonce), key);
+        }
+        assert!(!receiver.out_of_order_indexes);
+    }
+
+    #[test]
+    fn test_epoch_rollover() {
+        let mut sender = session(&[7; 32]);
+        let mut ratchet = CryptoRatchet::new();
+
+        let first = ratchet.key_out(&mut sender, EPOCH_LENGTH - 1).unwrap();
+        assert_eq!(first, vec![7; 32]);
+        let second = ratchet.key_out(&mut sender, EPOCH_LENGTH).unwrap();
+        assert_eq!(second, CryptoRatchet::next_key(&first));
+        assert_eq!(ratchet.epoch_out, 1);
+
+        // the key of the former epoch is overwritten
+        assert_eq!(sender.cipher_out, Some(second.clone()));
+        assert_eq!(ratchet.key_out(&mut sender, 0).unwrap(), second);
+    }
+
+    #[test]
+    fn test_out_of_order() {
+        let mut sender = session(&[7; 32]);
+        let mut receiver = session(&[7; 32]);
+        let mut ratchet_out = CryptoRatchet::new();
+        let mut ratchet_in = CryptoRatchet::new();
+
+        let nonces = [0, 2 * EPOCH_LENGTH + 5, 3, EPOCH_LENGTH + 1, 1];
+        let keys: Vec<Vec<u8>> = {
+            let mut sorted = nonces.to_vec();
+            sorted.sort();
+            let keys: BTreeMap<u64, Vec<u8>> = sorted
+                .into_iter()
+                .map(|nonce| (nonce, ratchet_out.key_out(&mut sender, nonce).unwrap()))
+                .collect();
+            nonces.iter().map(|nonce| keys[nonce].clone()).collect()
+        };
+
+        for (nonce, key) in nonces.iter().zip(keys) {
+            assert_eq!(receive(&mut ratchet_in, &mut receiver, *nonce), key);
+        }
+        assert_eq!(ratchet_in.epoch_in, 3);
+        assert_eq!(receiver.highest_index_nonce_in, 2 * EPOCH_LENGTH + 5);
+        assert!(receiver.out_of_order_indexes);
+    }
+
+    #[test]
+    fn test_replay_and_complete_epoch() {
+        let mut receiver = session(&[7; 32]);
+        let mut ratchet = CryptoRatchet::new();
+
+        receive(&mut ratchet, &mut receiver, 3);
+        assert!(ratchet.key_in(&mut receiver, 3).is_none());
+
+        // the key of a complete epoch is deleted
+        for nonce in (0..EPOCH_LENGTH).filter(|nonce| *nonce != 3) {
+            receive(&mut ratchet, &mut receiver, nonce);
+        }
+        assert!(ratchet.keys_in.is_empty());
+        assert!(ratchet.key_in(&mut receiver, 10).is_none());
+    }
+
+    #[test]
+    fn test_skip_limits() {
+        let mut receiver = session(&[7; 32]);
+        let mut ratchet = CryptoRatchet::new();
+
+        assert!(ratchet
+            .key_in(&mut receiver, (MAX_SKIP_EPOCHS + 1) * EPOCH_LENGTH)
+            .is_none());
+        assert_eq!(ratchet.epoch_in, 0);
+
+        // only the newest incoming epoch keys are kept
+        receive(&mut ratchet, &mut receiver, (MAX_EPOCHS_IN as u64 + 4) * EPOCH_LENGTH);
+        assert_eq!(ratchet.keys_in.len(), MAX_EPOCHS_IN);
+        assert!(ratchet.key_in(&mut receiver, 0).is_none());
+    }
+}
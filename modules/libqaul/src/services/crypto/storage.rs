@@ -11,9 +11,10 @@ use state::InitCell;
 use std::collections::BTreeMap;
 use std::sync::RwLock;
 
-use super::CryptoState;
+use super::{CryptoRatchet, CryptoState};
 use crate::services::messaging::proto;
 use crate::storage::database::DataBase;
+use crate::storage::encryption::EncryptedTree;
 
 /// mutable state of messages, scheduled for sending
 pub static CRYPTOSTORAGE: InitCell<RwLock<CryptoStorage>> = InitCell::new();
@@ -24,12 +25,16 @@ pub struct CryptoAccount {
     /// user crypto session state storage
     ///
     /// value: bincode of `CryptoState`
-    pub state: sled::Tree,
+    pub state: EncryptedTree,
     /// unprocessable out of order handshake
     /// state messages
     ///
     /// value: bincode of `proto::Encrypted`
-    pub cache: sled::Tree,
+    pub cache: EncryptedTree,
+    /// key ratchet state of the crypto sessions
+    ///
+    /// value: bincode of `CryptoRatchet`
+    pub ratchet: EncryptedTree,
 }
 
 impl CryptoAccount {
@@ -138,6 +143,55 @@ impl CryptoAccount {
         }
     }
 
+    /// get the key ratchet state of a session
+    ///
+    /// Returns `None` if the session doesn't use the key ratchet.
+    pub fn get_ratchet(&self, remote_id: PeerId, session_id: u32) -> Option<CryptoRatchet> {
+        // create db key
+        let key = Self::create_state_key(remote_id, session_id);
+
+        // get result from data base
+        match self.ratchet.get(key) {
+            Ok(Some(ratchet_bytes)) => match bincode::deserialize(&ratchet_bytes) {
+                Ok(ratchet) => return Some(ratchet),
+                Err(e) => log::error!("{}", e),
+            },
+            Ok(None) => return None,
+            Err(e) => log::error!("{}", e),
+        }
+
+        None
+    }
+
+    /// save CryptoState and key ratchet state of a session to db
+    ///
+    /// Both are saved together, so that the old keys
+    /// are deleted from the data base.
+    pub fn save_state_ratchet(
+        &self,
+        remote_id: PeerId,
+        session_id: u32,
+        crypto_state: CryptoState,
+        ratchet: &CryptoRatchet,
+    ) {
+        // create key
+        let key = Self::create_state_key(remote_id, session_id);
+
+        // save ratchet in data base
+        let ratchet_bytes = bincode::serialize(ratchet).unwrap();
+        if let Err(e) = self.ratchet.insert(key, ratchet_bytes) {
+            log::error!("Error ratchet to db: {}", e);
+        }
+
+        // flush trees to disk
+        if let Err(e) = self.ratchet.flush() {
+            log::error!("Error db flush: {}", e);
+        }
+
+        // save state
+        self.save_state(remote_id, session_id, crypto_state);
+    }
+
     /// save an incoming, out of order message to cache
     pub fn save_cache_message(
         &self,
@@ -191,10 +245,7 @@ impl CryptoStorage {
 
             // check if user account ID is in map
             if let Some(crypto_account_db) = crypto_storage.db_ref.get(&account_id.to_bytes()) {
-                return CryptoAccount {
-                    state: crypto_account_db.state.clone(),
-                    cache: crypto_account_db.cache.clone(),
-                };
+                return crypto_account_db.clone();
             }
         }
 
@@ -215,10 +266,15 @@ impl CryptoStorage {
         let db = DataBase::get_user_db(account_id);
 
         // open trees
-        let state: sled::Tree = db.open_tree("crypto_state").unwrap();
-        let cache: sled::Tree = db.open_tree("crypto_cache").unwrap();
-
-        let crypto_account = CryptoAccount { state, cache };
+        let state = EncryptedTree::open(&db, "crypto_state");
+        let cache = EncryptedTree::open(&db, "crypto_cache");
+        let ratchet = EncryptedTree::open(&db, "crypto_ratchet");
+
+        let crypto_account = CryptoAccount {
+            state,
+            cache,
+            ratchet,
+        };
 
         // get group storage for writing
         let mut crypto_storage = CRYPTOSTORAGE.get().write().unwrap();
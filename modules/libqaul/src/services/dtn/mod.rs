@@ -16,8 +16,9 @@ use std::{convert::TryInto, fmt, sync::RwLock};
 use super::messaging::{proto, MessagingServiceType};
 use crate::node::user_accounts::{UserAccount, UserAccounts};
 use crate::rpc::Rpc;
-use crate::storage::configuration::Configuration;
+use crate::storage::configuration::{Configuration, ConfigurationError};
 use crate::storage::database::DataBase;
+use crate::storage::encryption::EncryptedTree;
 
 /// Import protobuf message definition generated by
 /// the rust module prost-build.
@@ -44,11 +45,11 @@ pub struct DtnStorageState {
     /// DTN message table ref
     ///
     /// value: bincode of `DtnMessageEntry`
-    pub db_ref: sled::Tree,
+    pub db_ref: EncryptedTree,
     /// DTN message id table ref (org_dtn_sig => new_dtn_sig)
     /// This is used to prevent dup DTN message incoming
     /// saved as `Vec<u8>`
-    pub db_ref_id: sled::Tree,
+    pub db_ref_id: EncryptedTree,
 }
 
 /// mutable state of storge
@@ -65,8 +66,8 @@ impl Dtn {
         let db = DataBase::get_node_db();
 
         // open trees
-        let dtn_messages: sled::Tree = db.open_tree("dtn-messages").unwrap();
-        let db_ref_id: sled::Tree = db.open_tree("dtn-messages-ids").unwrap();
+        let dtn_messages = EncryptedTree::open(&db, "dtn-messages");
+        let db_ref_id = EncryptedTree::open(&db, "dtn-messages-ids");
 
         // calc current used size
         let mut used_size: u64 = 0;
@@ -87,6 +88,37 @@ impl Dtn {
         STORAGESTATE.set(RwLock::new(storage_state));
     }
 
+    /// Re-check the storage quotas after a configuration change
+    ///
+    /// The quotas are read from the configuration for every
+    /// incoming DTN message, a lowered quota therefore applies
+    /// immediately. Messages already stored are kept until
+    /// they are delivered.
+    pub fn apply_configuration() {
+        let used_size = STORAGESTATE.get().read().unwrap().used_size;
+        let config = Configuration::get();
+        for user in &config.user_accounts {
+            let total_limit = (user.storage.size_total as u64) * 1024 * 1024;
+            if used_size > total_limit {
+                log::warn!(
+                    "dtn storage of user {} exceeds the new quota: {} of {} bytes used",
+                    user.id,
+                    used_size,
+                    total_limit
+                );
+            }
+        }
+    }
+
+    /// Convert the configuration validation errors to a response message
+    fn configuration_errors(errors: Vec<ConfigurationError>) -> String {
+        errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
     /// Convert Group ID from String to Binary
     fn id_string_to_bin(id: String) -> Result<Vec<u8>, String> {
         // check length
@@ -234,6 +266,7 @@ impl Dtn {
             let container = super::messaging::proto::Container {
                 signature: signature.clone(),
                 envelope: Some(envelop),
+                recipients: Vec::new(),
             };
             super::messaging::Messaging::save_unconfirmed_message(
                 MessagingServiceType::DtnStored,
@@ -307,8 +340,13 @@ impl Dtn {
                             .unwrap(),
                     );
 
-                    //if container.envelope.receiver_id
-                    if let Ok(receiver_id) = PeerId::from_bytes(&envelope.receiver_id) {
+                    // group messages are addressed to the group,
+                    // they carry their single recipient in the container
+                    let receiver_bytes = match container.recipients.as_slice() {
+                        [recipient] => recipient,
+                        _ => &envelope.receiver_id,
+                    };
+                    if let Ok(receiver_id) = PeerId::from_bytes(receiver_bytes) {
                         if receiver_id == *user_id {
                             // by process geneal message, the confirm message is transfered to the original sender.
                             super::messaging::process::MessagingProcess::process_received_message(
@@ -455,8 +493,12 @@ impl Dtn {
                             if status {
                                 let mut opt = user_profile.storage.clone();
                                 opt.users.push(user_id_string);
-                                Configuration::update_user_storage(my_user_id.to_string(), &opt);
-                                Configuration::save();
+                                if let Err(errors) =
+                                    Configuration::set_user_storage(my_user_id.to_string(), opt)
+                                {
+                                    status = false;
+                                    message = Self::configuration_errors(errors);
+                                }
                             }
 
                             let proto_message = proto_rpc::Dtn {
@@ -514,8 +556,12 @@ impl Dtn {
                             if status {
                                 let mut opt = user_profile.storage.clone();
                                 opt.users.remove(idx.unwrap());
-                                Configuration::update_user_storage(my_user_id.to_string(), &opt);
-                                Configuration::save();
+                                if let Err(errors) =
+                                    Configuration::set_user_storage(my_user_id.to_string(), opt)
+                                {
+                                    status = false;
+                                    message = Self::configuration_errors(errors);
+                                }
                             }
 
                             let proto_message = proto_rpc::Dtn {
@@ -539,19 +585,20 @@ impl Dtn {
                 Some(proto_rpc::dtn::Message::DtnSetTotalSizeRequest(req)) => {
                     match Configuration::get_user(my_user_id.to_string()) {
                         // CHANGE: save it in user profile, not to configuration directly.
-                        Some(_user_profile) => {
-                            Configuration::update_total_size(
+                        Some(user_profile) => {
+                            let mut opt = user_profile.storage.clone();
+                            opt.size_total = req.total_size;
+                            let (status, message) = match Configuration::set_user_storage(
                                 my_user_id.to_string(),
-                                req.total_size,
-                            );
-                            Configuration::save();
+                                opt,
+                            ) {
+                                Ok(_) => (true, "".to_string()),
+                                Err(errors) => (false, Self::configuration_errors(errors)),
+                            };
 
                             let proto_message = proto_rpc::Dtn {
                                 message: Some(proto_rpc::dtn::Message::DtnSetTotalSizeResponse(
-                                    proto_rpc::DtnSetTotalSizeResponse {
-                                        status: true,
-                                        message: "".to_string(),
-                                    },
+                                    proto_rpc::DtnSetTotalSizeResponse { status, message },
                                 )),
                             };
                             // send message
@@ -0,0 +1,193 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Bloom Filter for Feed Synchronisation
+//!
+//! Neighbours reconcile their feed messages by sending a bloom
+//! filter over the ids of their messages. The receiver sends back
+//! all messages which are not contained in the filter.
+//!
+//! A bloom filter has no false negatives: a message contained in
+//! the filter is known to the sender, except for a small false
+//! positive rate. To not miss the same messages on every
+//! synchronisation, each filter is created with a new seed.
+//!
+//! The indexes are derived from the SHA-256 hash of the seed
+//! and the message id via double hashing, so that all nodes
+//! calculate the same indexes independent of their platform.
+
+use sha2::{Digest, Sha256};
+
+/// false positive rate the filter is sized for
+const FALSE_POSITIVE_RATE: f64 = 0.01;
+/// maximal size of the filter in bytes
+pub const MAX_BYTES: usize = 16 * 1024;
+/// maximal number of hash functions
+const MAX_HASHES: u32 = 16;
+
+/// Bloom filter over feed message ids
+#[derive(Debug, Clone, PartialEq)]
+pub struct BloomFilter {
+    /// filter bits
+    pub bits: Vec<u8>,
+    /// number of hash functions
+    pub hashes: u32,
+    /// seed of the hash functions
+    pub seed: u32,
+}
+
+impl BloomFilter {
+    /// Create an empty filter sized for `count` message ids
+    ///
+    /// The filter is limited to `max_bytes`, at most `MAX_BYTES`.
+    /// For more messages the false positive rate rises.
+    pub fn new(count: usize, seed: u32, max_bytes: usize) -> Self {
+        if count == 0 {
+            return Self {
+                bits: Vec::new(),
+                hashes: 0,
+                seed,
+            };
+        }
+
+        // optimal number of bits and hash functions
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-(count as f64) * FALSE_POSITIVE_RATE.ln() / (ln2 * ln2)).ceil() as usize;
+        let bytes = ((bits + 7) / 8).clamp(1, max_bytes.clamp(1, MAX_BYTES));
+        let hashes = ((bytes * 8) as f64 / count as f64 * ln2).round() as u32;
+
+        Self {
+            bits: vec![0; bytes],
+            hashes: hashes.clamp(1, MAX_HASHES),
+            seed,
+        }
+    }
+
+    /// Add a message id to the filter
+    pub fn insert(&mut self, id: &[u8]) {
+        if self.bits.is_empty() {
+            return;
+        }
+        for index in self.indexes(id) {
+            self.bits[index / 8] |= 1 << (index % 8);
+        }
+    }
+
+    /// Check if a message id is contained in the filter
+    ///
+    /// An empty filter contains nothing.
+    pub fn contains(&self, id: &[u8]) -> bool {
+        if self.bits.is_empty() || self.hashes == 0 {
+            return false;
+        }
+        self.indexes(id)
+            .iter()
+            .all(|index| self.bits[index / 8] & (1 << (index % 8)) != 0)
+    }
+
+    /// calculate the bit indexes of a message id
+    fn indexes(&self, id: &[u8]) -> Vec<usize> {
+        let hash = Sha256::new()
+            .chain_update(self.seed.to_be_bytes())
+            .chain_update(id)
+            .finalize();
+        let h1 = u64::from_be_bytes(hash[0..8].try_into().unwrap());
+        let h2 = u64::from_be_bytes(hash[8..16].try_into().unwrap());
+
+        let size = self.bits.len() as u64 * 8;
+        (0..self.hashes.min(MAX_HASHES) as u64)
+            .map(|i| (h1.wrapping_add(i.wrapping_mul(h2)) % size) as usize)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(count: usize, tag: u8) -> Vec<Vec<u8>> {
+        (0..count as u32)
+            .map(|i| [vec![tag], i.to_be_bytes().to_vec()].concat())
+            .collect()
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        for count in [1, 10, 1000, 50_000] {
+            let ids = ids(count, 0);
+            let mut filter = BloomFilter::new(count, 7, MAX_BYTES);
+            for id in &ids {
+                filter.insert(id);
+            }
+            assert!(ids.iter().all(|id| filter.contains(id)));
+        }
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        let mut filter = BloomFilter::new(1000, 7, MAX_BYTES);
+        for id in ids(1000, 0) {
+            filter.insert(&id);
+        }
+        let false_positives = ids(10_000, 1)
+            .iter()
+            .filter(|id| filter.contains(id))
+            .count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn test_empty_filter() {
+        let filter = BloomFilter::new(0, 7, MAX_BYTES);
+        assert!(filter.bits.is_empty());
+        assert!(!filter.contains(b"id"));
+
+        // a received filter without hash functions contains nothing
+        let filter = BloomFilter {
+            bits: vec![0xff; 8],
+            hashes: 0,
+            seed: 7,
+        };
+        assert!(!filter.contains(b"id"));
+    }
+
+    #[test]
+    fn test_size_clamping() {
+        // 1000 ids at 1% need 9586 bits
+        let filter = BloomFilter::new(1000, 7, MAX_BYTES);
+        assert_eq!(filter.bits.len(), 1199);
+        assert_eq!(filter.hashes, 7);
+
+        assert_eq!(
+            BloomFilter::new(1_000_000, 7, MAX_BYTES).bits.len(),
+            MAX_BYTES
+        );
+        assert_eq!(
+            BloomFilter::new(1_000_000, 7, 2 * MAX_BYTES).bits.len(),
+            MAX_BYTES
+        );
+        assert_eq!(BloomFilter::new(1000, 7, 256).bits.len(), 256);
+        assert_eq!(BloomFilter::new(1000, 7, 0).bits.len(), 1);
+    }
+
+    #[test]
+    fn test_hash_count_clamping() {
+        // a single id gets a whole byte, more hashes than useful
+        let filter = BloomFilter::new(1, 7, MAX_BYTES);
+        assert_eq!(filter.bits.len(), 2);
+        assert_eq!(filter.hashes, 11);
+
+        // a capped filter gets at least one hash function
+        assert_eq!(BloomFilter::new(1_000_000, 7, 1).hashes, 1);
+
+        // received filters are limited to MAX_HASHES
+        let mut filter = BloomFilter {
+            bits: vec![0; 64],
+            hashes: 1000,
+            seed: 7,
+        };
+        assert_eq!(filter.indexes(b"id").len(), MAX_HASHES as usize);
+        filter.insert(b"id");
+        assert!(filter.contains(b"id"));
+    }
+}
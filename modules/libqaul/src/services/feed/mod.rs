@@ -6,6 +6,21 @@
 //! The feed service sends and receives feed messages into the network.
 //! Feed messages are not encrypted and for everybody to read.
 //! They should reach everyone in the network.
+//!
+//! Besides the text, a feed message can contain references to
+//! attached files, hashtags, the message it replies to and
+//! an expiry time. Expired messages are neither shown nor forwarded.
+//!
+//! Moderation is local to this node: the messages of blocked senders
+//! and messages with muted hashtags are stored and forwarded,
+//! but they are not shown.
+//!
+//! Neighbours reconcile their feed messages of the configured time
+//! window with a bloom filter, see `bloom`. Messages older than the
+//! configured retention age are removed, as well as the oldest messages
+//! when the stored feed exceeds the configured size.
+//! The ids of removed messages of the time window are kept and added
+//! to the filter, so that neighbours don't send them again.
 
 //use bs58::decode;
 use libp2p::{
@@ -28,7 +43,9 @@ use crate::connections::{internet::Internet, lan::Lan, ConnectionModule};
 use crate::router;
 use crate::router::flooder::Flooder;
 use crate::rpc::Rpc;
+use crate::storage::configuration::Configuration;
 use crate::storage::database::DataBase;
+use crate::storage::encryption::EncryptedTree;
 use crate::utilities::timestamp;
 
 /// Import protobuf message definition generated by
@@ -40,9 +57,28 @@ pub mod proto_net {
     include!("qaul.net.feed.rs");
 }
 
+pub mod bloom;
+use bloom::BloomFilter;
+
 /// mutable state of feed messages
 static FEED: InitCell<RwLock<Feed>> = InitCell::new();
 
+/// maximum number of attachments per message
+const MAX_ATTACHMENTS: usize = 8;
+/// maximum number of hashtags per message
+const MAX_HASHTAGS: usize = 16;
+/// maximum length of a hashtag in characters
+const MAX_HASHTAG_LENGTH: usize = 64;
+/// maximum number of messages sent per synchronisation
+const MAX_SYNC_MESSAGES: usize = 64;
+/// key of the last index in the `feed_id` tree
+///
+/// It is saved when messages are removed, so that the
+/// index continues after all messages were removed.
+const LAST_INDEX_KEY: &[u8] = b"last_index";
+/// maximum number of removed message ids kept
+const MAX_PRUNED_IDS: usize = 4096;
+
 /// For storing in data base
 #[derive(Serialize, Deserialize, Clone)]
 pub struct FeedMessageData {
@@ -90,12 +126,30 @@ pub struct Feed {
     /// sled data base tree for message_id to last index
     ///
     /// value: bincode of `u64`
-    pub tree_ids: sled::Tree,
+    ///
+    /// The key `LAST_INDEX_KEY` contains the last index.
+    pub tree_ids: EncryptedTree,
 
     /// sled data base tree of
     ///
     /// value: bincode of `FeedMessageData`
-    pub tree: sled::Tree,
+    pub tree: EncryptedTree,
+    /// sled data base tree of message_id to signed message
+    ///
+    /// value: encoded `proto_net::FeedMessageContent`
+    pub tree_content: EncryptedTree,
+    /// sled data base tree of blocked senders
+    ///
+    /// key: user id, value: bincode of the blocking timestamp
+    pub tree_blocked: EncryptedTree,
+    /// sled data base tree of muted hashtags
+    ///
+    /// key: hashtag, value: bincode of the muting timestamp
+    pub tree_muted: EncryptedTree,
+    /// sled data base tree of removed messages of the sync window
+    ///
+    /// key: message id, value: bincode of the `u64` sent timestamp
+    pub tree_pruned: EncryptedTree,
     /// last recent message
     pub last_message: u64,
 }
@@ -105,8 +159,12 @@ impl Feed {
     pub fn init() {
         // get database and initialize tree
         let db = DataBase::get_node_db();
-        let tree: sled::Tree = db.open_tree("feed").unwrap();
-        let tree_ids: sled::Tree = db.open_tree("feed_id").unwrap();
+        let tree = EncryptedTree::open(&db, "feed");
+        let tree_ids = EncryptedTree::open(&db, "feed_id");
+        let tree_content = EncryptedTree::open(&db, "feed_content");
+        let tree_blocked = EncryptedTree::open(&db, "feed_blocked");
+        let tree_muted = EncryptedTree::open(&db, "feed_muted");
+        let tree_pruned = EncryptedTree::open(&db, "feed_pruned");
 
         // get last key
         let last_message: u64;
@@ -131,12 +189,23 @@ impl Feed {
                 last_message = 0;
             }
         }
+        let last_message = match tree_ids.get(LAST_INDEX_KEY) {
+            Ok(Some(bytes)) => {
+                let last_index: u64 = bincode::deserialize(&bytes).unwrap_or(0);
+                last_message.max(last_index)
+            }
+            _ => last_message,
+        };
 
         // create feed messages state
         let feed = Feed {
             messages: BTreeMap::new(),
             tree,
             tree_ids,
+            tree_content,
+            tree_blocked,
+            tree_muted,
+            tree_pruned,
             last_message,
         };
         FEED.set(RwLock::new(feed));
@@ -145,18 +214,45 @@ impl Feed {
     /// Send message via all swarms
     pub fn send(
         user_account: &UserAccount,
-        content: String,
+        send_message: proto::SendMessage,
         lan: Option<&mut Lan>,
         internet: Option<&mut Internet>,
     ) {
         // create timestamp
         let timestamp = timestamp::Timestamp::get_timestamp();
 
+        // check message
+        if send_message.expires != 0 && send_message.expires <= timestamp {
+            log::error!("feed message expires in the past");
+            return;
+        }
+        if send_message.attachments.len() > MAX_ATTACHMENTS {
+            log::error!(
+                "feed message has too many attachments: {}",
+                send_message.attachments.len()
+            );
+            return;
+        }
+
         // create feed message
+        let hashtags = Self::collect_hashtags(&send_message.content, &send_message.hashtags);
         let msg = proto_net::FeedMessageContent {
             sender: user_account.id.to_bytes(),
-            content: content.clone(),
+            content: send_message.content,
             time: timestamp,
+            attachments: send_message
+                .attachments
+                .into_iter()
+                .map(|attachment| proto_net::FeedAttachment {
+                    name: attachment.name,
+                    mime_type: attachment.mime_type,
+                    size: attachment.size,
+                    reference: attachment.reference,
+                })
+                .collect(),
+            hashtags,
+            reply_to: send_message.reply_to,
+            expires: send_message.expires,
         };
 
         // encode feed message
@@ -180,7 +276,7 @@ impl Feed {
             .expect("Vec<u8> provides capacity as needed");
 
         // save message in feed store
-        Self::save_message(container.signature.clone(), msg);
+        Self::save_message(container.signature.clone(), msg, &container.message);
 
         // flood via floodsub
         if lan.is_some() {
@@ -230,13 +326,25 @@ impl Feed {
                             return;
                         }
 
+                        // expired messages are not stored nor forwarded
+                        if Self::is_outdated(
+                            message.time,
+                            message.expires,
+                            timestamp::Timestamp::get_timestamp(),
+                        ) {
+                            log::trace!("feed message {:?} expired", feed_container.signature);
+                            return;
+                        }
+
                         // check if message exists is in feed store
                         let mut new_message = true;
 
                         {
                             let feed = FEED.get().read().unwrap();
 
-                            if feed.messages.contains_key(&feed_container.signature) {
+                            if feed.messages.contains_key(&feed_container.signature)
+                                || Self::is_pruned(&feed, &feed_container.signature)
+                            {
                                 new_message = false;
                             }
                         }
@@ -244,7 +352,11 @@ impl Feed {
                         // check if message exists
                         if new_message {
                             // write message to store
-                            Self::save_message(feed_container.signature.clone(), feed_content);
+                            Self::save_message(
+                                feed_container.signature.clone(),
+                                feed_content,
+                                &feed_container.message,
+                            );
 
                             // display message
                             log::trace!("message received:");
@@ -282,22 +394,54 @@ impl Feed {
     }
 
     //Save message by sync
+    //
+    // `message` is the signed message content, it is
+    // empty when the neighbour stored the message before
+    // signed messages were synchronised.
     pub fn save_message_by_sync(
         message_id: &Vec<u8>,
         sender_id: &Vec<u8>,
         content: String,
         time: u64,
+        message: &Vec<u8>,
     ) {
+        let msg_content = match message.is_empty() {
+            true => proto_net::FeedMessageContent {
+                sender: sender_id.clone(),
+                content,
+                time,
+                ..Default::default()
+            },
+            false => match Self::verify_synced_message(message_id, message) {
+                Some(msg_content) => msg_content,
+                None => return,
+            },
+        };
+        if Self::is_outdated(
+            msg_content.time,
+            msg_content.expires,
+            timestamp::Timestamp::get_timestamp(),
+        ) {
+            return;
+        }
+
         let mut feed = FEED.get().write().unwrap();
         if let Some(_index) = feed.tree_ids.get(&message_id[..]).unwrap() {
             return;
         }
-
-        let msg_content = proto_net::FeedMessageContent {
-            sender: sender_id.clone(),
-            content: content.clone(),
-            time: time,
-        };
+        if Self::is_pruned(&feed, message_id) {
+            return;
+        }
+        let sender_id = msg_content.sender.clone();
+        let content = msg_content.content.clone();
+        let time = msg_content.time;
+
+        // save signed message
+        if !message.is_empty() {
+            if let Err(e) = feed.tree_content.insert(&message_id[..], message.clone()) {
+                log::error!("Error saving feed content to data base: {}", e);
+            }
+        }
 
         // insert message to in memory BTreeMap
         feed.messages.insert(message_id.clone(), msg_content);
@@ -344,13 +488,91 @@ impl Feed {
         feed.last_message = last_message;
     }
 
+    /// Check a signed message received by synchronisation
+    ///
+    /// The message id is the signature of the message.
+    /// If the sender is unknown, the public key is taken from
+    /// the user id of the sender.
+    fn verify_synced_message(
+        message_id: &Vec<u8>,
+        message: &Vec<u8>,
+    ) -> Option<proto_net::FeedMessageContent> {
+        let msg_content = match proto_net::FeedMessageContent::decode(&message[..]) {
+            Ok(msg_content) => msg_content,
+            Err(e) => {
+                log::error!("synced feed message could not be decoded: {:?}", e);
+                return None;
+            }
+        };
+        let sender_id = PeerId::from_bytes(&msg_content.sender).ok()?;
+        let key = match router::users::Users::get_pub_key(&sender_id)
+            .or_else(|| Self::get_key_from_id(&sender_id))
+        {
+            Some(key) => key,
+            None => {
+                log::error!("no public key for synced feed message {:?}", message_id);
+                return None;
+            }
+        };
+        let container = proto_net::FeedContainer {
+            signature: message_id.clone(),
+            message: message.clone(),
+        };
+        if !Self::validate_message(&container, key) {
+            log::error!("Validation of synced feed message {:?} failed", message_id);
+            return None;
+        }
+        Some(msg_content)
+    }
+
+    /// Get the public key contained in a user id
+    ///
+    /// The ID's of ed25519 keys are identity multihashes,
+    /// which contain the protobuf encoded public key.
+    fn get_key_from_id(user_id: &PeerId) -> Option<PublicKey> {
+        let bytes = user_id.to_bytes();
+        // identity multihash code and length of the digest
+        if bytes.len() < 2 || bytes[0] != 0x00 || bytes[1] as usize != bytes.len() - 2 {
+            return None;
+        }
+        let key = PublicKey::try_decode_protobuf(&bytes[2..]).ok()?;
+        match PeerId::from_public_key(&key) == *user_id {
+            true => Some(key),
+            false => None,
+        }
+    }
+
+    /// Get the signed message content
+    ///
+    /// Returns an empty vector for messages
+    /// which were stored without it.
+    pub fn get_message_bytes(message_id: &Vec<u8>) -> Vec<u8> {
+        let feed = FEED.get().read().unwrap();
+        match feed.tree_content.get(&message_id[..]) {
+            Ok(Some(message)) => message.to_vec(),
+            _ => Vec::new(),
+        }
+    }
+
     /// Save a Message
     ///
     /// This function saves a new message in the data base and in the in-memory BTreeMap
-    fn save_message(signature: Vec<u8>, message: proto_net::FeedMessageContent) {
+    fn save_message(
+        signature: Vec<u8>,
+        message: proto_net::FeedMessageContent,
+        message_bytes: &Vec<u8>,
+    ) {
         // open feed map for writing
         let mut feed = FEED.get().write().unwrap();
 
+        // save signed message
+        if let Err(e) = feed
+            .tree_content
+            .insert(&signature[..], message_bytes.clone())
+        {
+            log::error!("Error saving feed content to data base: {}", e);
+        }
+
         // insert message to in memory BTreeMap
         feed.messages.insert(signature.clone(), message.clone());
 
@@ -364,7 +586,7 @@ impl Feed {
         let message_data = FeedMessageData {
             index: last_message,
             message_id: signature.clone(),
-            sender_id: message.sender,
+            sender_id: message.sender.clone(),
             timestamp_sent: message.time,
             timestamp_received,
             content: message.content.clone(),
@@ -396,23 +618,26 @@ impl Feed {
         feed.last_message = last_message;
     }
 
+    /// Get the ids of the latest feed messages
+    ///
+    /// They are advertised to neighbours, which don't
+    /// support the feed synchronisation filter.
     pub fn get_latest_message_ids(count: usize) -> Vec<Vec<u8>> {
         let mut ids: Vec<Vec<u8>> = vec![];
 
         // get feed message store
         let feed = FEED.get().read().unwrap();
-        let mut msg_count: usize = count;
-        if feed.last_message < (count as u64) {
-            msg_count = feed.last_message as usize;
-        }
+        let msg_count = feed.last_message.min(count as u64);
 
-        let first_message = feed.last_message - (msg_count as u64);
+        let first_message = feed.last_message - msg_count;
         let first_message_bytes = first_message.to_be_bytes().to_vec();
         for res in feed.tree.range(first_message_bytes.as_slice()..) {
             match res {
                 Ok((_id, message_bytes)) => {
-                    let message: FeedMessageData = bincode::deserialize(&message_bytes).unwrap();
-                    ids.push(message.message_id.clone());
+                    match bincode::deserialize::<FeedMessageData>(&message_bytes) {
+                        Ok(message) => ids.push(message.message_id.clone()),
+                        Err(e) => log::error!("Error decoding feed message: {}", e),
+                    }
                 }
                 Err(e) => {
                     log::error!("Error retrieving feed message from data base: {}", e);
@@ -422,22 +647,297 @@ impl Feed {
         ids
     }
 
-    //return missing feed ids to request to the neighbour
+    /// Return the advertised feed ids we don't have
+    ///
+    /// The missing messages are requested from the neighbour.
     pub fn process_received_feed_ids(ids: &Vec<Vec<u8>>) -> Vec<Vec<u8>> {
         let mut missing_ids: Vec<Vec<u8>> = vec![];
 
         let feed = FEED.get().read().unwrap();
         for id in ids {
-            match feed.tree_ids.get(&id[..]).unwrap() {
-                Some(_index) => {}
-                _ => {
-                    missing_ids.push(id.clone());
-                }
+            match feed.tree_ids.contains_key(&id[..]) {
+                Ok(true) => {}
+                Ok(false) if Self::is_pruned(&feed, id) => {}
+                Ok(false) => missing_ids.push(id.clone()),
+                Err(e) => log::error!("Error retrieving feed id from data base: {}", e),
             }
         }
         missing_ids
     }
 
+    /// Was the message removed from the feed
+    fn is_pruned(feed: &Feed, message_id: &Vec<u8>) -> bool {
+        matches!(feed.tree_pruned.contains_key(&message_id[..]), Ok(true))
+    }
+
+    /// Start of the synchronisation time window
+    fn get_sync_since(now: u64) -> u64 {
+        let window = Configuration::get().feed.sync_window;
+        now.saturating_sub(window * 1000)
+    }
+
+    /// Create the feed synchronisation filter
+    ///
+    /// Returns the start of the synchronisation time window
+    /// and a bloom filter over the ids of all messages sent
+    /// within this window, of at most `max_bytes`.
+    /// The filter contains the removed messages of the window
+    /// as well, so that they are not sent to us again.
+    pub fn create_sync_filter(max_bytes: usize) -> (u64, BloomFilter) {
+        let since = Self::get_sync_since(timestamp::Timestamp::get_timestamp());
+        let mut ids = Self::get_window_ids(since);
+        ids.extend(Self::get_pruned_ids(since));
+
+        let mut filter = BloomFilter::new(ids.len(), rand::random(), max_bytes);
+        for id in &ids {
+            filter.insert(id);
+        }
+        (since, filter)
+    }
+
+    /// Reconcile our feed with the synchronisation filter of a neighbour
+    ///
+    /// Returns the messages of the time window the
+    /// neighbour does not have, the newest first.
+    pub fn reconcile(since: u64, filter: &BloomFilter) -> Vec<(Vec<u8>, Vec<u8>, String, u64)> {
+        let now = timestamp::Timestamp::get_timestamp();
+        let mut missing: Vec<Vec<u8>> = vec![];
+
+        {
+            let feed = FEED.get().read().unwrap();
+            for res in feed.tree.iter().rev() {
+                let message: FeedMessageData = match res {
+                    Ok((_id, message_bytes)) => match bincode::deserialize(&message_bytes) {
+                        Ok(message) => message,
+                        Err(_) => continue,
+                    },
+                    Err(e) => {
+                        log::error!("Error retrieving feed message from data base: {}", e);
+                        continue;
+                    }
+                };
+
+                // messages are received after they were sent
+                if message.timestamp_received < since {
+                    break;
+                }
+                if message.timestamp_sent < since || filter.contains(&message.message_id) {
+                    continue;
+                }
+                let expires = Self::get_content(&feed, &message.message_id)
+                    .map(|content| content.expires)
+                    .unwrap_or(0);
+                if Self::is_outdated(message.timestamp_sent, expires, now) {
+                    continue;
+                }
+
+                missing.push(message.message_id);
+                if missing.len() >= MAX_SYNC_MESSAGES {
+                    break;
+                }
+            }
+        }
+
+        Self::get_messges_by_ids(&missing)
+    }
+
+    /// get the ids of all messages sent since the timestamp
+    fn get_window_ids(since: u64) -> Vec<Vec<u8>> {
+        let mut ids: Vec<Vec<u8>> = vec![];
+
+        let feed = FEED.get().read().unwrap();
+        for res in feed.tree.iter().rev() {
+            match res {
+                Ok((_id, message_bytes)) => {
+                    let message: FeedMessageData = match bincode::deserialize(&message_bytes) {
+                        Ok(message) => message,
+                        Err(_) => continue,
+                    };
+                    // messages are received after they were sent
+                    if message.timestamp_received < since {
+                        break;
+                    }
+                    if message.timestamp_sent >= since {
+                        ids.push(message.message_id);
+                    }
+                }
+                Err(e) => {
+                    log::error!("Error retrieving feed message from data base: {}", e);
+                }
+            }
+        }
+        ids
+    }
+
+    /// get the ids of the removed messages sent since the timestamp
+    fn get_pruned_ids(since: u64) -> Vec<Vec<u8>> {
+        let feed = FEED.get().read().unwrap();
+        Self::get_pruned(&feed)
+            .into_iter()
+            .filter(|(_id, time)| *time >= since)
+            .map(|(id, _time)| id)
+            .collect()
+    }
+
+    /// get all removed message ids with their sent timestamp
+    fn get_pruned(feed: &Feed) -> Vec<(Vec<u8>, u64)> {
+        let mut pruned: Vec<(Vec<u8>, u64)> = vec![];
+        for res in feed.tree_pruned.iter() {
+            match res {
+                Ok((id, time_bytes)) => {
+                    if let Ok(time) = bincode::deserialize::<u64>(&time_bytes) {
+                        pruned.push((id.to_vec(), time));
+                    }
+                }
+                Err(e) => {
+                    log::error!("Error retrieving pruned feed id from data base: {}", e);
+                }
+            }
+        }
+        pruned
+    }
+
+    /// Select the removed message ids which are no longer kept
+    ///
+    /// These are the ids of messages sent before the sync
+    /// window and the oldest ids exceeding `max`.
+    fn select_dropped_pruned(
+        mut pruned: Vec<(Vec<u8>, u64)>,
+        since: u64,
+        max: usize,
+    ) -> Vec<Vec<u8>> {
+        pruned.sort_by_key(|(_id, time)| *time);
+        let outside = pruned.iter().filter(|(_id, time)| *time < since).count();
+        let excess = pruned.len().saturating_sub(max);
+        pruned
+            .into_iter()
+            .take(outside.max(excess))
+            .map(|(id, _time)| id)
+            .collect()
+    }
+
+    /// Remove outdated messages
+    ///
+    /// Removes expired messages and the messages older than
+    /// the configured maximal age. When the stored feed is still
+    /// larger than the configured maximal size, the oldest
+    /// messages are removed.
+    ///
+    /// The ids of removed messages within the sync window are kept,
+    /// the ids which left the window are dropped.
+    pub fn maintain() {
+        let max_size = Configuration::get().feed.max_size as usize * 1024 * 1024;
+        let now = timestamp::Timestamp::get_timestamp();
+        let since = Self::get_sync_since(now);
+
+        let mut feed = FEED.get().write().unwrap();
+
+        // check all messages, the oldest first
+        let mut entries: Vec<(Vec<u8>, Vec<u8>, u64, usize, bool)> = vec![];
+        let mut size: usize = 0;
+        for res in feed.tree.iter() {
+            match res {
+                Ok((key, message_bytes)) => {
+                    let message: FeedMessageData = match bincode::deserialize(&message_bytes) {
+                        Ok(message) => message,
+                        Err(_) => continue,
+                    };
+                    let content = feed
+                        .tree_content
+                        .get(&message.message_id[..])
+                        .ok()
+                        .flatten();
+                    let expires = content
+                        .as_ref()
+                        .and_then(|bytes| proto_net::FeedMessageContent::decode(&bytes[..]).ok())
+                        .map(|content| content.expires)
+                        .unwrap_or(0);
+                    let message_size =
+                        message_bytes.len() + content.map(|bytes| bytes.len()).unwrap_or(0);
+
+                    let outdated = Self::is_outdated(message.timestamp_sent, expires, now);
+                    if !outdated {
+                        size += message_size;
+                    }
+                    entries.push((
+                        key.to_vec(),
+                        message.message_id,
+                        message.timestamp_sent,
+                        message_size,
+                        outdated,
+                    ));
+                }
+                Err(e) => {
+                    log::error!("Error retrieving feed message from data base: {}", e);
+                }
+            }
+        }
+
+        // remove the oldest messages until the feed fits
+        if max_size != 0 {
+            for entry in entries.iter_mut() {
+                if size <= max_size {
+                    break;
+                }
+                if !entry.4 {
+                    entry.4 = true;
+                    size -= entry.3;
+                }
+            }
+        }
+
+        // remove messages
+        let mut removed = 0;
+        for (key, message_id, time, _size, remove) in entries {
+            if !remove {
+                continue;
+            }
+            if let Err(e) = feed.tree.remove(&key[..]) {
+                log::error!("Error removing feed message from data base: {}", e);
+                continue;
+            }
+            let _ = feed.tree_ids.remove(&message_id[..]);
+            let _ = feed.tree_content.remove(&message_id[..]);
+            feed.messages.remove(&message_id);
+            if time >= since {
+                let time_bytes = bincode::serialize(&time).unwrap();
+                if let Err(e) = feed.tree_pruned.insert(&message_id[..], time_bytes) {
+                    log::error!("Error saving pruned feed id to data base: {}", e);
+                }
+            }
+            removed += 1;
+        }
+
+        // drop the removed ids which are no longer needed
+        let dropped = Self::select_dropped_pruned(Self::get_pruned(&feed), since, MAX_PRUNED_IDS);
+        for message_id in &dropped {
+            let _ = feed.tree_pruned.remove(&message_id[..]);
+        }
+        if !dropped.is_empty() {
+            if let Err(e) = feed.tree_pruned.flush() {
+                log::error!("Error when flushing data base to disk: {}", e);
+            }
+        }
+
+        if removed > 0 {
+            let last_message_bytes = bincode::serialize(&feed.last_message).unwrap();
+            if let Err(e) = feed.tree_ids.insert(LAST_INDEX_KEY, last_message_bytes) {
+                log::error!("Error saving feed index to data base: {}", e);
+            }
+            for tree in [
+                &feed.tree,
+                &feed.tree_ids,
+                &feed.tree_content,
+                &feed.tree_pruned,
+            ] {
+                if let Err(e) = tree.flush() {
+                    log::error!("Error when flushing data base to disk: {}", e);
+                }
+            }
+            log::debug!("{} outdated feed messages removed", removed);
+        }
+    }
+
     pub fn get_messges_by_ids(ids: &Vec<Vec<u8>>) -> Vec<(Vec<u8>, Vec<u8>, String, u64)> {
         let mut res: Vec<(Vec<u8>, Vec<u8>, String, u64)> = vec![];
         let feed = FEED.get().read().unwrap();
@@ -462,7 +962,15 @@ impl Feed {
     ///
     /// This function get messages from data base
     /// that are newer then the last message.
-    fn get_messages(last_message: u64) -> proto::FeedMessageList {
+    ///
+    /// Messages can be filtered by hashtag and author.
+    /// Expired messages and messages hidden by the
+    /// local moderation are left out.
+    fn get_messages(request: &proto::FeedMessageRequest) -> proto::FeedMessageList {
+        let last_message = request.last_index;
+        let hashtag = Self::normalize_hashtag(&request.hashtag);
+        let now = timestamp::Timestamp::get_timestamp();
+
         // create empty feed list
         let mut feed_list = proto::FeedMessageList {
             feed_message: Vec::new(),
@@ -489,6 +997,30 @@ impl Feed {
                             log::trace!("key find error");
                         }
 
+                        // get attachments, hashtags, reply and expiry
+                        let content =
+                            Self::get_content(&feed, &message.message_id).unwrap_or_default();
+                        let hashtags: Vec<String> = content
+                            .hashtags
+                            .iter()
+                            .filter_map(|tag| Self::normalize_hashtag(tag))
+                            .collect();
+
+                        // filter messages
+                        if !request.author.is_empty() && request.author != message.sender_id {
+                            continue;
+                        }
+                        if let Some(tag) = &hashtag {
+                            if !hashtags.contains(tag) {
+                                continue;
+                            }
+                        }
+                        if Self::is_expired(content.expires, now)
+                            || Self::is_hidden(&feed, &message.sender_id, &hashtags)
+                        {
+                            continue;
+                        }
+
                         let sender_id_base58 =
                             bs58::encode(message.sender_id.clone()).into_string();
 
@@ -512,6 +1044,19 @@ impl Feed {
                             content: message.content.clone(),
                             // data base index
                             index: message.index,
+                            attachments: content
+                                .attachments
+                                .into_iter()
+                                .map(|attachment| proto::FeedAttachment {
+                                    name: attachment.name,
+                                    mime_type: attachment.mime_type,
+                                    size: attachment.size,
+                                    reference: attachment.reference,
+                                })
+                                .collect(),
+                            hashtags,
+                            reply_to: content.reply_to,
+                            expires: content.expires,
                         };
 
                         // add message to feed list
@@ -527,6 +1072,174 @@ impl Feed {
         feed_list
     }
 
+    /// get the signed message content from the data base
+    fn get_content(feed: &Feed, message_id: &Vec<u8>) -> Option<proto_net::FeedMessageContent> {
+        match feed.tree_content.get(&message_id[..]) {
+            Ok(Some(bytes)) => proto_net::FeedMessageContent::decode(&bytes[..]).ok(),
+            _ => None,
+        }
+    }
+
+    /// Has the message expired
+    fn is_expired(expires: u64, now: u64) -> bool {
+        expires != 0 && expires <= now
+    }
+
+    /// Has the message expired or is it older than the configured maximal age
+    fn is_outdated(time: u64, expires: u64, now: u64) -> bool {
+        Self::is_outdated_by(time, expires, Configuration::get().feed.max_age, now)
+    }
+
+    /// Has the message expired or is it older than the maximal age
+    ///
+    /// The maximal age is given in seconds, 0 means unlimited.
+    fn is_outdated_by(time: u64, expires: u64, max_age: u64, now: u64) -> bool {
+        Self::is_expired(expires, now) || (max_age != 0 && time + max_age * 1000 < now)
+    }
+
+    /// Is the message hidden by the local moderation
+    fn is_hidden(feed: &Feed, sender_id: &Vec<u8>, hashtags: &Vec<String>) -> bool {
+        if let Ok(true) = feed.tree_blocked.contains_key(&sender_id[..]) {
+            return true;
+        }
+        hashtags
+            .iter()
+            .any(|tag| matches!(feed.tree_muted.contains_key(tag.as_bytes()), Ok(true)))
+    }
+
+    /// Normalize a hashtag
+    ///
+    /// Removes the leading '#' and converts it to lower case.
+    /// Only letters, digits and '_' are part of a hashtag.
+    fn normalize_hashtag(tag: &str) -> Option<String> {
+        let tag: String = tag
+            .trim()
+            .trim_start_matches('#')
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .take(MAX_HASHTAG_LENGTH)
+            .collect::<String>()
+            .to_lowercase();
+        match tag.is_empty() {
+            true => None,
+            false => Some(tag),
+        }
+    }
+
+    /// Collect the hashtags of the content and the additional hashtags
+    fn collect_hashtags(content: &str, additional: &Vec<String>) -> Vec<String> {
+        let mut hashtags: Vec<String> = Vec::new();
+        let from_content = content
+            .split_whitespace()
+            .filter(|word| word.starts_with('#'));
+        for tag in from_content.chain(additional.iter().map(|tag| tag.as_str())) {
+            if let Some(tag) = Self::normalize_hashtag(tag) {
+                if !hashtags.contains(&tag) {
+                    hashtags.push(tag);
+                }
+            }
+        }
+        hashtags.truncate(MAX_HASHTAGS);
+        hashtags
+    }
+
+    /// Change the local moderation
+    fn moderate(moderation: proto::FeedModeration) {
+        let feed = FEED.get().read().unwrap();
+        let timestamp = bincode::serialize(&timestamp::Timestamp::get_timestamp()).unwrap();
+
+        let result = match moderation.action {
+            Some(proto::feed_moderation::Action::BlockSender(user_id)) => {
+                if PeerId::from_bytes(&user_id).is_err() {
+                    log::error!("feed moderation: invalid user id");
+                    return;
+                }
+                feed.tree_blocked
+                    .insert(&user_id[..], timestamp)
+                    .map(|_| ())
+            }
+            Some(proto::feed_moderation::Action::UnblockSender(user_id)) => {
+                feed.tree_blocked.remove(&user_id[..]).map(|_| ())
+            }
+            Some(proto::feed_moderation::Action::MuteHashtag(tag)) => {
+                match Self::normalize_hashtag(&tag) {
+                    Some(tag) => feed
+                        .tree_muted
+                        .insert(tag.as_bytes(), timestamp)
+                        .map(|_| ()),
+                    None => {
+                        log::error!("feed moderation: invalid hashtag {}", tag);
+                        return;
+                    }
+                }
+            }
+            Some(proto::feed_moderation::Action::UnmuteHashtag(tag)) => {
+                match Self::normalize_hashtag(&tag) {
+                    Some(tag) => feed.tree_muted.remove(tag.as_bytes()).map(|_| ()),
+                    None => return,
+                }
+            }
+            None => return,
+        };
+
+        match result {
+            Ok(()) => {
+                if let Err(e) = feed.tree_blocked.flush() {
+                    log::error!("Error when flushing data base to disk: {}", e);
+                }
+                if let Err(e) = feed.tree_muted.flush() {
+                    log::error!("Error when flushing data base to disk: {}", e);
+                }
+            }
+            Err(e) => log::error!("Error saving feed moderation to data base: {}", e),
+        }
+    }
+
+    /// Get the local moderation settings
+    fn get_moderation_list() -> proto::FeedModerationList {
+        let feed = FEED.get().read().unwrap();
+
+        let blocked_senders = feed
+            .tree_blocked
+            .iter()
+            .filter_map(|res| res.ok())
+            .map(|(user_id, _)| user_id.to_vec())
+            .collect();
+        let muted_hashtags = feed
+            .tree_muted
+            .iter()
+            .filter_map(|res| res.ok())
+            .filter_map(|(tag, _)| String::from_utf8(tag.to_vec()).ok())
+            .collect();
+
+        proto::FeedModerationList {
+            blocked_senders,
+            muted_hashtags,
+        }
+    }
+
+    /// Send an RPC message to the client
+    fn send_rpc_message(message: proto::feed::Message) {
+        // pack message
+        let proto_message = proto::Feed {
+            message: Some(message),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Feed.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+
     /// Sign a message with the private key
     /// The signature can be validated with the corresponding public key.
     pub fn sign_message(buf: &Vec<u8>, keys: Keypair) -> Vec<u8> {
@@ -550,26 +1263,21 @@ impl Feed {
                 match feed.message {
                     Some(proto::feed::Message::Request(feed_request)) => {
                         // get feed messages from data base
-                        let feed_list = Self::get_messages(feed_request.last_index);
-
-                        // pack message
-                        let proto_message = proto::Feed {
-                            message: Some(proto::feed::Message::Received(feed_list)),
-                        };
-
-                        // encode message
-                        let mut buf = Vec::with_capacity(proto_message.encoded_len());
-                        proto_message
-                            .encode(&mut buf)
-                            .expect("Vec<u8> provides capacity as needed");
+                        let feed_list = Self::get_messages(&feed_request);
 
                         // send message
-                        Rpc::send_message(
-                            buf,
-                            crate::rpc::proto::Modules::Feed.into(),
-                            "".to_string(),
-                            Vec::new(),
-                        );
+                        Self::send_rpc_message(proto::feed::Message::Received(feed_list));
+                    }
+                    Some(proto::feed::Message::Moderation(moderation)) => {
+                        Self::moderate(moderation);
+                        Self::send_rpc_message(proto::feed::Message::ModerationList(
+                            Self::get_moderation_list(),
+                        ));
+                    }
+                    Some(proto::feed::Message::ModerationRequest(_)) => {
+                        Self::send_rpc_message(proto::feed::Message::ModerationList(
+                            Self::get_moderation_list(),
+                        ));
                     }
                     Some(proto::feed::Message::Send(send_feed)) => {
                         // print message
@@ -583,7 +1291,7 @@ impl Feed {
                                     Some(account) => {
                                         user_account = account;
                                         // send the message
-                                        Self::send(&user_account, send_feed.content, lan, internet);
+                                        Self::send(&user_account, send_feed, lan, internet);
                                     }
                                     None => {
                                         log::error!(
@@ -610,3 +1318,134 @@ impl Feed {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// create a feed on a temporary data base
+    fn test_feed() -> (sled::Db, Feed) {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let feed = Feed {
+            messages: BTreeMap::new(),
+            tree_ids: EncryptedTree::open(&db, "feed_id"),
+            tree: EncryptedTree::open(&db, "feed"),
+            tree_content: EncryptedTree::open(&db, "feed_content"),
+            tree_blocked: EncryptedTree::open(&db, "feed_blocked"),
+            tree_muted: EncryptedTree::open(&db, "feed_muted"),
+            tree_pruned: EncryptedTree::open(&db, "feed_pruned"),
+            last_message: 0,
+        };
+        (db, feed)
+    }
+
+    #[test]
+    fn test_normalize_hashtag() {
+        assert_eq!(Feed::normalize_hashtag("#Qaul"), Some("qaul".to_string()));
+        assert_eq!(
+            Feed::normalize_hashtag("  ##mesh_net "),
+            Some("mesh_net".to_string())
+        );
+        assert_eq!(Feed::normalize_hashtag("#qaul!"), Some("qaul".to_string()));
+        assert_eq!(Feed::normalize_hashtag("#"), None);
+        assert_eq!(Feed::normalize_hashtag("#!qaul"), None);
+        assert_eq!(
+            Feed::normalize_hashtag(&"a".repeat(MAX_HASHTAG_LENGTH + 10)),
+            Some("a".repeat(MAX_HASHTAG_LENGTH))
+        );
+    }
+
+    #[test]
+    fn test_collect_hashtags() {
+        let hashtags = Feed::collect_hashtags(
+            "Hello #qaul and #Mesh, no#tag #qaul",
+            &vec![
+                "mesh".to_string(),
+                "#Community".to_string(),
+                "#".to_string(),
+            ],
+        );
+        assert_eq!(hashtags, vec!["qaul", "mesh", "community"]);
+
+        let content: Vec<String> = (0..MAX_HASHTAGS + 4).map(|i| format!("#t{}", i)).collect();
+        let hashtags = Feed::collect_hashtags(&content.join(" "), &Vec::new());
+        assert_eq!(hashtags.len(), MAX_HASHTAGS);
+        assert_eq!(hashtags[0], "t0");
+    }
+
+    #[test]
+    fn test_is_outdated() {
+        let now = 100_000;
+
+        // expiry
+        assert!(!Feed::is_outdated_by(now, 0, 0, now));
+        assert!(Feed::is_outdated_by(now, now, 0, now));
+        assert!(!Feed::is_outdated_by(now, now + 1, 0, now));
+
+        // maximal age in seconds
+        assert!(!Feed::is_outdated_by(now - 10_000, 0, 10, now));
+        assert!(Feed::is_outdated_by(now - 10_001, 0, 10, now));
+        assert!(!Feed::is_outdated_by(0, 0, 0, now));
+    }
+
+    #[test]
+    fn test_is_hidden() {
+        let (db, feed) = test_feed();
+        let sender = vec![1, 2, 3];
+        let tags = vec!["qaul".to_string()];
+        assert!(!Feed::is_hidden(&feed, &sender, &tags));
+
+        // muted hashtag
+        db.open_tree("feed_muted")
+            .unwrap()
+            .insert(b"qaul", vec![0])
+            .unwrap();
+        assert!(Feed::is_hidden(&feed, &sender, &tags));
+        assert!(!Feed::is_hidden(&feed, &sender, &vec!["mesh".to_string()]));
+
+        // blocked sender
+        db.open_tree("feed_blocked")
+            .unwrap()
+            .insert(&sender[..], vec![0])
+            .unwrap();
+        assert!(Feed::is_hidden(&feed, &sender, &Vec::new()));
+        assert!(!Feed::is_hidden(&feed, &vec![4], &Vec::new()));
+    }
+
+    #[test]
+    fn test_get_key_from_id() {
+        let key = Keypair::generate_ed25519().public();
+        let user_id = PeerId::from_public_key(&key);
+        assert_eq!(Feed::get_key_from_id(&user_id), Some(key));
+
+        // random ID's don't contain a key
+        assert_eq!(Feed::get_key_from_id(&PeerId::random()), None);
+    }
+
+    #[test]
+    fn test_pruned_ids_outside_window_are_dropped() {
+        let pruned = vec![
+            (vec![1], 3000),
+            (vec![2], 1000),
+            (vec![3], 2000),
+            (vec![4], 4000),
+        ];
+        let dropped = Feed::select_dropped_pruned(pruned, 2500, 10);
+        assert_eq!(dropped, vec![vec![2], vec![3]]);
+    }
+
+    #[test]
+    fn test_pruned_ids_are_bounded() {
+        let pruned: Vec<(Vec<u8>, u64)> = (0..10u8).map(|i| (vec![i], 100 + i as u64)).collect();
+        let dropped = Feed::select_dropped_pruned(pruned, 0, 4);
+        assert_eq!(dropped.len(), 6);
+        assert_eq!(dropped[0], vec![0]);
+        assert_eq!(dropped[5], vec![5]);
+    }
+
+    #[test]
+    fn test_pruned_ids_within_bounds_are_kept() {
+        let pruned = vec![(vec![1], 3000), (vec![2], 4000)];
+        assert!(Feed::select_dropped_pruned(pruned, 2000, 4).is_empty());
+    }
+}
@@ -3,7 +3,7 @@
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Feed {
     /// message type
-    #[prost(oneof = "feed::Message", tags = "1, 2, 3")]
+    #[prost(oneof = "feed::Message", tags = "1, 2, 3, 4, 5, 6")]
     pub message: ::core::option::Option<feed::Message>,
 }
 /// Nested message and enum types in `Feed`.
@@ -20,6 +20,15 @@ pub mod feed {
         /// request received messages
         #[prost(message, tag = "3")]
         Request(super::FeedMessageRequest),
+        /// change the local moderation
+        #[prost(message, tag = "4")]
+        Moderation(super::FeedModeration),
+        /// request the local moderation settings
+        #[prost(message, tag = "5")]
+        ModerationRequest(super::FeedModerationRequest),
+        /// local moderation settings
+        #[prost(message, tag = "6")]
+        ModerationList(super::FeedModerationList),
     }
 }
 /// request feed messages
@@ -39,6 +48,17 @@ pub struct FeedMessageRequest {
     /// is 0, all feed messages will be sent.
     #[prost(uint64, tag = "2")]
     pub last_index: u64,
+    /// only send messages with this hashtag
+    ///
+    /// The hashtag can be given with or without
+    /// the leading '#'. An empty string matches all messages.
+    #[prost(string, tag = "3")]
+    pub hashtag: ::prost::alloc::string::String,
+    /// only send messages of this author
+    ///
+    /// An empty value matches all messages.
+    #[prost(bytes = "vec", tag = "4")]
+    pub author: ::prost::alloc::vec::Vec<u8>,
 }
 /// List of feed messages
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -47,7 +67,7 @@ pub struct FeedMessageList {
     pub feed_message: ::prost::alloc::vec::Vec<FeedMessage>,
 }
 /// A single feed message
-#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct FeedMessage {
     #[prost(bytes = "vec", tag = "1")]
     pub sender_id: ::prost::alloc::vec::Vec<u8>,
@@ -73,10 +93,96 @@ pub struct FeedMessage {
     pub content: ::prost::alloc::string::String,
     #[prost(uint64, tag = "8")]
     pub index: u64,
+    /// attached files
+    #[prost(message, repeated, tag = "11")]
+    pub attachments: ::prost::alloc::vec::Vec<FeedAttachment>,
+    /// hashtags without the leading '#', in lower case
+    #[prost(string, repeated, tag = "12")]
+    pub hashtags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// message id of the feed message this is a reply to
+    #[prost(bytes = "vec", tag = "13")]
+    pub reply_to: ::prost::alloc::vec::Vec<u8>,
+    /// expiry timestamp in milliseconds, 0 if the message does not expire
+    #[prost(uint64, tag = "14")]
+    pub expires: u64,
 }
-/// send feed message
+/// Reference to a file attached to a feed message
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FeedAttachment {
+    /// file name
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// mime type of the file
+    #[prost(string, tag = "2")]
+    pub mime_type: ::prost::alloc::string::String,
+    /// file size in bytes
+    #[prost(uint64, tag = "3")]
+    pub size: u64,
+    /// where to retrieve the file, e.g. a URL or a file ID
+    #[prost(string, tag = "4")]
+    pub reference: ::prost::alloc::string::String,
+}
+/// send feed message
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SendMessage {
     #[prost(string, tag = "1")]
     pub content: ::prost::alloc::string::String,
+    /// attached files
+    #[prost(message, repeated, tag = "2")]
+    pub attachments: ::prost::alloc::vec::Vec<FeedAttachment>,
+    /// message id of the feed message this is a reply to
+    #[prost(bytes = "vec", tag = "3")]
+    pub reply_to: ::prost::alloc::vec::Vec<u8>,
+    /// expiry timestamp in milliseconds
+    ///
+    /// 0 means the message does not expire.
+    #[prost(uint64, tag = "4")]
+    pub expires: u64,
+    /// additional hashtags
+    ///
+    /// Hashtags in the content are added automatically.
+    #[prost(string, repeated, tag = "5")]
+    pub hashtags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Change the local moderation
+///
+/// Moderation is local to this node: messages of blocked
+/// senders and with muted hashtags are still stored and
+/// forwarded, but they are not shown.
+/// The node answers with the `FeedModerationList`.
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FeedModeration {
+    #[prost(oneof = "feed_moderation::Action", tags = "1, 2, 3, 4")]
+    pub action: ::core::option::Option<feed_moderation::Action>,
+}
+/// Nested message and enum types in `FeedModeration`.
+pub mod feed_moderation {
+    #[derive(Clone, PartialEq, Eq, Hash, ::prost::Oneof)]
+    pub enum Action {
+        /// hide all messages of this user
+        #[prost(bytes, tag = "1")]
+        BlockSender(::prost::alloc::vec::Vec<u8>),
+        /// show the messages of this user again
+        #[prost(bytes, tag = "2")]
+        UnblockSender(::prost::alloc::vec::Vec<u8>),
+        /// hide all messages with this hashtag
+        #[prost(string, tag = "3")]
+        MuteHashtag(::prost::alloc::string::String),
+        /// show the messages with this hashtag again
+        #[prost(string, tag = "4")]
+        UnmuteHashtag(::prost::alloc::string::String),
+    }
+}
+/// Request the local moderation settings
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FeedModerationRequest {}
+/// Local moderation settings
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct FeedModerationList {
+    /// blocked senders
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub blocked_senders: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    /// muted hashtags, in lower case
+    #[prost(string, repeated, tag = "2")]
+    pub muted_hashtags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
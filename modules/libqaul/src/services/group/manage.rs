@@ -9,6 +9,7 @@ use std::collections::BTreeMap;
 use super::group_id::GroupId;
 use super::{Group, GroupInvited, GroupStorage};
 use crate::node::UserAccounts;
+use crate::router::users::Users;
 use crate::services::chat::{self, Chat, ChatStorage};
 use crate::utilities::timestamp::Timestamp;
 
@@ -285,6 +286,7 @@ impl GroupManage {
                 profile_pic,
                 about,
                 college,
+                trust: Self::member_trust(account_id, &m.user_id),
             };
             members.push(member);
         }
@@ -305,6 +307,14 @@ impl GroupManage {
         Ok(res)
     }
 
+    /// get the trust level of the user account in a group member as protobuf value
+    fn member_trust(account_id: &PeerId, user_id: &[u8]) -> i32 {
+        match PeerId::from_bytes(user_id) {
+            Ok(id) => Users::get_trust(account_id, &id).as_proto(),
+            Err(_) => super::proto_rpc::TrustLevel::Unverified as i32,
+        }
+    }
+
     /// get group list from rpc command
     ///
     /// `account_id` the user account ID
@@ -336,6 +346,7 @@ impl GroupManage {
                             profile_pic,
                             about: m.about.clone(),
                             college: m.college.clone(),
+                            trust: Self::member_trust(account_id, &m.user_id),
                         };
                         members.push(member);
                     }
@@ -373,6 +384,7 @@ impl GroupManage {
                     let mut members: Vec<super::proto_rpc::GroupMember> = Vec::new();
                     let invite: GroupInvited = bincode::deserialize(&invite_bytes).unwrap();
                     for (_, member) in invite.group.members {
+                        let trust = Self::member_trust(account_id, &member.user_id);
                         members.push(super::proto_rpc::GroupMember {
                             user_id: member.user_id,
                             role: member.role,
@@ -384,6 +396,7 @@ impl GroupManage {
                             profile_pic: member.profile_pic,
                             about: member.about,
                             college: member.college,
+                            trust,
                         });
                     }
 
@@ -513,6 +526,20 @@ impl GroupManage {
         // save group
         GroupStorage::save_group(account_id, group);
 
+        // rotate our sender key if members left the group
+        if !first_join && !orign_members.is_empty() {
+            super::SenderKeys::rotate(&account_id, &notify.group_id);
+            for left_member in orign_members.keys() {
+                if let Ok(left_member_id) = PeerId::from_bytes(left_member) {
+                    super::SenderKeys::remove_member(
+                        &account_id,
+                        &notify.group_id,
+                        &left_member_id,
+                    );
+                }
+            }
+        }
+
         // save events
         if first_join {
             let event = chat::rpc_proto::ChatContentMessage {
@@ -222,6 +222,14 @@ impl Member {
         };
         Group::send_notify_message(&user_account, user_id, proto_message.encode_to_vec());
 
+        // rotate our sender key, so the removed user can't
+        // decrypt any further group messages
+        super::SenderKeys::rotate(account_id, group_id);
+        super::SenderKeys::remove_member(account_id, group_id, user_id);
+
+        // inform the remaining members
+        Group::post_group_update(account_id, group_id);
+
         // save group event
         let event = chat::rpc_proto::ChatContentMessage {
             message: Some(chat::rpc_proto::chat_content_message::Message::GroupEvent(
@@ -335,6 +343,9 @@ impl Member {
         // save group
         GroupStorage::save_group(account_id.to_owned(), group.clone());
 
+        // remove all sender keys of this group
+        super::SenderKeys::remove_group(account_id, &group.id);
+
         // save event
         let event = chat::rpc_proto::ChatContentMessage {
             message: Some(chat::rpc_proto::chat_content_message::Message::GroupEvent(
@@ -444,6 +455,9 @@ impl Member {
         // save group
         GroupStorage::save_group(account_id.to_owned(), group.clone());
 
+        // remove all sender keys of this group
+        super::SenderKeys::remove_group(account_id, &group.id);
+
         // save event
         let event = chat::rpc_proto::ChatContentMessage {
             message: Some(chat::rpc_proto::chat_content_message::Message::GroupEvent(
@@ -24,6 +24,7 @@ pub mod group_id;
 pub mod manage;
 pub mod member;
 pub mod message;
+pub mod sender_key;
 pub mod storage;
 
 pub use group_id::GroupId;
@@ -31,6 +32,7 @@ pub use manage::GroupManage;
 pub use manage::GroupManage as Manage;
 pub use member::Member;
 pub use message::GroupMessage;
+pub use sender_key::SenderKeys;
 pub use storage::GroupStorage;
 
 /// Import protobuf message definition generated by
@@ -214,6 +216,64 @@ impl Group {
         }
     }
 
+    /// Send a message to all members of a group
+    ///
+    /// Messages of direct chats are encrypted for the other user.
+    /// Messages of groups are encrypted once with our sender key,
+    /// if this fails, they are encrypted for every member.
+    pub fn send_to_members(
+        user_account: &UserAccount,
+        group: &Group,
+        data: Vec<u8>,
+        message_type: MessagingServiceType,
+        message_id: &Vec<u8>,
+    ) {
+        let mut recipients: Vec<PeerId> = Vec::new();
+        for user_id in group.members.keys() {
+            match PeerId::from_bytes(user_id) {
+                Ok(receiver) if receiver != user_account.id => recipients.push(receiver),
+                Ok(_) => {}
+                Err(e) => log::error!("invalid group member id: {}", e),
+            }
+        }
+        if recipients.is_empty() {
+            return;
+        }
+
+        if !group.is_direct_chat {
+            match Messaging::pack_and_send_group_message(
+                user_account,
+                group,
+                &recipients,
+                data.clone(),
+                message_id,
+            ) {
+                Ok(_) => return,
+                Err(err) => log::error!("group message encryption failed {}", err),
+            }
+        }
+
+        for receiver in recipients {
+            log::trace!("send message to {}", receiver.to_base58());
+            if let Err(error) = Messaging::pack_and_send_message(
+                user_account,
+                &receiver,
+                data.clone(),
+                message_type.clone(),
+                message_id,
+                true,
+            ) {
+                log::error!(
+                    "group message send error - receiver: {}, group_id: {}, message_id: {}, error: {}",
+                    receiver.to_base58(),
+                    bs58::encode(&group.id).into_string(),
+                    bs58::encode(message_id).into_string(),
+                    error
+                );
+            }
+        }
+    }
+
     /// Send capsuled group message through messaging service
     #[allow(dead_code)]
     pub fn send_group_message(
@@ -393,6 +453,14 @@ impl Group {
                         &group_info,
                     );
                 }
+                Some(proto_net::group_container::Message::SenderKey(sender_key)) => {
+                    log::trace!("group sender key arrived");
+                    if let Err(error) =
+                        SenderKeys::on_distribution(sender_id, receiver_id, &sender_key)
+                    {
+                        log::error!("group sender key error {}", error);
+                    }
+                }
                 None => {
                     log::error!("group message from {} was empty", sender_id.to_base58())
                 }
@@ -0,0 +1,654 @@
+// Copyright (c) 2022 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Group Sender Keys
+//!
+//! Group messages are encrypted once by the sender with a
+//! symmetric sender key chain, instead of encrypting them
+//! separately for every group member.
+//!
+//! Every group member creates its own chain per group, and
+//! distributes it to all other members via the pairwise
+//! encrypted messaging sessions.
+//! For every message, a new message key is derived from the chain
+//! and the chain is ratcheted forward:
+//!
+//! * message key: `SHA256(0x01 | chain_key)`
+//! * next chain key: `SHA256(0x02 | chain_key)`
+//!
+//! When a member leaves or is removed from a group, all remaining
+//! members rotate their sender keys, so that the former member
+//! can't decrypt any further messages.
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use libp2p::PeerId;
+use prost::Message;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{Group, GroupStorage};
+use crate::node::user_accounts::{UserAccount, UserAccounts};
+use crate::services::messaging::{self, process::MessagingProcess};
+
+/// Maximal number of message keys a chain is ratcheted forward
+/// to decrypt an out of order message.
+const MAX_SKIP: u32 = 2000;
+
+/// Sender key chain of a group member
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SenderKeyChain {
+    /// key id
+    ///
+    /// increases with every rotation of the key
+    pub key_id: u32,
+    /// current chain key
+    pub chain_key: Vec<u8>,
+    /// iteration of the current chain key
+    pub iteration: u32,
+    /// message keys of skipped iterations
+    ///
+    /// These are kept to decrypt messages arriving out of order.
+    pub skipped: BTreeMap<u32, Vec<u8>>,
+    /// members this key has been distributed to
+    ///
+    /// This is only used for our own chains.
+    pub distributed_to: BTreeSet<Vec<u8>>,
+}
+
+/// Group Sender Keys
+pub struct SenderKeys {}
+
+impl SenderKeys {
+    /// create the data base key of a chain
+    ///
+    /// {group_id}{user_id}{key_id}
+    fn create_key(group_id: &[u8], user_id: &PeerId, key_id: u32) -> Vec<u8> {
+        let mut key = Self::create_prefix(group_id, user_id);
+        key.extend_from_slice(&key_id.to_be_bytes());
+        key
+    }
+
+    /// create the data base key prefix of all chains of a member
+    fn create_prefix(group_id: &[u8], user_id: &PeerId) -> Vec<u8> {
+        let mut key = group_id.to_vec();
+        key.extend_from_slice(&user_id.to_bytes());
+        key
+    }
+
+    /// get the current chain of a group member
+    fn get_current(
+        account_id: &PeerId,
+        group_id: &[u8],
+        user_id: &PeerId,
+    ) -> Option<SenderKeyChain> {
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+        let prefix = Self::create_prefix(group_id, user_id);
+
+        match db_ref.sender_keys.scan_prefix(prefix).next_back() {
+            Some(Ok((_key, chain_bytes))) => bincode::deserialize(&chain_bytes).ok(),
+            Some(Err(e)) => {
+                log::error!("{}", e);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// get a specific chain of a group member
+    fn get_chain(
+        account_id: &PeerId,
+        group_id: &[u8],
+        user_id: &PeerId,
+        key_id: u32,
+    ) -> Option<SenderKeyChain> {
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+
+        match db_ref
+            .sender_keys
+            .get(Self::create_key(group_id, user_id, key_id))
+        {
+            Ok(Some(chain_bytes)) => bincode::deserialize(&chain_bytes).ok(),
+            Ok(None) => None,
+            Err(e) => {
+                log::error!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// save a chain to the data base
+    fn save_chain(account_id: &PeerId, group_id: &[u8], user_id: &PeerId, chain: &SenderKeyChain) {
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+
+        let chain_bytes = bincode::serialize(chain).unwrap();
+        if let Err(e) = db_ref.sender_keys.insert(
+            Self::create_key(group_id, user_id, chain.key_id),
+            chain_bytes,
+        ) {
+            log::error!("Error saving sender key to data base: {}", e);
+        }
+        if let Err(e) = db_ref.sender_keys.flush() {
+            log::error!("Error sender keys flush: {}", e);
+        }
+    }
+
+    /// remove all chains of a member with a key id smaller then `key_id`
+    fn remove_chains_before(account_id: &PeerId, group_id: &[u8], user_id: &PeerId, key_id: u32) {
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+
+        let first_key = Self::create_key(group_id, user_id, 0);
+        let last_key = Self::create_key(group_id, user_id, key_id);
+        for (key, _) in db_ref
+            .sender_keys
+            .range(first_key.as_slice()..last_key.as_slice())
+            .flatten()
+        {
+            if let Err(e) = db_ref.sender_keys.remove(key) {
+                log::error!("Error removing sender key: {}", e);
+            }
+        }
+    }
+
+    /// create a new random chain
+    fn new_chain(key_id: u32) -> SenderKeyChain {
+        let mut chain_key = vec![0u8; 32];
+        rand::rng().fill_bytes(&mut chain_key);
+
+        SenderKeyChain {
+            key_id,
+            chain_key,
+            iteration: 0,
+            skipped: BTreeMap::new(),
+            distributed_to: BTreeSet::new(),
+        }
+    }
+
+    /// derive the message key and the next chain key
+    fn ratchet(chain_key: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let message_key = Sha256::new()
+            .chain_update([0x01])
+            .chain_update(chain_key)
+            .finalize()
+            .to_vec();
+        let next_chain_key = Sha256::new()
+            .chain_update([0x02])
+            .chain_update(chain_key)
+            .finalize()
+            .to_vec();
+
+        (message_key, next_chain_key)
+    }
+
+    /// create the nonce of a message
+    ///
+    /// Every message key is only used once.
+    fn create_nonce(key_id: u32, iteration: u32) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[0..4].copy_from_slice(&key_id.to_be_bytes());
+        nonce[4..8].copy_from_slice(&iteration.to_be_bytes());
+        nonce
+    }
+
+    /// create the associated data of a message
+    fn create_associated_data(group_id: &[u8], sender_id: &PeerId) -> Vec<u8> {
+        let mut data = group_id.to_vec();
+        data.extend_from_slice(&sender_id.to_bytes());
+        data
+    }
+
+    /// Encrypt a group message with our sender key
+    ///
+    /// Distributes our sender key to all group members,
+    /// which haven't received it yet, before the message
+    /// is encrypted.
+    pub fn encrypt(
+        user_account: &UserAccount,
+        group: &Group,
+        data: &[u8],
+    ) -> Result<messaging::proto::GroupEncrypted, String> {
+        let mut chain = match Self::get_current(&user_account.id, &group.id, &user_account.id) {
+            Some(chain) => chain,
+            None => Self::new_chain(0),
+        };
+
+        // distribute the key to new members
+        let mut distributed = false;
+        for user_id in group.members.keys() {
+            if *user_id == user_account.id.to_bytes() || chain.distributed_to.contains(user_id) {
+                continue;
+            }
+            match PeerId::from_bytes(user_id) {
+                Ok(receiver) => {
+                    Self::send_distribution(user_account, &receiver, &group.id, &chain);
+                    chain.distributed_to.insert(user_id.clone());
+                    distributed = true;
+                }
+                Err(e) => log::error!("invalid group member id: {}", e),
+            }
+        }
+        if distributed {
+            log::trace!(
+                "distributed sender key {} of group {}",
+                chain.key_id,
+                bs58::encode(&group.id).into_string()
+            );
+        }
+
+        // the chain is saved even if the encryption fails,
+        // a message key must never be used twice
+        let result = Self::encrypt_with_chain(&mut chain, &group.id, &user_account.id, data);
+        Self::save_chain(&user_account.id, &group.id, &user_account.id, &chain);
+
+        result
+    }
+
+    /// ratchet our chain forward and encrypt a message with it
+    fn encrypt_with_chain(
+        chain: &mut SenderKeyChain,
+        group_id: &[u8],
+        sender_id: &PeerId,
+        data: &[u8],
+    ) -> Result<messaging::proto::GroupEncrypted, String> {
+        // ratchet the chain
+        let (message_key, next_chain_key) = Self::ratchet(&chain.chain_key);
+        let iteration = chain.iteration;
+        chain.chain_key = next_chain_key;
+        chain.iteration += 1;
+
+        // encrypt message
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&message_key));
+        let nonce = Self::create_nonce(chain.key_id, iteration);
+        let aad = Self::create_associated_data(group_id, sender_id);
+        let encrypted = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: data,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| "sender key encryption failed".to_string())?;
+
+        Ok(messaging::proto::GroupEncrypted {
+            group_id: group_id.to_vec(),
+            key_id: chain.key_id,
+            iteration,
+            data: encrypted,
+        })
+    }
+
+    /// Check if we have the sender key to decrypt a group message
+    pub fn has_key(
+        account_id: &PeerId,
+        sender_id: &PeerId,
+        message: &messaging::proto::GroupEncrypted,
+    ) -> bool {
+        Self::get_chain(account_id, &message.group_id, sender_id, message.key_id).is_some()
+    }
+
+    /// Decrypt a group message with the sender key of the sender
+    pub fn decrypt(
+        account_id: &PeerId,
+        sender_id: &PeerId,
+        message: &messaging::proto::GroupEncrypted,
+    ) -> Result<Vec<u8>, String> {
+        let mut chain =
+            match Self::get_chain(account_id, &message.group_id, sender_id, message.key_id) {
+                Some(chain) => chain,
+                None => return Err("sender key not found".to_string()),
+            };
+        let decrypted = Self::decrypt_with_chain(&mut chain, sender_id, message)?;
+
+        // only save the advanced chain after successful decryption
+        Self::save_chain(account_id, &message.group_id, sender_id, &chain);
+
+        Ok(decrypted)
+    }
+
+    /// decrypt a message with the chain of its sender
+    ///
+    /// The chain is only advanced if the decryption succeeds.
+    fn decrypt_with_chain(
+        chain: &mut SenderKeyChain,
+        sender_id: &PeerId,
+        message: &messaging::proto::GroupEncrypted,
+    ) -> Result<Vec<u8>, String> {
+        if chain.key_id != message.key_id {
+            return Err("sender key not found".to_string());
+        }
+        let mut next_chain = chain.clone();
+
+        // get message key
+        let message_key = if message.iteration < next_chain.iteration {
+            match next_chain.skipped.remove(&message.iteration) {
+                Some(key) => key,
+                None => return Err("message key already used".to_string()),
+            }
+        } else {
+            if message.iteration - next_chain.iteration > MAX_SKIP {
+                return Err("too many skipped messages".to_string());
+            }
+
+            // ratchet forward & keep the skipped keys
+            let mut message_key = Vec::new();
+            while next_chain.iteration <= message.iteration {
+                let (key, next_chain_key) = Self::ratchet(&next_chain.chain_key);
+                if next_chain.iteration == message.iteration {
+                    message_key = key;
+                } else {
+                    next_chain.skipped.insert(next_chain.iteration, key);
+                }
+                next_chain.chain_key = next_chain_key;
+                next_chain.iteration += 1;
+            }
+            while next_chain.skipped.len() > MAX_SKIP as usize {
+                next_chain.skipped.pop_first();
+            }
+            message_key
+        };
+
+        // decrypt message
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&message_key));
+        let nonce = Self::create_nonce(message.key_id, message.iteration);
+        let aad = Self::create_associated_data(&message.group_id, sender_id);
+        let decrypted = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &message.data,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| "sender key decryption failed".to_string())?;
+
+        *chain = next_chain;
+
+        Ok(decrypted)
+    }
+
+    /// send our sender key to a group member
+    fn send_distribution(
+        user_account: &UserAccount,
+        receiver: &PeerId,
+        group_id: &[u8],
+        chain: &SenderKeyChain,
+    ) {
+        let proto_message = super::proto_net::GroupContainer {
+            message: Some(super::proto_net::group_container::Message::SenderKey(
+                super::proto_net::SenderKeyDistribution {
+                    group_id: group_id.to_vec(),
+                    key_id: chain.key_id,
+                    chain_key: chain.chain_key.clone(),
+                    iteration: chain.iteration,
+                },
+            )),
+        };
+
+        Group::send_notify_message(user_account, receiver, proto_message.encode_to_vec());
+    }
+
+    /// process a sender key distribution from network
+    pub fn on_distribution(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        distribution: &super::proto_net::SenderKeyDistribution,
+    ) -> Result<(), String> {
+        // check group membership
+        let group =
+            match GroupStorage::get_group(account_id.to_owned(), distribution.group_id.clone()) {
+                Some(group) => group,
+                None => return Err("group not found".to_string()),
+            };
+        if !group.are_members(&account_id.to_bytes(), &sender_id.to_bytes()) {
+            return Err("sender is not member of this group".to_string());
+        }
+        if distribution.chain_key.len() != 32 {
+            return Err("invalid sender key".to_string());
+        }
+
+        // a repeated distribution must not reset the chain
+        if Self::get_chain(account_id, &group.id, sender_id, distribution.key_id).is_some() {
+            Self::process_pending(account_id, &group.id, sender_id, distribution.key_id);
+            return Ok(());
+        }
+
+        let chain = SenderKeyChain {
+            key_id: distribution.key_id,
+            chain_key: distribution.chain_key.clone(),
+            iteration: distribution.iteration,
+            skipped: BTreeMap::new(),
+            distributed_to: BTreeSet::new(),
+        };
+        Self::save_chain(account_id, &group.id, sender_id, &chain);
+
+        // only keep the previous key for messages still on their way
+        if distribution.key_id > 1 {
+            Self::remove_chains_before(account_id, &group.id, sender_id, distribution.key_id - 1);
+        }
+
+        // decrypt the messages waiting for this key
+        Self::process_pending(account_id, &group.id, sender_id, distribution.key_id);
+
+        Ok(())
+    }
+
+    /// Rotate our sender key of a group
+    ///
+    /// The new key is distributed to the remaining members
+    /// with the next group message.
+    pub fn rotate(account_id: &PeerId, group_id: &[u8]) {
+        let key_id = match Self::get_current(account_id, group_id, account_id) {
+            Some(chain) => chain.key_id + 1,
+            None => return,
+        };
+
+        Self::save_chain(account_id, group_id, account_id, &Self::new_chain(key_id));
+        Self::remove_chains_before(account_id, group_id, account_id, key_id);
+
+        log::trace!(
+            "rotated sender key of group {} to key id {}",
+            bs58::encode(group_id).into_string(),
+            key_id
+        );
+    }
+
+    /// remove all sender keys of a member
+    pub fn remove_member(account_id: &PeerId, group_id: &[u8], user_id: &PeerId) {
+        Self::remove_chains_before(account_id, group_id, user_id, u32::MAX);
+        if let Err(e) = GroupStorage::get_db_ref(account_id.to_owned())
+            .sender_keys
+            .remove(Self::create_key(group_id, user_id, u32::MAX))
+        {
+            log::error!("Error removing sender key: {}", e);
+        }
+    }
+
+    /// remove all sender keys and pending messages of a group
+    pub fn remove_group(account_id: &PeerId, group_id: &[u8]) {
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+
+        for tree in [&db_ref.sender_keys, &db_ref.sender_key_pending] {
+            for (key, _) in tree.scan_prefix(group_id).flatten() {
+                if let Err(e) = tree.remove(key) {
+                    log::error!("Error removing sender key: {}", e);
+                }
+            }
+            if let Err(e) = tree.flush() {
+                log::error!("Error sender keys flush: {}", e);
+            }
+        }
+    }
+
+    /// Save a group message, for which we don't have the sender key yet
+    ///
+    /// The message is processed once the sender key arrives.
+    pub fn save_pending(
+        account_id: &PeerId,
+        sender_id: &PeerId,
+        message: &messaging::proto::GroupEncrypted,
+        container: &messaging::proto::Container,
+    ) {
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+
+        let mut key = Self::create_key(&message.group_id, sender_id, message.key_id);
+        key.extend_from_slice(&container.signature);
+
+        let container_bytes = bincode::serialize(container).unwrap();
+        if let Err(e) = db_ref.sender_key_pending.insert(key, container_bytes) {
+            log::error!("Error saving pending group message: {}", e);
+        }
+        if let Err(e) = db_ref.sender_key_pending.flush() {
+            log::error!("Error pending group messages flush: {}", e);
+        }
+    }
+
+    /// process all pending messages of a sender key
+    fn process_pending(account_id: &PeerId, group_id: &[u8], sender_id: &PeerId, key_id: u32) {
+        let user_account = match UserAccounts::get_by_id(account_id.to_owned()) {
+            Some(user_account) => user_account,
+            None => return,
+        };
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+
+        let prefix = Self::create_key(group_id, sender_id, key_id);
+        let mut containers = Vec::new();
+        for (key, container_bytes) in db_ref.sender_key_pending.scan_prefix(prefix).flatten() {
+            if let Err(e) = db_ref.sender_key_pending.remove(key) {
+                log::error!("Error removing pending group message: {}", e);
+            }
+            match bincode::deserialize::<messaging::proto::Container>(&container_bytes) {
+                Ok(container) => containers.push(container),
+                Err(e) => log::error!("Error decoding pending group message: {}", e),
+            }
+        }
+        if let Err(e) = db_ref.sender_key_pending.flush() {
+            log::error!("Error pending group messages flush: {}", e);
+        }
+
+        for container in containers {
+            MessagingProcess::process_received_message(user_account.clone(), container);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUP_ID: &[u8] = b"group-id";
+
+    /// the chain a member stores from our sender key distribution
+    fn distributed(chain: &SenderKeyChain) -> SenderKeyChain {
+        SenderKeyChain {
+            key_id: chain.key_id,
+            chain_key: chain.chain_key.clone(),
+            iteration: chain.iteration,
+            skipped: BTreeMap::new(),
+            distributed_to: BTreeSet::new(),
+        }
+    }
+
+    fn encrypt(
+        chain: &mut SenderKeyChain,
+        sender_id: &PeerId,
+        count: usize,
+    ) -> Vec<messaging::proto::GroupEncrypted> {
+        (0..count)
+            .map(|i| {
+                SenderKeys::encrypt_with_chain(chain, GROUP_ID, sender_id, &[i as u8; 16]).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let sender_id = PeerId::random();
+        let mut sender = SenderKeys::new_chain(0);
+        let mut receiver = distributed(&sender);
+
+        for (i, message) in encrypt(&mut sender, &sender_id, 3).iter().enumerate() {
+            assert_eq!(message.iteration, i as u32);
+            assert_eq!(
+                SenderKeys::decrypt_with_chain(&mut receiver, &sender_id, message).unwrap(),
+                vec![i as u8; 16]
+            );
+        }
+        assert_eq!(receiver.chain_key, sender.chain_key);
+        assert!(receiver.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_out_of_order() {
+        let sender_id = PeerId::random();
+        let mut sender = SenderKeys::new_chain(0);
+        let mut receiver = distributed(&sender);
+        let messages = encrypt(&mut sender, &sender_id, 4);
+
+        for i in [3, 0, 2] {
+            assert_eq!(
+                SenderKeys::decrypt_with_chain(&mut receiver, &sender_id, &messages[i]).unwrap(),
+                vec![i as u8; 16]
+            );
+        }
+        assert_eq!(receiver.skipped.keys().collect::<Vec<_>>(), vec![&1]);
+
+        // a message key is only used once
+        assert!(SenderKeys::decrypt_with_chain(&mut receiver, &sender_id, &messages[2]).is_err());
+        assert!(SenderKeys::decrypt_with_chain(&mut receiver, &sender_id, &messages[1]).is_ok());
+        assert!(receiver.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_failed_decryption_keeps_chain() {
+        let sender_id = PeerId::random();
+        let mut sender = SenderKeys::new_chain(0);
+        let mut receiver = distributed(&sender);
+        let mut messages = encrypt(&mut sender, &sender_id, 2);
+
+        // wrong sender
+        assert!(
+            SenderKeys::decrypt_with_chain(&mut receiver, &PeerId::random(), &messages[1])
+                .is_err()
+        );
+        // tampered message
+        messages[1].data[0] ^= 1;
+        assert!(SenderKeys::decrypt_with_chain(&mut receiver, &sender_id, &messages[1]).is_err());
+        // too many skipped messages
+        let mut far = messages[0].clone();
+        far.iteration = MAX_SKIP + 1;
+        assert!(SenderKeys::decrypt_with_chain(&mut receiver, &sender_id, &far).is_err());
+
+        assert_eq!(receiver.iteration, 0);
+        assert!(SenderKeys::decrypt_with_chain(&mut receiver, &sender_id, &messages[0]).is_ok());
+    }
+
+    #[test]
+    fn test_rotation_on_member_removal() {
+        let sender_id = PeerId::random();
+        let mut sender = SenderKeys::new_chain(0);
+        let mut removed_member = distributed(&sender);
+        let mut remaining_member = distributed(&sender);
+        let message = &encrypt(&mut sender, &sender_id, 1)[0];
+        assert!(SenderKeys::decrypt_with_chain(&mut removed_member, &sender_id, message).is_ok());
+        assert!(SenderKeys::decrypt_with_chain(&mut remaining_member, &sender_id, message).is_ok());
+
+        // the sender rotates its key and only distributes it to the remaining member
+        let mut sender = SenderKeys::new_chain(sender.key_id + 1);
+        let mut remaining_member = distributed(&sender);
+        let message = &encrypt(&mut sender, &sender_id, 1)[0];
+        assert_eq!(message.key_id, 1);
+
+        assert!(SenderKeys::decrypt_with_chain(&mut removed_member, &sender_id, message).is_err());
+        // the old chain can't decrypt the new key id, even if the key id is forged
+        let mut forged = message.clone();
+        forged.key_id = 0;
+        assert!(SenderKeys::decrypt_with_chain(&mut removed_member, &sender_id, &forged).is_err());
+
+        assert!(SenderKeys::decrypt_with_chain(&mut remaining_member, &sender_id, message).is_ok());
+    }
+}
@@ -13,6 +13,7 @@ use std::sync::RwLock;
 
 use super::{Group, GroupInvited};
 use crate::storage::database::DataBase;
+use crate::storage::encryption::EncryptedTree;
 
 /// mutable state of all user groups
 pub static GROUPSTORAGE: InitCell<RwLock<GroupStorage>> = InitCell::new();
@@ -22,10 +23,16 @@ pub static GROUPSTORAGE: InitCell<RwLock<GroupStorage>> = InitCell::new();
 pub struct GroupAccountDb {
     /// group DB reference
     /// bincode of `Group`
-    pub groups: sled::Tree,
+    pub groups: EncryptedTree,
     /// invited DB ref
     /// bincode of `GroupInvited`
-    pub invited: sled::Tree,
+    pub invited: EncryptedTree,
+    /// sender keys DB ref
+    /// bincode of `SenderKeyChain`
+    pub sender_keys: EncryptedTree,
+    /// group messages waiting for a sender key
+    /// bincode of messaging `Container`
+    pub sender_key_pending: EncryptedTree,
 }
 
 /// qaul Chat Conversation Storage
@@ -53,10 +60,7 @@ impl GroupStorage {
 
             // check if user account ID is in map
             if let Some(group_account_db) = group_storage.db_ref.get(&account_id.to_bytes()) {
-                return GroupAccountDb {
-                    groups: group_account_db.groups.clone(),
-                    invited: group_account_db.invited.clone(),
-                };
+                return group_account_db.clone();
             }
         }
 
@@ -64,10 +68,7 @@ impl GroupStorage {
         let group_account_db = Self::create_groupaccountdb(account_id);
 
         // return group_account_db structure
-        GroupAccountDb {
-            groups: group_account_db.groups.clone(),
-            invited: group_account_db.invited.clone(),
-        }
+        group_account_db
     }
 
     /// create group account db entry when it does not exist
@@ -76,10 +77,17 @@ impl GroupStorage {
         let db = DataBase::get_user_db(account_id);
 
         // open trees
-        let groups: sled::Tree = db.open_tree("groups").unwrap();
-        let invited: sled::Tree = db.open_tree("invited").unwrap();
-
-        let group_account_db = GroupAccountDb { groups, invited };
+        let groups = EncryptedTree::open(&db, "groups");
+        let invited = EncryptedTree::open(&db, "invited");
+        let sender_keys = EncryptedTree::open(&db, "sender_keys");
+        let sender_key_pending = EncryptedTree::open(&db, "sender_key_pending");
+
+        let group_account_db = GroupAccountDb {
+            groups,
+            invited,
+            sender_keys,
+            sender_key_pending,
+        };
 
         // get group storage for writing
         let mut group_storage = GROUPSTORAGE.get().write().unwrap();
@@ -289,6 +297,9 @@ impl GroupStorage {
         
         // remove all chat messages associated with this group
         crate::services::chat::ChatStorage::delete_all_group_messages(&account_id, group_id);
+
+        // remove the sender keys of this group
+        super::SenderKeys::remove_group(&account_id, group_id);
         
         // flush groups tree to disk
         if let Err(e) = db_ref.groups.flush() {
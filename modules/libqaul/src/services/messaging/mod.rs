@@ -11,7 +11,7 @@ use prost::Message;
 use serde::{Deserialize, Serialize};
 use sled;
 use state::InitCell;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::RwLock;
 
 #[cfg(emulate)]
@@ -22,10 +22,12 @@ pub mod retransmit;
 
 use super::chat::{ChatFile, ChatStorage};
 use super::crypto::Crypto;
+use super::group::{sender_key::SenderKeys, Group};
 use crate::connections::ConnectionModule;
 use crate::node::user_accounts::{UserAccount, UserAccounts};
 use crate::router::table::RoutingTable;
 use crate::storage::database::DataBase;
+use crate::storage::encryption::EncryptedTree;
 use crate::utilities::timestamp::Timestamp;
 use process::MessagingProcess;
 use qaul_messaging::QaulMessagingReceived;
@@ -104,7 +106,7 @@ pub struct UnConfirmedMessages {
     /// signature => UnConfirmedMessage
     ///
     /// value: bincode of `UnConfirmedMessage`
-    pub unconfirmed: sled::Tree,
+    pub unconfirmed: EncryptedTree,
 }
 
 /// Qaul Messaging Structure
@@ -139,7 +141,7 @@ impl Messaging {
         let db = DataBase::get_node_db();
 
         // open trees
-        let unconfirmed: sled::Tree = db.open_tree("unconfirmed").unwrap();
+        let unconfirmed = EncryptedTree::open(&db, "unconfirmed");
         let unconfirmed_messages = UnConfirmedMessages { unconfirmed };
         UNCONFIRMED.set(RwLock::new(unconfirmed_messages));
     }
@@ -196,8 +198,14 @@ impl Messaging {
 
         let unconfirmed = UNCONFIRMED.get().write().unwrap();
 
+        // group messages are saved per receiver
+        let mut key = signature.to_owned();
+        if let Ok(false) = unconfirmed.unconfirmed.contains_key(signature) {
+            key = Self::create_group_unconfirmed_key(signature, &sender_id);
+        }
+
         // check and remove unconfirmed from DB
-        match unconfirmed.unconfirmed.remove(signature) {
+        match unconfirmed.unconfirmed.remove(key) {
             Ok(v) => {
                 if let Err(e) = unconfirmed.unconfirmed.flush() {
                     log::error!("Error unconfirmed table flush: {}", e);
@@ -272,6 +280,17 @@ impl Messaging {
         }
     }
 
+    /// create the unconfirmed table key of a group message
+    ///
+    /// A group message is sent once, but confirmed by every receiver.
+    ///
+    /// {signature}{receiver id}
+    fn create_group_unconfirmed_key(signature: &Vec<u8>, receiver: &PeerId) -> Vec<u8> {
+        let mut key = signature.clone();
+        key.extend_from_slice(&receiver.to_bytes());
+        key
+    }
+
     fn on_scheduled_message(signature: &Vec<u8>) {
         let unconfirmed = UNCONFIRMED.get().write().unwrap();
         if !unconfirmed.unconfirmed.contains_key(signature).unwrap() {
@@ -404,6 +423,7 @@ impl Messaging {
             let container = proto::Container {
                 signature: signature.clone(),
                 envelope: Some(envelope),
+                recipients: Vec::new(),
             };
 
             // in common message case, save into unconfirmed table
@@ -434,6 +454,125 @@ impl Messaging {
         }
     }
 
+    /// pack, sign and schedule a group message for sending
+    ///
+    /// The data is encrypted once with the sender key of the group
+    /// and sent to all recipients in one container.
+    /// The container is only split by the next hop of the recipients,
+    /// each receiver confirms the message separately.
+    ///
+    /// The function returns the message signature on success,
+    /// otherwise an error message string.
+    pub fn pack_and_send_group_message(
+        user_account: &UserAccount,
+        group: &Group,
+        recipients: &Vec<PeerId>,
+        data: Vec<u8>,
+        message_id: &Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        log::trace!(
+            "pack_and_send_group_message to group {}",
+            bs58::encode(&group.id).into_string()
+        );
+
+        // encrypt data
+        let group_encrypted = SenderKeys::encrypt(user_account, group, &data)?;
+
+        let envelop_payload = proto::EnvelopPayload {
+            payload: Some(proto::envelop_payload::Payload::GroupEncrypted(
+                group_encrypted,
+            )),
+        };
+
+        // create envelope
+        let envelope = proto::Envelope {
+            sender_id: user_account.id.to_bytes(),
+            receiver_id: group.id.clone(),
+            payload: envelop_payload.encode_to_vec(),
+        };
+
+        // sign message
+        let signature = match user_account.keys.sign(&envelope.encode_to_vec()) {
+            Ok(signature) => signature,
+            Err(_) => return Err("messaging signing error".to_string()),
+        };
+
+        // save into unconfirmed table for every receiver
+        for receiver in recipients {
+            let container = proto::Container {
+                signature: signature.clone(),
+                envelope: Some(envelope.clone()),
+                recipients: vec![receiver.to_bytes()],
+            };
+
+            let new_entry = UnConfirmedMessage {
+                receiver_id: receiver.to_bytes(),
+                container: container.encode_to_vec(),
+                last_sent: Timestamp::get_timestamp(),
+                message_type: MessagingServiceType::Chat,
+                message_id: message_id.to_owned(),
+                retry: 1,
+                scheduled: false,
+                scheduled_dtn: false,
+                is_dtn: false,
+            };
+            let unconfirmed = UNCONFIRMED.get().write().unwrap();
+            if let Err(e) = unconfirmed.unconfirmed.insert(
+                Self::create_group_unconfirmed_key(&signature, receiver),
+                bincode::serialize(&new_entry).unwrap(),
+            ) {
+                log::error!("{}", e);
+            }
+        }
+        if let Err(e) = UNCONFIRMED.get().read().unwrap().unconfirmed.flush() {
+            log::error!("Error unconfirmed table flush: {}", e);
+        }
+
+        // schedule message for sending
+        let container = proto::Container {
+            signature: signature.clone(),
+            envelope: Some(envelope),
+            recipients: Vec::new(),
+        };
+        Self::schedule_group_container(container, recipients, false);
+
+        Ok(signature)
+    }
+
+    /// schedule a group message container
+    ///
+    /// The recipients are grouped by their next hop,
+    /// one container is scheduled per next hop.
+    /// Recipients without a route get their own container, which
+    /// the sender schedules via its DTN storage node.
+    fn schedule_group_container(
+        container: proto::Container,
+        recipients: &Vec<PeerId>,
+        is_forward: bool,
+    ) {
+        let mut next_hops: HashMap<PeerId, Vec<PeerId>> = HashMap::new();
+        for receiver in recipients {
+            match RoutingTable::get_route_to_user(receiver.to_owned()) {
+                Some(route) => next_hops.entry(route.node).or_default().push(*receiver),
+                None if is_forward => {
+                    log::trace!("no route to group member {}", receiver.to_base58())
+                }
+                None => {
+                    let mut dtn_container = container.clone();
+                    dtn_container.recipients = vec![receiver.to_bytes()];
+                    Self::schedule_message(*receiver, dtn_container, true, false, false, false);
+                }
+            }
+        }
+
+        for (_node, receivers) in next_hops {
+            let mut hop_container = container.clone();
+            hop_container.recipients = receivers.iter().map(|r| r.to_bytes()).collect();
+
+            Self::schedule_message(receivers[0], hop_container, false, is_forward, false, false);
+        }
+    }
+
     /// pack, sign and schedule a message for sending
     pub fn send_dtn_message(
         user_account: &UserAccount,
@@ -457,6 +596,7 @@ impl Messaging {
             let container_dtn = proto::Container {
                 signature: signature_dtn.clone(),
                 envelope: Some(envelope_dtn),
+                recipients: Vec::new(),
             };
 
             // in common message case, save into unconfirmed table
@@ -539,7 +679,18 @@ impl Messaging {
             // check for route
             if let Some(route) = RoutingTable::get_route_to_user(message.receiver) {
                 // update unconfirmed table set scheduled flag.
-                Self::on_scheduled_message(&message.container.signature);
+                if message.container.recipients.is_empty() {
+                    Self::on_scheduled_message(&message.container.signature);
+                } else {
+                    for recipient in &message.container.recipients {
+                        if let Ok(recipient_id) = PeerId::from_bytes(recipient) {
+                            Self::on_scheduled_message(&Self::create_group_unconfirmed_key(
+                                &message.container.signature,
+                                &recipient_id,
+                            ));
+                        }
+                    }
+                }
 
                 // create binary message
                 let data = message.container.encode_to_vec();
@@ -548,10 +699,13 @@ impl Messaging {
                 return Some((route.node, route.module, data));
             } else {
                 // user is offline we schedule through DTN service
+                //
+                // group messages are only stored for a single recipient
                 if !message.is_forward
                     && !message.is_dtn
                     && !message.scheduled_dtn
                     && message.is_common
+                    && message.container.recipients.len() <= 1
                 {
                     // get storage node id
                     if let Ok(my_user_id) =
@@ -570,7 +724,18 @@ impl Messaging {
                                 } else {
                                     log::error!("DTN scheduled...");
                                     // update unconfirmed table
-                                    Self::on_scheduled_as_dtn_message(&message.container.signature);
+                                    if message.container.recipients.is_empty() {
+                                        Self::on_scheduled_as_dtn_message(
+                                            &message.container.signature,
+                                        );
+                                    } else {
+                                        Self::on_scheduled_as_dtn_message(
+                                            &Self::create_group_unconfirmed_key(
+                                                &message.container.signature,
+                                                &message.receiver,
+                                            ),
+                                        );
+                                    }
                                 }
                             }
                         }
@@ -634,6 +799,12 @@ impl Messaging {
         // decode message container
         match proto::Container::decode(&received.data[..]) {
             Ok(container) => {
+                // group message
+                if !container.recipients.is_empty() {
+                    Self::received_group_message(container);
+                    return;
+                }
+
                 if let Some(envelope) = container.envelope.clone() {
                     match PeerId::from_bytes(&envelope.receiver_id) {
                         Ok(receiver_id) => {
@@ -668,4 +839,33 @@ impl Messaging {
             Err(e) => log::error!("Messaging container decoding error: {}", e),
         }
     }
+
+    /// process a received group message
+    ///
+    /// The message is processed for all local user accounts
+    /// in the recipients list, and forwarded to the others.
+    fn received_group_message(container: proto::Container) {
+        let mut forward: Vec<PeerId> = Vec::new();
+        for recipient in &container.recipients {
+            match PeerId::from_bytes(recipient) {
+                Ok(receiver_id) => match UserAccounts::get_by_id(receiver_id) {
+                    Some(user_account) => {
+                        let mut local_container = container.clone();
+                        local_container.recipients = vec![recipient.clone()];
+                        MessagingProcess::process_received_message(user_account, local_container);
+                    }
+                    None => forward.push(receiver_id),
+                },
+                Err(e) => log::error!(
+                    "invalid recipient of group message {}: {}",
+                    bs58::encode(&container.signature).into_string(),
+                    e
+                ),
+            }
+        }
+
+        if !forward.is_empty() {
+            Self::schedule_group_container(container, &forward, true);
+        }
+    }
 }
@@ -8,6 +8,8 @@
 
 use libp2p::PeerId;
 use prost::Message;
+use std::collections::VecDeque;
+use std::sync::Mutex;
 
 use crate::node::user_accounts::UserAccount;
 use crate::router;
@@ -15,10 +17,18 @@ use crate::services::chat::{self, rpc_proto, ChatFile, ChatStorage};
 use crate::services::crypto::sessionmanager::CryptoSessionManager;
 use crate::services::crypto::Crypto;
 use crate::services::dtn;
-use crate::services::group::{self, Group, GroupId};
+use crate::services::group::{self, sender_key::SenderKeys, Group, GroupId};
 use crate::services::rtc;
 use crate::utilities::timestamp::Timestamp;
 
+/// number of group message signatures remembered per node
+const GROUP_SEEN_MAX: usize = 1000;
+
+/// signatures of the processed group messages
+///
+/// key: {user account id}{signature}
+static GROUP_SEEN: Mutex<VecDeque<Vec<u8>>> = Mutex::new(VecDeque::new());
+
 /// Qaul Messaging Structure
 pub struct MessagingProcess {}
 
@@ -220,11 +230,67 @@ impl MessagingProcess {
         }
     }
 
+    /// process a group message encrypted with the sender key of the sender
+    ///
+    /// Messages for which the sender key has not arrived yet,
+    /// are saved and processed once the key arrives.
+    fn on_group_encrypted_message(
+        sender_id: &PeerId,
+        user_account: UserAccount,
+        group_encrypted: &super::proto::GroupEncrypted,
+        container: &super::proto::Container,
+    ) {
+        // the same group message can arrive several times,
+        // only confirm it again
+        let mut seen_key = user_account.id.to_bytes();
+        seen_key.extend_from_slice(&container.signature);
+        if GROUP_SEEN.lock().unwrap().contains(&seen_key) {
+            if let Err(e) = super::Messaging::send_confirmation(
+                &user_account.id,
+                sender_id,
+                &container.signature,
+            ) {
+                log::error!("send confirmation failed {}", e);
+            }
+            return;
+        }
+
+        // wait for the sender key
+        if !SenderKeys::has_key(&user_account.id, sender_id, group_encrypted) {
+            log::trace!(
+                "sender key {} of {} missing, save group message",
+                group_encrypted.key_id,
+                sender_id.to_base58()
+            );
+            SenderKeys::save_pending(&user_account.id, sender_id, group_encrypted, container);
+            return;
+        }
+
+        // decrypt data
+        let decrypted = match SenderKeys::decrypt(&user_account.id, sender_id, group_encrypted) {
+            Ok(decrypted) => decrypted,
+            Err(e) => {
+                log::error!("group message decryption error: {}", e);
+                return;
+            }
+        };
+
+        {
+            let mut seen = GROUP_SEEN.lock().unwrap();
+            if seen.len() >= GROUP_SEEN_MAX {
+                seen.pop_front();
+            }
+            seen.push_back(seen_key);
+        }
+
+        Self::on_decrypted_message(sender_id, user_account, &decrypted, &container.signature);
+    }
+
     /// process received message
     pub fn process_received_message(user_account: UserAccount, container: super::proto::Container) {
         // check envelop
         let envelope;
-        match container.envelope {
+        match container.envelope.clone() {
             Some(v) => envelope = v,
             _ => {
                 log::error!("No Envelope in Message Container");
@@ -266,18 +332,6 @@ impl MessagingProcess {
             return;
         }
 
-        // check receiver_id
-        let receiver_id;
-        match PeerId::from_bytes(&envelope.receiver_id) {
-            Ok(v) => {
-                receiver_id = v;
-            }
-            _ => {
-                log::error!("Error retrieving PeerId");
-                return;
-            }
-        }
-
         match super::proto::EnvelopPayload::decode(&envelope.payload[..]) {
             Ok(payload) => {
                 match payload.payload {
@@ -304,7 +358,29 @@ impl MessagingProcess {
                             &container.signature,
                         );
                     }
+                    Some(super::proto::envelop_payload::Payload::GroupEncrypted(
+                        ref group_encrypted,
+                    )) => {
+                        Self::on_group_encrypted_message(
+                            &sender_id,
+                            user_account,
+                            group_encrypted,
+                            &container,
+                        );
+                    }
                     Some(super::proto::envelop_payload::Payload::Dtn(dtn)) => {
+                        // check receiver_id
+                        let receiver_id;
+                        match PeerId::from_bytes(&envelope.receiver_id) {
+                            Ok(v) => {
+                                receiver_id = v;
+                            }
+                            _ => {
+                                log::error!("Error retrieving PeerId");
+                                return;
+                            }
+                        }
+
                         dtn::Dtn::net(&receiver_id, &sender_id, &container.signature, &dtn);
                     }
                     _ => {
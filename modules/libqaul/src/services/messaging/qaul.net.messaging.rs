@@ -12,6 +12,16 @@ pub struct Container {
     /// Message envelope
     #[prost(message, optional, tag = "2")]
     pub envelope: ::core::option::Option<Envelope>,
+    /// group message recipients
+    ///
+    /// Group messages are encrypted once with the sender key
+    /// and addressed to the group.
+    /// This field contains the user ID's of the group members
+    /// this copy of the message shall be delivered to.
+    /// It is not signed, and is split by the relaying nodes
+    /// according to the route of each recipient.
+    #[prost(bytes = "vec", repeated, tag = "3")]
+    pub recipients: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
 /// message envelop with sender and receiver
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -30,7 +40,7 @@ pub struct Envelope {
 /// envelop payload
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EnvelopPayload {
-    #[prost(oneof = "envelop_payload::Payload", tags = "1, 2")]
+    #[prost(oneof = "envelop_payload::Payload", tags = "1, 2, 3")]
     pub payload: ::core::option::Option<envelop_payload::Payload>,
 }
 /// Nested message and enum types in `EnvelopPayload`.
@@ -43,6 +53,9 @@ pub mod envelop_payload {
         /// DTN message
         #[prost(bytes, tag = "2")]
         Dtn(::prost::alloc::vec::Vec<u8>),
+        /// group message encrypted with the sender key
+        #[prost(message, tag = "3")]
+        GroupEncrypted(super::GroupEncrypted),
     }
 }
 /// encrypted message data
@@ -59,6 +72,12 @@ pub struct Encrypted {
     /// of maximally 64KB each.
     #[prost(message, repeated, tag = "3")]
     pub data: ::prost::alloc::vec::Vec<Data>,
+    /// the session keys are ratcheted forward
+    ///
+    /// This flag is set on the handshake messages
+    /// to announce the support of the key ratchet.
+    #[prost(bool, tag = "4")]
+    pub ratchet: bool,
 }
 /// encrypted message data
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -76,6 +95,28 @@ pub struct Data {
     #[prost(bytes = "vec", tag = "2")]
     pub data: ::prost::alloc::vec::Vec<u8>,
 }
+/// group message data
+///
+/// The message is encrypted once with the sender key
+/// of the sending group member.
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct GroupEncrypted {
+    /// group id
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// sender key id
+    ///
+    /// the sender key is rotated when a member
+    /// leaves the group.
+    #[prost(uint32, tag = "2")]
+    pub key_id: u32,
+    /// iteration of the sender key chain
+    #[prost(uint32, tag = "3")]
+    pub iteration: u32,
+    /// encrypted message data
+    #[prost(bytes = "vec", tag = "4")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
 /// messaging unified message
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct Messaging {
@@ -4,6 +4,16 @@
 //! # Qaul Real Time Communication (RTC) Module
 //!
 //! The RTC module manages and forwards the session requests
+//!
+//! Direct calls are negotiated between two users.
+//! Group calls are hosted by one group member, whose node runs
+//! the SFU, see `rtc_group.rs`.
+//! ICE candidates and ICE restarts are exchanged via qaul,
+//! see `rtc_ice.rs`.
+//! Screens and files are shared within direct calls,
+//! see `rtc_share.rs`.
+//! Recordings of calls are announced to all participants,
+//! see `rtc_recording.rs`.
 
 use libp2p::PeerId;
 
@@ -14,16 +24,24 @@ use state::InitCell;
 use std::collections::BTreeMap;
 use std::sync::RwLock;
 
+pub mod rtc_group;
+pub mod rtc_ice;
 pub mod rtc_managing;
 pub mod rtc_messaging;
+pub mod rtc_recording;
+pub mod rtc_share;
 
 use super::chat::Chat;
 use super::group;
 use super::group::group_id::GroupId;
 use super::messaging::{proto, Messaging, MessagingServiceType};
 use crate::utilities::timestamp;
+use rtc_group::RtcGroup;
+use rtc_ice::RtcIce;
 use rtc_managing::RtcManaging;
 use rtc_messaging::RtcMessaging;
+use rtc_recording::RtcRecording;
+use rtc_share::RtcShare;
 
 /// Import protobuf message definition generated by
 /// the rust module prost-build.
@@ -52,6 +70,29 @@ pub struct RtcSession {
     pub sdp_offer: Option<String>,
     // SDP answer from callee (str0m JSON serialized SdpAnswer)
     pub sdp_answer: Option<String>,
+    // user id of the group call host (empty for direct calls)
+    pub host_id: Vec<u8>,
+    // participants of a group call
+    pub participants: BTreeMap<Vec<u8>, RtcParticipant>,
+    // ICE candidates received from the other side
+    pub remote_candidates: Vec<proto_net::RtcIceCandidate>,
+    // we sent an ICE restart offer and wait for the answer
+    pub restarting: bool,
+    // user ids of the users recording the call
+    pub recorders: Vec<Vec<u8>>,
+}
+
+// Structure of a group call participant
+#[derive(Clone)]
+pub struct RtcParticipant {
+    // user id
+    pub user_id: Vec<u8>,
+    // audio is muted
+    pub audio_muted: bool,
+    // video is muted
+    pub video_muted: bool,
+    // joined at
+    pub joined_at: u64,
 }
 
 /// Structure to management for sessions.
@@ -92,9 +133,16 @@ impl Rtc {
     }
 
     /// remove session on the storage
+    ///
+    /// The app is informed that the call has ended.
     pub fn remove_session(session_id: &Vec<u8>) {
-        let mut sessions = RTCSESSIONS.get().write().unwrap();
-        sessions.sessions.remove(session_id);
+        let removed = {
+            let mut sessions = RTCSESSIONS.get().write().unwrap();
+            sessions.sessions.remove(session_id)
+        };
+        if removed.is_some() {
+            RtcShare::on_session_ended(session_id);
+        }
     }
 
     /// Send capsuled group message through messaging service
@@ -161,6 +209,27 @@ impl Rtc {
     pub fn net(sender_id: &PeerId, receiver_id: &PeerId, data: &Vec<u8>) {
         match proto_net::RtcContainer::decode(&data[..]) {
             Ok(messaging) => match messaging.message {
+                Some(proto_net::rtc_container::Message::RtcSessionRequest(session_req))
+                    if !session_req.group_id.is_empty() =>
+                {
+                    RtcGroup::on_session_request(&sender_id, &receiver_id, &session_req);
+                }
+                Some(proto_net::rtc_container::Message::RtcSessionManagement(session_mgr))
+                    if RtcRecording::is_announcement(&session_mgr) =>
+                {
+                    RtcRecording::on_announcement(&sender_id, &receiver_id, &session_mgr);
+                }
+                Some(proto_net::rtc_container::Message::RtcSessionManagement(session_mgr))
+                    if !session_mgr.group_id.is_empty() =>
+                {
+                    RtcGroup::on_session_management(&sender_id, &receiver_id, &session_mgr);
+                }
+                Some(proto_net::rtc_container::Message::RtcGroupState(group_state)) => {
+                    RtcGroup::on_group_state(&sender_id, &receiver_id, &group_state);
+                }
+                Some(proto_net::rtc_container::Message::RtcIceCandidate(candidate)) => {
+                    RtcIce::on_candidate(&sender_id, &receiver_id, &candidate);
+                }
                 Some(proto_net::rtc_container::Message::RtcSessionRequest(session_req)) => {
                     log::error!("on_session_request");
                     rtc_managing::RtcManaging::on_session_request(
@@ -247,6 +316,11 @@ impl Rtc {
                             log::error!("rtc management error {}", error);
                         }
                     }
+                    Some(proto_rpc::rtc_rpc::Message::RtcIceCandidate(candidate)) => {
+                        if let Err(error) = RtcIce::send_candidate(&my_user_id, &candidate) {
+                            log::error!("rtc ICE candidate error {}", error);
+                        }
+                    }
                     Some(proto_rpc::rtc_rpc::Message::RtcOutgoing(session_req)) => {
                         if let Err(error) = RtcMessaging::send_message(&my_user_id, &session_req) {
                             log::error!("rtc message error {}", error);
@@ -3,7 +3,7 @@
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RtcRpc {
     /// message type
-    #[prost(oneof = "rtc_rpc::Message", tags = "1, 2, 3, 4, 5, 6, 7")]
+    #[prost(oneof = "rtc_rpc::Message", tags = "1, 2, 3, 4, 5, 6, 7, 8")]
     pub message: ::core::option::Option<rtc_rpc::Message>,
 }
 /// Nested message and enum types in `RtcRpc`.
@@ -32,6 +32,9 @@ pub mod rtc_rpc {
         /// rtc session list response
         #[prost(message, tag = "7")]
         RtcSessionListResponse(super::RtcSessionListResponse),
+        /// rtc ICE candidate of our side
+        #[prost(message, tag = "8")]
+        RtcIceCandidate(super::RtcIceCandidate),
     }
 }
 /// rtc session request (caller initiates with SDP offer)
@@ -63,12 +66,39 @@ pub struct RtcSessionManagement {
     /// group id
     #[prost(bytes = "vec", tag = "1")]
     pub group_id: ::prost::alloc::vec::Vec<u8>,
-    /// option (1: accept with SDP answer, 2: deny, 3: end)
+    /// option (1: accept with SDP answer, 2: deny, 3: end,
+    /// 6: ICE restart with SDP offer, 7: answer ICE restart with SDP answer,
+    /// 11: start recording, 12: stop recording)
     #[prost(uint32, tag = "2")]
     pub option: u32,
-    /// SDP answer from callee (JSON serialized str0m SdpAnswer, only when option=1)
+    /// SDP answer from callee (JSON serialized str0m SdpAnswer, only when option=1 or option=7)
     #[prost(string, tag = "3")]
     pub sdp_answer: ::prost::alloc::string::String,
+    /// SDP offer of an ICE restart (only when option=6)
+    #[prost(string, tag = "4")]
+    pub sdp_offer: ::prost::alloc::string::String,
+}
+/// rtc ICE candidate for trickle ICE
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RtcIceCandidate {
+    /// group id
+    #[prost(bytes = "vec", tag = "1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// ICE candidate string
+    #[prost(string, tag = "2")]
+    pub candidate: ::prost::alloc::string::String,
+    /// SDP mid
+    #[prost(string, tag = "3")]
+    pub sdp_mid: ::prost::alloc::string::String,
+    /// SDP m-line index
+    #[prost(uint32, tag = "4")]
+    pub sdp_mline_index: u32,
+    /// ICE username fragment of the session
+    #[prost(string, tag = "5")]
+    pub ufrag: ::prost::alloc::string::String,
+    /// all candidates have been sent
+    #[prost(bool, tag = "6")]
+    pub end_of_candidates: bool,
 }
 /// rtc outgoing
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -94,7 +124,7 @@ pub struct RtcIncoming {
 #[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct RtcSessionListRequest {}
 /// rtc session
-#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RtcSession {
     /// group id
     #[prost(bytes = "vec", tag = "1")]
@@ -114,6 +144,36 @@ pub struct RtcSession {
     /// SDP answer
     #[prost(string, tag = "6")]
     pub sdp_answer: ::prost::alloc::string::String,
+    /// user id of the group call host
+    ///
+    /// empty for direct calls
+    #[prost(bytes = "vec", tag = "7")]
+    pub host_id: ::prost::alloc::vec::Vec<u8>,
+    /// participants of a group call
+    #[prost(message, repeated, tag = "8")]
+    pub participants: ::prost::alloc::vec::Vec<RtcParticipant>,
+    /// ICE candidates received from the other side
+    #[prost(message, repeated, tag = "9")]
+    pub remote_candidates: ::prost::alloc::vec::Vec<RtcIceCandidate>,
+    /// user ids of the users recording the call
+    #[prost(bytes = "vec", repeated, tag = "10")]
+    pub recorders: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
+/// rtc group call participant
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RtcParticipant {
+    /// user id
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// audio is muted
+    #[prost(bool, tag = "2")]
+    pub audio_muted: bool,
+    /// video is muted
+    #[prost(bool, tag = "3")]
+    pub video_muted: bool,
+    /// joined at
+    #[prost(uint64, tag = "4")]
+    pub joined_at: u64,
 }
 /// rtc session list response
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -0,0 +1,909 @@
+// Copyright (c) 2022 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # RTC Group Calls
+//!
+//! A group call is hosted by the group member who starts it.
+//! The node of the host runs a str0m SFU, which forwards the media
+//! of every participant to all other participants.
+//!
+//! The signaling runs over qaul:
+//!
+//! * The host announces the call to all group members with a
+//!   `RtcSessionRequest` containing the group id.
+//! * A member joins by sending its SDP offer to the host, the
+//!   host answers with the SDP answer of the SFU.
+//! * The host sends the participants and their mute state to all
+//!   group members with a `RtcGroupState` message.
+//!
+//! The SFU only offers host candidates of the local network,
+//! therefore group calls work in the LAN without any
+//! STUN or TURN service.
+//!
+//! The SFU itself is run by the app, which registers a handler
+//! via `RtcGroup::set_sfu_handler`.
+
+use libp2p::PeerId;
+use prost::Message;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use super::{proto_net, RtcParticipant, RtcSession};
+use crate::connections::events::emit_tauri_event;
+use crate::node::user_accounts::{UserAccount, UserAccounts};
+use crate::services::chat::Chat;
+use crate::services::group::{Group, GroupStorage};
+use crate::services::messaging::{proto, MessagingServiceType};
+use crate::utilities::timestamp::Timestamp;
+
+/// session management option: accept, with SDP answer of the SFU
+const OPTION_ACCEPT: u32 = 1;
+/// session management option: deny
+const OPTION_DENY: u32 = 2;
+/// session management option: end or leave the call
+const OPTION_END: u32 = 3;
+/// session management option: join, with SDP offer of the participant
+const OPTION_JOIN: u32 = 4;
+/// session management option: mute state of the participant
+const OPTION_MUTE: u32 = 5;
+
+/// Request to the SFU of a group call
+pub enum RtcSfuRequest {
+    /// add a participant with its SDP offer
+    ///
+    /// The handler returns the SDP answer.
+    Join {
+        group_id: Vec<u8>,
+        user_id: Vec<u8>,
+        sdp_offer: String,
+    },
+    /// remove a participant
+    Leave { group_id: Vec<u8>, user_id: Vec<u8> },
+    /// add a trickled ICE candidate of a participant
+    Candidate {
+        group_id: Vec<u8>,
+        user_id: Vec<u8>,
+        candidate: String,
+    },
+    /// end the call and stop the SFU
+    End { group_id: Vec<u8> },
+}
+
+/// SFU handler type
+pub type RtcSfuHandler = Arc<dyn Fn(RtcSfuRequest) -> Result<Option<String>, String> + Send + Sync>;
+
+/// SFU handler, registered by the app
+static SFU_HANDLER: RwLock<Option<RtcSfuHandler>> = RwLock::new(None);
+
+/// RTC Group Calls
+pub struct RtcGroup {}
+
+impl RtcGroup {
+    /// Set the handler running the SFU of the group calls we host
+    pub fn set_sfu_handler(handler: RtcSfuHandler) {
+        *SFU_HANDLER.write().unwrap() = Some(handler);
+    }
+
+    /// pass a request to the SFU handler
+    fn sfu(request: RtcSfuRequest) -> Result<Option<String>, String> {
+        match SFU_HANDLER.read().unwrap().as_ref() {
+            Some(handler) => handler(request),
+            None => Err("no SFU available to host group calls".to_string()),
+        }
+    }
+
+    /// Start a group call and announce it to all group members
+    ///
+    /// We are the host of this call.
+    pub fn start(
+        my_user_id: &PeerId,
+        group_id: &Vec<u8>,
+        session_type: u32,
+    ) -> Result<RtcSession, String> {
+        if let Some(_session) = super::Rtc::get_session_from_id(group_id) {
+            return Err("session already exists!".to_string());
+        }
+        let user_account = UserAccounts::get_by_id(*my_user_id)
+            .ok_or_else(|| "user account has problem".to_string())?;
+        let group = Self::get_group(my_user_id, group_id)?;
+
+        let session = RtcSession {
+            user_id: my_user_id.to_bytes(),
+            group_id: group_id.clone(),
+            session_type,
+            created_at: Timestamp::get_timestamp(),
+            state: 3,
+            sdp_offer: None,
+            sdp_answer: None,
+            host_id: my_user_id.to_bytes(),
+            participants: BTreeMap::new(),
+            remote_candidates: Vec::new(),
+            restarting: false,
+            recorders: Vec::new(),
+        };
+        super::Rtc::update_session(session.clone());
+
+        // announce call to all group members
+        let proto_message = proto_net::RtcContainer {
+            message: Some(proto_net::rtc_container::Message::RtcSessionRequest(
+                proto_net::RtcSessionRequest {
+                    session_type,
+                    sdp_offer: String::new(),
+                    group_id: group_id.clone(),
+                    host_id: my_user_id.to_bytes(),
+                },
+            )),
+        };
+        Self::send_to_group(&user_account, &group, proto_message);
+
+        log::info!(
+            "RTC group call started in group {}",
+            bs58::encode(group_id).into_string()
+        );
+
+        Ok(session)
+    }
+
+    /// Join a group call with our SDP offer
+    ///
+    /// If we are the host, the SDP answer of our SFU is returned.
+    /// Otherwise the offer is sent to the host, and the answer
+    /// arrives via the `qaul-rtc-group-answer` event.
+    pub fn join(
+        my_user_id: &PeerId,
+        group_id: &Vec<u8>,
+        sdp_offer: String,
+    ) -> Result<Option<String>, String> {
+        let mut session = super::Rtc::get_session_from_id(group_id)
+            .ok_or_else(|| "session does not exist!".to_string())?;
+        let user_account = UserAccounts::get_by_id(*my_user_id)
+            .ok_or_else(|| "user account has problem".to_string())?;
+
+        // we host the call
+        if session.host_id == my_user_id.to_bytes() {
+            let sdp_answer = Self::sfu(RtcSfuRequest::Join {
+                group_id: group_id.clone(),
+                user_id: my_user_id.to_bytes(),
+                sdp_offer: sdp_offer.clone(),
+            })?;
+
+            session.sdp_offer = Some(sdp_offer);
+            session.sdp_answer = sdp_answer.clone();
+            Self::add_participant(&mut session, my_user_id.to_bytes());
+            super::Rtc::update_session(session.clone());
+            Self::send_group_state(&user_account, &session);
+
+            return Ok(sdp_answer);
+        }
+
+        // send the offer to the host
+        session.state = 1;
+        session.sdp_offer = Some(sdp_offer.clone());
+        super::Rtc::update_session(session.clone());
+
+        Self::send_management(
+            &user_account,
+            &session,
+            proto_net::RtcSessionManagement {
+                option: OPTION_JOIN,
+                group_id: group_id.clone(),
+                sdp_offer,
+                ..Default::default()
+            },
+        )?;
+
+        Ok(None)
+    }
+
+    /// Set our mute state in a group call
+    pub fn set_muted(
+        my_user_id: &PeerId,
+        group_id: &Vec<u8>,
+        audio_muted: bool,
+        video_muted: bool,
+    ) -> Result<(), String> {
+        let mut session = super::Rtc::get_session_from_id(group_id)
+            .ok_or_else(|| "session does not exist!".to_string())?;
+        let user_account = UserAccounts::get_by_id(*my_user_id)
+            .ok_or_else(|| "user account has problem".to_string())?;
+
+        if !Self::set_participant_muted(
+            &mut session,
+            &my_user_id.to_bytes(),
+            audio_muted,
+            video_muted,
+        ) {
+            return Err("you are not participating in this call".to_string());
+        }
+        super::Rtc::update_session(session.clone());
+
+        if session.host_id == my_user_id.to_bytes() {
+            Self::send_group_state(&user_account, &session);
+            return Ok(());
+        }
+
+        Self::send_management(
+            &user_account,
+            &session,
+            proto_net::RtcSessionManagement {
+                option: OPTION_MUTE,
+                group_id: group_id.clone(),
+                audio_muted,
+                video_muted,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Leave a group call
+    ///
+    /// If we are the host, the call is ended for all participants.
+    pub fn leave(my_user_id: &PeerId, group_id: &Vec<u8>) -> Result<(), String> {
+        let session = super::Rtc::get_session_from_id(group_id)
+            .ok_or_else(|| "session does not exist!".to_string())?;
+        let user_account = UserAccounts::get_by_id(*my_user_id)
+            .ok_or_else(|| "user account has problem".to_string())?;
+
+        super::Rtc::remove_session(group_id);
+
+        // end call
+        if session.host_id == my_user_id.to_bytes() {
+            if let Err(e) = Self::sfu(RtcSfuRequest::End {
+                group_id: group_id.clone(),
+            }) {
+                log::error!("SFU end error: {}", e);
+            }
+
+            let group = Self::get_group(my_user_id, group_id)?;
+            let proto_message = proto_net::RtcContainer {
+                message: Some(proto_net::rtc_container::Message::RtcSessionManagement(
+                    proto_net::RtcSessionManagement {
+                        option: OPTION_END,
+                        group_id: group_id.clone(),
+                        ..Default::default()
+                    },
+                )),
+            };
+            Self::send_to_group(&user_account, &group, proto_message);
+
+            log::info!(
+                "RTC group call ended in group {}",
+                bs58::encode(group_id).into_string()
+            );
+            return Ok(());
+        }
+
+        // leave call
+        Self::send_management(
+            &user_account,
+            &session,
+            proto_net::RtcSessionManagement {
+                option: OPTION_END,
+                group_id: group_id.clone(),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Process an incoming group call announcement
+    pub fn on_session_request(
+        sender_id: &PeerId,
+        receiver_id: &PeerId,
+        req: &proto_net::RtcSessionRequest,
+    ) {
+        // only the host announces the call
+        if req.host_id != sender_id.to_bytes() {
+            log::warn!("RTC group call announced by other user than host");
+            return;
+        }
+        if let Err(e) = Self::get_group_of_sender(sender_id, receiver_id, &req.group_id) {
+            log::warn!("RTC group call: {}", e);
+            return;
+        }
+        if let Some(_session) = super::Rtc::get_session_from_id(&req.group_id) {
+            log::warn!(
+                "RTC session already exists for group {}",
+                bs58::encode(&req.group_id).into_string()
+            );
+            return;
+        }
+
+        let session = RtcSession {
+            user_id: receiver_id.to_bytes(),
+            group_id: req.group_id.clone(),
+            session_type: req.session_type,
+            created_at: Timestamp::get_timestamp(),
+            state: 2,
+            sdp_offer: None,
+            sdp_answer: None,
+            host_id: req.host_id.clone(),
+            participants: BTreeMap::new(),
+            remote_candidates: Vec::new(),
+            restarting: false,
+            recorders: Vec::new(),
+        };
+        super::Rtc::update_session(session.clone());
+        Self::emit("qaul-rtc-group-call", &session);
+
+        log::info!(
+            "RTC group call from {} in group {}",
+            sender_id.to_base58(),
+            bs58::encode(&req.group_id).into_string()
+        );
+    }
+
+    /// Process an incoming group call management message
+    pub fn on_session_management(
+        sender_id: &PeerId,
+        receiver_id: &PeerId,
+        req: &proto_net::RtcSessionManagement,
+    ) {
+        let mut session = match super::Rtc::get_session_from_id(&req.group_id) {
+            Some(session) => session,
+            None => {
+                log::warn!(
+                    "RTC group call management for unknown call {}",
+                    bs58::encode(&req.group_id).into_string()
+                );
+                return;
+            }
+        };
+        let user_account = match UserAccounts::get_by_id(*receiver_id) {
+            Some(user_account) => user_account,
+            None => return,
+        };
+
+        // messages from the host to a participant
+        if session.host_id == sender_id.to_bytes() {
+            match req.option {
+                OPTION_ACCEPT => {
+                    session.state = 3;
+                    session.sdp_answer = Some(req.sdp_answer.clone());
+                    super::Rtc::update_session(session.clone());
+                    Self::emit("qaul-rtc-group-answer", &session);
+                }
+                OPTION_DENY => {
+                    session.state = 2;
+                    super::Rtc::update_session(session.clone());
+                    Self::emit("qaul-rtc-group-denied", &session);
+                }
+                OPTION_END => {
+                    super::Rtc::remove_session(&req.group_id);
+                    Self::emit("qaul-rtc-group-ended", &session);
+                }
+                _ => log::error!("Unknown group call option from host: {}", req.option),
+            }
+            return;
+        }
+
+        // messages from a participant to the host
+        if session.host_id != receiver_id.to_bytes() {
+            log::warn!("RTC group call management to other user than host");
+            return;
+        }
+        if let Err(e) = Self::get_group_of_sender(sender_id, receiver_id, &req.group_id) {
+            log::warn!("RTC group call: {}", e);
+            return;
+        }
+
+        match req.option {
+            OPTION_JOIN => {
+                let result = Self::sfu(RtcSfuRequest::Join {
+                    group_id: req.group_id.clone(),
+                    user_id: sender_id.to_bytes(),
+                    sdp_offer: req.sdp_offer.clone(),
+                });
+                let reply = match result {
+                    Ok(sdp_answer) => {
+                        Self::add_participant(&mut session, sender_id.to_bytes());
+                        super::Rtc::update_session(session.clone());
+
+                        proto_net::RtcSessionManagement {
+                            option: OPTION_ACCEPT,
+                            group_id: req.group_id.clone(),
+                            sdp_answer: sdp_answer.unwrap_or_default(),
+                            ..Default::default()
+                        }
+                    }
+                    Err(e) => {
+                        log::error!("SFU join error: {}", e);
+                        proto_net::RtcSessionManagement {
+                            option: OPTION_DENY,
+                            group_id: req.group_id.clone(),
+                            ..Default::default()
+                        }
+                    }
+                };
+                Self::send_to_user(&user_account, sender_id, reply);
+            }
+            OPTION_MUTE => {
+                if !Self::set_participant_muted(
+                    &mut session,
+                    &sender_id.to_bytes(),
+                    req.audio_muted,
+                    req.video_muted,
+                ) {
+                    return;
+                }
+                super::Rtc::update_session(session.clone());
+            }
+            OPTION_END => {
+                if !Self::remove_participant(&mut session, &sender_id.to_bytes()) {
+                    return;
+                }
+                super::Rtc::update_session(session.clone());
+
+                if let Err(e) = Self::sfu(RtcSfuRequest::Leave {
+                    group_id: req.group_id.clone(),
+                    user_id: sender_id.to_bytes(),
+                }) {
+                    log::error!("SFU leave error: {}", e);
+                }
+            }
+            _ => {
+                log::error!("Unknown group call option: {}", req.option);
+                return;
+            }
+        }
+
+        Self::send_group_state(&user_account, &session);
+    }
+
+    /// Process an ICE candidate of a participant of our call
+    pub fn on_candidate(
+        sender_id: &PeerId,
+        receiver_id: &PeerId,
+        candidate: &proto_net::RtcIceCandidate,
+    ) {
+        let session = match super::Rtc::get_session_from_id(&candidate.group_id) {
+            Some(session) => session,
+            None => return,
+        };
+        if session.host_id != receiver_id.to_bytes()
+            || !session.participants.contains_key(&sender_id.to_bytes())
+        {
+            log::warn!("RTC group call candidate from other user than participant");
+            return;
+        }
+        if candidate.candidate.is_empty() {
+            return;
+        }
+
+        if let Err(e) = Self::sfu(RtcSfuRequest::Candidate {
+            group_id: candidate.group_id.clone(),
+            user_id: sender_id.to_bytes(),
+            candidate: candidate.candidate.clone(),
+        }) {
+            log::error!("SFU candidate error: {}", e);
+        }
+    }
+
+    /// Process the group call state from the host
+    pub fn on_group_state(
+        sender_id: &PeerId,
+        receiver_id: &PeerId,
+        state: &proto_net::RtcGroupState,
+    ) {
+        if state.host_id != sender_id.to_bytes() {
+            log::warn!("RTC group state from other user than host");
+            return;
+        }
+        let group = match Self::get_group_of_sender(sender_id, receiver_id, &state.group_id) {
+            Ok(group) => group,
+            Err(e) => {
+                log::warn!("RTC group state: {}", e);
+                return;
+            }
+        };
+
+        // members who missed the announcement learn about the call
+        let mut session = match super::Rtc::get_session_from_id(&state.group_id) {
+            Some(session) => session,
+            None => RtcSession {
+                user_id: receiver_id.to_bytes(),
+                group_id: state.group_id.clone(),
+                session_type: state.session_type,
+                created_at: Timestamp::get_timestamp(),
+                state: 2,
+                sdp_offer: None,
+                sdp_answer: None,
+                host_id: state.host_id.clone(),
+                participants: BTreeMap::new(),
+                remote_candidates: Vec::new(),
+                restarting: false,
+                recorders: Vec::new(),
+            },
+        };
+        if let Err(e) = Self::apply_group_state(&mut session, state, &group) {
+            log::warn!("RTC group state: {}", e);
+            return;
+        }
+        super::Rtc::update_session(session.clone());
+        Self::emit("qaul-rtc-group-state", &session);
+    }
+
+    /// add a participant to a session
+    fn add_participant(session: &mut RtcSession, user_id: Vec<u8>) {
+        session
+            .participants
+            .entry(user_id.clone())
+            .or_insert(RtcParticipant {
+                user_id,
+                audio_muted: false,
+                video_muted: false,
+                joined_at: Timestamp::get_timestamp(),
+            });
+    }
+
+    /// set the mute state of a participant
+    ///
+    /// Returns false if the user is not participating in the call.
+    fn set_participant_muted(
+        session: &mut RtcSession,
+        user_id: &Vec<u8>,
+        audio_muted: bool,
+        video_muted: bool,
+    ) -> bool {
+        match session.participants.get_mut(user_id) {
+            Some(participant) => {
+                participant.audio_muted = audio_muted;
+                participant.video_muted = video_muted;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// remove a participant from a session
+    ///
+    /// Returns false if the user was not participating in the call.
+    fn remove_participant(session: &mut RtcSession, user_id: &Vec<u8>) -> bool {
+        session.participants.remove(user_id).is_some()
+    }
+
+    /// take over the participants of the group state from the host
+    ///
+    /// Participants who are not members of the group are dropped.
+    fn apply_group_state(
+        session: &mut RtcSession,
+        state: &proto_net::RtcGroupState,
+        group: &Group,
+    ) -> Result<(), String> {
+        if session.host_id != state.host_id {
+            return Err("group state from other host".to_string());
+        }
+
+        session.participants = state
+            .participants
+            .iter()
+            .filter(|participant| group.is_member(&participant.user_id))
+            .map(|participant| {
+                (
+                    participant.user_id.clone(),
+                    RtcParticipant {
+                        user_id: participant.user_id.clone(),
+                        audio_muted: participant.audio_muted,
+                        video_muted: participant.video_muted,
+                        joined_at: participant.joined_at,
+                    },
+                )
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// get the group of a call and check the membership of the sender
+    fn get_group_of_sender(
+        sender_id: &PeerId,
+        my_user_id: &PeerId,
+        group_id: &Vec<u8>,
+    ) -> Result<Group, String> {
+        let group = Self::get_group(my_user_id, group_id)?;
+        match group.is_member(&sender_id.to_bytes()) {
+            true => Ok(group),
+            false => Err("sender is not member of the group".to_string()),
+        }
+    }
+
+    /// get the group of a call and check our membership
+    pub(super) fn get_group(my_user_id: &PeerId, group_id: &Vec<u8>) -> Result<Group, String> {
+        match GroupStorage::get_group(my_user_id.to_owned(), group_id.to_owned()) {
+            Some(group) if group.is_member(&my_user_id.to_bytes()) => Ok(group),
+            Some(_) => Err("you are not member in this group".to_string()),
+            None => Err("group not found".to_string()),
+        }
+    }
+
+    /// send the participants of our call to all group members
+    fn send_group_state(user_account: &UserAccount, session: &RtcSession) {
+        let group = match Self::get_group(&user_account.id, &session.group_id) {
+            Ok(group) => group,
+            Err(e) => {
+                log::error!("RTC group state: {}", e);
+                return;
+            }
+        };
+
+        let proto_message = proto_net::RtcContainer {
+            message: Some(proto_net::rtc_container::Message::RtcGroupState(
+                proto_net::RtcGroupState {
+                    group_id: session.group_id.clone(),
+                    host_id: session.host_id.clone(),
+                    session_type: session.session_type,
+                    participants: session
+                        .participants
+                        .values()
+                        .map(|participant| proto_net::RtcParticipant {
+                            user_id: participant.user_id.clone(),
+                            audio_muted: participant.audio_muted,
+                            video_muted: participant.video_muted,
+                            joined_at: participant.joined_at,
+                        })
+                        .collect(),
+                },
+            )),
+        };
+        Self::send_to_group(user_account, &group, proto_message);
+        Self::emit("qaul-rtc-group-state", session);
+    }
+
+    /// send a management message to the host of a call
+    fn send_management(
+        user_account: &UserAccount,
+        session: &RtcSession,
+        management: proto_net::RtcSessionManagement,
+    ) -> Result<(), String> {
+        let host_id = PeerId::from_bytes(&session.host_id)
+            .map_err(|e| format!("Invalid host ID: {:?}", e))?;
+        Self::send_to_user(user_account, &host_id, management);
+        Ok(())
+    }
+
+    /// send a management message to a user
+    fn send_to_user(
+        user_account: &UserAccount,
+        receiver: &PeerId,
+        management: proto_net::RtcSessionManagement,
+    ) {
+        let proto_message = proto_net::RtcContainer {
+            message: Some(proto_net::rtc_container::Message::RtcSessionManagement(
+                management,
+            )),
+        };
+        super::Rtc::send_rtc_message_through_message(
+            user_account,
+            receiver.to_owned(),
+            &proto_message.encode_to_vec(),
+        );
+    }
+
+    /// send a message to all members of the group
+    pub(super) fn send_to_group(
+        user_account: &UserAccount,
+        group: &Group,
+        container: proto_net::RtcContainer,
+    ) {
+        let last_index = match group.get_member(&user_account.id.to_bytes()) {
+            Some(member) => member.last_message_index + 1,
+            None => return,
+        };
+        let message_id = Chat::generate_message_id(&group.id, &user_account.id, last_index);
+
+        let send_message = proto::Messaging {
+            message: Some(proto::messaging::Message::CommonMessage(
+                proto::CommonMessage {
+                    message_id: message_id.clone(),
+                    group_id: group.id.clone(),
+                    sent_at: Timestamp::get_timestamp(),
+                    payload: Some(proto::common_message::Payload::RtcMessage(
+                        proto::RtcMessage {
+                            content: container.encode_to_vec(),
+                        },
+                    )),
+                },
+            )),
+        };
+
+        Group::send_to_members(
+            user_account,
+            group,
+            send_message.encode_to_vec(),
+            MessagingServiceType::Rtc,
+            &message_id,
+        );
+    }
+
+    /// emit a group call event to the app
+    fn emit(event_name: &str, session: &RtcSession) {
+        let participants: Vec<serde_json::Value> = session
+            .participants
+            .values()
+            .map(|participant| {
+                serde_json::json!({
+                    "user_id": bs58::encode(&participant.user_id).into_string(),
+                    "audio_muted": participant.audio_muted,
+                    "video_muted": participant.video_muted,
+                    "joined_at": participant.joined_at,
+                })
+            })
+            .collect();
+
+        emit_tauri_event(
+            event_name,
+            serde_json::json!({
+                "group_id": bs58::encode(&session.group_id).into_string(),
+                "host_id": bs58::encode(&session.host_id).into_string(),
+                "session_type": session.session_type,
+                "state": session.state,
+                "sdp_answer": session.sdp_answer,
+                "participants": participants,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::group::GroupMember;
+
+    fn user(id: u8) -> Vec<u8> {
+        vec![id; 4]
+    }
+
+    fn group(members: &[Vec<u8>]) -> Group {
+        Group {
+            id: vec![9; 16],
+            name: "group".to_string(),
+            is_direct_chat: false,
+            created_at: 0,
+            status: 0,
+            revision: 0,
+            members: members
+                .iter()
+                .map(|user_id| {
+                    (
+                        user_id.clone(),
+                        GroupMember {
+                            user_id: user_id.clone(),
+                            role: 0,
+                            joined_at: 0,
+                            state: 0,
+                            last_message_index: 0,
+                            name: String::new(),
+                            reg_no: String::new(),
+                            profile_pic: String::new(),
+                            about: String::new(),
+                            college: String::new(),
+                        },
+                    )
+                })
+                .collect(),
+            unread_messages: 0,
+            last_message_at: 0,
+            last_message_data: Vec::new(),
+            last_message_sender_id: Vec::new(),
+        }
+    }
+
+    fn session(host_id: Vec<u8>) -> RtcSession {
+        RtcSession {
+            user_id: host_id.clone(),
+            group_id: vec![9; 16],
+            session_type: 1,
+            created_at: 0,
+            state: 3,
+            sdp_offer: None,
+            sdp_answer: None,
+            host_id,
+            participants: BTreeMap::new(),
+            remote_candidates: Vec::new(),
+            restarting: false,
+            recorders: Vec::new(),
+        }
+    }
+
+    fn state(host_id: Vec<u8>, participants: &[Vec<u8>]) -> proto_net::RtcGroupState {
+        proto_net::RtcGroupState {
+            group_id: vec![9; 16],
+            host_id,
+            session_type: 1,
+            participants: participants
+                .iter()
+                .map(|user_id| proto_net::RtcParticipant {
+                    user_id: user_id.clone(),
+                    audio_muted: true,
+                    video_muted: false,
+                    joined_at: 7,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn join_mute_and_leave() {
+        let mut session = session(user(1));
+
+        // muting before joining fails
+        assert!(!RtcGroup::set_participant_muted(
+            &mut session,
+            &user(2),
+            true,
+            true
+        ));
+
+        RtcGroup::add_participant(&mut session, user(2));
+        assert!(RtcGroup::set_participant_muted(
+            &mut session,
+            &user(2),
+            true,
+            false
+        ));
+        let joined_at = session.participants[&user(2)].joined_at;
+
+        // joining again keeps the participant state
+        RtcGroup::add_participant(&mut session, user(2));
+        let participant = &session.participants[&user(2)];
+        assert!(participant.audio_muted);
+        assert!(!participant.video_muted);
+        assert_eq!(participant.joined_at, joined_at);
+
+        assert!(RtcGroup::remove_participant(&mut session, &user(2)));
+        assert!(!RtcGroup::remove_participant(&mut session, &user(2)));
+        assert!(session.participants.is_empty());
+    }
+
+    #[test]
+    fn group_state_of_host() {
+        let group = group(&[user(1), user(2), user(3)]);
+        let mut session = session(user(1));
+
+        RtcGroup::apply_group_state(&mut session, &state(user(1), &[user(1), user(2)]), &group)
+            .unwrap();
+        assert_eq!(
+            session.participants.keys().cloned().collect::<Vec<_>>(),
+            vec![user(1), user(2)]
+        );
+        assert!(session.participants[&user(2)].audio_muted);
+        assert_eq!(session.participants[&user(2)].joined_at, 7);
+
+        // a participant left
+        RtcGroup::apply_group_state(&mut session, &state(user(1), &[user(1)]), &group).unwrap();
+        assert_eq!(
+            session.participants.keys().cloned().collect::<Vec<_>>(),
+            vec![user(1)]
+        );
+    }
+
+    #[test]
+    fn group_state_of_other_host_is_rejected() {
+        let group = group(&[user(1), user(2)]);
+        let mut session = session(user(1));
+        RtcGroup::add_participant(&mut session, user(1));
+
+        assert!(
+            RtcGroup::apply_group_state(&mut session, &state(user(2), &[user(2)]), &group).is_err()
+        );
+        assert_eq!(
+            session.participants.keys().cloned().collect::<Vec<_>>(),
+            vec![user(1)]
+        );
+    }
+
+    #[test]
+    fn group_state_drops_non_members() {
+        let group = group(&[user(1), user(2)]);
+        let mut session = session(user(1));
+
+        RtcGroup::apply_group_state(
+            &mut session,
+            &state(user(1), &[user(1), user(2), user(5)]),
+            &group,
+        )
+        .unwrap();
+        assert_eq!(
+            session.participants.keys().cloned().collect::<Vec<_>>(),
+            vec![user(1), user(2)]
+        );
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright (c) 2022 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # RTC ICE Candidate Exchange
+//!
+//! The SDP offer and answer only contain the candidates, which
+//! were known when they were created.
+//! Further candidates, e.g. server reflexive candidates from a
+//! STUN server, are sent incrementally to the other side
+//! (trickle ICE).
+//!
+//! When the network of a node changes, the node restarts ICE
+//! with a new SDP offer.
+//! If both sides restart at the same time, the offer of the user
+//! with the smaller user id wins.
+
+use libp2p::PeerId;
+use prost::Message;
+
+use super::{proto_net, proto_rpc, Rtc, RtcGroup, RtcSession};
+use crate::connections::events::emit_tauri_event;
+use crate::node::user_accounts::UserAccounts;
+
+/// session management option: ICE restart offer
+const OPTION_RESTART_OFFER: u32 = 6;
+/// session management option: ICE restart answer
+const OPTION_RESTART_ANSWER: u32 = 7;
+
+/// RTC ICE Candidate Exchange
+pub struct RtcIce {}
+
+impl RtcIce {
+    /// Send one of our ICE candidates to the other side of a session
+    ///
+    /// In a group call, the candidates are sent to the host.
+    pub fn send_candidate(
+        my_user_id: &PeerId,
+        req: &proto_rpc::RtcIceCandidate,
+    ) -> Result<(), String> {
+        let session = Rtc::get_session_from_id(&req.group_id)
+            .ok_or_else(|| "session does not exist!".to_string())?;
+        let user_account = UserAccounts::get_by_id(*my_user_id)
+            .ok_or_else(|| "user account has problem".to_string())?;
+
+        let (receiver, group_id) = if session.host_id.is_empty() {
+            (session.group_id.clone(), Vec::new())
+        } else if session.host_id == my_user_id.to_bytes() {
+            return Err("the candidates of the SFU are part of its SDP answer".to_string());
+        } else {
+            (session.host_id.clone(), session.group_id.clone())
+        };
+        let receiver =
+            PeerId::from_bytes(&receiver).map_err(|e| format!("Invalid peer ID: {:?}", e))?;
+
+        let proto_message = proto_net::RtcContainer {
+            message: Some(proto_net::rtc_container::Message::RtcIceCandidate(
+                proto_net::RtcIceCandidate {
+                    candidate: req.candidate.clone(),
+                    sdp_mid: req.sdp_mid.clone(),
+                    sdp_mline_index: req.sdp_mline_index,
+                    group_id,
+                    ufrag: req.ufrag.clone(),
+                    end_of_candidates: req.end_of_candidates,
+                },
+            )),
+        };
+        Rtc::send_rtc_message_through_message(
+            &user_account,
+            receiver,
+            &proto_message.encode_to_vec(),
+        );
+
+        Ok(())
+    }
+
+    /// Process an incoming ICE candidate
+    pub fn on_candidate(
+        sender_id: &PeerId,
+        receiver_id: &PeerId,
+        candidate: &proto_net::RtcIceCandidate,
+    ) {
+        if !candidate.group_id.is_empty() {
+            RtcGroup::on_candidate(sender_id, receiver_id, candidate);
+            return;
+        }
+
+        let mut session = match Rtc::get_session_from_id(&sender_id.to_bytes()) {
+            Some(session) => session,
+            None => {
+                log::warn!(
+                    "ICE candidate received but session does not exist: {}",
+                    sender_id.to_base58()
+                );
+                return;
+            }
+        };
+
+        // drop candidates of a former ICE session
+        if !candidate.ufrag.is_empty() {
+            let ufrags: Vec<String> = [&session.sdp_offer, &session.sdp_answer]
+                .iter()
+                .filter_map(|sdp| sdp.as_ref().and_then(|sdp| Self::ice_ufrag(sdp)))
+                .collect();
+            if !ufrags.is_empty() && !ufrags.contains(&candidate.ufrag) {
+                log::debug!("ICE candidate of a former ICE session dropped");
+                return;
+            }
+        }
+
+        if !session.remote_candidates.contains(candidate) {
+            session.remote_candidates.push(candidate.clone());
+            Rtc::update_session(session.clone());
+        }
+
+        emit_tauri_event(
+            "qaul-rtc-ice-candidate",
+            serde_json::json!({
+                "group_id": bs58::encode(&session.group_id).into_string(),
+                "candidate": candidate.candidate,
+                "sdp_mid": candidate.sdp_mid,
+                "sdp_mline_index": candidate.sdp_mline_index,
+                "ufrag": candidate.ufrag,
+                "end_of_candidates": candidate.end_of_candidates,
+            }),
+        );
+    }
+
+    /// Restart ICE of a session with a new SDP offer
+    ///
+    /// Participants of a group call rejoin the call with the new offer.
+    pub fn restart(
+        my_user_id: &PeerId,
+        group_id: &Vec<u8>,
+        sdp_offer: String,
+    ) -> Result<(), String> {
+        let mut session = Rtc::get_session_from_id(group_id)
+            .ok_or_else(|| "session does not exist!".to_string())?;
+
+        if !session.host_id.is_empty() {
+            if session.host_id == my_user_id.to_bytes() {
+                return Err("the host of a group call does not restart ICE".to_string());
+            }
+            return RtcGroup::join(my_user_id, group_id, sdp_offer).map(|_| ());
+        }
+
+        session.sdp_offer = Some(sdp_offer.clone());
+        session.sdp_answer = None;
+        session.remote_candidates.clear();
+        session.restarting = true;
+        Rtc::update_session(session.clone());
+
+        Self::send_management(
+            my_user_id,
+            &session,
+            proto_net::RtcSessionManagement {
+                option: OPTION_RESTART_OFFER,
+                sdp_offer,
+                ..Default::default()
+            },
+        )?;
+
+        log::info!(
+            "ICE restart sent to {}",
+            bs58::encode(group_id).into_string()
+        );
+        Ok(())
+    }
+
+    /// Answer the ICE restart of the other side
+    pub fn restart_answer(
+        my_user_id: &PeerId,
+        group_id: &Vec<u8>,
+        sdp_answer: String,
+    ) -> Result<(), String> {
+        let mut session = Rtc::get_session_from_id(group_id)
+            .ok_or_else(|| "session does not exist!".to_string())?;
+
+        session.sdp_answer = Some(sdp_answer.clone());
+        Rtc::update_session(session.clone());
+
+        Self::send_management(
+            my_user_id,
+            &session,
+            proto_net::RtcSessionManagement {
+                option: OPTION_RESTART_ANSWER,
+                sdp_answer,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Process an incoming ICE restart offer
+    pub fn on_restart(
+        sender_id: &PeerId,
+        receiver_id: &PeerId,
+        req: &proto_net::RtcSessionManagement,
+    ) {
+        let mut session = match Rtc::get_session_from_id(&sender_id.to_bytes()) {
+            Some(session) => session,
+            None => return,
+        };
+
+        // both sides restarted, the smaller user id wins
+        if session.restarting && receiver_id.to_bytes() < sender_id.to_bytes() {
+            log::info!("ICE restart of {} ignored", sender_id.to_base58());
+            return;
+        }
+
+        session.restarting = false;
+        session.sdp_offer = Some(req.sdp_offer.clone());
+        session.sdp_answer = None;
+        session.remote_candidates.clear();
+        Rtc::update_session(session.clone());
+
+        emit_tauri_event(
+            "qaul-rtc-ice-restart",
+            serde_json::json!({
+                "group_id": bs58::encode(&session.group_id).into_string(),
+                "sdp_offer": req.sdp_offer,
+            }),
+        );
+
+        log::info!("ICE restart received from {}", sender_id.to_base58());
+    }
+
+    /// Process the answer to our ICE restart offer
+    pub fn on_restart_answer(
+        sender_id: &PeerId,
+        _receiver_id: &PeerId,
+        req: &proto_net::RtcSessionManagement,
+    ) {
+        let mut session = match Rtc::get_session_from_id(&sender_id.to_bytes()) {
+            Some(session) => session,
+            None => return,
+        };
+        if !session.restarting {
+            log::warn!(
+                "unexpected ICE restart answer from {}",
+                sender_id.to_base58()
+            );
+            return;
+        }
+
+        session.restarting = false;
+        session.sdp_answer = Some(req.sdp_answer.clone());
+        Rtc::update_session(session.clone());
+
+        emit_tauri_event(
+            "qaul-rtc-ice-restart-answer",
+            serde_json::json!({
+                "group_id": bs58::encode(&session.group_id).into_string(),
+                "sdp_answer": req.sdp_answer,
+            }),
+        );
+    }
+
+    /// Get the ICE username fragment of an SDP
+    ///
+    /// Works for plain SDP strings and for JSON serialized
+    /// str0m `SdpOffer` and `SdpAnswer` structures.
+    pub fn ice_ufrag(sdp: &str) -> Option<String> {
+        let start = sdp.find("a=ice-ufrag:")? + "a=ice-ufrag:".len();
+        let ufrag: String = sdp[start..]
+            .chars()
+            .take_while(|c| !c.is_whitespace() && *c != '\\' && *c != '"')
+            .collect();
+
+        match ufrag.is_empty() {
+            true => None,
+            false => Some(ufrag),
+        }
+    }
+
+    /// send a management message to the other side of a direct call
+    fn send_management(
+        my_user_id: &PeerId,
+        session: &RtcSession,
+        management: proto_net::RtcSessionManagement,
+    ) -> Result<(), String> {
+        let user_account = UserAccounts::get_by_id(*my_user_id)
+            .ok_or_else(|| "user account has problem".to_string())?;
+        let receiver = PeerId::from_bytes(&session.group_id)
+            .map_err(|e| format!("Invalid peer ID: {:?}", e))?;
+
+        let proto_message = proto_net::RtcContainer {
+            message: Some(proto_net::rtc_container::Message::RtcSessionManagement(
+                management,
+            )),
+        };
+        Rtc::send_rtc_message_through_message(
+            &user_account,
+            receiver,
+            &proto_message.encode_to_vec(),
+        );
+
+        Ok(())
+    }
+}
@@ -7,6 +7,7 @@ use super::Rtc;
 use crate::{node::user_accounts::UserAccounts, utilities::timestamp};
 use libp2p::PeerId;
 use prost::Message;
+use std::collections::BTreeMap;
 
 pub struct RtcManaging {}
 
@@ -24,6 +25,30 @@ impl RtcManaging {
                 created_at: session.created_at,
                 sdp_offer: session.sdp_offer.clone().unwrap_or_default(),
                 sdp_answer: session.sdp_answer.clone().unwrap_or_default(),
+                host_id: session.host_id.clone(),
+                participants: session
+                    .participants
+                    .values()
+                    .map(|participant| super::proto_rpc::RtcParticipant {
+                        user_id: participant.user_id.clone(),
+                        audio_muted: participant.audio_muted,
+                        video_muted: participant.video_muted,
+                        joined_at: participant.joined_at,
+                    })
+                    .collect(),
+                remote_candidates: session
+                    .remote_candidates
+                    .iter()
+                    .map(|candidate| super::proto_rpc::RtcIceCandidate {
+                        group_id: session.group_id.clone(),
+                        candidate: candidate.candidate.clone(),
+                        sdp_mid: candidate.sdp_mid.clone(),
+                        sdp_mline_index: candidate.sdp_mline_index,
+                        ufrag: candidate.ufrag.clone(),
+                        end_of_candidates: candidate.end_of_candidates,
+                    })
+                    .collect(),
+                recorders: session.recorders.clone(),
             };
             res.sessions.push(entry);
         }
@@ -76,6 +101,11 @@ impl RtcManaging {
             created_at: timestamp::Timestamp::get_timestamp(),
             sdp_offer: sdp_offer.clone(),
             sdp_answer: None,
+            host_id: Vec::new(),
+            participants: BTreeMap::new(),
+            remote_candidates: Vec::new(),
+            restarting: false,
+            recorders: Vec::new(),
         };
         super::Rtc::update_session(session);
 
@@ -85,6 +115,7 @@ impl RtcManaging {
                 super::proto_net::RtcSessionRequest {
                     session_type: req.session_type,
                     sdp_offer: sdp_offer.unwrap_or_default(),
+                    ..Default::default()
                 },
             )),
         };
@@ -125,6 +156,7 @@ impl RtcManaging {
                     super::proto_net::RtcSessionManagement {
                         option,
                         sdp_answer: sdp_answer.unwrap_or_default(),
+                        ..Default::default()
                     },
                 ),
             ),
@@ -217,6 +249,22 @@ impl RtcManaging {
                             bs58::encode(&req.group_id).into_string()
                         );
                     }
+                    6 => {
+                        // Restart ICE with a new SDP offer
+                        super::RtcIce::restart(my_user_id, &req.group_id, req.sdp_offer.clone())?;
+                    }
+                    7 => {
+                        // Answer ICE restart with SDP answer
+                        super::RtcIce::restart_answer(
+                            my_user_id,
+                            &req.group_id,
+                            req.sdp_answer.clone(),
+                        )?;
+                    }
+                    11 | 12 => {
+                        // Announce the start or stop of our recording
+                        super::RtcRecording::announce(my_user_id, &req.group_id, req.option == 11)?;
+                    }
                     _ => {
                         return Err("unknown session management option".to_string());
                     }
@@ -255,6 +303,11 @@ impl RtcManaging {
             created_at: timestamp::Timestamp::get_timestamp(),
             sdp_offer,
             sdp_answer: None,
+            host_id: Vec::new(),
+            participants: BTreeMap::new(),
+            remote_candidates: Vec::new(),
+            restarting: false,
+            recorders: Vec::new(),
         };
         super::Rtc::update_session(session);
 
@@ -268,7 +321,7 @@ impl RtcManaging {
     /// Process incoming session management from network (caller receives answer)
     pub fn on_session_management(
         sender_id: &PeerId,
-        _receiver_id: &PeerId,
+        receiver_id: &PeerId,
         req: &super::proto_net::RtcSessionManagement,
     ) {
         match super::Rtc::get_session_from_id(&sender_id.to_bytes()) {
@@ -308,6 +361,18 @@ impl RtcManaging {
                             sender_id.to_base58()
                         );
                     }
+                    6 => {
+                        // ICE restart offer
+                        super::RtcIce::on_restart(sender_id, receiver_id, req);
+                    }
+                    7 => {
+                        // ICE restart answer
+                        super::RtcIce::on_restart_answer(sender_id, receiver_id, req);
+                    }
+                    8 | 9 | 10 => {
+                        // screen or file share
+                        super::RtcShare::on_share_management(sender_id, receiver_id, req);
+                    }
                     _ => {
                         log::error!("Unknown session management option: {}", req.option);
                     }
@@ -0,0 +1,167 @@
+// Copyright (c) 2022 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # RTC Call Recording Announcements
+//!
+//! Recording a call is opt-in and every participant is told about it.
+//! When a user starts or stops recording, an `RtcSessionManagement`
+//! message is sent:
+//!
+//! * option 11: recording started
+//! * option 12: recording stopped
+//!
+//! In a direct call the message is sent to the other side,
+//! in a group call to all members of the group.
+//! The users recording a call are kept in `RtcSession::recorders`.
+//!
+//! The recording itself is done by the app.
+
+use libp2p::PeerId;
+use prost::Message;
+
+use super::{proto_net, Rtc, RtcGroup, RtcSession};
+use crate::connections::events::emit_tauri_event;
+use crate::node::user_accounts::UserAccounts;
+
+/// session management option: recording started
+const OPTION_RECORDING_STARTED: u32 = 11;
+/// session management option: recording stopped
+const OPTION_RECORDING_STOPPED: u32 = 12;
+
+/// RTC Call Recording Announcements
+pub struct RtcRecording {}
+
+impl RtcRecording {
+    /// Is this management message a recording announcement
+    pub fn is_announcement(req: &proto_net::RtcSessionManagement) -> bool {
+        req.option == OPTION_RECORDING_STARTED || req.option == OPTION_RECORDING_STOPPED
+    }
+
+    /// Announce the start or stop of our recording to all participants
+    pub fn announce(
+        my_user_id: &PeerId,
+        group_id: &Vec<u8>,
+        recording: bool,
+    ) -> Result<(), String> {
+        let mut session = Rtc::get_session_from_id(group_id)
+            .ok_or_else(|| "session does not exist!".to_string())?;
+        if session.state != 3 && session.host_id != my_user_id.to_bytes() {
+            return Err("session is not established".to_string());
+        }
+        let user_account = UserAccounts::get_by_id(*my_user_id)
+            .ok_or_else(|| "user account has problem".to_string())?;
+
+        let option = match recording {
+            true => OPTION_RECORDING_STARTED,
+            false => OPTION_RECORDING_STOPPED,
+        };
+
+        if session.host_id.is_empty() {
+            let receiver = PeerId::from_bytes(&session.group_id)
+                .map_err(|e| format!("Invalid peer ID: {:?}", e))?;
+            let proto_message = Self::container(option, Vec::new());
+            Rtc::send_rtc_message_through_message(
+                &user_account,
+                receiver,
+                &proto_message.encode_to_vec(),
+            );
+        } else {
+            let group = RtcGroup::get_group(my_user_id, group_id)?;
+            RtcGroup::send_to_group(
+                &user_account,
+                &group,
+                Self::container(option, group_id.clone()),
+            );
+        }
+
+        Self::set_recorder(&mut session, my_user_id.to_bytes(), recording);
+        Rtc::update_session(session.clone());
+        Self::emit(&session, my_user_id, recording);
+
+        log::info!(
+            "RTC recording {} in {}",
+            if recording { "started" } else { "stopped" },
+            bs58::encode(group_id).into_string()
+        );
+        Ok(())
+    }
+
+    /// Process an incoming recording announcement
+    pub fn on_announcement(
+        sender_id: &PeerId,
+        receiver_id: &PeerId,
+        req: &proto_net::RtcSessionManagement,
+    ) {
+        let session_id = match req.group_id.is_empty() {
+            true => sender_id.to_bytes(),
+            false => req.group_id.clone(),
+        };
+        let mut session = match Rtc::get_session_from_id(&session_id) {
+            Some(session) => session,
+            None => {
+                log::debug!(
+                    "recording announcement for unknown call from {}",
+                    sender_id.to_base58()
+                );
+                return;
+            }
+        };
+
+        // only members of the group can record a group call
+        if !req.group_id.is_empty() {
+            match RtcGroup::get_group(receiver_id, &req.group_id) {
+                Ok(group) if group.is_member(&sender_id.to_bytes()) => {}
+                _ => {
+                    log::warn!(
+                        "recording announcement from non member {}",
+                        sender_id.to_base58()
+                    );
+                    return;
+                }
+            }
+        }
+
+        let recording = req.option == OPTION_RECORDING_STARTED;
+        Self::set_recorder(&mut session, sender_id.to_bytes(), recording);
+        Rtc::update_session(session.clone());
+        Self::emit(&session, sender_id, recording);
+    }
+
+    /// add or remove a user from the recorders of a session
+    fn set_recorder(session: &mut RtcSession, user_id: Vec<u8>, recording: bool) {
+        session.recorders.retain(|recorder| *recorder != user_id);
+        if recording {
+            session.recorders.push(user_id);
+        }
+    }
+
+    /// create the announcement message
+    fn container(option: u32, group_id: Vec<u8>) -> proto_net::RtcContainer {
+        proto_net::RtcContainer {
+            message: Some(proto_net::rtc_container::Message::RtcSessionManagement(
+                proto_net::RtcSessionManagement {
+                    option,
+                    group_id,
+                    ..Default::default()
+                },
+            )),
+        }
+    }
+
+    /// emit the recording state to the app
+    fn emit(session: &RtcSession, user_id: &PeerId, recording: bool) {
+        emit_tauri_event(
+            "qaul-rtc-recording",
+            serde_json::json!({
+                "group_id": bs58::encode(&session.group_id).into_string(),
+                "user_id": user_id.to_base58(),
+                "recording": recording,
+                "recorders": session
+                    .recorders
+                    .iter()
+                    .map(|recorder| bs58::encode(recorder).into_string())
+                    .collect::<Vec<_>>(),
+            }),
+        );
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright (c) 2022 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # RTC Screen and File Shares
+//!
+//! Within an established direct call, a user can share the screen
+//! or stream files to the other side.
+//! Every share is a separate WebRTC connection with data channels,
+//! which is offered by the sharing node.
+//!
+//! The signaling runs over qaul via `RtcSessionManagement` messages:
+//!
+//! * option 8: the sharing node sends its SDP offer
+//! * option 9: the other side answers with its SDP answer
+//! * option 10: either side stops the share
+//!
+//! When the call ends, all its shares are stopped.
+//!
+//! The connections are run by the app, which registers a handler
+//! via `RtcShare::set_share_handler`.
+
+use libp2p::PeerId;
+use prost::Message;
+use std::sync::{Arc, RwLock};
+
+use super::{proto_net, Rtc, RtcSession};
+use crate::connections::events::emit_tauri_event;
+use crate::node::user_accounts::UserAccounts;
+
+/// session management option: share offer
+const OPTION_SHARE_OFFER: u32 = 8;
+/// session management option: share answer
+const OPTION_SHARE_ANSWER: u32 = 9;
+/// session management option: share stop
+const OPTION_SHARE_STOP: u32 = 10;
+
+/// Share event for the app running the share connections
+pub enum RtcShareEvent {
+    /// the other side answered our share
+    Answer {
+        group_id: Vec<u8>,
+        share_id: String,
+        sdp_answer: String,
+    },
+    /// the other side stopped a share
+    Stop { group_id: Vec<u8>, share_id: String },
+    /// the call ended, all its shares and its recording are stopped
+    SessionEnded { group_id: Vec<u8> },
+}
+
+/// Share handler type
+pub type RtcShareHandler = Arc<dyn Fn(RtcShareEvent) + Send + Sync>;
+
+/// Share handler, registered by the app
+static SHARE_HANDLER: RwLock<Option<RtcShareHandler>> = RwLock::new(None);
+
+/// RTC Screen and File Shares
+pub struct RtcShare {}
+
+impl RtcShare {
+    /// Set the handler running the connections of our shares
+    pub fn set_share_handler(handler: RtcShareHandler) {
+        *SHARE_HANDLER.write().unwrap() = Some(handler);
+    }
+
+    /// pass an event to the share handler
+    fn handle(event: RtcShareEvent) {
+        match SHARE_HANDLER.read().unwrap().as_ref() {
+            Some(handler) => handler(event),
+            None => log::warn!("no share handler registered"),
+        }
+    }
+
+    /// Offer a share to the other side of an established direct call
+    pub fn offer(
+        my_user_id: &PeerId,
+        group_id: &Vec<u8>,
+        share_id: &str,
+        sdp_offer: String,
+    ) -> Result<(), String> {
+        let session = Self::get_established_session(group_id)?;
+
+        Self::send_management(
+            my_user_id,
+            &session,
+            proto_net::RtcSessionManagement {
+                option: OPTION_SHARE_OFFER,
+                sdp_offer,
+                share_id: share_id.to_string(),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Answer the share offer of the other side
+    pub fn answer(
+        my_user_id: &PeerId,
+        group_id: &Vec<u8>,
+        share_id: &str,
+        sdp_answer: String,
+    ) -> Result<(), String> {
+        let session = Self::get_established_session(group_id)?;
+
+        Self::send_management(
+            my_user_id,
+            &session,
+            proto_net::RtcSessionManagement {
+                option: OPTION_SHARE_ANSWER,
+                sdp_answer,
+                share_id: share_id.to_string(),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Stop a share
+    pub fn stop(my_user_id: &PeerId, group_id: &Vec<u8>, share_id: &str) -> Result<(), String> {
+        let session = Rtc::get_session_from_id(group_id)
+            .ok_or_else(|| "session does not exist!".to_string())?;
+
+        Self::send_management(
+            my_user_id,
+            &session,
+            proto_net::RtcSessionManagement {
+                option: OPTION_SHARE_STOP,
+                share_id: share_id.to_string(),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Inform the app that a call has ended
+    pub fn on_session_ended(group_id: &Vec<u8>) {
+        Self::handle(RtcShareEvent::SessionEnded {
+            group_id: group_id.clone(),
+        });
+    }
+
+    /// Process an incoming share message
+    pub fn on_share_management(
+        sender_id: &PeerId,
+        _receiver_id: &PeerId,
+        req: &proto_net::RtcSessionManagement,
+    ) {
+        let session = match Rtc::get_session_from_id(&sender_id.to_bytes()) {
+            Some(session) if session.state == 3 => session,
+            _ => {
+                log::warn!(
+                    "share message received without established session: {}",
+                    sender_id.to_base58()
+                );
+                return;
+            }
+        };
+        let group_id = bs58::encode(&session.group_id).into_string();
+
+        match req.option {
+            OPTION_SHARE_OFFER => {
+                emit_tauri_event(
+                    "qaul-rtc-share-offer",
+                    serde_json::json!({
+                        "group_id": group_id,
+                        "share_id": req.share_id,
+                        "sdp_offer": req.sdp_offer,
+                    }),
+                );
+            }
+            OPTION_SHARE_ANSWER => {
+                Self::handle(RtcShareEvent::Answer {
+                    group_id: session.group_id.clone(),
+                    share_id: req.share_id.clone(),
+                    sdp_answer: req.sdp_answer.clone(),
+                });
+            }
+            OPTION_SHARE_STOP => {
+                Self::handle(RtcShareEvent::Stop {
+                    group_id: session.group_id.clone(),
+                    share_id: req.share_id.clone(),
+                });
+                emit_tauri_event(
+                    "qaul-rtc-share-stopped",
+                    serde_json::json!({
+                        "group_id": group_id,
+                        "share_id": req.share_id,
+                    }),
+                );
+            }
+            _ => log::error!("Unknown share option: {}", req.option),
+        }
+    }
+
+    /// get an established direct call
+    fn get_established_session(group_id: &Vec<u8>) -> Result<RtcSession, String> {
+        match Rtc::get_session_from_id(group_id) {
+            Some(session) if !session.host_id.is_empty() => {
+                Err("shares are only available in direct calls".to_string())
+            }
+            Some(session) if session.state == 3 => Ok(session),
+            Some(_) => Err("session is not established".to_string()),
+            None => Err("session does not exist!".to_string()),
+        }
+    }
+
+    /// send a management message to the other side of a direct call
+    fn send_management(
+        my_user_id: &PeerId,
+        session: &RtcSession,
+        management: proto_net::RtcSessionManagement,
+    ) -> Result<(), String> {
+        let user_account = UserAccounts::get_by_id(*my_user_id)
+            .ok_or_else(|| "user account has problem".to_string())?;
+        let receiver = PeerId::from_bytes(&session.group_id)
+            .map_err(|e| format!("Invalid peer ID: {:?}", e))?;
+
+        let proto_message = proto_net::RtcContainer {
+            message: Some(proto_net::rtc_container::Message::RtcSessionManagement(
+                management,
+            )),
+        };
+        Rtc::send_rtc_message_through_message(
+            &user_account,
+            receiver,
+            &proto_message.encode_to_vec(),
+        );
+
+        Ok(())
+    }
+}
@@ -8,12 +8,21 @@
 //! On the first startup a `config.yaml` file is saved.
 //! It can be configured and will be read on the next startup.
 //! All options are configurable from the command-line too.
+//!
+//! While libqaul is running, the configuration can be changed
+//! via the typed API `Configuration::apply_update()`.
+//! Every change is validated before it is applied and saved.
+//! Subscribers are notified of each changed section and
+//! re-apply it to the running modules.
 
 use config::{Config, File};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use libp2p::{multiaddr::Protocol, Multiaddr};
 use serde::{Deserialize, Serialize};
 use state::InitCell;
 use std::{
-    fs,
+    collections::BTreeSet,
+    fmt, fs,
     path::Path,
     sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
 };
@@ -21,6 +30,14 @@ use std::{
 /// make configuration globally accessible mutable state
 static CONFIG: InitCell<RwLock<Configuration>> = InitCell::new();
 
+/// subscribers that are informed about changed configuration sections
+static SUBSCRIBERS: InitCell<RwLock<Vec<Sender<ConfigurationSection>>>> = InitCell::new();
+
+/// minimal registration time to live accepted by rendezvous servers
+const RENDEZVOUS_MIN_TTL: u64 = 7200;
+/// maximal registration time to live accepted by rendezvous servers
+const RENDEZVOUS_MAX_TTL: u64 = 259200;
+
 /// Configuration of the local Node
 ///
 /// Here the keys and identity are stored
@@ -42,7 +59,7 @@ impl Default for Node {
 }
 
 /// LAN Connection Module
-#[derive(Debug, Deserialize, Clone, Serialize)]
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
 pub struct Lan {
     pub active: bool,
     pub listen: Vec<String>,
@@ -62,20 +79,88 @@ impl Default for Lan {
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize)]
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
 pub struct InternetPeer {
     pub address: String,
     pub name: String,
     pub enabled: bool,
+    /// this peer was not entered by the user but
+    /// found via rendezvous or peer exchange
+    #[serde(default)]
+    pub discovered: bool,
+}
+
+/// Rendezvous Discovery Options
+///
+/// The node registers itself at a rendezvous server
+/// and discovers the other nodes registered under the
+/// same namespace.
+/// The server address needs to contain the `/p2p/` peer id.
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
+pub struct RendezvousOptions {
+    pub enabled: bool,
+    pub server: String,
+    pub namespace: String,
+    //Registration time to live, unit: seconds
+    pub ttl: u64,
+    //Discover the registered nodes every 60 seconds, unit: seconds
+    pub discover_interval: u64,
+}
+
+impl Default for RendezvousOptions {
+    fn default() -> Self {
+        RendezvousOptions {
+            enabled: false,
+            server: String::from(""),
+            namespace: String::from("qaul"),
+            ttl: 7200,             //2h, unit: seconds
+            discover_interval: 60, //1min, unit: seconds
+        }
+    }
+}
+
+/// Peer Exchange Options
+///
+/// Neighbours share the addresses of the peers they have
+/// recently seen. The Internet module dials up to
+/// `max_auto_peers` of them and stores them in the
+/// peers list.
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
+pub struct PeerExchangeOptions {
+    pub enabled: bool,
+    //Sending the seen peers every 60 seconds to direct neighbours.
+    pub exchange_interval: u64,
+    //Maximum number of addresses sent per exchange message.
+    pub max_shared: u32,
+    //Maximum number of discovered peers kept in the peers list.
+    pub max_auto_peers: u32,
+    //How long a seen peer is shared, unit: seconds
+    pub seen_period_limit: u64,
+}
+
+impl Default for PeerExchangeOptions {
+    fn default() -> Self {
+        PeerExchangeOptions {
+            enabled: true,
+            exchange_interval: 60, //1min, unit: seconds
+            max_shared: 20,
+            max_auto_peers: 5,
+            seen_period_limit: 3600, //1h, unit: seconds
+        }
+    }
 }
 
 /// Internet Overlay Connection Module
-#[derive(Debug, Deserialize, Clone, Serialize)]
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
 pub struct Internet {
     pub active: bool,
     pub peers: Vec<InternetPeer>,
     pub do_listen: bool,
     pub listen: Vec<String>,
+    #[serde(default)]
+    pub rendezvous: RendezvousOptions,
+    #[serde(default)]
+    pub peer_exchange: PeerExchangeOptions,
 }
 
 impl Default for Internet {
@@ -106,6 +191,7 @@ impl Default for Internet {
                 address: String::from("/ip4/158.255.0.159/udp/9229/quic-v1"),
                 name: String::from("COICT"),
                 enabled: true,
+                discovered: false,
             }],
             do_listen: false,
             #[cfg(any(target_os = "android", target_os = "ios"))]
@@ -117,11 +203,13 @@ impl Default for Internet {
             ],
             #[cfg(not(any(target_os = "android", target_os = "ios")))]
             listen: vec![listen_ipv4_quic, listen_ipv4, listen_ipv6_quic, listen_ipv6],
+            rendezvous: RendezvousOptions::default(),
+            peer_exchange: PeerExchangeOptions::default(),
         }
     }
 }
 /// local user accounts that are stored on this node
-#[derive(Debug, Deserialize, Clone, Serialize)]
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
 pub struct UserAccount {
     pub name: String,
     pub id: String,
@@ -132,6 +220,10 @@ pub struct UserAccount {
     pub about: Option<String>,
     pub reg_no: Option<String>,
     pub college: Option<String>,
+    /// Argon2 hash of the account passphrase in PHC string format.
+    /// Accounts without passphrase are logged in on startup.
+    #[serde(default)]
+    pub passphrase: Option<String>,
 }
 
 impl Default for UserAccount {
@@ -146,6 +238,7 @@ impl Default for UserAccount {
             about: None,
             reg_no: None,
             college: None,
+            passphrase: None,
         }
     }
 }
@@ -155,7 +248,7 @@ impl Default for UserAccount {
 /// The following options can be configured:
 ///
 /// * logging to file
-#[derive(Debug, Deserialize, Clone, Serialize)]
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
 pub struct DebugOption {
     pub log: bool,
 }
@@ -172,7 +265,7 @@ impl Default for DebugOption {
 /// All units are second
 /// because rtt is measured as micro seconds
 /// * routing options
-#[derive(Debug, Deserialize, Clone, Serialize)]
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
 pub struct RoutingOptions {
     //Sending the table every 10 seconds to direct neighbours.
     pub sending_table_period: u64,
@@ -195,12 +288,43 @@ impl Default for RoutingOptions {
     }
 }
 
+/// Feed Configuration Options
+///
+/// Feed messages older than `max_age` are removed, and the
+/// oldest messages are removed when the stored feed exceeds
+/// `max_size`. A value of 0 disables the limit.
+///
+/// Neighbours reconcile the feed messages sent within the
+/// `sync_window` every `sync_interval`.
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
+pub struct FeedOptions {
+    //How long feed messages are kept, unit: seconds
+    pub max_age: u64,
+    //Maximal size of the stored feed, unit: MB
+    pub max_size: u32,
+    //Time window of the feed synchronisation, unit: seconds
+    pub sync_window: u64,
+    //Synchronise the feed with every neighbour every 5 minutes.
+    pub sync_interval: u64,
+}
+
+impl Default for FeedOptions {
+    fn default() -> Self {
+        FeedOptions {
+            max_age: 2592000,    //30 days, unit: seconds
+            max_size: 64,        //64 MB
+            sync_window: 604800, //7 days, unit: seconds
+            sync_interval: 300,  //5min, unit: seconds
+        }
+    }
+}
+
 /// Storage Configuration Options
 ///
 /// The following options can be configured:
 /// size_total units are MB
 /// * storage options
-#[derive(Debug, Deserialize, Clone, Serialize)]
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
 pub struct StorageOptions {
     //storage node users
     pub users: Vec<String>,
@@ -217,6 +341,27 @@ impl Default for StorageOptions {
     }
 }
 
+/// Encryption at Rest
+///
+/// When enabled, all data base records and the private keys
+/// in this configuration are encrypted with a key derived
+/// from the storage passphrase.
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq, Default)]
+pub struct StorageEncryption {
+    /// encryption at rest is enabled
+    pub enabled: bool,
+    /// base64 encoded Argon2 salt of the key derivation
+    pub salt: String,
+    /// base64 encoded verifier to check the passphrase
+    pub verifier: String,
+    /// all plaintext records have been encrypted
+    pub migrated: bool,
+    /// base64 encoded previous storage key, sealed with the
+    /// current key, while a passphrase change is migrated
+    #[serde(default)]
+    pub previous_key: String,
+}
+
 /// Configuration Structure of libqaul
 ///
 /// This structure contains the entire configuration of libqaul.
@@ -228,6 +373,10 @@ pub struct Configuration {
     pub user_accounts: Vec<UserAccount>,
     pub debug: DebugOption,
     pub routing: RoutingOptions,
+    #[serde(default)]
+    pub feed: FeedOptions,
+    #[serde(default)]
+    pub storage_encryption: StorageEncryption,
 }
 
 impl Default for Configuration {
@@ -239,10 +388,79 @@ impl Default for Configuration {
             user_accounts: Vec::new(),
             debug: DebugOption::default(),
             routing: RoutingOptions::default(),
+            feed: FeedOptions::default(),
+            storage_encryption: StorageEncryption::default(),
         }
     }
 }
 
+/// Sections of the configuration that can be changed at runtime
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConfigurationSection {
+    Lan,
+    Internet,
+    Routing,
+    Debug,
+    UserAccounts,
+    Feed,
+}
+
+impl ConfigurationSection {
+    /// whether a validated field belongs to this section
+    fn contains(&self, field: &str) -> bool {
+        let prefix = match self {
+            ConfigurationSection::Lan => "lan",
+            ConfigurationSection::Internet => "internet",
+            ConfigurationSection::Routing => "routing",
+            ConfigurationSection::Debug => "debug",
+            ConfigurationSection::UserAccounts => "user_accounts",
+            ConfigurationSection::Feed => "feed",
+        };
+        field
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('['))
+    }
+}
+
+/// A configuration value that did not pass the validation
+#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
+pub struct ConfigurationError {
+    /// path of the invalid field, e.g. `internet.rendezvous.ttl`
+    pub field: String,
+    /// human readable description of the problem
+    pub message: String,
+}
+
+impl ConfigurationError {
+    fn new(field: &str, message: &str) -> Self {
+        ConfigurationError {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigurationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Runtime changeable part of the configuration
+///
+/// This structure is used by the settings screens.
+/// Only the sections that are set are changed,
+/// the node and user keys can't be changed this way.
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+pub struct ConfigurationUpdate {
+    pub lan: Option<Lan>,
+    pub internet: Option<Internet>,
+    pub routing: Option<RoutingOptions>,
+    pub debug: Option<DebugOption>,
+    #[serde(default)]
+    pub feed: Option<FeedOptions>,
+}
+
 /// Profile information for setting node profile
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct UserProfile {
@@ -279,7 +497,10 @@ pub fn init() {
         }
         Ok(c) => {
             match c.try_deserialize::<Configuration>() {
-                Ok(config) => config,
+                Ok(mut config) => {
+                    config.remove_duplicate_peers();
+                    config
+                }
                 Err(e) => {
                     log::error!("Failed to deserialize config ({}), using default and backing up old config", e);
                     // Backup the corrupted config
@@ -297,6 +518,9 @@ pub fn init() {
 
     // put configuration to state
     CONFIG.set(RwLock::new(config));
+
+    // create subscriber state
+    SUBSCRIBERS.set(RwLock::new(Vec::new()));
 }
 
     /// Load a configuration file for upgrading purposes
@@ -390,8 +614,7 @@ pub fn init() {
         );
 
         // Update the configuration
-        {
-            let mut config = CONFIG.get().write().unwrap();
+        let result = Self::update(|config| {
             if let Some(first_user) = config.user_accounts.first_mut() {
                 log::info!("Updating config for user: {}", first_user.id);
 
@@ -414,14 +637,428 @@ pub fn init() {
                     college: Some(profile.college.clone()),
                     storage: StorageOptions::default(),
                     active: true,
+                    passphrase: None,
                 });
             }
+        });
+        if let Err(errors) = result {
+            Self::log_errors("node profile", &errors);
+        }
+    }
+
+    /// Set the profile of a specific user account
+    ///
+    /// Returns false if no user account with this id exists.
+    pub fn set_user_profile(user_id: String, profile: UserProfile) -> bool {
+        let mut found = false;
+        let result = Self::update(|config| {
+            if let Some(user) = config
+                .user_accounts
+                .iter_mut()
+                .find(|user| user.id == user_id)
+            {
+                user.name = profile.name;
+                user.college = Some(profile.college);
+                user.reg_no = Some(profile.reg_no);
+                user.profile_pic = Some(profile.profile);
+                user.about = Some(profile.about);
+                found = true;
+            }
+        });
+        match result {
+            Ok(_) => found,
+            Err(errors) => {
+                Self::log_errors("user profile", &errors);
+                false
+            }
+        }
+    }
+
+    /// Set or remove the passphrase hash of a user account
+    pub fn set_user_passphrase(user_id: String, passphrase: Option<String>) {
+        let result = Self::update(|config| {
+            if let Some(user) = config
+                .user_accounts
+                .iter_mut()
+                .find(|user| user.id == user_id)
+            {
+                user.passphrase = passphrase;
+            }
+        });
+        if let Err(errors) = result {
+            Self::log_errors("user passphrase", &errors);
+        }
+    }
+
+    /// Add a new user account
+    pub fn add_user_account(user: UserAccount) {
+        if let Err(errors) = Self::update(|config| config.user_accounts.push(user)) {
+            Self::log_errors("user account", &errors);
+        }
+    }
+
+    /// log the validation errors of a rejected change
+    fn log_errors(change: &str, errors: &Vec<ConfigurationError>) {
+        for error in errors {
+            log::error!(
+                "{} not saved, invalid {}: {}",
+                change,
+                error.field,
+                error.message
+            );
+        }
+    }
+    /// Get the runtime changeable sections of the configuration
+    pub fn get_settings() -> ConfigurationUpdate {
+        let config = CONFIG.get().read().unwrap();
+        ConfigurationUpdate {
+            lan: Some(config.lan.clone()),
+            internet: Some(config.internet.clone()),
+            routing: Some(config.routing.clone()),
+            debug: Some(config.debug.clone()),
+            feed: Some(config.feed.clone()),
+        }
+    }
+
+    /// Subscribe to configuration changes
+    ///
+    /// The receiver gets every changed section after the
+    /// change has been validated, applied and saved.
+    pub fn subscribe() -> Receiver<ConfigurationSection> {
+        let (sender, receiver) = unbounded();
+        let mut subscribers = SUBSCRIBERS.get().write().unwrap();
+        subscribers.push(sender);
+        receiver
+    }
+
+    /// Validate a configuration update without applying it
+    pub fn validate_update(update: &ConfigurationUpdate) -> Result<(), Vec<ConfigurationError>> {
+        let config = CONFIG.get().read().unwrap();
+        let mut new_config = config.clone();
+        Self::merge_update(&mut new_config, update.clone());
+        new_config.validate_sections(&config.changed_sections(&new_config))
+    }
+
+    /// Validate and apply a configuration update
+    ///
+    /// Returns the changed sections.
+    pub fn apply_update(
+        update: ConfigurationUpdate,
+    ) -> Result<Vec<ConfigurationSection>, Vec<ConfigurationError>> {
+        Self::update(|config| Self::merge_update(config, update))
+    }
+
+    /// Set the LAN connection module options
+    pub fn set_lan(lan: Lan) -> Result<Vec<ConfigurationSection>, Vec<ConfigurationError>> {
+        Self::update(|config| config.lan = lan)
+    }
+
+    /// Set the Internet connection module options
+    pub fn set_internet(
+        internet: Internet,
+    ) -> Result<Vec<ConfigurationSection>, Vec<ConfigurationError>> {
+        Self::update(|config| config.internet = internet)
+    }
+
+    /// Set the routing options
+    pub fn set_routing(
+        routing: RoutingOptions,
+    ) -> Result<Vec<ConfigurationSection>, Vec<ConfigurationError>> {
+        Self::update(|config| config.routing = routing)
+    }
+
+    /// Set the debugging options
+    pub fn set_debug(
+        debug: DebugOption,
+    ) -> Result<Vec<ConfigurationSection>, Vec<ConfigurationError>> {
+        Self::update(|config| config.debug = debug)
+    }
+
+    /// Set the feed retention and synchronisation options
+    pub fn set_feed(
+        feed: FeedOptions,
+    ) -> Result<Vec<ConfigurationSection>, Vec<ConfigurationError>> {
+        Self::update(|config| config.feed = feed)
+    }
+
+    /// Set the DTN storage options of a user account
+    pub fn set_user_storage(
+        user_id: String,
+        storage: StorageOptions,
+    ) -> Result<Vec<ConfigurationSection>, Vec<ConfigurationError>> {
+        Self::update(|config| {
+            for user in config.user_accounts.iter_mut() {
+                if user.id == user_id {
+                    user.storage = storage;
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Change the configuration
+    ///
+    /// The change is applied to a copy of the configuration
+    /// and the changed sections are validated. Only a valid
+    /// configuration is put to state and saved. Afterwards all
+    /// subscribers are informed about the changed sections.
+    pub fn update<F>(change: F) -> Result<Vec<ConfigurationSection>, Vec<ConfigurationError>>
+    where
+        F: FnOnce(&mut Configuration),
+    {
+        let changed;
+        {
+            let mut config = CONFIG.get().write().unwrap();
+            let mut new_config = config.clone();
+            change(&mut new_config);
+
+            changed = config.changed_sections(&new_config);
+            new_config.validate_sections(&changed)?;
+            *config = new_config;
+        }
+
+        if !changed.is_empty() {
+            Self::save();
+            Self::notify(&changed);
         }
 
-        // Save configuration to persist the change
-        log::info!("Saving configuration...");
-        Self::save();
+        Ok(changed)
     }
+
+    /// Validate the entire configuration
+    pub fn validate(&self) -> Result<(), Vec<ConfigurationError>> {
+        let mut errors: Vec<ConfigurationError> = Vec::new();
+
+        // connection modules
+        Self::validate_listen("lan.listen", &self.lan.listen, &mut errors);
+        Self::validate_listen("internet.listen", &self.internet.listen, &mut errors);
+
+        let mut addresses = BTreeSet::new();
+        for (index, peer) in self.internet.peers.iter().enumerate() {
+            let field = format!("internet.peers[{}].address", index);
+            if peer.address.parse::<Multiaddr>().is_err() {
+                errors.push(ConfigurationError::new(&field, "invalid multiaddress"));
+            } else if !addresses.insert(peer.address.clone()) {
+                errors.push(ConfigurationError::new(&field, "duplicate peer address"));
+            }
+        }
+
+        // rendezvous discovery
+        let rendezvous = &self.internet.rendezvous;
+        if rendezvous.enabled {
+            match rendezvous.server.parse::<Multiaddr>() {
+                Ok(address) => {
+                    if !address.iter().any(|p| matches!(p, Protocol::P2p(_))) {
+                        errors.push(ConfigurationError::new(
+                            "internet.rendezvous.server",
+                            "address needs to contain the /p2p/ peer id",
+                        ));
+                    }
+                }
+                Err(_) => errors.push(ConfigurationError::new(
+                    "internet.rendezvous.server",
+                    "invalid multiaddress",
+                )),
+            }
+        }
+        if rendezvous.namespace.is_empty() || rendezvous.namespace.len() > 255 {
+            errors.push(ConfigurationError::new(
+                "internet.rendezvous.namespace",
+                "needs to be between 1 and 255 characters long",
+            ));
+        }
+        if rendezvous.ttl < RENDEZVOUS_MIN_TTL || rendezvous.ttl > RENDEZVOUS_MAX_TTL {
+            errors.push(ConfigurationError::new(
+                "internet.rendezvous.ttl",
+                &format!(
+                    "needs to be between {} and {} seconds",
+                    RENDEZVOUS_MIN_TTL, RENDEZVOUS_MAX_TTL
+                ),
+            ));
+        }
+        if rendezvous.discover_interval == 0 {
+            errors.push(ConfigurationError::new(
+                "internet.rendezvous.discover_interval",
+                "needs to be at least 1 second",
+            ));
+        }
+
+        // peer exchange
+        let peer_exchange = &self.internet.peer_exchange;
+        if peer_exchange.exchange_interval == 0 {
+            errors.push(ConfigurationError::new(
+                "internet.peer_exchange.exchange_interval",
+                "needs to be at least 1 second",
+            ));
+        }
+        if peer_exchange.seen_period_limit == 0 {
+            errors.push(ConfigurationError::new(
+                "internet.peer_exchange.seen_period_limit",
+                "needs to be at least 1 second",
+            ));
+        }
+
+        // routing
+        let routing = &self.routing;
+        if routing.sending_table_period == 0 {
+            errors.push(ConfigurationError::new(
+                "routing.sending_table_period",
+                "needs to be at least 1 second",
+            ));
+        }
+        if routing.ping_neighbour_period == 0 {
+            errors.push(ConfigurationError::new(
+                "routing.ping_neighbour_period",
+                "needs to be at least 1 second",
+            ));
+        }
+        if routing.maintain_period_limit <= routing.sending_table_period {
+            errors.push(ConfigurationError::new(
+                "routing.maintain_period_limit",
+                "needs to be longer than the sending_table_period",
+            ));
+        }
+
+        // feed
+        let feed = &self.feed;
+        if feed.sync_interval == 0 {
+            errors.push(ConfigurationError::new(
+                "feed.sync_interval",
+                "needs to be at least 1 second",
+            ));
+        }
+        if feed.max_age != 0 && feed.sync_window > feed.max_age {
+            errors.push(ConfigurationError::new(
+                "feed.sync_window",
+                "needs to be shorter than the max_age",
+            ));
+        }
+
+        // DTN storage quotas
+        for (index, user) in self.user_accounts.iter().enumerate() {
+            if user.storage.size_total == 0 {
+                errors.push(ConfigurationError::new(
+                    &format!("user_accounts[{}].storage.size_total", index),
+                    "needs to be at least 1 MB",
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Validate the given sections of the configuration
+    ///
+    /// Invalid values in other sections, e.g. from an older
+    /// config.yaml, don't block changes to these sections.
+    fn validate_sections(
+        &self,
+        sections: &[ConfigurationSection],
+    ) -> Result<(), Vec<ConfigurationError>> {
+        let errors: Vec<ConfigurationError> = match self.validate() {
+            Ok(()) => return Ok(()),
+            Err(errors) => errors
+                .into_iter()
+                .filter(|error| sections.iter().any(|section| section.contains(&error.field)))
+                .collect(),
+        };
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Remove peers with the same address as a previous peer
+    ///
+    /// Older versions didn't validate the configuration, so a
+    /// config.yaml can contain duplicate peers. They would fail
+    /// the validation of every later change of the peers list.
+    fn remove_duplicate_peers(&mut self) {
+        let mut addresses = BTreeSet::new();
+        self.internet.peers.retain(|peer| {
+            if addresses.insert(peer.address.clone()) {
+                true
+            } else {
+                log::warn!("remove duplicate peer {} from configuration", peer.address);
+                false
+            }
+        });
+    }
+
+    /// check that all listening addresses are valid multiaddresses
+    fn validate_listen(field: &str, listen: &Vec<String>, errors: &mut Vec<ConfigurationError>) {
+        for (index, address) in listen.iter().enumerate() {
+            if address.parse::<Multiaddr>().is_err() {
+                errors.push(ConfigurationError::new(
+                    &format!("{}[{}]", field, index),
+                    "invalid multiaddress",
+                ));
+            }
+        }
+    }
+
+    /// put the sections of an update into the configuration
+    fn merge_update(config: &mut Configuration, update: ConfigurationUpdate) {
+        if let Some(lan) = update.lan {
+            config.lan = lan;
+        }
+        if let Some(internet) = update.internet {
+            config.internet = internet;
+        }
+        if let Some(routing) = update.routing {
+            config.routing = routing;
+        }
+        if let Some(debug) = update.debug {
+            config.debug = debug;
+        }
+        if let Some(feed) = update.feed {
+            config.feed = feed;
+        }
+    }
+
+    /// compare two configurations and return the sections that differ
+    fn changed_sections(&self, other: &Configuration) -> Vec<ConfigurationSection> {
+        let mut changed = Vec::new();
+        if self.lan != other.lan {
+            changed.push(ConfigurationSection::Lan);
+        }
+        if self.internet != other.internet {
+            changed.push(ConfigurationSection::Internet);
+        }
+        if self.routing != other.routing {
+            changed.push(ConfigurationSection::Routing);
+        }
+        if self.debug != other.debug {
+            changed.push(ConfigurationSection::Debug);
+        }
+        if self.user_accounts != other.user_accounts {
+            changed.push(ConfigurationSection::UserAccounts);
+        }
+        if self.feed != other.feed {
+            changed.push(ConfigurationSection::Feed);
+        }
+        changed
+    }
+
+    /// inform all subscribers about the changed sections
+    fn notify(changed: &Vec<ConfigurationSection>) {
+        let mut subscribers = SUBSCRIBERS.get().write().unwrap();
+        subscribers.retain(|subscriber| {
+            for section in changed {
+                if subscriber.send(*section).is_err() {
+                    return false;
+                }
+            }
+            true
+        });
+    }
+
     /// Save current configuration to config.yaml file
     pub fn save() {
         let config = CONFIG.get();
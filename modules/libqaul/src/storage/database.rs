@@ -11,7 +11,9 @@ use state::InitCell;
 use std::{collections::BTreeMap, path::Path, sync::RwLock, thread, time::Duration};
 use sysinfo::{Pid, Process, System};
 
-use crate::router::users::UserData;
+use super::encryption::EncryptedTree;
+use crate::router::users::{UserData, UserTrust};
+use crate::utilities::qaul_id::QaulId;
 
 /// make database globally accessible
 static DATABASE: InitCell<RwLock<DataBase>> = InitCell::new();
@@ -208,7 +210,7 @@ impl DbUsers {
         let db = DataBase::get_node_db();
 
         // open tree from data base
-        let tree: sled::Tree = db.open_tree("users").unwrap();
+        let tree = EncryptedTree::open(&db, "users");
 
         // clone key
         let key = user.key.clone();
@@ -225,11 +227,89 @@ impl DbUsers {
     }
 
     // get user table
-    pub fn get_tree() -> sled::Tree {
+    pub fn get_tree() -> EncryptedTree {
         // get data base
         let db = DataBase::get_node_db();
 
         // open tree from data base
-        db.open_tree("users").unwrap()
+        EncryptedTree::open(&db, "users")
     }
-}
\ No newline at end of file
+}
+
+/// Users trust table
+///
+/// Stores the trust level each user account of this node
+/// has in a user, keyed by the account ID followed by the
+/// q8id of the user.
+pub struct DbUsersTrust {}
+
+impl DbUsersTrust {
+    /// Set the trust record of a user account in a user
+    pub fn set(account_id: &PeerId, q8id: &[u8], trust: UserTrust) {
+        let tree = Self::get_tree();
+
+        let trust_bytes = bincode::serialize(&trust).unwrap();
+        if let Err(e) = tree.insert(Self::key(account_id, q8id), trust_bytes) {
+            log::error!("Error saving user trust to data base: {}", e);
+        } else if let Err(e) = tree.flush() {
+            log::error!("Error when flushing data base to disk: {}", e);
+        }
+    }
+
+    /// Get the trust record of a user account in a user
+    pub fn get(account_id: &PeerId, q8id: &[u8]) -> Option<UserTrust> {
+        match Self::get_tree().get(Self::key(account_id, q8id)) {
+            Ok(Some(trust_bytes)) => match bincode::deserialize::<UserTrust>(&trust_bytes) {
+                Ok(trust) => Some(trust),
+                Err(e) => {
+                    log::error!("Error decoding user trust: {}", e);
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// Move the trust records of the node to all user accounts
+    ///
+    /// Before the trust level was kept per account, it was
+    /// stored per node, keyed by the user ID.
+    pub fn migrate_node_records(accounts: &Vec<PeerId>) {
+        let tree = EncryptedTree::open(&DataBase::get_node_db(), "users_trust");
+        if tree.is_empty() {
+            return;
+        }
+
+        for (user_id, trust_bytes) in tree.iter().flatten() {
+            match bincode::deserialize::<UserTrust>(&trust_bytes) {
+                Ok(trust) => {
+                    let q8id = match PeerId::from_bytes(&user_id) {
+                        Ok(id) => QaulId::to_q8id(id),
+                        Err(_) => continue,
+                    };
+                    for account_id in accounts {
+                        Self::set(account_id, &q8id, trust.clone());
+                    }
+                }
+                Err(e) => log::error!("Error decoding user trust: {}", e),
+            }
+            let _ = tree.remove(&user_id);
+        }
+        if let Err(e) = tree.flush() {
+            log::error!("Error when flushing data base to disk: {}", e);
+        }
+    }
+
+    /// data base key of a trust record
+    fn key(account_id: &PeerId, q8id: &[u8]) -> Vec<u8> {
+        let mut key = account_id.to_bytes();
+        key.extend_from_slice(q8id);
+        key
+    }
+
+    /// get user trust table
+    fn get_tree() -> EncryptedTree {
+        let db = DataBase::get_node_db();
+        EncryptedTree::open(&db, "users_account_trust")
+    }
+}
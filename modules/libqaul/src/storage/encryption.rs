@@ -0,0 +1,711 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Encryption at Rest
+//!
+//! Encrypts the records of all libqaul data bases and the private
+//! keys stored in the configuration file.
+//!
+//! The storage key is derived from the storage passphrase with
+//! Argon2id. Records are sealed with ChaCha20-Poly1305 and prefixed
+//! with a small header, so that plaintext records of older versions
+//! can still be read until they have been migrated.
+//!
+//! When encryption is enabled, libqaul starts locked and waits
+//! until it is unlocked via RPC, before the node, the router and
+//! the services are initialized.
+//!
+//! The data base keys stay in plaintext, as they are used for
+//! ordered range queries. They only contain ID's and time stamps.
+
+use argon2::Argon2;
+use base64::Engine;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use libp2p::PeerId;
+use serde::Serialize;
+use sled::IVec;
+use state::InitCell;
+use std::{ops::RangeBounds, sync::RwLock};
+
+use super::configuration::{Configuration, StorageEncryption};
+use super::database::DataBase;
+
+/// header of an encrypted record
+const HEADER: [u8; 3] = [0x71, 0x65, 0x01];
+/// length of the nonce
+const NONCE_LENGTH: usize = 12;
+/// prefix of encrypted key strings in the configuration
+const KEY_PREFIX: &str = "enc:";
+/// known plaintext to verify the passphrase
+const VERIFIER: &[u8] = b"qaul storage key";
+
+/// encryption state
+static STATE: InitCell<RwLock<EncryptionState>> = InitCell::new();
+
+/// Encryption State
+struct EncryptionState {
+    /// encryption at rest is enabled
+    enabled: bool,
+    /// all records are encrypted
+    migrated: bool,
+    /// derived storage key, None when locked
+    key: Option<[u8; 32]>,
+    /// previous storage key, while a passphrase change is migrated
+    previous_key: Option<[u8; 32]>,
+}
+
+/// Encryption Status
+#[derive(Debug, Clone, Serialize)]
+pub struct EncryptionStatus {
+    /// encryption at rest is enabled
+    pub enabled: bool,
+    /// the storage is locked and waits for the passphrase
+    pub locked: bool,
+    /// all records have been encrypted
+    pub migrated: bool,
+}
+
+/// Encryption at Rest Module
+pub struct Encryption {}
+
+impl Encryption {
+    /// initialize encryption state from the configuration
+    pub fn init() {
+        let config = Configuration::get();
+        let state = EncryptionState {
+            enabled: config.storage_encryption.enabled,
+            migrated: config.storage_encryption.migrated,
+            key: None,
+            previous_key: None,
+        };
+        STATE.set(RwLock::new(state));
+    }
+
+    /// get the encryption status
+    ///
+    /// Before the storage is initialized, encryption is reported as disabled.
+    pub fn status() -> EncryptionStatus {
+        match STATE.try_get() {
+            Some(state) => {
+                let state = state.read().unwrap();
+                EncryptionStatus {
+                    enabled: state.enabled,
+                    locked: state.enabled && state.key.is_none(),
+                    migrated: state.migrated,
+                }
+            }
+            None => EncryptionStatus {
+                enabled: false,
+                locked: false,
+                migrated: false,
+            },
+        }
+    }
+
+    /// check if the storage is encrypted and still locked
+    pub fn is_locked() -> bool {
+        Self::status().locked
+    }
+
+    /// Unlock the storage with the passphrase
+    ///
+    /// Unfinished migrations are resumed. After an interrupted
+    /// passphrase change, the previous key is recovered from
+    /// the configuration to re-encrypt the remaining records.
+    pub fn unlock(passphrase: &str) -> Result<(), String> {
+        let settings = Configuration::get().storage_encryption.clone();
+        if !settings.enabled {
+            return Err("storage encryption is not enabled".to_string());
+        }
+        if !Self::is_locked() {
+            return Ok(());
+        }
+
+        let key = Self::verify(passphrase, &settings)?;
+        let previous_key = Self::open_previous_key(&key, &settings)?;
+        {
+            let mut state = STATE.get().write().unwrap();
+            state.key = Some(key);
+            state.previous_key = previous_key;
+        }
+
+        if !settings.migrated {
+            Self::migrate(previous_key, key);
+        }
+
+        Ok(())
+    }
+
+    /// Enable encryption at rest
+    ///
+    /// All existing plaintext records and keys are encrypted.
+    pub fn enable(passphrase: &str) -> Result<(), String> {
+        if passphrase.is_empty() {
+            return Err("passphrase must not be empty".to_string());
+        }
+        if Self::status().enabled {
+            return Err("storage encryption is already enabled".to_string());
+        }
+
+        let (settings, key) = Self::create_settings(passphrase)?;
+        {
+            let mut state = STATE.get().write().unwrap();
+            state.enabled = true;
+            state.migrated = false;
+            state.key = Some(key);
+        }
+        {
+            let mut config = Configuration::get_mut();
+            config.storage_encryption = settings;
+        }
+        Configuration::save();
+
+        Self::migrate(None, key);
+
+        Ok(())
+    }
+
+    /// Change the storage passphrase
+    ///
+    /// All records and keys are re-encrypted with the new storage key.
+    /// The old key is kept in the configuration, sealed with the new
+    /// key, until the migration has finished.
+    pub fn change_passphrase(old_passphrase: &str, new_passphrase: &str) -> Result<(), String> {
+        if new_passphrase.is_empty() {
+            return Err("passphrase must not be empty".to_string());
+        }
+        let settings = Configuration::get().storage_encryption.clone();
+        if !settings.enabled {
+            return Err("storage encryption is not enabled".to_string());
+        }
+
+        let old_key = Self::verify(old_passphrase, &settings)?;
+        if !settings.previous_key.is_empty() {
+            return Err("the previous passphrase change is still being migrated".to_string());
+        }
+        let (mut new_settings, new_key) = Self::create_settings(new_passphrase)?;
+        new_settings.previous_key =
+            base64::engine::general_purpose::STANDARD.encode(Self::seal(&new_key, &old_key));
+
+        {
+            let mut state = STATE.get().write().unwrap();
+            state.migrated = false;
+            state.key = Some(new_key);
+            state.previous_key = Some(old_key);
+        }
+        {
+            let mut config = Configuration::get_mut();
+            config.storage_encryption = new_settings;
+        }
+        Configuration::save();
+
+        Self::migrate(Some(old_key), new_key);
+
+        Ok(())
+    }
+
+    /// create new encryption settings and the derived key
+    fn create_settings(passphrase: &str) -> Result<(StorageEncryption, [u8; 32]), String> {
+        let salt: [u8; 16] = rand::random();
+        let key = Self::derive_key(passphrase, &salt)?;
+        let settings = StorageEncryption {
+            enabled: true,
+            salt: base64::engine::general_purpose::STANDARD.encode(salt),
+            verifier: base64::engine::general_purpose::STANDARD.encode(Self::seal(&key, VERIFIER)),
+            migrated: false,
+            previous_key: String::new(),
+        };
+
+        Ok((settings, key))
+    }
+
+    /// derive the storage key and check it against the verifier
+    fn verify(passphrase: &str, settings: &StorageEncryption) -> Result<[u8; 32], String> {
+        let salt = base64::engine::general_purpose::STANDARD
+            .decode(&settings.salt)
+            .map_err(|e| e.to_string())?;
+        let verifier = base64::engine::general_purpose::STANDARD
+            .decode(&settings.verifier)
+            .map_err(|e| e.to_string())?;
+        let key = Self::derive_key(passphrase, &salt)?;
+
+        match Self::open(&key, &verifier) {
+            Some(plaintext) if plaintext == VERIFIER => Ok(key),
+            _ => Err("wrong passphrase".to_string()),
+        }
+    }
+
+    /// recover the previous key of an unfinished passphrase change
+    fn open_previous_key(
+        key: &[u8; 32],
+        settings: &StorageEncryption,
+    ) -> Result<Option<[u8; 32]>, String> {
+        if settings.previous_key.is_empty() {
+            return Ok(None);
+        }
+        let sealed = base64::engine::general_purpose::STANDARD
+            .decode(&settings.previous_key)
+            .map_err(|e| e.to_string())?;
+        Self::open(key, &sealed)
+            .and_then(|previous_key| previous_key.try_into().ok())
+            .map(Some)
+            .ok_or("previous storage key can't be decrypted".to_string())
+    }
+
+    /// derive a storage key from a passphrase with Argon2id
+    fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| e.to_string())?;
+        Ok(key)
+    }
+
+    /// encrypt a record with the key
+    fn seal(key: &[u8; 32], plaintext: &[u8]) -> Vec<u8> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+        let nonce: [u8; NONCE_LENGTH] = rand::random();
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .expect("encryption of record failed");
+
+        let mut record = Vec::with_capacity(HEADER.len() + NONCE_LENGTH + ciphertext.len());
+        record.extend_from_slice(&HEADER);
+        record.extend_from_slice(&nonce);
+        record.extend_from_slice(&ciphertext);
+        record
+    }
+
+    /// decrypt a record with the key
+    ///
+    /// Returns None if the record is not encrypted or
+    /// can't be decrypted with this key.
+    fn open(key: &[u8; 32], record: &[u8]) -> Option<Vec<u8>> {
+        if !Self::is_sealed(record) {
+            return None;
+        }
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+        let nonce = &record[HEADER.len()..HEADER.len() + NONCE_LENGTH];
+        let ciphertext = &record[HEADER.len() + NONCE_LENGTH..];
+        cipher.decrypt(Nonce::from_slice(nonce), ciphertext).ok()
+    }
+
+    /// check if a record has the header of an encrypted record
+    fn is_sealed(record: &[u8]) -> bool {
+        record.len() > HEADER.len() + NONCE_LENGTH && record[..HEADER.len()] == HEADER
+    }
+
+    /// encrypt a record before it is written to the data base
+    fn encrypt_record(value: &[u8]) -> Vec<u8> {
+        let state = STATE.get().read().unwrap();
+        match state.key {
+            Some(key) if state.enabled => Self::seal(&key, value),
+            _ => value.to_vec(),
+        }
+    }
+
+    /// decrypt a record read from the data base
+    ///
+    /// Records without encryption header, or encrypted with the
+    /// previous key, are returned as long as the migration has
+    /// not finished.
+    fn decrypt_record(value: IVec) -> sled::Result<IVec> {
+        let state = STATE.get().read().unwrap();
+        if !state.enabled {
+            return Ok(value);
+        }
+        for key in state.key.iter().chain(state.previous_key.iter()) {
+            if let Some(plaintext) = Self::open(key, &value) {
+                return Ok(IVec::from(plaintext));
+            }
+        }
+        if !state.migrated && !Self::is_sealed(&value) {
+            return Ok(value);
+        }
+
+        Err(sled::Error::Unsupported(
+            "record can't be decrypted".to_string(),
+        ))
+    }
+
+    /// encrypt a base64 encoded key string for the configuration file
+    pub fn encrypt_key_string(keys: String) -> String {
+        let state = STATE.get().read().unwrap();
+        match state.key {
+            Some(key) if state.enabled => Self::seal_key_string(&key, &keys),
+            _ => keys,
+        }
+    }
+
+    /// decrypt a key string from the configuration file
+    ///
+    /// Returns the base64 encoded keys.
+    pub fn decrypt_key_string(keys: &str) -> Option<String> {
+        if !keys.starts_with(KEY_PREFIX) {
+            return Some(keys.to_string());
+        }
+        let state = STATE.get().read().unwrap();
+        let key = state.key?;
+        Self::open_key_string(&key, keys).or_else(|| {
+            state
+                .previous_key
+                .and_then(|previous_key| Self::open_key_string(&previous_key, keys))
+        })
+    }
+
+    /// encrypt a key string with the storage key
+    fn seal_key_string(key: &[u8; 32], keys: &str) -> String {
+        let sealed = Self::seal(key, keys.as_bytes());
+        KEY_PREFIX.to_string() + &base64::engine::general_purpose::STANDARD.encode(sealed)
+    }
+
+    /// decrypt a key string with the storage key
+    fn open_key_string(key: &[u8; 32], keys: &str) -> Option<String> {
+        let sealed = base64::engine::general_purpose::STANDARD
+            .decode(keys.strip_prefix(KEY_PREFIX)?)
+            .ok()?;
+        String::from_utf8(Self::open(key, &sealed)?).ok()
+    }
+
+    /// Encrypt all plaintext records and keys
+    ///
+    /// Records encrypted with the old key are re-encrypted with
+    /// the new key. When all records are migrated, the migration
+    /// is marked as finished and the old key is removed from the
+    /// configuration.
+    fn migrate(old_key: Option<[u8; 32]>, new_key: [u8; 32]) {
+        log::info!("migrate storage encryption");
+
+        // re-encrypt the keys in the configuration
+        {
+            let mut config = Configuration::get_mut();
+            let node_keys = Self::recrypt_key_string(&config.node.keys, old_key, &new_key);
+            config.node.keys = node_keys;
+            for user in config.user_accounts.iter_mut() {
+                user.keys = Self::recrypt_key_string(&user.keys, old_key, &new_key);
+            }
+        }
+        Configuration::save();
+
+        // re-encrypt all data bases
+        let mut databases = vec![DataBase::get_node_db()];
+        let accounts: Vec<String> = Configuration::get()
+            .user_accounts
+            .iter()
+            .map(|user| user.id.clone())
+            .collect();
+        for id in accounts {
+            match id.parse::<PeerId>() {
+                Ok(account_id) => databases.push(DataBase::get_user_db(account_id)),
+                Err(e) => log::error!("invalid user account id {}: {}", id, e),
+            }
+        }
+        for db in databases {
+            if let Err(e) = Self::migrate_db(&db, old_key, &new_key) {
+                log::error!("storage encryption migration failed: {}", e);
+                return;
+            }
+        }
+
+        // mark migration as finished
+        {
+            let mut state = STATE.get().write().unwrap();
+            state.migrated = true;
+            state.previous_key = None;
+        }
+        {
+            let mut config = Configuration::get_mut();
+            config.storage_encryption.migrated = true;
+            config.storage_encryption.previous_key = String::new();
+        }
+        Configuration::save();
+
+        log::info!("storage encryption migration finished");
+    }
+
+    /// re-encrypt all records of a data base
+    fn migrate_db(
+        db: &sled::Db,
+        old_key: Option<[u8; 32]>,
+        new_key: &[u8; 32],
+    ) -> sled::Result<()> {
+        for name in db.tree_names() {
+            let tree = db.open_tree(&name)?;
+            for entry in tree.iter() {
+                let (key, value) = entry?;
+
+                let plaintext = if !Self::is_sealed(&value) {
+                    value.to_vec()
+                } else if Self::open(new_key, &value).is_some() {
+                    // already encrypted with the new key
+                    continue;
+                } else {
+                    match old_key.and_then(|old_key| Self::open(&old_key, &value)) {
+                        Some(plaintext) => plaintext,
+                        None => {
+                            log::error!("record can't be decrypted during migration");
+                            continue;
+                        }
+                    }
+                };
+                tree.insert(key, Self::seal(new_key, &plaintext))?;
+            }
+            tree.flush()?;
+        }
+        Ok(())
+    }
+
+    /// re-encrypt a key string of the configuration
+    fn recrypt_key_string(keys: &str, old_key: Option<[u8; 32]>, new_key: &[u8; 32]) -> String {
+        let plain = match keys.strip_prefix(KEY_PREFIX) {
+            None => Some(keys.to_string()),
+            Some(_) => match Self::open_key_string(new_key, keys) {
+                Some(_) => return keys.to_string(),
+                None => old_key.and_then(|old_key| Self::open_key_string(&old_key, keys)),
+            },
+        };
+
+        match plain {
+            Some(plain) if !plain.is_empty() => Self::seal_key_string(new_key, &plain),
+            Some(plain) => plain,
+            None => {
+                log::error!("key string can't be decrypted during migration");
+                keys.to_string()
+            }
+        }
+    }
+}
+
+/// Encrypted Data Base Tree
+///
+/// Wraps a sled tree and transparently encrypts and
+/// decrypts all values.
+#[derive(Clone)]
+pub struct EncryptedTree {
+    tree: sled::Tree,
+}
+
+impl EncryptedTree {
+    /// open a tree of a data base
+    pub fn open(db: &sled::Db, name: &str) -> Self {
+        EncryptedTree {
+            tree: db.open_tree(name).unwrap(),
+        }
+    }
+
+    /// insert a value, returns the decrypted previous value
+    pub fn insert<K, V>(&self, key: K, value: V) -> sled::Result<Option<IVec>>
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let record = Encryption::encrypt_record(value.as_ref());
+        self.tree
+            .insert(key, record)?
+            .map(Encryption::decrypt_record)
+            .transpose()
+    }
+
+    /// get a decrypted value
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> sled::Result<Option<IVec>> {
+        self.tree
+            .get(key)?
+            .map(Encryption::decrypt_record)
+            .transpose()
+    }
+
+    /// get the entry with the greatest key less than the given key
+    pub fn get_lt<K: AsRef<[u8]>>(&self, key: K) -> sled::Result<Option<(IVec, IVec)>> {
+        match self.tree.get_lt(key)? {
+            Some((key, value)) => Ok(Some((key, Encryption::decrypt_record(value)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// remove a value, returns the decrypted removed value
+    pub fn remove<K: AsRef<[u8]>>(&self, key: K) -> sled::Result<Option<IVec>> {
+        self.tree
+            .remove(key)?
+            .map(Encryption::decrypt_record)
+            .transpose()
+    }
+
+    /// check if the tree contains a key
+    pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> sled::Result<bool> {
+        self.tree.contains_key(key)
+    }
+
+    /// number of entries in the tree
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// check if the tree is empty
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// flush tree to disk
+    pub fn flush(&self) -> sled::Result<usize> {
+        self.tree.flush()
+    }
+
+    /// iterate over all entries
+    pub fn iter(&self) -> Iter {
+        Iter {
+            iter: self.tree.iter(),
+        }
+    }
+
+    /// iterate over a range of keys
+    pub fn range<K, R>(&self, range: R) -> Iter
+    where
+        K: AsRef<[u8]>,
+        R: RangeBounds<K>,
+    {
+        Iter {
+            iter: self.tree.range(range),
+        }
+    }
+
+    /// iterate over all keys starting with a prefix
+    pub fn scan_prefix<P: AsRef<[u8]>>(&self, prefix: P) -> Iter {
+        Iter {
+            iter: self.tree.scan_prefix(prefix),
+        }
+    }
+}
+
+/// Iterator over decrypted entries of an encrypted tree
+pub struct Iter {
+    iter: sled::Iter,
+}
+
+impl Iterator for Iter {
+    type Item = sled::Result<(IVec, IVec)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|entry| {
+            entry.and_then(|(key, value)| Ok((key, Encryption::decrypt_record(value)?)))
+        })
+    }
+}
+
+impl DoubleEndedIterator for Iter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|entry| {
+            entry.and_then(|(key, value)| Ok((key, Encryption::decrypt_record(value)?)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// open a temporary data base with plaintext records
+    fn plaintext_db() -> sled::Db {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let tree = db.open_tree("messages").unwrap();
+        tree.insert(b"a", b"first record".to_vec()).unwrap();
+        tree.insert(b"b", b"second record".to_vec()).unwrap();
+        db
+    }
+
+    /// decrypt all values of a tree, fails on plaintext records
+    fn open_tree(db: &sled::Db, key: &[u8; 32]) -> Vec<Vec<u8>> {
+        db.open_tree("messages")
+            .unwrap()
+            .iter()
+            .map(|entry| {
+                let (_, value) = entry.unwrap();
+                assert!(Encryption::is_sealed(&value));
+                Encryption::open(key, &value).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn seal_and_open_round_trip() {
+        let key: [u8; 32] = rand::random();
+        let record = Encryption::seal(&key, b"qaul record");
+
+        assert!(Encryption::is_sealed(&record));
+        assert_ne!(&record[HEADER.len() + NONCE_LENGTH..], b"qaul record");
+        assert_eq!(
+            Encryption::open(&key, &record),
+            Some(b"qaul record".to_vec())
+        );
+
+        // plaintext records are not opened
+        assert_eq!(Encryption::open(&key, b"qaul record"), None);
+
+        // key strings of the configuration
+        let keys = Encryption::seal_key_string(&key, "a2V5cw==");
+        assert!(keys.starts_with(KEY_PREFIX));
+        assert_eq!(
+            Encryption::open_key_string(&key, &keys),
+            Some("a2V5cw==".to_string())
+        );
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let (settings, key) = Encryption::create_settings("correct passphrase").unwrap();
+
+        assert_eq!(Encryption::verify("correct passphrase", &settings), Ok(key));
+        assert_eq!(
+            Encryption::verify("wrong passphrase", &settings),
+            Err("wrong passphrase".to_string())
+        );
+
+        // records can't be opened with another key
+        let record = Encryption::seal(&key, b"qaul record");
+        let other_key: [u8; 32] = rand::random();
+        assert_eq!(Encryption::open(&other_key, &record), None);
+    }
+
+    #[test]
+    fn migrate_plaintext_tree() {
+        let db = plaintext_db();
+        let key: [u8; 32] = rand::random();
+
+        Encryption::migrate_db(&db, None, &key).unwrap();
+        assert_eq!(
+            open_tree(&db, &key),
+            vec![b"first record".to_vec(), b"second record".to_vec()]
+        );
+
+        // migrating again leaves the records untouched
+        let sealed = db.open_tree("messages").unwrap().get(b"a").unwrap();
+        Encryption::migrate_db(&db, None, &key).unwrap();
+        assert_eq!(db.open_tree("messages").unwrap().get(b"a").unwrap(), sealed);
+    }
+
+    #[test]
+    fn migrate_tree_to_new_key() {
+        let db = plaintext_db();
+        let old_key: [u8; 32] = rand::random();
+        let new_key: [u8; 32] = rand::random();
+        Encryption::migrate_db(&db, None, &old_key).unwrap();
+
+        // a plaintext record written before the passphrase change
+        db.open_tree("messages")
+            .unwrap()
+            .insert(b"c", b"third record".to_vec())
+            .unwrap();
+
+        Encryption::migrate_db(&db, Some(old_key), &new_key).unwrap();
+        assert_eq!(
+            open_tree(&db, &new_key),
+            vec![
+                b"first record".to_vec(),
+                b"second record".to_vec(),
+                b"third record".to_vec()
+            ]
+        );
+    }
+}
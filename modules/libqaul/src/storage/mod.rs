@@ -7,15 +7,18 @@
 //!
 //! * configuration management
 //! * database handling
+//! * encryption at rest
 
 use libp2p::PeerId;
 use std::path::{Path, PathBuf};
 
 pub mod configuration;
 pub mod database;
+pub mod encryption;
 
 use configuration::Configuration;
 use database::DataBase;
+use encryption::Encryption;
 use state;
 
 /// make storage path accessible
@@ -34,6 +37,9 @@ impl Storage {
         // initialize configuration
         Configuration::init();
 
+        // initialize encryption state
+        Encryption::init();
+
         // initialize data base
         DataBase::init();
     }
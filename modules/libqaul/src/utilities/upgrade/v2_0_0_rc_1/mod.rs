@@ -85,6 +85,7 @@ impl VersionUpgrade {
                             address: multiaddr.to_string(),
                             name: peer.name.clone(),
                             enabled: peer.enabled,
+                            discovered: false,
                         });
                     }
                     None => {}
@@ -95,6 +96,8 @@ impl VersionUpgrade {
                 peers,
                 do_listen: old_cfg.internet.do_listen,
                 listen: old_cfg.internet.listen.clone(),
+                rendezvous: old_cfg.internet.rendezvous.clone(),
+                peer_exchange: old_cfg.internet.peer_exchange.clone(),
             };
 
             // copy user account
@@ -113,6 +116,7 @@ impl VersionUpgrade {
                     about: None,       // Default to None for upgraded configurations
                     reg_no: None,      // Default to None for upgraded configurations
                     college: None,     // Default to None for upgraded configurations
+                    passphrase: None,  // no passphrase protection in older versions
                 });
             }
 
@@ -137,6 +141,8 @@ impl VersionUpgrade {
                 user_accounts,
                 debug,
                 routing,
+                feed: crate::storage::configuration::FeedOptions::default(),
+                storage_encryption: crate::storage::configuration::StorageEncryption::default(),
             };
 
             // save new configuration to file
@@ -0,0 +1,133 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Upgrade to new version 2.0.0-rc.5
+//!
+//! Changes to be upgraded:
+//!
+//! * configuration file: add the encryption at rest section
+//!   - config.storage_encryption
+//!
+//! The data bases are not rewritten by this upgrade:
+//! older versions have no storage passphrase, so there is no
+//! key to encrypt them with. Encryption at rest stays disabled
+//! and the records stay readable as plaintext.
+//!
+//! The data bases are encrypted later, when the user enables
+//! encryption at rest. `Encryption::enable` then seals all
+//! plaintext records of the node and user account data bases.
+//! Until this migration has finished, plaintext records are
+//! still accepted, and an interrupted migration is resumed on
+//! the next unlock.
+
+use std::path::{Path, PathBuf};
+
+use super::backup;
+
+/// # Version Upgrade Logic
+pub struct VersionUpgrade {}
+impl VersionUpgrade {
+    /// Upgrade to new Version
+    ///
+    /// Returns a result, containing a tuple with ( new_version, new_path )
+    pub fn upgrade(storage_path: &Path, old_path: &Path) -> Result<(String, PathBuf), String> {
+        let version = "2.0.0-rc.5";
+        println!("upgrade to version {}", version);
+        let new_path = storage_path.join("backup").join(version);
+
+        // cleanup dest
+        backup::Backup::remove_folder(&new_path);
+
+        // create dest
+        if let Err(_) = std::fs::create_dir(&new_path) {
+            return Err("failed to create destinaton folder".to_string());
+        }
+
+        // move unchanged contents
+        println!("move content");
+        if Self::move_content(Path::new(old_path), &new_path) == false {
+            return Err("Error moving content".to_string());
+        }
+
+        // create new version file
+        println!("create version file");
+        let path = Path::new(new_path.to_str().unwrap()).join("version");
+        if let Err(_) = std::fs::write(path, version) {
+            println!("failed to create version file!");
+        }
+
+        // update config.yaml
+        println!("upgrade config.yaml");
+        if !Self::upgrade_config(old_path, &new_path) {
+            return Err("configuration upgrade failed".to_string());
+        }
+
+        // remove old backup
+        backup::Backup::remove_folder(old_path);
+
+        Ok((version.to_string(), new_path))
+    }
+
+    /// upgrade config structure
+    fn upgrade_config(old_path: &Path, new_path: &Path) -> bool {
+        // load old config
+        if let Some(mut config) = crate::storage::configuration::Configuration::load(
+            old_path.join("config.yaml").to_str().unwrap(),
+        ) {
+            // the existing data bases and keys are not encrypted
+            config.storage_encryption = crate::storage::configuration::StorageEncryption {
+                enabled: false,
+                salt: String::new(),
+                verifier: String::new(),
+                migrated: false,
+                previous_key: String::new(),
+            };
+
+            // save new configuration to file
+            if let Ok(yaml) = serde_yaml_ng::to_string(&config) {
+                if let Err(_) = std::fs::write(Path::new(new_path).join("config.yaml"), yaml) {
+                    println!("Error: creating config.yaml");
+                    return false;
+                }
+            } else {
+                println!("Error: config serialize");
+                return false;
+            }
+            return true;
+        } else {
+            println!("Error: Configuration can't be loaded");
+            false
+        }
+    }
+
+    /// move unchanged content
+    fn move_content(old_path: &Path, new_path: &Path) -> bool {
+        let mut files: Vec<String> = vec![];
+        let mut folders: Vec<String> = vec![];
+
+        for entry_res in std::fs::read_dir(old_path).unwrap() {
+            let entry = entry_res.unwrap();
+            let file_name_buf = entry.file_name();
+            let file_name = file_name_buf.to_str().unwrap();
+
+            if entry.file_type().unwrap().is_dir() {
+                if file_name.starts_with(".") {
+                    continue;
+                }
+                let path = String::from(file_name);
+                folders.push(path);
+            } else {
+                if file_name == "version" || file_name == "config.yaml" {
+                    continue;
+                }
+                let path = String::from(file_name);
+                files.push(path);
+            }
+        }
+
+        if super::backup::Backup::move_files(&files, old_path, new_path) == false {
+            return false;
+        }
+        super::backup::Backup::move_folders(&folders, old_path, new_path)
+    }
+}
@@ -0,0 +1,30 @@
+//!
+//! Example program that permanently deletes everything in the MEGA Rubbish Bin.
+//!
+
+use std::env;
+
+async fn run(mega: &mut mega::Client) -> mega::Result<()> {
+    mega.empty_rubbish_bin().await?;
+
+    println!("rubbish bin successfully emptied !");
+
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let email = env::var("MEGA_EMAIL").expect("missing MEGA_EMAIL environment variable");
+    let password = env::var("MEGA_PASSWORD").expect("missing MEGA_PASSWORD environment variable");
+    let mfa = env::var("MEGA_MFA").ok();
+
+    let http_client = reqwest::Client::new();
+    let mut mega = mega::Client::builder().build(http_client).unwrap();
+
+    mega.login(&email, &password, mfa.as_deref()).await.unwrap();
+
+    let result = run(&mut mega).await;
+    mega.logout().await.unwrap();
+
+    result.unwrap();
+}
@@ -0,0 +1,72 @@
+//!
+//! Example program that lists the previous versions of a file from MEGA,
+//! optionally restoring one of them (by its index in the listing).
+//!
+
+use std::env;
+
+async fn run(
+    mega: &mut mega::Client,
+    distant_file_path: &str,
+    restore_index: Option<usize>,
+) -> mega::Result<()> {
+    let nodes = mega.fetch_own_nodes().await?;
+
+    let node = nodes
+        .get_node_by_path(distant_file_path)
+        .expect("could not find node by path");
+
+    let versions = nodes.versions(node);
+    if versions.is_empty() {
+        println!("{} has no previous versions", node.name());
+        return Ok(());
+    }
+
+    for (index, version) in versions.iter().enumerate() {
+        let modified_at = version
+            .modified_at()
+            .map_or_else(|| String::from("unknown date"), |date| date.to_rfc3339());
+        println!(
+            "[{index}] {} ({} bytes, {modified_at})",
+            version.handle(),
+            version.size()
+        );
+    }
+
+    if let Some(index) = restore_index {
+        let version = versions.get(index).expect("no version with this index");
+        mega.restore_version(&nodes, version).await?;
+        println!("version successfully restored !");
+    }
+
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let email = env::var("MEGA_EMAIL").expect("missing MEGA_EMAIL environment variable");
+    let password = env::var("MEGA_PASSWORD").expect("missing MEGA_PASSWORD environment variable");
+    let mfa = env::var("MEGA_MFA").ok();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (distant_file_path, restore_index) = match args.as_slice() {
+        [distant_file_path] => (distant_file_path, None),
+        [distant_file_path, index] => (
+            distant_file_path,
+            Some(index.parse().expect("invalid version index")),
+        ),
+        _ => panic!(
+            "expected 1 or 2 command-line arguments: {{distant_file_path}} [{{restore_index}}]"
+        ),
+    };
+
+    let http_client = reqwest::Client::new();
+    let mut mega = mega::Client::builder().build(http_client).unwrap();
+
+    mega.login(&email, &password, mfa.as_deref()).await.unwrap();
+
+    let result = run(&mut mega, distant_file_path, restore_index).await;
+    mega.logout().await.unwrap();
+
+    result.unwrap();
+}
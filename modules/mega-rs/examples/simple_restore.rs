@@ -0,0 +1,53 @@
+//!
+//! Example program that moves a file (or folder) from MEGA to the Rubbish Bin,
+//! and then restores it back to where it was.
+//!
+
+use std::env;
+
+async fn run(mega: &mut mega::Client, distant_node_path: &str) -> mega::Result<()> {
+    let nodes = mega.fetch_own_nodes().await?;
+
+    let node = nodes
+        .get_node_by_path(distant_node_path)
+        .expect("could not find node by path");
+
+    mega.move_to_rubbish_bin(&nodes, node).await?;
+
+    println!("node successfully moved to the rubbish bin !");
+
+    // The node needs to be fetched again, to know about its restore attribute.
+    let nodes = mega.fetch_own_nodes().await?;
+
+    let node = nodes
+        .get_node_by_handle(node.handle())
+        .expect("could not find node in the rubbish bin");
+
+    mega.restore_node(&nodes, node).await?;
+
+    println!("node successfully restored !");
+
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let email = env::var("MEGA_EMAIL").expect("missing MEGA_EMAIL environment variable");
+    let password = env::var("MEGA_PASSWORD").expect("missing MEGA_PASSWORD environment variable");
+    let mfa = env::var("MEGA_MFA").ok();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [distant_node_path] = args.as_slice() else {
+        panic!("expected 1 command-line argument: {{distant_node_path}}");
+    };
+
+    let http_client = reqwest::Client::new();
+    let mut mega = mega::Client::builder().build(http_client).unwrap();
+
+    mega.login(&email, &password, mfa.as_deref()).await.unwrap();
+
+    let result = run(&mut mega, distant_node_path).await;
+    mega.logout().await.unwrap();
+
+    result.unwrap();
+}
@@ -20,6 +20,9 @@ pub(crate) struct NodeAttributes {
     /// The last modified date of the node.
     #[serde(rename = "t", skip_serializing_if = "Option::is_none")]
     pub modified_at: Option<i64>,
+    /// The handle of the folder the node was in before being moved to the Rubbish Bin.
+    #[serde(rename = "rr", skip_serializing_if = "Option::is_none")]
+    pub restore_handle: Option<String>,
     /// Catch-all for the remaining fields (if any).
     #[serde(flatten)]
     pub other: HashMap<String, Value>,
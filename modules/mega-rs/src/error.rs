@@ -53,12 +53,24 @@ pub enum Error {
     /// Invalid RSA private key format.
     #[error("invalid RSA private key format")]
     InvalidRsaPrivateKeyFormat,
+    /// Invalid RSA public key format.
+    #[error("invalid RSA public key format")]
+    InvalidRsaPublicKeyFormat,
+    /// The operation is not supported for this kind of node.
+    #[error("unsupported node kind: {kind:?}")]
+    UnsupportedNodeKind {
+        /// The kind of the involved node.
+        kind: crate::NodeKind,
+    },
     /// Failed condensed MAC verification.
     #[error("condensed MAC mismatch")]
     CondensedMacMismatch,
     /// Failed to find node.
     #[error("failed to find node")]
     NodeNotFound,
+    /// The node is not a previous version of a file.
+    #[error("node is not a previous version of a file")]
+    NotAFileVersion,
     /// Failed to find node attribute.
     #[error("failed to find node attribute")]
     NodeAttributeNotFound,
@@ -90,6 +102,14 @@ pub enum Error {
         #[from]
         source: reqwest::Error,
     },
+    /// Filesystem watcher error.
+    #[cfg(feature = "sync")]
+    #[error("filesystem watcher error: {source}")]
+    NotifyError {
+        /// The source `notify` error.
+        #[from]
+        source: notify::Error,
+    },
     /// URL parse error.
     #[error("URL parse error: {source}")]
     UrlError {
@@ -226,10 +226,7 @@ pub async fn compute_condensed_mac<R: AsyncRead>(
     aes_iv: &[u8; 8],
 ) -> Result<[u8; 8]> {
     let mut chunk_size: u64 = 131_072; // 2^17
-    let mut cur_mac = [0u8; 16];
-
-    let mut final_mac_data = [0u8; 16];
-    let mut final_mac = cbc::Encryptor::<Aes128>::new(aes_key.into(), (&final_mac_data).into());
+    let mut chunk_macs = Vec::default();
 
     let mut buffer = {
         let chunk_size = usize::try_from(chunk_size).unwrap();
@@ -238,13 +235,6 @@ pub async fn compute_condensed_mac<R: AsyncRead>(
 
     let mut reader = pin!(reader.take(size));
 
-    let aes_iv = {
-        let mut data = [0u8; 16];
-        data[..8].copy_from_slice(aes_iv);
-        data[8..].copy_from_slice(aes_iv);
-        data
-    };
-
     loop {
         buffer.clear();
 
@@ -257,24 +247,51 @@ pub async fn compute_condensed_mac<R: AsyncRead>(
             break;
         }
 
-        let (chunks, leftover) = buffer.split_at(buffer.len() - buffer.len() % 16);
+        chunk_macs.push(compute_chunk_mac(&buffer, aes_key, aes_iv));
 
-        let mut mac = cbc::Encryptor::<Aes128>::new(aes_key.into(), (&aes_iv).into());
-        for chunk in chunks.chunks_exact(16) {
-            mac.encrypt_block_b2b_mut(chunk.into(), (&mut cur_mac).into());
+        if chunk_size < 1_048_576 {
+            chunk_size += 131_072;
         }
+    }
 
-        if !leftover.is_empty() {
-            let mut padded_chunk = [0u8; 16];
-            padded_chunk[..leftover.len()].copy_from_slice(leftover);
-            mac.encrypt_block_b2b_mut((&padded_chunk).into(), (&mut cur_mac).into());
-        }
+    Ok(condense_chunk_macs(&chunk_macs, aes_key))
+}
 
-        final_mac.encrypt_block_b2b_mut((&cur_mac).into(), (&mut final_mac_data).into());
+/// Computes the MAC of a single chunk of a file (as delimited by MEGA's chunk boundaries).
+///
+/// The chunk MACs are independent from one another, which allows to compute them for chunks transferred out of order.
+pub(crate) fn compute_chunk_mac(data: &[u8], aes_key: &[u8; 16], aes_iv: &[u8; 8]) -> [u8; 16] {
+    let aes_iv = {
+        let mut data = [0u8; 16];
+        data[..8].copy_from_slice(aes_iv);
+        data[8..].copy_from_slice(aes_iv);
+        data
+    };
 
-        if chunk_size < 1_048_576 {
-            chunk_size += 131_072;
-        }
+    let mut cur_mac = [0u8; 16];
+    let (chunks, leftover) = data.split_at(data.len() - data.len() % 16);
+
+    let mut mac = cbc::Encryptor::<Aes128>::new(aes_key.into(), (&aes_iv).into());
+    for chunk in chunks.chunks_exact(16) {
+        mac.encrypt_block_b2b_mut(chunk.into(), (&mut cur_mac).into());
+    }
+
+    if !leftover.is_empty() {
+        let mut padded_chunk = [0u8; 16];
+        padded_chunk[..leftover.len()].copy_from_slice(leftover);
+        mac.encrypt_block_b2b_mut((&padded_chunk).into(), (&mut cur_mac).into());
+    }
+
+    cur_mac
+}
+
+/// Condenses the MACs of all the chunks of a file (in order) into the file's condensed MAC.
+pub(crate) fn condense_chunk_macs(chunk_macs: &[[u8; 16]], aes_key: &[u8; 16]) -> [u8; 8] {
+    let mut final_mac_data = [0u8; 16];
+    let mut final_mac = cbc::Encryptor::<Aes128>::new(aes_key.into(), (&final_mac_data).into());
+
+    for cur_mac in chunk_macs {
+        final_mac.encrypt_block_b2b_mut(cur_mac.into(), (&mut final_mac_data).into());
     }
 
     for i in 0..4 {
@@ -282,5 +299,5 @@ pub async fn compute_condensed_mac<R: AsyncRead>(
         final_mac_data[i + 4] = final_mac_data[i + 8] ^ final_mac_data[i + 12];
     }
 
-    Ok(final_mac_data[..8].try_into().unwrap())
+    final_mac_data[..8].try_into().unwrap()
 }
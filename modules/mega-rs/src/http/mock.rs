@@ -0,0 +1,141 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::io::{AsyncRead, AsyncReadExt, Cursor};
+use url::Url;
+
+use crate::error::Result;
+use crate::http::{ClientState, HttpClient};
+use crate::protocol::commands::{Request, Response};
+
+/// An `HttpClient` answering API requests with queued responses, for tests.
+///
+/// It also serves (and receives) the contents of a single remote file, for transfers.
+#[derive(Debug, Default)]
+pub(crate) struct MockClient {
+    /// The raw responses to the upcoming requests, in order.
+    responses: Mutex<VecDeque<json::Value>>,
+    /// The requests sent so far, as they would have been serialized.
+    requests: Arc<Mutex<Vec<json::Value>>>,
+    /// The (encrypted) contents of the remote file.
+    remote: Arc<Mutex<Vec<u8>>>,
+    /// The offsets and lengths of the uploaded chunks.
+    received: Mutex<BTreeMap<u64, u64>>,
+    /// The ranges requested so far.
+    downloads: Arc<Mutex<Vec<(u64, u64)>>>,
+    /// The amount of downloads to serve before failing all others.
+    failing_after: Option<usize>,
+}
+
+impl MockClient {
+    /// Creates a mock client answering with the given raw responses, in order.
+    pub(crate) fn new(responses: impl IntoIterator<Item = json::Value>) -> Self {
+        Self {
+            responses: Mutex::new(responses.into_iter().collect()),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the (encrypted) contents of the remote file.
+    pub(crate) fn with_remote(self, data: Vec<u8>) -> Self {
+        *self.remote.lock().unwrap() = data;
+        self
+    }
+
+    /// Makes all downloads fail after the given amount of them succeeded.
+    pub(crate) fn failing_after(mut self, downloads: usize) -> Self {
+        self.failing_after = Some(downloads);
+        self
+    }
+
+    /// Returns a handle to the requests sent so far (which stays valid once the client is boxed).
+    pub(crate) fn requests(&self) -> Arc<Mutex<Vec<json::Value>>> {
+        Arc::clone(&self.requests)
+    }
+
+    /// Returns a handle to the contents of the remote file.
+    pub(crate) fn remote(&self) -> Arc<Mutex<Vec<u8>>> {
+        Arc::clone(&self.remote)
+    }
+
+    /// Returns a handle to the ranges downloaded so far.
+    pub(crate) fn downloads(&self) -> Arc<Mutex<Vec<(u64, u64)>>> {
+        Arc::clone(&self.downloads)
+    }
+}
+
+/// Extracts the last path segment of a transfer URL (the range or offset of the chunk).
+fn last_segment(url: &Url) -> &str {
+    url.path_segments()
+        .and_then(Iterator::last)
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl HttpClient for MockClient {
+    async fn send_requests(
+        &self,
+        _state: &ClientState,
+        requests: &[Request],
+        _query_params: &[(&str, &str)],
+    ) -> Result<Vec<Response>> {
+        let mut responses = Vec::with_capacity(requests.len());
+        for request in requests {
+            self.requests.lock().unwrap().push(json::to_value(request)?);
+            let value = (self.responses.lock().unwrap().pop_front())
+                .expect("no response queued for this request");
+            responses.push(request.parse_response_data(value)?);
+        }
+        Ok(responses)
+    }
+
+    async fn get(&self, url: Url) -> Result<Pin<Box<dyn AsyncRead + Send>>> {
+        let (start, end) = last_segment(&url)
+            .split_once('-')
+            .and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)))
+            .expect("unexpected GET request");
+
+        let mut downloads = self.downloads.lock().unwrap();
+        if self
+            .failing_after
+            .is_some_and(|amount| downloads.len() >= amount)
+        {
+            return Err(io::Error::from(io::ErrorKind::ConnectionReset).into());
+        }
+        downloads.push((start, end));
+
+        let remote = self.remote.lock().unwrap();
+        let range = usize::try_from(start).unwrap()..=usize::try_from(end).unwrap();
+        Ok(Box::pin(Cursor::new(remote[range].to_vec())))
+    }
+
+    async fn post(
+        &self,
+        url: Url,
+        mut body: Pin<Box<dyn AsyncRead + Send + Sync>>,
+        _content_length: Option<u64>,
+    ) -> Result<Pin<Box<dyn AsyncRead + Send>>> {
+        let offset: u64 = last_segment(&url).parse().expect("unexpected POST request");
+
+        let mut data = Vec::default();
+        body.read_to_end(&mut data).await?;
+
+        let mut remote = self.remote.lock().unwrap();
+        let start = usize::try_from(offset).unwrap();
+        remote[start..(start + data.len())].copy_from_slice(&data);
+
+        // The completion handle gets returned once all of the file's data got received.
+        let mut received = self.received.lock().unwrap();
+        received.insert(offset, data.len() as u64);
+        let response = if received.values().sum::<u64>() == remote.len() as u64 {
+            "CompletionHandle"
+        } else {
+            ""
+        };
+
+        Ok(Box::pin(Cursor::new(response.as_bytes().to_vec())))
+    }
+}
@@ -11,6 +11,9 @@ use zeroize::Zeroize;
 #[cfg(feature = "reqwest")]
 mod reqwest;
 
+#[cfg(test)]
+pub(crate) mod mock;
+
 use crate::error::Result;
 use crate::protocol::commands::{Request, Response};
 use crate::utils::rsa::RsaPrivateKey;
@@ -23,21 +23,29 @@ mod fingerprint;
 mod http;
 mod protocol;
 mod sessions;
+#[cfg(feature = "sync")]
+mod sync;
+mod transfers;
 mod utils;
 
 pub use crate::error::{Error, ErrorCode, Result};
 pub use crate::fingerprint::{compute_condensed_mac, compute_sparse_checksum};
-pub use crate::protocol::commands::{FileNode, NodeKind};
+pub use crate::protocol::commands::{FileNode, NodeKind, ShareAccess};
 pub use crate::sessions::SessionInfo;
+#[cfg(feature = "sync")]
+pub use crate::sync::{SyncEngine, SyncOptions, SyncReport};
+pub use crate::transfers::{TransferOptions, TransferProgress};
 pub use crate::utils::StorageQuotas;
 
 use crate::attributes::NodeAttributes;
 use crate::fingerprint::NodeFingerprint;
 use crate::http::{ClientState, HttpClient, UserSession};
-use crate::protocol::commands::{Request, Response, UploadAttributes};
+use crate::protocol::commands::{
+    CopyAttributes, DownloadResponse, Request, Response, ShareTarget, UploadAttributes,
+};
 use crate::protocol::events::{EventBatchResponse, EventResponse, EventResponseKind};
 use crate::protocol::{FILE_KEY_SIZE, FOLDER_KEY_SIZE, USER_KEY_SIZE, USER_SID_SIZE};
-use crate::utils::rsa::RsaPrivateKey;
+use crate::utils::rsa::{RsaPrivateKey, RsaPublicKey};
 
 pub(crate) const DEFAULT_API_ORIGIN: &str = "https://g.api.mega.co.nz/";
 
@@ -533,8 +541,10 @@ impl Client {
         let responses = self.send_requests(&[request_1, request_2]).await?;
 
         let (files, attr) = match responses.as_slice() {
-            [Response::FetchNodes(files), Response::UserAttributes(attr)] => (files, attr),
-            [Response::Error(code)] => {
+            [Response::FetchNodes(files), Response::UserAttributes(attr)] => (files, Some(attr)),
+            // Accounts that never migrated to the `^!keys` attribute don't have one.
+            [Response::FetchNodes(files), Response::Error(_)] => (files, None),
+            [Response::Error(code), ..] => {
                 return Err(Error::from(*code));
             }
             _ => {
@@ -543,16 +553,44 @@ impl Client {
         };
 
         let mut nodes = HashMap::<String, Node>::default();
-        let share_keys = utils::extract_share_keys(&session, attr)?;
+        let mut share_keys = match attr {
+            Some(attr) => utils::extract_share_keys(session, attr)?,
+            None => HashMap::default(),
+        };
 
-        // This method of getting share keys seems to be unneeded.
-        // (maybe an earlier implementation that got decommissionned/deprecated ?).
-        //
-        // for share in files.ok.iter().flatten() {
-        //     let mut share_key = BASE64_URL_SAFE_NO_PAD.decode(&share.key)?;
-        //     utils::decrypt_ebc_in_place(&session.key, &mut share_key);
-        //     share_keys.insert(share.handle.clone(), share_key);
-        // }
+        // Keys of our own outgoing shares, encrypted with the master key
+        // (superseded by the `^!keys` attribute, which takes precedence).
+        for share in files.ok.iter().flatten() {
+            let mut share_key = BASE64_URL_SAFE_NO_PAD.decode(&share.key)?;
+            if share_key.len() != FOLDER_KEY_SIZE {
+                continue;
+            }
+            utils::decrypt_ebc_in_place(&session.key, &mut share_key);
+            share_keys.entry(share.hash.clone()).or_insert(share_key);
+        }
+
+        // Keys of incoming shares, attached to the root node of each share.
+        for file in &files.nodes {
+            let Some((_, s_key)) = file.s_user.as_deref().zip(file.s_key.as_deref()) else {
+                continue;
+            };
+
+            let share_key = if s_key.len() >= 44 {
+                // Keys bigger than this size are using RSA instead of AES.
+                let data = BASE64_URL_SAFE_NO_PAD.decode(s_key)?;
+                let (encrypted, _) = utils::rsa::get_mpi(&data)?;
+                session.privk.decrypt_key(encrypted, FOLDER_KEY_SIZE)
+            } else {
+                let mut share_key = BASE64_URL_SAFE_NO_PAD.decode(s_key)?;
+                if share_key.len() != FOLDER_KEY_SIZE {
+                    continue;
+                }
+                utils::decrypt_ebc_in_place(&session.key, &mut share_key);
+                share_key
+            };
+
+            share_keys.insert(file.handle.clone(), share_key);
+        }
 
         for file in &files.nodes {
             let (thumbnail_handle, preview_image_handle) = file
@@ -563,13 +601,6 @@ impl Client {
 
             match file.kind {
                 NodeKind::File | NodeKind::Folder => {
-                    // if let Some((_, s_key)) = file.s_user.as_deref().zip(file.s_key.as_deref()) {
-                    //     let mut share_key = BASE64_URL_SAFE_NO_PAD.decode(s_key)?;
-                    //     utils::decrypt_ebc_in_place(&session.key, &mut share_key);
-                    //     utils::decrypt_ebc_in_place(&share_key, &mut file_key);
-                    //     share_keys.insert(file.handle.clone(), share_key.clone());
-                    // }
-
                     let Some(file_key) = file.key.as_deref() else {
                         continue;
                     };
@@ -681,6 +712,10 @@ impl Client {
                         download_id: None,
                         thumbnail_handle,
                         preview_image_handle,
+                        restore_handle: attrs.restore_handle,
+                        share_key: share_keys
+                            .get(&file.handle)
+                            .and_then(|key| key.as_slice().try_into().ok()),
                     };
 
                     if let Some(parent) = nodes.get_mut(&file.parent) {
@@ -713,6 +748,8 @@ impl Client {
                         download_id: None,
                         thumbnail_handle,
                         preview_image_handle,
+                        restore_handle: None,
+                        share_key: None,
                     };
                     nodes.insert(node.handle.clone(), node);
                 }
@@ -740,6 +777,8 @@ impl Client {
                         download_id: None,
                         thumbnail_handle,
                         preview_image_handle,
+                        restore_handle: None,
+                        share_key: None,
                     };
                     nodes.insert(node.handle.clone(), node);
                 }
@@ -767,6 +806,8 @@ impl Client {
                         download_id: None,
                         thumbnail_handle,
                         preview_image_handle,
+                        restore_handle: None,
+                        share_key: None,
                     };
                     nodes.insert(node.handle.clone(), node);
                 }
@@ -888,6 +929,8 @@ impl Client {
                     download_id: Some(node_id.to_string()),
                     thumbnail_handle: None,
                     preview_image_handle: None,
+                    restore_handle: None,
+                    share_key: None,
                 };
 
                 nodes.insert(node.handle.clone(), node);
@@ -1017,6 +1060,8 @@ impl Client {
                                 download_id: Some(node_id.to_string()),
                                 thumbnail_handle,
                                 preview_image_handle,
+                                restore_handle: attrs.restore_handle,
+                                share_key: None,
                             };
 
                             if let Some(parent) = nodes.get_mut(&file.parent) {
@@ -1045,72 +1090,7 @@ impl Client {
     /// - https://mega.nz/#P!{payload}
     #[allow(rustdoc::bare_urls)]
     pub async fn fetch_protected_nodes(&self, url: &str, password: &str) -> Result<Nodes> {
-        let decoded_full = match url.split_at(19) {
-            ("https://mega.nz/#P!", payload) => BASE64_URL_SAFE_NO_PAD.decode(payload)?,
-            _ => {
-                return Err(Error::InvalidUrlFormat);
-            }
-        };
-
-        if decoded_full.len() < 2 {
-            return Err(Error::UrlTooShort);
-        }
-
-        let (&algorithm, rest) = decoded_full.split_first().unwrap();
-        let (&kind, rest) = rest.split_first().unwrap();
-
-        let (key_size, is_folder) = match kind {
-            0 => (FOLDER_KEY_SIZE, true),
-            1 => (FILE_KEY_SIZE, false),
-            _ => {
-                return Err(Error::InvalidUrlFormat);
-            }
-        };
-
-        if decoded_full.len() < 72 + key_size {
-            return Err(Error::UrlTooShort);
-        }
-
-        let (handle, rest) = rest.split_at(6);
-        let (salt, rest) = rest.split_at(32);
-        let (key, mac) = rest.split_at(key_size);
-
-        let dec_key = pbkdf2_hmac_array::<Sha512, 64>(password.as_bytes(), salt, 100_000);
-
-        match algorithm {
-            1 => {
-                // It's fine to unwrap here, HMAC can take keys of any size.
-                let mut hmac: Hmac<Sha256> =
-                    Mac::new_from_slice(&decoded_full[..40 + key_size]).unwrap();
-                hmac.update(&dec_key[32..64]);
-                hmac.verify_slice(mac)?;
-            }
-            2 => {
-                // It's fine to unwrap here, HMAC can take keys of any size.
-                let mut hmac: Hmac<Sha256> = Mac::new_from_slice(&dec_key[32..64]).unwrap();
-                hmac.update(&decoded_full[..40 + key_size]);
-                hmac.verify_slice(mac)?;
-            }
-            version => {
-                return Err(Error::InvalidAlgorithmVersion { version });
-            }
-        }
-
-        let handle = BASE64_URL_SAFE_NO_PAD.encode(handle);
-        let kind = if is_folder { "folder" } else { "file" };
-
-        let derived_key = {
-            let derived_key: Vec<u8> = key
-                .iter()
-                .copied()
-                .zip(dec_key.into_iter())
-                .map(|(a, b)| a ^ b)
-                .collect();
-
-            BASE64_URL_SAFE_NO_PAD.encode(&derived_key)
-        };
-
-        let url = format!("https://mega.nz/{kind}/{handle}#{derived_key}");
+        let url = unprotect_public_url(url, password)?;
         self.fetch_public_nodes(&url).await
     }
 
@@ -1130,8 +1110,8 @@ impl Client {
         })
     }
 
-    /// Downloads a file into the given writer.
-    pub async fn download_node<W: AsyncWrite>(&self, node: &Node, writer: W) -> Result<()> {
+    /// Requests the URL to download a file from.
+    pub(crate) async fn request_download(&self, node: &Node) -> Result<DownloadResponse> {
         let responses = if let Some(download_id) = node.download_id() {
             let request = if node.handle.as_str() == download_id {
                 Request::Download {
@@ -1163,15 +1143,16 @@ impl Client {
             self.send_requests(&[request]).await?
         };
 
-        let response = match responses.as_slice() {
-            [Response::Download(response)] => response,
-            [Response::Error(code)] => {
-                return Err(Error::from(*code));
-            }
-            _ => {
-                return Err(Error::InvalidResponseType);
-            }
-        };
+        match responses.as_slice() {
+            [Response::Download(response)] => Ok(response.clone()),
+            [Response::Error(code)] => Err(Error::from(*code)),
+            _ => Err(Error::InvalidResponseType),
+        }
+    }
+
+    /// Downloads a file into the given writer.
+    pub async fn download_node<W: AsyncWrite>(&self, node: &Node, writer: W) -> Result<()> {
+        let response = self.request_download(node).await?;
 
         let url =
             Url::parse(format!("{0}/{1}-{2}", response.download_url, 0, response.size).as_str())?;
@@ -1250,12 +1231,9 @@ impl Client {
         reader: R,
         last_modified: LastModified,
     ) -> Result<()> {
-        let session = self
-            .state
-            .session
-            .as_ref()
-            .ok_or(Error::MissingUserSession)?
-            .expose_secret();
+        if !self.has_user_session() {
+            return Err(Error::MissingUserSession);
+        }
 
         let request = Request::Upload {
             s: size,
@@ -1352,6 +1330,37 @@ impl Client {
             upload_future,
         )?;
 
+        self.complete_upload(
+            parent,
+            name,
+            (aes_key, aes_iv[..8].try_into().unwrap()),
+            condensed_mac,
+            sparse_checksum,
+            last_modified,
+            completion_handle,
+        )
+        .await
+    }
+
+    /// Attaches the uploaded data as a new file node within a parent folder.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn complete_upload(
+        &self,
+        parent: &Node,
+        name: &str,
+        (aes_key, aes_iv): ([u8; 16], [u8; 8]),
+        condensed_mac: [u8; 8],
+        sparse_checksum: [u8; 16],
+        last_modified: LastModified,
+        completion_handle: String,
+    ) -> Result<()> {
+        let session = self
+            .state
+            .session
+            .as_ref()
+            .ok_or(Error::MissingUserSession)?
+            .expose_secret();
+
         let attributes = {
             let last_modified = last_modified.resolve().timestamp();
             let fingerprint = NodeFingerprint::new(sparse_checksum, last_modified);
@@ -1359,6 +1368,7 @@ impl Client {
                 name: name.to_string(),
                 fingerprint: Some(fingerprint.serialize()),
                 modified_at: None,
+                restore_handle: None,
                 other: HashMap::default(),
             }
         };
@@ -1370,7 +1380,7 @@ impl Client {
 
         let mut key = [0u8; FILE_KEY_SIZE];
         key[..16].copy_from_slice(&aes_key);
-        key[16..24].copy_from_slice(&aes_iv[..8]);
+        key[16..24].copy_from_slice(&aes_iv);
         key[24..].copy_from_slice(&condensed_mac);
         utils::merge_key_mac(&mut key);
 
@@ -1630,6 +1640,7 @@ impl Client {
             name: name.to_string(),
             fingerprint: None,
             modified_at: None,
+            restore_handle: None,
             other: HashMap::default(),
         };
 
@@ -1675,33 +1686,12 @@ impl Client {
 
     /// Renames a node.
     pub async fn rename_node(&self, node: &Node, name: &str) -> Result<()> {
-        let attributes = {
-            let fingerprint =
-                (node.sparse_checksum.zip(node.modified_at)).map(|(checksum, modified_at)| {
-                    NodeFingerprint::new(checksum, modified_at.timestamp()).serialize()
-                });
-
-            NodeAttributes {
-                name: name.to_string(),
-                fingerprint,
-                modified_at: None,
-                other: HashMap::default(),
-            }
-        };
-
-        let attributes_buffer = {
-            let buffer = attributes.pack_and_encrypt(&node.aes_key)?;
-            BASE64_URL_SAFE_NO_PAD.encode(&buffer)
+        let attributes = NodeAttributes {
+            name: name.to_string(),
+            ..node.attributes()
         };
 
-        let idempotence_id = utils::random_string(10);
-
-        let request = Request::SetFileAttributes {
-            n: node.handle.clone().into(),
-            key: None,
-            attr: attributes_buffer,
-            i: idempotence_id,
-        };
+        let request = set_attributes_request(node, &attributes)?;
 
         let responses = self.send_requests(&[request]).await?;
 
@@ -1767,6 +1757,539 @@ impl Client {
         Ok(())
     }
 
+    /// Copies a node into a folder (server-side), optionally under a different name.
+    ///
+    /// Folders are copied along with all of their descendants (but without the previous versions of
+    /// their files). The keys of the copies get re-encrypted with the user's master key, so copies of
+    /// nodes from incoming shares stay accessible even after the share goes away.
+    pub async fn copy_node(
+        &self,
+        nodes: &Nodes,
+        node: &Node,
+        parent: &Node,
+        name: Option<&str>,
+    ) -> Result<()> {
+        let session = self
+            .state
+            .session
+            .as_ref()
+            .ok_or(Error::MissingUserSession)?
+            .expose_secret();
+
+        let mut copies = Vec::new();
+        let mut queue = vec![(node, None)];
+        while let Some((current, copy_parent)) = queue.pop() {
+            let mut attributes = current.attributes();
+            attributes.restore_handle = None;
+            if copy_parent.is_none() {
+                if let Some(name) = name {
+                    attributes.name = name.to_string();
+                }
+            }
+
+            let attr = {
+                let buffer = attributes.pack_and_encrypt(&current.aes_key)?;
+                BASE64_URL_SAFE_NO_PAD.encode(&buffer)
+            };
+
+            let key = {
+                let mut key = current.key_data();
+                utils::encrypt_ebc_in_place(&session.key, &mut key);
+                BASE64_URL_SAFE_NO_PAD.encode(&key)
+            };
+
+            copies.push(CopyAttributes {
+                handle: current.handle.clone(),
+                parent: copy_parent,
+                kind: current.kind,
+                attr,
+                key,
+            });
+
+            // The children of files are their previous versions, which aren't copied.
+            if current.kind.is_folder() {
+                let children = current
+                    .children
+                    .iter()
+                    .filter_map(|handle| nodes.get_node_by_handle(handle))
+                    .map(|child| (child, Some(current.handle.clone())));
+                queue.extend(children);
+            }
+        }
+
+        let idempotence_id = utils::random_string(10);
+
+        let request = Request::Copy {
+            t: parent.handle.clone().into(),
+            n: copies,
+            ov: None,
+            i: idempotence_id,
+        };
+
+        let responses = self.send_requests(&[request]).await?;
+
+        match responses.as_slice() {
+            [Response::Copy(_)] => {}
+            [Response::Error(code)] => {
+                return Err(Error::from(*code));
+            }
+            _ => {
+                return Err(Error::InvalidResponseType);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Restores a previous version of a file (as returned by [`Nodes::versions`]).
+    ///
+    /// The previous version gets copied to become the new current version, so the file's history
+    /// is kept (the version being replaced becomes a previous version itself).
+    pub async fn restore_version(&self, nodes: &Nodes, version: &Node) -> Result<()> {
+        let session = self
+            .state
+            .session
+            .as_ref()
+            .ok_or(Error::MissingUserSession)?
+            .expose_secret();
+
+        let current = nodes.current_version(version);
+        if current.handle == version.handle {
+            return Err(Error::NotAFileVersion);
+        }
+        let parent = current.parent.as_deref().ok_or(Error::NodeNotFound)?;
+
+        let attr = {
+            let attributes = NodeAttributes {
+                name: current.name.clone(),
+                ..version.attributes()
+            };
+            let buffer = attributes.pack_and_encrypt(&version.aes_key)?;
+            BASE64_URL_SAFE_NO_PAD.encode(&buffer)
+        };
+
+        let key = {
+            let mut key = version.key_data();
+            utils::encrypt_ebc_in_place(&session.key, &mut key);
+            BASE64_URL_SAFE_NO_PAD.encode(&key)
+        };
+
+        let idempotence_id = utils::random_string(10);
+
+        let request = Request::Copy {
+            t: parent.to_string().into(),
+            n: vec![CopyAttributes {
+                handle: version.handle.clone(),
+                parent: None,
+                kind: version.kind,
+                attr,
+                key,
+            }],
+            ov: Some(current.handle.clone()),
+            i: idempotence_id,
+        };
+
+        let responses = self.send_requests(&[request]).await?;
+
+        match responses.as_slice() {
+            [Response::Copy(_)] => {}
+            [Response::Error(code)] => {
+                return Err(Error::from(*code));
+            }
+            _ => {
+                return Err(Error::InvalidResponseType);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deletes all the previous versions of a file, keeping only its current version.
+    pub async fn delete_versions(&self, nodes: &Nodes, node: &Node) -> Result<()> {
+        let requests: Vec<_> = nodes
+            .versions(node)
+            .into_iter()
+            .map(|version| Request::Delete {
+                n: version.handle.clone().into(),
+                i: utils::random_string(10),
+            })
+            .collect();
+
+        if requests.is_empty() {
+            return Ok(());
+        }
+
+        let responses = self.send_requests(&requests).await?;
+
+        for response in responses {
+            match response {
+                Response::Error(ErrorCode::OK) => {}
+                Response::Error(code) => {
+                    return Err(Error::from(code));
+                }
+                _ => {
+                    return Err(Error::InvalidResponseType);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves a node to the Rubbish Bin, remembering where it was so that it can be restored later
+    /// (with [`Client::restore_node`], or from MEGA's own clients).
+    pub async fn move_to_rubbish_bin(&self, nodes: &Nodes, node: &Node) -> Result<()> {
+        let rubbish_bin = nodes.rubbish_bin().ok_or(Error::NodeNotFound)?;
+
+        let attributes = NodeAttributes {
+            restore_handle: node.parent.clone(),
+            ..node.attributes()
+        };
+
+        let requests = [
+            set_attributes_request(node, &attributes)?,
+            Request::Move {
+                n: node.handle.clone().into(),
+                t: rubbish_bin.handle.clone().into(),
+                i: utils::random_string(10),
+            },
+        ];
+
+        let responses = self.send_requests(&requests).await?;
+
+        for response in responses {
+            match response {
+                Response::Error(ErrorCode::OK) => {}
+                Response::Error(code) => {
+                    return Err(Error::from(code));
+                }
+                _ => {
+                    return Err(Error::InvalidResponseType);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Restores a node from the Rubbish Bin to the folder it was in before being deleted.
+    ///
+    /// Falls back to the root of the Cloud Drive if that folder is unknown, gone or deleted too.
+    pub async fn restore_node(&self, nodes: &Nodes, node: &Node) -> Result<()> {
+        let parent = node
+            .restore_handle
+            .as_deref()
+            .and_then(|handle| nodes.get_node_by_handle(handle))
+            .filter(|parent| !nodes.is_in_rubbish_bin(parent))
+            .or_else(|| nodes.cloud_drive())
+            .ok_or(Error::NodeNotFound)?;
+
+        let attributes = NodeAttributes {
+            restore_handle: None,
+            ..node.attributes()
+        };
+
+        let requests = [
+            Request::Move {
+                n: node.handle.clone().into(),
+                t: parent.handle.clone().into(),
+                i: utils::random_string(10),
+            },
+            set_attributes_request(node, &attributes)?,
+        ];
+
+        let responses = self.send_requests(&requests).await?;
+
+        for response in responses {
+            match response {
+                Response::Error(ErrorCode::OK) => {}
+                Response::Error(code) => {
+                    return Err(Error::from(code));
+                }
+                _ => {
+                    return Err(Error::InvalidResponseType);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Permanently deletes everything in the Rubbish Bin.
+    pub async fn empty_rubbish_bin(&self) -> Result<()> {
+        let request = Request::CleanRubbishBin {};
+
+        let responses = self.send_requests(&[request]).await?;
+
+        match responses.as_slice() {
+            [Response::Error(ErrorCode::OK) | Response::CleanRubbishBin(_)] => {}
+            [Response::Error(code)] => {
+                return Err(Error::from(*code));
+            }
+            _ => {
+                return Err(Error::InvalidResponseType);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a public link for a file or folder, returning its URL.
+    ///
+    /// Exporting a folder shares it with the `EXP` pseudo-user, which re-encrypts the keys of all its
+    /// descendants with the share key (hence the need for the whole `nodes` collection).
+    ///
+    /// Returned URL formats:
+    /// - https://mega.nz/file/{node_id}#{node_key}
+    /// - https://mega.nz/folder/{node_id}#{node_key}
+    /// - https://mega.nz/#P!{payload} (if a password is set)
+    #[allow(rustdoc::bare_urls)]
+    pub async fn export_node(
+        &self,
+        nodes: &Nodes,
+        node: &Node,
+        options: &ExportOptions,
+    ) -> Result<String> {
+        let session = self
+            .state
+            .session
+            .as_ref()
+            .ok_or(Error::MissingUserSession)?
+            .expose_secret();
+
+        let request = Request::ExportLink {
+            n: node.handle.clone().into(),
+            ets: options.expires_at.map(|it| it.timestamp()),
+            d: None,
+            i: utils::random_string(10),
+        };
+
+        let (responses, link_key) = match node.kind {
+            NodeKind::File => {
+                let responses = self.send_requests(&[request]).await?;
+                (responses, node.key_data())
+            }
+            NodeKind::Folder => {
+                let share_key = node.share_key.unwrap_or_else(rand::random);
+                let target = ShareTarget {
+                    user: String::from("EXP"),
+                    access: Some(ShareAccess::ReadOnly),
+                    key: None,
+                };
+                let share = Self::share_request(session, nodes, node, &share_key, target);
+                let responses = self.send_requests(&[share, request]).await?;
+                (responses, share_key.to_vec())
+            }
+            kind => {
+                return Err(Error::UnsupportedNodeKind { kind });
+            }
+        };
+
+        let response = match responses.as_slice() {
+            [response] => response,
+            [share, response] => {
+                check_share_response(share)?;
+                response
+            }
+            _ => {
+                return Err(Error::InvalidResponseType);
+            }
+        };
+
+        let public_handle = match response {
+            Response::ExportLink(response) => response.handle.as_str(),
+            Response::Error(code) => {
+                return Err(Error::from(*code));
+            }
+            _ => {
+                return Err(Error::InvalidResponseType);
+            }
+        };
+
+        let Some(password) = options.password.as_deref() else {
+            let kind = if node.kind.is_folder() {
+                "folder"
+            } else {
+                "file"
+            };
+            let key = BASE64_URL_SAFE_NO_PAD.encode(&link_key);
+            return Ok(format!("https://mega.nz/{kind}/{public_handle}#{key}"));
+        };
+
+        protect_public_url(node.kind, public_handle, &link_key, password)
+    }
+
+    /// Deletes the public link of a file or folder.
+    pub async fn revoke_export(&self, node: &Node) -> Result<()> {
+        let request = Request::ExportLink {
+            n: node.handle.clone().into(),
+            ets: None,
+            d: Some(1),
+            i: utils::random_string(10),
+        };
+
+        let responses = match node.kind {
+            NodeKind::File => self.send_requests(&[request]).await?,
+            NodeKind::Folder => {
+                // Exported folders are also shared with the `EXP` pseudo-user.
+                let unshare = Request::Share {
+                    n: node.handle.clone().into(),
+                    s: vec![ShareTarget {
+                        user: String::from("EXP"),
+                        access: None,
+                        key: None,
+                    }],
+                    ok: None,
+                    ha: None,
+                    cr: None,
+                    i: utils::random_string(10),
+                };
+                self.send_requests(&[request, unshare]).await?
+            }
+            kind => {
+                return Err(Error::UnsupportedNodeKind { kind });
+            }
+        };
+
+        match responses.as_slice() {
+            [Response::Error(ErrorCode::OK)] => {}
+            [Response::Error(ErrorCode::OK), share] => {
+                check_share_response(share)?;
+            }
+            [Response::Error(code), ..] => {
+                return Err(Error::from(*code));
+            }
+            _ => {
+                return Err(Error::InvalidResponseType);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Shares a folder with another MEGA user.
+    ///
+    /// The share key gets encrypted with the RSA public key of the recipient,
+    /// and the keys of all the folder's descendants are re-encrypted with the share key
+    /// (hence the need for the whole `nodes` collection).
+    pub async fn share_folder(
+        &self,
+        nodes: &Nodes,
+        node: &Node,
+        email: &str,
+        access: ShareAccess,
+    ) -> Result<()> {
+        let session = self
+            .state
+            .session
+            .as_ref()
+            .ok_or(Error::MissingUserSession)?
+            .expose_secret();
+
+        if !node.kind.is_folder() {
+            return Err(Error::UnsupportedNodeKind { kind: node.kind });
+        }
+
+        let email = email.to_lowercase();
+
+        let request = Request::UserPublicKey {
+            u: email.clone().into(),
+        };
+        let responses = self.send_requests(&[request]).await?;
+
+        let response = match responses.as_slice() {
+            [Response::UserPublicKey(response)] => response,
+            [Response::Error(code)] => {
+                return Err(Error::from(*code));
+            }
+            _ => {
+                return Err(Error::InvalidResponseType);
+            }
+        };
+
+        let public_key = {
+            let data = BASE64_URL_SAFE_NO_PAD.decode(&response.pubk)?;
+            RsaPublicKey::from_mpi_bytes(&data)?
+        };
+
+        let share_key = node.share_key.unwrap_or_else(rand::random);
+        let target = ShareTarget {
+            user: email,
+            access: Some(access),
+            key: Some(BASE64_URL_SAFE_NO_PAD.encode(public_key.encrypt_key(&share_key))),
+        };
+
+        let request = Self::share_request(session, nodes, node, &share_key, target);
+        let responses = self.send_requests(&[request]).await?;
+
+        match responses.as_slice() {
+            [response] => check_share_response(response),
+            _ => Err(Error::InvalidResponseType),
+        }
+    }
+
+    /// Stops sharing a folder with another MEGA user.
+    pub async fn unshare_folder(&self, node: &Node, email: &str) -> Result<()> {
+        let request = Request::Share {
+            n: node.handle.clone().into(),
+            s: vec![ShareTarget {
+                user: email.to_lowercase(),
+                access: None,
+                key: None,
+            }],
+            ok: None,
+            ha: None,
+            cr: None,
+            i: utils::random_string(10),
+        };
+
+        let responses = self.send_requests(&[request]).await?;
+
+        match responses.as_slice() {
+            [response] => check_share_response(response),
+            _ => Err(Error::InvalidResponseType),
+        }
+    }
+
+    /// Builds the request sharing a folder with the given target.
+    fn share_request(
+        session: &UserSession,
+        nodes: &Nodes,
+        node: &Node,
+        share_key: &[u8; 16],
+        target: ShareTarget,
+    ) -> Request {
+        let mut encrypted_share_key = *share_key;
+        utils::encrypt_ebc_in_place(&session.key, &mut encrypted_share_key);
+
+        // The keys of every node within the share must be available under the share key,
+        // for the recipients to be able to decrypt them.
+        let mut handles = Vec::default();
+        let mut keys = Vec::default();
+        let mut queue = vec![node];
+        while let Some(current) = queue.pop() {
+            let mut key = current.key_data();
+            utils::encrypt_ebc_in_place(share_key, &mut key);
+
+            keys.push(json::Value::from(0));
+            keys.push(json::Value::from(handles.len()));
+            keys.push(json::Value::from(BASE64_URL_SAFE_NO_PAD.encode(&key)));
+            handles.push(current.handle.clone());
+
+            let children = current.children.iter();
+            queue.extend(children.filter_map(|handle| nodes.get_node_by_handle(handle)));
+        }
+
+        Request::Share {
+            n: node.handle.clone().into(),
+            s: vec![target],
+            ok: Some(BASE64_URL_SAFE_NO_PAD.encode(encrypted_share_key)),
+            ha: Some(utils::share_handle_auth(&session.key, &node.handle)),
+            cr: Some((vec![node.handle.clone()], handles, keys)),
+            i: utils::random_string(10),
+        }
+    }
+
     /// Performs a request for events, respecting the configured rules about timeouts and retries.
     async fn request_events(&self, event_cursor: &str) -> Result<EventBatchResponse> {
         let url = {
@@ -1874,6 +2397,7 @@ impl Client {
                         sparse_checksum: fingerprint.map(|it| it.checksum),
                         created_at: Some(Utc.timestamp_opt(event.ts, 0).unwrap()),
                         modified_at,
+                        restore_handle: attrs.restore_handle,
                     };
 
                     events.push(Event::NodeUpdated { attrs });
@@ -1961,6 +2485,7 @@ impl Client {
                         sparse_checksum: fingerprint.map(|it| it.checksum),
                         created_at: Some(Utc.timestamp_opt(event.ts, 0).unwrap()),
                         modified_at,
+                        restore_handle: attrs.restore_handle,
                     };
 
                     events.push(Event::NodeUpdated { attrs });
@@ -1984,6 +2509,21 @@ impl Client {
     }
 }
 
+/// Builds the request for replacing the attributes of a node.
+fn set_attributes_request(node: &Node, attributes: &NodeAttributes) -> Result<Request> {
+    let attributes_buffer = {
+        let buffer = attributes.pack_and_encrypt(&node.aes_key)?;
+        BASE64_URL_SAFE_NO_PAD.encode(&buffer)
+    };
+
+    Ok(Request::SetFileAttributes {
+        n: node.handle.clone().into(),
+        key: None,
+        attr: attributes_buffer,
+        i: utils::random_string(10),
+    })
+}
+
 fn construct_event_node(
     session: &UserSession,
     nodes: &Nodes,
@@ -2060,11 +2600,12 @@ fn construct_event_node(
                         return Some(file_key);
                     }
 
-                    // if let Some(share_key) = share_keys.get(file_user) {
-                    //     // shared file or folder
-                    //     utils::decrypt_ebc_in_place(&share_key, &mut file_key);
-                    //     return Some(file_key);
-                    // }
+                    let share_node = nodes.get_node_by_handle(file_user);
+                    if let Some(share_key) = share_node.and_then(|it| it.share_key.as_ref()) {
+                        // shared file or folder
+                        utils::decrypt_ebc_in_place(share_key, &mut file_key);
+                        return Some(file_key);
+                    }
 
                     None
                 }
@@ -2114,6 +2655,7 @@ fn construct_event_node(
                 download_id: nodes.download_id.clone(),
                 thumbnail_handle,
                 preview_image_handle,
+                restore_handle: attrs.restore_handle,
             }))
         }
         NodeKind::Root => Ok(Some(EventNode {
@@ -2132,6 +2674,7 @@ fn construct_event_node(
             download_id: nodes.download_id.clone(),
             thumbnail_handle,
             preview_image_handle,
+            restore_handle: None,
         })),
         NodeKind::Inbox => Ok(Some(EventNode {
             name: String::from("Inbox"),
@@ -2149,6 +2692,7 @@ fn construct_event_node(
             download_id: nodes.download_id.clone(),
             thumbnail_handle,
             preview_image_handle,
+            restore_handle: None,
         })),
         NodeKind::Trash => Ok(Some(EventNode {
             name: String::from("Trash"),
@@ -2166,11 +2710,122 @@ fn construct_event_node(
             download_id: nodes.download_id.clone(),
             thumbnail_handle,
             preview_image_handle,
+            restore_handle: None,
         })),
         NodeKind::Unknown => Ok(None),
     }
 }
 
+/// Resolves a password-protected MEGA link into its regular public link.
+fn unprotect_public_url(url: &str, password: &str) -> Result<String> {
+    let decoded_full = match url.split_at(19) {
+        ("https://mega.nz/#P!", payload) => BASE64_URL_SAFE_NO_PAD.decode(payload)?,
+        _ => {
+            return Err(Error::InvalidUrlFormat);
+        }
+    };
+
+    if decoded_full.len() < 2 {
+        return Err(Error::UrlTooShort);
+    }
+
+    let (&algorithm, rest) = decoded_full.split_first().unwrap();
+    let (&kind, rest) = rest.split_first().unwrap();
+
+    let (key_size, is_folder) = match kind {
+        0 => (FOLDER_KEY_SIZE, true),
+        1 => (FILE_KEY_SIZE, false),
+        _ => {
+            return Err(Error::InvalidUrlFormat);
+        }
+    };
+
+    if decoded_full.len() < 72 + key_size {
+        return Err(Error::UrlTooShort);
+    }
+
+    let (handle, rest) = rest.split_at(6);
+    let (salt, rest) = rest.split_at(32);
+    let (key, mac) = rest.split_at(key_size);
+
+    let dec_key = pbkdf2_hmac_array::<Sha512, 64>(password.as_bytes(), salt, 100_000);
+
+    match algorithm {
+        1 => {
+            // It's fine to unwrap here, HMAC can take keys of any size.
+            let mut hmac: Hmac<Sha256> =
+                Mac::new_from_slice(&decoded_full[..40 + key_size]).unwrap();
+            hmac.update(&dec_key[32..64]);
+            hmac.verify_slice(mac)?;
+        }
+        2 => {
+            // It's fine to unwrap here, HMAC can take keys of any size.
+            let mut hmac: Hmac<Sha256> = Mac::new_from_slice(&dec_key[32..64]).unwrap();
+            hmac.update(&decoded_full[..40 + key_size]);
+            hmac.verify_slice(mac)?;
+        }
+        version => {
+            return Err(Error::InvalidAlgorithmVersion { version });
+        }
+    }
+
+    let handle = BASE64_URL_SAFE_NO_PAD.encode(handle);
+    let kind = if is_folder { "folder" } else { "file" };
+
+    let derived_key = {
+        let derived_key: Vec<u8> = key
+            .iter()
+            .copied()
+            .zip(dec_key.into_iter())
+            .map(|(a, b)| a ^ b)
+            .collect();
+
+        BASE64_URL_SAFE_NO_PAD.encode(&derived_key)
+    };
+
+    Ok(format!("https://mega.nz/{kind}/{handle}#{derived_key}"))
+}
+
+/// Protects a public MEGA link with a password (the reverse of `unprotect_public_url`, using algorithm version 2).
+fn protect_public_url(
+    kind: NodeKind,
+    public_handle: &str,
+    key: &[u8],
+    password: &str,
+) -> Result<String> {
+    let handle = BASE64_URL_SAFE_NO_PAD.decode(public_handle)?;
+    let salt: [u8; 32] = rand::random();
+    let dec_key = pbkdf2_hmac_array::<Sha512, 64>(password.as_bytes(), &salt, 100_000);
+
+    let mut payload = vec![2, u8::from(kind.is_file())];
+    payload.extend_from_slice(&handle);
+    payload.extend_from_slice(&salt);
+    payload.extend(key.iter().zip(dec_key).map(|(a, b)| a ^ b));
+
+    // It's fine to unwrap here, HMAC can take keys of any size.
+    let mut hmac: Hmac<Sha256> = Mac::new_from_slice(&dec_key[32..64]).unwrap();
+    hmac.update(&payload);
+    payload.extend_from_slice(&hmac.finalize().into_bytes());
+
+    let payload = BASE64_URL_SAFE_NO_PAD.encode(&payload);
+    Ok(format!("https://mega.nz/#P!{payload}"))
+}
+
+/// Checks the response to a `Request::Share` message, including the results for each involved user.
+fn check_share_response(response: &Response) -> Result<()> {
+    match response {
+        Response::Error(ErrorCode::OK) => Ok(()),
+        Response::Error(code) => Err(Error::from(*code)),
+        Response::Share(response) => {
+            match response.results.iter().find(|code| **code != ErrorCode::OK) {
+                Some(code) => Err(Error::from(*code)),
+                None => Ok(()),
+            }
+        }
+        _ => Err(Error::InvalidResponseType),
+    }
+}
+
 /// Represents a node, as part of an [`Event`].
 #[derive(Debug, PartialEq)]
 pub struct EventNode {
@@ -2204,6 +2859,8 @@ pub struct EventNode {
     pub(crate) thumbnail_handle: Option<String>,
     /// The handle of the node's preview image.
     pub(crate) preview_image_handle: Option<String>,
+    /// The handle of the folder the node was in before being moved to the Rubbish Bin.
+    pub(crate) restore_handle: Option<String>,
 }
 
 impl EventNode {
@@ -2298,6 +2955,8 @@ pub struct EventNodeAttributes {
     pub(crate) created_at: Option<DateTime<Utc>>,
     /// The last modification date of the node.
     pub(crate) modified_at: Option<DateTime<Utc>>,
+    /// The handle of the folder the node was in before being moved to the Rubbish Bin.
+    pub(crate) restore_handle: Option<String>,
 }
 
 impl EventNodeAttributes {
@@ -2376,7 +3035,7 @@ impl EventBatch {
 }
 
 /// Represents a node stored in MEGA (either a file or a folder).
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Node {
     /// The name of the node.
     pub(crate) name: String,
@@ -2410,6 +3069,10 @@ pub struct Node {
     pub(crate) thumbnail_handle: Option<String>,
     /// The handle of the node's preview image.
     pub(crate) preview_image_handle: Option<String>,
+    /// The share key of the node, if it is the root of a share.
+    pub(crate) share_key: Option<[u8; 16]>,
+    /// The handle of the folder the node was in before being moved to the Rubbish Bin.
+    pub(crate) restore_handle: Option<String>,
 }
 
 impl Node {
@@ -2492,6 +3155,43 @@ impl Node {
     pub fn has_preview_image(&self) -> bool {
         self.preview_image_handle.is_some()
     }
+
+    /// Returns the attributes of the node, as stored by MEGA.
+    pub(crate) fn attributes(&self) -> NodeAttributes {
+        let fingerprint =
+            (self.sparse_checksum.zip(self.modified_at)).map(|(checksum, modified_at)| {
+                NodeFingerprint::new(checksum, modified_at.timestamp()).serialize()
+            });
+
+        NodeAttributes {
+            name: self.name.clone(),
+            fingerprint,
+            modified_at: None,
+            restore_handle: self.restore_handle.clone(),
+            other: HashMap::default(),
+        }
+    }
+
+    /// Returns the key data of the node, as stored by MEGA (with the MAC merged in, for files).
+    pub(crate) fn key_data(&self) -> Vec<u8> {
+        let Some((aes_iv, condensed_mac)) = self.aes_iv.zip(self.condensed_mac) else {
+            return self.aes_key.to_vec();
+        };
+
+        let mut key = [self.aes_key.as_slice(), &aes_iv, &condensed_mac].concat();
+        utils::merge_key_mac(&mut key);
+        key
+    }
+
+    /// Returns the handle of the folder the node was in before being moved to the Rubbish Bin.
+    pub fn restore_handle(&self) -> Option<&str> {
+        self.restore_handle.as_deref()
+    }
+
+    /// Returns whether this node is the root of a share (incoming, outgoing or public link).
+    pub fn is_share_root(&self) -> bool {
+        self.share_key.is_some()
+    }
 }
 
 /// Represents a collection of nodes from MEGA.
@@ -2595,6 +3295,52 @@ impl Nodes {
         self.nodes.get(handle)
     }
 
+    /// Checks whether a node is (somewhere) inside the Rubbish Bin.
+    pub fn is_in_rubbish_bin(&self, node: &Node) -> bool {
+        let Some(rubbish_bin) = self.rubbish_bin.as_deref() else {
+            return false;
+        };
+
+        let mut parent = node.parent.as_deref();
+        while let Some(handle) = parent {
+            if handle == rubbish_bin {
+                return true;
+            }
+            parent = self.nodes.get(handle).and_then(|it| it.parent.as_deref());
+        }
+        false
+    }
+
+    /// Gets the previous versions of a file, from the newest to the oldest.
+    ///
+    /// MEGA stores the previous version of a file as its child, so versions form a chain.
+    pub fn versions(&self, node: &Node) -> Vec<&Node> {
+        let mut versions = Vec::new();
+        let mut current = node;
+        while let Some(previous) = current
+            .children
+            .iter()
+            .filter_map(|handle| self.nodes.get(handle))
+            .find(|it| it.kind.is_file())
+        {
+            versions.push(previous);
+            current = previous;
+        }
+        versions
+    }
+
+    /// Gets the current version of a file, given any of its versions.
+    pub fn current_version<'a>(&'a self, node: &'a Node) -> &'a Node {
+        let mut current = node;
+        while let Some(parent) = current.parent.as_deref().and_then(|it| self.nodes.get(it)) {
+            if !parent.kind.is_file() {
+                break;
+            }
+            current = parent;
+        }
+        current
+    }
+
     /// Creates a borrowing iterator over the nodes.
     pub fn iter(&self) -> impl Iterator<Item = &Node> {
         self.nodes.values()
@@ -2639,6 +3385,9 @@ impl Nodes {
             })
             .collect();
 
+        // Moved or updated nodes keep being the root of their share.
+        let share_key = self.nodes.get(&node.handle).and_then(|it| it.share_key);
+
         let new_node = Node {
             name: node.name,
             handle: node.handle,
@@ -2656,6 +3405,8 @@ impl Nodes {
             download_id: node.download_id,
             thumbnail_handle: node.thumbnail_handle,
             preview_image_handle: node.preview_image_handle,
+            restore_handle: node.restore_handle,
+            share_key,
         };
 
         // Add this node to its parent's children.
@@ -2696,6 +3447,7 @@ impl Nodes {
         node.sparse_checksum = attrs.sparse_checksum;
         node.created_at = attrs.created_at;
         node.modified_at = attrs.modified_at;
+        node.restore_handle = attrs.restore_handle;
     }
 
     pub(crate) fn delete_node(&mut self, handle: &str) {
@@ -2744,6 +3496,15 @@ impl From<AttributeKind> for u8 {
     }
 }
 
+/// Represents the options for exporting a node as a public link.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExportOptions {
+    /// The date after which the link stops working (only available to PRO accounts).
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The password to protect the link with (producing a `#P!` link).
+    pub password: Option<String>,
+}
+
 /// Represents the last modified date to set for a newly uploaded MEGA node.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LastModified {
@@ -2778,3 +3539,437 @@ pub struct UserInfo {
     /// The country code of the user.
     pub country_code: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+    use rsa::traits::{PrivateKeyParts, PublicKeyParts};
+
+    use super::*;
+    use crate::http::mock::MockClient;
+
+    pub(crate) const MASTER_KEY: [u8; 16] = [7; 16];
+    pub(crate) const USER_HANDLE: &str = "OwnUser0000";
+
+    fn generate_rsa_keys() -> (RsaPrivateKey, RsaPublicKey) {
+        let key = rsa::RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let private_key = RsaPrivateKey {
+            p: key.primes()[0].clone(),
+            q: key.primes()[1].clone(),
+            d: key.d().clone(),
+            u: <_>::default(),
+        };
+        let public_key = RsaPublicKey {
+            n: key.n().clone(),
+            e: key.e().clone(),
+        };
+        (private_key, public_key)
+    }
+
+    pub(crate) fn test_client(client: MockClient, privk: RsaPrivateKey) -> Client {
+        let mut client = Client::builder().build(client).unwrap();
+        client.state.session = Some(SecretBox::new(Box::new(UserSession {
+            sid: String::from("sid"),
+            key: MASTER_KEY,
+            sek: <_>::default(),
+            privk,
+            user_handle: USER_HANDLE.to_string(),
+        })));
+        client
+    }
+
+    pub(crate) fn test_node(handle: &str, kind: NodeKind, parent: &str, children: &[&str]) -> Node {
+        Node {
+            name: handle.to_lowercase(),
+            handle: handle.to_string(),
+            owner: USER_HANDLE.to_string(),
+            size: 0,
+            kind,
+            parent: Some(parent.to_string()),
+            children: children.iter().map(|it| it.to_string()).collect(),
+            aes_key: rand::random(),
+            aes_iv: kind.is_file().then(rand::random),
+            condensed_mac: kind.is_file().then(rand::random),
+            sparse_checksum: None,
+            created_at: None,
+            modified_at: None,
+            download_id: None,
+            thumbnail_handle: None,
+            preview_image_handle: None,
+            restore_handle: None,
+            share_key: None,
+        }
+    }
+
+    fn encrypt_b64(key: &[u8], data: &[u8]) -> String {
+        let mut data = data.to_vec();
+        utils::encrypt_ebc_in_place(key, &mut data);
+        BASE64_URL_SAFE_NO_PAD.encode(&data)
+    }
+
+    fn decrypt_b64(key: &[u8], data: &json::Value) -> Vec<u8> {
+        let mut data = BASE64_URL_SAFE_NO_PAD
+            .decode(data.as_str().unwrap())
+            .unwrap();
+        utils::decrypt_ebc_in_place(key, &mut data);
+        data
+    }
+
+    fn encrypted_attrs(name: &str, key: &[u8; 16]) -> String {
+        let attrs = NodeAttributes {
+            name: name.to_string(),
+            fingerprint: None,
+            modified_at: None,
+            restore_handle: None,
+            other: HashMap::default(),
+        };
+        BASE64_URL_SAFE_NO_PAD.encode(attrs.pack_and_encrypt(key).unwrap())
+    }
+
+    #[test]
+    fn export_file_link() {
+        let mock = MockClient::new([json::json!("AAECAwQF")]);
+        let requests = mock.requests();
+        let client = test_client(mock, RsaPrivateKey::default());
+
+        let file = test_node("FILE0000", NodeKind::File, "ROOT0000", &[]);
+        let nodes = Nodes::new(HashMap::default(), String::default(), None);
+        let options = ExportOptions {
+            expires_at: Utc.timestamp_opt(1_900_000_000, 0).single(),
+            password: None,
+        };
+
+        let url = block_on(client.export_node(&nodes, &file, &options)).unwrap();
+
+        let mut key = BASE64_URL_SAFE_NO_PAD
+            .decode(url.strip_prefix("https://mega.nz/file/AAECAwQF#").unwrap())
+            .unwrap();
+        utils::unmerge_key_mac(&mut key);
+        assert_eq!(&key[..16], file.aes_key());
+        assert_eq!(&key[16..24], file.aes_iv().unwrap());
+        assert_eq!(&key[24..], file.condensed_mac().unwrap());
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["a"], "l");
+        assert_eq!(requests[0]["n"], "FILE0000");
+        assert_eq!(requests[0]["ets"], 1_900_000_000);
+        assert!(requests[0].get("d").is_none());
+    }
+
+    #[test]
+    fn export_protected_folder_link() {
+        let mock = MockClient::new([json::json!(0), json::json!("AAECAwQF")]);
+        let requests = mock.requests();
+        let client = test_client(mock, RsaPrivateKey::default());
+
+        let folder = test_node("FOLD0000", NodeKind::Folder, "ROOT0000", &["FILE0000"]);
+        let file = test_node("FILE0000", NodeKind::File, "FOLD0000", &[]);
+        let (folder_key, file_key) = (folder.key_data(), file.key_data());
+        let nodes = Nodes::new(
+            HashMap::from([(folder.handle.clone(), folder), (file.handle.clone(), file)]),
+            String::default(),
+            None,
+        );
+        let folder = nodes.get_node_by_handle("FOLD0000").unwrap();
+        let options = ExportOptions {
+            expires_at: None,
+            password: Some(String::from("hunter2")),
+        };
+
+        let url = block_on(client.export_node(&nodes, folder, &options)).unwrap();
+
+        let requests = requests.lock().unwrap();
+        let [share, export] = requests.as_slice() else {
+            panic!("unexpected requests: {requests:?}");
+        };
+        assert_eq!(share["a"], "s2");
+        assert_eq!(share["s"], json::json!([{ "u": "EXP", "r": 0 }]));
+        assert_eq!(
+            share["ha"],
+            utils::share_handle_auth(&MASTER_KEY, "FOLD0000")
+        );
+        assert_eq!(export["a"], "l");
+
+        // The share key is stored for ourselves, and every node key is re-encrypted with it.
+        let share_key = decrypt_b64(&MASTER_KEY, &share["ok"]);
+        assert_eq!(share["cr"][0], json::json!(["FOLD0000"]));
+        assert_eq!(share["cr"][1], json::json!(["FOLD0000", "FILE0000"]));
+        assert_eq!(decrypt_b64(&share_key, &share["cr"][2][2]), folder_key);
+        assert_eq!(decrypt_b64(&share_key, &share["cr"][2][5]), file_key);
+
+        let public_url = unprotect_public_url(&url, "hunter2").unwrap();
+        let key = BASE64_URL_SAFE_NO_PAD.encode(&share_key);
+        assert_eq!(public_url, format!("https://mega.nz/folder/AAECAwQF#{key}"));
+        assert!(matches!(
+            unprotect_public_url(&url, "hunter3"),
+            Err(Error::HmacMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn share_folder_with_user() {
+        let (recipient_privk, recipient_pubk) = generate_rsa_keys();
+        let pubk = [
+            utils::rsa::to_mpi(&recipient_pubk.n),
+            utils::rsa::to_mpi(&recipient_pubk.e),
+        ]
+        .concat();
+
+        let mock = MockClient::new([
+            json::json!({ "u": "Recipient00", "pubk": BASE64_URL_SAFE_NO_PAD.encode(pubk) }),
+            json::json!({ "r": [0], "u": [] }),
+            json::json!({ "r": [-11] }),
+        ]);
+        let requests = mock.requests();
+        let client = test_client(mock, RsaPrivateKey::default());
+
+        // An already shared folder keeps its share key.
+        let mut folder = test_node("FOLD0000", NodeKind::Folder, "ROOT0000", &[]);
+        folder.share_key = Some([9; 16]);
+        let nodes = Nodes::new(HashMap::default(), String::default(), None);
+
+        block_on(client.share_folder(
+            &nodes,
+            &folder,
+            "Friend@Example.com",
+            ShareAccess::ReadWrite,
+        ))
+        .unwrap();
+
+        let result = block_on(client.unshare_folder(&folder, "friend@example.com"));
+        assert!(matches!(
+            result,
+            Err(Error::MegaError {
+                code: ErrorCode::EACCESS
+            })
+        ));
+
+        let requests = requests.lock().unwrap();
+        let [public_key, share, unshare] = requests.as_slice() else {
+            panic!("unexpected requests: {requests:?}");
+        };
+        assert_eq!(
+            public_key,
+            &json::json!({ "a": "uk", "u": "friend@example.com" })
+        );
+        assert_eq!(share["s"][0]["u"], "friend@example.com");
+        assert_eq!(share["s"][0]["r"], 1);
+        assert_eq!(decrypt_b64(&MASTER_KEY, &share["ok"]), [9; 16]);
+
+        let encrypted = BASE64_URL_SAFE_NO_PAD
+            .decode(share["s"][0]["k"].as_str().unwrap())
+            .unwrap();
+        let (encrypted, _) = utils::rsa::get_mpi(&encrypted).unwrap();
+        assert_eq!(recipient_privk.decrypt_key(encrypted, 16), [9; 16]);
+
+        assert_eq!(unshare["s"], json::json!([{ "u": "friend@example.com" }]));
+        assert!(unshare.get("ok").is_none());
+    }
+
+    #[test]
+    fn fetch_own_nodes_decrypts_shares() {
+        let (privk, pubk) = generate_rsa_keys();
+
+        // incoming share, with its share key encrypted with our public key.
+        let in_share_key: [u8; 16] = rand::random();
+        let in_folder_key: [u8; 16] = rand::random();
+        let in_file = test_node("FILE0000", NodeKind::File, "INSH0000", &[]);
+
+        // outgoing share, with its share key encrypted with our master key.
+        let out_share_key: [u8; 16] = rand::random();
+        let out_folder_key: [u8; 16] = rand::random();
+
+        let files = json::json!({
+            "f": [
+                { "t": 2, "a": "", "h": "ROOT0000", "p": "", "ts": 0, "u": USER_HANDLE },
+                {
+                    "t": 1,
+                    "a": encrypted_attrs("Shared with me", &in_folder_key),
+                    "h": "INSH0000",
+                    "p": "FOREIGN0",
+                    "ts": 0,
+                    "u": "Sharer00000",
+                    "k": format!("INSH0000:{}", encrypt_b64(&in_share_key, &in_folder_key)),
+                    "su": "Sharer00000",
+                    "sk": BASE64_URL_SAFE_NO_PAD.encode(pubk.encrypt_key(&in_share_key)),
+                },
+                {
+                    "t": 0,
+                    "a": encrypted_attrs("paper.pdf", in_file.aes_key()),
+                    "h": "FILE0000",
+                    "p": "INSH0000",
+                    "ts": 0,
+                    "u": "Sharer00000",
+                    "k": format!("INSH0000:{}", encrypt_b64(&in_share_key, &in_file.key_data())),
+                    "s": 42,
+                },
+                {
+                    "t": 1,
+                    "a": encrypted_attrs("Shared by me", &out_folder_key),
+                    "h": "OUTS0000",
+                    "p": "ROOT0000",
+                    "ts": 0,
+                    "u": USER_HANDLE,
+                    "k": format!("{USER_HANDLE}:{}", encrypt_b64(&MASTER_KEY, &out_folder_key)),
+                },
+            ],
+            "ok": [{ "h": "OUTS0000", "ha": "", "k": encrypt_b64(&MASTER_KEY, &out_share_key) }],
+            "sn": "cursor",
+        });
+
+        // -9 (ENOENT): this account doesn't have a `^!keys` attribute.
+        let mock = MockClient::new([files, json::json!(-9)]);
+        let client = test_client(mock, privk);
+
+        let nodes = block_on(client.fetch_own_nodes()).unwrap();
+        assert_eq!(nodes.len(), 4);
+
+        let in_share = nodes.get_node_by_handle("INSH0000").unwrap();
+        assert_eq!(in_share.name(), "Shared with me");
+        assert_eq!(in_share.share_key, Some(in_share_key));
+        assert_eq!(in_share.children(), ["FILE0000"]);
+        assert!(nodes.roots().any(|it| it.handle() == "INSH0000"));
+
+        let file = nodes.get_node_by_handle("FILE0000").unwrap();
+        assert_eq!(file.name(), "paper.pdf");
+        assert_eq!(file.aes_key(), in_file.aes_key());
+        assert_eq!(file.condensed_mac(), in_file.condensed_mac());
+
+        let out_share = nodes.get_node_by_path("/Root/Shared by me").unwrap();
+        assert_eq!(out_share.share_key, Some(out_share_key));
+    }
+
+    #[test]
+    fn copy_folder_reencrypts_keys() {
+        let mock = MockClient::new([json::json!({ "f": [] })]);
+        let requests = mock.requests();
+        let client = test_client(mock, RsaPrivateKey::default());
+
+        let folder = test_node("FOLD0000", NodeKind::Folder, "ROOT0000", &["FILE0000"]);
+        let file = test_node("FILE0000", NodeKind::File, "FOLD0000", &["VERS0000"]);
+        let version = test_node("VERS0000", NodeKind::File, "FILE0000", &[]);
+        let target = test_node("DEST0000", NodeKind::Folder, "ROOT0000", &[]);
+        let (folder_key, file_key) = (folder.key_data(), file.key_data());
+        let nodes = Nodes::new(
+            HashMap::from([
+                (folder.handle.clone(), folder),
+                (file.handle.clone(), file),
+                (version.handle.clone(), version),
+            ]),
+            String::default(),
+            None,
+        );
+        let folder = nodes.get_node_by_handle("FOLD0000").unwrap();
+
+        block_on(client.copy_node(&nodes, folder, &target, Some("copy"))).unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["a"], "p");
+        assert_eq!(requests[0]["t"], "DEST0000");
+        assert!(requests[0].get("ov").is_none());
+
+        // previous versions of the copied files are left out
+        let copies = requests[0]["n"].as_array().unwrap();
+        assert_eq!(copies.len(), 2);
+
+        assert_eq!(copies[0]["h"], "FOLD0000");
+        assert!(copies[0].get("p").is_none());
+        assert_eq!(decrypt_b64(&MASTER_KEY, &copies[0]["k"]), folder_key);
+        let mut attr = BASE64_URL_SAFE_NO_PAD
+            .decode(copies[0]["a"].as_str().unwrap())
+            .unwrap();
+        let attrs = NodeAttributes::decrypt_and_unpack(folder.aes_key(), &mut attr).unwrap();
+        assert_eq!(attrs.name, "copy");
+
+        assert_eq!(copies[1]["h"], "FILE0000");
+        assert_eq!(copies[1]["p"], "FOLD0000");
+        assert_eq!(decrypt_b64(&MASTER_KEY, &copies[1]["k"]), file_key);
+    }
+
+    #[test]
+    fn restore_previous_version() {
+        let mock = MockClient::new([json::json!({ "f": [] })]);
+        let requests = mock.requests();
+        let client = test_client(mock, RsaPrivateKey::default());
+
+        let file = test_node("FILE0000", NodeKind::File, "ROOT0000", &["VERS0001"]);
+        let newer = test_node("VERS0001", NodeKind::File, "FILE0000", &["VERS0000"]);
+        let older = test_node("VERS0000", NodeKind::File, "VERS0001", &[]);
+        let nodes = Nodes::new(
+            HashMap::from([
+                (file.handle.clone(), file),
+                (newer.handle.clone(), newer),
+                (older.handle.clone(), older),
+            ]),
+            String::default(),
+            None,
+        );
+        let file = nodes.get_node_by_handle("FILE0000").unwrap();
+        let older = nodes.get_node_by_handle("VERS0000").unwrap();
+
+        let versions: Vec<_> = nodes.versions(file).into_iter().map(Node::handle).collect();
+        assert_eq!(versions, ["VERS0001", "VERS0000"]);
+        assert_eq!(nodes.current_version(older).handle(), "FILE0000");
+
+        assert!(matches!(
+            block_on(client.restore_version(&nodes, file)),
+            Err(Error::NotAFileVersion)
+        ));
+        block_on(client.restore_version(&nodes, older)).unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["a"], "p");
+        assert_eq!(requests[0]["t"], "ROOT0000");
+        assert_eq!(requests[0]["ov"], "FILE0000");
+        assert_eq!(requests[0]["n"][0]["h"], "VERS0000");
+    }
+
+    #[test]
+    fn restore_node_to_original_folder() {
+        let mock = MockClient::new(vec![json::json!(0); 4]);
+        let requests = mock.requests();
+        let client = test_client(mock, RsaPrivateKey::default());
+
+        let mut root = test_node("ROOT0000", NodeKind::Root, "", &["FOLD0000"]);
+        root.parent = None;
+        let mut trash = test_node("TRSH0000", NodeKind::Trash, "", &["FILE0000"]);
+        trash.parent = None;
+        let folder = test_node("FOLD0000", NodeKind::Folder, "ROOT0000", &[]);
+        let mut file = test_node("FILE0000", NodeKind::File, "TRSH0000", &[]);
+        file.restore_handle = Some("FOLD0000".to_string());
+        let nodes = Nodes::new(
+            HashMap::from([
+                (root.handle.clone(), root),
+                (trash.handle.clone(), trash),
+                (folder.handle.clone(), folder),
+                (file.handle.clone(), file),
+            ]),
+            String::default(),
+            None,
+        );
+        let file = nodes.get_node_by_handle("FILE0000").unwrap();
+        assert!(nodes.is_in_rubbish_bin(file));
+
+        block_on(client.restore_node(&nodes, file)).unwrap();
+
+        // without a known original folder, nodes get restored to the root of the Cloud Drive
+        let mut orphan = file.clone();
+        orphan.restore_handle = Some("GONE0000".to_string());
+        block_on(client.restore_node(&nodes, &orphan)).unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0]["a"], "m");
+        assert_eq!(requests[0]["t"], "FOLD0000");
+        assert_eq!(requests[1]["a"], "a");
+        let mut attr = BASE64_URL_SAFE_NO_PAD
+            .decode(requests[1]["attr"].as_str().unwrap())
+            .unwrap();
+        let attrs = NodeAttributes::decrypt_and_unpack(file.aes_key(), &mut attr).unwrap();
+        assert_eq!(attrs.restore_handle, None);
+        assert_eq!(requests[2]["t"], "ROOT0000");
+    }
+}
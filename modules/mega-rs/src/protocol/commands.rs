@@ -255,6 +255,25 @@ pub enum Request {
         #[serde(rename = "i")]
         i: String,
     },
+    /// Message for copying nodes (server-side) into a folder.
+    #[serde(rename = "p")]
+    Copy {
+        /// The handle of the target parent node.
+        #[serde(rename = "t", serialize_with = "serialize_secret_string")]
+        t: SecretString,
+        /// The nodes to copy, parents before their children.
+        #[serde(rename = "n")]
+        n: Vec<CopyAttributes>,
+        /// The handle of the file the (single) copy becomes a new version of.
+        #[serde(rename = "ov", skip_serializing_if = "Option::is_none")]
+        ov: Option<String>,
+        /// The idempotence token (needed for request retries).
+        #[serde(rename = "i")]
+        i: String,
+    },
+    /// Message for permanently deleting everything in the Rubbish Bin.
+    #[serde(rename = "dr")]
+    CleanRubbishBin {},
     /// Message for uploading file attributes (also used for downloading file attributes).
     #[serde(rename = "ufa")]
     UploadFileAttributes {
@@ -284,6 +303,54 @@ pub enum Request {
         #[serde(rename = "fa")]
         fa: String,
     },
+    /// Message for creating (or deleting) the public link of a node.
+    #[serde(rename = "l")]
+    ExportLink {
+        /// The handle of the node to export.
+        #[serde(rename = "n", serialize_with = "serialize_secret_string")]
+        n: SecretString,
+        /// The expiry timestamp of the link (only available to PRO accounts).
+        #[serde(rename = "ets", skip_serializing_if = "Option::is_none")]
+        ets: Option<i64>,
+        /// Set to 1 to delete the public link instead.
+        #[serde(rename = "d", skip_serializing_if = "Option::is_none")]
+        d: Option<i32>,
+        /// The idempotence token (needed for request retries).
+        #[serde(rename = "i")]
+        i: String,
+    },
+    /// Message for creating, updating or removing the shares of a folder.
+    #[serde(rename = "s2")]
+    Share {
+        /// The handle of the shared folder.
+        #[serde(rename = "n", serialize_with = "serialize_secret_string")]
+        n: SecretString,
+        /// The users to share the folder with (`EXP` for public links).
+        #[serde(rename = "s")]
+        s: Vec<ShareTarget>,
+        /// The share key, encrypted with the owner's master key.
+        #[serde(rename = "ok", skip_serializing_if = "Option::is_none")]
+        ok: Option<String>,
+        /// The share handle authentication, proving knowledge of the master key.
+        #[serde(rename = "ha", skip_serializing_if = "Option::is_none")]
+        ha: Option<String>,
+        /// The keys of the shared nodes, encrypted with the share key.
+        ///
+        /// Consists of the share handles, the node handles and a flat list of
+        /// `(share_index, node_index, encrypted_key)` triples.
+        #[serde(rename = "cr", skip_serializing_if = "Option::is_none")]
+        cr: Option<(Vec<String>, Vec<String>, Vec<Value>)>,
+        /// The idempotence token (needed for request retries).
+        #[serde(rename = "i")]
+        i: String,
+    },
+    /// Message for fetching the RSA public key of another user.
+    #[serde(rename = "uk")]
+    UserPublicKey {
+        /// The email address (or handle) of the user.
+        #[serde(rename = "u", serialize_with = "serialize_secret_string")]
+        u: SecretString,
+    },
 }
 
 /// Represents a response message from MEGA's API.
@@ -323,10 +390,20 @@ pub enum Response {
     Move(MoveResponse),
     /// Response for the `Request::Delete` message.
     Delete(DeleteResponse),
+    /// Response for the `Request::Copy` message.
+    Copy(CopyResponse),
+    /// Response for the `Request::CleanRubbishBin` message.
+    CleanRubbishBin(CleanRubbishBinResponse),
     /// Response for the `Request::UploadFileAttributes` message.
     UploadFileAttributes(UploadFileAttributesResponse),
     /// Response for the `Request::PutFileAttributes` message.
     PutFileAttributes(PutFileAttributesResponse),
+    /// Response for the `Request::ExportLink` message.
+    ExportLink(ExportLinkResponse),
+    /// Response for the `Request::Share` message.
+    Share(ShareResponse),
+    /// Response for the `Request::UserPublicKey` message.
+    UserPublicKey(UserPublicKeyResponse),
 }
 
 /// Response for the `Request::PreLogin` message.
@@ -588,6 +665,38 @@ pub struct UploadCompleteResponse {
     pub f: Vec<FileNode>,
 }
 
+/// Represents a node to copy, as part of a `Request::Copy` message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CopyAttributes {
+    /// The handle of the node to copy.
+    #[serde(rename = "h")]
+    pub handle: String,
+    /// The handle of the (copied) parent node, for nodes nested in the copied tree.
+    #[serde(rename = "p", skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// The kind of the node to copy.
+    #[serde(rename = "t")]
+    pub kind: NodeKind,
+    /// The attributes for the copy.
+    #[serde(rename = "a")]
+    pub attr: String,
+    /// The key data for the copy, encrypted with the user's master key.
+    #[serde(rename = "k")]
+    pub key: String,
+}
+
+/// Response for the `Request::Copy` message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CopyResponse {
+    /// The nodes that got created.
+    #[serde(rename = "f")]
+    pub f: Vec<FileNode>,
+}
+
+/// Response for the `Request::CleanRubbishBin` message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CleanRubbishBinResponse {}
+
 /// Response for the `Request::SetFileAttributes` message.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SetFileAttributesResponse {}
@@ -606,6 +715,61 @@ pub struct PutFileAttributesResponse {
     fa: String,
 }
 
+/// Represents a user (or the `EXP` pseudo-user) to share a folder with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShareTarget {
+    /// The email address (or handle) of the user.
+    #[serde(rename = "u")]
+    pub user: String,
+    /// The access level to grant (the share is removed if absent).
+    #[serde(rename = "r", skip_serializing_if = "Option::is_none")]
+    pub access: Option<ShareAccess>,
+    /// The share key, encrypted with the user's RSA public key.
+    #[serde(rename = "k", skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+/// Represents the access level granted to the users of a shared folder.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize_repr, Deserialize_repr)]
+pub enum ShareAccess {
+    /// The folder's contents can only be read.
+    ReadOnly = 0,
+    /// The folder's contents can be read and modified.
+    ReadWrite = 1,
+    /// The folder's contents can be read, modified and deleted.
+    Full = 2,
+}
+
+/// Response for the `Request::ExportLink` message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportLinkResponse {
+    /// The public handle of the exported node.
+    pub handle: String,
+}
+
+/// Response for the `Request::Share` message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShareResponse {
+    /// The results for each of the involved users.
+    #[serde(rename = "r", default)]
+    pub results: Vec<ErrorCode>,
+    /// Catch-all for the remaining fields (if any).
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
+}
+
+/// Response for the `Request::UserPublicKey` message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserPublicKeyResponse {
+    /// The handle of the user.
+    #[serde(rename = "u")]
+    pub user_handle: String,
+    /// The user's RSA public key, as MPI-formatted numbers.
+    #[serde(rename = "pubk")]
+    pub pubk: String,
+}
+
 /// Response for the `Request::Move` message.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MoveResponse {}
@@ -682,6 +846,14 @@ impl Request {
                 let response = json::from_value(value)?;
                 Response::Delete(response)
             }
+            Request::Copy { .. } => {
+                let response = json::from_value(value)?;
+                Response::Copy(response)
+            }
+            Request::CleanRubbishBin { .. } => {
+                let response = json::from_value(value)?;
+                Response::CleanRubbishBin(response)
+            }
             Request::UploadFileAttributes { .. } => {
                 let response = json::from_value(value)?;
                 Response::UploadFileAttributes(response)
@@ -690,6 +862,18 @@ impl Request {
                 let response = json::from_value(value)?;
                 Response::PutFileAttributes(PutFileAttributesResponse { fa: response })
             }
+            Request::ExportLink { .. } => {
+                let response = json::from_value(value)?;
+                Response::ExportLink(ExportLinkResponse { handle: response })
+            }
+            Request::Share { .. } => {
+                let response = json::from_value(value)?;
+                Response::Share(response)
+            }
+            Request::UserPublicKey { .. } => {
+                let response = json::from_value(value)?;
+                Response::UserPublicKey(response)
+            }
         };
 
         Ok(response)
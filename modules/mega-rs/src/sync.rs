@@ -0,0 +1,1123 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Utc};
+use futures::future::Either;
+use futures::io::AllowStdIo;
+use futures::StreamExt;
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use crate::fingerprint;
+use crate::{Client, Error, LastModified, Node, Nodes, Result, TransferOptions};
+
+/// The suffix of the files being downloaded, which are ignored until they get renamed into place.
+const PARTIAL_SUFFIX: &str = ".mega-partial";
+
+/// Represents the options for synchronizing a local directory with a MEGA folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncOptions {
+    /// The file to persist the state of the synchronization to.
+    ///
+    /// The state of the ongoing transfer also gets persisted next to it, allowing to resume it after a restart.
+    pub state_file: PathBuf,
+    /// How long to let local changes settle before synchronizing them.
+    pub debounce: Duration,
+    /// The interval of the full local rescans, catching up on the changes missed by the filesystem watcher.
+    pub rescan_interval: Duration,
+    /// The options for the file transfers.
+    pub transfers: TransferOptions,
+}
+
+impl SyncOptions {
+    /// Creates the default options, persisting the state of the synchronization to the given file.
+    pub fn new(state_file: impl Into<PathBuf>) -> Self {
+        Self {
+            state_file: state_file.into(),
+            debounce: Duration::from_secs(2),
+            rescan_interval: Duration::from_secs(300),
+            transfers: TransferOptions::default(),
+        }
+    }
+}
+
+/// Represents the outcome of a synchronization pass.
+///
+/// All the paths are relative to the synchronized directories, with `/` as separator.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// The uploaded files and created remote folders.
+    pub uploaded: Vec<String>,
+    /// The downloaded files and created local folders.
+    pub downloaded: Vec<String>,
+    /// The entries deleted on either side, following their deletion on the other one.
+    pub deleted: Vec<String>,
+    /// The files changed on both sides, whose local version got kept as a conflicted copy.
+    pub conflicts: Vec<String>,
+    /// The entries that failed to synchronize (they get retried in the next pass).
+    pub failed: Vec<String>,
+}
+
+impl SyncReport {
+    /// Returns whether nothing happened during the pass.
+    pub fn is_empty(&self) -> bool {
+        self.uploaded.is_empty()
+            && self.downloaded.is_empty()
+            && self.deleted.is_empty()
+            && self.conflicts.is_empty()
+            && self.failed.is_empty()
+    }
+}
+
+/// Represents the persisted state of a synchronization.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct SyncState {
+    /// The synchronized local directory.
+    local_root: PathBuf,
+    /// The handle of the synchronized remote folder.
+    remote_root: String,
+    /// The entries as of their last synchronization, keyed by their relative path.
+    entries: BTreeMap<String, SyncEntry>,
+}
+
+impl SyncState {
+    /// Loads a previously persisted state (unreadable states are ignored, as if there were none).
+    fn load(path: &Path) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        json::from_slice(&data).ok()
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    /// Forgets about an entry, along with all of its descendants.
+    fn forget(&mut self, path: &str) {
+        let prefix = format!("{path}/");
+        self.entries
+            .retain(|key, _| key != path && !key.starts_with(&prefix));
+    }
+}
+
+/// Represents an entry (file or folder) as it was when both sides were last in sync.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SyncEntry {
+    /// Whether the entry is a folder.
+    folder: bool,
+    /// The size (in bytes) of the file.
+    size: u64,
+    /// The local last modified date of the file (as a UNIX timestamp).
+    modified_at: i64,
+    /// The sparse checksum of the file.
+    checksum: Option<[u8; 16]>,
+    /// The handle of the remote node, unless it didn't come back from MEGA yet.
+    handle: Option<String>,
+}
+
+impl SyncEntry {
+    fn folder(handle: Option<String>) -> Self {
+        Self {
+            folder: true,
+            size: 0,
+            modified_at: 0,
+            checksum: None,
+            handle,
+        }
+    }
+
+    fn file(local: &LocalEntry, checksum: Option<[u8; 16]>, handle: Option<String>) -> Self {
+        Self {
+            folder: false,
+            size: local.size,
+            modified_at: local.modified_at,
+            checksum,
+            handle,
+        }
+    }
+}
+
+/// Represents a local entry, as found while scanning the synchronized directory.
+#[derive(Debug, Clone, PartialEq)]
+struct LocalEntry {
+    /// Whether the entry is a folder.
+    folder: bool,
+    /// The size (in bytes) of the file.
+    size: u64,
+    /// The last modified date of the file (as a UNIX timestamp).
+    modified_at: i64,
+    /// The sparse checksum of the file (only computed if its size or date changed since the last synchronization).
+    checksum: Option<[u8; 16]>,
+}
+
+/// Represents how one side of an entry changed since the last synchronization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    /// The entry doesn't exist (and wasn't known to exist).
+    Absent,
+    /// The entry is unchanged.
+    Unchanged,
+    /// The entry appeared.
+    Created,
+    /// The entry changed (in contents or in kind).
+    Modified,
+    /// The entry disappeared.
+    Deleted,
+}
+
+impl Change {
+    fn is_new(self) -> bool {
+        matches!(self, Change::Created | Change::Modified)
+    }
+}
+
+/// Represents an operation needed to bring both sides of an entry back in sync.
+#[derive(Debug, Clone, PartialEq)]
+enum SyncAction {
+    /// Uploads the local file, moving the replaced remote node (if any) to the Rubbish Bin.
+    Upload {
+        path: String,
+        replaces: Option<String>,
+    },
+    /// Downloads the remote file over the local one.
+    Download { path: String, handle: String },
+    /// Creates the remote folder.
+    CreateRemoteFolder { path: String },
+    /// Creates the local folder.
+    CreateLocalFolder { path: String, handle: String },
+    /// Deletes the remote node (by moving it to the Rubbish Bin).
+    DeleteRemote { path: String, handle: String },
+    /// Deletes the local file or folder.
+    DeleteLocal { path: String },
+    /// Keeps the local entry as a conflicted copy, before bringing the remote one in its place.
+    Conflict { path: String, handle: String },
+    /// Records an entry that is already in sync.
+    Record { path: String, entry: SyncEntry },
+    /// Forgets an entry that is gone from both sides.
+    Forget { path: String },
+}
+
+impl SyncAction {
+    fn path(&self) -> &str {
+        match self {
+            SyncAction::Upload { path, .. }
+            | SyncAction::Download { path, .. }
+            | SyncAction::CreateRemoteFolder { path }
+            | SyncAction::CreateLocalFolder { path, .. }
+            | SyncAction::DeleteRemote { path, .. }
+            | SyncAction::DeleteLocal { path }
+            | SyncAction::Conflict { path, .. }
+            | SyncAction::Record { path, .. }
+            | SyncAction::Forget { path } => path,
+        }
+    }
+
+    /// Returns whether this action brings (or keeps) data on either side.
+    fn keeps_data(&self) -> bool {
+        !matches!(
+            self,
+            SyncAction::DeleteRemote { .. }
+                | SyncAction::DeleteLocal { .. }
+                | SyncAction::Forget { .. }
+        )
+    }
+}
+
+/// Two-way synchronization between a local directory and a MEGA folder.
+///
+/// Local changes are detected by scanning the directory (triggered by a filesystem watcher) and comparing the files'
+/// fingerprints to the ones from the last synchronization, and remote changes by applying MEGA's action packets to the nodes.
+/// Files changed on both sides are resolved by keeping both copies: the local one gets renamed to a conflicted copy (and uploaded
+/// as such) before the remote one gets downloaded in its place.
+pub struct SyncEngine {
+    /// The synchronized local directory.
+    local_root: PathBuf,
+    /// The options of the synchronization.
+    options: SyncOptions,
+    /// The state of the synchronization.
+    state: SyncState,
+}
+
+impl SyncEngine {
+    /// Creates a synchronization between a local directory and a remote folder, resuming from its persisted state (if any).
+    ///
+    /// A persisted state for a different local directory or remote folder is discarded.
+    pub fn new(
+        local_root: impl Into<PathBuf>,
+        remote_root: &Node,
+        options: SyncOptions,
+    ) -> Result<Self> {
+        if !remote_root.kind().is_folder() && !remote_root.kind().is_root() {
+            return Err(Error::UnsupportedNodeKind {
+                kind: remote_root.kind(),
+            });
+        }
+
+        let local_root = local_root.into();
+        std::fs::create_dir_all(&local_root)?;
+
+        let state = SyncState::load(&options.state_file)
+            .filter(|state| {
+                state.local_root == local_root && state.remote_root == remote_root.handle()
+            })
+            .unwrap_or_else(|| SyncState {
+                local_root: local_root.clone(),
+                remote_root: remote_root.handle().to_string(),
+                entries: BTreeMap::default(),
+            });
+
+        Ok(Self {
+            local_root,
+            options,
+            state,
+        })
+    }
+
+    /// Returns the synchronized local directory.
+    pub fn local_root(&self) -> &Path {
+        &self.local_root
+    }
+
+    /// Performs a single synchronization pass, against the given (up-to-date) nodes.
+    ///
+    /// Entries within remote folders which are still being created get synchronized in a later pass,
+    /// once the creation of their parent came back as an event.
+    pub async fn sync_once(&mut self, client: &Client, nodes: &Nodes) -> Result<SyncReport> {
+        let root = nodes
+            .get_node_by_handle(&self.state.remote_root)
+            .ok_or(Error::NodeNotFound)?;
+
+        let mut local = scan_local(&self.local_root, &self.options.state_file)?;
+        for (path, entry) in local.iter_mut() {
+            let known = self.state.entries.get(path);
+            let unchanged = known.is_some_and(|known| {
+                !known.folder && known.size == entry.size && known.modified_at == entry.modified_at
+            });
+            if !entry.folder && !unchanged {
+                entry.checksum =
+                    Some(local_checksum(&self.local_root.join(path), entry.size).await?);
+            }
+        }
+
+        let remote = scan_remote(nodes, root);
+        let actions = plan(&self.state.entries, &local, &remote);
+
+        let mut report = SyncReport::default();
+        for action in actions {
+            let path = action.path().to_string();
+            if let Err(error) = self
+                .execute(client, nodes, root, &local, &remote, &action, &mut report)
+                .await
+            {
+                tracing::warn!(?path, %error, "failed to synchronize entry");
+                report.failed.push(path);
+            }
+        }
+
+        self.state.save(&self.options.state_file)?;
+        Ok(report)
+    }
+
+    /// Keeps both sides in sync until an error occurs, calling the given function after every pass that did something.
+    ///
+    /// The nodes get kept up-to-date with the events from MEGA along the way.
+    pub async fn run<F>(&mut self, client: &Client, nodes: &mut Nodes, mut on_pass: F) -> Result<()>
+    where
+        F: FnMut(&SyncReport),
+    {
+        let (sender, mut receiver) = futures::channel::mpsc::unbounded();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                if event.is_ok() {
+                    let _ = sender.unbounded_send(());
+                }
+            })?;
+        watcher.watch(&self.local_root, RecursiveMode::Recursive)?;
+
+        loop {
+            let report = self.sync_once(client, nodes).await?;
+            if !report.is_empty() {
+                on_pass(&report);
+            }
+
+            // Our own changes to the directory got picked up by the watcher as well.
+            // Changes made concurrently by the user are still caught up by the periodic rescans.
+            while let Ok(Some(())) = receiver.try_next() {}
+
+            let events = {
+                let remote = client.wait_events(nodes);
+                let local = tokio::time::timeout(self.options.rescan_interval, receiver.next());
+                futures::pin_mut!(remote, local);
+                match futures::future::select(remote, local).await {
+                    Either::Left((events, _)) => Some(events?),
+                    Either::Right(_) => None,
+                }
+            };
+
+            match events {
+                Some(events) => nodes.apply_events(events)?,
+                None => {
+                    // Let the burst of local changes settle before scanning.
+                    tokio::time::sleep(self.options.debounce).await;
+                    while let Ok(Some(())) = receiver.try_next() {}
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn execute(
+        &mut self,
+        client: &Client,
+        nodes: &Nodes,
+        root: &Node,
+        local: &BTreeMap<String, LocalEntry>,
+        remote: &BTreeMap<String, &Node>,
+        action: &SyncAction,
+        report: &mut SyncReport,
+    ) -> Result<()> {
+        let remote_parent = |path: &str| match path.rsplit_once('/') {
+            Some((parent, _)) => remote.get(parent).copied(),
+            None => Some(root),
+        };
+
+        match action {
+            SyncAction::Upload { path, replaces } => {
+                let Some(parent) = remote_parent(path) else {
+                    return Ok(());
+                };
+                let entry = &local[path];
+
+                let last_modified = DateTime::from_timestamp(entry.modified_at, 0)
+                    .map_or(LastModified::Now, LastModified::Set);
+                client
+                    .upload_node_chunked(
+                        parent,
+                        file_name(path),
+                        &self.local_root.join(path),
+                        last_modified,
+                        &self.transfer_options(),
+                        |_| {},
+                    )
+                    .await?;
+
+                self.state
+                    .entries
+                    .insert(path.clone(), SyncEntry::file(entry, entry.checksum, None));
+
+                if let Some(replaced) = replaces
+                    .as_deref()
+                    .and_then(|it| nodes.get_node_by_handle(it))
+                {
+                    discard_remote(client, nodes, replaced).await?;
+                }
+
+                report.uploaded.push(path.clone());
+            }
+            SyncAction::Download { path, handle } => {
+                let node = nodes
+                    .get_node_by_handle(handle)
+                    .ok_or(Error::NodeNotFound)?;
+                self.download(client, path, node).await?;
+                report.downloaded.push(path.clone());
+            }
+            SyncAction::CreateRemoteFolder { path } => {
+                let Some(parent) = remote_parent(path) else {
+                    return Ok(());
+                };
+                client.create_folder(parent, file_name(path)).await?;
+                self.state
+                    .entries
+                    .insert(path.clone(), SyncEntry::folder(None));
+                report.uploaded.push(path.clone());
+            }
+            SyncAction::CreateLocalFolder { path, handle } => {
+                std::fs::create_dir_all(self.local_root.join(path))?;
+                self.state
+                    .entries
+                    .insert(path.clone(), SyncEntry::folder(Some(handle.clone())));
+                report.downloaded.push(path.clone());
+            }
+            SyncAction::DeleteRemote { path, handle } => {
+                if let Some(node) = nodes.get_node_by_handle(handle) {
+                    discard_remote(client, nodes, node).await?;
+                }
+                self.state.forget(path);
+                report.deleted.push(path.clone());
+            }
+            SyncAction::DeleteLocal { path } => {
+                let local_path = self.local_root.join(path);
+                let result = match local.get(path) {
+                    Some(entry) if entry.folder => std::fs::remove_dir_all(&local_path),
+                    _ => std::fs::remove_file(&local_path),
+                };
+                match result {
+                    Err(error) if error.kind() != io::ErrorKind::NotFound => {
+                        return Err(error.into())
+                    }
+                    _ => {}
+                }
+                self.state.forget(path);
+                report.deleted.push(path.clone());
+            }
+            SyncAction::Conflict { path, handle } => {
+                let node = nodes
+                    .get_node_by_handle(handle)
+                    .ok_or(Error::NodeNotFound)?;
+
+                // The conflicted copy is unknown to the state, so it gets uploaded by the next pass.
+                let copy = conflicted_copy_path(path, Utc::now());
+                std::fs::rename(self.local_root.join(path), self.local_root.join(&copy))?;
+                self.state.forget(path);
+
+                if node.kind().is_folder() {
+                    std::fs::create_dir_all(self.local_root.join(path))?;
+                    self.state
+                        .entries
+                        .insert(path.clone(), SyncEntry::folder(Some(handle.clone())));
+                } else {
+                    self.download(client, path, node).await?;
+                }
+
+                report.conflicts.push(path.clone());
+            }
+            SyncAction::Record { path, entry } => {
+                self.state.entries.insert(path.clone(), entry.clone());
+            }
+            SyncAction::Forget { path } => {
+                self.state.forget(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Downloads a remote file into its local path, only replacing the local file once the download completed.
+    async fn download(&mut self, client: &Client, path: &str, node: &Node) -> Result<()> {
+        let local_path = self.local_root.join(path);
+        if let Some(parent) = local_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let partial_path = {
+            let mut name = local_path.file_name().unwrap_or_default().to_os_string();
+            name.push(PARTIAL_SUFFIX);
+            local_path.with_file_name(name)
+        };
+
+        client
+            .download_node_chunked(node, &partial_path, &self.transfer_options(), |_| {})
+            .await?;
+
+        if let Some(modified_at) = node.modified_at() {
+            let file = File::options().write(true).open(&partial_path)?;
+            file.set_modified(SystemTime::from(modified_at))?;
+        }
+        std::fs::rename(&partial_path, &local_path)?;
+
+        let metadata = std::fs::metadata(&local_path)?;
+        let entry = LocalEntry {
+            folder: false,
+            size: metadata.len(),
+            modified_at: modified_timestamp(&metadata),
+            checksum: None,
+        };
+        let checksum = match node.sparse_checksum() {
+            Some(checksum) => *checksum,
+            None => local_checksum(&local_path, entry.size).await?,
+        };
+
+        self.state.entries.insert(
+            path.to_string(),
+            SyncEntry::file(&entry, Some(checksum), Some(node.handle().to_string())),
+        );
+
+        Ok(())
+    }
+
+    /// Returns the options for the file transfers, persisting their state next to the one of the synchronization.
+    fn transfer_options(&self) -> TransferOptions {
+        TransferOptions {
+            state_file: Some(transfer_state_file(&self.options.state_file)),
+            ..self.options.transfers.clone()
+        }
+    }
+}
+
+/// Returns the file to persist the state of the ongoing transfer to.
+fn transfer_state_file(state_file: &Path) -> PathBuf {
+    state_file.with_extension("transfer")
+}
+
+/// Moves a remote node to the Rubbish Bin (or deletes it, if there is none).
+async fn discard_remote(client: &Client, nodes: &Nodes, node: &Node) -> Result<()> {
+    match nodes.rubbish_bin() {
+        Some(_) => client.move_to_rubbish_bin(nodes, node).await,
+        None => client.delete_node(node).await,
+    }
+}
+
+/// Determines the operations needed to bring both sides back in sync.
+///
+/// The returned actions are ordered by path, so that folders come before their contents.
+fn plan(
+    entries: &BTreeMap<String, SyncEntry>,
+    local: &BTreeMap<String, LocalEntry>,
+    remote: &BTreeMap<String, &Node>,
+) -> Vec<SyncAction> {
+    let mut paths: Vec<&String> = entries
+        .keys()
+        .chain(local.keys())
+        .chain(remote.keys())
+        .collect();
+    paths.sort_unstable();
+    paths.dedup();
+
+    let mut actions: Vec<SyncAction> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let known = entries.get(path);
+            plan_entry(path, known, local.get(path), remote.get(path).copied())
+        })
+        .collect();
+
+    // A folder deleted on one side only gets deleted on the other one if nothing changed within it in the meantime.
+    // Otherwise, it gets recreated to hold the changes.
+    // The contents of conflicted folders get synchronized by the next pass, under their new path.
+    let mut idx = 0;
+    while idx < actions.len() {
+        let (path, recreated) = match &actions[idx] {
+            SyncAction::DeleteRemote { path, handle } if remote[path].kind().is_folder() => {
+                let recreated = SyncAction::CreateLocalFolder {
+                    path: path.clone(),
+                    handle: handle.clone(),
+                };
+                (path.clone(), Some(recreated))
+            }
+            SyncAction::DeleteLocal { path } if local[path].folder => {
+                let recreated = SyncAction::CreateRemoteFolder { path: path.clone() };
+                (path.clone(), Some(recreated))
+            }
+            SyncAction::Conflict { path, .. } => (path.clone(), None),
+            _ => {
+                idx += 1;
+                continue;
+            }
+        };
+
+        let prefix = format!("{path}/");
+        let within = |action: &SyncAction| action.path().starts_with(&prefix);
+        match recreated {
+            Some(recreated)
+                if actions
+                    .iter()
+                    .any(|action| within(action) && action.keeps_data()) =>
+            {
+                actions[idx] = recreated;
+            }
+            _ => actions.retain(|action| !within(action)),
+        }
+        idx += 1;
+    }
+
+    actions
+}
+
+/// Determines the operation needed to bring both sides of a single entry back in sync.
+fn plan_entry(
+    path: &str,
+    known: Option<&SyncEntry>,
+    local: Option<&LocalEntry>,
+    remote: Option<&Node>,
+) -> Option<SyncAction> {
+    let path = path.to_string();
+    let local_change = local_change(known, local);
+    let remote_change = remote_change(known, remote);
+
+    match (local, remote) {
+        (None, None) => known.map(|_| SyncAction::Forget { path }),
+        (Some(entry), None) => match (local_change, remote_change) {
+            // The remote node is still being created.
+            (Change::Unchanged, Change::Absent) => None,
+            (Change::Unchanged, Change::Deleted) => Some(SyncAction::DeleteLocal { path }),
+            _ if entry.folder => Some(SyncAction::CreateRemoteFolder { path }),
+            _ => Some(SyncAction::Upload {
+                path,
+                replaces: None,
+            }),
+        },
+        (None, Some(node)) => {
+            let handle = node.handle().to_string();
+            match (local_change, remote_change) {
+                (Change::Deleted, Change::Unchanged) => {
+                    Some(SyncAction::DeleteRemote { path, handle })
+                }
+                _ if node.kind().is_folder() => {
+                    Some(SyncAction::CreateLocalFolder { path, handle })
+                }
+                _ => Some(SyncAction::Download { path, handle }),
+            }
+        }
+        (Some(entry), Some(node)) => {
+            let handle = node.handle().to_string();
+
+            if entry.folder && node.kind().is_folder() {
+                let synced = SyncEntry::folder(Some(handle));
+                return (known != Some(&synced)).then_some(SyncAction::Record {
+                    path,
+                    entry: synced,
+                });
+            }
+
+            // The local checksum is only computed for new or changed files.
+            let checksum = entry.checksum.or_else(|| known.and_then(|it| it.checksum));
+            let same_content = !entry.folder
+                && node.kind().is_file()
+                && entry.size == node.size()
+                && checksum.is_some()
+                && checksum.as_ref() == node.sparse_checksum();
+
+            match (local_change, remote_change) {
+                (Change::Unchanged, Change::Unchanged) => {
+                    let synced = SyncEntry::file(entry, checksum, Some(handle));
+                    (known != Some(&synced)).then_some(SyncAction::Record {
+                        path,
+                        entry: synced,
+                    })
+                }
+                _ if same_content => {
+                    let synced = SyncEntry::file(entry, checksum, Some(handle));
+                    Some(SyncAction::Record {
+                        path,
+                        entry: synced,
+                    })
+                }
+                (local_change, Change::Unchanged) if local_change.is_new() => {
+                    // Local files replacing remote folders are handled as conflicts, to never discard a whole tree.
+                    if entry.folder || node.kind().is_folder() {
+                        Some(SyncAction::Conflict { path, handle })
+                    } else {
+                        Some(SyncAction::Upload {
+                            path,
+                            replaces: Some(handle),
+                        })
+                    }
+                }
+                (Change::Unchanged, remote_change) if remote_change.is_new() => {
+                    if entry.folder || node.kind().is_folder() {
+                        Some(SyncAction::Conflict { path, handle })
+                    } else {
+                        Some(SyncAction::Download { path, handle })
+                    }
+                }
+                _ => Some(SyncAction::Conflict { path, handle }),
+            }
+        }
+    }
+}
+
+/// Determines how the local side of an entry changed since the last synchronization.
+fn local_change(known: Option<&SyncEntry>, local: Option<&LocalEntry>) -> Change {
+    match (known, local) {
+        (None, None) => Change::Absent,
+        (None, Some(_)) => Change::Created,
+        (Some(_), None) => Change::Deleted,
+        (Some(known), Some(local)) if known.folder != local.folder => Change::Modified,
+        (Some(_), Some(local)) if local.folder => Change::Unchanged,
+        (Some(known), Some(local)) => {
+            let touched = known.size != local.size || known.modified_at != local.modified_at;
+            let rewritten = known.size != local.size || local.checksum != known.checksum;
+            if touched && rewritten {
+                Change::Modified
+            } else {
+                Change::Unchanged
+            }
+        }
+    }
+}
+
+/// Determines how the remote side of an entry changed since the last synchronization.
+fn remote_change(known: Option<&SyncEntry>, remote: Option<&Node>) -> Change {
+    let Some(known) = known else {
+        return if remote.is_some() {
+            Change::Created
+        } else {
+            Change::Absent
+        };
+    };
+
+    match (known.handle.as_deref(), remote) {
+        (None, None) => Change::Absent,
+        (Some(_), None) => Change::Deleted,
+        (None, Some(_)) => Change::Created,
+        (Some(handle), Some(node)) => {
+            let same_kind = known.folder == node.kind().is_folder();
+            let same_contents = known.folder
+                || node.sparse_checksum().is_none()
+                || node.sparse_checksum() == known.checksum.as_ref();
+            if handle == node.handle() && same_kind && same_contents {
+                Change::Unchanged
+            } else {
+                Change::Modified
+            }
+        }
+    }
+}
+
+/// Lists the entries of the local directory (recursively), keyed by their relative path.
+fn scan_local(root: &Path, state_file: &Path) -> Result<BTreeMap<String, LocalEntry>> {
+    let mut entries = BTreeMap::default();
+    let mut queue = vec![(root.to_path_buf(), String::default())];
+
+    while let Some((dir, prefix)) = queue.pop() {
+        for dir_entry in std::fs::read_dir(&dir)? {
+            let dir_entry = dir_entry?;
+
+            // Entries with non-UTF-8 names can't be named the same within MEGA.
+            let Some(name) = dir_entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+
+            let metadata = dir_entry.metadata()?;
+            let local_path = dir_entry.path();
+            if name.ends_with(PARTIAL_SUFFIX)
+                || metadata.is_symlink()
+                || local_path == state_file
+                || local_path == transfer_state_file(state_file)
+            {
+                continue;
+            }
+
+            let path = if prefix.is_empty() {
+                name
+            } else {
+                format!("{prefix}/{name}")
+            };
+
+            let entry = if metadata.is_dir() {
+                queue.push((local_path, path.clone()));
+                LocalEntry {
+                    folder: true,
+                    size: 0,
+                    modified_at: 0,
+                    checksum: None,
+                }
+            } else {
+                LocalEntry {
+                    folder: false,
+                    size: metadata.len(),
+                    modified_at: modified_timestamp(&metadata),
+                    checksum: None,
+                }
+            };
+
+            entries.insert(path, entry);
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Lists the descendants of the remote folder, keyed by their relative path.
+///
+/// Only the first of multiple nodes with the same name within a folder gets synchronized.
+fn scan_remote<'a>(nodes: &'a Nodes, root: &'a Node) -> BTreeMap<String, &'a Node> {
+    let mut entries = BTreeMap::default();
+    let mut queue = vec![(root, String::default())];
+
+    while let Some((folder, prefix)) = queue.pop() {
+        let children = folder
+            .children()
+            .iter()
+            .filter_map(|handle| nodes.get_node_by_handle(handle));
+
+        for node in children {
+            if !node.kind().is_file() && !node.kind().is_folder() {
+                continue;
+            }
+
+            let path = if prefix.is_empty() {
+                node.name().to_string()
+            } else {
+                format!("{prefix}/{}", node.name())
+            };
+
+            if entries.contains_key(&path) {
+                continue;
+            }
+            if node.kind().is_folder() {
+                queue.push((node, path.clone()));
+            }
+            entries.insert(path, node);
+        }
+    }
+
+    entries
+}
+
+async fn local_checksum(path: &Path, size: u64) -> Result<[u8; 16]> {
+    let file = File::open(path)?;
+    fingerprint::compute_sparse_checksum(AllowStdIo::new(file), size).await
+}
+
+fn modified_timestamp(metadata: &std::fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .and_then(|duration| i64::try_from(duration.as_secs()).ok())
+        .unwrap_or_default()
+}
+
+/// Returns the last component of a relative path.
+fn file_name(path: &str) -> &str {
+    path.rsplit_once('/').map_or(path, |(_, name)| name)
+}
+
+/// Returns the path of the conflicted copy of a file (`notes (conflicted copy 2024-05-01 103000).txt`).
+fn conflicted_copy_path(path: &str, now: DateTime<Utc>) -> String {
+    let (parent, name) = match path.rsplit_once('/') {
+        Some((parent, name)) => (Some(parent), name),
+        None => (None, path),
+    };
+
+    // Leading dots are part of the name of hidden files, not of their extension.
+    let (stem, extension) = match name.rfind('.') {
+        Some(idx) if idx > 0 => name.split_at(idx),
+        _ => (name, ""),
+    };
+
+    let name = format!(
+        "{stem} (conflicted copy {}){extension}",
+        now.format("%Y-%m-%d %H%M%S")
+    );
+    match parent {
+        Some(parent) => format!("{parent}/{name}"),
+        None => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::tests::test_node;
+    use crate::NodeKind;
+
+    fn local_file(size: u64, modified_at: i64, checksum: Option<[u8; 16]>) -> LocalEntry {
+        LocalEntry {
+            folder: false,
+            size,
+            modified_at,
+            checksum,
+        }
+    }
+
+    fn remote_file(handle: &str, size: u64, checksum: [u8; 16]) -> Node {
+        let mut node = test_node(handle, NodeKind::File, "ROOT0000", &[]);
+        node.size = size;
+        node.sparse_checksum = Some(checksum);
+        node
+    }
+
+    fn synced_file(handle: &str, size: u64, modified_at: i64, checksum: [u8; 16]) -> SyncEntry {
+        SyncEntry {
+            folder: false,
+            size,
+            modified_at,
+            checksum: Some(checksum),
+            handle: Some(handle.to_string()),
+        }
+    }
+
+    #[test]
+    fn plans_transfers_for_one_sided_changes() {
+        let entries = BTreeMap::from([
+            (
+                "edited.txt".to_string(),
+                synced_file("EDIT0000", 3, 10, [1; 16]),
+            ),
+            (
+                "updated.txt".to_string(),
+                synced_file("UPDT0000", 3, 10, [2; 16]),
+            ),
+            (
+                "removed.txt".to_string(),
+                synced_file("REMV0000", 3, 10, [3; 16]),
+            ),
+            (
+                "touched.txt".to_string(),
+                synced_file("TOUC0000", 3, 10, [4; 16]),
+            ),
+        ]);
+        let local = BTreeMap::from([
+            ("edited.txt".to_string(), local_file(5, 20, Some([9; 16]))),
+            ("updated.txt".to_string(), local_file(3, 10, None)),
+            ("touched.txt".to_string(), local_file(3, 30, Some([4; 16]))),
+            ("new.txt".to_string(), local_file(1, 40, Some([5; 16]))),
+        ]);
+        let (edited, updated, touched) = (
+            remote_file("EDIT0000", 3, [1; 16]),
+            remote_file("UPDT0001", 4, [8; 16]),
+            remote_file("TOUC0000", 3, [4; 16]),
+        );
+        let remote = BTreeMap::from([
+            ("edited.txt".to_string(), &edited),
+            ("updated.txt".to_string(), &updated),
+            ("touched.txt".to_string(), &touched),
+        ]);
+
+        assert_eq!(
+            plan(&entries, &local, &remote),
+            [
+                SyncAction::Upload {
+                    path: "edited.txt".to_string(),
+                    replaces: Some("EDIT0000".to_string()),
+                },
+                SyncAction::Upload {
+                    path: "new.txt".to_string(),
+                    replaces: None,
+                },
+                SyncAction::Forget {
+                    path: "removed.txt".to_string(),
+                },
+                SyncAction::Record {
+                    path: "touched.txt".to_string(),
+                    entry: synced_file("TOUC0000", 3, 30, [4; 16]),
+                },
+                SyncAction::Download {
+                    path: "updated.txt".to_string(),
+                    handle: "UPDT0001".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_both_copies_on_conflict() {
+        let entries = BTreeMap::from([
+            (
+                "notes.md".to_string(),
+                synced_file("NOTE0000", 3, 10, [1; 16]),
+            ),
+            (
+                "same.md".to_string(),
+                synced_file("SAME0000", 3, 10, [2; 16]),
+            ),
+        ]);
+        let local = BTreeMap::from([
+            ("notes.md".to_string(), local_file(4, 20, Some([5; 16]))),
+            ("same.md".to_string(), local_file(4, 20, Some([6; 16]))),
+        ]);
+        let (notes, same) = (
+            remote_file("NOTE0001", 5, [7; 16]),
+            remote_file("SAME0001", 4, [6; 16]),
+        );
+        let remote = BTreeMap::from([
+            ("notes.md".to_string(), &notes),
+            ("same.md".to_string(), &same),
+        ]);
+
+        assert_eq!(
+            plan(&entries, &local, &remote),
+            [
+                SyncAction::Conflict {
+                    path: "notes.md".to_string(),
+                    handle: "NOTE0001".to_string(),
+                },
+                SyncAction::Record {
+                    path: "same.md".to_string(),
+                    entry: synced_file("SAME0001", 4, 20, [6; 16]),
+                },
+            ]
+        );
+
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 10, 30, 0).unwrap();
+        assert_eq!(
+            conflicted_copy_path("week 1/notes.md", now),
+            "week 1/notes (conflicted copy 2024-05-01 103000).md"
+        );
+        assert_eq!(
+            conflicted_copy_path(".hidden", now),
+            ".hidden (conflicted copy 2024-05-01 103000)"
+        );
+    }
+
+    #[test]
+    fn deleted_folders_are_recreated_to_hold_changes() {
+        let entries = BTreeMap::from([
+            (
+                "kept".to_string(),
+                SyncEntry::folder(Some("KEPT0000".to_string())),
+            ),
+            (
+                "kept/a.txt".to_string(),
+                synced_file("AAAA0000", 3, 10, [1; 16]),
+            ),
+            (
+                "gone".to_string(),
+                SyncEntry::folder(Some("GONE0000".to_string())),
+            ),
+            (
+                "gone/b.txt".to_string(),
+                synced_file("BBBB0000", 3, 10, [2; 16]),
+            ),
+        ]);
+        // Both folders got deleted locally, but a file got updated remotely within `kept` in the meantime.
+        let local = BTreeMap::default();
+        let (kept, a, gone, b) = (
+            test_node("KEPT0000", NodeKind::Folder, "ROOT0000", &["AAAA0001"]),
+            remote_file("AAAA0001", 4, [9; 16]),
+            test_node("GONE0000", NodeKind::Folder, "ROOT0000", &["BBBB0000"]),
+            remote_file("BBBB0000", 3, [2; 16]),
+        );
+        let remote = BTreeMap::from([
+            ("kept".to_string(), &kept),
+            ("kept/a.txt".to_string(), &a),
+            ("gone".to_string(), &gone),
+            ("gone/b.txt".to_string(), &b),
+        ]);
+
+        assert_eq!(
+            plan(&entries, &local, &remote),
+            [
+                SyncAction::DeleteRemote {
+                    path: "gone".to_string(),
+                    handle: "GONE0000".to_string(),
+                },
+                SyncAction::CreateLocalFolder {
+                    path: "kept".to_string(),
+                    handle: "KEPT0000".to_string(),
+                },
+                SyncAction::Download {
+                    path: "kept/a.txt".to_string(),
+                    handle: "AAAA0001".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn waits_for_created_nodes_to_come_back() {
+        let mut pending = synced_file("", 3, 10, [1; 16]);
+        pending.handle = None;
+        let entries = BTreeMap::from([("upload.txt".to_string(), pending.clone())]);
+        let local = BTreeMap::from([("upload.txt".to_string(), local_file(3, 10, None))]);
+
+        assert!(plan(&entries, &local, &BTreeMap::default()).is_empty());
+
+        let node = remote_file("UPLD0000", 3, [1; 16]);
+        let remote = BTreeMap::from([("upload.txt".to_string(), &node)]);
+        assert_eq!(
+            plan(&entries, &local, &remote),
+            [SyncAction::Record {
+                path: "upload.txt".to_string(),
+                entry: synced_file("UPLD0000", 3, 10, [1; 16]),
+            }]
+        );
+    }
+}
@@ -0,0 +1,634 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use aes::Aes128;
+use cipher::{KeyIvInit, StreamCipher};
+use futures::io::{AllowStdIo, AsyncReadExt, Cursor};
+use futures::stream::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::fingerprint::{self, compute_chunk_mac, condense_chunk_macs};
+use crate::protocol::commands::{Request, Response};
+use crate::{utils, Client, Error, ErrorCode, LastModified, Node, Result};
+
+/// Represents the progress of a chunked transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferProgress {
+    /// The amount of bytes transferred so far (including the ones from resumed attempts).
+    pub transferred: u64,
+    /// The total amount of bytes to transfer.
+    pub total: u64,
+}
+
+/// Represents the options for chunked transfers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferOptions {
+    /// The maximum amount of chunks to transfer concurrently.
+    pub concurrency: usize,
+    /// The file to persist the state of the transfer to, allowing to resume it after a failure.
+    ///
+    /// The state contains the encryption key of the transferred file, so it should be kept as private as the file itself.
+    /// It gets deleted once the transfer completes.
+    pub state_file: Option<PathBuf>,
+}
+
+impl Default for TransferOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            state_file: None,
+        }
+    }
+}
+
+/// Represents the persisted state of a chunked transfer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TransferState {
+    /// The handle of the downloaded node, or the URL to upload the chunks to.
+    target: String,
+    /// The size (in bytes) of the transferred file.
+    size: u64,
+    /// The AES key data of the transferred file.
+    aes_key: [u8; 16],
+    /// The AES IV data of the transferred file.
+    aes_iv: [u8; 8],
+    /// The MACs of the completed chunks, keyed by their offset.
+    chunks: BTreeMap<u64, [u8; 16]>,
+    /// The completion handle of the upload, once all of its chunks got received.
+    completion_handle: Option<String>,
+}
+
+impl TransferState {
+    fn new(target: String, size: u64, aes_key: [u8; 16], aes_iv: [u8; 8]) -> Self {
+        Self {
+            target,
+            size,
+            aes_key,
+            aes_iv,
+            chunks: BTreeMap::default(),
+            completion_handle: None,
+        }
+    }
+
+    /// Loads a previously persisted state (unreadable states are ignored, as if there were none).
+    fn load(path: &Path) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        json::from_slice(&data).ok()
+    }
+
+    fn save(&self, path: Option<&Path>) -> Result<()> {
+        if let Some(path) = path {
+            std::fs::write(path, json::to_vec(self)?)?;
+        }
+        Ok(())
+    }
+
+    /// Forgets about the completed chunks whose local data doesn't match their MAC anymore.
+    ///
+    /// Returns whether all the completed chunks were still valid.
+    fn verify_chunks(&mut self, file: &mut File) -> Result<bool> {
+        let bounds: BTreeMap<u64, u64> = chunk_bounds(self.size).into_iter().collect();
+        let mut buffer = Vec::default();
+        let mut valid = true;
+
+        for (offset, mac) in std::mem::take(&mut self.chunks) {
+            let Some(&len) = bounds.get(&offset) else {
+                valid = false;
+                continue;
+            };
+
+            buffer.resize(usize::try_from(len).unwrap(), 0);
+            file.seek(SeekFrom::Start(offset))?;
+            if file.read_exact(&mut buffer).is_err()
+                || compute_chunk_mac(&buffer, &self.aes_key, &self.aes_iv) != mac
+            {
+                valid = false;
+                continue;
+            }
+
+            self.chunks.insert(offset, mac);
+        }
+
+        Ok(valid)
+    }
+
+    /// Returns the amount of bytes covered by the completed chunks.
+    fn transferred(&self) -> u64 {
+        chunk_bounds(self.size)
+            .into_iter()
+            .filter(|(offset, _)| self.chunks.contains_key(offset))
+            .map(|(_, len)| len)
+            .sum()
+    }
+
+    fn condensed_mac(&self) -> [u8; 8] {
+        let chunk_macs: Vec<[u8; 16]> = self.chunks.values().copied().collect();
+        condense_chunk_macs(&chunk_macs, &self.aes_key)
+    }
+}
+
+impl Client {
+    /// Downloads a file into a local file, transferring multiple chunks concurrently.
+    ///
+    /// With a state file configured, an interrupted download can be resumed by calling this method again with the same options.
+    /// The chunks from previous attempts are only kept if the local data still matches their MACs,
+    /// and the condensed MAC of the whole file gets verified at the end, like with [`Client::download_node`].
+    pub async fn download_node_chunked<F>(
+        &self,
+        node: &Node,
+        path: &Path,
+        options: &TransferOptions,
+        progress: F,
+    ) -> Result<()>
+    where
+        F: Fn(TransferProgress) + Sync,
+    {
+        let (Some(aes_iv), Some(condensed_mac)) = (node.aes_iv, node.condensed_mac) else {
+            return Err(Error::UnsupportedNodeKind { kind: node.kind });
+        };
+
+        let state_file = options.state_file.as_deref();
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        file.set_len(node.size)?;
+
+        let mut state = state_file
+            .and_then(TransferState::load)
+            .filter(|state| {
+                state.target == node.handle
+                    && state.size == node.size
+                    && state.aes_key == node.aes_key
+                    && state.aes_iv == aes_iv
+            })
+            .unwrap_or_else(|| {
+                TransferState::new(node.handle.clone(), node.size, node.aes_key, aes_iv)
+            });
+        state.verify_chunks(&mut file)?;
+
+        let pending: Vec<(u64, u64)> = chunk_bounds(node.size)
+            .into_iter()
+            .filter(|(offset, _)| !state.chunks.contains_key(offset))
+            .collect();
+
+        let total = node.size;
+        let transferred = AtomicU64::new(state.transferred());
+        progress(TransferProgress {
+            transferred: transferred.load(Ordering::Relaxed),
+            total,
+        });
+
+        if !pending.is_empty() {
+            let url = self.request_download(node).await?.download_url;
+
+            let file = Mutex::new(&mut file);
+            let state = Mutex::new(&mut state);
+
+            futures::stream::iter(pending)
+                .map(|(offset, len)| {
+                    let (url, file, state) = (&url, &file, &state);
+                    let (transferred, progress) = (&transferred, &progress);
+                    async move {
+                        let mut data = self.download_chunk(url, offset, len).await?;
+                        apply_keystream(&node.aes_key, &aes_iv, offset, &mut data);
+                        let mac = compute_chunk_mac(&data, &node.aes_key, &aes_iv);
+
+                        {
+                            let mut file = file.lock().unwrap();
+                            file.seek(SeekFrom::Start(offset))?;
+                            file.write_all(&data)?;
+                        }
+
+                        {
+                            let mut state = state.lock().unwrap();
+                            state.chunks.insert(offset, mac);
+                            state.save(state_file)?;
+                        }
+
+                        let transferred = transferred.fetch_add(len, Ordering::Relaxed) + len;
+                        progress(TransferProgress { transferred, total });
+
+                        Ok::<_, Error>(())
+                    }
+                })
+                .buffer_unordered(options.concurrency.max(1))
+                .try_collect::<()>()
+                .await?;
+        }
+
+        file.sync_all()?;
+
+        // The recorded chunks can't be trusted for a later attempt, whatever the outcome.
+        remove_state_file(state_file)?;
+
+        if state.condensed_mac() != condensed_mac {
+            return Err(Error::CondensedMacMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Uploads a local file within a parent folder, transferring multiple chunks concurrently.
+    ///
+    /// With a state file configured, an interrupted upload can be resumed by calling this method again with the same options.
+    /// The upload starts over if the local file doesn't match the MACs of the chunks from previous attempts anymore.
+    pub async fn upload_node_chunked<F>(
+        &self,
+        parent: &Node,
+        name: &str,
+        path: &Path,
+        last_modified: LastModified,
+        options: &TransferOptions,
+        progress: F,
+    ) -> Result<()>
+    where
+        F: Fn(TransferProgress) + Sync,
+    {
+        if !self.has_user_session() {
+            return Err(Error::MissingUserSession);
+        }
+
+        let state_file = options.state_file.as_deref();
+
+        let mut file = File::open(path)?;
+        let size = file.metadata()?.len();
+
+        let mut resumed = state_file
+            .and_then(TransferState::load)
+            .filter(|state| state.size == size && Url::parse(&state.target).is_ok());
+
+        if let Some(state) = resumed.as_mut() {
+            if !state.verify_chunks(&mut file)? {
+                resumed = None;
+            }
+        }
+
+        let mut state = match resumed {
+            Some(state) => state,
+            None => {
+                let request = Request::Upload {
+                    s: size,
+                    ssl: if self.state.https { 2 } else { 0 },
+                };
+                let responses = self.send_requests(&[request]).await?;
+
+                let upload_url = match responses.as_slice() {
+                    [Response::Upload(response)] => response.upload_url.clone(),
+                    [Response::Error(code)] => {
+                        return Err(Error::from(*code));
+                    }
+                    _ => {
+                        return Err(Error::InvalidResponseType);
+                    }
+                };
+
+                let (aes_key, aes_iv) = rand::random();
+                TransferState::new(upload_url, size, aes_key, aes_iv)
+            }
+        };
+
+        let mut pending: Vec<(u64, u64)> = chunk_bounds(size)
+            .into_iter()
+            .filter(|(offset, _)| !state.chunks.contains_key(offset))
+            .collect();
+
+        // Empty files don't have any chunks, but their upload still needs to be completed.
+        if size == 0 && state.completion_handle.is_none() {
+            pending.push((0, 0));
+        }
+
+        let total = size;
+        let transferred = AtomicU64::new(state.transferred());
+        progress(TransferProgress {
+            transferred: transferred.load(Ordering::Relaxed),
+            total,
+        });
+
+        {
+            let (aes_key, aes_iv) = (state.aes_key, state.aes_iv);
+            let url = state.target.clone();
+
+            let file = Mutex::new(&mut file);
+            let state = Mutex::new(&mut state);
+
+            futures::stream::iter(pending)
+                .map(|(offset, len)| {
+                    let (url, file, state) = (&url, &file, &state);
+                    let (transferred, progress) = (&transferred, &progress);
+                    async move {
+                        let mut data = vec![0u8; usize::try_from(len).unwrap()];
+                        {
+                            let mut file = file.lock().unwrap();
+                            file.seek(SeekFrom::Start(offset))?;
+                            file.read_exact(&mut data)?;
+                        }
+
+                        let mac = compute_chunk_mac(&data, &aes_key, &aes_iv);
+                        apply_keystream(&aes_key, &aes_iv, offset, &mut data);
+
+                        let completion_handle = self.upload_chunk(url, offset, data).await?;
+
+                        {
+                            let mut state = state.lock().unwrap();
+                            if len > 0 {
+                                state.chunks.insert(offset, mac);
+                            }
+                            if completion_handle.is_some() {
+                                state.completion_handle = completion_handle;
+                            }
+                            state.save(state_file)?;
+                        }
+
+                        let transferred = transferred.fetch_add(len, Ordering::Relaxed) + len;
+                        progress(TransferProgress { transferred, total });
+
+                        Ok::<_, Error>(())
+                    }
+                })
+                .buffer_unordered(options.concurrency.max(1))
+                .try_collect::<()>()
+                .await?;
+        }
+
+        let completion_handle =
+            state
+                .completion_handle
+                .clone()
+                .ok_or(Error::MissingResponseField {
+                    field: "completion handle",
+                })?;
+
+        let sparse_checksum = {
+            file.seek(SeekFrom::Start(0))?;
+            fingerprint::compute_sparse_checksum(AllowStdIo::new(&mut file), size).await?
+        };
+
+        self.complete_upload(
+            parent,
+            name,
+            (state.aes_key, state.aes_iv),
+            state.condensed_mac(),
+            sparse_checksum,
+            last_modified,
+            completion_handle,
+        )
+        .await?;
+
+        remove_state_file(state_file)
+    }
+
+    /// Downloads the (still encrypted) data of a single chunk.
+    async fn download_chunk(&self, url: &str, offset: u64, len: u64) -> Result<Vec<u8>> {
+        let url = Url::parse(&format!("{url}/{offset}-{}", offset + len - 1))?;
+
+        let url = &url;
+        self.retry_chunk(move || async move {
+            let mut reader = self.client.get(url.clone()).await?.take(len);
+
+            let mut buffer = Vec::with_capacity(usize::try_from(len).unwrap());
+            reader.read_to_end(&mut buffer).await?;
+
+            if buffer.len() as u64 != len {
+                return Err(Error::from(io::Error::from(io::ErrorKind::UnexpectedEof)));
+            }
+
+            Ok(buffer)
+        })
+        .await
+    }
+
+    /// Uploads the (already encrypted) data of a single chunk.
+    ///
+    /// Returns the completion handle if this chunk was the last one the upload was waiting for.
+    async fn upload_chunk(&self, url: &str, offset: u64, data: Vec<u8>) -> Result<Option<String>> {
+        let url = Url::parse(&format!("{url}/{offset}"))?;
+
+        let (url, data) = (&url, &data);
+        let response = self
+            .retry_chunk(move || async move {
+                let body = Box::pin(Cursor::new(data.clone()));
+                let content_length = Some(data.len() as u64);
+                let mut reader = self.client.post(url.clone(), body, content_length).await?;
+
+                let mut buffer = Vec::default();
+                reader.read_to_end(&mut buffer).await?;
+
+                Ok(String::from_utf8_lossy(&buffer).into_owned())
+            })
+            .await?;
+
+        if response.is_empty() {
+            return Ok(None);
+        }
+
+        // Failures are reported as (negative) error codes instead of a completion handle.
+        if let Ok(code) = json::from_str::<ErrorCode>(&response) {
+            return Err(Error::from(code));
+        }
+
+        Ok(Some(response))
+    }
+
+    /// Performs a chunk transfer, respecting the configured rules about retries.
+    async fn retry_chunk<T, Fut>(&self, transfer: impl Fn() -> Fut) -> Result<T>
+    where
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut delay = self.state.min_retry_delay;
+        let mut attempt = 1;
+        loop {
+            match transfer().await {
+                Ok(value) => return Ok(value),
+                Err(error) if attempt >= self.state.max_retries => return Err(error),
+                Err(_) => {}
+            }
+
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(self.state.max_retry_delay);
+            attempt += 1;
+        }
+    }
+}
+
+/// Returns the offset and length of all the chunks of a file, as delimited by MEGA.
+fn chunk_bounds(size: u64) -> Vec<(u64, u64)> {
+    utils::chunks_iterator()
+        .take_while(|(start, _)| *start < size)
+        .map(|(start, end)| (start, end.min(size - 1) - start + 1))
+        .collect()
+}
+
+/// Encrypts (or decrypts) the data of a chunk in place, the chunk starting at the given offset of the file.
+fn apply_keystream(aes_key: &[u8; 16], aes_iv: &[u8; 8], offset: u64, data: &mut [u8]) {
+    let mut iv = [0u8; 16];
+    iv[..8].copy_from_slice(aes_iv);
+    iv[8..].copy_from_slice(&(offset / 16).to_be_bytes());
+
+    let mut ctr = ctr::Ctr128BE::<Aes128>::new(aes_key.into(), (&iv).into());
+    ctr.apply_keystream(data);
+}
+
+fn remove_state_file(path: Option<&Path>) -> Result<()> {
+    match path.map(std::fs::remove_file) {
+        Some(Err(error)) if error.kind() != io::ErrorKind::NotFound => Err(error.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use base64::prelude::{Engine, BASE64_URL_SAFE_NO_PAD};
+
+    use super::*;
+    use crate::http::mock::MockClient;
+    use crate::tests::{test_client, test_node, MASTER_KEY};
+    use crate::utils::rsa::RsaPrivateKey;
+    use crate::NodeKind;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mega-{}-{name}", utils::random_string(8)))
+    }
+
+    #[tokio::test]
+    async fn chunked_download_resumes_after_failure() {
+        // 4 chunks: 128 KiB, 256 KiB, 384 KiB and the remaining 208,608 bytes.
+        let data: Vec<u8> = (0..1_000_000).map(|_| rand::random()).collect();
+
+        let mut node = test_node("FILE0000", NodeKind::File, "ROOT0000", &[]);
+        node.size = data.len() as u64;
+        let (aes_key, aes_iv) = (node.aes_key, node.aes_iv.unwrap());
+        node.condensed_mac = Some(
+            fingerprint::compute_condensed_mac(Cursor::new(&data), node.size, &aes_key, &aes_iv)
+                .await
+                .unwrap(),
+        );
+
+        let mut encrypted = data.clone();
+        apply_keystream(&aes_key, &aes_iv, 0, &mut encrypted);
+        let download = json::json!({ "g": "https://dl.test/file", "s": node.size, "at": "" });
+
+        let path = temp_path("download");
+        let options = TransferOptions {
+            concurrency: 1,
+            state_file: Some(temp_path("download.state")),
+        };
+
+        // The connection drops after the first two chunks.
+        let mock = MockClient::new([download.clone()])
+            .with_remote(encrypted.clone())
+            .failing_after(2);
+        let mut client = test_client(mock, RsaPrivateKey::default());
+        client.state.max_retries = 2;
+        client.state.min_retry_delay = Duration::from_millis(1);
+
+        let result = client
+            .download_node_chunked(&node, &path, &options, |_| {})
+            .await;
+        assert!(matches!(result, Err(Error::IoError { .. })));
+
+        // Corrupting a downloaded chunk gets it downloaded again.
+        {
+            let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+            file.seek(SeekFrom::Start(1_000)).unwrap();
+            file.write_all(b"corrupted").unwrap();
+        }
+
+        let mock = MockClient::new([download]).with_remote(encrypted);
+        let downloads = mock.downloads();
+        let client = test_client(mock, RsaPrivateKey::default());
+
+        let updates = Mutex::new(Vec::default());
+        client
+            .download_node_chunked(&node, &path, &options, |progress| {
+                updates.lock().unwrap().push(progress.transferred);
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            downloads.lock().unwrap().as_slice(),
+            [(0, 131_071), (393_216, 786_431), (786_432, 999_999)]
+        );
+        assert_eq!(
+            updates.into_inner().unwrap(),
+            [262_144, 393_216, 786_432, 1_000_000]
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+        assert!(!options.state_file.as_ref().unwrap().exists());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn chunked_upload_produces_valid_node() {
+        let data: Vec<u8> = (0..700_000).map(|_| rand::random()).collect();
+        let path = temp_path("upload");
+        std::fs::write(&path, &data).unwrap();
+
+        let mock = MockClient::new([
+            json::json!({ "p": "https://ul.test/file" }),
+            json::json!({ "f": [] }),
+        ])
+        .with_remote(vec![0; data.len()]);
+        let (requests, remote) = (mock.requests(), mock.remote());
+        let client = test_client(mock, RsaPrivateKey::default());
+
+        let parent = test_node("ROOT0000", NodeKind::Root, "", &[]);
+        let options = TransferOptions {
+            concurrency: 3,
+            state_file: Some(temp_path("upload.state")),
+        };
+
+        client
+            .upload_node_chunked(
+                &parent,
+                "lecture.zip",
+                &path,
+                LastModified::Now,
+                &options,
+                |_| {},
+            )
+            .await
+            .unwrap();
+
+        let requests = requests.lock().unwrap().clone();
+        let [upload, complete] = requests.as_slice() else {
+            panic!("unexpected requests: {requests:?}");
+        };
+        assert_eq!(upload["s"], 700_000);
+        assert_eq!(complete["n"][0]["h"], "CompletionHandle");
+
+        let mut key = BASE64_URL_SAFE_NO_PAD
+            .decode(complete["n"][0]["k"].as_str().unwrap())
+            .unwrap();
+        utils::decrypt_ebc_in_place(&MASTER_KEY, &mut key);
+        utils::unmerge_key_mac(&mut key);
+        let aes_key: [u8; 16] = key[..16].try_into().unwrap();
+        let aes_iv: [u8; 8] = key[16..24].try_into().unwrap();
+
+        let mut uploaded = remote.lock().unwrap().clone();
+        apply_keystream(&aes_key, &aes_iv, 0, &mut uploaded);
+        assert_eq!(uploaded, data);
+
+        let condensed_mac =
+            fingerprint::compute_condensed_mac(Cursor::new(&data), 700_000, &aes_key, &aes_iv)
+                .await
+                .unwrap();
+        assert_eq!(key[24..], condensed_mac);
+        assert!(!options.state_file.as_ref().unwrap().exists());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -16,7 +16,7 @@ pub mod rsa;
 
 use crate::http::UserSession;
 use crate::protocol::commands::UserAttributesResponse;
-use crate::Result;
+use crate::{Error, Result};
 
 /// Represents storage quotas from MEGA.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -77,6 +77,14 @@ pub(crate) fn merge_key_mac(key: &mut [u8]) {
     }
 }
 
+/// Computes the authentication of a share handle, proving knowledge of the owner's master key.
+pub(crate) fn share_handle_auth(key: &[u8], handle: &str) -> String {
+    let mut data = [handle.as_bytes(), handle.as_bytes()].concat();
+    data.resize(16, 0);
+    encrypt_ebc_in_place(key, &mut data);
+    BASE64_URL_SAFE_NO_PAD.encode(&data)
+}
+
 pub(crate) fn random_string(len: usize) -> String {
     let mut rng = rand::thread_rng();
     Alphanumeric.sample_string(&mut rng, len)
@@ -143,7 +151,9 @@ pub(crate) fn extract_share_keys(
     // - 12 bytes of IV data suitable for the AES-128-GCM algorithm.
     // - arbitrarily-sized payload to be decrypted using AES-128-GCM.
 
-    assert_eq!(attr_value[0], 20);
+    if attr_value.len() < 14 || attr_value[0] != 20 {
+        return Err(Error::InvalidResponseFormat);
+    }
     let (iv, data) = attr_value[2..].split_at(12);
     let gcm = Aes128Gcm::new(derived_key.as_slice().into());
     let mut data = data.to_vec();
@@ -155,16 +165,18 @@ pub(crate) fn extract_share_keys(
     let mut share_keys = HashMap::default();
 
     let mut cursor = 0;
-    while cursor < data.len() {
-        let Ok(tag) = KeysAttrTag::try_from(data[cursor]) else {
-            continue;
-        };
+    while cursor + 4 <= data.len() {
+        let tag = KeysAttrTag::try_from(data[cursor]);
         let len = (usize::from(data[cursor + 1]) << 16)
             + (usize::from(data[cursor + 2]) << 8)
             + usize::from(data[cursor + 3]);
         cursor += 4;
 
-        if tag == KeysAttrTag::ShareKeys {
+        if cursor + len > data.len() {
+            return Err(Error::InvalidResponseFormat);
+        }
+
+        if tag == Ok(KeysAttrTag::ShareKeys) {
             // The share keys section consists of multiple 23 bytes long chunks,
             // each corresponding to a single share key.
             // Each chunk consists of:
@@ -173,7 +185,7 @@ pub(crate) fn extract_share_keys(
             // - trust flag (1 byte)
             //   (this flag seems to have to do with whether the key has been "exposed" in some way?)
 
-            for chunk in data[cursor..(cursor + len)].chunks(23) {
+            for chunk in data[cursor..(cursor + len)].chunks_exact(23) {
                 let (handle, rest) = chunk.split_at(6);
                 let (share_key, _trust) = rest.split_at(16);
                 let handle = BASE64_URL_SAFE_NO_PAD.encode(handle);
@@ -214,7 +226,6 @@ pub(crate) fn extract_attachments(attrs_str: &str) -> (Option<String>, Option<St
 }
 
 /// Produces an infinite iterator of all the consecutive chunk bounds.
-#[allow(unused)]
 pub(crate) fn chunks_iterator() -> impl Iterator<Item = (u64, u64)> {
     std::iter::successors(Some(131_072), |&(mut chunk_size): &u64| {
         if chunk_size < 1_048_576 {
@@ -29,6 +29,64 @@ impl RsaPrivateKey {
         let m = rsa::BigUint::from_bytes_be(data);
         decrypt_rsa(&m, &self.p, &self.q, &self.d).to_bytes_be()
     }
+
+    /// Decrypts a key that got padded before encryption (see [`RsaPublicKey::encrypt_key`]).
+    pub fn decrypt_key(&self, data: &[u8], key_size: usize) -> Vec<u8> {
+        let n = &self.p * &self.q;
+        let padded_len = n.bits().div_ceil(8).saturating_sub(2);
+
+        // Leading zero bytes of the padded key are lost in the integer conversion.
+        let mut decrypted = self.decrypt(data);
+        if decrypted.len() < padded_len {
+            let missing = padded_len - decrypted.len();
+            decrypted.splice(0..0, vec![0; missing]);
+        }
+
+        decrypted.truncate(key_size);
+        decrypted
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RsaPublicKey {
+    pub n: BigUint,
+    pub e: BigUint,
+}
+
+impl RsaPublicKey {
+    pub fn from_mpi_bytes(data: &[u8]) -> Result<Self> {
+        let (n, data) = get_mpi(data).map_err(|_| Error::InvalidRsaPublicKeyFormat)?;
+        let (e, _) = get_mpi(data).map_err(|_| Error::InvalidRsaPublicKeyFormat)?;
+        Ok(Self {
+            n: rsa::BigUint::from_bytes_be(n),
+            e: rsa::BigUint::from_bytes_be(e),
+        })
+    }
+
+    /// Encrypts a key for the owner of this public key, returning it as an MPI-formatted number.
+    ///
+    /// Like MEGA's clients, the key is padded with random bytes up to 2 bytes short of the modulus size.
+    pub fn encrypt_key(&self, key: &[u8]) -> Vec<u8> {
+        let padded_len = self.n.bits().div_ceil(8).saturating_sub(2);
+
+        let mut data = key.to_vec();
+        data.extend((key.len()..padded_len).map(|_| rand::random::<u8>()));
+
+        let m = rsa::BigUint::from_bytes_be(&data);
+        let c = m.modpow(&self.e, &self.n);
+        to_mpi(&c)
+    }
+}
+
+/// Encodes the number as MPI-formatted bytes (16-bit bit length prefix, followed by the bytes in BE order).
+pub(crate) fn to_mpi(value: &rsa::BigUint) -> Vec<u8> {
+    let bytes = value.to_bytes_be();
+    let bits = bytes.len() * 8;
+    let mut output = Vec::with_capacity(bytes.len() + 2);
+    output.push((bits >> 8) as u8);
+    output.push(bits as u8);
+    output.extend_from_slice(&bytes);
+    output
 }
 
 /// Extracts the bytes (in BE order) of the MPI-formatted number, along with the rest of the data.
@@ -0,0 +1,111 @@
+//! Objects exported to BlueZ
+//!
+//! BlueZ reads the qaul advertisement and GATT service from
+//! these objects after they were registered, and calls
+//! `WriteValue` on the message characteristic for every
+//! chunk a remote device writes to us.
+
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use zbus::dbus_interface;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+/// `org.bluez.LEAdvertisement1` of the qaul service
+pub struct Advertisement {
+    pub service_uuid: String,
+}
+
+#[dbus_interface(name = "org.bluez.LEAdvertisement1")]
+impl Advertisement {
+    /// called by BlueZ when the advertisement was removed
+    fn release(&self) {
+        log::info!("BlueZ released the qaul advertisement");
+    }
+
+    #[dbus_interface(property, name = "Type")]
+    fn kind(&self) -> String {
+        "peripheral".to_string()
+    }
+
+    #[dbus_interface(property, name = "ServiceUUIDs")]
+    fn service_uuids(&self) -> Vec<String> {
+        vec![self.service_uuid.clone()]
+    }
+}
+
+/// `org.bluez.GattService1` of the qaul service
+pub struct Service {
+    pub uuid: String,
+}
+
+#[dbus_interface(name = "org.bluez.GattService1")]
+impl Service {
+    #[dbus_interface(property, name = "UUID")]
+    fn uuid(&self) -> String {
+        self.uuid.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn primary(&self) -> bool {
+        true
+    }
+}
+
+/// `org.bluez.GattCharacteristic1` of the qaul service
+///
+/// The read characteristic returns the small qaul id of this node,
+/// the message characteristic forwards written chunks together
+/// with the object path of the writing device.
+pub struct Characteristic {
+    pub uuid: String,
+    pub service: OwnedObjectPath,
+    pub value: Vec<u8>,
+    pub writes: Option<mpsc::UnboundedSender<(OwnedObjectPath, Vec<u8>)>>,
+}
+
+#[dbus_interface(name = "org.bluez.GattCharacteristic1")]
+impl Characteristic {
+    fn read_value(&self, _options: HashMap<String, OwnedValue>) -> Vec<u8> {
+        self.value.clone()
+    }
+
+    fn write_value(
+        &self,
+        value: Vec<u8>,
+        options: HashMap<String, OwnedValue>,
+    ) -> zbus::fdo::Result<()> {
+        let writes = self
+            .writes
+            .as_ref()
+            .ok_or_else(|| zbus::fdo::Error::NotSupported("read only".to_string()))?;
+        let device = match options.get("device").map(|value| &**value) {
+            Some(Value::ObjectPath(path)) => OwnedObjectPath::from(path.to_owned()),
+            _ => {
+                return Err(zbus::fdo::Error::InvalidArgs(
+                    "device option missing".to_string(),
+                ))
+            }
+        };
+        writes
+            .send((device, value))
+            .map_err(|_| zbus::fdo::Error::Failed("BLE host stopped".to_string()))
+    }
+
+    #[dbus_interface(property, name = "UUID")]
+    fn uuid(&self) -> String {
+        self.uuid.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn service(&self) -> OwnedObjectPath {
+        self.service.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn flags(&self) -> Vec<String> {
+        match self.writes {
+            Some(_) => vec!["write".to_string()],
+            None => vec!["read".to_string()],
+        }
+    }
+}
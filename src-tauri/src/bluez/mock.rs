@@ -0,0 +1,541 @@
+//! # Mock BlueZ Service
+//!
+//! Serves the BlueZ interfaces used by the BLE host on a
+//! peer-to-peer D-Bus connection. The host can therefore run
+//! without a system bus or Bluetooth adapter.
+//!
+//! Remote qaul nodes are simulated as peers: `add_peer` adds a
+//! device advertising the qaul service, `peer_write` writes a
+//! message from a peer to the host, and `written` returns the
+//! chunks the host wrote to a peer.
+
+use std::collections::{BTreeMap, HashMap};
+use std::os::unix::net::UnixStream;
+use std::sync::{Arc, Mutex};
+use zbus::fdo::{ManagedObjects, ObjectManager};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{dbus_interface, Connection, ConnectionBuilder, Guid};
+
+use super::{
+    frame, ADAPTER_PATH, CHARACTERISTIC_INTERFACE, MESSAGE_CHARACTERISTIC_UUID,
+    READ_CHARACTERISTIC_UUID, SERVICE_UUID,
+};
+
+/// Mock BlueZ service
+pub struct MockBluez {
+    connection: Connection,
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Default)]
+struct MockState {
+    discovering: bool,
+    advertisement: Option<OwnedObjectPath>,
+    application: Option<OwnedObjectPath>,
+    /// small qaul ids of the peers, by address
+    qaul_ids: BTreeMap<String, Vec<u8>>,
+    /// chunks written to the peers, by address
+    written: BTreeMap<String, Vec<Vec<u8>>>,
+}
+
+impl MockBluez {
+    /// Start the mock service
+    ///
+    /// Returns the service and the connection the host uses.
+    pub async fn start() -> zbus::Result<(MockBluez, Connection)> {
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let (service, host) = UnixStream::pair()?;
+        let guid = Guid::generate();
+
+        let (connection, host) = futures::try_join!(
+            ConnectionBuilder::unix_stream(service)
+                .server(&guid)
+                .p2p()
+                .serve_at("/", ObjectManager)?
+                .serve_at(
+                    ADAPTER_PATH,
+                    MockAdapter {
+                        state: state.clone(),
+                    }
+                )?
+                .serve_at(
+                    ADAPTER_PATH,
+                    MockAdvertisingManager {
+                        state: state.clone(),
+                    }
+                )?
+                .serve_at(
+                    ADAPTER_PATH,
+                    MockGattManager {
+                        state: state.clone(),
+                    }
+                )?
+                .build(),
+            ConnectionBuilder::unix_stream(host).p2p().build(),
+        )?;
+
+        Ok((MockBluez { connection, state }, host))
+    }
+
+    /// is the host advertising the qaul service
+    pub fn is_advertising(&self) -> bool {
+        self.state.lock().unwrap().advertisement.is_some()
+    }
+
+    /// is the host scanning
+    pub fn is_discovering(&self) -> bool {
+        self.state.lock().unwrap().discovering
+    }
+
+    /// add a remote qaul node in range
+    pub async fn add_peer(&self, address: &str, qaul_id: &[u8], mtu: u16) -> zbus::Result<()> {
+        let device = device_path(address);
+        let server = self.connection.object_server();
+        self.state
+            .lock()
+            .unwrap()
+            .qaul_ids
+            .insert(address.to_string(), qaul_id.to_vec());
+
+        server
+            .at(
+                format!("{}/service0001/char0002", device),
+                MockCharacteristic {
+                    uuid: READ_CHARACTERISTIC_UUID.to_string(),
+                    value: qaul_id.to_vec(),
+                    address: None,
+                    mtu,
+                    state: self.state.clone(),
+                },
+            )
+            .await?;
+        server
+            .at(
+                format!("{}/service0001/char0004", device),
+                MockCharacteristic {
+                    uuid: MESSAGE_CHARACTERISTIC_UUID.to_string(),
+                    value: Vec::new(),
+                    address: Some(address.to_string()),
+                    mtu,
+                    state: self.state.clone(),
+                },
+            )
+            .await?;
+        // the device is announced last, as BlueZ only
+        // announces devices after receiving an advertisement
+        server
+            .at(
+                device,
+                MockDevice {
+                    address: address.to_string(),
+                    connected: false,
+                },
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// remove a remote node, as BlueZ does when it is out of range
+    pub async fn remove_peer(&self, address: &str) -> zbus::Result<()> {
+        let device = device_path(address);
+        let server = self.connection.object_server();
+        server
+            .remove::<MockCharacteristic, _>(format!("{}/service0001/char0002", device))
+            .await?;
+        server
+            .remove::<MockCharacteristic, _>(format!("{}/service0001/char0004", device))
+            .await?;
+        server.remove::<MockDevice, _>(device.as_str()).await?;
+        Ok(())
+    }
+
+    /// write a message from a peer to the message characteristic of the host
+    pub async fn peer_write(&self, address: &str, data: &[u8], mtu: u16) -> zbus::Result<()> {
+        let (application, qaul_id) = {
+            let state = self.state.lock().unwrap();
+            let application = state
+                .application
+                .clone()
+                .ok_or_else(|| zbus::Error::Failure("no application registered".to_string()))?;
+            (application, state.qaul_ids.get(address).cloned().unwrap_or_default())
+        };
+        let characteristic =
+            find_characteristic(&self.connection, &application, MESSAGE_CHARACTERISTIC_UUID)
+                .await?;
+        let device = ObjectPath::try_from(device_path(address))?;
+
+        for chunk in frame(&qaul_id, data, mtu.into()) {
+            let options = HashMap::from([("device", Value::from(device.clone()))]);
+            self.connection
+                .call_method(
+                    None::<()>,
+                    characteristic.as_str(),
+                    Some(CHARACTERISTIC_INTERFACE),
+                    "WriteValue",
+                    &(chunk, options),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// chunks the host wrote to a peer
+    pub fn written(&self, address: &str) -> Vec<Vec<u8>> {
+        self.state
+            .lock()
+            .unwrap()
+            .written
+            .get(address)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+fn device_path(address: &str) -> String {
+    format!("{}/dev_{}", ADAPTER_PATH, address.replace(':', "_"))
+}
+
+/// find a characteristic of the application exported by the host
+async fn find_characteristic(
+    connection: &Connection,
+    application: &OwnedObjectPath,
+    uuid: &str,
+) -> zbus::Result<OwnedObjectPath> {
+    let objects: ManagedObjects = connection
+        .call_method(
+            None::<()>,
+            application.as_str(),
+            Some("org.freedesktop.DBus.ObjectManager"),
+            "GetManagedObjects",
+            &(),
+        )
+        .await?
+        .body()?;
+
+    objects
+        .into_iter()
+        .find(|(_, interfaces)| {
+            interfaces.iter().any(|(interface, properties)| {
+                interface.as_str() == CHARACTERISTIC_INTERFACE
+                    && matches!(
+                        properties.get("UUID").map(|value| &**value),
+                        Some(Value::Str(value)) if value.as_str() == uuid
+                    )
+            })
+        })
+        .map(|(path, _)| path)
+        .ok_or_else(|| zbus::Error::Failure(format!("characteristic {} not exported", uuid)))
+}
+
+struct MockAdapter {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[dbus_interface(name = "org.bluez.Adapter1")]
+impl MockAdapter {
+    fn start_discovery(&self) {
+        self.state.lock().unwrap().discovering = true;
+    }
+
+    fn stop_discovery(&self) {
+        self.state.lock().unwrap().discovering = false;
+    }
+
+    fn set_discovery_filter(&self, _filter: HashMap<String, OwnedValue>) {}
+
+    #[dbus_interface(property)]
+    fn address(&self) -> String {
+        "00:00:00:00:00:01".to_string()
+    }
+
+    #[dbus_interface(property)]
+    fn name(&self) -> String {
+        "mock".to_string()
+    }
+
+    #[dbus_interface(property)]
+    fn powered(&self) -> bool {
+        true
+    }
+}
+
+struct MockAdvertisingManager {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[dbus_interface(name = "org.bluez.LEAdvertisingManager1")]
+impl MockAdvertisingManager {
+    fn register_advertisement(
+        &self,
+        advertisement: OwnedObjectPath,
+        _options: HashMap<String, OwnedValue>,
+    ) {
+        self.state.lock().unwrap().advertisement = Some(advertisement);
+    }
+
+    fn unregister_advertisement(&self, _advertisement: OwnedObjectPath) {
+        self.state.lock().unwrap().advertisement = None;
+    }
+}
+
+struct MockGattManager {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[dbus_interface(name = "org.bluez.GattManager1")]
+impl MockGattManager {
+    /// BlueZ reads the GATT application on registration
+    async fn register_application(
+        &self,
+        application: OwnedObjectPath,
+        _options: HashMap<String, OwnedValue>,
+        #[zbus(connection)] connection: &Connection,
+    ) -> zbus::fdo::Result<()> {
+        for uuid in [READ_CHARACTERISTIC_UUID, MESSAGE_CHARACTERISTIC_UUID] {
+            find_characteristic(connection, &application, uuid)
+                .await
+                .map_err(|e| zbus::fdo::Error::InvalidArgs(e.to_string()))?;
+        }
+        self.state.lock().unwrap().application = Some(application);
+        Ok(())
+    }
+
+    fn unregister_application(&self, _application: OwnedObjectPath) {
+        self.state.lock().unwrap().application = None;
+    }
+}
+
+struct MockDevice {
+    address: String,
+    connected: bool,
+}
+
+#[dbus_interface(name = "org.bluez.Device1")]
+impl MockDevice {
+    fn connect(&mut self) {
+        self.connected = true;
+    }
+
+    fn disconnect(&mut self) {
+        self.connected = false;
+    }
+
+    #[dbus_interface(property)]
+    fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    #[dbus_interface(property, name = "RSSI")]
+    fn rssi(&self) -> i16 {
+        -60
+    }
+
+    #[dbus_interface(property, name = "UUIDs")]
+    fn uuids(&self) -> Vec<String> {
+        vec![SERVICE_UUID.to_string()]
+    }
+
+    #[dbus_interface(property)]
+    fn services_resolved(&self) -> bool {
+        self.connected
+    }
+}
+
+struct MockCharacteristic {
+    uuid: String,
+    value: Vec<u8>,
+    /// address of the peer for the message characteristic
+    address: Option<String>,
+    mtu: u16,
+    state: Arc<Mutex<MockState>>,
+}
+
+#[dbus_interface(name = "org.bluez.GattCharacteristic1")]
+impl MockCharacteristic {
+    fn read_value(&self, _options: HashMap<String, OwnedValue>) -> Vec<u8> {
+        self.value.clone()
+    }
+
+    fn write_value(
+        &self,
+        value: Vec<u8>,
+        _options: HashMap<String, OwnedValue>,
+    ) -> zbus::fdo::Result<()> {
+        let address = self
+            .address
+            .clone()
+            .ok_or_else(|| zbus::fdo::Error::NotSupported("read only".to_string()))?;
+        if value.len() + 3 > self.mtu as usize {
+            return Err(zbus::fdo::Error::InvalidArgs("exceeds MTU".to_string()));
+        }
+        self.state
+            .lock()
+            .unwrap()
+            .written
+            .entry(address)
+            .or_default()
+            .push(value);
+        Ok(())
+    }
+
+    #[dbus_interface(property, name = "UUID")]
+    fn uuid(&self) -> String {
+        self.uuid.clone()
+    }
+
+    #[dbus_interface(property, name = "MTU")]
+    fn mtu(&self) -> u16 {
+        self.mtu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{BluezHost, Reassembly};
+    use super::*;
+    use libqaul::connections::ble::proto;
+    use prost::Message;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    const PEER: &str = "AA:BB:CC:DD:EE:FF";
+
+    async fn next(receiver: &mut mpsc::UnboundedReceiver<Vec<u8>>) -> proto::ble::Message {
+        let data = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .expect("no sys message from host")
+            .unwrap();
+        proto::Ble::decode(&data[..]).unwrap().message.unwrap()
+    }
+
+    fn sys(message: proto::ble::Message) -> Vec<u8> {
+        proto::Ble {
+            message: Some(message),
+        }
+        .encode_to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_host_against_mock() {
+        let (mock, connection) = MockBluez::start().await.unwrap();
+        let (to_node, mut from_host) = mpsc::unbounded_channel();
+        let host = BluezHost::new(connection, to_node);
+
+        host.process(&sys(proto::ble::Message::InfoRequest(
+            proto::BleInfoRequest {},
+        )))
+        .await;
+        match next(&mut from_host).await {
+            proto::ble::Message::InfoResponse(info) => {
+                assert_eq!(info.device.unwrap().id, "00:00:00:00:00:01")
+            }
+            message => panic!("unexpected {:?}", message),
+        }
+
+        host.process(&sys(proto::ble::Message::StartRequest(
+            proto::BleStartRequest {
+                qaul_id: b"local".to_vec(),
+                power_setting: 0,
+            },
+        )))
+        .await;
+        assert!(matches!(
+            next(&mut from_host).await,
+            proto::ble::Message::StartResult(result) if result.success
+        ));
+        assert!(mock.is_advertising() && mock.is_discovering());
+
+        // discovery
+        mock.add_peer(PEER, b"peer", 23).await.unwrap();
+        assert!(matches!(
+            next(&mut from_host).await,
+            proto::ble::Message::DeviceDiscovered(device) if device.qaul_id == b"peer"
+        ));
+
+        // framed send
+        let data: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        host.process(&sys(proto::ble::Message::DirectSend(
+            proto::BleDirectSend {
+                message_id: vec![1],
+                receiver_id: b"peer".to_vec(),
+                sender_id: b"local".to_vec(),
+                data: data.clone(),
+            },
+        )))
+        .await;
+        assert!(matches!(
+            next(&mut from_host).await,
+            proto::ble::Message::DirectSendResult(result) if result.success
+        ));
+        let mut reassembly = Reassembly::default();
+        let chunks = mock.written(PEER);
+        assert!(chunks.len() > 1 && chunks.iter().all(|chunk| chunk.len() <= 20));
+        let received: Vec<(Vec<u8>, Vec<u8>)> =
+            chunks.iter().filter_map(|c| reassembly.add(c)).collect();
+        assert_eq!(received, vec![(b"local".to_vec(), data.clone())]);
+
+        // framed receive
+        mock.peer_write(PEER, &data, 23).await.unwrap();
+        assert!(matches!(
+            next(&mut from_host).await,
+            proto::ble::Message::DirectReceived(message)
+                if message.from == b"peer" && message.data == data
+        ));
+
+        // out of range
+        mock.remove_peer(PEER).await.unwrap();
+        assert!(matches!(
+            next(&mut from_host).await,
+            proto::ble::Message::DeviceUnavailable(device) if device.qaul_id == b"peer"
+        ));
+
+        host.process(&sys(proto::ble::Message::StopRequest(
+            proto::BleStopRequest {},
+        )))
+        .await;
+        assert!(matches!(
+            next(&mut from_host).await,
+            proto::ble::Message::StopResult(result) if result.success
+        ));
+        assert!(!mock.is_advertising() && !mock.is_discovering());
+    }
+
+    #[test]
+    fn test_reassembly_of_mobile_frame() {
+        // chunks an Android host writes with the BLE 4.0 default MTU
+        let chunks: [&[u8]; 3] = [
+            br#"$${"qaulId":"0a1b2c3"#,
+            br#"d","message":"080112"#,
+            br#"0568656c6c6f"}$$"#,
+        ];
+        let message = (vec![0x0a, 0x1b, 0x2c, 0x3d], b"\x08\x01\x12\x05hello".to_vec());
+
+        let mut reassembly = Reassembly::default();
+        let received: Vec<_> = chunks.iter().filter_map(|c| reassembly.add(c)).collect();
+        assert_eq!(received, vec![message.clone()]);
+
+        // the closing delimiter in a chunk of its own
+        let whole = chunks.concat();
+        assert_eq!(reassembly.add(&whole[..whole.len() - 2]), None);
+        assert_eq!(reassembly.add(b"$$"), Some(message.clone()));
+
+        // our frames are the same
+        assert_eq!(
+            frame(&message.0, &message.1, 23),
+            chunks.iter().map(|c| c.to_vec()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_reassembly_discards_incomplete_frames() {
+        let chunks = frame(b"peer", &[7; 100], 23);
+        let mut reassembly = Reassembly::default();
+        assert_eq!(reassembly.add(&chunks[1]), None);
+        assert_eq!(reassembly.add(&chunks[0]), None);
+
+        let received: Vec<_> = chunks.iter().filter_map(|c| reassembly.add(c)).collect();
+        assert_eq!(received, vec![(b"peer".to_vec(), vec![7; 100])]);
+    }
+}
@@ -0,0 +1,707 @@
+//! # BlueZ BLE Host
+//!
+//! Linux host side of the `qaul.sys.ble` protocol of libqaul.
+//!
+//! libqaul hands its sys BLE messages to the host system, which
+//! owns the radio. On Linux this host talks to BlueZ over D-Bus:
+//!
+//! * advertises the qaul service and exports a GATT service with a
+//!   read characteristic containing the small qaul id and a message
+//!   characteristic remote devices write to,
+//! * scans for devices advertising the qaul service, connects to them
+//!   and reads their small qaul id to report them as discovered,
+//! * writes direct messages in chunks fitting the ATT MTU to the
+//!   message characteristic of the receiving device and reassembles
+//!   the chunks written to us.
+//!
+//! Messages are framed like the Android and iOS qaul BLE hosts do:
+//! a JSON object with the hex encoded small qaul id of the sender and
+//! the hex encoded message, enclosed in `$$` delimiters. The frame is
+//! written in chunks fitting the ATT MTU.
+//!
+//! Setting the environment variable `KNOWLIA_BLUEZ_MOCK` runs the
+//! host against the in-process mock BlueZ service of the `mock` module.
+
+mod gatt;
+pub mod mock;
+mod proxies;
+
+use futures::StreamExt;
+use libqaul::connections::ble::proto;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use zbus::fdo::{ManagedObjects, ObjectManager, ObjectManagerProxy};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, Value};
+use zbus::{CacheProperties, Connection};
+
+use gatt::{Advertisement, Characteristic, Service};
+use proxies::{
+    Adapter1Proxy, Device1Proxy, GattCharacteristic1Proxy, GattManager1Proxy,
+    LEAdvertisingManager1Proxy,
+};
+
+/// qaul BLE service
+pub const SERVICE_UUID: &str = "99e91399-80ed-4943-9bcb-39c532a76023";
+/// characteristic containing the small qaul id of the node
+pub const READ_CHARACTERISTIC_UUID: &str = "99e91400-80ed-4943-9bcb-39c532a76023";
+/// characteristic direct messages are written to
+pub const MESSAGE_CHARACTERISTIC_UUID: &str = "99e91401-80ed-4943-9bcb-39c532a76023";
+
+/// environment variable selecting the mock BlueZ service
+pub const MOCK_ENV: &str = "KNOWLIA_BLUEZ_MOCK";
+
+/// Bluetooth adapter used by the host
+const ADAPTER_PATH: &str = "/org/bluez/hci0";
+/// exported GATT application
+const APP_PATH: &str = "/org/knowlia/qaul";
+const SERVICE_PATH: &str = "/org/knowlia/qaul/service0";
+const READ_PATH: &str = "/org/knowlia/qaul/service0/char0";
+const MESSAGE_PATH: &str = "/org/knowlia/qaul/service0/char1";
+/// exported advertisement
+const ADVERTISEMENT_PATH: &str = "/org/knowlia/advertisement0";
+
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const CHARACTERISTIC_INTERFACE: &str = "org.bluez.GattCharacteristic1";
+
+/// ATT MTU of BLE 4.0, used when BlueZ does not report the MTU
+const DEFAULT_MTU: u16 = 23;
+/// ATT protocol overhead of a GATT write in bytes
+const ATT_OVERHEAD: usize = 3;
+/// delimiter at the start and end of a message frame
+const FRAME_DELIMITER: &[u8] = b"$$";
+/// time to wait for the GATT services of a connected device
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(10);
+/// polling interval of the sys messages of libqaul
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Start the BlueZ host and relay the sys messages of libqaul to it
+pub fn spawn() {
+    ::tauri::async_runtime::spawn(async {
+        // the mock service needs to stay alive as long as the host
+        let (connection, _mock) = match std::env::var_os(MOCK_ENV) {
+            Some(_) => match mock::MockBluez::start().await {
+                Ok((mock, connection)) => {
+                    log::info!("BLE host runs against the mock BlueZ service");
+                    (connection, Some(mock))
+                }
+                Err(e) => {
+                    log::error!("Failed to start mock BlueZ service: {}", e);
+                    return;
+                }
+            },
+            None => match Connection::system().await {
+                Ok(connection) => (connection, None),
+                Err(e) => {
+                    log::error!("BLE unavailable, no D-Bus system bus: {}", e);
+                    return;
+                }
+            },
+        };
+
+        let (to_node, mut from_host) = mpsc::unbounded_channel();
+        let host = BluezHost::new(connection, to_node);
+        libqaul::api::attach_sys();
+
+        loop {
+            while let Ok(data) = libqaul::api::receive_sys() {
+                host.process(&data).await;
+            }
+            while let Ok(data) = from_host.try_recv() {
+                libqaul::api::send_sys(data);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+/// BLE host on top of BlueZ
+///
+/// Processes the sys BLE messages of libqaul and sends
+/// the resulting sys messages to `to_node`.
+#[derive(Clone)]
+pub struct BluezHost {
+    connection: Connection,
+    to_node: mpsc::UnboundedSender<Vec<u8>>,
+    state: Arc<Mutex<HostState>>,
+}
+
+#[derive(Default)]
+struct HostState {
+    /// small qaul id of this node, set when started
+    qaul_id: Option<Vec<u8>>,
+    /// object paths of the identified devices by small qaul id
+    devices: BTreeMap<Vec<u8>, OwnedObjectPath>,
+    /// device watcher and message receiver
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl BluezHost {
+    /// create a new host on a D-Bus connection to BlueZ
+    pub fn new(connection: Connection, to_node: mpsc::UnboundedSender<Vec<u8>>) -> Self {
+        BluezHost {
+            connection,
+            to_node,
+            state: Arc::new(Mutex::new(HostState::default())),
+        }
+    }
+
+    /// process a sys BLE message from libqaul
+    pub async fn process(&self, data: &[u8]) {
+        let message = match proto::Ble::decode(data) {
+            Ok(proto::Ble {
+                message: Some(message),
+            }) => message,
+            _ => {
+                log::error!("BLE host received an invalid sys message");
+                return;
+            }
+        };
+
+        match message {
+            proto::ble::Message::InfoRequest(_) => {
+                let device = match self.info().await {
+                    Ok(device) => Some(device),
+                    Err(e) => {
+                        log::error!("BlueZ adapter unavailable: {}", e);
+                        None
+                    }
+                };
+                self.send(proto::ble::Message::InfoResponse(proto::BleInfoResponse {
+                    device,
+                }));
+            }
+            proto::ble::Message::StartRequest(request) => {
+                let result = self.start(request.qaul_id).await;
+                if let Err(e) = &result {
+                    log::error!("BLE host start failed: {}", e);
+                }
+                self.send(proto::ble::Message::StartResult(proto::BleStartResult {
+                    success: result.is_ok(),
+                    error_reason: proto::BleError::UnknownError.into(),
+                    error_message: result.err().map(|e| e.to_string()).unwrap_or_default(),
+                }));
+            }
+            proto::ble::Message::StopRequest(_) => {
+                self.stop().await;
+                self.send(proto::ble::Message::StopResult(proto::BleStopResult {
+                    success: true,
+                    ..Default::default()
+                }));
+            }
+            proto::ble::Message::DirectSend(send) => {
+                let result = self.direct_send(&send).await;
+                self.send(proto::ble::Message::DirectSendResult(
+                    proto::BleDirectSendResult {
+                        id: send.message_id,
+                        success: result.is_ok(),
+                        error_message: result.err().map(|e| e.to_string()).unwrap_or_default(),
+                    },
+                ));
+            }
+            _ => log::warn!("BLE host: unexpected sys message"),
+        }
+    }
+
+    /// send a sys BLE message to libqaul
+    fn send(&self, message: proto::ble::Message) {
+        let message = proto::Ble {
+            message: Some(message),
+        };
+        let _ = self.to_node.send(message.encode_to_vec());
+    }
+
+    /// device information of the adapter
+    async fn info(&self) -> zbus::Result<proto::BleDeviceInfo> {
+        let adapter = self.adapter().await?;
+        Ok(proto::BleDeviceInfo {
+            ble_support: true,
+            id: adapter.address().await?,
+            name: adapter.name().await?,
+            bluetooth_on: adapter.powered().await?,
+            ..Default::default()
+        })
+    }
+
+    /// advertise the qaul service and scan for other nodes
+    async fn start(&self, qaul_id: Vec<u8>) -> zbus::Result<()> {
+        if qaul_id.is_empty() {
+            return Err(zbus::Error::Failure("qaul id missing".to_string()));
+        }
+        self.stop().await;
+
+        let (writes, received) = mpsc::unbounded_channel();
+        self.export(qaul_id.clone(), writes).await?;
+
+        let app = ObjectPath::try_from(APP_PATH)?;
+        GattManager1Proxy::builder(&self.connection)
+            .path(ADAPTER_PATH)?
+            .build()
+            .await?
+            .register_application(&app, HashMap::new())
+            .await?;
+
+        let advertisement = ObjectPath::try_from(ADVERTISEMENT_PATH)?;
+        LEAdvertisingManager1Proxy::builder(&self.connection)
+            .path(ADAPTER_PATH)?
+            .build()
+            .await?
+            .register_advertisement(&advertisement, HashMap::new())
+            .await?;
+
+        let adapter = self.adapter().await?;
+        adapter
+            .set_discovery_filter(HashMap::from([
+                ("UUIDs", Value::from(vec![SERVICE_UUID])),
+                ("Transport", Value::from("le")),
+            ]))
+            .await?;
+        adapter.start_discovery().await?;
+
+        let watcher = self.clone();
+        let receiver = self.clone();
+        let mut state = self.state.lock().unwrap();
+        state.qaul_id = Some(qaul_id);
+        state.tasks.push(tokio::spawn(async move {
+            if let Err(e) = watcher.watch_devices().await {
+                log::error!("BLE device watcher stopped: {}", e);
+            }
+        }));
+        state
+            .tasks
+            .push(tokio::spawn(receiver.receive_messages(received)));
+
+        Ok(())
+    }
+
+    /// stop advertising and scanning
+    ///
+    /// Errors are logged, as the host is stopped in any case.
+    async fn stop(&self) {
+        let (tasks, devices, started) = {
+            let mut state = self.state.lock().unwrap();
+            let started = state.qaul_id.take().is_some();
+            (
+                std::mem::take(&mut state.tasks),
+                std::mem::take(&mut state.devices),
+                started,
+            )
+        };
+        for task in tasks {
+            task.abort();
+        }
+
+        if started {
+            if let Err(e) = self.unregister().await {
+                log::warn!("BLE host stop: {}", e);
+            }
+        }
+        self.unexport().await;
+
+        for path in devices.into_values() {
+            if let Ok(device) = self.device(&path).await {
+                let _ = device.disconnect().await;
+            }
+        }
+    }
+
+    /// stop scanning and unregister the exported objects from BlueZ
+    async fn unregister(&self) -> zbus::Result<()> {
+        self.adapter().await?.stop_discovery().await?;
+        LEAdvertisingManager1Proxy::builder(&self.connection)
+            .path(ADAPTER_PATH)?
+            .build()
+            .await?
+            .unregister_advertisement(&ObjectPath::try_from(ADVERTISEMENT_PATH)?)
+            .await?;
+        GattManager1Proxy::builder(&self.connection)
+            .path(ADAPTER_PATH)?
+            .build()
+            .await?
+            .unregister_application(&ObjectPath::try_from(APP_PATH)?)
+            .await
+    }
+
+    /// export the advertisement and the GATT application
+    async fn export(
+        &self,
+        qaul_id: Vec<u8>,
+        writes: mpsc::UnboundedSender<(OwnedObjectPath, Vec<u8>)>,
+    ) -> zbus::Result<()> {
+        let server = self.connection.object_server();
+        let service = OwnedObjectPath::try_from(SERVICE_PATH)?;
+
+        server
+            .at(
+                ADVERTISEMENT_PATH,
+                Advertisement {
+                    service_uuid: SERVICE_UUID.to_string(),
+                },
+            )
+            .await?;
+        server
+            .at(
+                SERVICE_PATH,
+                Service {
+                    uuid: SERVICE_UUID.to_string(),
+                },
+            )
+            .await?;
+        server
+            .at(
+                READ_PATH,
+                Characteristic {
+                    uuid: READ_CHARACTERISTIC_UUID.to_string(),
+                    service: service.clone(),
+                    value: qaul_id,
+                    writes: None,
+                },
+            )
+            .await?;
+        server
+            .at(
+                MESSAGE_PATH,
+                Characteristic {
+                    uuid: MESSAGE_CHARACTERISTIC_UUID.to_string(),
+                    service,
+                    value: Vec::new(),
+                    writes: Some(writes),
+                },
+            )
+            .await?;
+        server.at(APP_PATH, ObjectManager).await?;
+
+        Ok(())
+    }
+
+    /// remove the exported objects
+    async fn unexport(&self) {
+        let server = self.connection.object_server();
+        let _ = server.remove::<ObjectManager, _>(APP_PATH).await;
+        let _ = server.remove::<Characteristic, _>(MESSAGE_PATH).await;
+        let _ = server.remove::<Characteristic, _>(READ_PATH).await;
+        let _ = server.remove::<Service, _>(SERVICE_PATH).await;
+        let _ = server.remove::<Advertisement, _>(ADVERTISEMENT_PATH).await;
+    }
+
+    /// identify added qaul devices and report removed ones
+    async fn watch_devices(self) -> zbus::Result<()> {
+        let manager = self.object_manager().await?;
+        let mut added = manager.receive_interfaces_added().await?;
+        let mut removed = manager.receive_interfaces_removed().await?;
+
+        // devices BlueZ already knows
+        for (path, interfaces) in manager.get_managed_objects().await? {
+            let qaul_device = interfaces.iter().any(|(interface, properties)| {
+                interface.as_str() == DEVICE_INTERFACE
+                    && properties
+                        .get("UUIDs")
+                        .is_some_and(|uuids| has_uuid(uuids, SERVICE_UUID))
+            });
+            if qaul_device {
+                self.spawn_identify(path);
+            }
+        }
+
+        loop {
+            tokio::select! {
+                Some(signal) = added.next() => {
+                    let args = signal.args()?;
+                    let qaul_device = args
+                        .interfaces_and_properties()
+                        .get(DEVICE_INTERFACE)
+                        .and_then(|properties| properties.get("UUIDs"))
+                        .is_some_and(|uuids| has_uuid(uuids, SERVICE_UUID));
+                    if qaul_device {
+                        self.spawn_identify(args.object_path().clone().into());
+                    }
+                }
+                Some(signal) = removed.next() => {
+                    let args = signal.args()?;
+                    if args.interfaces().contains(&DEVICE_INTERFACE) {
+                        self.device_lost(args.object_path().clone().into());
+                    }
+                }
+                else => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn spawn_identify(&self, path: OwnedObjectPath) {
+        let host = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = host.identify(path.clone()).await {
+                log::warn!("BLE device {} not identified: {}", path.as_str(), e);
+            }
+        });
+    }
+
+    /// connect to a device and read its small qaul id
+    async fn identify(&self, path: OwnedObjectPath) -> zbus::Result<Vec<u8>> {
+        if let Some(qaul_id) = self.qaul_id_of(&path) {
+            return Ok(qaul_id);
+        }
+
+        let device = self.device(&path).await?;
+        if !device.services_resolved().await.unwrap_or(false) {
+            device.connect().await?;
+            let started = Instant::now();
+            while !device.services_resolved().await? {
+                if started.elapsed() > RESOLVE_TIMEOUT {
+                    return Err(zbus::Error::Failure("services not resolved".to_string()));
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+
+        let characteristic = self
+            .find_characteristic(&path, READ_CHARACTERISTIC_UUID)
+            .await?;
+        let qaul_id = characteristic.read_value(HashMap::new()).await?;
+        if qaul_id.is_empty() {
+            return Err(zbus::Error::Failure("empty qaul id".to_string()));
+        }
+        let rssi = device.rssi().await.unwrap_or(0);
+
+        self.state
+            .lock()
+            .unwrap()
+            .devices
+            .insert(qaul_id.clone(), path);
+        self.send(proto::ble::Message::DeviceDiscovered(
+            proto::BleDeviceDiscovered {
+                qaul_id: qaul_id.clone(),
+                rssi: rssi.into(),
+            },
+        ));
+
+        Ok(qaul_id)
+    }
+
+    /// report a removed device as unavailable
+    fn device_lost(&self, path: OwnedObjectPath) {
+        let qaul_id = {
+            let mut state = self.state.lock().unwrap();
+            let qaul_id = state
+                .devices
+                .iter()
+                .find(|(_, device)| **device == path)
+                .map(|(qaul_id, _)| qaul_id.clone());
+            if let Some(qaul_id) = &qaul_id {
+                state.devices.remove(qaul_id);
+            }
+            qaul_id
+        };
+        if let Some(qaul_id) = qaul_id {
+            self.send(proto::ble::Message::DeviceUnavailable(
+                proto::BleDeviceUnavailable { qaul_id },
+            ));
+        }
+    }
+
+    /// reassemble the chunks written to the message characteristic
+    async fn receive_messages(
+        self,
+        mut received: mpsc::UnboundedReceiver<(OwnedObjectPath, Vec<u8>)>,
+    ) {
+        let mut reassembly: HashMap<OwnedObjectPath, Reassembly> = HashMap::new();
+
+        while let Some((device, chunk)) = received.recv().await {
+            let (sender_id, data) = match reassembly.entry(device.clone()).or_default().add(&chunk)
+            {
+                Some(message) => message,
+                None => continue,
+            };
+            // a device may write before we discovered it
+            let from = match (self.identify(device.clone()).await, sender_id.is_empty()) {
+                (_, false) => sender_id,
+                (Ok(qaul_id), true) => qaul_id,
+                (Err(e), true) => {
+                    log::warn!("BLE message from unknown device dropped: {}", e);
+                    continue;
+                }
+            };
+            self.send(proto::ble::Message::DirectReceived(
+                proto::BleDirectReceived { from, data },
+            ));
+        }
+    }
+
+    /// write a direct message to the message characteristic of the receiver
+    async fn direct_send(&self, send: &proto::BleDirectSend) -> zbus::Result<()> {
+        let path = self
+            .state
+            .lock()
+            .unwrap()
+            .devices
+            .get(&send.receiver_id)
+            .cloned()
+            .ok_or_else(|| zbus::Error::Failure("device not available".to_string()))?;
+
+        let characteristic = self
+            .find_characteristic(&path, MESSAGE_CHARACTERISTIC_UUID)
+            .await?;
+        let mtu = characteristic.mtu().await.unwrap_or(DEFAULT_MTU);
+
+        for chunk in frame(&send.sender_id, &send.data, mtu.into()) {
+            characteristic
+                .write_value(&chunk, HashMap::from([("type", Value::from("request"))]))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    fn qaul_id_of(&self, path: &OwnedObjectPath) -> Option<Vec<u8>> {
+        let state = self.state.lock().unwrap();
+        state
+            .devices
+            .iter()
+            .find(|(_, device)| *device == path)
+            .map(|(qaul_id, _)| qaul_id.clone())
+    }
+
+    /// find a characteristic of a remote device by its UUID
+    async fn find_characteristic(
+        &self,
+        device: &OwnedObjectPath,
+        uuid: &str,
+    ) -> zbus::Result<GattCharacteristic1Proxy<'static>> {
+        let prefix = format!("{}/", device.as_str());
+        let objects: ManagedObjects = self.object_manager().await?.get_managed_objects().await?;
+
+        let path = objects
+            .into_iter()
+            .find(|(path, interfaces)| {
+                path.as_str().starts_with(&prefix)
+                    && interfaces.iter().any(|(interface, properties)| {
+                        interface.as_str() == CHARACTERISTIC_INTERFACE
+                            && matches!(
+                                properties.get("UUID").map(|value| &**value),
+                                Some(Value::Str(value)) if value.as_str().eq_ignore_ascii_case(uuid)
+                            )
+                    })
+            })
+            .map(|(path, _)| path)
+            .ok_or_else(|| zbus::Error::Failure(format!("characteristic {} not found", uuid)))?;
+
+        GattCharacteristic1Proxy::builder(&self.connection)
+            .path(path)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await
+    }
+
+    async fn adapter(&self) -> zbus::Result<Adapter1Proxy<'static>> {
+        Adapter1Proxy::builder(&self.connection)
+            .path(ADAPTER_PATH)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await
+    }
+
+    async fn device(&self, path: &OwnedObjectPath) -> zbus::Result<Device1Proxy<'static>> {
+        Device1Proxy::builder(&self.connection)
+            .path(path.clone())?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await
+    }
+
+    async fn object_manager(&self) -> zbus::Result<ObjectManagerProxy<'static>> {
+        ObjectManagerProxy::builder(&self.connection)
+            .destination("org.bluez")?
+            .path("/")?
+            .build()
+            .await
+    }
+}
+
+/// check if a D-Bus string array contains a UUID
+fn has_uuid(uuids: &Value<'_>, uuid: &str) -> bool {
+    match uuids {
+        Value::Array(array) => array.get().iter().any(
+            |value| matches!(value, Value::Str(value) if value.as_str().eq_ignore_ascii_case(uuid)),
+        ),
+        _ => false,
+    }
+}
+
+/// Message of the Android and iOS qaul BLE hosts
+#[derive(Serialize, Deserialize)]
+struct FrameMessage {
+    /// hex encoded small qaul id of the sender
+    #[serde(rename = "qaulId", alias = "qaul_id", default)]
+    qaul_id: String,
+    /// hex encoded message
+    message: String,
+}
+
+/// frame a message and split it into chunks fitting the ATT MTU
+pub fn frame(sender_id: &[u8], data: &[u8], mtu: usize) -> Vec<Vec<u8>> {
+    let message = FrameMessage {
+        qaul_id: hex::encode(sender_id),
+        message: hex::encode(data),
+    };
+    let json = serde_json::to_vec(&message).expect("frame message serializes");
+    let frame = [FRAME_DELIMITER, &json[..], FRAME_DELIMITER].concat();
+
+    let size = mtu.saturating_sub(ATT_OVERHEAD).max(FRAME_DELIMITER.len() + 1);
+    frame.chunks(size).map(|chunk| chunk.to_vec()).collect()
+}
+
+/// Reassembly of the chunks written by a device
+#[derive(Default)]
+pub struct Reassembly {
+    data: Vec<u8>,
+}
+
+impl Reassembly {
+    /// add a chunk
+    ///
+    /// Returns the small qaul id of the sender and the message,
+    /// when the end of a frame was added. A chunk starting a new
+    /// frame discards an unfinished one.
+    pub fn add(&mut self, chunk: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+        // the JSON contains no delimiter, only the first chunk of
+        // a frame starts with it, or the last one if it is nothing else
+        if chunk.starts_with(FRAME_DELIMITER) && chunk.len() > FRAME_DELIMITER.len() {
+            if !self.data.is_empty() {
+                log::debug!("BLE frame incomplete, message discarded");
+            }
+            self.data.clear();
+        } else if self.data.is_empty() {
+            log::debug!("BLE chunk outside of a frame discarded");
+            return None;
+        }
+        self.data.extend_from_slice(chunk);
+
+        let delimiter = FRAME_DELIMITER.len();
+        if self.data.len() < 2 * delimiter || !self.data.ends_with(FRAME_DELIMITER) {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data);
+        let json = &data[delimiter..data.len() - delimiter];
+
+        let message: FrameMessage = match serde_json::from_slice(json) {
+            Ok(message) => message,
+            Err(e) => {
+                log::warn!("BLE frame not decodable: {}", e);
+                return None;
+            }
+        };
+        match (hex::decode(&message.qaul_id), hex::decode(&message.message)) {
+            (Ok(sender_id), Ok(data)) => Some((sender_id, data)),
+            _ => {
+                log::warn!("BLE frame with invalid hex encoding");
+                None
+            }
+        }
+    }
+}
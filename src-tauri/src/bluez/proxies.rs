@@ -0,0 +1,76 @@
+//! D-Bus proxies of the BlueZ interfaces used by the BLE host
+//!
+//! See the BlueZ D-Bus API documentation in `doc/org.bluez.*.rst`.
+
+use std::collections::HashMap;
+use zbus::dbus_proxy;
+use zbus::zvariant::{ObjectPath, Value};
+
+#[dbus_proxy(interface = "org.bluez.Adapter1", default_service = "org.bluez")]
+trait Adapter1 {
+    fn start_discovery(&self) -> zbus::Result<()>;
+
+    fn stop_discovery(&self) -> zbus::Result<()>;
+
+    fn set_discovery_filter(&self, filter: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+
+    #[dbus_proxy(property)]
+    fn address(&self) -> zbus::Result<String>;
+
+    #[dbus_proxy(property)]
+    fn name(&self) -> zbus::Result<String>;
+
+    #[dbus_proxy(property)]
+    fn powered(&self) -> zbus::Result<bool>;
+}
+
+#[dbus_proxy(
+    interface = "org.bluez.LEAdvertisingManager1",
+    default_service = "org.bluez"
+)]
+trait LEAdvertisingManager1 {
+    fn register_advertisement(
+        &self,
+        advertisement: &ObjectPath<'_>,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    fn unregister_advertisement(&self, advertisement: &ObjectPath<'_>) -> zbus::Result<()>;
+}
+
+#[dbus_proxy(interface = "org.bluez.GattManager1", default_service = "org.bluez")]
+trait GattManager1 {
+    fn register_application(
+        &self,
+        application: &ObjectPath<'_>,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    fn unregister_application(&self, application: &ObjectPath<'_>) -> zbus::Result<()>;
+}
+
+#[dbus_proxy(interface = "org.bluez.Device1", default_service = "org.bluez")]
+trait Device1 {
+    fn connect(&self) -> zbus::Result<()>;
+
+    fn disconnect(&self) -> zbus::Result<()>;
+
+    #[dbus_proxy(property, name = "RSSI")]
+    fn rssi(&self) -> zbus::Result<i16>;
+
+    #[dbus_proxy(property)]
+    fn services_resolved(&self) -> zbus::Result<bool>;
+}
+
+#[dbus_proxy(
+    interface = "org.bluez.GattCharacteristic1",
+    default_service = "org.bluez"
+)]
+trait GattCharacteristic1 {
+    fn read_value(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<Vec<u8>>;
+
+    fn write_value(&self, value: &[u8], options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+
+    #[dbus_proxy(property, name = "MTU")]
+    fn mtu(&self) -> zbus::Result<u16>;
+}
@@ -3,20 +3,12 @@ use directories::ProjectDirs;
 use reqwest::header::{HeaderMap, ACCEPT, ACCEPT_LANGUAGE, USER_AGENT};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::io::Write;
 use std::path::PathBuf;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Manager};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DownloadProgress {
-    pub file_id: String,
-    pub file_name: String,
-    pub downloaded: u64,
-    pub total: Option<u64>,
-    pub percentage: f64,
-    pub site: String,   // "google drive", "dropbox", "onedrive", "etc."
-    pub status: String, // "downloading", "completed", "error"
-}
+use super::listing::is_google_apps_file;
+use crate::sql::{model::DownloadData, queries};
+use crate::tauri::downloads::{DownloadManager, DownloadRequest, DownloadSource, DownloadStatus};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DownloadRecord {
@@ -32,7 +24,7 @@ pub struct DownloadsData {
 }
 
 /// Get the download directory (Documents/KNOWLIA#NOTES)
-fn get_download_dir() -> Result<PathBuf> {
+pub(crate) fn get_download_dir() -> Result<PathBuf> {
     let documents_dir = dirs::document_dir()
         .ok_or_else(|| anyhow::anyhow!("Could not find Documents directory"))?;
 
@@ -44,7 +36,7 @@ fn get_download_dir() -> Result<PathBuf> {
 }
 
 /// Build HTTP client with proper headers (async)
-async fn build_http_client() -> Result<reqwest::Client> {
+pub(crate) async fn build_http_client() -> Result<reqwest::Client> {
     let mut headers = HeaderMap::new();
     headers.insert(
         USER_AGENT,
@@ -62,119 +54,46 @@ async fn build_http_client() -> Result<reqwest::Client> {
         .map_err(Into::into)
 }
 
-/// Download file from Google Drive with progress tracking (async)
-pub async fn download_file(
+/// URL to download a Google Drive file from, which may lead to a virus scan confirm page
+pub(crate) fn download_url(file_id: &str) -> String {
+    format!("https://drive.google.com/uc?export=download&id={}", file_id)
+}
+
+/// Queue the download of a Google Drive file in the download manager
+///
+/// Google Docs, Sheets, Slides and Drawings are downloaded as PDF export.
+/// Returns the path the file is downloaded to, progress is reported as `download-progress`.
+pub fn download_file(
     file_id: String,
     file_name: String,
+    mime_type: Option<String>,
     app_handle: AppHandle,
 ) -> Result<PathBuf> {
-    let download_dir = get_download_dir()?;
-    let file_path = download_dir.join(&file_name);
-
-    // Emit start event
-    let _ = app_handle.emit(
-        "download-progress",
-        DownloadProgress {
-            file_id: file_id.clone(),
-            file_name: file_name.clone(),
-            downloaded: 0,
-            total: None,
-            percentage: 0.0,
-            status: "downloading".to_string(),
-            site: "google drive".to_string(),
-        },
-    );
-
-    let client = build_http_client().await?;
-    let url = format!("https://drive.google.com/uc?export=download&id={}", file_id);
-
-    let mut response = client
-        .get(&url)
-        .send()
-        .await
-        .with_context(|| format!("Failed to download file: {}", file_name))?;
+    let exported = mime_type.as_deref().is_some_and(is_google_apps_file);
+    let file_name = if exported && !file_name.to_lowercase().ends_with(".pdf") {
+        format!("{}.pdf", file_name)
+    } else {
+        file_name
+    };
 
-    if !response.status().is_success() {
-        let _ = app_handle.emit(
-            "download-progress",
-            DownloadProgress {
-                file_id: file_id.clone(),
-                file_name: file_name.clone(),
-                downloaded: 0,
-                total: None,
-                percentage: 0.0,
-                status: format!("error: HTTP {}", response.status()),
-                site: "google drive".to_string(),
+    let manager = app_handle.state::<DownloadManager>();
+    let download = manager
+        .enqueue(
+            &app_handle,
+            DownloadRequest {
+                source: DownloadSource::GoogleDrive { file_id, mime_type },
+                file_name,
+                destination: None,
+                checksum: None,
             },
-        );
-        return Err(anyhow::anyhow!(
-            "HTTP {} – {}",
-            response.status(),
-            response.text().await.unwrap_or_default()
-        ));
-    }
-
-    // Get content length if available
-    let total_size = response.content_length();
-
-    // Create file
-    let mut file = fs::File::create(&file_path)
-        .with_context(|| format!("Failed to create file: {:?}", file_path))?;
-
-    let mut downloaded: u64 = 0;
-
-    while let Some(chunk) = response.chunk().await? {
-        file.write_all(&chunk)?;
-        downloaded += chunk.len() as u64;
-
-        // Calculate percentage
-        let percentage = if let Some(total) = total_size {
-            (downloaded as f64 / total as f64) * 100.0
-        } else {
-            0.0
-        };
-
-        // Emit progress event every 64KB or on completion
-        if downloaded % 65536 == 0 || chunk.len() < 8192 {
-            let _ = app_handle.emit(
-                "download-progress",
-                DownloadProgress {
-                    file_id: file_id.clone(),
-                    file_name: file_name.clone(),
-                    downloaded,
-                    total: total_size,
-                    percentage,
-                    status: "downloading".to_string(),
-                    site: "google drive".to_string(),
-                },
-            );
-        }
-    }
-
-    // Emit completion event
-    let _ = app_handle.emit(
-        "download-progress",
-        DownloadProgress {
-            file_id: file_id.clone(),
-            file_name: file_name.clone(),
-            downloaded,
-            total: total_size,
-            percentage: 100.0,
-            status: "completed".to_string(),
-            site: "Google Drive".to_string(),
-        },
-    );
+        )
+        .map_err(|e| anyhow::anyhow!(e))?;
 
-    // Record the download in our tracking system
-    if let Err(e) = record_download(&file_id, &file_name, &file_path) {
-        eprintln!("Failed to record download: {}", e);
-    }
-
-    Ok(file_path)
+    Ok(PathBuf::from(download.file_path))
 }
 
-/// Get downloads data file path
-fn get_downloads_data_path() -> Result<PathBuf> {
+/// Get the path of the JSON file that tracked downloads before the download manager
+fn get_legacy_downloads_path() -> Result<PathBuf> {
     let app_data_dir = ProjectDirs::from("com", "knowlia", "tabletop")
         .context("Failed to get project directories")?
         .data_dir()
@@ -182,97 +101,74 @@ fn get_downloads_data_path() -> Result<PathBuf> {
     Ok(app_data_dir.join("qaul-desktop").join("downloads.json"))
 }
 
-/// Load downloads data from JSON file
-fn load_downloads_data() -> Result<DownloadsData> {
-    let downloads_path = get_downloads_data_path()?;
-
+/// Move the downloads recorded in the legacy JSON file into the download history
+pub fn import_legacy_downloads() -> Result<()> {
+    let downloads_path = get_legacy_downloads_path()?;
     if !downloads_path.exists() {
-        // Create empty downloads data if file doesn't exist
-        let empty_data = DownloadsData {
-            downloads: Vec::new(),
-        };
-        save_downloads_data(&empty_data)?;
-        return Ok(empty_data);
+        return Ok(());
     }
 
     let content = fs::read_to_string(&downloads_path)
         .with_context(|| format!("Failed to read downloads data from {:?}", downloads_path))?;
+    let data: DownloadsData =
+        serde_json::from_str(&content).with_context(|| "Failed to parse downloads data")?;
 
-    serde_json::from_str(&content).with_context(|| "Failed to parse downloads data")
-}
-
-/// Save downloads data to JSON file
-fn save_downloads_data(data: &DownloadsData) -> Result<()> {
-    let downloads_path = get_downloads_data_path()?;
-
-    // Ensure parent directory exists
-    if let Some(parent) = downloads_path.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create parent directory: {:?}", parent))?;
-    }
-
-    let content =
-        serde_json::to_string_pretty(data).with_context(|| "Failed to serialize downloads data")?;
-
-    fs::write(&downloads_path, content)
-        .with_context(|| format!("Failed to write downloads data to {:?}", downloads_path))
-}
-
-/// Get downloads data mutex (initialize if needed)
-static CACHE: std::sync::OnceLock<std::sync::Mutex<DownloadsData>> = std::sync::OnceLock::new();
-
-fn get_downloads_cache() -> Result<&'static std::sync::Mutex<DownloadsData>> {
-    Ok(CACHE.get_or_init(|| {
-        let data = load_downloads_data().unwrap_or_else(|_| DownloadsData {
-            downloads: Vec::new(),
-        });
-        std::sync::Mutex::new(data)
-    }))
-}
-
-/// Record a download in the tracking system
-fn record_download(file_id: &str, file_name: &str, file_path: &PathBuf) -> Result<()> {
-    let cache = get_downloads_cache()?;
-    let mut data = cache
-        .lock()
-        .map_err(|_| anyhow::anyhow!("Failed to lock downloads cache"))?;
-
-    // Check if this file is already recorded
-    if data.downloads.iter().any(|d| d.file_id == file_id) {
-        return Ok(()); // Already recorded
+    for record in data.downloads {
+        let source = DownloadSource::GoogleDrive {
+            file_id: record.file_id.clone(),
+            mime_type: None,
+        };
+        let download_time = chrono::DateTime::parse_from_rfc3339(&record.download_time)
+            .map(|time| time.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|_| chrono::Utc::now());
+
+        queries::insert_download(&DownloadData {
+            id: uuid::Uuid::new_v4().to_string(),
+            source_kind: source.kind().to_string(),
+            source_id: source.id(),
+            source: serde_json::to_string(&source)?,
+            file_name: record.file_name,
+            file_path: record.file_path,
+            status: DownloadStatus::Completed.as_str().to_string(),
+            downloaded: 0,
+            total: None,
+            checksum: None,
+            error: None,
+            created_at: download_time,
+            updated_at: download_time,
+            completed_at: Some(download_time),
+        })?;
     }
 
-    let record = DownloadRecord {
-        file_id: file_id.to_string(),
-        file_name: file_name.to_string(),
-        download_time: chrono::Utc::now().to_rfc3339(),
-        file_path: file_path.to_string_lossy().to_string(),
-    };
-
-    data.downloads.push(record);
-
-    // Save to file
-    save_downloads_data(&*data)?;
-
-    println!("Recorded download: {} ({})", file_name, file_id);
-    Ok(())
+    fs::rename(
+        &downloads_path,
+        downloads_path.with_extension("json.imported"),
+    )
+    .with_context(|| format!("Failed to rename {:?}", downloads_path))
 }
 
-/// Get all recorded downloads
+/// Get all completed downloads, of all sources
 pub fn get_recorded_downloads() -> Result<Vec<DownloadRecord>> {
-    let cache = get_downloads_cache()?;
-    let data = cache
-        .lock()
-        .map_err(|_| anyhow::anyhow!("Failed to lock downloads cache"))?;
-    Ok(data.downloads.clone())
+    let downloads = queries::get_downloads_by_status(&[DownloadStatus::Completed.as_str()])?;
+
+    Ok(downloads
+        .into_iter()
+        .map(|download| DownloadRecord {
+            file_id: download.source_id,
+            file_name: download.file_name,
+            download_time: download
+                .completed_at
+                .unwrap_or(download.updated_at)
+                .to_rfc3339(),
+            file_path: download.file_path,
+        })
+        .collect())
 }
 
-/// Check if a file has been downloaded
+/// Check if a Google Drive file has been downloaded
 pub fn is_file_downloaded(file_id: &str) -> bool {
-    if let Ok(cache) = get_downloads_cache() {
-        if let Ok(data) = cache.lock() {
-            return data.downloads.iter().any(|d| d.file_id == file_id);
-        }
-    }
-    false
+    matches!(
+        queries::get_download_by_source("google_drive", file_id),
+        Ok(Some(download)) if download.status == DownloadStatus::Completed.as_str()
+    )
 }
@@ -112,6 +112,7 @@ fn scan_directory(dir: &PathBuf) -> Result<Vec<GDriveItem>> {
                 .and_then(|p| p.to_str())
                 .map(|s| s.to_string()),
             children,
+            mime_type: None,
         };
 
         items.push(item);
@@ -128,7 +129,7 @@ fn scan_directory(dir: &PathBuf) -> Result<Vec<GDriveItem>> {
 }
 
 /// Get file extension from filename
-fn get_file_extension(filename: &str) -> Option<String> {
+pub(super) fn get_file_extension(filename: &str) -> Option<String> {
     std::path::Path::new(filename)
         .extension()
         .and_then(|ext| ext.to_str())
@@ -136,7 +137,7 @@ fn get_file_extension(filename: &str) -> Option<String> {
 }
 
 /// Format bytes to human readable format
-fn format_bytes(bytes: u64) -> String {
+pub(super) fn format_bytes(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
     let mut size = bytes as f64;
     let mut unit_index = 0;
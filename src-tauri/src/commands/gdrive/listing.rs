@@ -0,0 +1,561 @@
+use super::{download::build_http_client, GDriveItem};
+use anyhow::{Context, Result};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Url;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const DRIVE_FILES_URL: &str = "https://www.googleapis.com/drive/v3/files";
+const FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
+/// Prefix of the MIME types of Google Docs, Sheets, Slides and Drawings
+const GOOGLE_APPS_MIME_PREFIX: &str = "application/vnd.google-apps.";
+const DEFAULT_PAGE_SIZE: u32 = 100;
+
+/// One page of the contents of a Drive folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriveFolderPage {
+    pub items: Vec<GDriveItem>,
+    /// Token to fetch the next page with, if there is one
+    pub next_page_token: Option<String>,
+    /// Whether the page comes from the cache, because Drive couldn't be reached
+    pub cached: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DriveFileList {
+    next_page_token: Option<String>,
+    #[serde(default)]
+    files: Vec<DriveFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DriveFile {
+    id: String,
+    name: String,
+    mime_type: String,
+    size: Option<String>,
+    web_view_link: Option<String>,
+}
+
+impl DriveFile {
+    fn into_item(self, parent_id: &str) -> GDriveItem {
+        let is_folder = self.mime_type == FOLDER_MIME_TYPE;
+        let file_type = if is_folder {
+            None
+        } else if is_google_apps_file(&self.mime_type) {
+            // Google Docs have no extension and get exported to PDF
+            Some("pdf".to_string())
+        } else {
+            super::downloads::get_file_extension(&self.name)
+        };
+
+        GDriveItem {
+            name: self.name,
+            data_id: Some(self.id),
+            is_folder,
+            size: self
+                .size
+                .and_then(|size| size.parse().ok())
+                .map(super::downloads::format_bytes),
+            file_type,
+            url: self.web_view_link,
+            parent_id: Some(parent_id.to_string()),
+            children: None,
+            mime_type: Some(self.mime_type),
+        }
+    }
+}
+
+/// Whether the file is a Google Doc, Sheet, Slide or Drawing, which can only be exported
+pub fn is_google_apps_file(mime_type: &str) -> bool {
+    mime_type.starts_with(GOOGLE_APPS_MIME_PREFIX) && mime_type != FOLDER_MIME_TYPE
+}
+
+/// Get the API key for the Drive API from the environment
+fn get_api_key() -> Result<String> {
+    dotenvy::dotenv().ok();
+    std::env::var("GOOGLE_DRIVE_API_KEY")
+        .context("Google Drive API key not configured, set GOOGLE_DRIVE_API_KEY")
+}
+
+/// Build the query for the files of a folder, keeping folders whatever the MIME type filter
+fn folder_query(folder_id: &str, mime_types: &[String]) -> String {
+    let mut query = format!(
+        "'{}' in parents and trashed = false",
+        folder_id.replace('\'', "")
+    );
+    if !mime_types.is_empty() {
+        let mime_filter = mime_types
+            .iter()
+            .chain(std::iter::once(&FOLDER_MIME_TYPE.to_string()))
+            .map(|mime_type| format!("mimeType = '{}'", mime_type.replace('\'', "")))
+            .collect::<Vec<_>>()
+            .join(" or ");
+        query.push_str(&format!(" and ({})", mime_filter));
+    }
+    query
+}
+
+/// List one page of a publicly shared Drive folder
+async fn fetch_folder_page(
+    folder_id: &str,
+    page_token: Option<&str>,
+    page_size: u32,
+    mime_types: &[String],
+) -> Result<DriveFolderPage> {
+    let api_key = get_api_key()?;
+    let client = build_http_client().await?;
+
+    let mut request = client.get(DRIVE_FILES_URL).query(&[
+        ("q", folder_query(folder_id, mime_types)),
+        (
+            "fields",
+            "nextPageToken,files(id,name,mimeType,size,webViewLink)".to_string(),
+        ),
+        ("orderBy", "folder,name".to_string()),
+        ("pageSize", page_size.to_string()),
+        ("supportsAllDrives", "true".to_string()),
+        ("includeItemsFromAllDrives", "true".to_string()),
+        ("key", api_key),
+    ]);
+    if let Some(page_token) = page_token {
+        request = request.query(&[("pageToken", page_token)]);
+    }
+
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to list Drive folder {}", folder_id))?;
+    if !response.status().is_success() {
+        anyhow::bail!(
+            "HTTP {} – {}",
+            response.status(),
+            response.text().await.unwrap_or_default()
+        );
+    }
+
+    let list: DriveFileList = response
+        .json()
+        .await
+        .context("Invalid Drive folder listing")?;
+
+    Ok(DriveFolderPage {
+        items: list
+            .files
+            .into_iter()
+            .map(|file| file.into_item(folder_id))
+            .collect(),
+        next_page_token: list.next_page_token,
+        cached: false,
+    })
+}
+
+/// Most folder listings kept in the cache, the least recently listed ones get dropped
+const MAX_CACHED_FOLDERS: usize = 500;
+/// Most items kept of a single folder listing
+const MAX_CACHED_ITEMS: usize = 5000;
+
+/// The listed items of a folder, as far as they were paged through
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFolder {
+    page: DriveFolderPage,
+    /// When the folder was last listed, in milliseconds since the epoch
+    updated_at: i64,
+}
+
+/// Listed folders, persisted so folders can still be browsed offline
+static FOLDER_CACHE: Mutex<Option<HashMap<String, CachedFolder>>> = Mutex::new(None);
+
+fn cache_key(folder_id: &str, mime_types: &[String]) -> String {
+    format!("{}|{}", folder_id, mime_types.join(","))
+}
+
+/// Add a listed page to the cached folder
+///
+/// The first page replaces the cached listing, the following pages are appended
+/// when they continue it.
+fn cache_page(
+    folders: &mut HashMap<String, CachedFolder>,
+    key: String,
+    page_token: Option<&str>,
+    page: &DriveFolderPage,
+    now: i64,
+) {
+    match (page_token, folders.get_mut(&key)) {
+        (None, _) => {
+            let mut page = page.clone();
+            page.items.truncate(MAX_CACHED_ITEMS);
+            folders.insert(
+                key,
+                CachedFolder {
+                    page,
+                    updated_at: now,
+                },
+            );
+        }
+        (Some(token), Some(folder)) if folder.page.next_page_token.as_deref() == Some(token) => {
+            let room = MAX_CACHED_ITEMS.saturating_sub(folder.page.items.len());
+            folder
+                .page
+                .items
+                .extend(page.items.iter().take(room).cloned());
+            folder.page.next_page_token = page.next_page_token.clone();
+            folder.updated_at = now;
+        }
+        // A page of a listing that isn't cached from its start
+        (Some(_), _) => return,
+    }
+
+    while folders.len() > MAX_CACHED_FOLDERS {
+        let Some(oldest) = folders
+            .iter()
+            .min_by_key(|(_, folder)| folder.updated_at)
+            .map(|(key, _)| key.clone())
+        else {
+            break;
+        };
+        folders.remove(&oldest);
+    }
+}
+
+/// Get the path of the folder cache file
+fn get_cache_path(app_handle: &AppHandle) -> Result<PathBuf> {
+    let cache_dir = app_handle
+        .path()
+        .app_cache_dir()
+        .context("Failed to get app cache directory")?;
+    fs::create_dir_all(&cache_dir)
+        .with_context(|| format!("Failed to create cache directory: {:?}", cache_dir))?;
+    Ok(cache_dir.join("gdrive_folders.json"))
+}
+
+/// Run a closure on the folder cache, loading it from disk first if needed
+fn with_cache<T>(
+    app_handle: &AppHandle,
+    f: impl FnOnce(&mut HashMap<String, CachedFolder>) -> T,
+) -> Result<T> {
+    let mut cache = FOLDER_CACHE
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Failed to lock Drive folder cache"))?;
+
+    if cache.is_none() {
+        let path = get_cache_path(app_handle)?;
+        let folders = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        *cache = Some(folders);
+    }
+
+    Ok(f(cache.as_mut().unwrap()))
+}
+
+fn store_page(
+    app_handle: &AppHandle,
+    key: String,
+    page_token: Option<&str>,
+    page: &DriveFolderPage,
+) -> Result<()> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let content = with_cache(app_handle, |folders| {
+        cache_page(folders, key, page_token, page, now);
+        serde_json::to_string(folders)
+    })??;
+
+    let path = get_cache_path(app_handle)?;
+    fs::write(&path, content).with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// List one page of a Drive folder, falling back to the cache and then to the bundled catalogue
+///
+/// Offline, the first page contains all cached items of the folder.
+pub async fn list_folder(
+    app_handle: &AppHandle,
+    folder_id: &str,
+    page_token: Option<&str>,
+    page_size: Option<u32>,
+    mime_types: &[String],
+) -> Result<DriveFolderPage> {
+    let key = cache_key(folder_id, mime_types);
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE);
+
+    let error = match fetch_folder_page(folder_id, page_token, page_size, mime_types).await {
+        Ok(page) => {
+            if let Err(e) = store_page(app_handle, key, page_token, &page) {
+                eprintln!("Failed to cache Drive folder {}: {}", folder_id, e);
+            }
+            return Ok(page);
+        }
+        Err(e) => e,
+    };
+    eprintln!("Failed to list Drive folder {}: {:#}", folder_id, error);
+
+    // Continuation pages can't be served offline, the first one already had everything
+    if page_token.is_some() {
+        return Err(error);
+    }
+
+    if let Some(folder) = with_cache(app_handle, |folders| folders.get(&key).cloned())? {
+        return Ok(DriveFolderPage {
+            items: folder.page.items,
+            next_page_token: None,
+            cached: true,
+        });
+    }
+
+    // Only unfiltered listings can be served from the catalogue
+    if mime_types.is_empty() {
+        if let Some(items) = super::find_catalogue_children(folder_id) {
+            return Ok(DriveFolderPage {
+                items,
+                next_page_token: None,
+                cached: true,
+            });
+        }
+    }
+
+    Err(error)
+}
+
+/// URL to export a Google Doc, Sheet, Slide or Drawing as PDF
+pub fn export_pdf_url(file_id: &str, mime_type: &str) -> Option<String> {
+    let kind = mime_type.strip_prefix(GOOGLE_APPS_MIME_PREFIX)?;
+    let url = match kind {
+        "document" => format!(
+            "https://docs.google.com/document/d/{}/export?format=pdf",
+            file_id
+        ),
+        "spreadsheet" => format!(
+            "https://docs.google.com/spreadsheets/d/{}/export?format=pdf",
+            file_id
+        ),
+        "presentation" => format!(
+            "https://docs.google.com/presentation/d/{}/export/pdf",
+            file_id
+        ),
+        "drawing" => format!("https://docs.google.com/drawings/d/{}/export/pdf", file_id),
+        _ => return None,
+    };
+    Some(url)
+}
+
+/// Resolve the URL a Drive file can be downloaded from
+///
+/// Files too large for Google's virus scan get an HTML confirm page instead of the
+/// file. The confirm form on it leads to the actual download.
+pub async fn resolve_download_url(file_id: &str) -> Result<String> {
+    let url = super::download::download_url(file_id);
+    let client = build_http_client().await?;
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to download Drive file {}", file_id))?;
+
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    if !response.status().is_success() || !is_html {
+        // Dropping the response stops the body, the download itself requests the URL again
+        return Ok(url);
+    }
+
+    let page = response.text().await?;
+    confirm_url(&page).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Drive file {} can't be downloaded, it may not be shared publicly",
+            file_id
+        )
+    })
+}
+
+/// Find the download URL on a Drive virus scan confirm page
+fn confirm_url(page: &str) -> Option<String> {
+    let document = Html::parse_document(page);
+
+    // Current confirm page: a form with the file ID, confirm token and UUID as hidden inputs
+    let form_selector = Selector::parse("form#download-form").ok()?;
+    let input_selector = Selector::parse("input[type=hidden]").ok()?;
+    if let Some(form) = document.select(&form_selector).next() {
+        let action = form.value().attr("action")?;
+        let params = form
+            .select(&input_selector)
+            .filter_map(|input| Some((input.value().attr("name")?, input.value().attr("value")?)));
+        return Url::parse_with_params(action, params)
+            .ok()
+            .map(|url| url.to_string());
+    }
+
+    // Older confirm page: a link with the confirm token
+    let link_selector = Selector::parse("a#uc-download-link").ok()?;
+    let href = document
+        .select(&link_selector)
+        .next()?
+        .value()
+        .attr("href")?;
+    Url::parse("https://drive.google.com")
+        .ok()?
+        .join(href)
+        .ok()
+        .map(|url| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Virus scan confirm page served by drive.usercontent.google.com
+    const FORM_PAGE: &str = r#"<!DOCTYPE html><html><head><title>Google Drive - Virus scan warning</title></head>
+<body><div class="uc-main"><div id="uc-text"><p class="uc-warning-caption">Google Drive can't scan this file for viruses.</p>
+<p class="uc-warning-subcaption"><span class="uc-name-size"><a href="/open?id=1AbC">archive.zip</a> (1.2G)</span> is too large for Google to scan for viruses. Would you still like to download this file?</p>
+<form id="download-form" action="https://drive.usercontent.google.com/download" method="get">
+<input type="submit" id="uc-download-link" class="goog-inline-block jfk-button jfk-button-action" value="Download anyway"/>
+<input type="hidden" name="id" value="1AbC">
+<input type="hidden" name="export" value="download">
+<input type="hidden" name="confirm" value="t">
+<input type="hidden" name="uuid" value="0f4c6e2a-1b2c-4d5e-8f90-a1b2c3d4e5f6">
+</form></div></div></body></html>"#;
+
+    /// Older confirm page served by drive.google.com
+    const LINK_PAGE: &str = r#"<!DOCTYPE html><html><head><title>Google Drive - Virus scan warning</title></head>
+<body><div class="uc-main"><div id="uc-text"><p class="uc-warning-caption">Google Drive can't scan this file for viruses.</p>
+<a id="uc-download-link" class="goog-inline-block jfk-button jfk-button-action" href="/uc?export=download&amp;confirm=Xy_z&amp;id=1AbC">Download anyway</a>
+</div></div></body></html>"#;
+
+    fn page(ids: &[&str], next_page_token: Option<&str>) -> DriveFolderPage {
+        DriveFolderPage {
+            items: ids
+                .iter()
+                .map(|id| GDriveItem {
+                    name: id.to_string(),
+                    data_id: Some(id.to_string()),
+                    is_folder: false,
+                    size: None,
+                    file_type: None,
+                    url: None,
+                    parent_id: None,
+                    children: None,
+                    mime_type: None,
+                })
+                .collect(),
+            next_page_token: next_page_token.map(str::to_string),
+            cached: false,
+        }
+    }
+
+    fn ids(folder: &CachedFolder) -> Vec<String> {
+        folder
+            .page
+            .items
+            .iter()
+            .map(|item| item.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_confirm_url_form() {
+        assert_eq!(
+            confirm_url(FORM_PAGE).as_deref(),
+            Some("https://drive.usercontent.google.com/download?id=1AbC&export=download&confirm=t&uuid=0f4c6e2a-1b2c-4d5e-8f90-a1b2c3d4e5f6")
+        );
+    }
+
+    #[test]
+    fn test_confirm_url_link() {
+        assert_eq!(
+            confirm_url(LINK_PAGE).as_deref(),
+            Some("https://drive.google.com/uc?export=download&confirm=Xy_z&id=1AbC")
+        );
+    }
+
+    #[test]
+    fn test_confirm_url_missing() {
+        assert_eq!(
+            confirm_url("<html><body>Quota exceeded</body></html>"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cache_pages_of_folder() {
+        let mut folders = HashMap::new();
+        let key = cache_key("folder", &[]);
+
+        cache_page(
+            &mut folders,
+            key.clone(),
+            None,
+            &page(&["a", "b"], Some("t1")),
+            1,
+        );
+        cache_page(
+            &mut folders,
+            key.clone(),
+            Some("t1"),
+            &page(&["c"], Some("t2")),
+            2,
+        );
+        // a page listed twice, or not continuing the cached listing, is skipped
+        cache_page(
+            &mut folders,
+            key.clone(),
+            Some("t1"),
+            &page(&["c"], Some("t2")),
+            3,
+        );
+        cache_page(
+            &mut folders,
+            key.clone(),
+            Some("other"),
+            &page(&["x"], None),
+            4,
+        );
+        assert_eq!(folders.len(), 1);
+        assert_eq!(ids(&folders[&key]), ["a", "b", "c"]);
+        assert_eq!(folders[&key].page.next_page_token.as_deref(), Some("t2"));
+
+        // listing the folder again starts over
+        cache_page(&mut folders, key.clone(), None, &page(&["d"], None), 5);
+        assert_eq!(ids(&folders[&key]), ["d"]);
+    }
+
+    #[test]
+    fn test_cache_is_bounded() {
+        let mut folders = HashMap::new();
+        for n in 0..MAX_CACHED_FOLDERS + 10 {
+            let key = cache_key(&format!("folder-{}", n), &[]);
+            cache_page(&mut folders, key, None, &page(&["a"], None), n as i64);
+        }
+        assert_eq!(folders.len(), MAX_CACHED_FOLDERS);
+        assert!(!folders.contains_key(&cache_key("folder-9", &[])));
+        assert!(folders.contains_key(&cache_key("folder-10", &[])));
+
+        let many: Vec<String> = (0..MAX_CACHED_ITEMS + 1).map(|n| n.to_string()).collect();
+        let many: Vec<&str> = many.iter().map(String::as_str).collect();
+        let key = cache_key("large", &[]);
+        cache_page(
+            &mut folders,
+            key.clone(),
+            None,
+            &page(&many, Some("t")),
+            i64::MAX,
+        );
+        cache_page(
+            &mut folders,
+            key.clone(),
+            Some("t"),
+            &page(&["x"], None),
+            i64::MAX,
+        );
+        assert_eq!(folders[&key].page.items.len(), MAX_CACHED_ITEMS);
+    }
+}
@@ -14,9 +14,14 @@ pub struct GDriveItem {
     pub url: Option<String>, // Made optional to handle null values
     pub parent_id: Option<String>,
     pub children: Option<Vec<GDriveItem>>,
+    #[serde(default)]
+    pub mime_type: Option<String>,
 }
 
-/// Static cache for gdrive data
+/// Static cache for the bundled catalogue of shared folders
+///
+/// The catalogue provides the root folders to browse. Their contents are listed live
+/// from Drive, the catalogue only serves as fallback when Drive can't be reached.
 static GDRIVE_DATA_CACHE: OnceLock<Vec<GDriveItem>> = OnceLock::new();
 static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
 
@@ -58,6 +63,21 @@ fn load_gdrive_data() -> Result<Vec<GDriveItem>> {
     Ok(data)
 }
 
+/// Find the children of a folder in the bundled catalogue
+fn find_catalogue_children(folder_id: &str) -> Option<Vec<GDriveItem>> {
+    fn find<'a>(items: &'a [GDriveItem], folder_id: &str) -> Option<&'a GDriveItem> {
+        items.iter().find_map(|item| {
+            if item.is_folder && item.data_id.as_deref() == Some(folder_id) {
+                Some(item)
+            } else {
+                find(item.children.as_deref().unwrap_or_default(), folder_id)
+            }
+        })
+    }
+
+    let data = load_gdrive_data().ok()?;
+    Some(find(&data, folder_id)?.children.clone().unwrap_or_default())
+}
 
 /// Get root folders only (top level)
 #[command]
@@ -84,10 +104,12 @@ pub fn get_root_folders() -> Result<Vec<GDriveItem>, String> {
 
 pub mod download;
 pub mod downloads;
+pub mod listing;
 
 use download::download_file as download_drive_file_impl;
 use download::{get_recorded_downloads, is_file_downloaded, DownloadRecord};
 use downloads::scan_downloads_with_metadata as scan_downloads_with_metadata_impl;
+use listing::DriveFolderPage;
 
 /// Test command to verify Tauri communication
 #[command]
@@ -107,15 +129,37 @@ pub async fn scan_downloads_with_metadata() -> Result<Vec<GDriveItem>, String> {
     scan_downloads_with_metadata_impl().await
 }
 
-/// Download file from Google Drive
+/// List one page of a publicly shared Google Drive folder
+///
+/// With MIME types given, only files of these types are listed, along with all subfolders.
+#[command]
+pub async fn list_drive_folder(
+    folder_id: String,
+    page_token: Option<String>,
+    page_size: Option<u32>,
+    mime_types: Option<Vec<String>>,
+    app_handle: AppHandle,
+) -> Result<DriveFolderPage, String> {
+    listing::list_folder(
+        &app_handle,
+        &folder_id,
+        page_token.as_deref(),
+        page_size,
+        &mime_types.unwrap_or_default(),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Queue the download of a file from Google Drive
 #[command]
 pub async fn download_drive_file(
     file_id: String,
     file_name: String,
+    mime_type: Option<String>,
     app_handle: AppHandle,
 ) -> Result<String, String> {
-    let file_path = download_drive_file_impl(file_id, file_name, app_handle)
-        .await
+    let file_path = download_drive_file_impl(file_id, file_name, mime_type, app_handle)
         .map_err(|e| e.to_string())?;
 
     Ok(file_path.to_string_lossy().to_string())
@@ -1,11 +1,13 @@
 use libp2p::PeerId;
 use serde_json::Value;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use tauri::{AppHandle, Emitter};
 use tokio::time::{interval, Duration};
 
+use crate::tauri::downloads::{DownloadManager, DownloadRequest, DownloadSource};
+
 // Helper function to decode peer_id array to readable string
 fn decode_peer_id(peer_id_array: &[u8]) -> String {
     match PeerId::from_bytes(&peer_id_array) {
@@ -187,6 +189,8 @@ pub async fn start_file_download(
     file_id: u64,
     file_name: String,
     file_size: u64,
+    app_handle: AppHandle,
+    manager: tauri_crate::State<'_, DownloadManager>,
 ) -> Result<String, String> {
     log::info!(
         "Starting file download: file_id={}, file_name={}, file_size={}",
@@ -212,23 +216,25 @@ pub async fn start_file_download(
         return Ok(destination_path.to_string_lossy().to_string());
     }
 
-    // Get the source file path from libqaul storage
-    match qaul_cli::get_file_path(file_id) {
-        Ok(source_path) => {
-            log::info!("Found file in libqaul storage: {}", source_path);
-            
-            // Copy file from libqaul storage to KnowliaDownloads
-            fs::copy(&source_path, &destination_path)
-                .map_err(|e| format!("Failed to copy file: {}", e))?;
-            
-            log::info!("File copied to: {:?}", destination_path);
-            Ok(destination_path.to_string_lossy().to_string())
-        }
-        Err(e) => {
-            log::warn!("File not yet received in libqaul storage: {}", e);
-            Err(format!("File not yet available: {}", e))
-        }
-    }
+    // The download manager copies the file out of libqaul's storage once it has been received
+    let file_extension = Path::new(&file_name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let download = manager.enqueue(
+        &app_handle,
+        DownloadRequest {
+            source: DownloadSource::Qaul {
+                file_id,
+                file_extension,
+            },
+            file_name,
+            destination: Some(destination_path.to_string_lossy().to_string()),
+            checksum: None,
+        },
+    )?;
+
+    Ok(download.file_path)
 }
 
 #[tauri_crate::command]
@@ -1,7 +1,8 @@
 use ::tauri::{command, State};
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use crate::tauri::zotero::{ZoteroManager, ZoteroConfig, ZoteroItem, ZoteroCollection};
+use crate::tauri::zotero_complete::{ZoteroManager, ZoteroConfig, ZoteroItem, ZoteroCollection, ZoteroConflict, SyncReport};
+use crate::tauri::zotero_bibliography::{self, BibliographyFormat, CitationStyle, Citation};
 use std::path::PathBuf;
 
 /// Global state for Zotero manager
@@ -36,7 +37,7 @@ pub async fn init_zotero(state: State<'_, ZoteroState>) -> Result<bool, String>
     std::fs::create_dir_all(&app_data_dir).map_err(|e| e.to_string())?;
     
     // Initialize Zotero manager
-    let manager: ZoteroManager = ZoteroManager::new(app_data_dir)
+    let mut manager: ZoteroManager = ZoteroManager::new(app_data_dir)
         .await
         .map_err(|e| format!("Failed to initialize Zotero manager: {}", e))?;
     
@@ -163,7 +164,7 @@ pub async fn create_zotero_item(
     let manager = manager_guard.as_ref()
         .ok_or("Zotero manager not initialized")?;
     
-    let created_item: ZoteroItem = manager.create_item(&item).await
+    let created_item: ZoteroItem = manager.create_item(item).await
         .map_err(|e| format!("Failed to create item: {}", e))?;
     
     Ok(created_item)
@@ -179,7 +180,7 @@ pub async fn update_zotero_item(
     let manager = manager_guard.as_ref()
         .ok_or("Zotero manager not initialized")?;
     
-    let updated_item: ZoteroItem = manager.update_item(&item).await
+    let updated_item: ZoteroItem = manager.update_item(item).await
         .map_err(|e| format!("Failed to update item: {}", e))?;
     
     Ok(updated_item)
@@ -211,7 +212,7 @@ pub async fn create_zotero_collection(
     let manager = manager_guard.as_ref()
         .ok_or("Zotero manager not initialized")?;
     
-    let created_collection: ZoteroCollection = manager.create_collection(&collection.name, collection.parent_collection.as_deref()).await
+    let created_collection: ZoteroCollection = manager.create_collection(collection).await
         .map_err(|e| format!("Failed to create collection: {}", e))?;
     
     Ok(created_collection)
@@ -227,7 +228,7 @@ pub async fn update_zotero_collection(
     let manager = manager_guard.as_ref()
         .ok_or("Zotero manager not initialized")?;
     
-    let updated_collection: ZoteroCollection = manager.update_collection(&collection).await
+    let updated_collection: ZoteroCollection = manager.update_collection(collection).await
         .map_err(|e| format!("Failed to update collection: {}", e))?;
     
     Ok(updated_collection)
@@ -274,3 +275,118 @@ pub async fn get_zotero_stats(state: State<'_, ZoteroState>) -> Result<(usize, u
     
     Ok(stats)
 }
+
+/// Sync the local library with Zotero
+#[command]
+pub async fn sync_zotero(state: State<'_, ZoteroState>) -> Result<SyncReport, String> {
+    let manager_guard: tokio::sync::MutexGuard<Option<ZoteroManager>> = state.manager.lock().await;
+    let manager = manager_guard.as_ref()
+        .ok_or("Zotero manager not initialized")?;
+    
+    let report: SyncReport = manager.sync().await
+        .map_err(|e| format!("Failed to sync Zotero library: {}", e))?;
+    
+    Ok(report)
+}
+
+/// Get the items changed both locally and in Zotero
+#[command]
+pub async fn get_zotero_conflicts(state: State<'_, ZoteroState>) -> Result<Vec<ZoteroConflict>, String> {
+    let manager_guard: tokio::sync::MutexGuard<Option<ZoteroManager>> = state.manager.lock().await;
+    let manager = manager_guard.as_ref()
+        .ok_or("Zotero manager not initialized")?;
+    
+    let conflicts: Vec<ZoteroConflict> = manager.get_conflicts().await
+        .map_err(|e| format!("Failed to get conflicts: {}", e))?;
+    
+    Ok(conflicts)
+}
+
+/// Resolve a conflict by keeping the local or the remote version of the item
+#[command]
+pub async fn resolve_zotero_conflict(
+    state: State<'_, ZoteroState>,
+    item_key: String,
+    keep_local: bool,
+) -> Result<(), String> {
+    let manager_guard: tokio::sync::MutexGuard<Option<ZoteroManager>> = state.manager.lock().await;
+    let manager = manager_guard.as_ref()
+        .ok_or("Zotero manager not initialized")?;
+    
+    manager.resolve_conflict(&item_key, keep_local).await
+        .map_err(|e| format!("Failed to resolve conflict: {}", e))?;
+    
+    Ok(())
+}
+
+/// Download the file of an attachment to the downloads directory
+#[command]
+pub async fn download_zotero_attachment(
+    state: State<'_, ZoteroState>,
+    item_key: String,
+) -> Result<String, String> {
+    let manager_guard: tokio::sync::MutexGuard<Option<ZoteroManager>> = state.manager.lock().await;
+    let manager = manager_guard.as_ref()
+        .ok_or("Zotero manager not initialized")?;
+    
+    let download_dir = crate::commands::gdrive::download::get_download_dir()
+        .map_err(|e| e.to_string())?;
+    let path = manager.download_attachment(&item_key, &download_dir.join("zotero")).await
+        .map_err(|e| format!("Failed to download attachment: {}", e))?;
+    
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Get the given items, in that order, or else the items of a collection
+async fn select_items(
+    manager: &ZoteroManager,
+    collection_key: Option<String>,
+    item_keys: Option<Vec<String>>,
+) -> Result<Vec<ZoteroItem>, String> {
+    if let Some(item_keys) = item_keys {
+        let items: Vec<ZoteroItem> = manager.get_items().await
+            .map_err(|e| format!("Failed to get items: {}", e))?;
+        return Ok(item_keys.iter()
+            .filter_map(|key| items.iter().find(|item| &item.key == key).cloned())
+            .collect());
+    }
+
+    let collection_key = collection_key
+        .ok_or("Select a collection or items to cite")?;
+    manager.get_collection_items(&collection_key).await
+        .map_err(|e| format!("Failed to get collection items: {}", e))
+}
+
+/// Export a collection or selected items as BibTeX, BibLaTeX, RIS or CSL-JSON
+#[command]
+pub async fn export_zotero_bibliography(
+    state: State<'_, ZoteroState>,
+    format: BibliographyFormat,
+    collection_key: Option<String>,
+    item_keys: Option<Vec<String>>,
+) -> Result<String, String> {
+    let manager_guard: tokio::sync::MutexGuard<Option<ZoteroManager>> = state.manager.lock().await;
+    let manager = manager_guard.as_ref()
+        .ok_or("Zotero manager not initialized")?;
+    
+    let items = select_items(manager, collection_key, item_keys).await?;
+    
+    Ok(zotero_bibliography::export(&items, format))
+}
+
+/// Format a collection or selected items in the APA, IEEE or Harvard style
+#[command]
+pub async fn format_zotero_citations(
+    state: State<'_, ZoteroState>,
+    style: CitationStyle,
+    collection_key: Option<String>,
+    item_keys: Option<Vec<String>>,
+) -> Result<Vec<Citation>, String> {
+    let manager_guard: tokio::sync::MutexGuard<Option<ZoteroManager>> = state.manager.lock().await;
+    let manager = manager_guard.as_ref()
+        .ok_or("Zotero manager not initialized")?;
+    
+    let items = select_items(manager, collection_key, item_keys).await?;
+    
+    Ok(zotero_bibliography::format_citations(&items, style))
+}
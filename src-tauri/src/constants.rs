@@ -1,5 +1,8 @@
 pub const DB_POOL_SIZE: usize = 5;
 
+// Download manager
+pub const MAX_CONCURRENT_DOWNLOADS: usize = 3;
+
 // LLM Related Constants
 pub const VISION_MODEL_NAME: &str = "qwen/qwen-2-vl-7b-instruct";
 pub const CHAT_MODEL_NAME: &str = "anthropic/claude-3.5-haiku";
@@ -56,6 +59,10 @@ pub const EMBEDDING_SIZE: usize = 384;
 pub const MAX_ROWS: u32 = 10; // max rows in semantic search
 pub const HOURS_AGO: u32 = 6; // semantic search
 
+// Transcription HF
+pub const TRANSCRIPTION_MODEL_ID: &str = "openai/whisper-tiny";
+pub const TRANSCRIPTION_MODEL_REVISION: &str = "main";
+
 // Context Bandit Model name
 pub const BANDIT_MODEL_PATH: &str = "context_bandit_model";
 
@@ -1,6 +1,7 @@
 extern crate tauri as tauri_crate;
 use ::tauri_crate::{Manager, AppHandle, Emitter, Listener, LogicalPosition, Window, LogicalSize, State, WebviewWindowBuilder, WebviewUrl, Event, Builder, App, async_runtime};
 use embedding::service::EmbeddingManager;
+use transcription::service::TranscriptionManager;
 use llm::{mcp_client, mcp_server, LoycaServer};
 use ocr::OcrManager;
 use tauri_plugin_updater::UpdaterExt;
@@ -24,6 +25,12 @@ use crate::commands::zotero::get_zotero_items;
 use crate::commands::zotero::get_zotero_config;
 use crate::commands::zotero::configure_zotero;
 use crate::commands::zotero::init_zotero;
+use crate::commands::zotero::sync_zotero;
+use crate::commands::zotero::get_zotero_conflicts;
+use crate::commands::zotero::resolve_zotero_conflict;
+use crate::commands::zotero::download_zotero_attachment;
+use crate::commands::zotero::export_zotero_bibliography;
+use crate::commands::zotero::format_zotero_citations;
 use crate::tauri::group::open_file;
 use crate::commands::zotero::is_zotero_configured;
 use crate::tauri::ble::{
@@ -39,13 +46,14 @@ use contextual_bandit::{manager as cb_manager, notifications};
 // Commands modules - uncomment when needed
  use commands::bolt::{confirm_verification, get_location_suggestions, start_verification};
 use commands::gdrive::{
-    download_drive_file, get_recorded_downloads_command, get_root_folders, open_storage_file,
-    scan_downloads, scan_downloads_with_metadata, test_downloads_command,
+    download_drive_file, get_recorded_downloads_command, get_root_folders, list_drive_folder,
+    open_storage_file, scan_downloads, scan_downloads_with_metadata, test_downloads_command,
 };
 use commands::zotero::ZoteroState;
 
 
 mod embedding;
+mod transcription;
 mod llm;
 mod helpers;
 mod sql;
@@ -70,6 +78,8 @@ pub mod modes;
 use crate::tauri::user::{get_all_users, get_online_users, get_offline_users, user_profile, set_node_profile_tauri};
 use crate::tauri::group::{create_group, create_direct_chat, get_or_create_direct_chat, get_group_info, get_group_list, rename_group, get_pending_invitations, get_new_message_id, invite_user_to_group, reply_to_group_invitation, remove_user_from_group, leave_group, get_messages, send_message, read_file_as_base64, delete_all_group_messages, delete_messages, get_total_unread_count};
 pub mod str0m;
+#[cfg(target_os = "linux")]
+pub mod bluez;
 pub mod tauri;
 // Import qaul module functions
 /// Result type for group operations
@@ -104,6 +114,7 @@ pub struct GroupMember {
     pub profile_pic: String,
     pub about: String,
     pub is_online: bool,
+    pub trust: i32,
 }
 
 /// Application state
@@ -175,6 +186,7 @@ impl From<libqaul::services::group::proto_rpc::GroupMember> for GroupMember {
             profile_pic: proto.profile_pic,
             about: proto.about,
             is_online,
+            trust: proto.trust,
         }
     }
 }
@@ -528,6 +540,9 @@ fn resize_avatar_window(width: f64, height: f64, window: Window) -> Result<(), S
 
 /// Helper function to get current user (for internal use by plugins)
 /// This is NOT a Tauri command - it's used by other Rust code
+///
+/// Returns the user account currently selected in libqaul,
+/// see `tauri::user::switch_user_account`.
 pub fn get_current_user_internal() -> Option<libqaul::node::user_accounts::UserAccount> {
     libqaul::node::user_accounts::UserAccounts::get_default_user()
 }
@@ -536,14 +551,18 @@ pub fn get_current_user_internal() -> Option<libqaul::node::user_accounts::UserA
 #[tauri_crate::command]
 async fn get_current_user_id(state: State<'_, AppState>) -> GroupResult<Option<String>> {
     let current_user = state.current_user.lock().unwrap();
-    Ok(current_user.clone())
+    Ok(current_user
+        .clone()
+        .or_else(|| get_current_user_internal().map(|account| account.id.to_base58())))
 }
 
 /// Get current user (Tauri command for frontend)
 #[tauri_crate::command]
 async fn get_current_user(state: State<'_, AppState>) -> GroupResult<Option<String>> {
     let current_user = state.current_user.lock().unwrap();
-    Ok(current_user.clone())
+    Ok(current_user
+        .clone()
+        .or_else(|| get_current_user_internal().map(|account| account.id.to_base58())))
 }
 
 /// Save sidebar collapse state
@@ -1189,6 +1208,7 @@ pub fn run() {
     Builder::default()
     .manage(session_state)
     .manage(EmbeddingManager::new())
+    .manage(TranscriptionManager::new())
     .manage(OcrManager::new())
     .manage(ZoteroState::new())
     .manage(app_state)
@@ -1220,6 +1240,16 @@ pub fn run() {
             
             // Initialize MEGA state
             app.manage(crate::tauri::mega::MegaState::default());
+
+            // Initialize download manager, picking up downloads interrupted on last exit
+            if let Err(e) = commands::gdrive::download::import_legacy_downloads() {
+                log::error!("Failed to import legacy downloads: {}", e);
+            }
+            let download_manager = crate::tauri::downloads::DownloadManager::default();
+            if let Err(e) = download_manager.resume_interrupted(&app_handle) {
+                log::error!("Failed to resume interrupted downloads: {}", e);
+            }
+            app.manage(download_manager);
             
             // Initialize gdrive app handle for resource resolution
             commands::gdrive::init_app_handle(&app_handle);
@@ -1271,6 +1301,7 @@ pub fn run() {
             get_location_suggestions,
             //GDRIVE
             get_root_folders,
+            list_drive_folder,
             download_drive_file,
             open_file,
             scan_downloads,
@@ -1344,6 +1375,8 @@ pub fn run() {
             embedding::create_embedding,
             embedding::compute_text_similarity,
             embedding::compute_embedding_similarity,
+            transcription::init_transcription_service,
+            transcription::transcription_service_info,
             // OCR service
             ocr::init_ocr_service,
             ocr::ocr_service_info,
@@ -1397,6 +1430,26 @@ pub fn run() {
             tauri::rtc::rtc_create_offer,
             tauri::rtc::rtc_create_answer,
             tauri::rtc::start_str0m_server,
+            tauri::rtc::rtc_group_call_start,
+            tauri::rtc::rtc_group_call_join,
+            tauri::rtc::rtc_group_call_mute,
+            tauri::rtc::rtc_group_call_leave,
+            tauri::rtc::rtc_send_ice_candidate,
+            tauri::rtc::rtc_get_remote_candidates,
+            tauri::rtc::rtc_get_ice_config,
+            tauri::rtc::rtc_set_ice_config,
+            tauri::rtc::rtc_ice_restart,
+            tauri::rtc::rtc_ice_restart_answer,
+            tauri::rtc::rtc_share_screen_start,
+            tauri::rtc::rtc_send_file,
+            tauri::rtc::rtc_share_answer,
+            tauri::rtc::rtc_share_stop,
+            tauri::rtc_recording::rtc_recording_start,
+            tauri::rtc_recording::rtc_recording_audio,
+            tauri::rtc_recording::rtc_recording_video,
+            tauri::rtc_recording::rtc_recording_stop,
+            tauri::rtc_recording::rtc_recording_get,
+            tauri::rtc_recording::rtc_recording_transcribe,
             // TIMETABLE
             timetable::init_database,
             timetable::fetch_timetable_data,
@@ -1421,13 +1474,44 @@ pub fn run() {
             delete_zotero_collection,
             clear_zotero_cache,
             get_zotero_stats,
+            sync_zotero,
+            get_zotero_conflicts,
+            resolve_zotero_conflict,
+            download_zotero_attachment,
+            export_zotero_bibliography,
+            format_zotero_citations,
             // MEGA commands commented out as module doesn't exist
             tauri::mega::mega_login,
+            tauri::mega::mega_resume_session,
             tauri::mega::mega_logout,
             tauri::mega::mega_get_tree_listing,
             tauri::mega::mega_get_cloud_drive,
             tauri::mega::mega_get_inbox,
             tauri::mega::mega_get_rubbish_bin,
+            tauri::mega::mega_get_storage_quotas,
+            tauri::mega::mega_upload_file,
+            tauri::mega::mega_download_file,
+            tauri::mega::mega_create_folder,
+            tauri::mega::mega_rename_node,
+            tauri::mega::mega_move_node,
+            tauri::mega::mega_delete_node,
+            tauri::mega::mega_restore_node,
+            tauri::mega::mega_get_thumbnail,
+            tauri::mega::mega_get_preview_image,
+            tauri::mega::mega_upload_thumbnail,
+            tauri::mega::mega_upload_preview_image,
+            tauri::mega::mega_start_sync,
+            tauri::mega::mega_stop_sync,
+            tauri::mega::mega_get_sync_status,
+            //DOWNLOADS
+            tauri::downloads::download_enqueue,
+            tauri::downloads::download_pause,
+            tauri::downloads::download_resume,
+            tauri::downloads::download_cancel,
+            tauri::downloads::download_remove,
+            tauri::downloads::download_get_queue,
+            tauri::downloads::download_get_recent,
+            tauri::downloads::download_clear_history,
             // Qaul commands
             crate::tauri::qaul::qaul_send_command,
             crate::tauri::qaul::get_internet_neighbours_ui_command,
@@ -1440,7 +1524,27 @@ pub fn run() {
             crate::tauri::user::get_offline_user_mappings,
             crate::tauri::user::convert_peer_id_to_q8id,
             crate::tauri::user::convert_q8id_to_peer_id,
+            crate::tauri::user::list_user_accounts,
+            crate::tauri::user::create_user_account,
+            crate::tauri::user::login_user_account,
+            crate::tauri::user::logout_user_account,
+            crate::tauri::user::switch_user_account,
+            crate::tauri::user::set_user_account_passphrase,
+            crate::tauri::user::get_security_number,
+            crate::tauri::user::get_verification_qr,
+            crate::tauri::user::verify_user_qr,
+            crate::tauri::user::set_user_verified,
+            crate::tauri::user::get_user_trust,
             crate::tauri::qaul::get_network_stats,
+            // Configuration commands
+            crate::tauri::config::qaul_get_configuration,
+            crate::tauri::config::qaul_validate_configuration,
+            crate::tauri::config::qaul_update_configuration,
+            // Storage encryption commands
+            crate::tauri::encryption::qaul_storage_encryption_status,
+            crate::tauri::encryption::qaul_unlock_storage,
+            crate::tauri::encryption::qaul_enable_storage_encryption,
+            crate::tauri::encryption::qaul_change_storage_passphrase,
             get_total_unread_count,
             // BLE commands
             get_ble_info,
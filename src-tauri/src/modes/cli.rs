@@ -8,9 +8,11 @@
 use super::ble::Ble;
 use super::chat::Chat;
 use super::chatfile::ChatFile;
+use super::configuration::Configuration;
 use super::connections::Connections;
 use super::debug::Debug;
 use super::dtn::Dtn;
+use super::encryption::Encryption;
 use super::feed::Feed;
 use super::group::Group;
 use super::node::Node;
@@ -78,6 +80,14 @@ impl Cli {
             cmd if cmd.starts_with("dtn ") => {
                 Dtn::cli(cmd.strip_prefix("dtn ").unwrap());
             }
+            // runtime configuration functions
+            cmd if cmd.starts_with("config ") => {
+                Configuration::cli(cmd.strip_prefix("config ").unwrap());
+            }
+            // storage encryption functions
+            cmd if cmd.starts_with("storage ") => {
+                Encryption::cli(cmd.strip_prefix("storage ").unwrap());
+            }
             // unknown command
             _ => log::error!("unknown command"),
         }
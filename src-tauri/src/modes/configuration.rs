@@ -0,0 +1,169 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Configuration module functions
+
+use super::rpc::Rpc;
+use prost::Message;
+
+/// include generated protobuf RPC rust definition file
+mod proto {
+    include!("../../../modules/libqaul/src/rpc/protobuf_generated/rust/qaul.rpc.configuration.rs");
+}
+
+/// Configuration module function handling
+pub struct Configuration {}
+
+impl Configuration {
+    /// CLI command interpretation
+    ///
+    /// The CLI commands of the configuration module are processed here
+    pub fn cli(command: &str) {
+        match command {
+            // show the runtime configuration
+            cmd if cmd.starts_with("get") => {
+                Self::configuration_request();
+            }
+            // set routing table sending period
+            cmd if cmd.starts_with("routing table ") => {
+                match cmd.strip_prefix("routing table ").unwrap().parse::<u64>() {
+                    Ok(period) => Self::routing_table_period(period),
+                    Err(_) => log::error!("invalid period"),
+                }
+            }
+            // unknown command
+            _ => log::error!("unknown config command"),
+        }
+    }
+
+    /// request the runtime configuration from libqaul
+    fn configuration_request() {
+        let proto_message = proto::Configuration {
+            message: Some(proto::configuration::Message::ConfigurationRequest(
+                proto::ConfigurationRequest {},
+            )),
+        };
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Configuration.into(),
+            "".to_string(),
+        );
+    }
+
+    /// change the routing table sending period
+    ///
+    /// The other routing options are kept. The current
+    /// values are read from libqaul directly.
+    fn routing_table_period(period: u64) {
+        let routing = libqaul::storage::configuration::Configuration::get()
+            .routing
+            .clone();
+        let proto_message = proto::Configuration {
+            message: Some(proto::configuration::Message::UpdateRequest(
+                proto::ConfigurationUpdate {
+                    lan: None,
+                    internet: None,
+                    routing: Some(proto::RoutingConfiguration {
+                        sending_table_period: period,
+                        ping_neighbour_period: routing.ping_neighbour_period,
+                        hop_count_penalty: routing.hop_count_penalty,
+                        maintain_period_limit: routing.maintain_period_limit,
+                    }),
+                    debug: None,
+                    feed: None,
+                },
+            )),
+        };
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Configuration.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Process received RPC message
+    ///
+    /// Decodes received protobuf encoded binary RPC message
+    /// of the configuration module.
+    pub fn rpc(data: Vec<u8>) {
+        match proto::Configuration::decode(&data[..]) {
+            Ok(configuration) => match configuration.message {
+                Some(proto::configuration::Message::ConfigurationInfo(info)) => {
+                    println!("====================================");
+                    println!("Configuration");
+                    if let Some(lan) = info.lan {
+                        println!("\tLAN active: {}", lan.active);
+                        for address in lan.listen {
+                            println!("\t\tlisten {}", address);
+                        }
+                    }
+                    if let Some(internet) = info.internet {
+                        println!("\tInternet active: {}", internet.active);
+                        for address in internet.listen {
+                            println!("\t\tlisten {}", address);
+                        }
+                        for peer in internet.peers {
+                            println!(
+                                "\t\tpeer {} {} enabled: {}",
+                                peer.name, peer.address, peer.enabled
+                            );
+                        }
+                    }
+                    if let Some(routing) = info.routing {
+                        println!("\tRouting");
+                        println!(
+                            "\t\tsending table period: {}s",
+                            routing.sending_table_period
+                        );
+                        println!(
+                            "\t\tping neighbour period: {}s",
+                            routing.ping_neighbour_period
+                        );
+                        println!("\t\thop count penalty: {}s", routing.hop_count_penalty);
+                        println!(
+                            "\t\tmaintain period limit: {}s",
+                            routing.maintain_period_limit
+                        );
+                    }
+                    if let Some(debug) = info.debug {
+                        println!("\tDebug log: {}", debug.log);
+                    }
+                    if let Some(feed) = info.feed {
+                        println!("\tFeed");
+                        println!("\t\tmax age: {}s", feed.max_age);
+                        println!("\t\tmax size: {} MB", feed.max_size);
+                        println!("\t\tsync window: {}s", feed.sync_window);
+                        println!("\t\tsync interval: {}s", feed.sync_interval);
+                    }
+                }
+                Some(proto::configuration::Message::ConfigurationResult(result)) => {
+                    println!("====================================");
+                    println!("Configuration Update");
+                    if result.success {
+                        println!("\tSuccess");
+                    } else {
+                        println!("\tFailed");
+                        for error in result.errors {
+                            println!("\t{}: {}", error.field, error.message);
+                        }
+                    }
+                }
+                Some(proto::configuration::Message::ConfigurationChanged(changed)) => {
+                    for section in changed.sections {
+                        if let Ok(section) = proto::ConfigurationSection::try_from(section) {
+                            println!("configuration changed: {}", section.as_str_name());
+                        }
+                    }
+                }
+                _ => {
+                    log::error!("unprocessable RPC configuration message");
+                }
+            },
+            Err(error) => {
+                log::error!("{:?}", error);
+            }
+        }
+    }
+}
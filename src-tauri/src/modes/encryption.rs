@@ -0,0 +1,115 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Storage encryption module functions
+
+use super::rpc::Rpc;
+use prost::Message;
+
+/// include generated protobuf RPC rust definition file
+mod proto {
+    include!("../../../modules/libqaul/src/rpc/protobuf_generated/rust/qaul.rpc.encryption.rs");
+}
+
+/// Storage encryption module function handling
+pub struct Encryption {}
+
+impl Encryption {
+    /// CLI command interpretation
+    ///
+    /// The CLI commands of the storage encryption module are processed here
+    pub fn cli(command: &str) {
+        match command {
+            // show the encryption state
+            cmd if cmd.starts_with("state") => {
+                Self::send(proto::encryption::Message::StateRequest(
+                    proto::EncryptionStateRequest {},
+                ));
+            }
+            // unlock the encrypted storage
+            cmd if cmd.starts_with("unlock ") => {
+                let passphrase = cmd.strip_prefix("unlock ").unwrap().to_string();
+                Self::send(proto::encryption::Message::Unlock(proto::UnlockStorage {
+                    passphrase,
+                }));
+            }
+            // enable encryption at rest
+            cmd if cmd.starts_with("enable ") => {
+                let passphrase = cmd.strip_prefix("enable ").unwrap().to_string();
+                Self::send(proto::encryption::Message::Enable(
+                    proto::EnableEncryption { passphrase },
+                ));
+            }
+            // change the storage passphrase
+            cmd if cmd.starts_with("passphrase ") => {
+                let mut iter = cmd.strip_prefix("passphrase ").unwrap().split_whitespace();
+                match (iter.next(), iter.next()) {
+                    (Some(old_passphrase), Some(new_passphrase)) => {
+                        Self::send(proto::encryption::Message::ChangePassphrase(
+                            proto::ChangeStoragePassphrase {
+                                old_passphrase: old_passphrase.to_string(),
+                                new_passphrase: new_passphrase.to_string(),
+                            },
+                        ));
+                    }
+                    _ => log::error!("storage passphrase {{Old Passphrase}} {{New Passphrase}}"),
+                }
+            }
+            // unknown command
+            _ => log::error!("unknown storage command"),
+        }
+    }
+
+    /// encode and send a storage encryption message
+    fn send(message: proto::encryption::Message) {
+        let proto_message = proto::Encryption {
+            message: Some(message),
+        };
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Encryption.into(),
+            "".to_string(),
+        );
+    }
+
+    /// print the encryption state
+    fn print_state(state: proto::EncryptionState) {
+        println!("\tenabled: {}", state.enabled);
+        println!("\tlocked: {}", state.locked);
+        println!("\tmigrated: {}", state.migrated);
+    }
+
+    /// Process received RPC message
+    ///
+    /// Decodes received protobuf encoded binary RPC message
+    /// of the storage encryption module.
+    pub fn rpc(data: Vec<u8>) {
+        match proto::Encryption::decode(&data[..]) {
+            Ok(encryption) => match encryption.message {
+                Some(proto::encryption::Message::State(state)) => {
+                    println!("====================================");
+                    println!("Storage Encryption");
+                    Self::print_state(state);
+                }
+                Some(proto::encryption::Message::Result(result)) => {
+                    println!("====================================");
+                    println!("Storage Encryption");
+                    if result.success {
+                        println!("\tSuccess");
+                    } else {
+                        println!("\tFailed: {}", result.error_message);
+                    }
+                    if let Some(state) = result.state {
+                        Self::print_state(state);
+                    }
+                }
+                _ => {
+                    log::error!("unprocessable RPC encryption message");
+                }
+            },
+            Err(error) => {
+                log::error!("{:?}", error);
+            }
+        }
+    }
+}
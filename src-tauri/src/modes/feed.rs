@@ -40,6 +40,45 @@ impl Feed {
                     }
                 }
             },
+            // request feed messages with a hashtag
+            cmd if cmd.starts_with("tag ") => {
+                Self::request_filtered_feed_list(cmd.strip_prefix("tag ").unwrap().to_string(), Vec::new());
+            },
+            // request feed messages of a user
+            cmd if cmd.starts_with("author ") => {
+                match Self::user_id_string_to_bin(cmd.strip_prefix("author ").unwrap()) {
+                    Ok(user_id) => Self::request_filtered_feed_list("".to_string(), user_id),
+                    Err(e) => log::error!("{}", e),
+                }
+            },
+            // block a user
+            cmd if cmd.starts_with("block ") => {
+                match Self::user_id_string_to_bin(cmd.strip_prefix("block ").unwrap()) {
+                    Ok(user_id) => Self::moderate(proto::feed_moderation::Action::BlockSender(user_id)),
+                    Err(e) => log::error!("{}", e),
+                }
+            },
+            // unblock a user
+            cmd if cmd.starts_with("unblock ") => {
+                match Self::user_id_string_to_bin(cmd.strip_prefix("unblock ").unwrap()) {
+                    Ok(user_id) => Self::moderate(proto::feed_moderation::Action::UnblockSender(user_id)),
+                    Err(e) => log::error!("{}", e),
+                }
+            },
+            // mute a hashtag
+            cmd if cmd.starts_with("mute ") => {
+                Self::moderate(proto::feed_moderation::Action::MuteHashtag(cmd.strip_prefix("mute ").unwrap().to_string()));
+            },
+            // unmute a hashtag
+            cmd if cmd.starts_with("unmute ") => {
+                Self::moderate(proto::feed_moderation::Action::UnmuteHashtag(cmd.strip_prefix("unmute ").unwrap().to_string()));
+            },
+            // request moderation settings
+            cmd if cmd.starts_with("moderation") => {
+                Self::send_rpc(proto::feed::Message::ModerationRequest(
+                    proto::FeedModerationRequest{}
+                ));
+            },
             // unknown command
             _ => log::error!("unknown feed command"),
         }
@@ -52,6 +91,10 @@ impl Feed {
             message: Some(proto::feed::Message::Send(
                 proto::SendMessage{
                     content: message_text,
+                    attachments: Vec::new(),
+                    reply_to: Vec::new(),
+                    expires: 0,
+                    hashtags: Vec::new(),
                 }
             )),
         };
@@ -72,6 +115,8 @@ impl Feed {
                 proto::FeedMessageRequest{
                     last_received: Vec::new(),
                     last_index,
+                    hashtag: "".to_string(),
+                    author: Vec::new(),
                 }
             )),
         };
@@ -84,6 +129,46 @@ impl Feed {
         Rpc::send_message(buf, super::rpc::proto::Modules::Feed.into(), "".to_string());
     }
 
+    /// request feed messages filtered by hashtag and author via rpc
+    fn request_filtered_feed_list(hashtag: String, author: Vec<u8>) {
+        Self::send_rpc(proto::feed::Message::Request(
+            proto::FeedMessageRequest{
+                last_received: Vec::new(),
+                last_index: 0,
+                hashtag,
+                author,
+            }
+        ));
+    }
+
+    /// change the local moderation via rpc
+    fn moderate(action: proto::feed_moderation::Action) {
+        Self::send_rpc(proto::feed::Message::Moderation(
+            proto::FeedModeration{
+                action: Some(action),
+            }
+        ));
+    }
+
+    /// encode and send feed rpc message
+    fn send_rpc(message: proto::feed::Message) {
+        let proto_message = proto::Feed {
+            message: Some(message),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message.encode(&mut buf).expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(buf, super::rpc::proto::Modules::Feed.into(), "".to_string());
+    }
+
+    /// Convert User ID from String to Binary
+    fn user_id_string_to_bin(id: &str) -> Result<Vec<u8>, String> {
+        bs58::decode(id.trim()).into_vec().map_err(|e| format!("invalid user id: {}", e))
+    }
+
     /// Process received RPC message
     /// 
     /// Decodes received protobuf encoded binary RPC message
@@ -107,10 +192,33 @@ impl Feed {
                             println!("Timestamp Received - {}", message.timestamp_received);
                             println!("Message ID {}", message.message_id_base58);
                             println!("From {}", message.sender_id_base58);
+                            if !message.reply_to.is_empty() {
+                                println!("Reply to {}", bs58::encode(message.reply_to).into_string());
+                            }
+                            if !message.hashtags.is_empty() {
+                                println!("Hashtags #{}", message.hashtags.join(" #"));
+                            }
+                            if message.expires != 0 {
+                                println!("Expires {}", message.expires);
+                            }
                             println!("\t{}", message.content);
+                            for attachment in message.attachments {
+                                println!("\tAttachment {} ({}, {} bytes) {}", attachment.name, attachment.mime_type, attachment.size, attachment.reference);
+                            }
                             println!("");
                         }
                     }
+                    Some(proto::feed::Message::ModerationList(moderation)) => {
+                        println!("====================================");
+                        println!("Feed Moderation");
+                        println!("------------------------------------");
+                        for user_id in moderation.blocked_senders {
+                            println!("Blocked {}", bs58::encode(user_id).into_string());
+                        }
+                        for hashtag in moderation.muted_hashtags {
+                            println!("Muted #{}", hashtag);
+                        }
+                    }
                     _ => {
                         log::error!("unprocessable RPC feed message");
                     },
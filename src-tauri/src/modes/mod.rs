@@ -2,9 +2,11 @@ pub mod ble;
 pub mod chat;
 pub mod chatfile;
 pub mod cli;
+pub mod configuration;
 pub mod connections;
 pub mod debug;
 pub mod dtn;
+pub mod encryption;
 pub mod feed;
 pub mod group;
 pub mod node;
@@ -51,6 +51,7 @@ impl Rpc {
             request_id,
             user_id,
             data,
+            session_token: super::user_accounts::UserAccounts::get_session_token(),
         };
 
         // encode message
@@ -112,6 +113,12 @@ impl Rpc {
                     Ok(proto::Modules::Dtn) => {
                         super::dtn::Dtn::rpc(message.data);
                     }
+                    Ok(proto::Modules::Configuration) => {
+                        super::configuration::Configuration::rpc(message.data);
+                    }
+                    Ok(proto::Modules::Encryption) => {
+                        super::encryption::Encryption::rpc(message.data);
+                    }
                     Ok(proto::Modules::None) => {}
                     Err(_) => {}
                 }
@@ -187,6 +187,7 @@ impl Rtc {
                     group_id: group_id.clone(),
                     option: 1,
                     sdp_answer: "".to_string(),
+                    sdp_offer: "".to_string(),
                 },
             )),
         };
@@ -210,6 +211,7 @@ impl Rtc {
                     group_id: group_id.clone(),
                     option: 2,
                     sdp_answer: "".to_string(),
+                    sdp_offer: "".to_string(),
                 },
             )),
         };
@@ -233,6 +235,7 @@ impl Rtc {
                     group_id: group_id.clone(),
                     option: 3,
                     sdp_answer: "".to_string(),
+                    sdp_offer: "".to_string(),
                 },
             )),
         };
@@ -336,6 +339,32 @@ impl Rtc {
                                 "\ttype: {} , state: {} , created at: {}",
                                 session.session_type, session.state, session.created_at
                             );
+                            if !session.remote_candidates.is_empty() {
+                                println!(
+                                    "\tremote ICE candidates: {}",
+                                    session.remote_candidates.len()
+                                );
+                            }
+                            for recorder in session.recorders {
+                                println!(
+                                    "\trecorded by: {}",
+                                    bs58::encode(recorder).into_string()
+                                );
+                            }
+                            if !session.host_id.is_empty() {
+                                println!(
+                                    "\thost: {}",
+                                    bs58::encode(session.host_id).into_string()
+                                );
+                                for participant in session.participants {
+                                    println!(
+                                        "\t\tparticipant: {} , audio muted: {} , video muted: {}",
+                                        bs58::encode(participant.user_id).into_string(),
+                                        participant.audio_muted,
+                                        participant.video_muted
+                                    );
+                                }
+                            }
                         }
                     }
                     _ => {
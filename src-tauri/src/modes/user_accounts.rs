@@ -6,6 +6,7 @@
 use super::rpc::Rpc;
 use prost::Message;
 use state::InitCell;
+use std::collections::BTreeMap;
 use std::sync::RwLock;
 
 /// include generated protobuf RPC rust definition file
@@ -30,6 +31,8 @@ pub enum MyUserAccountInitialiation {
 pub struct UserAccounts {
     initialiation: MyUserAccountInitialiation,
     my_user_account: Option<proto::MyUserAccount>,
+    /// session tokens received from libqaul, by user id
+    session_tokens: BTreeMap<Vec<u8>, Vec<u8>>,
 }
 
 impl UserAccounts {
@@ -39,6 +42,7 @@ impl UserAccounts {
         let user_accounts = UserAccounts {
             initialiation: MyUserAccountInitialiation::Uninitialized,
             my_user_account: None,
+            session_tokens: BTreeMap::new(),
         };
         USERACCOUNTS.set(RwLock::new(user_accounts));
 
@@ -58,6 +62,19 @@ impl UserAccounts {
         None
     }
 
+    /// return the session token of the user account
+    pub fn get_session_token() -> Vec<u8> {
+        let user_accounts = USERACCOUNTS.get().read().unwrap();
+
+        if let Some(my_user_account) = &user_accounts.my_user_account {
+            if let Some(token) = user_accounts.session_tokens.get(&my_user_account.id) {
+                return token.clone();
+            }
+        }
+
+        Vec::new()
+    }
+
     /// CLI command interpretation
     ///
     /// The CLI commands of user accounts module are processed here
@@ -71,6 +88,83 @@ impl UserAccounts {
             cmd if cmd.starts_with("create ") => {
                 Self::parse_create_command(cmd.strip_prefix("create ").unwrap());
             }
+            // list all user accounts of this node
+            cmd if cmd.starts_with("list") => {
+                Self::send(proto::user_accounts::Message::GetUserAccounts(true));
+            }
+            // log in a user account
+            cmd if cmd.starts_with("login ") => {
+                let mut parts = cmd.strip_prefix("login ").unwrap().split_whitespace();
+                match parts.next().map(|id| bs58::decode(id).into_vec()) {
+                    Some(Ok(user_id)) => {
+                        Self::send(proto::user_accounts::Message::LoginUserAccount(
+                            proto::LoginUserAccount {
+                                user_id,
+                                passphrase: parts.next().map(|p| p.to_string()),
+                            },
+                        ));
+                    }
+                    _ => log::error!("invalid user id"),
+                }
+            }
+            // log out a user account
+            cmd if cmd.starts_with("logout ") => {
+                let mut parts = cmd.strip_prefix("logout ").unwrap().split_whitespace();
+                match parts.next().map(|id| bs58::decode(id).into_vec()) {
+                    Some(Ok(user_id)) => {
+                        Self::send(proto::user_accounts::Message::LogoutUserAccount(
+                            proto::LogoutUserAccount {
+                                user_id,
+                                passphrase: parts.next().map(|p| p.to_string()),
+                            },
+                        ));
+                    }
+                    _ => log::error!("invalid user id"),
+                }
+            }
+            // switch the current user account
+            cmd if cmd.starts_with("switch ") => {
+                let mut parts = cmd.strip_prefix("switch ").unwrap().split_whitespace();
+                match parts.next().map(|id| bs58::decode(id).into_vec()) {
+                    Some(Ok(user_id)) => {
+                        Self::send(proto::user_accounts::Message::SwitchUserAccount(
+                            proto::SwitchUserAccount {
+                                user_id,
+                                passphrase: parts.next().map(|p| p.to_string()),
+                            },
+                        ));
+                    }
+                    _ => log::error!("invalid user id"),
+                }
+            }
+            // set the passphrase of the current user account
+            // `-` stands for no passphrase
+            cmd if cmd.starts_with("passphrase ") => {
+                let parts: Vec<&str> = cmd
+                    .strip_prefix("passphrase ")
+                    .unwrap()
+                    .split_whitespace()
+                    .collect();
+                let user_id = match Self::get_user_id() {
+                    Some(user_id) => user_id,
+                    None => {
+                        log::error!("no user account selected");
+                        return;
+                    }
+                };
+                if parts.len() != 2 {
+                    log::error!("usage: account passphrase {{old|-}} {{new|-}}");
+                    return;
+                }
+                let optional = |p: &str| if p == "-" { None } else { Some(p.to_string()) };
+                Self::send(proto::user_accounts::Message::SetPassphrase(
+                    proto::SetPassphrase {
+                        user_id,
+                        old_passphrase: optional(parts[0]),
+                        new_passphrase: optional(parts[1]),
+                    },
+                ));
+            }
             // unknown command
             _ => log::error!("unknown account command"),
         }
@@ -91,6 +185,7 @@ impl UserAccounts {
 
         let mut reg_no = None;
         let mut college = None;
+        let mut passphrase = None;
 
         let mut i = 1;
         while i < parts.len() {
@@ -131,6 +226,15 @@ impl UserAccounts {
                         return;
                     }
                 }
+                "--passphrase" => {
+                    if i + 1 < parts.len() {
+                        passphrase = Some(parts[i + 1].to_string());
+                        i += 2;
+                    } else {
+                        log::error!("--passphrase requires a value");
+                        return;
+                    }
+                }
                 _ => {
                     log::error!("unknown option: {}", parts[i]);
                     return;
@@ -138,7 +242,7 @@ impl UserAccounts {
             }
         }
 
-        Self::create_user_account(name, profile_pic, about, reg_no, college);
+        Self::create_user_account(name, profile_pic, about, reg_no, college, passphrase);
     }
 
     /// Create new user account
@@ -148,6 +252,7 @@ impl UserAccounts {
         about: Option<String>,
         reg_no: Option<String>,
         college: Option<String>,
+        passphrase: Option<String>,
     ) {
         // create info request message
         let proto_message = proto::UserAccounts {
@@ -158,6 +263,7 @@ impl UserAccounts {
                     about,
                     reg_no,
                     college,
+                    passphrase,
                 },
             )),
         };
@@ -176,6 +282,20 @@ impl UserAccounts {
         );
     }
 
+    /// encode and send a user accounts message
+    fn send(message: proto::user_accounts::Message) {
+        let proto_message = proto::UserAccounts {
+            message: Some(message),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Useraccounts.into(),
+            "".to_string(),
+        );
+    }
+
     /// Request default user account
     fn request_default_account() {
         // create info request message
@@ -208,6 +328,9 @@ impl UserAccounts {
                     Some(proto::user_accounts::Message::DefaultUserAccount(
                         proto_defaultuseraccount,
                     )) => {
+                        // account without session token
+                        let mut login_id = None;
+
                         // get state
                         let mut user_accounts = USERACCOUNTS.get().write().unwrap();
 
@@ -234,6 +357,14 @@ impl UserAccounts {
                                     //println!("    registration number: {}", reg_no);
                                 }
 
+                                // log in to get a session token
+                                if !user_accounts
+                                    .session_tokens
+                                    .contains_key(&my_user_account.id)
+                                {
+                                    login_id = Some(my_user_account.id.clone());
+                                }
+
                                 // save it to state
                                 user_accounts.my_user_account = Some(my_user_account);
                                 user_accounts.initialiation =
@@ -243,9 +374,22 @@ impl UserAccounts {
                             }
                         } else {
                             // save it to state
+                            user_accounts.my_user_account = None;
                             user_accounts.initialiation =
                                 MyUserAccountInitialiation::NoDefaultAccount;
                         }
+                        drop(user_accounts);
+
+                        // accounts without passphrase log in without one,
+                        // protected accounts need to be logged in by the user
+                        if let Some(user_id) = login_id {
+                            Self::send(proto::user_accounts::Message::LoginUserAccount(
+                                proto::LoginUserAccount {
+                                    user_id,
+                                    passphrase: None,
+                                },
+                            ));
+                        }
                     }
                     Some(proto::user_accounts::Message::MyUserAccount(proto_myuseraccount)) => {
                         // get state
@@ -274,6 +418,37 @@ impl UserAccounts {
                         user_accounts.my_user_account = Some(proto_myuseraccount);
                         user_accounts.initialiation = MyUserAccountInitialiation::Initialized;
                     }
+                    Some(proto::user_accounts::Message::UserAccountList(list)) => {
+                        println!("User accounts on this node:");
+                        for entry in list.user_accounts {
+                            if let Some(account) = entry.my_user_account {
+                                let current = if account.id == list.current { "*" } else { " " };
+                                println!(
+                                    "{} {}, ID[{}] logged in: {} passphrase: {}",
+                                    current,
+                                    account.name,
+                                    account.id_base58,
+                                    entry.logged_in,
+                                    entry.has_passphrase
+                                );
+                            }
+                        }
+                    }
+                    Some(proto::user_accounts::Message::SessionResult(result)) => {
+                        if result.success {
+                            if !result.session_token.is_empty() {
+                                let mut user_accounts = USERACCOUNTS.get().write().unwrap();
+                                user_accounts
+                                    .session_tokens
+                                    .insert(result.user_id, result.session_token);
+                            }
+                            println!("Success");
+                            // the current user account may have changed
+                            Self::request_default_account();
+                        } else {
+                            println!("Failed: {}", result.message);
+                        }
+                    }
                     _ => {
                         log::error!("unprocessable RPC user accounts message");
                     }
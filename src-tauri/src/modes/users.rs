@@ -43,6 +43,21 @@ impl Users {
 
                 Self::send_user_update(user_id, false, true);
             }
+            // remove the verification of a user
+            cmd if cmd.starts_with("unverify ") => {
+                let user_id = cmd.strip_prefix("unverify ").unwrap();
+                Self::send_set_trust(user_id, false);
+            }
+            // verification QR code payload for a user
+            cmd if cmd.starts_with("qr ") => {
+                let user_id = cmd.strip_prefix("qr ").unwrap();
+                Self::send_verification_qr_request(user_id);
+            }
+            // verify a user with a scanned QR code payload
+            cmd if cmd.starts_with("verify-qr ") => {
+                let payload = cmd.strip_prefix("verify-qr ").unwrap();
+                Self::send_verify_qr(payload);
+            }
             // security number for a user
             cmd if cmd.starts_with("secure ") => {
                 let user_id = cmd.strip_prefix("secure ").unwrap();
@@ -138,6 +153,53 @@ impl Users {
         );
     }
 
+    /// create rpc verification QR code request
+    fn send_verification_qr_request(user_id_base58: &str) {
+        let user_id = bs58::decode(user_id_base58).into_vec().unwrap();
+
+        Self::send(proto::users::Message::VerificationQrRequest(
+            proto::VerificationQrRequest { user_id },
+        ));
+    }
+
+    /// send a scanned verification QR code payload
+    fn send_verify_qr(qr_payload: &str) {
+        Self::send(proto::users::Message::VerifyQrRequest(
+            proto::VerifyQrRequest {
+                qr_payload: qr_payload.trim().to_string(),
+            },
+        ));
+    }
+
+    /// set a user to verified or unverified
+    fn send_set_trust(user_id_base58: &str, verified: bool) {
+        let user_id = bs58::decode(user_id_base58).into_vec().unwrap();
+
+        Self::send(proto::users::Message::SetTrustRequest(
+            proto::SetTrustRequest { user_id, verified },
+        ));
+    }
+
+    /// encode and send a users rpc message
+    fn send(message: proto::users::Message) {
+        let proto_message = proto::Users {
+            message: Some(message),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            super::rpc::proto::Modules::Users.into(),
+            "".to_string(),
+        );
+    }
+
     /// create rpc user update message
     fn send_user_update(user_id_base58: &str, verified: bool, blocked: bool) {
         let user_id = bs58::decode(user_id_base58).into_vec().unwrap();
@@ -157,6 +219,7 @@ impl Users {
                 about: None,
                 reg_no: None,
                 college: None,
+                trust: 0,
             })),
         };
 
@@ -195,6 +258,7 @@ impl Users {
                 about: None,
                 reg_no: None,
                 college: None,
+                trust: 0,
             })),
         };
 
@@ -251,7 +315,9 @@ impl Users {
                     let mut line = 1;
                     println!("");
                     println!("All known Users");
-                    println!("No. | User Name | User Id | Veryfied | Blocked | Connectivity");
+                    println!(
+                        "No. | User Name | User Id | Veryfied | Blocked | Connectivity | Trust"
+                    );
                     println!("    | Group ID | Public Key");
 
                     for user in proto_userlist.user {
@@ -268,14 +334,18 @@ impl Users {
                         if user.connectivity == 1 {
                             onlined = "Online";
                         }
+                        let trust = proto::TrustLevel::try_from(user.trust)
+                            .unwrap_or(proto::TrustLevel::Unverified)
+                            .as_str_name();
                         println!(
-                            "{} | {} | {:?} | {} | {} | {}",
+                            "{} | {} | {:?} | {} | {} | {} | {}",
                             line,
                             user.name,
                             bs58::encode(user.id).into_string(),
                             verified,
                             blocked,
-                            onlined
+                            onlined,
+                            trust
                         );
                         let group_uuid;
                         match Uuid::from_slice(&user.group_id) {
@@ -333,6 +403,37 @@ impl Users {
                     }
                     println!("");
                 }
+                Some(proto::users::Message::VerificationQrResponse(resp)) => {
+                    println!(
+                        "Verification QR code for {}:",
+                        bs58::encode(resp.user_id).into_string()
+                    );
+                    println!("{}", resp.qr_payload);
+                }
+                Some(proto::users::Message::VerificationResult(result)) => {
+                    if result.success {
+                        let trust = proto::TrustLevel::try_from(result.trust)
+                            .unwrap_or(proto::TrustLevel::Unverified)
+                            .as_str_name();
+                        println!(
+                            "User {} trust level: {}",
+                            bs58::encode(result.user_id).into_string(),
+                            trust
+                        );
+                    } else {
+                        println!("Verification failed: {}", result.error_message);
+                    }
+                }
+                Some(proto::users::Message::KeyChanged(warning)) => {
+                    println!(
+                        "WARNING: the key of user {} changed, new user id {}",
+                        warning.name,
+                        bs58::encode(warning.user_id).into_string()
+                    );
+                    if warning.was_verified {
+                        println!("The previous key was verified, verify the user again.");
+                    }
+                }
                 Some(proto::users::Message::UserUpdate(user)) => {
                     let mut verified = "N";
                     let mut blocked = "N";
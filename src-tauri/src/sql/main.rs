@@ -101,6 +101,26 @@ pub fn get_conn() -> Result<Connection, DataError> {
     }
 }
 
+/// Use a database in a temporary directory for the tests
+#[cfg(test)]
+pub fn init_test_pool() {
+    static TEST_DIR: OnceCell<tempfile::TempDir> = OnceCell::new();
+
+    DB_POOL.get_or_init(|| {
+        let dir = TEST_DIR.get_or_init(|| tempfile::tempdir().unwrap());
+        let db_path = dir.path().join("sqlite.db");
+
+        unsafe {
+            sqlite3_auto_extension(Some(std::mem::transmute(sqlite3_vec_init as *const ())));
+        }
+        let setup_conn = Connection::open(&db_path).unwrap();
+        setup_database(&setup_conn, dir.path()).unwrap();
+        drop(setup_conn);
+
+        DbPool::new(&db_path, DB_POOL_SIZE).unwrap()
+    });
+}
+
 pub fn init(app: &App) -> Result<(), DataError> {
     if DB_POOL.get().is_some() {
         return Ok(());
@@ -252,6 +272,32 @@ fn setup_database(conn: &Connection, app_data_dir: &Path) -> Result<(), DataErro
         (),
     )?;
 
+    // Download queue and history (shared by all download sources)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS downloads (
+            id TEXT PRIMARY KEY NOT NULL,
+            source_kind TEXT NOT NULL,
+            source_id TEXT NOT NULL,
+            source TEXT NOT NULL,
+            file_name TEXT NOT NULL,
+            file_path TEXT NOT NULL,
+            status TEXT NOT NULL,
+            downloaded INTEGER NOT NULL DEFAULT 0,
+            total INTEGER,
+            checksum TEXT,
+            error TEXT,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            completed_at DATETIME
+        )",
+        (),
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS downloads_source ON downloads (source_kind, source_id)",
+        (),
+    )?;
+
     /* EMBEDDINGS SUPPORT
      * Note: There are no support for foreign keys in sqlite-vec
      * https://github.com/asg017/sqlite-vec/blob/a2dd24f27ec7e4a5743e58f5ab6835deea5db58d/site/features/vec0.md
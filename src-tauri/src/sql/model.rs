@@ -145,3 +145,23 @@ pub struct ToolData {
     pub description: String,
     pub enabled: bool,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DownloadData {
+    pub id: String,
+    pub source_kind: String, // "google_drive", "mega", "qaul", "web"
+    pub source_id: String,   // file ID, node handle or URL, depending on the source
+    pub source: String,      // JSON-encoded source, as needed to (re)start the download
+    pub file_name: String,
+    pub file_path: String,
+    pub status: String, // "queued", "downloading", "paused", "completed", "failed", "cancelled"
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    pub checksum: Option<String>, // JSON-encoded expected checksum
+    pub error: Option<String>,
+    #[serde(default = "chrono::Utc::now")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default = "chrono::Utc::now")]
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
@@ -0,0 +1,363 @@
+use crate::sql::{convert_date_str_to_utc, error::DataError, get_conn, model::DownloadData};
+use chrono::{DateTime, Utc};
+
+const DOWNLOAD_COLUMNS: &str = "
+    id,
+    source_kind,
+    source_id,
+    source,
+    file_name,
+    file_path,
+    status,
+    downloaded,
+    total,
+    checksum,
+    error,
+    created_at,
+    updated_at,
+    completed_at";
+
+fn row_to_download(row: &rusqlite::Row) -> Result<DownloadData, rusqlite::Error> {
+    let created_at_str: String = row.get(11)?;
+    let updated_at_str: String = row.get(12)?;
+    let completed_at_str: Option<String> = row.get(13)?;
+    let downloaded: i64 = row.get(7)?;
+    let total: Option<i64> = row.get(8)?;
+
+    Ok(DownloadData {
+        id: row.get(0)?,
+        source_kind: row.get(1)?,
+        source_id: row.get(2)?,
+        source: row.get(3)?,
+        file_name: row.get(4)?,
+        file_path: row.get(5)?,
+        status: row.get(6)?,
+        downloaded: downloaded as u64,
+        total: total.map(|t| t as u64),
+        checksum: row.get(9)?,
+        error: row.get(10)?,
+        created_at: convert_date_str_to_utc(&created_at_str)?,
+        updated_at: convert_date_str_to_utc(&updated_at_str)?,
+        completed_at: completed_at_str
+            .map(|s| convert_date_str_to_utc(&s))
+            .transpose()?,
+    })
+}
+
+/// Formats a date like SQLite's `datetime('now')`, so dates sort the same
+fn format_date(date: &DateTime<Utc>) -> String {
+    date.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Inserts a new download into the queue
+pub fn insert_download(download: &DownloadData) -> Result<(), DataError> {
+    let conn = get_conn()?;
+
+    conn.execute(
+        "INSERT INTO downloads (
+            id,
+            source_kind,
+            source_id,
+            source,
+            file_name,
+            file_path,
+            status,
+            downloaded,
+            total,
+            checksum,
+            created_at,
+            updated_at,
+            completed_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        rusqlite::params![
+            &download.id,
+            &download.source_kind,
+            &download.source_id,
+            &download.source,
+            &download.file_name,
+            &download.file_path,
+            &download.status,
+            download.downloaded as i64,
+            download.total.map(|t| t as i64),
+            &download.checksum,
+            format_date(&download.created_at),
+            format_date(&download.updated_at),
+            download.completed_at.as_ref().map(format_date),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Updates the status of a download, recording the error of failed ones
+pub fn update_download_status(
+    id: &str,
+    status: &str,
+    error: Option<&str>,
+) -> Result<(), DataError> {
+    let conn = get_conn()?;
+
+    conn.execute(
+        "UPDATE downloads
+         SET
+            status = ?2,
+            error = ?3,
+            updated_at = datetime('now'),
+            completed_at = CASE WHEN ?2 = 'completed' THEN datetime('now') ELSE completed_at END
+         WHERE id = ?1",
+        rusqlite::params![id, status, error],
+    )?;
+
+    Ok(())
+}
+
+/// Updates the amount of bytes downloaded so far
+pub fn update_download_progress(
+    id: &str,
+    downloaded: u64,
+    total: Option<u64>,
+) -> Result<(), DataError> {
+    let conn = get_conn()?;
+
+    conn.execute(
+        "UPDATE downloads
+         SET
+            downloaded = ?2,
+            total = COALESCE(?3, total),
+            updated_at = datetime('now')
+         WHERE id = ?1",
+        rusqlite::params![id, downloaded as i64, total.map(|t| t as i64)],
+    )?;
+
+    Ok(())
+}
+
+/// Get a download by id
+pub fn get_download(id: &str) -> Result<DownloadData, DataError> {
+    let conn = get_conn()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM downloads WHERE id = ?1",
+        DOWNLOAD_COLUMNS
+    ))?;
+
+    match stmt.query_row([id], row_to_download) {
+        Ok(download) => Ok(download),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err(DataError::NotFound),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Get the latest download of a file from the given source
+pub fn get_download_by_source(
+    source_kind: &str,
+    source_id: &str,
+) -> Result<Option<DownloadData>, DataError> {
+    let conn = get_conn()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM downloads
+         WHERE source_kind = ?1 AND source_id = ?2
+         ORDER BY created_at DESC
+         LIMIT 1",
+        DOWNLOAD_COLUMNS
+    ))?;
+
+    match stmt.query_row([source_kind, source_id], row_to_download) {
+        Ok(download) => Ok(Some(download)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Get all downloads with one of the given statuses, oldest first
+pub fn get_downloads_by_status(statuses: &[&str]) -> Result<Vec<DownloadData>, DataError> {
+    let conn = get_conn()?;
+
+    let placeholders = statuses
+        .iter()
+        .map(|_| "?".to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM downloads WHERE status IN ({}) ORDER BY created_at ASC",
+        DOWNLOAD_COLUMNS, placeholders
+    ))?;
+
+    let rows = stmt.query_map(rusqlite::params_from_iter(statuses), row_to_download)?;
+
+    let mut downloads = Vec::new();
+    for row in rows {
+        downloads.push(row?);
+    }
+
+    Ok(downloads)
+}
+
+/// Get the most recently updated downloads, across all sources
+pub fn get_recent_downloads(limit: u32) -> Result<Vec<DownloadData>, DataError> {
+    let conn = get_conn()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM downloads ORDER BY updated_at DESC LIMIT ?1",
+        DOWNLOAD_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map([limit], row_to_download)?;
+
+    let mut downloads = Vec::new();
+    for row in rows {
+        downloads.push(row?);
+    }
+
+    Ok(downloads)
+}
+
+/// Removes a download from the history
+pub fn delete_download(id: &str) -> Result<(), DataError> {
+    let conn = get_conn()?;
+
+    conn.execute("DELETE FROM downloads WHERE id = ?1", [id])?;
+
+    Ok(())
+}
+
+/// Removes all completed, failed and cancelled downloads from the history
+pub fn clear_finished_downloads() -> Result<(), DataError> {
+    let conn = get_conn()?;
+
+    conn.execute(
+        "DELETE FROM downloads WHERE status IN ('completed', 'failed', 'cancelled')",
+        (),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::main::init_test_pool;
+    use std::sync::{Mutex, MutexGuard};
+
+    /// the tests share one database, so they must not clear each others downloads
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    fn lock() -> MutexGuard<'static, ()> {
+        LOCK.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn queued_download(source_id: &str) -> DownloadData {
+        let now = Utc::now();
+        DownloadData {
+            id: uuid::Uuid::new_v4().to_string(),
+            source_kind: "web".to_string(),
+            source_id: source_id.to_string(),
+            source: format!(r#"{{"kind":"web","url":"{}"}}"#, source_id),
+            file_name: "notes.pdf".to_string(),
+            file_path: "/tmp/notes.pdf".to_string(),
+            status: "queued".to_string(),
+            downloaded: 0,
+            total: None,
+            checksum: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+            completed_at: None,
+        }
+    }
+
+    fn insert(source_id: &str) -> String {
+        init_test_pool();
+        let download = queued_download(source_id);
+        insert_download(&download).unwrap();
+        download.id
+    }
+
+    #[test]
+    fn test_completed_download() {
+        let _lock = lock();
+        let id = insert("https://example.com/completed.pdf");
+
+        let download = get_download(&id).unwrap();
+        assert_eq!(download.status, "queued");
+        assert!(download.completed_at.is_none());
+
+        update_download_status(&id, "downloading", None).unwrap();
+        update_download_progress(&id, 512, Some(1024)).unwrap();
+        let download = get_download(&id).unwrap();
+        assert_eq!(download.status, "downloading");
+        assert_eq!(download.downloaded, 512);
+        assert_eq!(download.total, Some(1024));
+
+        // the total is kept when it is not reported
+        update_download_progress(&id, 1024, None).unwrap();
+        update_download_status(&id, "completed", None).unwrap();
+        let download = get_download(&id).unwrap();
+        assert_eq!(download.status, "completed");
+        assert_eq!(download.downloaded, 1024);
+        assert_eq!(download.total, Some(1024));
+        assert!(download.completed_at.is_some());
+    }
+
+    #[test]
+    fn test_failed_download_is_resumed() {
+        let _lock = lock();
+        let id = insert("https://example.com/failed.pdf");
+
+        update_download_status(&id, "downloading", None).unwrap();
+        update_download_status(&id, "failed", Some("connection reset")).unwrap();
+        let download = get_download(&id).unwrap();
+        assert_eq!(download.status, "failed");
+        assert_eq!(download.error.as_deref(), Some("connection reset"));
+        assert!(download.completed_at.is_none());
+
+        // resuming clears the error
+        update_download_status(&id, "queued", None).unwrap();
+        let download = get_download(&id).unwrap();
+        assert_eq!(download.status, "queued");
+        assert!(download.error.is_none());
+    }
+
+    #[test]
+    fn test_downloads_by_status() {
+        let _lock = lock();
+        let queued = insert("https://example.com/queued.pdf");
+        let downloading = insert("https://example.com/downloading.pdf");
+        let paused = insert("https://example.com/paused.pdf");
+        update_download_status(&downloading, "downloading", None).unwrap();
+        update_download_status(&paused, "paused", None).unwrap();
+
+        let ids: Vec<String> = get_downloads_by_status(&["queued", "downloading"])
+            .unwrap()
+            .into_iter()
+            .map(|download| download.id)
+            .collect();
+        assert!(ids.contains(&queued));
+        assert!(ids.contains(&downloading));
+        assert!(!ids.contains(&paused));
+
+        let latest = get_download_by_source("web", "https://example.com/paused.pdf")
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.id, paused);
+        assert_eq!(latest.status, "paused");
+    }
+
+    #[test]
+    fn test_clear_finished_downloads() {
+        let _lock = lock();
+        let paused = insert("https://example.com/clear-paused.pdf");
+        let cancelled = insert("https://example.com/clear-cancelled.pdf");
+        update_download_status(&paused, "paused", None).unwrap();
+        update_download_status(&cancelled, "cancelled", None).unwrap();
+
+        clear_finished_downloads().unwrap();
+        assert_eq!(get_download(&paused).unwrap().status, "paused");
+        assert!(matches!(get_download(&cancelled), Err(DataError::NotFound)));
+
+        delete_download(&paused).unwrap();
+        assert!(matches!(get_download(&paused), Err(DataError::NotFound)));
+    }
+}
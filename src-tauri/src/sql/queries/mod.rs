@@ -1,12 +1,14 @@
 mod apps;
 mod chat;
 mod contextual_bandit;
+mod downloads;
 mod tools;
 mod user_intention;
 
 pub use apps::*;
 pub use chat::*;
 pub use contextual_bandit::*;
+pub use downloads::*;
 pub use tools::*;
 pub use user_intention::*;
 
@@ -4,7 +4,7 @@ use std::io::ErrorKind;
 use std::net::{SocketAddr, UdpSocket};
 use std::ops::Deref;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError};
 use std::sync::{Arc, Weak};
 use std::thread;
 use std::time::{Duration, Instant};
@@ -21,6 +21,117 @@ use str0m::{net::Receive, Candidate, Event, IceConnectionState, Input, Output, R
 use crate::str0m::util;
 use tauri::{Manager, path::BaseDirectory};
 
+/// Commands to the main run loop of the SFU
+pub enum SfuCommand {
+    /// Add a client, optionally bound to a qaul user id
+    Join(Rtc, Option<Vec<u8>>),
+    /// Disconnect the clients of a qaul user id
+    Leave(Vec<u8>),
+    /// Add a trickled remote ICE candidate to the clients of a qaul user id
+    Candidate(Vec<u8>, Candidate),
+    /// Disconnect all clients and stop the run loop
+    Stop,
+}
+
+/// SFU of a qaul group call hosted on this node
+///
+/// The SFU binds to the LAN address of this node, so that the
+/// other group members can connect via host candidates only,
+/// without any STUN or TURN server.
+pub struct SfuRoom {
+    tx: SyncSender<SfuCommand>,
+    addr: SocketAddr,
+}
+
+impl SfuRoom {
+    /// Bind a UDP socket and start the run loop of the SFU
+    pub fn spawn() -> Result<SfuRoom, String> {
+        let host_addr = util::select_lan_address();
+        let socket = UdpSocket::bind(format!("{host_addr}:0"))
+            .map_err(|e| format!("Failed to bind UDP socket: {}", e))?;
+        let addr = socket
+            .local_addr()
+            .map_err(|e| format!("Failed to get local address: {}", e))?;
+        info!("SFU bound UDP port: {}", addr);
+
+        let (tx, rx) = mpsc::sync_channel(10);
+        thread::spawn(move || run(socket, rx));
+
+        Ok(SfuRoom { tx, addr })
+    }
+
+    /// Add a participant and return the SDP answer as JSON
+    ///
+    /// A former connection of the same user is replaced.
+    pub fn join(&self, user_id: Vec<u8>, offer: &str) -> Result<String, String> {
+        let offer: SdpOffer = match serde_json::from_str(offer) {
+            Ok(offer) => offer,
+            Err(_) => SdpOffer::from_sdp_string(offer)
+                .map_err(|e| format!("Failed to parse SDP offer: {:?}", e))?,
+        };
+        let (rtc, answer) = accept_offer(self.addr, offer)?;
+
+        self.command(SfuCommand::Leave(user_id.clone()))?;
+        self.command(SfuCommand::Join(rtc, Some(user_id)))?;
+
+        serde_json::to_string(&answer).map_err(|e| format!("Failed to serialize SDP answer: {}", e))
+    }
+
+    /// Disconnect a participant
+    pub fn leave(&self, user_id: Vec<u8>) {
+        if let Err(e) = self.command(SfuCommand::Leave(user_id)) {
+            warn!("{}", e);
+        }
+    }
+
+    /// Add a trickled ICE candidate of a participant
+    pub fn add_candidate(&self, user_id: Vec<u8>, candidate: &str) -> Result<(), String> {
+        let candidate = Candidate::from_sdp_string(candidate)
+            .map_err(|e| format!("Failed to parse ICE candidate: {:?}", e))?;
+        self.command(SfuCommand::Candidate(user_id, candidate))
+    }
+
+    /// Disconnect all participants and stop the SFU
+    pub fn stop(&self) {
+        let _ = self.tx.send(SfuCommand::Stop);
+    }
+
+    /// Pass a command to the run loop without blocking the caller
+    fn command(&self, command: SfuCommand) -> Result<(), String> {
+        self.tx.try_send(command).map_err(|e| match e {
+            TrySendError::Full(_) => "SFU is busy, try again later".to_string(),
+            TrySendError::Disconnected(_) => "SFU is not running".to_string(),
+        })
+    }
+}
+
+/// Create an Rtc instance for an SDP offer with a host candidate
+fn accept_offer(addr: SocketAddr, offer: SdpOffer) -> Result<(Rtc, SdpAnswer), String> {
+    let mut rtc = Rtc::builder()
+        // Uncomment this to see statistics
+        // .set_stats_interval(Some(Duration::from_secs(1)))
+        // .set_ice_lite(true)
+        .build();
+
+    // Add multiple ICE candidates for better connectivity
+    // Add host candidate
+    let host_candidate = Candidate::host(addr, "udp")
+        .map_err(|e| format!("Failed to create ICE candidate: {:?}", e))?;
+    rtc.add_local_candidate(host_candidate);
+
+    // Try to add srflx candidates (server reflexive)
+    // Note: In a production environment, you would want to add STUN server candidates here
+    info!("Added local ICE candidate: {}", addr);
+
+    // Create an SDP Answer.
+    let answer = rtc
+        .sdp_api()
+        .accept_offer(offer)
+        .map_err(|e| format!("Failed to accept offer: {:?}", e))?;
+
+    Ok((rtc, answer))
+}
+
 fn load_existing_cert(app: &tauri::AppHandle) -> Result<(Vec<u8>, Vec<u8>), Box<dyn std::error::Error>> {
     // Try to load certificate files from resources first
     let cert_result = app.path().resolve("cer.pem", BaseDirectory::Resource);
@@ -168,7 +279,7 @@ pub fn main(app: &tauri::AppHandle) {
 }
 
 // Handle a web request.
-fn web_request(request: &Request, addr: SocketAddr, tx: SyncSender<Rtc>) -> Response {
+fn web_request(request: &Request, addr: SocketAddr, tx: SyncSender<SfuCommand>) -> Response {
     if request.method() == "GET" {
         return Response::html(include_str!("chat.html"));
     }
@@ -177,29 +288,10 @@ fn web_request(request: &Request, addr: SocketAddr, tx: SyncSender<Rtc>) -> Resp
     let mut data = request.data().expect("body to be available");
 
     let offer: SdpOffer = serde_json::from_reader(&mut data).expect("serialized offer");
-    let mut rtc = Rtc::builder()
-        // Uncomment this to see statistics
-        // .set_stats_interval(Some(Duration::from_secs(1)))
-        // .set_ice_lite(true)
-        .build();
-
-    // Add multiple ICE candidates for better connectivity
-    // Add host candidate
-    let host_candidate = Candidate::host(addr, "udp").expect("a host candidate");
-    rtc.add_local_candidate(host_candidate);
-    
-    // Try to add srflx candidates (server reflexive)
-    // Note: In a production environment, you would want to add STUN server candidates here
-    info!("Added local ICE candidate: {}", addr);
-
-    // Create an SDP Answer.
-    let answer = rtc
-        .sdp_api()
-        .accept_offer(offer)
-        .expect("offer to be accepted");
+    let (rtc, answer) = accept_offer(addr, offer).expect("offer to be accepted");
 
     // The Rtc instance is shipped off to the main run loop.
-    if let Err(e) = tx.send(rtc) {
+    if let Err(e) = tx.send(SfuCommand::Join(rtc, None)) {
         warn!("Failed to send Rtc instance to main loop: {:?}", e);
         warn!("Channel might be full or disconnected. This usually happens when the server is overloaded.");
         return Response::text("Server error: unable to join room. The server might be busy, please try again.").with_status_code(500);
@@ -212,7 +304,7 @@ fn web_request(request: &Request, addr: SocketAddr, tx: SyncSender<Rtc>) -> Resp
 
 /// This is the "main run loop" that handles all clients, reads and writes UdpSocket traffic,
 /// and forwards media data between clients.
-fn run(socket: UdpSocket, rx: Receiver<Rtc>) -> Result<(), RtcError> {
+fn run(socket: UdpSocket, rx: Receiver<SfuCommand>) -> Result<(), RtcError> {
     let mut clients: Vec<Client> = vec![];
     let mut to_propagate: VecDeque<Propagated> = VecDeque::new();
     let mut buf = vec![0; 2000];
@@ -222,14 +314,36 @@ fn run(socket: UdpSocket, rx: Receiver<Rtc>) -> Result<(), RtcError> {
         clients.retain(|c| c.rtc.is_alive());
 
         // Spawn new incoming clients from the web server thread.
-        if let Some(mut client) = spawn_new_client(&rx) {
-            // Add incoming tracks present in other already connected clients.
-            for track in clients.iter().flat_map(|c| c.tracks_in.iter()) {
-                let weak = Arc::downgrade(&track.id);
-                client.handle_track_open(weak);
-            }
+        match receive_command(&rx) {
+            Some(SfuCommand::Join(rtc, user)) => {
+                let mut client = Client::new(rtc, user);
+
+                // Add incoming tracks present in other already connected clients.
+                for track in clients.iter().flat_map(|c| c.tracks_in.iter()) {
+                    let weak = Arc::downgrade(&track.id);
+                    client.handle_track_open(weak);
+                }
 
-            clients.push(client);
+                clients.push(client);
+            }
+            Some(SfuCommand::Leave(user)) => {
+                for client in clients.iter_mut().filter(|c| c.user.as_ref() == Some(&user)) {
+                    client.rtc.disconnect();
+                }
+            }
+            Some(SfuCommand::Candidate(user, candidate)) => {
+                for client in clients.iter_mut().filter(|c| c.user.as_ref() == Some(&user)) {
+                    client.rtc.add_remote_candidate(candidate.clone());
+                }
+            }
+            Some(SfuCommand::Stop) => {
+                for client in clients.iter_mut() {
+                    client.rtc.disconnect();
+                }
+                info!("SFU stopped");
+                return Ok(());
+            }
+            None => {}
         }
 
         // Poll clients until they return timeout
@@ -273,15 +387,17 @@ fn run(socket: UdpSocket, rx: Receiver<Rtc>) -> Result<(), RtcError> {
     }
 }
 
-/// Receive new clients from the receiver and create new Client instances.
-fn spawn_new_client(rx: &Receiver<Rtc>) -> Option<Client> {
+/// Receive the next command for the run loop.
+///
+/// When all senders are dropped, the run loop is stopped.
+fn receive_command(rx: &Receiver<SfuCommand>) -> Option<SfuCommand> {
     // try_recv here won't lock up the thread.
     match rx.try_recv() {
-        Ok(rtc) => Some(Client::new(rtc)),
+        Ok(command) => Some(command),
         Err(TryRecvError::Empty) => None,
         Err(TryRecvError::Disconnected) => {
-            debug!("Receiver<Rtc> disconnected - no more incoming connections");
-            None
+            debug!("Receiver<SfuCommand> disconnected - no more incoming connections");
+            Some(SfuCommand::Stop)
         }
     }
 }
@@ -376,6 +492,8 @@ fn read_socket_input<'a>(socket: &UdpSocket, buf: &'a mut Vec<u8>) -> Option<Inp
 #[derive(Debug)]
 struct Client {
     id: ClientId,
+    /// qaul user id of a group call participant
+    user: Option<Vec<u8>>,
     rtc: Rtc,
     pending: Option<SdpPendingOffer>,
     cid: Option<ChannelId>,
@@ -431,11 +549,12 @@ impl TrackOut {
 }
 
 impl Client {
-    fn new(rtc: Rtc) -> Client {
+    fn new(rtc: Rtc, user: Option<Vec<u8>>) -> Client {
         static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
         let next_id = ID_COUNTER.fetch_add(1, Ordering::SeqCst);
         Client {
             id: ClientId(next_id),
+            user,
             rtc,
             pending: None,
             cid: None,
@@ -0,0 +1,265 @@
+//! ICE candidate gathering
+//!
+//! Host candidates are gathered from all network interfaces,
+//! including IPv6. Server reflexive candidates are requested
+//! from a STUN server with a minimal STUN binding client (RFC 5389).
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+/// STUN magic cookie
+const MAGIC_COOKIE: u32 = 0x2112_A442;
+/// STUN binding request message type
+const BINDING_REQUEST: u16 = 0x0001;
+/// STUN binding success response message type
+const BINDING_RESPONSE: u16 = 0x0101;
+/// STUN MAPPED-ADDRESS attribute
+const ATTR_MAPPED_ADDRESS: u16 = 0x0001;
+/// STUN XOR-MAPPED-ADDRESS attribute
+const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+
+/// Get the addresses of all network interfaces usable for ICE
+///
+/// Loopback and IPv6 link local addresses are skipped, since
+/// they are not reachable by other nodes.
+/// Falls back to the host address if there is no network.
+pub fn interface_addresses() -> Vec<IpAddr> {
+    let mut addresses: Vec<IpAddr> = match get_if_addrs::get_if_addrs() {
+        Ok(interfaces) => interfaces
+            .iter()
+            .filter(|interface| !interface.is_loopback())
+            .map(|interface| interface.ip())
+            .filter(|ip| match ip {
+                IpAddr::V4(ip) => !ip.is_unspecified() && !ip.is_link_local(),
+                IpAddr::V6(ip) => !ip.is_unspecified() && (ip.segments()[0] & 0xffc0) != 0xfe80,
+            })
+            .collect(),
+        Err(e) => {
+            log::warn!("Failed to list network interfaces: {}", e);
+            Vec::new()
+        }
+    };
+    addresses.sort();
+    addresses.dedup();
+
+    if addresses.is_empty() {
+        if let Ok(ip) = super::util::select_host_address().parse() {
+            addresses.push(ip);
+        }
+    }
+
+    addresses
+}
+
+/// Request our server reflexive address from a STUN server
+///
+/// The request is sent from the given socket, so that the mapped
+/// address belongs to the socket's NAT binding.
+pub fn stun_binding(socket: &UdpSocket, server: &str, timeout: Duration) -> io::Result<SocketAddr> {
+    let local_addr = socket.local_addr()?;
+    let server_addr = server
+        .to_socket_addrs()?
+        .find(|addr| addr.is_ipv4() == local_addr.is_ipv4())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::AddrNotAvailable,
+                "no STUN server address of the socket's family",
+            )
+        })?;
+
+    let transaction_id: [u8; 12] = rand::random();
+    let request = encode_binding_request(&transaction_id);
+
+    let former_timeout = socket.read_timeout()?;
+    socket.set_read_timeout(Some(timeout))?;
+
+    let result = (|| {
+        socket.send_to(&request, server_addr)?;
+
+        let mut buf = [0u8; 1024];
+        loop {
+            let (n, source) = socket.recv_from(&mut buf)?;
+            if source != server_addr {
+                continue;
+            }
+            if let Some(addr) = parse_binding_response(&buf[..n], &transaction_id) {
+                return Ok(addr);
+            }
+        }
+    })();
+
+    socket.set_read_timeout(former_timeout)?;
+    result
+}
+
+/// Encode a STUN binding request without attributes
+pub fn encode_binding_request(transaction_id: &[u8; 12]) -> Vec<u8> {
+    let mut request = Vec::with_capacity(20);
+    request.extend_from_slice(&BINDING_REQUEST.to_be_bytes());
+    request.extend_from_slice(&0u16.to_be_bytes());
+    request.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+    request.extend_from_slice(transaction_id);
+    request
+}
+
+/// Parse the mapped address of a STUN binding success response
+///
+/// Returns `None` if the message is not a response to our request.
+pub fn parse_binding_response(message: &[u8], transaction_id: &[u8; 12]) -> Option<SocketAddr> {
+    if message.len() < 20
+        || u16::from_be_bytes([message[0], message[1]]) != BINDING_RESPONSE
+        || u32::from_be_bytes([message[4], message[5], message[6], message[7]]) != MAGIC_COOKIE
+        || &message[8..20] != transaction_id
+    {
+        return None;
+    }
+
+    let length = u16::from_be_bytes([message[2], message[3]]) as usize;
+    let attributes = message.get(20..20 + length)?;
+
+    let mut mapped = None;
+    let mut offset = 0;
+    while offset + 4 <= attributes.len() {
+        let attr_type = u16::from_be_bytes([attributes[offset], attributes[offset + 1]]);
+        let attr_length =
+            u16::from_be_bytes([attributes[offset + 2], attributes[offset + 3]]) as usize;
+        let value = attributes.get(offset + 4..offset + 4 + attr_length)?;
+
+        match attr_type {
+            ATTR_XOR_MAPPED_ADDRESS => return parse_address(value, Some(transaction_id)),
+            ATTR_MAPPED_ADDRESS => mapped = parse_address(value, None),
+            _ => {}
+        }
+
+        // attributes are padded to 4 bytes
+        offset += 4 + (attr_length + 3) / 4 * 4;
+    }
+
+    mapped
+}
+
+/// Parse a (XOR-)MAPPED-ADDRESS attribute value
+fn parse_address(value: &[u8], xor_transaction_id: Option<&[u8; 12]>) -> Option<SocketAddr> {
+    if value.len() < 4 {
+        return None;
+    }
+    let cookie = MAGIC_COOKIE.to_be_bytes();
+    let mut port = u16::from_be_bytes([value[2], value[3]]);
+    if xor_transaction_id.is_some() {
+        port ^= (MAGIC_COOKIE >> 16) as u16;
+    }
+
+    let ip = match value[1] {
+        0x01 => {
+            let mut octets: [u8; 4] = value.get(4..8)?.try_into().ok()?;
+            if xor_transaction_id.is_some() {
+                for (octet, key) in octets.iter_mut().zip(cookie.iter()) {
+                    *octet ^= key;
+                }
+            }
+            IpAddr::V4(Ipv4Addr::from(octets))
+        }
+        0x02 => {
+            let mut octets: [u8; 16] = value.get(4..20)?.try_into().ok()?;
+            if let Some(transaction_id) = xor_transaction_id {
+                let key = cookie.iter().chain(transaction_id.iter());
+                for (octet, key) in octets.iter_mut().zip(key) {
+                    *octet ^= key;
+                }
+            }
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Get the ICE username fragment of an SDP
+///
+/// Works for plain SDP strings and for JSON serialized
+/// str0m `SdpOffer` and `SdpAnswer` structures.
+pub fn ice_ufrag(sdp: &str) -> Option<String> {
+    libqaul::services::rtc::rtc_ice::RtcIce::ice_ufrag(sdp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(transaction_id: &[u8; 12], attr_type: u16, value: &[u8]) -> Vec<u8> {
+        let mut message = Vec::new();
+        message.extend_from_slice(&BINDING_RESPONSE.to_be_bytes());
+        message.extend_from_slice(&((4 + value.len()) as u16).to_be_bytes());
+        message.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        message.extend_from_slice(transaction_id);
+        message.extend_from_slice(&attr_type.to_be_bytes());
+        message.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        message.extend_from_slice(value);
+        message
+    }
+
+    #[test]
+    fn test_binding_request() {
+        let request = encode_binding_request(&[7; 12]);
+        assert_eq!(request.len(), 20);
+        assert_eq!(&request[0..4], &[0x00, 0x01, 0x00, 0x00]);
+        assert_eq!(&request[4..8], &MAGIC_COOKIE.to_be_bytes());
+    }
+
+    #[test]
+    fn test_xor_mapped_ipv4() {
+        let transaction_id = [1; 12];
+        // 192.0.2.1:32853 xored with the magic cookie
+        let value = [0x00, 0x01, 0xa1, 0x47, 0xe1, 0x12, 0xa6, 0x43];
+        let message = response(&transaction_id, ATTR_XOR_MAPPED_ADDRESS, &value);
+
+        assert_eq!(
+            parse_binding_response(&message, &transaction_id),
+            Some("192.0.2.1:32853".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_xor_mapped_ipv6() {
+        let transaction_id = [0x42; 12];
+        let addr: SocketAddr = "[2001:db8::1]:5000".parse().unwrap();
+        let IpAddr::V6(ip) = addr.ip() else {
+            unreachable!()
+        };
+
+        let mut value = vec![0x00, 0x02];
+        value.extend_from_slice(&(addr.port() ^ (MAGIC_COOKIE >> 16) as u16).to_be_bytes());
+        let key: Vec<u8> = MAGIC_COOKIE
+            .to_be_bytes()
+            .iter()
+            .chain(transaction_id.iter())
+            .copied()
+            .collect();
+        value.extend(ip.octets().iter().zip(key.iter()).map(|(a, b)| a ^ b));
+        let message = response(&transaction_id, ATTR_XOR_MAPPED_ADDRESS, &value);
+
+        assert_eq!(
+            parse_binding_response(&message, &transaction_id),
+            Some(addr)
+        );
+    }
+
+    #[test]
+    fn test_response_of_other_transaction() {
+        let value = [0x00, 0x01, 0xa1, 0x47, 0xe1, 0x12, 0xa6, 0x43];
+        let message = response(&[1; 12], ATTR_XOR_MAPPED_ADDRESS, &value);
+
+        assert_eq!(parse_binding_response(&message, &[2; 12]), None);
+    }
+
+    #[test]
+    fn test_ice_ufrag() {
+        let sdp = "v=0\r\na=ice-ufrag:abcd\r\na=ice-pwd:secret\r\n";
+        assert_eq!(ice_ufrag(sdp), Some("abcd".to_string()));
+
+        let json = serde_json::json!({"type": "offer", "sdp": sdp}).to_string();
+        assert_eq!(ice_ufrag(&json), Some("abcd".to_string()));
+    }
+}
@@ -1,2 +1,4 @@
 pub mod chat;
+pub mod ice;
+pub mod share;
 pub mod util;
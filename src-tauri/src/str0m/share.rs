@@ -0,0 +1,879 @@
+//! Screen and file shares over str0m data channels
+//!
+//! A share is a WebRTC connection offered by the sharing node,
+//! next to the call itself. It is signaled via qaul, see
+//! `libqaul::services::rtc::rtc_share`.
+//!
+//! The screen share is not a WebRTC video track. There is no
+//! VP8 or H.264 encoder in our dependencies, therefore the screen
+//! is shared as a stream of JPEG frames over the `screen` data
+//! channel, and the frontend draws them onto a canvas. This keeps
+//! the share to a few frames per second, which is enough for slides
+//! and documents, but not for video playback.
+//!
+//! Every frame is split into binary messages of at most
+//! `CHUNK_SIZE` bytes with a 12 byte header:
+//!
+//! | bytes | content                       |
+//! |-------|-------------------------------|
+//! | 0..4  | frame number (big endian)     |
+//! | 4..6  | chunk index (big endian)      |
+//! | 6..8  | chunk count (big endian)      |
+//! | 8..10 | frame width (big endian)      |
+//! | 10..12| frame height (big endian)     |
+//!
+//! Unchanged frames are skipped, and frames are dropped while the
+//! channel is congested, so that the other side always gets the
+//! latest picture.
+//!
+//! Files are streamed over the `files` data channel, one after the
+//! other. A file starts with the text message
+//! `{"type":"file-start","transfer_id":1,"name":"notes.pdf","size":1234}`,
+//! continues with binary chunks and ends with the text message
+//! `{"type":"file-end","transfer_id":1}`.
+//! Files, which could not be sent completely when the connection
+//! ends, are reported back to be sent via the chat file service.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{ErrorKind, Read};
+use std::net::{SocketAddr, UdpSocket};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use screenshots::Screen;
+use str0m::change::{SdpAnswer, SdpPendingOffer};
+use str0m::channel::ChannelId;
+use str0m::net::{Protocol, Receive};
+use str0m::{Candidate, Event, IceConnectionState, Input, Output, Rtc};
+use tracing::{debug, info, warn};
+
+use crate::str0m::ice;
+
+/// label of the screen data channel
+pub const SCREEN_CHANNEL: &str = "screen";
+/// label of the files data channel
+pub const FILES_CHANNEL: &str = "files";
+
+/// maximal size of a data channel message
+const CHUNK_SIZE: usize = 16 * 1024;
+/// header size of a screen frame chunk
+const FRAME_HEADER_SIZE: usize = 12;
+/// file streaming pauses and frames are dropped above this buffered amount
+const MAX_BUFFERED: usize = 1024 * 1024;
+/// maximal number of file chunks written per loop iteration
+const MAX_CHUNKS_PER_ITERATION: usize = 64;
+/// time for the other side to connect
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// a file share without files is closed after this time
+const FILES_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+/// progress is reported every this many bytes
+const PROGRESS_INTERVAL: u64 = 256 * 1024;
+/// maximal width of screen frames, larger screens are scaled down
+const MAX_FRAME_WIDTH: u32 = 1280;
+/// unchanged frames are resent after this time
+const FRAME_REFRESH: Duration = Duration::from_secs(2);
+
+/// Source of a screen share
+#[derive(Debug, Clone)]
+pub enum ScreenSource {
+    /// screen by its index in `Screen::all()`
+    Screen(usize),
+    /// the window in the foreground
+    ActiveWindow,
+}
+
+/// Options of a screen share
+#[derive(Debug, Clone)]
+pub struct ScreenOptions {
+    pub source: ScreenSource,
+    /// frames per second
+    pub fps: u32,
+    /// JPEG quality 1-100
+    pub quality: u8,
+}
+
+/// Kind of a share
+pub enum ShareKind {
+    Screen(ScreenOptions),
+    Files,
+}
+
+/// Progress of a file transfer
+#[derive(Debug, Clone)]
+pub struct FileProgress {
+    pub path: PathBuf,
+    pub sent: u64,
+    pub size: u64,
+}
+
+/// Callbacks of a share
+pub struct ShareCallbacks {
+    /// progress of the file transfers
+    pub on_progress: Box<dyn Fn(FileProgress) + Send>,
+    /// the share ended, with the files that were not sent completely
+    pub on_end: Box<dyn FnOnce(Vec<PathBuf>) + Send>,
+}
+
+/// Inputs of the run loop of a share
+enum ShareInput {
+    Packet {
+        source: SocketAddr,
+        destination: SocketAddr,
+        data: Vec<u8>,
+    },
+    Answer(SdpAnswer),
+    SendFile(PathBuf),
+    Stop,
+}
+
+/// Screen or file share connection
+pub struct ShareSession {
+    tx: Sender<ShareInput>,
+}
+
+impl ShareSession {
+    /// Create the share connection and return it with its SDP offer as JSON
+    ///
+    /// The connection is established, when the answer of the
+    /// other side is passed via `answer`.
+    pub fn offer(
+        kind: ShareKind,
+        callbacks: ShareCallbacks,
+    ) -> Result<(ShareSession, String), String> {
+        let mut sockets = Vec::new();
+        for ip in ice::interface_addresses() {
+            match UdpSocket::bind(SocketAddr::new(ip, 0)).and_then(|socket| {
+                let local_addr = socket.local_addr()?;
+                Ok((socket, local_addr))
+            }) {
+                Ok(socket) => sockets.push(socket),
+                Err(e) => warn!("Failed to bind UDP socket on {}: {}", ip, e),
+            }
+        }
+        if sockets.is_empty() {
+            return Err("Failed to bind UDP socket on any network interface".to_string());
+        }
+
+        let mut rtc = Rtc::builder().build();
+        for (_, addr) in &sockets {
+            let candidate = Candidate::host(*addr, "udp")
+                .map_err(|e| format!("Failed to create ICE candidate: {:?}", e))?;
+            rtc.add_local_candidate(candidate);
+        }
+
+        let mut change = rtc.sdp_api();
+        let (screen, files) = match kind {
+            ShareKind::Screen(options) => (
+                Some((change.add_channel(SCREEN_CHANNEL.to_string()), options)),
+                None,
+            ),
+            ShareKind::Files => (None, Some(change.add_channel(FILES_CHANNEL.to_string()))),
+        };
+        let (offer, pending) = change.apply().ok_or("Failed to apply SDP changes")?;
+        let offer_json = serde_json::to_string(&offer)
+            .map_err(|e| format!("Failed to serialize SDP offer: {}", e))?;
+
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        for (socket, local_addr) in &sockets {
+            let socket = socket
+                .try_clone()
+                .map_err(|e| format!("Failed to clone UDP socket: {}", e))?;
+            spawn_reader(socket, *local_addr, tx.clone(), stop.clone());
+        }
+
+        let share = ShareLoop {
+            rtc,
+            pending: Some(pending),
+            sockets,
+            screen,
+            screen_open: false,
+            frames: None,
+            frame_number: 0,
+            files_channel: files,
+            files_open: false,
+            files: VecDeque::new(),
+            current: None,
+            transfer_id: 0,
+            idle_since: Instant::now(),
+            stop,
+        };
+        thread::spawn(move || share.run(rx, callbacks));
+
+        Ok((ShareSession { tx }, offer_json))
+    }
+
+    /// Pass the SDP answer of the other side
+    pub fn answer(&self, sdp_answer: &str) -> Result<(), String> {
+        let answer: SdpAnswer = match serde_json::from_str(sdp_answer) {
+            Ok(answer) => answer,
+            Err(_) => SdpAnswer::from_sdp_string(sdp_answer)
+                .map_err(|e| format!("Failed to parse SDP answer: {:?}", e))?,
+        };
+        self.tx
+            .send(ShareInput::Answer(answer))
+            .map_err(|_| "share has ended".to_string())
+    }
+
+    /// Queue a file to be streamed
+    pub fn send_file(&self, path: PathBuf) -> Result<(), PathBuf> {
+        self.tx
+            .send(ShareInput::SendFile(path))
+            .map_err(|e| match e.0 {
+                ShareInput::SendFile(path) => path,
+                _ => unreachable!(),
+            })
+    }
+
+    /// Stop the share
+    pub fn stop(&self) {
+        let _ = self.tx.send(ShareInput::Stop);
+    }
+}
+
+/// Receive the UDP packets of a socket for the run loop
+fn spawn_reader(
+    socket: UdpSocket,
+    local_addr: SocketAddr,
+    tx: Sender<ShareInput>,
+    stop: Arc<AtomicBool>,
+) {
+    thread::spawn(move || {
+        if let Err(e) = socket.set_read_timeout(Some(Duration::from_millis(500))) {
+            warn!("Failed to set socket read timeout: {}", e);
+            return;
+        }
+
+        let mut buf = vec![0; 2000];
+        while !stop.load(Ordering::Relaxed) {
+            match socket.recv_from(&mut buf) {
+                Ok((n, source)) => {
+                    let packet = ShareInput::Packet {
+                        source,
+                        destination: local_addr,
+                        data: buf[..n].to_vec(),
+                    };
+                    if tx.send(packet).is_err() {
+                        return;
+                    }
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(e) => {
+                    warn!("UdpSocket read failed: {:?}", e);
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// JPEG encoded screen frame
+struct Frame {
+    width: u16,
+    height: u16,
+    data: Vec<u8>,
+}
+
+/// File being streamed
+struct FileTransfer {
+    path: PathBuf,
+    file: File,
+    transfer_id: u32,
+    size: u64,
+    sent: u64,
+    reported: u64,
+}
+
+impl FileTransfer {
+    /// current progress of the transfer
+    fn progress(&self) -> FileProgress {
+        FileProgress {
+            path: self.path.clone(),
+            sent: self.sent,
+            size: self.size,
+        }
+    }
+
+    /// count sent bytes
+    ///
+    /// Returns the progress to report, every `PROGRESS_INTERVAL` bytes.
+    fn add_sent(&mut self, n: usize) -> Option<FileProgress> {
+        self.sent += n as u64;
+        if self.sent - self.reported < PROGRESS_INTERVAL {
+            return None;
+        }
+        self.reported = self.sent;
+        Some(self.progress())
+    }
+}
+
+/// Split a frame into data channel messages with the frame header
+fn frame_messages(frame_number: u32, frame: &Frame) -> Vec<Vec<u8>> {
+    let chunks: Vec<&[u8]> = frame.data.chunks(CHUNK_SIZE - FRAME_HEADER_SIZE).collect();
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut message = Vec::with_capacity(FRAME_HEADER_SIZE + chunk.len());
+            message.extend_from_slice(&frame_number.to_be_bytes());
+            message.extend_from_slice(&(index as u16).to_be_bytes());
+            message.extend_from_slice(&(chunks.len() as u16).to_be_bytes());
+            message.extend_from_slice(&frame.width.to_be_bytes());
+            message.extend_from_slice(&frame.height.to_be_bytes());
+            message.extend_from_slice(chunk);
+            message
+        })
+        .collect()
+}
+
+/// text message announcing a file
+fn file_start_message(transfer_id: u32, name: &str, size: u64) -> String {
+    serde_json::json!({
+        "type": "file-start",
+        "transfer_id": transfer_id,
+        "name": name,
+        "size": size,
+    })
+    .to_string()
+}
+
+/// text message completing a file
+fn file_end_message(transfer_id: u32) -> String {
+    serde_json::json!({
+        "type": "file-end",
+        "transfer_id": transfer_id,
+    })
+    .to_string()
+}
+
+/// Collect the files which were not sent completely
+///
+/// These are the file being streamed, the queued files and
+/// the files which were still waiting in the input channel.
+fn collect_unsent(
+    current: Option<FileTransfer>,
+    files: &mut VecDeque<PathBuf>,
+    rx: &Receiver<ShareInput>,
+) -> Vec<PathBuf> {
+    let mut unsent: Vec<PathBuf> = current.map(|transfer| transfer.path).into_iter().collect();
+    unsent.extend(files.drain(..));
+    while let Ok(input) = rx.try_recv() {
+        if let ShareInput::SendFile(path) = input {
+            unsent.push(path);
+        }
+    }
+    unsent
+}
+
+/// Run loop state of a share
+struct ShareLoop {
+    rtc: Rtc,
+    pending: Option<SdpPendingOffer>,
+    sockets: Vec<(UdpSocket, SocketAddr)>,
+    screen: Option<(ChannelId, ScreenOptions)>,
+    screen_open: bool,
+    frames: Option<Receiver<Frame>>,
+    frame_number: u32,
+    files_channel: Option<ChannelId>,
+    files_open: bool,
+    files: VecDeque<PathBuf>,
+    current: Option<FileTransfer>,
+    transfer_id: u32,
+    idle_since: Instant,
+    stop: Arc<AtomicBool>,
+}
+
+impl ShareLoop {
+    fn run(mut self, rx: Receiver<ShareInput>, callbacks: ShareCallbacks) {
+        let started = Instant::now();
+
+        loop {
+            let Some(timeout) = self.poll_until_timeout() else {
+                break;
+            };
+
+            let connected = self.screen_open || self.files_open;
+            if !connected && started.elapsed() > CONNECT_TIMEOUT {
+                info!("Share was not connected in time");
+                break;
+            }
+            if self.files_open
+                && self.current.is_none()
+                && self.files.is_empty()
+                && self.idle_since.elapsed() > FILES_IDLE_TIMEOUT
+            {
+                info!("File share is idle");
+                break;
+            }
+
+            self.send_frame();
+            self.send_files(&callbacks);
+
+            // keep streaming while there is something to send
+            let now = Instant::now();
+            let mut wait = timeout.saturating_duration_since(now);
+            if self.screen_open || self.current.is_some() {
+                wait = wait.min(Duration::from_millis(10));
+            }
+
+            match rx.recv_timeout(wait.max(Duration::from_millis(1))) {
+                Ok(ShareInput::Packet {
+                    source,
+                    destination,
+                    data,
+                }) => {
+                    let Ok(contents) = data.as_slice().try_into() else {
+                        continue;
+                    };
+                    let input = Input::Receive(
+                        Instant::now(),
+                        Receive {
+                            proto: Protocol::Udp,
+                            source,
+                            destination,
+                            contents,
+                        },
+                    );
+                    if self.rtc.accepts(&input) {
+                        if let Err(e) = self.rtc.handle_input(input) {
+                            warn!("Share disconnected: {:?}", e);
+                            break;
+                        }
+                    }
+                }
+                Ok(ShareInput::Answer(answer)) => {
+                    if let Some(pending) = self.pending.take() {
+                        if let Err(e) = self.rtc.sdp_api().accept_answer(pending, answer) {
+                            warn!("Failed to accept share answer: {:?}", e);
+                            break;
+                        }
+                    }
+                }
+                Ok(ShareInput::SendFile(path)) => {
+                    if self.files_channel.is_none() {
+                        warn!("Share has no files channel");
+                        self.files.push_back(path);
+                        break;
+                    }
+                    self.files.push_back(path);
+                }
+                Ok(ShareInput::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+
+            if let Err(e) = self.rtc.handle_input(Input::Timeout(Instant::now())) {
+                warn!("Share disconnected: {:?}", e);
+                break;
+            }
+        }
+
+        self.stop.store(true, Ordering::Relaxed);
+        self.rtc.disconnect();
+
+        // files which were not sent completely
+        let unsent = collect_unsent(self.current.take(), &mut self.files, &rx);
+        (callbacks.on_end)(unsent);
+    }
+
+    /// Poll the output until it returns a timeout
+    ///
+    /// Returns `None` when the connection ended.
+    fn poll_until_timeout(&mut self) -> Option<Instant> {
+        loop {
+            if !self.rtc.is_alive() {
+                return None;
+            }
+
+            match self.rtc.poll_output() {
+                Ok(Output::Transmit(transmit)) => {
+                    if let Some((socket, _)) = self
+                        .sockets
+                        .iter()
+                        .find(|(_, addr)| *addr == transmit.source)
+                    {
+                        if let Err(e) = socket.send_to(&transmit.contents, transmit.destination) {
+                            debug!("Failed to send UDP data: {:?}", e);
+                        }
+                    }
+                }
+                Ok(Output::Timeout(t)) => return Some(t),
+                Ok(Output::Event(event)) => self.handle_event(event),
+                Err(e) => {
+                    warn!("Share poll_output failed: {:?}", e);
+                    return None;
+                }
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::IceConnectionStateChange(IceConnectionState::Disconnected) => {
+                self.rtc.disconnect();
+            }
+            Event::ChannelOpen(cid, _) => {
+                if let Some((screen, options)) = &self.screen {
+                    if *screen == cid {
+                        info!("Screen share connected");
+                        self.screen_open = true;
+                        self.frames = Some(spawn_capture(options.clone(), self.stop.clone()));
+                    }
+                }
+                if self.files_channel == Some(cid) {
+                    info!("File share connected");
+                    self.files_open = true;
+                    self.idle_since = Instant::now();
+                }
+            }
+            Event::ChannelClose(cid) => {
+                if self.screen.as_ref().map(|(screen, _)| *screen) == Some(cid)
+                    || self.files_channel == Some(cid)
+                {
+                    self.rtc.disconnect();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Send the latest captured frame
+    fn send_frame(&mut self) {
+        let (Some((cid, _)), Some(frames)) = (&self.screen, &self.frames) else {
+            return;
+        };
+        let Ok(frame) = frames.try_recv() else {
+            return;
+        };
+        let Some(mut channel) = self.rtc.channel(*cid) else {
+            return;
+        };
+
+        // drop the frame while the channel is congested
+        if channel.buffered_amount() > MAX_BUFFERED {
+            return;
+        }
+
+        self.frame_number = self.frame_number.wrapping_add(1);
+        for message in frame_messages(self.frame_number, &frame) {
+            if let Err(e) = channel.write(true, &message) {
+                warn!("Failed to send screen frame: {:?}", e);
+                return;
+            }
+        }
+    }
+
+    /// Stream the queued files
+    fn send_files(&mut self, callbacks: &ShareCallbacks) {
+        let Some(cid) = self.files_channel else {
+            return;
+        };
+        if !self.files_open {
+            return;
+        }
+
+        for _ in 0..MAX_CHUNKS_PER_ITERATION {
+            let Some(mut channel) = self.rtc.channel(cid) else {
+                return;
+            };
+            if channel.buffered_amount() > MAX_BUFFERED {
+                return;
+            }
+
+            // start the next file
+            if self.current.is_none() {
+                let Some(path) = self.files.pop_front() else {
+                    return;
+                };
+                let (file, size) = match File::open(&path).and_then(|file| {
+                    let size = file.metadata()?.len();
+                    Ok((file, size))
+                }) {
+                    Ok(file) => file,
+                    Err(e) => {
+                        warn!("Failed to open {}: {}", path.display(), e);
+                        continue;
+                    }
+                };
+
+                self.transfer_id += 1;
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let start = file_start_message(self.transfer_id, &name, size);
+                if let Err(e) = channel.write(false, start.as_bytes()) {
+                    warn!("Failed to start file transfer: {:?}", e);
+                    self.files.push_front(path);
+                    return;
+                }
+
+                self.current = Some(FileTransfer {
+                    path,
+                    file,
+                    transfer_id: self.transfer_id,
+                    size,
+                    sent: 0,
+                    reported: 0,
+                });
+                continue;
+            }
+
+            let Some(transfer) = self.current.as_mut() else {
+                return;
+            };
+            let mut buf = vec![0; CHUNK_SIZE];
+            let n = match transfer.file.read(&mut buf) {
+                Ok(n) => n,
+                Err(e) => {
+                    warn!("Failed to read {}: {}", transfer.path.display(), e);
+                    self.rtc.disconnect();
+                    return;
+                }
+            };
+
+            // file is complete
+            if n == 0 {
+                let end = file_end_message(transfer.transfer_id);
+                if let Err(e) = channel.write(false, end.as_bytes()) {
+                    warn!("Failed to end file transfer: {:?}", e);
+                    return;
+                }
+
+                (callbacks.on_progress)(transfer.progress());
+                info!("File {} sent", transfer.path.display());
+                self.current = None;
+                self.idle_since = Instant::now();
+                continue;
+            }
+
+            if let Err(e) = channel.write(true, &buf[..n]) {
+                warn!("Failed to send file chunk: {:?}", e);
+                return;
+            }
+            if let Some(progress) = transfer.add_sent(n) {
+                (callbacks.on_progress)(progress);
+            }
+        }
+    }
+}
+
+/// Capture the screen in the background
+///
+/// Only the latest frame is kept, if the run loop does not
+/// keep up, older frames are dropped.
+fn spawn_capture(options: ScreenOptions, stop: Arc<AtomicBool>) -> Receiver<Frame> {
+    let (tx, rx) = mpsc::sync_channel(1);
+
+    thread::spawn(move || {
+        let interval = Duration::from_millis(1000 / options.fps.clamp(1, 30) as u64);
+        let mut last_hash = None;
+        let mut last_sent: Option<Instant> = None;
+
+        while !stop.load(Ordering::Relaxed) {
+            let started = Instant::now();
+
+            match capture(&options.source) {
+                Ok(image) => {
+                    let mut hasher = DefaultHasher::new();
+                    image.as_raw().hash(&mut hasher);
+                    let hash = hasher.finish();
+
+                    let refresh = last_sent
+                        .map(|t| t.elapsed() >= FRAME_REFRESH)
+                        .unwrap_or(true);
+                    if last_hash != Some(hash) || refresh {
+                        match encode_frame(image, options.quality) {
+                            Ok(frame) => match tx.try_send(frame) {
+                                Ok(()) => {
+                                    last_hash = Some(hash);
+                                    last_sent = Some(Instant::now());
+                                }
+                                Err(TrySendError::Full(_)) => {}
+                                Err(TrySendError::Disconnected(_)) => return,
+                            },
+                            Err(e) => warn!("Failed to encode screen frame: {}", e),
+                        }
+                    }
+                }
+                Err(e) => warn!("Failed to capture screen: {}", e),
+            }
+
+            thread::sleep(interval.saturating_sub(started.elapsed()));
+        }
+    });
+
+    rx
+}
+
+/// Capture an image of the source
+fn capture(source: &ScreenSource) -> Result<image::RgbaImage, String> {
+    match source {
+        ScreenSource::Screen(index) => {
+            let screens = Screen::all().map_err(|e| format!("Failed to get screens: {}", e))?;
+            let screen = screens
+                .get(*index)
+                .ok_or_else(|| format!("Screen {} not found", index))?;
+            let image = screen
+                .capture()
+                .map_err(|e| format!("Failed to capture screen: {}", e))?;
+
+            // Convert from screenshots::image to image
+            image::RgbaImage::from_raw(image.width(), image.height(), image.into_raw())
+                .ok_or_else(|| "Failed to convert image format".to_string())
+        }
+        ScreenSource::ActiveWindow => crate::window_manager::capture_active_window(),
+    }
+}
+
+/// Scale down and encode a captured image as JPEG
+fn encode_frame(image: image::RgbaImage, quality: u8) -> Result<Frame, String> {
+    let image = if image.width() > MAX_FRAME_WIDTH {
+        let height =
+            (image.height() as u64 * MAX_FRAME_WIDTH as u64 / image.width() as u64).max(1) as u32;
+        image::imageops::resize(&image, MAX_FRAME_WIDTH, height, FilterType::Triangle)
+    } else {
+        image
+    };
+    let rgb = image::DynamicImage::ImageRgba8(image).to_rgb8();
+
+    let mut data = Vec::new();
+    JpegEncoder::new_with_quality(&mut data, quality.clamp(1, 100))
+        .encode_image(&rgb)
+        .map_err(|e| e.to_string())?;
+
+    Ok(Frame {
+        width: rgb.width().min(u16::MAX as u32) as u16,
+        height: rgb.height().min(u16::MAX as u32) as u16,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(file: File, size: u64) -> FileTransfer {
+        FileTransfer {
+            path: PathBuf::from("notes.pdf"),
+            file,
+            transfer_id: 1,
+            size,
+            sent: 0,
+            reported: 0,
+        }
+    }
+
+    #[test]
+    fn test_frame_messages() {
+        let data: Vec<u8> = (0..40_000u32).map(|i| i as u8).collect();
+        let frame = Frame {
+            width: 1280,
+            height: 720,
+            data: data.clone(),
+        };
+
+        let messages = frame_messages(7, &frame);
+        assert_eq!(messages.len(), 3);
+        for (index, message) in messages.iter().enumerate() {
+            assert!(message.len() <= CHUNK_SIZE);
+            assert_eq!(&message[0..4], &7u32.to_be_bytes());
+            assert_eq!(&message[4..6], &(index as u16).to_be_bytes());
+            assert_eq!(&message[6..8], &3u16.to_be_bytes());
+            assert_eq!(&message[8..10], &1280u16.to_be_bytes());
+            assert_eq!(&message[10..12], &720u16.to_be_bytes());
+        }
+
+        // the chunks reassemble to the frame
+        let payload: Vec<u8> = messages
+            .iter()
+            .flat_map(|message| message[FRAME_HEADER_SIZE..].to_vec())
+            .collect();
+        assert_eq!(payload, data);
+    }
+
+    #[test]
+    fn test_file_messages() {
+        let start: serde_json::Value =
+            serde_json::from_str(&file_start_message(3, "notes.pdf", 1234)).unwrap();
+        assert_eq!(
+            start,
+            serde_json::json!({
+                "type": "file-start",
+                "transfer_id": 3,
+                "name": "notes.pdf",
+                "size": 1234,
+            })
+        );
+
+        let end: serde_json::Value = serde_json::from_str(&file_end_message(3)).unwrap();
+        assert_eq!(
+            end,
+            serde_json::json!({"type": "file-end", "transfer_id": 3})
+        );
+    }
+
+    #[test]
+    fn test_file_progress() {
+        let file = tempfile::tempfile().unwrap();
+        let size = PROGRESS_INTERVAL * 2 + 100;
+        let mut transfer = transfer(file, size);
+
+        // progress is reported every interval
+        let mut reported = Vec::new();
+        while transfer.sent < size {
+            let n = (size - transfer.sent).min(CHUNK_SIZE as u64) as usize;
+            if let Some(progress) = transfer.add_sent(n) {
+                reported.push(progress.sent);
+            }
+        }
+        assert_eq!(reported, vec![PROGRESS_INTERVAL, PROGRESS_INTERVAL * 2]);
+
+        let progress = transfer.progress();
+        assert_eq!(progress.sent, size);
+        assert_eq!(progress.size, size);
+        assert_eq!(progress.path, PathBuf::from("notes.pdf"));
+    }
+
+    #[test]
+    fn test_collect_unsent() {
+        let (tx, rx) = mpsc::channel();
+        tx.send(ShareInput::Stop).unwrap();
+        tx.send(ShareInput::SendFile(PathBuf::from("c.txt")))
+            .unwrap();
+        let mut files = VecDeque::from(vec![PathBuf::from("b.txt")]);
+        let current = transfer(tempfile::tempfile().unwrap(), 10);
+
+        let unsent = collect_unsent(Some(current), &mut files, &rx);
+        assert_eq!(
+            unsent,
+            vec![
+                PathBuf::from("notes.pdf"),
+                PathBuf::from("b.txt"),
+                PathBuf::from("c.txt")
+            ]
+        );
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn test_send_file_to_ended_share() {
+        let (tx, rx) = mpsc::channel();
+        let share = ShareSession { tx };
+        drop(rx);
+
+        // the file is handed back to be sent via chat
+        assert_eq!(
+            share.send_file(PathBuf::from("notes.pdf")),
+            Err(PathBuf::from("notes.pdf"))
+        );
+    }
+}
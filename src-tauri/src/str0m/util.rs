@@ -3,3 +3,22 @@ pub fn select_host_address() -> String {
     // 0.0.0.0 is not a valid ICE candidate address
     "127.0.0.1".to_string()
 }
+
+/// Select the address of this node in the local network
+///
+/// Connecting a UDP socket does not send any packet, but lets the
+/// OS choose the interface of the default route.
+/// Falls back to the host address if there is no network.
+pub fn select_lan_address() -> String {
+    let addr = std::net::UdpSocket::bind("0.0.0.0:0")
+        .and_then(|socket| {
+            socket.connect("10.254.254.254:1")?;
+            socket.local_addr()
+        })
+        .map(|addr| addr.ip());
+
+    match addr {
+        Ok(ip) if !ip.is_unspecified() && !ip.is_loopback() => ip.to_string(),
+        _ => select_host_address(),
+    }
+}
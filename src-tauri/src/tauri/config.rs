@@ -0,0 +1,37 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Configuration Tauri Commands
+//!
+//! Read and change the runtime configuration of libqaul
+//! from the settings screens.
+//! Every applied change is announced with the
+//! `qaul-configuration-changed` event.
+
+use libqaul::storage::configuration::{
+    Configuration, ConfigurationError, ConfigurationSection, ConfigurationUpdate,
+};
+
+/// Get the runtime changeable configuration
+#[tauri_crate::command]
+pub fn qaul_get_configuration() -> Result<ConfigurationUpdate, String> {
+    Ok(Configuration::get_settings())
+}
+
+/// Validate a configuration update without applying it
+#[tauri_crate::command]
+pub fn qaul_validate_configuration(
+    update: ConfigurationUpdate,
+) -> Result<(), Vec<ConfigurationError>> {
+    Configuration::validate_update(&update)
+}
+
+/// Validate and apply a configuration update
+///
+/// Returns the changed sections, or the validation errors.
+#[tauri_crate::command]
+pub fn qaul_update_configuration(
+    update: ConfigurationUpdate,
+) -> Result<Vec<ConfigurationSection>, Vec<ConfigurationError>> {
+    Configuration::apply_update(update)
+}
@@ -0,0 +1,790 @@
+//! # Download Manager
+//!
+//! Downloads from Google Drive, MEGA, qaul and the web all go through one
+//! persistent queue, stored in the `downloads` table of the app database.
+//! At most [`MAX_CONCURRENT_DOWNLOADS`] downloads run at the same time, the
+//! others wait in the queue. Downloads can be paused, resumed and cancelled,
+//! and the ones interrupted by closing the app are queued again on startup.
+//!
+//! Every download reports its progress as `download-progress` event with the
+//! [`DownloadProgress`] schema, whatever its source. Once finished, downloads
+//! stay in the table as download history.
+
+use anyhow::{anyhow, Context, Result};
+use md5::Md5;
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri_crate::{async_runtime, AppHandle, Emitter};
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+
+use crate::commands::gdrive::{download as gdrive, listing as gdrive_listing};
+use crate::constants::MAX_CONCURRENT_DOWNLOADS;
+use crate::sql::{model::DownloadData, queries};
+
+use super::mega;
+
+/// Event carrying a [`DownloadProgress`]
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "download-progress";
+
+/// Minimum time between two progress events of a running download
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// How often to check whether a qaul file has been received
+const QAUL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Size of the buffer used when copying and hashing files
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Where a file gets downloaded from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DownloadSource {
+    GoogleDrive {
+        file_id: String,
+        /// Google Docs, Sheets, Slides and Drawings get exported to PDF
+        #[serde(default)]
+        mime_type: Option<String>,
+    },
+    Mega {
+        handle: String,
+    },
+    /// A file shared in a qaul chat, copied out of libqaul's storage once received
+    Qaul {
+        file_id: u64,
+        file_extension: String,
+    },
+    Web {
+        url: String,
+    },
+}
+
+impl DownloadSource {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DownloadSource::GoogleDrive { .. } => "google_drive",
+            DownloadSource::Mega { .. } => "mega",
+            DownloadSource::Qaul { .. } => "qaul",
+            DownloadSource::Web { .. } => "web",
+        }
+    }
+
+    /// The ID of the file within its source
+    pub fn id(&self) -> String {
+        match self {
+            DownloadSource::GoogleDrive { file_id, .. } => file_id.clone(),
+            DownloadSource::Mega { handle } => handle.clone(),
+            DownloadSource::Qaul { file_id, .. } => file_id.to_string(),
+            DownloadSource::Web { url } => url.clone(),
+        }
+    }
+}
+
+/// Expected checksum of a downloaded file, as lowercase hex
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "algorithm", content = "value", rename_all = "snake_case")]
+pub enum Checksum {
+    Sha256(String),
+    Md5(String),
+}
+
+impl Checksum {
+    /// Verify the checksum of a file
+    fn verify(&self, path: &Path) -> Result<()> {
+        let (expected, actual) = match self {
+            Checksum::Sha256(expected) => (expected, hash_file::<Sha256>(path)?),
+            Checksum::Md5(expected) => (expected, hash_file::<Md5>(path)?),
+        };
+
+        if !expected.eq_ignore_ascii_case(&actual) {
+            return Err(anyhow!(
+                "Checksum mismatch: expected {}, got {}",
+                expected,
+                actual
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn hash_file<D: Digest>(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let mut hasher = D::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// State of a download, as stored in the `status` column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadStatus {
+    Queued,
+    Downloading,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl DownloadStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DownloadStatus::Queued => "queued",
+            DownloadStatus::Downloading => "downloading",
+            DownloadStatus::Paused => "paused",
+            DownloadStatus::Completed => "completed",
+            DownloadStatus::Failed => "failed",
+            DownloadStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// A download to add to the queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadRequest {
+    pub source: DownloadSource,
+    pub file_name: String,
+    /// Local file to download to, defaults to a file in the download directory
+    pub destination: Option<String>,
+    pub checksum: Option<Checksum>,
+}
+
+/// Progress of a download, emitted to the frontend as `download-progress`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadProgress {
+    pub id: String,
+    pub source_kind: String,
+    pub source_id: String,
+    pub file_name: String,
+    pub file_path: String,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    pub percentage: f64,
+    pub status: String,
+    pub error: Option<String>,
+}
+
+impl From<&DownloadData> for DownloadProgress {
+    fn from(download: &DownloadData) -> Self {
+        let percentage = match (download.status.as_str(), download.total) {
+            ("completed", _) => 100.0,
+            (_, Some(total)) if total > 0 => download.downloaded as f64 / total as f64 * 100.0,
+            _ => 0.0,
+        };
+
+        Self {
+            id: download.id.clone(),
+            source_kind: download.source_kind.clone(),
+            source_id: download.source_id.clone(),
+            file_name: download.file_name.clone(),
+            file_path: download.file_path.clone(),
+            downloaded: download.downloaded,
+            total: download.total,
+            percentage,
+            status: download.status.clone(),
+            error: download.error.clone(),
+        }
+    }
+}
+
+fn emit_progress(app_handle: &AppHandle, download: &DownloadData) {
+    if let Err(e) = app_handle.emit(DOWNLOAD_PROGRESS_EVENT, DownloadProgress::from(download)) {
+        tracing::warn!("Failed to emit download progress: {}", e);
+    }
+}
+
+/// Reports the progress of a running download, throttled to [`PROGRESS_INTERVAL`]
+struct ProgressReporter<'a> {
+    app_handle: &'a AppHandle,
+    state: Mutex<(DownloadData, Instant)>,
+}
+
+impl<'a> ProgressReporter<'a> {
+    fn new(app_handle: &'a AppHandle, download: DownloadData) -> Self {
+        Self {
+            app_handle,
+            state: Mutex::new((download, Instant::now())),
+        }
+    }
+
+    fn report(&self, downloaded: u64, total: Option<u64>) {
+        let mut state = self.state.lock().unwrap();
+        let (download, last_report) = &mut *state;
+
+        download.downloaded = downloaded;
+        if total.is_some() {
+            download.total = total;
+        }
+        if last_report.elapsed() < PROGRESS_INTERVAL && download.total != Some(downloaded) {
+            return;
+        }
+        *last_report = Instant::now();
+
+        if let Err(e) = queries::update_download_progress(&download.id, downloaded, total) {
+            tracing::warn!(
+                "Failed to store progress of download {}: {}",
+                download.id,
+                e
+            );
+        }
+        emit_progress(self.app_handle, download);
+    }
+}
+
+/// The download queue, managed as Tauri state
+#[derive(Clone)]
+pub struct DownloadManager {
+    permits: Arc<Semaphore>,
+    /// Cancellation tokens of the queued and running downloads, by download ID
+    running: Arc<Mutex<HashMap<String, CancellationToken>>>,
+}
+
+impl Default for DownloadManager {
+    fn default() -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS)),
+            running: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl DownloadManager {
+    /// Queue the downloads interrupted when the app was last closed
+    pub fn resume_interrupted(&self, app_handle: &AppHandle) -> Result<(), String> {
+        let statuses = [
+            DownloadStatus::Queued.as_str(),
+            DownloadStatus::Downloading.as_str(),
+        ];
+        for download in queries::get_downloads_by_status(&statuses)? {
+            queries::update_download_status(&download.id, DownloadStatus::Queued.as_str(), None)?;
+            self.spawn(app_handle, download.id);
+        }
+        Ok(())
+    }
+
+    /// Add a download to the queue
+    pub fn enqueue(
+        &self,
+        app_handle: &AppHandle,
+        request: DownloadRequest,
+    ) -> Result<DownloadData, String> {
+        let file_path = match request.destination {
+            Some(destination) => PathBuf::from(destination),
+            None => {
+                let dir = gdrive::get_download_dir().map_err(|e| e.to_string())?;
+                unique_path(&dir, &request.file_name)?
+            }
+        };
+        let checksum = request
+            .checksum
+            .map(|checksum| serde_json::to_string(&checksum))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        let now = chrono::Utc::now();
+
+        let download = DownloadData {
+            id: uuid::Uuid::new_v4().to_string(),
+            source_kind: request.source.kind().to_string(),
+            source_id: request.source.id(),
+            source: serde_json::to_string(&request.source).map_err(|e| e.to_string())?,
+            file_name: request.file_name,
+            file_path: file_path.to_string_lossy().to_string(),
+            status: DownloadStatus::Queued.as_str().to_string(),
+            downloaded: 0,
+            total: None,
+            checksum,
+            error: None,
+            created_at: now,
+            updated_at: now,
+            completed_at: None,
+        };
+        queries::insert_download(&download)?;
+
+        emit_progress(app_handle, &download);
+        self.spawn(app_handle, download.id.clone());
+        Ok(download)
+    }
+
+    /// Pause a queued or running download, keeping what was downloaded so far
+    pub fn pause(&self, app_handle: &AppHandle, id: &str) -> Result<DownloadData, String> {
+        let download = queries::get_download(id)?;
+        if !self.stop(id) {
+            return Err(format!("Download {} is not running", download.file_name));
+        }
+
+        self.set_status(app_handle, id, DownloadStatus::Paused, None)
+    }
+
+    /// Resume a paused or failed download
+    pub fn resume(&self, app_handle: &AppHandle, id: &str) -> Result<DownloadData, String> {
+        let download = queries::get_download(id)?;
+        if download.status != DownloadStatus::Paused.as_str()
+            && download.status != DownloadStatus::Failed.as_str()
+        {
+            return Err(format!("Download {} is not paused", download.file_name));
+        }
+
+        let download = self.set_status(app_handle, id, DownloadStatus::Queued, None)?;
+        self.spawn(app_handle, id.to_string());
+        Ok(download)
+    }
+
+    /// Cancel a download, deleting what was downloaded so far
+    pub fn cancel(&self, app_handle: &AppHandle, id: &str) -> Result<DownloadData, String> {
+        let download = queries::get_download(id)?;
+        if download.status == DownloadStatus::Completed.as_str() {
+            return Err(format!(
+                "Download {} is already completed",
+                download.file_name
+            ));
+        }
+
+        self.stop(id);
+        discard_partial(app_handle, &download);
+        self.set_status(app_handle, id, DownloadStatus::Cancelled, None)
+    }
+
+    /// Remove a download from the history, cancelling it if unfinished
+    ///
+    /// The downloaded file itself is kept.
+    pub fn remove(&self, app_handle: &AppHandle, id: &str) -> Result<(), String> {
+        let download = queries::get_download(id)?;
+        if download.status != DownloadStatus::Completed.as_str() {
+            self.stop(id);
+            discard_partial(app_handle, &download);
+        }
+        queries::delete_download(id)?;
+        Ok(())
+    }
+
+    fn set_status(
+        &self,
+        app_handle: &AppHandle,
+        id: &str,
+        status: DownloadStatus,
+        error: Option<&str>,
+    ) -> Result<DownloadData, String> {
+        queries::update_download_status(id, status.as_str(), error)?;
+        let download = queries::get_download(id)?;
+        emit_progress(app_handle, &download);
+        Ok(download)
+    }
+
+    /// Stop a queued or running download, returns whether it was
+    fn stop(&self, id: &str) -> bool {
+        match self.running.lock().unwrap().remove(id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Run a queued download in the background, once a permit is available
+    fn spawn(&self, app_handle: &AppHandle, id: String) {
+        let token = CancellationToken::new();
+        self.running
+            .lock()
+            .unwrap()
+            .insert(id.clone(), token.clone());
+
+        let manager = self.clone();
+        let app_handle = app_handle.clone();
+        async_runtime::spawn(async move {
+            let result = tokio::select! {
+                // Paused or cancelled, which already updated the status
+                _ = token.cancelled() => return,
+                result = manager.run(&app_handle, &id) => result,
+            };
+            manager.running.lock().unwrap().remove(&id);
+
+            let update = match result {
+                Ok(()) => manager.set_status(&app_handle, &id, DownloadStatus::Completed, None),
+                Err(e) => {
+                    tracing::error!("Download {} failed: {:#}", id, e);
+                    let error = format!("{:#}", e);
+                    manager.set_status(&app_handle, &id, DownloadStatus::Failed, Some(&error))
+                }
+            };
+            if let Err(e) = update {
+                tracing::error!("Failed to update download {}: {}", id, e);
+            }
+        });
+    }
+
+    async fn run(&self, app_handle: &AppHandle, id: &str) -> Result<()> {
+        // qaul files only take a slot once they have been received
+        let queued = queries::get_download(id).map_err(|e| anyhow!(e))?;
+        let qaul_file = match serde_json::from_str::<DownloadSource>(&queued.source) {
+            Ok(DownloadSource::Qaul { file_id, .. }) => Some(wait_for_qaul_file(file_id).await?),
+            _ => None,
+        };
+
+        let _permit = self.permits.acquire().await?;
+
+        let download = self
+            .set_status(app_handle, id, DownloadStatus::Downloading, None)
+            .map_err(|e| anyhow!(e))?;
+        let source: DownloadSource =
+            serde_json::from_str(&download.source).context("Invalid download source")?;
+        let checksum: Option<Checksum> = download
+            .checksum
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .context("Invalid download checksum")?;
+        let path = PathBuf::from(&download.file_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {:?}", parent))?;
+        }
+
+        let reporter = ProgressReporter::new(app_handle, download);
+        match source {
+            DownloadSource::GoogleDrive { file_id, mime_type } => {
+                let export_url = mime_type
+                    .as_deref()
+                    .and_then(|mime_type| gdrive_listing::export_pdf_url(&file_id, mime_type));
+                let url = match export_url {
+                    Some(url) => url,
+                    None => gdrive_listing::resolve_download_url(&file_id).await?,
+                };
+                download_http(&url, &path, &reporter).await?
+            }
+            DownloadSource::Web { url } => download_http(&url, &path, &reporter).await?,
+            DownloadSource::Mega { handle } => {
+                mega::download_node_to(app_handle, &handle, &path, |progress| {
+                    reporter.report(progress.transferred, Some(progress.total))
+                })
+                .await
+                .map_err(|e| anyhow!(e))?
+            }
+            DownloadSource::Qaul { .. } => {
+                let source_path = qaul_file.ok_or_else(|| anyhow!("qaul file not received"))?;
+                copy_qaul_file(&source_path, &path, &reporter).await?
+            }
+        }
+
+        verify_download(checksum.as_ref(), &path)
+    }
+}
+
+/// Verify the checksum of a finished download, deleting the file on a mismatch
+fn verify_download(checksum: Option<&Checksum>, path: &Path) -> Result<()> {
+    if let Some(checksum) = checksum {
+        if let Err(e) = checksum.verify(path) {
+            let _ = fs::remove_file(path);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Delete the partially downloaded file of an unfinished download
+fn discard_partial(app_handle: &AppHandle, download: &DownloadData) {
+    if download.downloaded > 0 || download.status == DownloadStatus::Downloading.as_str() {
+        if let Err(e) = fs::remove_file(&download.file_path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("Failed to delete {}: {}", download.file_path, e);
+            }
+        }
+    }
+    if download.source_kind == "mega" {
+        if let Ok(state_file) = mega::download_state_file(app_handle, &download.source_id) {
+            let _ = fs::remove_file(state_file);
+        }
+    }
+}
+
+/// A path for the file in the directory that isn't taken yet
+fn unique_path(dir: &Path, file_name: &str) -> Result<PathBuf, String> {
+    // Only keep the file name, so it can't point outside of the directory
+    let file_name = Path::new(file_name)
+        .file_name()
+        .ok_or_else(|| format!("Invalid file name: {}", file_name))?;
+    let path = dir.join(file_name);
+    if !path.exists() {
+        return Ok(path);
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .ok_or_else(|| format!("No free file name for {}", file_name.to_string_lossy()))
+}
+
+/// Download a file over HTTP, resuming a partial file when the server supports ranges
+async fn download_http(url: &str, path: &Path, reporter: &ProgressReporter<'_>) -> Result<()> {
+    let existing = fs::metadata(path)
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+
+    let client = gdrive::build_http_client().await?;
+    let mut request = client.get(url);
+    if existing > 0 {
+        request = request.header(RANGE, format!("bytes={}-", existing));
+    }
+    let mut response = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to download {}", url))?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "HTTP {} – {}",
+            response.status(),
+            response.text().await.unwrap_or_default()
+        ));
+    }
+
+    let resumed = response.status() == StatusCode::PARTIAL_CONTENT;
+    let (mut file, mut downloaded) = if resumed {
+        let file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open file: {:?}", path))?;
+        (file, existing)
+    } else {
+        let file =
+            File::create(path).with_context(|| format!("Failed to create file: {:?}", path))?;
+        (file, 0)
+    };
+    let total = response.content_length().map(|length| length + downloaded);
+
+    reporter.report(downloaded, total);
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk)?;
+        downloaded += chunk.len() as u64;
+        reporter.report(downloaded, total);
+    }
+    file.flush()?;
+
+    if let Some(total) = total {
+        if downloaded != total {
+            return Err(anyhow!(
+                "Download ended after {} of {} bytes",
+                downloaded,
+                total
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Wait until a qaul chat file has been received completely, returning its path
+async fn wait_for_qaul_file(file_id: u64) -> Result<PathBuf> {
+    let account =
+        crate::get_current_user_internal().ok_or_else(|| anyhow!("No current user found"))?;
+
+    loop {
+        if let Some(path) = libqaul::services::chat::file::ChatFile::received_file_path(
+            account.id,
+            file_id,
+        ) {
+            return Ok(path);
+        }
+        tokio::time::sleep(QAUL_POLL_INTERVAL).await;
+    }
+}
+
+/// Copy a received qaul chat file out of libqaul's storage
+async fn copy_qaul_file(
+    source_path: &Path,
+    path: &Path,
+    reporter: &ProgressReporter<'_>,
+) -> Result<()> {
+    let mut source =
+        File::open(source_path).with_context(|| format!("Failed to open {:?}", source_path))?;
+    let total = source.metadata()?.len();
+    let mut destination =
+        File::create(path).with_context(|| format!("Failed to create file: {:?}", path))?;
+
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut copied = 0;
+    reporter.report(copied, Some(total));
+    loop {
+        let read = source.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        destination.write_all(&buffer[..read])?;
+        copied += read as u64;
+        reporter.report(copied, Some(total));
+        tokio::task::yield_now().await;
+    }
+    destination.flush()?;
+    Ok(())
+}
+
+/// Queue a download
+#[tauri_crate::command]
+pub async fn download_enqueue(
+    request: DownloadRequest,
+    app_handle: AppHandle,
+    manager: tauri_crate::State<'_, DownloadManager>,
+) -> Result<DownloadData, String> {
+    manager.enqueue(&app_handle, request)
+}
+
+/// Pause a queued or running download
+#[tauri_crate::command]
+pub async fn download_pause(
+    id: String,
+    app_handle: AppHandle,
+    manager: tauri_crate::State<'_, DownloadManager>,
+) -> Result<DownloadData, String> {
+    manager.pause(&app_handle, &id)
+}
+
+/// Resume a paused or failed download
+#[tauri_crate::command]
+pub async fn download_resume(
+    id: String,
+    app_handle: AppHandle,
+    manager: tauri_crate::State<'_, DownloadManager>,
+) -> Result<DownloadData, String> {
+    manager.resume(&app_handle, &id)
+}
+
+/// Cancel a download and delete its partial file
+#[tauri_crate::command]
+pub async fn download_cancel(
+    id: String,
+    app_handle: AppHandle,
+    manager: tauri_crate::State<'_, DownloadManager>,
+) -> Result<DownloadData, String> {
+    manager.cancel(&app_handle, &id)
+}
+
+/// Remove a download from the history
+#[tauri_crate::command]
+pub async fn download_remove(
+    id: String,
+    app_handle: AppHandle,
+    manager: tauri_crate::State<'_, DownloadManager>,
+) -> Result<(), String> {
+    manager.remove(&app_handle, &id)
+}
+
+/// Get the queued, running and paused downloads
+#[tauri_crate::command]
+pub async fn download_get_queue() -> Result<Vec<DownloadData>, String> {
+    let statuses = [
+        DownloadStatus::Queued.as_str(),
+        DownloadStatus::Downloading.as_str(),
+        DownloadStatus::Paused.as_str(),
+    ];
+    Ok(queries::get_downloads_by_status(&statuses)?)
+}
+
+/// Get the most recent downloads of all sources
+#[tauri_crate::command]
+pub async fn download_get_recent(limit: Option<u32>) -> Result<Vec<DownloadData>, String> {
+    Ok(queries::get_recent_downloads(limit.unwrap_or(50))?)
+}
+
+/// Remove all finished downloads from the history
+#[tauri_crate::command]
+pub async fn download_clear_history() -> Result<(), String> {
+    Ok(queries::clear_finished_downloads()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &[u8] = b"qaul download";
+
+    fn write_file(dir: &Path, name: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, CONTENT).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_checksum_mismatch_deletes_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_file(dir.path(), "notes.pdf");
+
+        let error = verify_download(Some(&Checksum::Sha256("0".repeat(64))), &path).unwrap_err();
+        assert!(error.to_string().contains("Checksum mismatch"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_checksum_match_keeps_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_file(dir.path(), "notes.pdf");
+        let sha256 = hash_file::<Sha256>(&path).unwrap();
+        let md5 = hash_file::<Md5>(&path).unwrap();
+
+        verify_download(Some(&Checksum::Sha256(sha256.to_uppercase())), &path).unwrap();
+        verify_download(Some(&Checksum::Md5(md5)), &path).unwrap();
+        verify_download(None, &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), CONTENT);
+    }
+
+    #[test]
+    fn test_unique_path_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(
+            unique_path(dir.path(), "notes.pdf").unwrap(),
+            dir.path().join("notes.pdf")
+        );
+        write_file(dir.path(), "notes.pdf");
+        assert_eq!(
+            unique_path(dir.path(), "notes.pdf").unwrap(),
+            dir.path().join("notes (1).pdf")
+        );
+        write_file(dir.path(), "notes (1).pdf");
+        assert_eq!(
+            unique_path(dir.path(), "notes.pdf").unwrap(),
+            dir.path().join("notes (2).pdf")
+        );
+
+        write_file(dir.path(), "README");
+        assert_eq!(
+            unique_path(dir.path(), "README").unwrap(),
+            dir.path().join("README (1)")
+        );
+    }
+
+    #[test]
+    fn test_unique_path_strips_directories() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(
+            unique_path(dir.path(), "../../etc/passwd").unwrap(),
+            dir.path().join("passwd")
+        );
+        assert_eq!(
+            unique_path(dir.path(), "/tmp/notes.pdf").unwrap(),
+            dir.path().join("notes.pdf")
+        );
+        assert_eq!(
+            unique_path(dir.path(), "sub/dir/notes.pdf").unwrap(),
+            dir.path().join("notes.pdf")
+        );
+        assert!(unique_path(dir.path(), "..").is_err());
+        assert!(unique_path(dir.path(), "").is_err());
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Storage Encryption Tauri Commands
+//!
+//! Unlock the encrypted libqaul storage on startup,
+//! enable encryption at rest and change the storage passphrase.
+//! While the storage is locked, the `qaul-storage-locked`
+//! event is emitted on startup.
+
+use libqaul::storage::encryption::{Encryption, EncryptionStatus};
+
+/// Get the encryption state of the storage
+#[tauri_crate::command]
+pub fn qaul_storage_encryption_status() -> Result<EncryptionStatus, String> {
+    Ok(Encryption::status())
+}
+
+/// Unlock the encrypted storage with the passphrase
+#[tauri_crate::command]
+pub fn qaul_unlock_storage(passphrase: String) -> Result<EncryptionStatus, String> {
+    Encryption::unlock(&passphrase)?;
+    Ok(Encryption::status())
+}
+
+/// Enable encryption at rest
+///
+/// All existing data is encrypted with the passphrase.
+#[tauri_crate::command]
+pub fn qaul_enable_storage_encryption(passphrase: String) -> Result<EncryptionStatus, String> {
+    Encryption::enable(&passphrase)?;
+    Ok(Encryption::status())
+}
+
+/// Change the storage passphrase
+#[tauri_crate::command]
+pub fn qaul_change_storage_passphrase(
+    old_passphrase: String,
+    new_passphrase: String,
+) -> Result<EncryptionStatus, String> {
+    Encryption::change_passphrase(&old_passphrase, &new_passphrase)?;
+    Ok(Encryption::status())
+}
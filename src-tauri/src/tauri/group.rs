@@ -411,6 +411,7 @@ impl Default for GroupMemberBase {
             name: String::default(),
             key_base58: String::default(),
             verified: false,
+            trust: "unverified".to_string(),
             blocked: false,
             profile: String::default(),
             about: String::default(),
@@ -427,6 +428,8 @@ pub struct GroupMemberBase {
     pub name: String,
     pub key_base58: String,
     pub verified: bool,
+    /// trust level of the user's key: unverified | verified | key_changed
+    pub trust: String,
     pub blocked: bool,
     pub profile: String,
     pub about: String,
@@ -537,6 +540,9 @@ impl From<ProtoGroupMember> for GroupMember {
         
         // Get user's last seen time - not available in User struct, set to None for now
         let last_seen = None;
+
+        let trust = libqaul::services::group::proto_rpc::TrustLevel::try_from(proto.trust)
+            .unwrap_or(libqaul::services::group::proto_rpc::TrustLevel::Unverified);
         
         Self {
             user_id: hex::encode(&proto.user_id),
@@ -549,7 +555,8 @@ impl From<ProtoGroupMember> for GroupMember {
                 id: hex::encode(&proto.user_id),
                 name: proto.name,
                 key_base58: peer_id.to_base58(),
-                verified: false, // You may want to implement verification logic
+                verified: trust == libqaul::services::group::proto_rpc::TrustLevel::Verified,
+                trust: trust.as_str_name().to_lowercase(),
                 blocked: false,  // You may want to implement block list check
                 profile: format_profile_pic(&proto.profile_pic),
                 about: proto.about,
@@ -664,6 +671,7 @@ impl From<libqaul::services::group::Group> for GroupInfo {
                                 },
                                 key_base58: base.get("key_base58").and_then(|v| v.as_str()).unwrap_or("").to_string(),
                                 verified: base.get("verified").and_then(|v| v.as_bool()).unwrap_or(false),
+                                trust: base.get("trust").and_then(|v| v.as_str()).unwrap_or("unverified").to_string(),
                                 blocked: base.get("blocked").and_then(|v| v.as_bool()).unwrap_or(false),
                                 profile: base.get("profile").and_then(|v| v.as_str()).unwrap_or("default-avatar.png").to_string(),
                                 about: base.get("about").and_then(|v| v.as_str()).unwrap_or("No bio available").to_string(),
@@ -681,6 +689,7 @@ impl From<libqaul::services::group::Group> for GroupInfo {
                                 },
                                 key_base58: String::new(),
                                 verified: false,
+                                trust: "unverified".to_string(),
                                 blocked: false,
                                 profile: if !m.profile_pic.is_empty() { m.profile_pic.clone() } else { "default-avatar.png".to_string() },
                                 about: if !m.about.is_empty() { m.about.clone() } else { "No bio available".to_string() },
@@ -1030,7 +1039,7 @@ pub async fn get_messages(group_id: String) -> Result<String, String> {
             
             // Get sender profile information from router users
             let sender_profile = {
-                let peer_id = match PeerId::from_bytes(&msg.sender_id) {
+                let sender_peer_id = match PeerId::from_bytes(&msg.sender_id) {
                     Ok(id) => Some(id),
                     Err(_) => {
                         log::warn!("Failed to parse peer ID from message sender");
@@ -1038,12 +1047,14 @@ pub async fn get_messages(group_id: String) -> Result<String, String> {
                     }
                 };
                 
-                peer_id.and_then(|id| {
+                sender_peer_id.and_then(|id| {
                     let user = Users::get_user_by_q8id(id.to_bytes());
                     user.map(|u| {
+                        let trust = Users::get_trust(&peer_id, &id);
                         serde_json::json!({
                             "name": u.name,
-                            "verified": u.verified,
+                            "verified": trust == libqaul::router::users::TrustLevel::Verified,
+                            "trust": trust.as_str(),
                             "blocked": u.blocked,
                             "id": id.to_base58()
                         })
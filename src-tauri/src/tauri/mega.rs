@@ -3,9 +3,30 @@
 //! Provides tree listing and file management functionality
 //!
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use text_trees::{FormatCharacters, TreeFormatting};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use tauri_crate::{AppHandle, Emitter, Manager};
+use tauri_plugin_stronghold::stronghold::Stronghold;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+use super::downloads::{DownloadManager, DownloadRequest, DownloadSource};
+use super::mega_cache::{
+    cached_image, generate_images, load_snapshot, store_image, MegaCache, MegaImageKind,
+};
+
+const NOT_LOGGED_IN: &str = "Not logged into MEGA. Please login first.";
+
+/// Stronghold client and record holding the serialized MEGA session
+const VAULT_CLIENT: &[u8] = b"mega";
+const VAULT_SESSION_KEY: &[u8] = b"session";
+/// OS keychain entry holding the random key of the vault
+const KEYCHAIN_SERVICE: &str = "knowlia-mega";
+const KEYCHAIN_USER: &str = "session-vault";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MegaNode {
@@ -23,17 +44,70 @@ pub struct MegaTreeResponse {
     pub rubbish_bin: Option<MegaNode>,
 }
 
+/// Progress of an upload, emitted to the frontend as `mega-transfer-progress`
+///
+/// Downloads go through the download manager and emit `download-progress` instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MegaTransferProgress {
+    pub transfer_id: String,
+    /// Always "upload", kept for compatibility with older frontends
+    pub direction: String,
+    pub name: String,
+    pub transferred: u64,
+    pub total: u64,
+}
+
+/// Outcome of a folder sync pass, emitted to the frontend as `mega-sync`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MegaSyncReport {
+    pub uploaded: Vec<String>,
+    pub downloaded: Vec<String>,
+    pub deleted: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+impl From<&mega::SyncReport> for MegaSyncReport {
+    fn from(report: &mega::SyncReport) -> Self {
+        Self {
+            uploaded: report.uploaded.clone(),
+            downloaded: report.downloaded.clone(),
+            deleted: report.deleted.clone(),
+            conflicts: report.conflicts.clone(),
+            failed: report.failed.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MegaSyncStatus {
+    pub running: bool,
+    pub local_path: Option<String>,
+    pub remote_path: Option<String>,
+}
+
+/// Background folder sync between a local directory and a MEGA folder
+pub struct MegaSync {
+    pub local_path: String,
+    pub remote_path: String,
+    pub handle: JoinHandle<()>,
+}
+
 /// Application state for MEGA client
 pub struct MegaState {
-    pub client: Arc<Mutex<Option<mega::Client>>>,
+    pub client: Arc<RwLock<Option<mega::Client>>>,
     pub logged_in: Arc<Mutex<bool>>,
+    pub sync: Arc<Mutex<Option<MegaSync>>>,
+    pub cache: Arc<MegaCache>,
 }
 
 impl Default for MegaState {
     fn default() -> Self {
         Self {
-            client: Arc::new(Mutex::new(None)),
+            client: Arc::new(RwLock::new(None)),
             logged_in: Arc::new(Mutex::new(false)),
+            sync: Arc::new(Mutex::new(None)),
+            cache: Arc::new(MegaCache::default()),
         }
     }
 }
@@ -67,12 +141,151 @@ fn construct_tree_node(nodes: &mega::Nodes, node: &mega::Node) -> MegaNode {
     }
 }
 
+/// Construct the trees of all root nodes
+pub(crate) fn build_tree_response(nodes: &mega::Nodes) -> MegaTreeResponse {
+    MegaTreeResponse {
+        cloud_drive: nodes.cloud_drive().map(|node| construct_tree_node(nodes, node)),
+        inbox: nodes.inbox().map(|node| construct_tree_node(nodes, node)),
+        rubbish_bin: nodes.rubbish_bin().map(|node| construct_tree_node(nodes, node)),
+    }
+}
+
+/// Get the tree, preferring the live cache, then the last snapshot, then MEGA itself
+///
+/// Falling back to the snapshot opens the browser instantly on slow connections (and
+/// offline); the frontend gets a `mega-tree-updated` event once the live tree is loaded.
+async fn load_tree(app_handle: &AppHandle, state: &MegaState) -> Result<MegaTreeResponse, String> {
+    if let Some(nodes) = state.cache.cached().await {
+        return Ok(build_tree_response(&nodes));
+    }
+    if let Some(tree) = load_snapshot(app_handle) {
+        return Ok(tree);
+    }
+
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+    let nodes = state.cache.nodes(client).await?;
+    Ok(build_tree_response(&nodes))
+}
+
+/// Find a node by its handle
+fn find_node<'a>(nodes: &'a mega::Nodes, handle: &str) -> Result<&'a mega::Node, String> {
+    nodes
+        .get_node_by_handle(handle)
+        .ok_or_else(|| format!("MEGA node not found: {}", handle))
+}
+
+/// Directory of the app data dir holding MEGA-related files
+pub(crate) fn mega_data_dir(app_handle: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join(name);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create directory {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// Get the key of the vault from the OS keychain
+///
+/// A random key is created on first use. A vault of an older version,
+/// encrypted with a built-in password, is removed then and the user
+/// needs to log in again.
+fn vault_key(dir: &Path) -> Result<Vec<u8>, String> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER)
+        .map_err(|e| format!("Failed to open the OS keychain: {}", e))?;
+
+    match entry.get_secret() {
+        Ok(key) => Ok(key),
+        Err(keyring::Error::NoEntry) => {
+            let key = rand::random::<[u8; 32]>().to_vec();
+            entry
+                .set_secret(&key)
+                .map_err(|e| format!("Failed to store the MEGA vault key in the OS keychain: {}", e))?;
+            for file in ["session.stronghold", "vault.salt"] {
+                let _ = std::fs::remove_file(dir.join(file));
+            }
+            Ok(key)
+        }
+        Err(e) => Err(format!("Failed to read the MEGA vault key from the OS keychain: {}", e)),
+    }
+}
+
+/// Open the Stronghold vault holding the MEGA session
+fn open_vault(app_handle: &AppHandle) -> Result<Stronghold, String> {
+    let dir = mega_data_dir(app_handle, "mega")?;
+    let key = vault_key(&dir)?;
+    Stronghold::new(dir.join("session.stronghold"), key)
+        .map_err(|e| format!("Failed to open MEGA session vault: {}", e))
+}
+
+/// Persist the serialized MEGA session in Stronghold
+fn store_session(app_handle: &AppHandle, session: &str) -> Result<(), String> {
+    let vault = open_vault(app_handle)?;
+    let client = vault
+        .inner()
+        .load_client(VAULT_CLIENT)
+        .or_else(|_| vault.inner().create_client(VAULT_CLIENT))
+        .map_err(|e| format!("Failed to open MEGA session vault client: {}", e))?;
+
+    client
+        .store()
+        .insert(VAULT_SESSION_KEY.to_vec(), session.as_bytes().to_vec(), None)
+        .map_err(|e| format!("Failed to store MEGA session: {}", e))?;
+
+    vault
+        .inner()
+        .write_client(VAULT_CLIENT)
+        .map_err(|e| format!("Failed to write MEGA session vault client: {}", e))?;
+    vault
+        .save()
+        .map_err(|e| format!("Failed to save MEGA session vault: {}", e))
+}
+
+/// Load the serialized MEGA session from Stronghold, if any
+fn load_session(app_handle: &AppHandle) -> Result<Option<String>, String> {
+    let vault = open_vault(app_handle)?;
+    let Ok(client) = vault.inner().load_client(VAULT_CLIENT) else {
+        return Ok(None);
+    };
+
+    let session = client
+        .store()
+        .get(VAULT_SESSION_KEY)
+        .map_err(|e| format!("Failed to read MEGA session: {}", e))?;
+
+    Ok(session.and_then(|data| String::from_utf8(data).ok()))
+}
+
+/// Remove the persisted MEGA session from Stronghold
+fn forget_session(app_handle: &AppHandle) -> Result<(), String> {
+    let vault = open_vault(app_handle)?;
+    let Ok(client) = vault.inner().load_client(VAULT_CLIENT) else {
+        return Ok(());
+    };
+
+    client
+        .store()
+        .delete(VAULT_SESSION_KEY)
+        .map_err(|e| format!("Failed to delete MEGA session: {}", e))?;
+
+    vault
+        .inner()
+        .write_client(VAULT_CLIENT)
+        .map_err(|e| format!("Failed to write MEGA session vault client: {}", e))?;
+    vault
+        .save()
+        .map_err(|e| format!("Failed to save MEGA session vault: {}", e))
+}
+
 /// Login to MEGA
 #[tauri_crate::command]
 pub async fn mega_login(
     email: String,
     password: String,
     mfa: Option<String>,
+    app_handle: AppHandle,
     state: tauri_crate::State<'_, MegaState>,
 ) -> Result<String, String> {
     let http_client = reqwest::Client::new();
@@ -85,142 +298,626 @@ pub async fn mega_login(
     mega_client.login(&email, &password, mfa_param).await
         .map_err(|e| format!("MEGA login failed: {}", e))?;
 
+    // Persist the session, so the next app start doesn't need to log in again
+    match mega_client.serialize_session().await {
+        Ok(session) => {
+            if let Err(e) = store_session(&app_handle, &session) {
+                tracing::warn!("Failed to persist MEGA session: {}", e);
+            }
+            state.cache.start(app_handle.clone(), session);
+        }
+        Err(e) => tracing::warn!("Failed to serialize MEGA session: {}", e),
+    }
+
     // Store the client in state
-    {
-        let mut client_guard = state.client.lock().unwrap();
-        *client_guard = Some(mega_client);
+    *state.client.write().await = Some(mega_client);
+    *state.logged_in.lock().unwrap() = true;
+
+    Ok("Successfully logged into MEGA".to_string())
+}
+
+/// Resume the MEGA session persisted in Stronghold, returning whether there was one to resume
+#[tauri_crate::command]
+pub async fn mega_resume_session(
+    app_handle: AppHandle,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<bool, String> {
+    if state.client.read().await.is_some() {
+        return Ok(true);
     }
-    
-    {
-        let mut logged_in_guard = state.logged_in.lock().unwrap();
-        *logged_in_guard = true;
+
+    let Some(session) = load_session(&app_handle)? else {
+        return Ok(false);
+    };
+
+    let http_client = reqwest::Client::new();
+    let mut mega_client = mega::Client::builder().build(http_client)
+        .map_err(|e| format!("Failed to create MEGA client: {}", e))?;
+
+    if let Err(e) = mega_client.resume_session(&session).await {
+        // The session got revoked (or expired), a new login is needed
+        tracing::warn!("Failed to resume MEGA session: {}", e);
+        forget_session(&app_handle)?;
+        return Ok(false);
     }
 
-    Ok("Successfully logged into MEGA".to_string())
+    *state.client.write().await = Some(mega_client);
+    *state.logged_in.lock().unwrap() = true;
+    state.cache.start(app_handle, session);
+
+    Ok(true)
 }
 
 /// Logout from MEGA
 #[tauri_crate::command]
-pub async fn mega_logout(state: tauri_crate::State<'_, MegaState>) -> Result<String, String> {
-    // Move client out so we can await safely
-    let client_option = {
-        let mut guard = state.client.lock().unwrap();
-        guard.take()
-    };
+pub async fn mega_logout(
+    app_handle: AppHandle,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<String, String> {
+    // The sync and cache tasks hold their own sessions, which are about to be invalidated
+    if let Some(sync) = state.sync.lock().unwrap().take() {
+        sync.handle.abort();
+    }
+    state.cache.stop(&app_handle).await;
+
+    // Move client out so the state doesn't stay locked while logging out
+    let client_option = state.client.write().await.take();
     if let Some(mut mega_client) = client_option {
         mega_client.logout().await
             .map_err(|e| format!("MEGA logout failed: {}", e))?;
     }
-    {
-        let mut guard = state.client.lock().unwrap();
-        *guard = None;
-    }
-    {
-        let mut logged_in_guard = state.logged_in.lock().unwrap();
-        *logged_in_guard = false;
-    }
+    *state.logged_in.lock().unwrap() = false;
+
+    forget_session(&app_handle)?;
+
     Ok("Successfully logged out from MEGA".to_string())
 }
 
 /// Get complete tree listing
 #[tauri_crate::command]
 pub async fn mega_get_tree_listing(
+    app_handle: AppHandle,
     state: tauri_crate::State<'_, MegaState>
 ) -> Result<MegaTreeResponse, String> {
-    let client = {
-        let mut guard = state.client.lock().unwrap();
-        guard.take().ok_or("Not logged into MEGA. Please login first.")?
-    };
-
-    let nodes = client.fetch_own_nodes().await
-        .map_err(|e| format!("Failed to fetch MEGA nodes: {}", e))?;
-
-    let cloud_drive = nodes.cloud_drive()
-        .map(|node| construct_tree_node(&nodes, node));
-    
-    let inbox = nodes.inbox()
-        .map(|node| construct_tree_node(&nodes, node));
-    
-    let rubbish_bin = nodes.rubbish_bin()
-        .map(|node| construct_tree_node(&nodes, node));
-
-    // Put the (possibly mutated) client back into the state
-    {
-        let mut guard = state.client.lock().unwrap();
-        *guard = Some(client);
-    }
-
-    Ok(MegaTreeResponse {
-        cloud_drive,
-        inbox,
-        rubbish_bin,
-    })
+    load_tree(&app_handle, &state).await
 }
 
 /// Get cloud drive only
 #[tauri_crate::command]
 pub async fn mega_get_cloud_drive(
+    app_handle: AppHandle,
     state: tauri_crate::State<'_, MegaState>
 ) -> Result<Option<MegaNode>, String> {
-    let client = {
-        let mut guard = state.client.lock().unwrap();
-        guard.take().ok_or("Not logged into MEGA. Please login first.")?
-    };
-
-    let nodes = client.fetch_own_nodes().await
-        .map_err(|e| format!("Failed to fetch MEGA nodes: {}", e))?;
-
-    let result = nodes.cloud_drive()
-        .map(|node| construct_tree_node(&nodes, node));
-    {
-        let mut guard = state.client.lock().unwrap();
-        *guard = Some(client);
-    }
-    Ok(result)
+    Ok(load_tree(&app_handle, &state).await?.cloud_drive)
 }
 
 /// Get inbox only
 #[tauri_crate::command]
 pub async fn mega_get_inbox(
+    app_handle: AppHandle,
     state: tauri_crate::State<'_, MegaState>
 ) -> Result<Option<MegaNode>, String> {
-    let client = {
-        let mut guard = state.client.lock().unwrap();
-        guard.take().ok_or("Not logged into MEGA. Please login first.")?
-    };
-
-    let nodes = client.fetch_own_nodes().await
-        .map_err(|e| format!("Failed to fetch MEGA nodes: {}", e))?;
-
-    let result = nodes.inbox()
-        .map(|node| construct_tree_node(&nodes, node));
-    {
-        let mut guard = state.client.lock().unwrap();
-        *guard = Some(client);
-    }
-    Ok(result)
+    Ok(load_tree(&app_handle, &state).await?.inbox)
 }
 
 /// Get rubbish bin only
 #[tauri_crate::command]
 pub async fn mega_get_rubbish_bin(
+    app_handle: AppHandle,
     state: tauri_crate::State<'_, MegaState>
 ) -> Result<Option<MegaNode>, String> {
-    let client = {
-        let mut guard = state.client.lock().unwrap();
-        guard.take().ok_or("Not logged into MEGA. Please login first.")?
+    Ok(load_tree(&app_handle, &state).await?.rubbish_bin)
+}
+
+/// Get the used and total storage of the account
+#[tauri_crate::command]
+pub async fn mega_get_storage_quotas(
+    state: tauri_crate::State<'_, MegaState>
+) -> Result<mega::StorageQuotas, String> {
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+
+    client.get_storage_quotas().await
+        .map_err(|e| format!("Failed to get MEGA storage quotas: {}", e))
+}
+
+/// Upload a local file into a MEGA folder, emitting `mega-transfer-progress` events
+///
+/// Returns the transfer ID used in the progress events. Interrupted uploads resume
+/// when retried with the same file, folder and name.
+#[tauri_crate::command]
+pub async fn mega_upload_file(
+    local_path: String,
+    parent_handle: String,
+    name: Option<String>,
+    transfer_id: Option<String>,
+    app_handle: AppHandle,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<String, String> {
+    let local_path = PathBuf::from(local_path);
+    let name = match name {
+        Some(name) => name,
+        None => local_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or("Invalid local file name")?
+            .to_string(),
+    };
+    let transfer_id = transfer_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+
+    // Cloned, so the node cache isn't locked for the whole upload
+    let parent = state.cache.node(client, &parent_handle).await?;
+    let existing = state.cache.children_named(&parent_handle, &name).await;
+
+    let state_file = mega_data_dir(&app_handle, "mega-transfers")?
+        .join(format!("upload-{}-{}.json", parent_handle, name));
+    let options = mega::TransferOptions {
+        state_file: Some(state_file),
+        ..Default::default()
+    };
+
+    client
+        .upload_node_chunked(
+            &parent,
+            &name,
+            &local_path,
+            mega::LastModified::Now,
+            &options,
+            |progress| emit_progress(&app_handle, &transfer_id, "upload", &name, progress),
+        )
+        .await
+        .map_err(|e| format!("Failed to upload {}: {}", name, e))?;
+
+    // Thumbnails are best-effort: the upload itself already succeeded
+    let attached =
+        attach_images(&app_handle, &state, client, &local_path, &parent_handle, &name, &existing)
+            .await;
+    if let Err(e) = attached {
+        tracing::warn!("Failed to attach thumbnail to {}: {}", name, e);
+    }
+
+    Ok(transfer_id)
+}
+
+/// Generate and upload the thumbnail and preview image of a freshly uploaded image or PDF
+async fn attach_images(
+    app_handle: &AppHandle,
+    state: &MegaState,
+    client: &mega::Client,
+    local_path: &Path,
+    parent_handle: &str,
+    name: &str,
+    existing: &HashSet<String>,
+) -> Result<(), String> {
+    let path = local_path.to_path_buf();
+    let images = tokio::task::spawn_blocking(move || generate_images(&path))
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some((thumbnail, preview)) = images else {
+        return Ok(());
+    };
+
+    let node = state
+        .cache
+        .wait_for_upload(parent_handle, name, existing)
+        .await
+        .ok_or("Uploaded node did not show up in the node cache")?;
+
+    client
+        .upload_thumbnail(&node, thumbnail.len() as u64, futures::io::Cursor::new(&thumbnail))
+        .await
+        .map_err(|e| e.to_string())?;
+    client
+        .upload_preview_image(&node, preview.len() as u64, futures::io::Cursor::new(&preview))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    store_image(app_handle, node.handle(), MegaImageKind::Thumbnail, &thumbnail);
+    store_image(app_handle, node.handle(), MegaImageKind::Preview, &preview);
+    Ok(())
+}
+
+/// Queue the download of a MEGA file into a local file in the download manager
+///
+/// Returns the ID of the download, which reports its progress as `download-progress`.
+/// Without a local path, the file is saved into the default download directory.
+#[tauri_crate::command]
+pub async fn mega_download_file(
+    handle: String,
+    local_path: Option<String>,
+    app_handle: AppHandle,
+    state: tauri_crate::State<'_, MegaState>,
+    downloads: tauri_crate::State<'_, DownloadManager>,
+) -> Result<String, String> {
+    let name = {
+        let client_guard = state.client.read().await;
+        let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+        state.cache.node(client, &handle).await?.name().to_string()
     };
 
-    let nodes = client.fetch_own_nodes().await
-        .map_err(|e| format!("Failed to fetch MEGA nodes: {}", e))?;
+    let download = downloads.enqueue(
+        &app_handle,
+        DownloadRequest {
+            source: DownloadSource::Mega { handle },
+            file_name: name,
+            destination: local_path,
+            checksum: None,
+        },
+    )?;
+
+    Ok(download.id)
+}
+
+/// File persisting the state of a chunked MEGA download, to resume it
+pub(crate) fn download_state_file(app_handle: &AppHandle, handle: &str) -> Result<PathBuf, String> {
+    Ok(mega_data_dir(app_handle, "mega-transfers")?.join(format!("download-{}.json", handle)))
+}
+
+/// Download a MEGA file into a local file, on behalf of the download manager
+///
+/// Interrupted downloads resume when retried with the same node and local file.
+/// The condensed MAC of the file is verified once it is complete.
+pub(crate) async fn download_node_to<F>(
+    app_handle: &AppHandle,
+    handle: &str,
+    local_path: &Path,
+    progress: F,
+) -> Result<(), String>
+where
+    F: Fn(mega::TransferProgress) + Sync,
+{
+    let state = app_handle.state::<MegaState>();
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+
+    // Cloned, so the node cache isn't locked for the whole download
+    let node = state.cache.node(client, handle).await?;
+
+    let options = mega::TransferOptions {
+        state_file: Some(download_state_file(app_handle, handle)?),
+        ..Default::default()
+    };
+
+    client
+        .download_node_chunked(&node, local_path, &options, progress)
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", node.name(), e))
+}
+
+/// Emit a transfer progress event to the frontend
+fn emit_progress(
+    app_handle: &AppHandle,
+    transfer_id: &str,
+    direction: &str,
+    name: &str,
+    progress: mega::TransferProgress,
+) {
+    let event = MegaTransferProgress {
+        transfer_id: transfer_id.to_string(),
+        direction: direction.to_string(),
+        name: name.to_string(),
+        transferred: progress.transferred,
+        total: progress.total,
+    };
+    if let Err(e) = app_handle.emit("mega-transfer-progress", event) {
+        tracing::warn!("Failed to emit MEGA transfer progress: {}", e);
+    }
+}
+
+/// Create a folder inside a MEGA folder
+#[tauri_crate::command]
+pub async fn mega_create_folder(
+    parent_handle: String,
+    name: String,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<String, String> {
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+
+    let nodes = state.cache.nodes(client).await?;
+    let parent = find_node(&nodes, &parent_handle)?;
+
+    client.create_folder(parent, &name).await
+        .map_err(|e| format!("Failed to create folder {}: {}", name, e))?;
+
+    Ok(format!("Created folder {}", name))
+}
+
+/// Rename a MEGA node
+#[tauri_crate::command]
+pub async fn mega_rename_node(
+    handle: String,
+    name: String,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<String, String> {
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+
+    let nodes = state.cache.nodes(client).await?;
+    let node = find_node(&nodes, &handle)?;
+
+    client.rename_node(node, &name).await
+        .map_err(|e| format!("Failed to rename {}: {}", node.name(), e))?;
 
-    let result = nodes.rubbish_bin()
-        .map(|node| construct_tree_node(&nodes, node));
-    {
-        let mut guard = state.client.lock().unwrap();
-        *guard = Some(client);
+    Ok(format!("Renamed {} to {}", node.name(), name))
+}
+
+/// Move a MEGA node into another folder
+#[tauri_crate::command]
+pub async fn mega_move_node(
+    handle: String,
+    parent_handle: String,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<String, String> {
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+
+    let nodes = state.cache.nodes(client).await?;
+    let node = find_node(&nodes, &handle)?;
+    let parent = find_node(&nodes, &parent_handle)?;
+
+    client.move_node(node, parent).await
+        .map_err(|e| format!("Failed to move {}: {}", node.name(), e))?;
+
+    Ok(format!("Moved {} to {}", node.name(), parent.name()))
+}
+
+/// Delete a MEGA node, moving it to the rubbish bin unless `permanent` is set
+/// (nodes already in the rubbish bin are always deleted permanently)
+#[tauri_crate::command]
+pub async fn mega_delete_node(
+    handle: String,
+    permanent: Option<bool>,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<String, String> {
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+
+    let nodes = state.cache.nodes(client).await?;
+    let node = find_node(&nodes, &handle)?;
+
+    if !permanent.unwrap_or(false) && !nodes.is_in_rubbish_bin(node) {
+        // Remembers the node's location, so that it can be restored there later
+        client.move_to_rubbish_bin(&nodes, node).await
+            .map_err(|e| format!("Failed to move {} to the rubbish bin: {}", node.name(), e))?;
+        Ok(format!("Moved {} to the rubbish bin", node.name()))
+    } else {
+        client.delete_node(node).await
+            .map_err(|e| format!("Failed to delete {}: {}", node.name(), e))?;
+        Ok(format!("Deleted {}", node.name()))
+    }
+}
+
+/// Restore a node from the rubbish bin, into the given folder or else where it was deleted from
+#[tauri_crate::command]
+pub async fn mega_restore_node(
+    handle: String,
+    parent_handle: Option<String>,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<String, String> {
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+
+    let nodes = state.cache.nodes(client).await?;
+    let node = find_node(&nodes, &handle)?;
+
+    if !nodes.is_in_rubbish_bin(node) {
+        return Err(format!("{} is not in the rubbish bin", node.name()));
+    }
+
+    match parent_handle.as_deref() {
+        Some(parent_handle) => {
+            let parent = find_node(&nodes, parent_handle)?;
+            client.move_node(node, parent).await
+                .map_err(|e| format!("Failed to restore {}: {}", node.name(), e))?;
+            Ok(format!("Restored {} to {}", node.name(), parent.name()))
+        }
+        None => {
+            client.restore_node(&nodes, node).await
+                .map_err(|e| format!("Failed to restore {}: {}", node.name(), e))?;
+            Ok(format!("Restored {}", node.name()))
+        }
     }
-    Ok(result)
+}
+
+/// Get the thumbnail of a MEGA node, as a data URL
+#[tauri_crate::command]
+pub async fn mega_get_thumbnail(
+    handle: String,
+    app_handle: AppHandle,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<String, String> {
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+
+    let node = state.cache.node(client, &handle).await?;
+    let data = cached_image(&app_handle, client, &node, MegaImageKind::Thumbnail).await?;
+
+    Ok(format!("data:image/jpeg;base64,{}", BASE64.encode(&data)))
+}
+
+/// Get the preview image of a MEGA node, as a data URL
+#[tauri_crate::command]
+pub async fn mega_get_preview_image(
+    handle: String,
+    app_handle: AppHandle,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<String, String> {
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+
+    let node = state.cache.node(client, &handle).await?;
+    let data = cached_image(&app_handle, client, &node, MegaImageKind::Preview).await?;
+
+    Ok(format!("data:image/jpeg;base64,{}", BASE64.encode(&data)))
+}
+
+/// Set the thumbnail of a MEGA node from a local (JPEG) image
+#[tauri_crate::command]
+pub async fn mega_upload_thumbnail(
+    handle: String,
+    image_path: String,
+    app_handle: AppHandle,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<String, String> {
+    let data = std::fs::read(&image_path)
+        .map_err(|e| format!("Failed to read {}: {}", image_path, e))?;
+
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+
+    let nodes = state.cache.nodes(client).await?;
+    let node = find_node(&nodes, &handle)?;
+
+    let size = data.len() as u64;
+    client.upload_thumbnail(node, size, futures::io::Cursor::new(&data)).await
+        .map_err(|e| format!("Failed to upload thumbnail of {}: {}", node.name(), e))?;
+    store_image(&app_handle, node.handle(), MegaImageKind::Thumbnail, &data);
+
+    Ok(format!("Uploaded thumbnail of {}", node.name()))
+}
+
+/// Set the preview image of a MEGA node from a local (JPEG) image
+#[tauri_crate::command]
+pub async fn mega_upload_preview_image(
+    handle: String,
+    image_path: String,
+    app_handle: AppHandle,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<String, String> {
+    let data = std::fs::read(&image_path)
+        .map_err(|e| format!("Failed to read {}: {}", image_path, e))?;
+
+    let client_guard = state.client.read().await;
+    let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+
+    let nodes = state.cache.nodes(client).await?;
+    let node = find_node(&nodes, &handle)?;
+
+    let size = data.len() as u64;
+    client.upload_preview_image(node, size, futures::io::Cursor::new(&data)).await
+        .map_err(|e| format!("Failed to upload preview image of {}: {}", node.name(), e))?;
+    store_image(&app_handle, node.handle(), MegaImageKind::Preview, &data);
+
+    Ok(format!("Uploaded preview image of {}", node.name()))
+}
+
+/// Keep a local directory and a MEGA folder in sync until the task gets aborted
+async fn run_sync(
+    app_handle: AppHandle,
+    session: String,
+    local_path: PathBuf,
+    remote_path: String,
+    state_file: PathBuf,
+) -> mega::Result<()> {
+    // The sync task gets its own client, so it never holds the shared one across long waits
+    let http_client = reqwest::Client::new();
+    let mut client = mega::Client::builder().build(http_client)?;
+    client.resume_session(&session).await?;
+
+    let mut nodes = client.fetch_own_nodes().await?;
+    let remote_root = nodes
+        .get_node_by_path(&remote_path)
+        .ok_or(mega::Error::NodeNotFound)?;
+
+    let options = mega::SyncOptions::new(state_file);
+    let mut engine = mega::SyncEngine::new(local_path, remote_root, options)?;
+
+    engine
+        .run(&client, &mut nodes, |report| {
+            if let Err(e) = app_handle.emit("mega-sync", MegaSyncReport::from(report)) {
+                tracing::warn!("Failed to emit MEGA sync report: {}", e);
+            }
+        })
+        .await
+}
+
+/// Start syncing a local directory with a MEGA folder in the background
+#[tauri_crate::command]
+pub async fn mega_start_sync(
+    local_path: String,
+    remote_path: String,
+    app_handle: AppHandle,
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<String, String> {
+    let session = {
+        let client_guard = state.client.read().await;
+        let client = client_guard.as_ref().ok_or(NOT_LOGGED_IN)?;
+        client.serialize_session().await
+            .map_err(|e| format!("Failed to serialize MEGA session: {}", e))?
+    };
+
+    let state_file = mega_data_dir(&app_handle, "mega-sync")?.join("state.json");
+
+    let handle = {
+        let app_handle = app_handle.clone();
+        let local_path = PathBuf::from(&local_path);
+        let remote_path = remote_path.clone();
+        tokio::spawn(async move {
+            let result = run_sync(
+                app_handle.clone(),
+                session,
+                local_path,
+                remote_path,
+                state_file,
+            )
+            .await;
+
+            if let Err(e) = result {
+                tracing::error!("MEGA folder sync stopped: {}", e);
+                let _ = app_handle.emit("mega-sync-error", e.to_string());
+            }
+        })
+    };
+
+    // Only a single folder is synced at a time
+    let previous = state.sync.lock().unwrap().replace(MegaSync {
+        local_path: local_path.clone(),
+        remote_path: remote_path.clone(),
+        handle,
+    });
+    if let Some(previous) = previous {
+        previous.handle.abort();
+    }
+
+    tracing::info!("Started MEGA folder sync: {} <-> {}", local_path, remote_path);
+    Ok(format!("Started syncing {} with {}", local_path, remote_path))
+}
+
+/// Stop the background folder sync
+#[tauri_crate::command]
+pub async fn mega_stop_sync(state: tauri_crate::State<'_, MegaState>) -> Result<String, String> {
+    match state.sync.lock().unwrap().take() {
+        Some(sync) => {
+            sync.handle.abort();
+            tracing::info!("Stopped MEGA folder sync");
+            Ok("Stopped MEGA folder sync".to_string())
+        }
+        None => Ok("MEGA folder sync is not running".to_string()),
+    }
+}
+
+/// Get the status of the background folder sync
+#[tauri_crate::command]
+pub async fn mega_get_sync_status(
+    state: tauri_crate::State<'_, MegaState>,
+) -> Result<MegaSyncStatus, String> {
+    let guard = state.sync.lock().unwrap();
+    Ok(match guard.as_ref() {
+        Some(sync) => MegaSyncStatus {
+            running: !sync.handle.is_finished(),
+            local_path: Some(sync.local_path.clone()),
+            remote_path: Some(sync.remote_path.clone()),
+        },
+        None => MegaSyncStatus {
+            running: false,
+            local_path: None,
+            remote_path: None,
+        },
+    })
 }
 
 // Legacy standalone example code (for reference/testing)
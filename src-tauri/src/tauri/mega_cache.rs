@@ -0,0 +1,347 @@
+//!
+//! Local cache of the MEGA node tree and of node thumbnails/preview images
+//! Keeps the tree current through the events API, so browsing works offline
+//!
+
+use image::DynamicImage;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri_crate::{AppHandle, Emitter};
+use tokio::sync::{RwLock, RwLockReadGuard};
+use tokio::task::JoinHandle;
+
+use super::mega::{build_tree_response, mega_data_dir, MegaTreeResponse};
+
+/// File (in the MEGA data directory) holding the last known tree
+const SNAPSHOT_FILE: &str = "tree.json";
+/// Delay before reloading the tree after losing the event stream (e.g. while offline)
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+/// How long to wait for an uploaded node to come back through the events API
+const UPLOAD_EVENT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Size (in pixels) of the square thumbnails, like the ones of the official MEGA clients
+const THUMBNAIL_SIZE: u32 = 120;
+/// Maximum size (in pixels) of the longest side of preview images
+const PREVIEW_SIZE: u32 = 1000;
+
+/// Kind of node image kept in the disk cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MegaImageKind {
+    Thumbnail,
+    Preview,
+}
+
+impl MegaImageKind {
+    fn cache_dir(self) -> &'static str {
+        match self {
+            MegaImageKind::Thumbnail => "mega/thumbnails",
+            MegaImageKind::Preview => "mega/previews",
+        }
+    }
+}
+
+/// Live MEGA node tree, kept current through the events API
+#[derive(Default)]
+pub struct MegaCache {
+    nodes: RwLock<Option<mega::Nodes>>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl MegaCache {
+    /// Get the cached nodes without touching the network
+    pub async fn cached(&self) -> Option<RwLockReadGuard<'_, mega::Nodes>> {
+        RwLockReadGuard::try_map(self.nodes.read().await, Option::as_ref).ok()
+    }
+
+    /// Get the cached nodes, fetching them first if the cache isn't loaded yet
+    pub async fn nodes(
+        &self,
+        client: &mega::Client,
+    ) -> Result<RwLockReadGuard<'_, mega::Nodes>, String> {
+        if let Some(nodes) = self.cached().await {
+            return Ok(nodes);
+        }
+
+        let nodes = client
+            .fetch_own_nodes()
+            .await
+            .map_err(|e| format!("Failed to fetch MEGA nodes: {}", e))?;
+
+        let mut guard = self.nodes.write().await;
+        guard.get_or_insert(nodes);
+        RwLockReadGuard::try_map(guard.downgrade(), Option::as_ref)
+            .map_err(|_| "MEGA node cache is empty".to_string())
+    }
+
+    /// Find a node by its handle
+    ///
+    /// The node gets cloned, so the cache doesn't stay locked during long transfers.
+    pub async fn node(&self, client: &mega::Client, handle: &str) -> Result<mega::Node, String> {
+        let nodes = self.nodes(client).await?;
+        nodes
+            .get_node_by_handle(handle)
+            .cloned()
+            .ok_or_else(|| format!("MEGA node not found: {}", handle))
+    }
+
+    /// Handles of the children of a folder with the given name
+    pub async fn children_named(&self, parent_handle: &str, name: &str) -> HashSet<String> {
+        let Some(nodes) = self.cached().await else {
+            return HashSet::new();
+        };
+
+        nodes
+            .get_node_by_handle(parent_handle)
+            .map(|parent| {
+                parent
+                    .children()
+                    .iter()
+                    .filter_map(|handle| nodes.get_node_by_handle(handle))
+                    .filter(|node| node.name() == name)
+                    .map(|node| node.handle().to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Wait for a freshly uploaded node to come back through the events API
+    ///
+    /// `existing` holds the handles of the children with the same name from before the upload.
+    pub async fn wait_for_upload(
+        &self,
+        parent_handle: &str,
+        name: &str,
+        existing: &HashSet<String>,
+    ) -> Option<mega::Node> {
+        let deadline = tokio::time::Instant::now() + UPLOAD_EVENT_TIMEOUT;
+
+        while tokio::time::Instant::now() < deadline {
+            let uploaded = self
+                .children_named(parent_handle, name)
+                .await
+                .into_iter()
+                .find(|handle| !existing.contains(handle));
+
+            if let Some(handle) = uploaded {
+                let nodes = self.cached().await?;
+                return nodes.get_node_by_handle(&handle).cloned();
+            }
+
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+
+        None
+    }
+
+    /// Start keeping the tree current in the background, with its own session
+    pub fn start(self: &Arc<Self>, app_handle: AppHandle, session: String) {
+        let cache = Arc::clone(self);
+        let handle = tokio::spawn(async move { cache.run(app_handle, session).await });
+
+        if let Some(previous) = self.task.lock().unwrap().replace(handle) {
+            previous.abort();
+        }
+    }
+
+    /// Stop updating the tree and forget everything cached about the account (on logout)
+    pub async fn stop(&self, app_handle: &AppHandle) {
+        if let Some(task) = self.task.lock().unwrap().take() {
+            task.abort();
+        }
+        self.nodes.write().await.take();
+
+        if let Ok(dir) = mega_data_dir(app_handle, "mega") {
+            let _ = std::fs::remove_file(dir.join(SNAPSHOT_FILE));
+        }
+        for kind in [MegaImageKind::Thumbnail, MegaImageKind::Preview] {
+            if let Ok(dir) = mega_data_dir(app_handle, kind.cache_dir()) {
+                let _ = std::fs::remove_dir_all(dir);
+            }
+        }
+    }
+
+    async fn run(&self, app_handle: AppHandle, session: String) {
+        let http_client = reqwest::Client::new();
+        let mut client = match mega::Client::builder().build(http_client) {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::error!("Failed to create MEGA client for the node cache: {}", e);
+                return;
+            }
+        };
+
+        loop {
+            if let Err(e) = self.follow_events(&mut client, &app_handle, &session).await {
+                tracing::warn!("MEGA node cache lost the event stream: {}", e);
+            }
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+    }
+
+    /// Load the whole tree, then apply the events from MEGA as they come in
+    async fn follow_events(
+        &self,
+        client: &mut mega::Client,
+        app_handle: &AppHandle,
+        session: &str,
+    ) -> mega::Result<()> {
+        if !client.has_user_session() {
+            client.resume_session(session).await?;
+        }
+
+        let nodes = client.fetch_own_nodes().await?;
+        *self.nodes.write().await = Some(nodes);
+        self.publish(app_handle).await;
+
+        loop {
+            // Readers can still browse the tree while waiting for events
+            let events = {
+                let guard = self.nodes.read().await;
+                let Some(nodes) = guard.as_ref() else {
+                    return Ok(());
+                };
+                client.wait_events(nodes).await?
+            };
+
+            if let Some(nodes) = self.nodes.write().await.as_mut() {
+                nodes.apply_events(events)?;
+            }
+            self.publish(app_handle).await;
+        }
+    }
+
+    /// Persist the tree snapshot and let the frontend know the tree changed
+    async fn publish(&self, app_handle: &AppHandle) {
+        let tree = match self.nodes.read().await.as_ref() {
+            Some(nodes) => build_tree_response(nodes),
+            None => return,
+        };
+
+        if let Err(e) = save_snapshot(app_handle, &tree) {
+            tracing::warn!("Failed to save MEGA tree snapshot: {}", e);
+        }
+        if let Err(e) = app_handle.emit("mega-tree-updated", ()) {
+            tracing::warn!("Failed to emit MEGA tree update: {}", e);
+        }
+    }
+}
+
+/// Load the last known tree, for browsing before the live tree is loaded (or while offline)
+pub fn load_snapshot(app_handle: &AppHandle) -> Option<MegaTreeResponse> {
+    let path = mega_data_dir(app_handle, "mega").ok()?.join(SNAPSHOT_FILE);
+    let data = std::fs::read(path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+fn save_snapshot(app_handle: &AppHandle, tree: &MegaTreeResponse) -> Result<(), String> {
+    let dir = mega_data_dir(app_handle, "mega")?;
+    let data =
+        serde_json::to_vec(tree).map_err(|e| format!("Failed to serialize MEGA tree: {}", e))?;
+
+    // Write to a temporary file first, so a crash never leaves a truncated snapshot
+    let temp_path = dir.join(format!("{}.tmp", SNAPSHOT_FILE));
+    std::fs::write(&temp_path, data)
+        .map_err(|e| format!("Failed to write MEGA tree snapshot: {}", e))?;
+    std::fs::rename(&temp_path, dir.join(SNAPSHOT_FILE))
+        .map_err(|e| format!("Failed to write MEGA tree snapshot: {}", e))
+}
+
+/// Get a node's thumbnail or preview image, from the disk cache or else from MEGA
+pub async fn cached_image(
+    app_handle: &AppHandle,
+    client: &mega::Client,
+    node: &mega::Node,
+    kind: MegaImageKind,
+) -> Result<Vec<u8>, String> {
+    // Handles change with every new version of a file, so cached images never go stale
+    let path = mega_data_dir(app_handle, kind.cache_dir())?.join(format!("{}.jpg", node.handle()));
+    if let Ok(data) = std::fs::read(&path) {
+        return Ok(data);
+    }
+
+    let mut data = Vec::new();
+    let result = match kind {
+        MegaImageKind::Thumbnail => client.download_thumbnail(node, &mut data).await,
+        MegaImageKind::Preview => client.download_preview_image(node, &mut data).await,
+    };
+    result.map_err(|e| format!("Failed to download image of {}: {}", node.name(), e))?;
+
+    store_image(app_handle, node.handle(), kind, &data);
+    Ok(data)
+}
+
+/// Put a node's thumbnail or preview image into the disk cache
+pub fn store_image(app_handle: &AppHandle, handle: &str, kind: MegaImageKind, data: &[u8]) {
+    let result = mega_data_dir(app_handle, kind.cache_dir()).and_then(|dir| {
+        std::fs::write(dir.join(format!("{}.jpg", handle)), data).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        tracing::warn!("Failed to cache MEGA image of {}: {}", handle, e);
+    }
+}
+
+/// Generate the thumbnail and preview image (as JPEG) of an image or PDF file
+///
+/// Returns `None` for other files, or if the file couldn't be decoded.
+pub fn generate_images(path: &Path) -> Option<(Vec<u8>, Vec<u8>)> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    let image = match extension.as_str() {
+        "pdf" => render_pdf_first_page(path)?,
+        _ => {
+            image::ImageFormat::from_extension(&extension)?;
+            image::open(path).ok()?
+        }
+    };
+
+    // Thumbnails are center-cropped squares
+    let thumbnail = {
+        let side = image.width().min(image.height());
+        let x = (image.width() - side) / 2;
+        let y = (image.height() - side) / 2;
+        image
+            .crop_imm(x, y, side, side)
+            .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+    };
+
+    let preview = if image.width() > PREVIEW_SIZE || image.height() > PREVIEW_SIZE {
+        image.thumbnail(PREVIEW_SIZE, PREVIEW_SIZE)
+    } else {
+        image
+    };
+
+    Some((encode_jpeg(&thumbnail)?, encode_jpeg(&preview)?))
+}
+
+fn encode_jpeg(image: &DynamicImage) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    DynamicImage::ImageRgb8(image.to_rgb8())
+        .write_to(
+            &mut std::io::Cursor::new(&mut data),
+            image::ImageFormat::Jpeg,
+        )
+        .ok()?;
+    Some(data)
+}
+
+/// Render the first page of a PDF with poppler's `pdftoppm` (PDFs get no images without it)
+fn render_pdf_first_page(path: &Path) -> Option<DynamicImage> {
+    let output = std::env::temp_dir().join(format!("mega-pdf-{}", uuid::Uuid::new_v4()));
+
+    let status = std::process::Command::new("pdftoppm")
+        .args(["-jpeg", "-f", "1", "-l", "1", "-singlefile", "-scale-to"])
+        .arg(PREVIEW_SIZE.to_string())
+        .arg(path)
+        .arg(&output)
+        .status()
+        .ok()?;
+
+    let rendered = output.with_extension("jpg");
+    let image = if status.success() {
+        image::open(&rendered).ok()
+    } else {
+        None
+    };
+    let _ = std::fs::remove_file(&rendered);
+    image
+}
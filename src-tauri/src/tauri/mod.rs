@@ -6,12 +6,22 @@
 //! This module provides Tauri-compatible functions for the CLI client,
 //! including user account management functionality.
 
+pub mod config;
+pub mod downloads;
+pub mod encryption;
 pub mod group;
 pub mod qaul;
 pub mod user;
 pub mod rtc;
+pub mod rtc_recording;
 pub mod timetable;
 pub mod mega;
+pub mod mega_cache;
 pub mod zotero;
+pub mod zotero_api;
+pub mod zotero_bibliography;
+pub mod zotero_complete;
+#[cfg(test)]
+mod zotero_mock;
 pub mod task;
 pub mod ble;
\ No newline at end of file
@@ -207,10 +207,20 @@ async fn start_qaul_node<R: Runtime>(app: &AppHandle<R>) {
     //libqaul::api::start_with_config(storage_path, None);
 
     // Wait for node to be ready
+    // an encrypted storage needs to be unlocked by the UI first
+    let mut locked_emitted = false;
     while !libqaul::api::initialization_finished() {
+        if !locked_emitted && libqaul::api::storage_locked() {
+            let _ = app.emit("qaul-storage-locked", ());
+            locked_emitted = true;
+        }
         async_std::task::sleep(std::time::Duration::from_millis(10)).await;
     }
 
+    // relay the sys BLE messages of libqaul to BlueZ
+    #[cfg(target_os = "linux")]
+    crate::bluez::spawn();
+
     // initialize user accounts
     UserAccounts::init();
     // Initialize active users in router
@@ -5,55 +5,178 @@
 //!
 //! Tauri command wrappers for qaul RTC functionality with proper
 //! SDP offer/answer exchange for WebRTC connections using str0m.
+//!
+//! Host candidates of all network interfaces are put into the SDP.
+//! Server reflexive candidates of the configured STUN server are
+//! trickled afterwards via qaul, and ICE is restarted when the
+//! network changes.
 
 use ::tauri::{command, AppHandle, Emitter};
 use libqaul::services::rtc;
 use libqaul::node::user_accounts::UserAccounts;
 use serde::{Deserialize, Serialize};
-use std::net::UdpSocket;
-use std::sync::{Arc, RwLock};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, Once, RwLock};
+use std::time::Duration;
 use once_cell::sync::Lazy;
+use str0m::{Candidate, Rtc};
+use crate::str0m::chat::SfuRoom;
+use crate::str0m::ice;
+use crate::str0m::share::{FileProgress, ScreenOptions, ScreenSource, ShareCallbacks, ShareKind, ShareSession};
 
 // Global str0m RTC instance for WebRTC connections
 static STR0M_MANAGER: Lazy<RwLock<Option<Str0mManager>>> = Lazy::new(|| RwLock::new(None));
 
+// SFU rooms of the group calls hosted on this node, by group id
+static SFU_ROOMS: Lazy<Mutex<HashMap<Vec<u8>, SfuRoom>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// ICE configuration
+static ICE_CONFIG: Lazy<RwLock<RtcIceConfig>> = Lazy::new(|| RwLock::new(RtcIceConfig::default()));
+
+// Sessions whose SDP was created by the str0m manager, by group id
+//
+// Only these sessions are restarted automatically on network changes,
+// calls negotiated by the frontend restart ICE themselves.
+static STR0M_SESSIONS: Lazy<Mutex<HashSet<Vec<u8>>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+// Our screen and file shares, by share id
+static SHARE_SESSIONS: Lazy<Mutex<HashMap<String, RtcShareEntry>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Network monitor is started once
+static NETWORK_MONITOR: Once = Once::new();
+
+/// Interval in which the network interfaces are checked for changes
+const NETWORK_MONITOR_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Timeout of a STUN binding request
+const STUN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// ICE configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RtcIceConfig {
+    /// STUN server for server reflexive candidates, e.g. `stun.example.org:3478`
+    ///
+    /// Without a STUN server only host candidates are used,
+    /// which is sufficient within a LAN.
+    pub stun_server: Option<String>,
+}
+
+/// Screen share options
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RtcScreenShareOptions {
+    /// index of the screen, the first screen by default
+    pub screen: Option<usize>,
+    /// share the active window instead of a screen
+    #[serde(default)]
+    pub active_window: bool,
+    /// frames per second, 5 by default
+    pub fps: Option<u32>,
+    /// JPEG quality 1-100, 60 by default
+    pub quality: Option<u8>,
+}
+
+/// Result of sending a file to the other side of a call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RtcFileSendResult {
+    /// share streaming the file, `None` if it was sent via chat
+    pub share_id: Option<String>,
+    /// the file was sent via chat, because there is no established call
+    pub fallback: bool,
+}
+
+/// One of our shares
+struct RtcShareEntry {
+    group_id: Vec<u8>,
+    files: bool,
+    session: ShareSession,
+}
+
 /// str0m WebRTC connection manager
+#[derive(Clone)]
 pub struct Str0mManager {
-    /// UDP socket for WebRTC traffic
-    pub socket: Arc<UdpSocket>,
-    /// Local address for ICE candidates
-    pub local_addr: std::net::SocketAddr,
+    /// UDP sockets for WebRTC traffic, one per network interface
+    pub sockets: Vec<Arc<UdpSocket>>,
+    /// Local addresses for host ICE candidates
+    pub local_addrs: Vec<SocketAddr>,
 }
 
 impl Str0mManager {
-    /// Create a new str0m manager with a bound UDP socket
+    /// Create a new str0m manager with a bound UDP socket per network interface
     pub fn new() -> Result<Self, String> {
-        let host_addr = crate::str0m::util::select_host_address();
-        let socket = UdpSocket::bind(format!("{}:0", host_addr))
-            .map_err(|e| format!("Failed to bind UDP socket: {}", e))?;
-        let local_addr = socket.local_addr()
-            .map_err(|e| format!("Failed to get local address: {}", e))?;
-        
-        log::info!("str0m manager initialized on {}", local_addr);
-        
+        let mut sockets = Vec::new();
+        let mut local_addrs = Vec::new();
+
+        for ip in ice::interface_addresses() {
+            match UdpSocket::bind(SocketAddr::new(ip, 0)).and_then(|socket| {
+                let local_addr = socket.local_addr()?;
+                Ok((socket, local_addr))
+            }) {
+                Ok((socket, local_addr)) => {
+                    sockets.push(Arc::new(socket));
+                    local_addrs.push(local_addr);
+                }
+                Err(e) => log::warn!("Failed to bind UDP socket on {}: {}", ip, e),
+            }
+        }
+        if sockets.is_empty() {
+            return Err("Failed to bind UDP socket on any network interface".to_string());
+        }
+
+        log::info!("str0m manager initialized on {:?}", local_addrs);
+
         Ok(Self {
-            socket: Arc::new(socket),
-            local_addr,
+            sockets,
+            local_addrs,
         })
     }
 
+    /// IP addresses of the network interfaces the manager is bound to
+    pub fn addresses(&self) -> Vec<IpAddr> {
+        self.local_addrs.iter().map(|addr| addr.ip()).collect()
+    }
+
+    /// Add a host candidate of every network interface
+    fn add_host_candidates(&self, rtc: &mut Rtc) -> Result<(), String> {
+        for addr in &self.local_addrs {
+            let candidate = Candidate::host(*addr, "udp")
+                .map_err(|e| format!("Failed to create ICE candidate: {:?}", e))?;
+            rtc.add_local_candidate(candidate);
+        }
+        Ok(())
+    }
+
+    /// Request the server reflexive candidates from a STUN server
+    ///
+    /// Sockets without a public mapping, or whose STUN request
+    /// fails, are skipped.
+    pub fn gather_srflx_candidates(&self, stun_server: &str) -> Vec<Candidate> {
+        let mut candidates = Vec::new();
+
+        for (socket, base) in self.sockets.iter().zip(self.local_addrs.iter()) {
+            match ice::stun_binding(socket, stun_server, STUN_TIMEOUT) {
+                Ok(mapped) if mapped != *base => {
+                    match Candidate::server_reflexive(mapped, *base, "udp") {
+                        Ok(candidate) => candidates.push(candidate),
+                        Err(e) => log::warn!("Failed to create srflx candidate: {:?}", e),
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => log::debug!("STUN request from {} failed: {}", base, e),
+            }
+        }
+
+        candidates
+    }
+
     /// Generate an SDP offer for initiating a call
     pub fn create_offer(&self, session_type: u32) -> Result<String, String> {
-        use str0m::Rtc;
-        use str0m::Candidate;
         use str0m::media::Direction;
         
         let mut rtc = Rtc::builder().build();
         
-        // Add local ICE candidate
-        let candidate = Candidate::host(self.local_addr, "udp")
-            .map_err(|e| format!("Failed to create ICE candidate: {:?}", e))?;
-        rtc.add_local_candidate(candidate);
+        // Add local ICE candidates
+        self.add_host_candidates(&mut rtc)?;
         
         // Add media based on session type
         let mut sdp_api = rtc.sdp_api();
@@ -95,8 +218,6 @@ impl Str0mManager {
 
     /// Process an SDP offer and generate an answer (for callee)
     pub fn create_answer(&self, offer_json: &str) -> Result<String, String> {
-        use str0m::Rtc;
-        use str0m::Candidate;
         use str0m::change::SdpOffer;
         
         // Parse the incoming offer
@@ -105,10 +226,8 @@ impl Str0mManager {
         
         let mut rtc = Rtc::builder().build();
         
-        // Add local ICE candidate
-        let candidate = Candidate::host(self.local_addr, "udp")
-            .map_err(|e| format!("Failed to create ICE candidate: {:?}", e))?;
-        rtc.add_local_candidate(candidate);
+        // Add local ICE candidates
+        self.add_host_candidates(&mut rtc)?;
         
         // Accept the offer and generate answer
         let answer = rtc.sdp_api()
@@ -136,6 +255,87 @@ pub struct RtcSessionInfo {
     pub sdp_offer: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sdp_answer: Option<String>,
+    /// host of a group call, empty for direct calls
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub host_id: String,
+    /// participants of a group call
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub participants: Vec<RtcParticipantInfo>,
+    /// ICE candidates received from the other side
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remote_candidates: Vec<RtcIceCandidateInfo>,
+    /// users recording the call
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recorders: Vec<String>,
+}
+
+impl From<rtc::RtcSession> for RtcSessionInfo {
+    fn from(session: rtc::RtcSession) -> Self {
+        Self {
+            group_id: bs58::encode(&session.group_id).into_string(),
+            session_type: session.session_type,
+            state: session.state as u32,
+            created_at: session.created_at,
+            host_id: match session.host_id.is_empty() {
+                true => String::new(),
+                false => bs58::encode(&session.host_id).into_string(),
+            },
+            participants: session
+                .participants
+                .values()
+                .map(|participant| RtcParticipantInfo {
+                    user_id: bs58::encode(&participant.user_id).into_string(),
+                    audio_muted: participant.audio_muted,
+                    video_muted: participant.video_muted,
+                })
+                .collect(),
+            remote_candidates: session
+                .remote_candidates
+                .iter()
+                .map(|candidate| RtcIceCandidateInfo {
+                    group_id: bs58::encode(&session.group_id).into_string(),
+                    candidate: candidate.candidate.clone(),
+                    sdp_mid: candidate.sdp_mid.clone(),
+                    sdp_mline_index: candidate.sdp_mline_index,
+                    ufrag: candidate.ufrag.clone(),
+                    end_of_candidates: candidate.end_of_candidates,
+                })
+                .collect(),
+            recorders: session
+                .recorders
+                .iter()
+                .map(|recorder| bs58::encode(recorder).into_string())
+                .collect(),
+            sdp_offer: session.sdp_offer,
+            sdp_answer: session.sdp_answer,
+        }
+    }
+}
+
+/// Participant of a group call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RtcParticipantInfo {
+    pub user_id: String,
+    pub audio_muted: bool,
+    pub video_muted: bool,
+}
+
+/// ICE candidate for trickle ICE
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RtcIceCandidateInfo {
+    pub group_id: String,
+    /// ICE candidate string, e.g. `candidate:1 1 udp 2130706431 192.168.1.2 50000 typ host`
+    pub candidate: String,
+    #[serde(default)]
+    pub sdp_mid: String,
+    #[serde(default)]
+    pub sdp_mline_index: u32,
+    /// ICE username fragment of the session the candidate belongs to
+    #[serde(default)]
+    pub ufrag: String,
+    /// all candidates have been sent
+    #[serde(default)]
+    pub end_of_candidates: bool,
 }
 
 /// RTC message content
@@ -172,6 +372,17 @@ pub async fn rtc_init() -> Result<(), String> {
     // Initialize str0m manager
     let manager = Str0mManager::new()?;
     *STR0M_MANAGER.write().unwrap() = Some(manager);
+
+    // Run the SFU of the group calls we host
+    rtc::rtc_group::RtcGroup::set_sfu_handler(Arc::new(handle_sfu_request));
+
+    // Run the connections of our screen and file shares
+    rtc::rtc_share::RtcShare::set_share_handler(Arc::new(handle_share_event));
+
+    // Restart ICE when the network changes
+    NETWORK_MONITOR.call_once(|| {
+        std::thread::spawn(monitor_network);
+    });
     
     log::info!("RTC module and str0m manager initialized");
     Ok(())
@@ -191,6 +402,7 @@ pub async fn rtc_session_request(request: RtcSessionRequest) -> Result<RtcSessio
     let group_id_bytes = parse_peer_id(&request.group_id)?;
     
     // Get or generate SDP offer
+    let generated = request.sdp_offer.is_none();
     let sdp_offer = if let Some(offer) = request.sdp_offer {
         offer
     } else {
@@ -214,6 +426,11 @@ pub async fn rtc_session_request(request: RtcSessionRequest) -> Result<RtcSessio
     )?;
     
     log::info!("RTC session request sent to {}", request.group_id);
+
+    if generated {
+        STR0M_SESSIONS.lock().unwrap().insert(session_id.clone());
+        trickle_candidates(session_id.clone(), &sdp_offer);
+    }
     
     Ok(RtcSessionInfo {
         group_id: bs58::encode(&session_id).into_string(),
@@ -225,6 +442,10 @@ pub async fn rtc_session_request(request: RtcSessionRequest) -> Result<RtcSessio
             .as_secs(),
         sdp_offer: Some(sdp_offer),
         sdp_answer: None,
+        host_id: String::new(),
+        participants: Vec::new(),
+        remote_candidates: Vec::new(),
+        recorders: Vec::new(),
     })
 }
 
@@ -245,6 +466,7 @@ pub async fn rtc_session_management(management: RtcSessionManagement) -> Result<
     let session = rtc::Rtc::get_session_from_id(&group_id_bytes)
         .ok_or("Session not found")?;
     
+    let generated = management.option == 1 && management.sdp_answer.is_none();
     let sdp_answer = if management.option == 1 {
         // Accepting - generate or use provided SDP answer
         if let Some(answer) = management.sdp_answer {
@@ -266,6 +488,7 @@ pub async fn rtc_session_management(management: RtcSessionManagement) -> Result<
         group_id: group_id_bytes.clone(),
         option: management.option,
         sdp_answer: sdp_answer.clone().unwrap_or_default(),
+        sdp_offer: String::new(),
     };
     
     // Send management command
@@ -274,6 +497,15 @@ pub async fn rtc_session_management(management: RtcSessionManagement) -> Result<
         &proto_management,
     )?;
     
+    if generated {
+        STR0M_SESSIONS.lock().unwrap().insert(session_id.clone());
+        if let Some(ref answer) = sdp_answer {
+            trickle_candidates(session_id.clone(), answer);
+        }
+    } else if management.option != 1 {
+        STR0M_SESSIONS.lock().unwrap().remove(&session_id);
+    }
+
     let new_state = match management.option {
         1 => 3, // Established
         2 | 3 => 0, // Ended/Declined
@@ -289,6 +521,10 @@ pub async fn rtc_session_management(management: RtcSessionManagement) -> Result<
         created_at: session.created_at,
         sdp_offer: session.sdp_offer,
         sdp_answer,
+        host_id: String::new(),
+        participants: Vec::new(),
+        remote_candidates: Vec::new(),
+        recorders: Vec::new(),
     })
 }
 
@@ -305,14 +541,7 @@ pub async fn rtc_session_list() -> Result<Vec<RtcSessionInfo>, String> {
     // Convert to frontend format with SDP info
     let mut result = Vec::new();
     for (_id, session) in sessions_storage.sessions.iter() {
-        result.push(RtcSessionInfo {
-            group_id: bs58::encode(&session.group_id).into_string(),
-            session_type: session.session_type,
-            state: session.state as u32,
-            created_at: session.created_at,
-            sdp_offer: session.sdp_offer.clone(),
-            sdp_answer: session.sdp_answer.clone(),
-        });
+        result.push(RtcSessionInfo::from(session.clone()));
     }
     
     Ok(result)
@@ -359,14 +588,7 @@ pub async fn rtc_get_session(group_id: String) -> Result<Option<RtcSessionInfo>,
     let group_id_bytes = parse_peer_id(&group_id)?;
     
     match rtc::Rtc::get_session_from_id(&group_id_bytes) {
-        Some(session) => Ok(Some(RtcSessionInfo {
-            group_id: bs58::encode(&session.group_id).into_string(),
-            session_type: session.session_type,
-            state: session.state as u32,
-            created_at: session.created_at,
-            sdp_offer: session.sdp_offer,
-            sdp_answer: session.sdp_answer,
-        })),
+        Some(session) => Ok(Some(RtcSessionInfo::from(session))),
         None => Ok(None),
     }
 }
@@ -376,6 +598,7 @@ pub async fn rtc_get_session(group_id: String) -> Result<Option<RtcSessionInfo>,
 pub async fn rtc_remove_session(group_id: String) -> Result<(), String> {
     let group_id_bytes = parse_peer_id(&group_id)?;
     rtc::Rtc::remove_session(&group_id_bytes);
+    STR0M_SESSIONS.lock().unwrap().remove(&group_id_bytes);
     Ok(())
 }
 
@@ -395,6 +618,550 @@ pub async fn rtc_create_answer(sdp_offer: String) -> Result<String, String> {
     manager.create_answer(&sdp_offer)
 }
 
+/// Start a group call in a group, hosted on this node
+///
+/// All group members are notified about the call.
+#[command]
+pub async fn rtc_group_call_start(group_id: String, session_type: u32) -> Result<RtcSessionInfo, String> {
+    let user_account = UserAccounts::get_default_user()
+        .ok_or("No user account found")?;
+    let group_id_bytes = parse_peer_id(&group_id)?;
+
+    let session = rtc::rtc_group::RtcGroup::start(&user_account.id, &group_id_bytes, session_type)?;
+
+    Ok(RtcSessionInfo::from(session))
+}
+
+/// Join a group call with an SDP offer
+///
+/// If no offer is provided, one is generated.
+/// Returns the SDP answer of the SFU if we are the host,
+/// otherwise the answer arrives via the `qaul-rtc-group-answer` event.
+#[command]
+pub async fn rtc_group_call_join(group_id: String, sdp_offer: Option<String>) -> Result<Option<String>, String> {
+    let user_account = UserAccounts::get_default_user()
+        .ok_or("No user account found")?;
+    let group_id_bytes = parse_peer_id(&group_id)?;
+
+    let session = rtc::Rtc::get_session_from_id(&group_id_bytes)
+        .ok_or("Session not found")?;
+
+    let generated = sdp_offer.is_none();
+    let sdp_offer = match sdp_offer {
+        Some(offer) => offer,
+        None => {
+            let manager = STR0M_MANAGER.read().unwrap();
+            let manager = manager.as_ref().ok_or("str0m manager not initialized. Call rtc_init first.")?;
+            manager.create_offer(session.session_type)?
+        }
+    };
+
+    let sdp_answer = rtc::rtc_group::RtcGroup::join(&user_account.id, &group_id_bytes, sdp_offer.clone())?;
+
+    // the SFU of the host only needs our candidates
+    if generated && session.host_id != user_account.id.to_bytes() {
+        STR0M_SESSIONS.lock().unwrap().insert(group_id_bytes.clone());
+        trickle_candidates(group_id_bytes, &sdp_offer);
+    }
+
+    Ok(sdp_answer)
+}
+
+/// Set our audio and video mute state in a group call
+#[command]
+pub async fn rtc_group_call_mute(group_id: String, audio_muted: bool, video_muted: bool) -> Result<(), String> {
+    let user_account = UserAccounts::get_default_user()
+        .ok_or("No user account found")?;
+    let group_id_bytes = parse_peer_id(&group_id)?;
+
+    rtc::rtc_group::RtcGroup::set_muted(&user_account.id, &group_id_bytes, audio_muted, video_muted)
+}
+
+/// Leave a group call, the host ends the call for everybody
+#[command]
+pub async fn rtc_group_call_leave(group_id: String) -> Result<(), String> {
+    let user_account = UserAccounts::get_default_user()
+        .ok_or("No user account found")?;
+    let group_id_bytes = parse_peer_id(&group_id)?;
+
+    STR0M_SESSIONS.lock().unwrap().remove(&group_id_bytes);
+    rtc::rtc_group::RtcGroup::leave(&user_account.id, &group_id_bytes)
+}
+
+/// Handle the SFU requests of the group calls we host
+///
+/// An SFU room is spawned for the first participant of a call.
+fn handle_sfu_request(request: rtc::rtc_group::RtcSfuRequest) -> Result<Option<String>, String> {
+    use rtc::rtc_group::RtcSfuRequest;
+
+    let mut rooms = SFU_ROOMS.lock().unwrap();
+    match request {
+        RtcSfuRequest::Join { group_id, user_id, sdp_offer } => {
+            if !rooms.contains_key(&group_id) {
+                rooms.insert(group_id.clone(), SfuRoom::spawn()?);
+            }
+            let room = rooms.get(&group_id).ok_or("SFU room not found")?;
+            room.join(user_id, &sdp_offer).map(Some)
+        }
+        RtcSfuRequest::Leave { group_id, user_id } => {
+            if let Some(room) = rooms.get(&group_id) {
+                room.leave(user_id);
+            }
+            Ok(None)
+        }
+        RtcSfuRequest::Candidate { group_id, user_id, candidate } => {
+            let room = rooms.get(&group_id).ok_or("SFU room not found")?;
+            room.add_candidate(user_id, &candidate)?;
+            Ok(None)
+        }
+        RtcSfuRequest::End { group_id } => {
+            if let Some(room) = rooms.remove(&group_id) {
+                room.stop();
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Send one of our ICE candidates to the other side (trickle ICE)
+///
+/// Used by the frontend for the candidates of its own WebRTC connections.
+#[command]
+pub async fn rtc_send_ice_candidate(candidate: RtcIceCandidateInfo) -> Result<(), String> {
+    let user_account = UserAccounts::get_default_user()
+        .ok_or("No user account found")?;
+    let group_id_bytes = parse_peer_id(&candidate.group_id)?;
+
+    rtc::rtc_ice::RtcIce::send_candidate(
+        &user_account.id,
+        &rtc::proto_rpc::RtcIceCandidate {
+            group_id: group_id_bytes,
+            candidate: candidate.candidate,
+            sdp_mid: candidate.sdp_mid,
+            sdp_mline_index: candidate.sdp_mline_index,
+            ufrag: candidate.ufrag,
+            end_of_candidates: candidate.end_of_candidates,
+        },
+    )
+}
+
+/// Get the ICE candidates received from the other side of a session
+#[command]
+pub async fn rtc_get_remote_candidates(group_id: String) -> Result<Vec<RtcIceCandidateInfo>, String> {
+    let group_id_bytes = parse_peer_id(&group_id)?;
+    let session = rtc::Rtc::get_session_from_id(&group_id_bytes)
+        .ok_or("Session not found")?;
+
+    Ok(RtcSessionInfo::from(session).remote_candidates)
+}
+
+/// Get the ICE configuration
+#[command]
+pub async fn rtc_get_ice_config() -> Result<RtcIceConfig, String> {
+    Ok(ICE_CONFIG.read().unwrap().clone())
+}
+
+/// Set the ICE configuration
+#[command]
+pub async fn rtc_set_ice_config(config: RtcIceConfig) -> Result<(), String> {
+    let stun_server = config
+        .stun_server
+        .map(|server| server.trim().trim_start_matches("stun:").to_string())
+        .filter(|server| !server.is_empty());
+
+    log::info!("RTC STUN server set to {:?}", stun_server);
+    ICE_CONFIG.write().unwrap().stun_server = stun_server;
+    Ok(())
+}
+
+/// Restart ICE of a session with a new SDP offer
+///
+/// If no offer is provided, one is generated with new ICE credentials
+/// and the candidates of the current network interfaces.
+#[command]
+pub async fn rtc_ice_restart(group_id: String, sdp_offer: Option<String>) -> Result<(), String> {
+    let group_id_bytes = parse_peer_id(&group_id)?;
+    restart_ice(group_id_bytes, sdp_offer)
+}
+
+/// Answer the ICE restart of the other side
+///
+/// If no answer is provided, one is generated from the restart offer.
+#[command]
+pub async fn rtc_ice_restart_answer(group_id: String, sdp_answer: Option<String>) -> Result<(), String> {
+    let user_account = UserAccounts::get_default_user()
+        .ok_or("No user account found")?;
+    let group_id_bytes = parse_peer_id(&group_id)?;
+    let session = rtc::Rtc::get_session_from_id(&group_id_bytes)
+        .ok_or("Session not found")?;
+
+    let generated = sdp_answer.is_none();
+    let sdp_answer = match sdp_answer {
+        Some(answer) => answer,
+        None => {
+            let offer = session.sdp_offer.ok_or("No SDP offer available to create answer from")?;
+            let manager = STR0M_MANAGER.read().unwrap();
+            let manager = manager.as_ref().ok_or("str0m manager not initialized. Call rtc_init first.")?;
+            manager.create_answer(&offer)?
+        }
+    };
+
+    rtc::rtc_ice::RtcIce::restart_answer(&user_account.id, &group_id_bytes, sdp_answer.clone())?;
+
+    if generated {
+        STR0M_SESSIONS.lock().unwrap().insert(group_id_bytes.clone());
+        trickle_candidates(group_id_bytes, &sdp_answer);
+    }
+    Ok(())
+}
+
+/// Restart ICE of a session
+fn restart_ice(group_id: Vec<u8>, sdp_offer: Option<String>) -> Result<(), String> {
+    let user_account = UserAccounts::get_default_user()
+        .ok_or("No user account found")?;
+    let session = rtc::Rtc::get_session_from_id(&group_id)
+        .ok_or("Session not found")?;
+
+    let generated = sdp_offer.is_none();
+    let sdp_offer = match sdp_offer {
+        Some(offer) => offer,
+        None => {
+            let manager = STR0M_MANAGER.read().unwrap();
+            let manager = manager.as_ref().ok_or("str0m manager not initialized. Call rtc_init first.")?;
+            manager.create_offer(session.session_type)?
+        }
+    };
+
+    rtc::rtc_ice::RtcIce::restart(&user_account.id, &group_id, sdp_offer.clone())?;
+
+    if generated {
+        STR0M_SESSIONS.lock().unwrap().insert(group_id.clone());
+        trickle_candidates(group_id, &sdp_offer);
+    }
+    Ok(())
+}
+
+/// Trickle the server reflexive candidates of our SDP to the other side
+///
+/// The STUN requests run in the background, so that the SDP can be
+/// sent right away with the host candidates.
+fn trickle_candidates(group_id: Vec<u8>, sdp: &str) {
+    let Some(stun_server) = ICE_CONFIG.read().unwrap().stun_server.clone() else {
+        return;
+    };
+    let Some(manager) = STR0M_MANAGER.read().unwrap().clone() else {
+        return;
+    };
+    let ufrag = ice::ice_ufrag(sdp).unwrap_or_default();
+
+    std::thread::spawn(move || {
+        let Some(user_account) = UserAccounts::get_default_user() else {
+            return;
+        };
+
+        let mut candidates: Vec<String> = manager
+            .gather_srflx_candidates(&stun_server)
+            .iter()
+            .map(|candidate| candidate.to_sdp_string())
+            .collect();
+        log::info!("Gathered {} srflx candidates via {}", candidates.len(), stun_server);
+
+        // the last message signals the end of the candidates
+        candidates.push(String::new());
+        let count = candidates.len();
+
+        for (index, candidate) in candidates.into_iter().enumerate() {
+            if let Err(e) = rtc::rtc_ice::RtcIce::send_candidate(
+                &user_account.id,
+                &rtc::proto_rpc::RtcIceCandidate {
+                    group_id: group_id.clone(),
+                    candidate,
+                    sdp_mid: String::new(),
+                    sdp_mline_index: 0,
+                    ufrag: ufrag.clone(),
+                    end_of_candidates: index + 1 == count,
+                },
+            ) {
+                log::warn!("Failed to send ICE candidate: {}", e);
+                return;
+            }
+        }
+    });
+}
+
+/// Watch the network interfaces and restart ICE when they change
+///
+/// The str0m manager is rebound to the new interfaces, and the
+/// sessions negotiated by it are restarted.
+/// The frontend is informed via the `rtc-network-changed` event,
+/// to restart the connections it negotiated itself.
+fn monitor_network() {
+    let mut addresses = ice::interface_addresses();
+
+    loop {
+        std::thread::sleep(NETWORK_MONITOR_INTERVAL);
+
+        let current = ice::interface_addresses();
+        if current == addresses {
+            continue;
+        }
+        log::info!("Network changed from {:?} to {:?}", addresses, current);
+        addresses = current;
+
+        match Str0mManager::new() {
+            Ok(manager) => *STR0M_MANAGER.write().unwrap() = Some(manager),
+            Err(e) => {
+                log::error!("Failed to rebind str0m manager: {}", e);
+                continue;
+            }
+        }
+
+        let Some(user_account) = UserAccounts::get_default_user() else {
+            continue;
+        };
+        let my_id = user_account.id.to_bytes();
+        let sessions: Vec<rtc::RtcSession> = rtc::RTCSESSIONS
+            .get()
+            .read()
+            .unwrap()
+            .sessions
+            .values()
+            .filter(|session| {
+                // established calls, and group calls we joined
+                session.host_id != my_id
+                    && (session.state == 3 || (session.state == 1 && !session.host_id.is_empty()))
+            })
+            .cloned()
+            .collect();
+
+        let str0m_sessions = STR0M_SESSIONS.lock().unwrap().clone();
+        let mut frontend_sessions = Vec::new();
+        for session in sessions {
+            if !str0m_sessions.contains(&session.group_id) {
+                frontend_sessions.push(bs58::encode(&session.group_id).into_string());
+                continue;
+            }
+            if let Err(e) = restart_ice(session.group_id.clone(), None) {
+                log::error!("ICE restart failed: {}", e);
+            }
+        }
+
+        libqaul::connections::events::emit_tauri_event(
+            "rtc-network-changed",
+            serde_json::json!({
+                "addresses": addresses.iter().map(|ip| ip.to_string()).collect::<Vec<_>>(),
+                "sessions": frontend_sessions,
+            }),
+        );
+    }
+}
+
+/// Share our screen or the active window with the other side of a direct call
+///
+/// The screen is not sent as a video track, but as JPEG frames
+/// over a data channel, see `crate::str0m::share`.
+///
+/// Returns the share id. The other side answers the share offer
+/// with `rtc_share_answer`.
+#[command]
+pub async fn rtc_share_screen_start(group_id: String, options: Option<RtcScreenShareOptions>) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let source = match options.active_window {
+        true => ScreenSource::ActiveWindow,
+        false => ScreenSource::Screen(options.screen.unwrap_or(0)),
+    };
+
+    start_share(
+        parse_peer_id(&group_id)?,
+        ShareKind::Screen(ScreenOptions {
+            source,
+            fps: options.fps.unwrap_or(5),
+            quality: options.quality.unwrap_or(60),
+        }),
+    )
+}
+
+/// Send a file to the other side of a direct call
+///
+/// The file is streamed over a data channel next to the call with
+/// `rtc-file-progress` events. If there is no established call,
+/// or the call drops before the file is complete, the file is
+/// sent via the chat instead.
+#[command]
+pub async fn rtc_send_file(group_id: String, path: String) -> Result<RtcFileSendResult, String> {
+    let group_id_bytes = parse_peer_id(&group_id)?;
+    let path = std::path::PathBuf::from(path);
+    if !path.is_file() {
+        return Err(format!("File not found: {}", path.display()));
+    }
+
+    if !can_stream_file(rtc::Rtc::get_session_from_id(&group_id_bytes).as_ref()) {
+        send_file_via_chat(&group_id_bytes, &path)?;
+        return Ok(RtcFileSendResult { share_id: None, fallback: true });
+    }
+
+    // queue the file in an open file share of the call
+    let mut path = path;
+    {
+        let shares = SHARE_SESSIONS.lock().unwrap();
+        for (share_id, entry) in shares.iter().filter(|(_, entry)| entry.files && entry.group_id == group_id_bytes) {
+            match entry.session.send_file(path) {
+                Ok(()) => return Ok(RtcFileSendResult { share_id: Some(share_id.clone()), fallback: false }),
+                Err(unsent) => path = unsent,
+            }
+        }
+    }
+
+    // offer a new file share
+    let share_id = start_share(group_id_bytes.clone(), ShareKind::Files)?;
+    let queued = match SHARE_SESSIONS.lock().unwrap().get(&share_id) {
+        Some(entry) => entry.session.send_file(path.clone()).is_ok(),
+        None => false,
+    };
+    if !queued {
+        send_file_via_chat(&group_id_bytes, &path)?;
+        return Ok(RtcFileSendResult { share_id: None, fallback: true });
+    }
+
+    Ok(RtcFileSendResult { share_id: Some(share_id), fallback: false })
+}
+
+/// Files can only be streamed in an established direct call
+fn can_stream_file(session: Option<&rtc::RtcSession>) -> bool {
+    session
+        .map(|session| session.state == 3 && session.host_id.is_empty())
+        .unwrap_or(false)
+}
+
+/// Answer the share offer of the other side
+#[command]
+pub async fn rtc_share_answer(group_id: String, share_id: String, sdp_answer: String) -> Result<(), String> {
+    let user_account = UserAccounts::get_default_user()
+        .ok_or("No user account found")?;
+    let group_id_bytes = parse_peer_id(&group_id)?;
+
+    rtc::rtc_share::RtcShare::answer(&user_account.id, &group_id_bytes, &share_id, sdp_answer)
+}
+
+/// Stop one of our shares, or a share of the other side
+#[command]
+pub async fn rtc_share_stop(group_id: String, share_id: String) -> Result<(), String> {
+    let user_account = UserAccounts::get_default_user()
+        .ok_or("No user account found")?;
+    let group_id_bytes = parse_peer_id(&group_id)?;
+
+    if let Some(entry) = SHARE_SESSIONS.lock().unwrap().get(&share_id) {
+        entry.session.stop();
+    }
+    rtc::rtc_share::RtcShare::stop(&user_account.id, &group_id_bytes, &share_id)
+}
+
+/// Offer a share to the other side of a direct call
+fn start_share(group_id: Vec<u8>, kind: ShareKind) -> Result<String, String> {
+    let user_account = UserAccounts::get_default_user()
+        .ok_or("No user account found")?;
+    let share_id = uuid::Uuid::new_v4().to_string();
+    let files = matches!(kind, ShareKind::Files);
+
+    let progress_group_id = bs58::encode(&group_id).into_string();
+    let progress_share_id = share_id.clone();
+    let end_group_id = group_id.clone();
+    let end_share_id = share_id.clone();
+    let callbacks = ShareCallbacks {
+        on_progress: Box::new(move |progress: FileProgress| {
+            libqaul::connections::events::emit_tauri_event(
+                "rtc-file-progress",
+                serde_json::json!({
+                    "group_id": progress_group_id,
+                    "share_id": progress_share_id,
+                    "path": progress.path.display().to_string(),
+                    "sent": progress.sent,
+                    "size": progress.size,
+                    "done": progress.sent == progress.size,
+                }),
+            );
+        }),
+        on_end: Box::new(move |unsent| on_share_ended(end_group_id, end_share_id, unsent)),
+    };
+
+    let (session, sdp_offer) = ShareSession::offer(kind, callbacks)?;
+    if let Err(e) = rtc::rtc_share::RtcShare::offer(&user_account.id, &group_id, &share_id, sdp_offer) {
+        session.stop();
+        return Err(e);
+    }
+
+    SHARE_SESSIONS.lock().unwrap().insert(
+        share_id.clone(),
+        RtcShareEntry { group_id, files, session },
+    );
+    Ok(share_id)
+}
+
+/// A share ended, send its unsent files via the chat
+fn on_share_ended(group_id: Vec<u8>, share_id: String, unsent: Vec<std::path::PathBuf>) {
+    SHARE_SESSIONS.lock().unwrap().remove(&share_id);
+
+    for path in unsent {
+        if let Err(e) = send_file_via_chat(&group_id, &path) {
+            log::error!("Failed to send {} via chat: {}", path.display(), e);
+        }
+    }
+}
+
+/// Send a file to the direct chat with the other side of a call
+fn send_file_via_chat(group_id: &Vec<u8>, path: &std::path::Path) -> Result<(), String> {
+    use libqaul::services::chat::file::ChatFile;
+    use libqaul::services::group::GroupId;
+
+    let user_account = UserAccounts::get_default_user()
+        .ok_or("No user account found")?;
+    let peer_id = libp2p::PeerId::from_bytes(group_id)
+        .map_err(|e| format!("Invalid peer ID: {:?}", e))?;
+    let direct_chat = GroupId::from_peers(&user_account.id, &peer_id).to_bytes();
+
+    ChatFile::send(&user_account, &direct_chat, path.display().to_string(), String::new())?;
+
+    libqaul::connections::events::emit_tauri_event(
+        "rtc-file-fallback",
+        serde_json::json!({
+            "group_id": bs58::encode(group_id).into_string(),
+            "path": path.display().to_string(),
+        }),
+    );
+    log::info!("File {} sent via chat", path.display());
+    Ok(())
+}
+
+/// Handle the share events of the other side
+fn handle_share_event(event: rtc::rtc_share::RtcShareEvent) {
+    use rtc::rtc_share::RtcShareEvent;
+
+    let shares = SHARE_SESSIONS.lock().unwrap();
+    match event {
+        RtcShareEvent::Answer { group_id, share_id, sdp_answer } => {
+            match shares.get(&share_id).filter(|entry| entry.group_id == group_id) {
+                Some(entry) => {
+                    if let Err(e) = entry.session.answer(&sdp_answer) {
+                        log::warn!("Share answer failed: {}", e);
+                    }
+                }
+                None => log::warn!("Answer for unknown share {}", share_id),
+            }
+        }
+        RtcShareEvent::Stop { group_id, share_id } => {
+            if let Some(entry) = shares.get(&share_id).filter(|entry| entry.group_id == group_id) {
+                entry.session.stop();
+            }
+        }
+        RtcShareEvent::SessionEnded { group_id } => {
+            for entry in shares.values().filter(|entry| entry.group_id == group_id) {
+                entry.session.stop();
+            }
+            crate::tauri::rtc_recording::on_session_ended(&group_id);
+        }
+    }
+}
+
 /// Helper function to parse peer ID from string
 fn parse_peer_id(id_str: &str) -> Result<Vec<u8>, String> {
     if id_str.starts_with("0x") || id_str.chars().all(|c| c.is_ascii_hexdigit()) {
@@ -451,3 +1218,39 @@ pub async fn start_str0m_server(app: AppHandle) -> Result<(), String> {
     });
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn session(state: u8, host_id: Vec<u8>) -> rtc::RtcSession {
+        rtc::RtcSession {
+            user_id: vec![1],
+            group_id: vec![2],
+            session_type: 1,
+            created_at: 0,
+            state,
+            sdp_offer: None,
+            sdp_answer: None,
+            host_id,
+            participants: BTreeMap::new(),
+            remote_candidates: Vec::new(),
+            restarting: false,
+            recorders: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_file_streaming_falls_back_to_chat() {
+        // no call
+        assert!(!can_stream_file(None));
+        // the call is not established yet
+        assert!(!can_stream_file(Some(&session(1, Vec::new()))));
+        assert!(!can_stream_file(Some(&session(2, Vec::new()))));
+        // group calls have no file share
+        assert!(!can_stream_file(Some(&session(3, vec![3]))));
+
+        assert!(can_stream_file(Some(&session(3, Vec::new()))));
+    }
+}
@@ -0,0 +1,378 @@
+// Copyright (c) 2022 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # RTC Call Recording Tauri Commands
+//!
+//! Recording a call is opt-in. When a user starts or stops recording,
+//! all participants are informed via qaul, see
+//! `libqaul::services::rtc::rtc_recording`.
+//!
+//! The media of a call is decoded by the WebRTC stack of the frontend.
+//! The frontend passes the mixed audio of the call as PCM samples
+//! and, optionally, the video as WebM chunks of a `MediaRecorder`.
+//! The audio is stored as mono WAV with the sample rate of the
+//! transcription model.
+//!
+//! A recording can be transcribed offline. The transcript is saved
+//! as LLM chat session, in which it can be summarised.
+
+use ::tauri::{command, AppHandle, Emitter, Manager};
+use chrono::Utc;
+use libqaul::node::user_accounts::UserAccounts;
+use libqaul::services::rtc;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::llm::system_prompts::MEETING_TRANSCRIPT_SYSTEM_PROMPT;
+use crate::sql::{self, model::MessageData};
+use crate::transcription::audio::{self, WavWriter};
+use crate::transcription::service::{TranscriptSegment, TranscriptionManager, SAMPLE_RATE};
+
+// Running recordings, by group id
+static RECORDINGS: Lazy<Mutex<HashMap<Vec<u8>, Recording>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Running recording of a call
+struct Recording {
+    id: String,
+    started_at: chrono::DateTime<Utc>,
+    audio: WavWriter,
+    audio_path: PathBuf,
+    video: Option<File>,
+    video_path: Option<PathBuf>,
+}
+
+impl Recording {
+    fn info(&self, group_id: &[u8]) -> RtcRecordingInfo {
+        RtcRecordingInfo {
+            recording_id: self.id.clone(),
+            group_id: bs58::encode(group_id).into_string(),
+            started_at: self.started_at.to_rfc3339(),
+            duration: self.audio.duration(),
+            audio_path: self.audio_path.display().to_string(),
+            video_path: self
+                .video_path
+                .as_ref()
+                .map(|path| path.display().to_string()),
+        }
+    }
+
+    /// Finish the audio and video files
+    fn finish(self) -> Result<(), String> {
+        self.audio
+            .finalize()
+            .map_err(|e| format!("Failed to finish audio file: {}", e))?;
+        if let Some(mut video) = self.video {
+            video
+                .flush()
+                .map_err(|e| format!("Failed to finish video file: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Recording information for the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RtcRecordingInfo {
+    pub recording_id: String,
+    pub group_id: String,
+    pub started_at: String,
+    /// recorded audio in seconds
+    pub duration: f32,
+    pub audio_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_path: Option<String>,
+}
+
+/// Transcript of a recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RtcTranscriptInfo {
+    /// LLM chat session containing the transcript
+    pub session_id: i64,
+    pub audio_path: String,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+/// Start recording a call and announce it to all participants
+///
+/// With `video`, the frontend also passes the video via
+/// `rtc_recording_video`.
+#[command]
+pub async fn rtc_recording_start(
+    app: AppHandle,
+    group_id: String,
+    video: Option<bool>,
+) -> Result<RtcRecordingInfo, String> {
+    let user_account = UserAccounts::get_default_user().ok_or("No user account found")?;
+    let group_id_bytes = parse_id(&group_id)?;
+    if RECORDINGS.lock().unwrap().contains_key(&group_id_bytes) {
+        return Err("Call is already recorded".to_string());
+    }
+
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?
+        .join("recordings");
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let started_at = Utc::now();
+    let name = format!("call-{}-{}", started_at.format("%Y%m%d-%H%M%S"), &id[..8]);
+
+    let audio_path = dir.join(format!("{}.wav", name));
+    let audio = WavWriter::create(&audio_path, SAMPLE_RATE)
+        .map_err(|e| format!("Failed to create audio file: {}", e))?;
+    let (video, video_path) = match video.unwrap_or(false) {
+        true => {
+            let path = dir.join(format!("{}.webm", name));
+            let file =
+                File::create(&path).map_err(|e| format!("Failed to create video file: {}", e))?;
+            (Some(file), Some(path))
+        }
+        false => (None, None),
+    };
+
+    // nobody is recorded without being told
+    if let Err(e) =
+        rtc::rtc_recording::RtcRecording::announce(&user_account.id, &group_id_bytes, true)
+    {
+        fs::remove_file(&audio_path).ok();
+        if let Some(path) = &video_path {
+            fs::remove_file(path).ok();
+        }
+        return Err(e);
+    }
+
+    let recording = Recording {
+        id,
+        started_at,
+        audio,
+        audio_path,
+        video,
+        video_path,
+    };
+    let info = recording.info(&group_id_bytes);
+    RECORDINGS.lock().unwrap().insert(group_id_bytes, recording);
+
+    log::info!("RTC recording started: {}", info.audio_path);
+    Ok(info)
+}
+
+/// Append audio samples of the call to the recording
+///
+/// The samples are mono in the range -1.0 to 1.0, they are
+/// resampled to the sample rate of the recording.
+#[command]
+pub async fn rtc_recording_audio(
+    group_id: String,
+    samples: Vec<f32>,
+    sample_rate: u32,
+) -> Result<(), String> {
+    let group_id_bytes = parse_id(&group_id)?;
+    if sample_rate == 0 {
+        return Err("Invalid sample rate".to_string());
+    }
+
+    let mut recordings = RECORDINGS.lock().unwrap();
+    let recording = recordings
+        .get_mut(&group_id_bytes)
+        .ok_or("Call is not recorded")?;
+    let samples = audio::resample(&samples, sample_rate, SAMPLE_RATE);
+    recording
+        .audio
+        .write(&samples)
+        .map_err(|e| format!("Failed to write audio: {}", e))
+}
+
+/// Append a WebM chunk of the call video to the recording
+#[command]
+pub async fn rtc_recording_video(group_id: String, data: Vec<u8>) -> Result<(), String> {
+    let group_id_bytes = parse_id(&group_id)?;
+
+    let mut recordings = RECORDINGS.lock().unwrap();
+    let recording = recordings
+        .get_mut(&group_id_bytes)
+        .ok_or("Call is not recorded")?;
+    let video = recording.video.as_mut().ok_or("Video is not recorded")?;
+    video
+        .write_all(&data)
+        .map_err(|e| format!("Failed to write video: {}", e))
+}
+
+/// Stop recording a call and announce it to all participants
+///
+/// With `transcribe`, the recording is transcribed in the background.
+/// The result is emitted as `rtc-transcript-ready` event, or
+/// `rtc-transcript-failed` on errors.
+#[command]
+pub async fn rtc_recording_stop(
+    app: AppHandle,
+    group_id: String,
+    transcribe: Option<bool>,
+    language: Option<String>,
+) -> Result<RtcRecordingInfo, String> {
+    let user_account = UserAccounts::get_default_user().ok_or("No user account found")?;
+    let group_id_bytes = parse_id(&group_id)?;
+
+    let recording = RECORDINGS
+        .lock()
+        .unwrap()
+        .remove(&group_id_bytes)
+        .ok_or("Call is not recorded")?;
+    let info = recording.info(&group_id_bytes);
+
+    // the call may have ended already
+    if let Err(e) =
+        rtc::rtc_recording::RtcRecording::announce(&user_account.id, &group_id_bytes, false)
+    {
+        log::warn!("Recording stop not announced: {}", e);
+    }
+
+    recording.finish()?;
+    log::info!("RTC recording stopped: {}", info.audio_path);
+
+    if transcribe.unwrap_or(false) {
+        let info = info.clone();
+        ::tauri::async_runtime::spawn(async move {
+            let title = format!(
+                "Call transcript {}",
+                info.started_at.get(..16).unwrap_or_default()
+            );
+            match transcribe_recording(&app, Path::new(&info.audio_path), title, language).await {
+                Ok(transcript) => {
+                    let _ = app.emit("rtc-transcript-ready", &transcript);
+                }
+                Err(e) => {
+                    log::error!("Transcription failed: {}", e);
+                    let _ = app.emit(
+                        "rtc-transcript-failed",
+                        serde_json::json!({
+                            "audio_path": info.audio_path,
+                            "error": e,
+                        }),
+                    );
+                }
+            }
+        });
+    }
+
+    Ok(info)
+}
+
+/// Finish the recording of a call that has ended
+///
+/// All participants have left, so the end of the recording is
+/// not announced. The frontend is informed with the
+/// `rtc-recording-stopped` event.
+pub fn on_session_ended(group_id: &[u8]) {
+    let Some(recording) = RECORDINGS.lock().unwrap().remove(group_id) else {
+        return;
+    };
+    let info = recording.info(group_id);
+
+    match recording.finish() {
+        Ok(()) => log::info!("RTC recording of ended call stopped: {}", info.audio_path),
+        Err(e) => log::error!("RTC recording of ended call not finished: {}", e),
+    }
+    libqaul::connections::events::emit_tauri_event(
+        "rtc-recording-stopped",
+        serde_json::to_value(&info).unwrap_or_default(),
+    );
+}
+
+/// Get the running recording of a call
+#[command]
+pub async fn rtc_recording_get(group_id: String) -> Result<Option<RtcRecordingInfo>, String> {
+    let group_id_bytes = parse_id(&group_id)?;
+    Ok(RECORDINGS
+        .lock()
+        .unwrap()
+        .get(&group_id_bytes)
+        .map(|recording| recording.info(&group_id_bytes)))
+}
+
+/// Transcribe a recording and save the transcript as LLM chat session
+#[command]
+pub async fn rtc_recording_transcribe(
+    app: AppHandle,
+    audio_path: String,
+    title: Option<String>,
+    language: Option<String>,
+) -> Result<RtcTranscriptInfo, String> {
+    let title = title.unwrap_or_else(|| "Call transcript".to_string());
+    transcribe_recording(&app, Path::new(&audio_path), title, language).await
+}
+
+/// Transcribe a WAV file and save the transcript as LLM chat session
+async fn transcribe_recording(
+    app: &AppHandle,
+    path: &Path,
+    title: String,
+    language: Option<String>,
+) -> Result<RtcTranscriptInfo, String> {
+    let manager = app.state::<TranscriptionManager>();
+    manager
+        .initialize(app.clone())
+        .await
+        .map_err(|e| format!("Failed to initialize transcription service: {}", e))?;
+
+    let (samples, sample_rate) =
+        audio::read_wav(path).map_err(|e| format!("Failed to read recording: {}", e))?;
+    let samples = audio::resample(&samples, sample_rate, SAMPLE_RATE);
+    let segments = manager
+        .transcribe(samples, language)
+        .await
+        .map_err(|e| format!("Failed to transcribe recording: {}", e))?;
+
+    let session_id = sql::queries::insert_chat_session(title, MEETING_TRANSCRIPT_SYSTEM_PROMPT)
+        .map_err(|e| e.to_string())?;
+    sql::queries::insert_message(MessageData {
+        id: None,
+        session_id,
+        role: "user".to_string(),
+        content: format_transcript(&segments),
+        created_at: Utc::now(),
+    })
+    .map_err(|e| e.to_string())?;
+
+    log::info!(
+        "Transcript of {} saved to chat session {}",
+        path.display(),
+        session_id
+    );
+    Ok(RtcTranscriptInfo {
+        session_id,
+        audio_path: path.display().to_string(),
+        segments,
+    })
+}
+
+/// Format the transcript with the time of every segment
+fn format_transcript(segments: &[TranscriptSegment]) -> String {
+    if segments.is_empty() {
+        return "Transcript of the call: nothing was recognized.".to_string();
+    }
+
+    let lines: Vec<String> = segments
+        .iter()
+        .map(|segment| {
+            let secs = segment.start as u64;
+            format!("[{:02}:{:02}] {}", secs / 60, secs % 60, segment.text)
+        })
+        .collect();
+    format!("Transcript of the call:\n\n{}", lines.join("\n"))
+}
+
+/// Parse a base58 group id
+fn parse_id(id: &str) -> Result<Vec<u8>, String> {
+    bs58::decode(id)
+        .into_vec()
+        .map_err(|e| format!("Invalid base58 group ID: {}", e))
+}
@@ -133,8 +133,13 @@ pub async fn set_node_profile_tauri(name: String, college: String, reg_no: Strin
         about: about.clone(),
     };
     
-    // Use the configuration's set_node_profile function
-    Configuration::set_node_profile(user_profile);
+    // Update the profile of the current user account
+    match UserAccounts::get_default_user() {
+        Some(user_account) => {
+            Configuration::set_user_profile(user_account.id.to_string(), user_profile);
+        }
+        None => Configuration::set_node_profile(user_profile),
+    }
     
     // Also update the users store to keep both systems in sync
     if let Some(user_account) = UserAccounts::get_default_user() {
@@ -235,6 +240,164 @@ pub async fn convert_q8id_to_peer_id(q8id: String) -> Result<String, String> {
     }
 }
 
+/// User account entry for the account switcher
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserAccountEntry {
+    pub id: String,
+    pub name: String,
+    pub profile_pic: Option<String>,
+    pub logged_in: bool,
+    pub has_passphrase: bool,
+    pub current: bool,
+}
+
+/// parse a base58 user account id
+fn parse_account_id(id: &str) -> Result<libp2p::PeerId, String> {
+    id.parse::<libp2p::PeerId>()
+        .map_err(|e| format!("Invalid user id {}: {}", id, e))
+}
+
+/// put the current user account id into the application state
+fn update_current_user(state: &crate::AppState) {
+    let mut current_user = state.current_user.lock().unwrap();
+    *current_user = UserAccounts::get_default_user().map(|account| account.id.to_base58());
+}
+
+/// List all user accounts of this node
+#[tauri_crate::command]
+pub async fn list_user_accounts() -> Result<Vec<UserAccountEntry>, String> {
+    let current = UserAccounts::get_default_user().map(|account| account.id);
+    Ok(UserAccounts::get_all()
+        .into_iter()
+        .map(|account| UserAccountEntry {
+            id: account.id.to_base58(),
+            name: account.name,
+            profile_pic: account.profile_pic,
+            logged_in: UserAccounts::is_logged_in(&account.id),
+            has_passphrase: UserAccounts::has_passphrase(&account.id),
+            current: current == Some(account.id),
+        })
+        .collect())
+}
+
+/// Create a new user account, optionally protected by a passphrase
+#[tauri_crate::command]
+pub async fn create_user_account(
+    state: tauri_crate::State<'_, crate::AppState>,
+    name: String,
+    passphrase: Option<String>,
+) -> Result<String, String> {
+    let account =
+        UserAccounts::create_with_passphrase(name, None, None, None, None, passphrase);
+    update_current_user(&state);
+    Ok(account.id.to_base58())
+}
+
+/// Log in a user account and make it the current account
+#[tauri_crate::command]
+pub async fn login_user_account(
+    state: tauri_crate::State<'_, crate::AppState>,
+    id: String,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    UserAccounts::login(parse_account_id(&id)?, passphrase)?;
+    update_current_user(&state);
+    Ok(())
+}
+
+/// Log out a user account, protected accounts need their passphrase
+#[tauri_crate::command]
+pub async fn logout_user_account(
+    state: tauri_crate::State<'_, crate::AppState>,
+    id: String,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    UserAccounts::logout(parse_account_id(&id)?, passphrase)?;
+    update_current_user(&state);
+    Ok(())
+}
+
+/// Switch to another logged in user account,
+/// protected accounts need their passphrase
+#[tauri_crate::command]
+pub async fn switch_user_account(
+    state: tauri_crate::State<'_, crate::AppState>,
+    id: String,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    UserAccounts::switch(parse_account_id(&id)?, passphrase)?;
+    update_current_user(&state);
+    Ok(())
+}
+
+/// Set, change or remove the passphrase of a logged in user account
+#[tauri_crate::command]
+pub async fn set_user_account_passphrase(
+    id: String,
+    old_passphrase: Option<String>,
+    new_passphrase: Option<String>,
+) -> Result<(), String> {
+    let user_id = parse_account_id(&id)?;
+    if !UserAccounts::is_logged_in(&user_id) {
+        return Err("User account is not logged in".to_string());
+    }
+    UserAccounts::set_passphrase(user_id, old_passphrase, new_passphrase)
+}
+
+/// get the current user account for a trust operation
+fn current_account_id() -> Result<libp2p::PeerId, String> {
+    UserAccounts::get_default_user()
+        .map(|account| account.id)
+        .ok_or_else(|| "No user account".to_string())
+}
+
+/// Get the security number shared with a user
+///
+/// Returns 8 blocks of 5 digits, to be compared in person.
+#[tauri_crate::command]
+pub async fn get_security_number(user_id: String) -> Result<Vec<String>, String> {
+    let my_id = current_account_id()?;
+    let user_id = parse_account_id(&user_id)?;
+    let hash = Users::get_security_number(&my_id, &user_id.to_bytes())?;
+    Ok(hash
+        .chunks(2)
+        .map(|block| format!("{:05}", block[0] as u32 + (block[1] as u32 * 256)))
+        .collect())
+}
+
+/// Get the QR code payload to verify a user in person
+#[tauri_crate::command]
+pub async fn get_verification_qr(user_id: String) -> Result<String, String> {
+    let my_id = current_account_id()?;
+    Users::get_verification_qr(&my_id, &parse_account_id(&user_id)?)
+}
+
+/// Verify a user with a scanned QR code payload
+///
+/// Returns the id of the verified user.
+#[tauri_crate::command]
+pub async fn verify_user_qr(payload: String) -> Result<String, String> {
+    let my_id = current_account_id()?;
+    Users::verify_qr(&my_id, &payload).map(|user_id| user_id.to_base58())
+}
+
+/// Mark a user as verified or unverified
+///
+/// Returns the new trust level of the user.
+#[tauri_crate::command]
+pub async fn set_user_verified(user_id: String, verified: bool) -> Result<String, String> {
+    let my_id = current_account_id()?;
+    Users::set_trust(&my_id, &parse_account_id(&user_id)?, verified)
+        .map(|trust| trust.as_str().to_string())
+}
+
+/// Get the trust level of a user
+#[tauri_crate::command]
+pub async fn get_user_trust(user_id: String) -> Result<String, String> {
+    let my_id = current_account_id()?;
+    Ok(Users::get_trust(&my_id, &parse_account_id(&user_id)?).as_str().to_string())
+}
+
 /// Register user-related Tauri commands
 pub fn register_commands<R: Runtime>() -> TauriPlugin<R> {
     Builder::new("user")
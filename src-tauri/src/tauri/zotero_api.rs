@@ -0,0 +1,292 @@
+//! # Zotero Web API v3 Client
+//!
+//! Client for the endpoints of the Zotero Web API used by the sync of
+//! [`ZoteroManager`](super::zotero_complete::ZoteroManager).
+//!
+//! Every response carries the version of the library in the
+//! `Last-Modified-Version` header. Reads are made incremental by only
+//! asking for objects changed since a version, writes are guarded by
+//! `If-Unmodified-Since-Version` so they fail with `412 Precondition
+//! Failed` when the library changed in the meantime.
+//!
+//! See <https://www.zotero.org/support/dev/web_api/v3/basics> and
+//! <https://www.zotero.org/support/dev/web_api/v3/syncing>.
+
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::zotero_complete::{LibraryType, Result, ZoteroConfig, ZoteroError};
+
+pub const DEFAULT_API_URL: &str = "https://api.zotero.org";
+const API_VERSION: &str = "3";
+/// Maximum number of objects per page of a listing
+const PAGE_LIMIT: usize = 100;
+/// Maximum number of objects per write or delete request
+pub const WRITE_LIMIT: usize = 50;
+
+/// Data returned with the library version it was read at
+#[derive(Debug, Clone)]
+pub struct Versioned<T> {
+    pub data: T,
+    pub library_version: u64,
+}
+
+/// An item or collection as returned by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiObject {
+    pub key: String,
+    pub version: u64,
+    pub data: Value,
+}
+
+/// Keys of the objects deleted since a version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Deleted {
+    #[serde(default)]
+    pub items: Vec<String>,
+    #[serde(default)]
+    pub collections: Vec<String>,
+}
+
+/// Outcome of writing multiple objects, by index in the request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WriteResult {
+    #[serde(default)]
+    pub successful: HashMap<String, ApiObject>,
+    #[serde(default)]
+    pub unchanged: HashMap<String, String>,
+    #[serde(default)]
+    pub failed: HashMap<String, WriteFailure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteFailure {
+    pub key: Option<String>,
+    pub code: u16,
+    pub message: String,
+}
+
+/// Client for one user or group library
+pub struct ZoteroApi {
+    http: reqwest::Client,
+    base_url: String,
+    library_prefix: String,
+    api_key: String,
+}
+
+impl ZoteroApi {
+    pub fn new(config: &ZoteroConfig) -> Result<Self> {
+        let library_prefix = match config.library_type {
+            LibraryType::User => {
+                let user_id = config.user_id.as_ref().ok_or_else(|| {
+                    ZoteroError::InvalidItemData("User ID required for user library".to_string())
+                })?;
+                format!("/users/{}", user_id)
+            }
+            LibraryType::Group => {
+                let group_id = config.group_id.as_ref().ok_or_else(|| {
+                    ZoteroError::InvalidItemData("Group ID required for group library".to_string())
+                })?;
+                format!("/groups/{}", group_id)
+            }
+        };
+
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base_url: config
+                .api_url
+                .clone()
+                .unwrap_or_else(|| DEFAULT_API_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            library_prefix,
+            api_key: config.api_key.clone(),
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}{}{}", self.base_url, self.library_prefix, path);
+        let request = self
+            .http
+            .request(method, url)
+            .header("Zotero-API-Version", API_VERSION);
+
+        // Sent as bearer token, so it gets dropped when following redirects to file storage
+        if self.api_key.is_empty() {
+            request
+        } else {
+            request.header(AUTHORIZATION, format!("Bearer {}", self.api_key))
+        }
+    }
+
+    /// Get all items or collections changed since a library version
+    ///
+    /// Returns `None` if nothing changed since that version.
+    pub async fn get_changed(
+        &self,
+        path: &str,
+        since: u64,
+    ) -> Result<Option<Versioned<Vec<ApiObject>>>> {
+        let mut objects = Vec::new();
+        let mut library_version = None;
+        let mut start = 0;
+
+        loop {
+            let response = self
+                .request(Method::GET, path)
+                .header("If-Modified-Since-Version", since.to_string())
+                .query(&[
+                    ("since", since.to_string()),
+                    ("format", "json".to_string()),
+                    ("start", start.to_string()),
+                    ("limit", PAGE_LIMIT.to_string()),
+                ])
+                .send()
+                .await?;
+            if response.status() == StatusCode::NOT_MODIFIED {
+                return Ok(None);
+            }
+            let response = check_status(response).await?;
+
+            let version = get_library_version(response.headers())?;
+            // The library changed while paging, start over to get a consistent state
+            if library_version.is_some_and(|library_version| library_version != version) {
+                objects.clear();
+                start = 0;
+                library_version = None;
+                continue;
+            }
+            library_version = Some(version);
+
+            let total = get_header(response.headers(), "Total-Results")
+                .and_then(|total| total.parse::<usize>().ok());
+            let page: Vec<ApiObject> = response.json().await?;
+            let page_len = page.len();
+            objects.extend(page);
+            start += page_len;
+
+            let done = match total {
+                Some(total) => start >= total,
+                None => page_len < PAGE_LIMIT,
+            };
+            if done || page_len == 0 {
+                break;
+            }
+        }
+
+        Ok(library_version.map(|library_version| Versioned {
+            data: objects,
+            library_version,
+        }))
+    }
+
+    /// Get the keys of the objects deleted since a library version
+    pub async fn get_deleted(&self, since: u64) -> Result<Versioned<Deleted>> {
+        let response = self
+            .request(Method::GET, "/deleted")
+            .query(&[("since", since.to_string())])
+            .send()
+            .await?;
+        let response = check_status(response).await?;
+
+        let library_version = get_library_version(response.headers())?;
+        Ok(Versioned {
+            data: response.json().await?,
+            library_version,
+        })
+    }
+
+    /// Create or update up to [`WRITE_LIMIT`] objects
+    ///
+    /// Objects with a `version` are only updated if unchanged since that version,
+    /// the others fail with code 412. The whole request fails with
+    /// [`ZoteroError::PreconditionFailed`] if the library changed since `library_version`.
+    pub async fn write(
+        &self,
+        path: &str,
+        objects: &[Value],
+        library_version: u64,
+    ) -> Result<Versioned<WriteResult>> {
+        let response = self
+            .request(Method::POST, path)
+            .header("If-Unmodified-Since-Version", library_version.to_string())
+            .json(objects)
+            .send()
+            .await?;
+        let response = check_status(response).await?;
+
+        let library_version = get_library_version(response.headers())?;
+        Ok(Versioned {
+            data: response.json().await?,
+            library_version,
+        })
+    }
+
+    /// Delete up to [`WRITE_LIMIT`] objects, returns the new library version
+    ///
+    /// `key_param` is `itemKey` or `collectionKey`, depending on the objects.
+    pub async fn delete(
+        &self,
+        path: &str,
+        key_param: &str,
+        keys: &[String],
+        library_version: u64,
+    ) -> Result<u64> {
+        let response = self
+            .request(Method::DELETE, path)
+            .header("If-Unmodified-Since-Version", library_version.to_string())
+            .query(&[(key_param, keys.join(","))])
+            .send()
+            .await?;
+        let response = check_status(response).await?;
+
+        get_library_version(response.headers())
+    }
+
+    /// Download the file of an attachment item
+    pub async fn download_file(&self, item_key: &str) -> Result<Vec<u8>> {
+        let response = self
+            .request(Method::GET, &format!("/items/{}/file", item_key))
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ZoteroError::ItemNotFound(item_key.to_string()));
+        }
+        let response = check_status(response).await?;
+
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+fn get_header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn get_library_version(headers: &HeaderMap) -> Result<u64> {
+    get_header(headers, "Last-Modified-Version")
+        .and_then(|version| version.parse().ok())
+        .ok_or_else(|| ZoteroError::Unknown("Missing Last-Modified-Version header".to_string()))
+}
+
+/// Turn error responses into the matching [`ZoteroError`]
+async fn check_status(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let message = response.text().await.unwrap_or_default();
+    Err(match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            ZoteroError::AuthenticationError(message)
+        }
+        StatusCode::PRECONDITION_FAILED => ZoteroError::PreconditionFailed(message),
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {
+            ZoteroError::RateLimitExceeded
+        }
+        _ => ZoteroError::Unknown(format!("HTTP {}: {}", status, message)),
+    })
+}
@@ -0,0 +1,1152 @@
+//! # Bibliography Export for Zotero Items
+//!
+//! Renders [`ZoteroItem`]s locally, without the Zotero server:
+//!
+//! * bibliography files in BibTeX, BibLaTeX, RIS and CSL-JSON, to be
+//!   imported into LaTeX, Word or any reference manager.
+//! * citations in the APA (7th edition), IEEE and Harvard (Cite Them
+//!   Right) styles, as plain text for the bibliography entry and the
+//!   in-text citation.
+//!
+//! Notes and attachments are not references and are left out.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+use super::zotero_complete::{Creator, ZoteroItem};
+
+/// File format of an exported bibliography
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BibliographyFormat {
+    Bibtex,
+    Biblatex,
+    Ris,
+    CslJson,
+}
+
+impl BibliographyFormat {
+    /// Extension of files in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BibliographyFormat::Bibtex | BibliographyFormat::Biblatex => "bib",
+            BibliographyFormat::Ris => "ris",
+            BibliographyFormat::CslJson => "json",
+        }
+    }
+}
+
+/// Citation style to format references in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CitationStyle {
+    Apa,
+    Ieee,
+    Harvard,
+}
+
+/// A reference formatted in a citation style
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Citation {
+    pub key: String,
+    /// Citation in the text, such as "(Turing, 1937)" or "[1]"
+    pub in_text: String,
+    /// Entry of the reference list
+    pub bibliography: String,
+}
+
+/// Whether an item is a reference, rather than a standalone note or attachment
+fn is_reference(item: &ZoteroItem) -> bool {
+    item.item_type != "note" && item.item_type != "attachment"
+}
+
+/// Export items as a bibliography file
+pub fn export(items: &[ZoteroItem], format: BibliographyFormat) -> String {
+    let items: Vec<&ZoteroItem> = items.iter().filter(|item| is_reference(item)).collect();
+
+    match format {
+        BibliographyFormat::Bibtex => to_bibtex(&items, false),
+        BibliographyFormat::Biblatex => to_bibtex(&items, true),
+        BibliographyFormat::Ris => to_ris(&items),
+        BibliographyFormat::CslJson => to_csl_json(&items),
+    }
+}
+
+/// Format items in a citation style
+///
+/// APA and Harvard references are sorted by author and year, IEEE ones
+/// are numbered in the order of the given items.
+pub fn format_citations(items: &[ZoteroItem], style: CitationStyle) -> Vec<Citation> {
+    let mut items: Vec<&ZoteroItem> = items.iter().filter(|item| is_reference(item)).collect();
+
+    if style != CitationStyle::Ieee {
+        items.sort_by_cached_key(|item| {
+            (
+                authors(item)
+                    .first()
+                    .map(|creator| family_name(creator).to_lowercase())
+                    .unwrap_or_else(|| title(item).to_lowercase()),
+                year(item).unwrap_or_default(),
+                title(item).to_lowercase(),
+            )
+        });
+    }
+
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| match style {
+            CitationStyle::Apa => Citation {
+                key: item.key.clone(),
+                in_text: apa_in_text(item),
+                bibliography: apa_entry(item),
+            },
+            CitationStyle::Ieee => Citation {
+                key: item.key.clone(),
+                in_text: format!("[{}]", index + 1),
+                bibliography: format!("[{}] {}", index + 1, ieee_entry(item)),
+            },
+            CitationStyle::Harvard => Citation {
+                key: item.key.clone(),
+                in_text: harvard_in_text(item),
+                bibliography: harvard_entry(item),
+            },
+        })
+        .collect()
+}
+
+// Item fields
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn title(item: &ZoteroItem) -> &str {
+    non_empty(&item.title).unwrap_or("Untitled")
+}
+
+fn creators<'a>(item: &'a ZoteroItem, creator_type: &str) -> Vec<&'a Creator> {
+    item.creators
+        .iter()
+        .filter(|creator| creator.creator_type == creator_type)
+        .collect()
+}
+
+/// Authors of an item, falling back to its editors for edited books
+fn authors(item: &ZoteroItem) -> Vec<&Creator> {
+    let authors = creators(item, "author");
+    if authors.is_empty() {
+        creators(item, "editor")
+    } else {
+        authors
+    }
+}
+
+/// Last name of a person, or the full name of an institution
+fn family_name(creator: &Creator) -> &str {
+    creator.last_name.as_deref().unwrap_or_default().trim()
+}
+
+fn given_name(creator: &Creator) -> Option<&str> {
+    creator
+        .first_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// Initials of given names, "Jean-Paul Marie" becomes "J.-P. M."
+fn initials(given: &str) -> String {
+    given
+        .split_whitespace()
+        .map(|name| {
+            name.split('-')
+                .filter_map(|part| part.chars().next())
+                .map(|initial| format!("{}.", initial))
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Year, month and day of the date of an item, as far as known
+fn date_parts(item: &ZoteroItem) -> Vec<u32> {
+    let Some(date) = non_empty(&item.date) else {
+        return vec![];
+    };
+
+    // ISO dates, "2021-03-04" or "2021-03"
+    let iso: Vec<&str> = date.split(['-', '/']).collect();
+    if iso[0].len() == 4 {
+        let parts: Vec<u32> = iso
+            .iter()
+            .map_while(|part| part.parse().ok())
+            .take(3)
+            .collect();
+        if !parts.is_empty() {
+            return parts;
+        }
+    }
+
+    // Free-form dates such as "March 2021", only the year is reliable
+    date.split(|c: char| !c.is_ascii_digit())
+        .find(|part| part.len() == 4)
+        .and_then(|year| year.parse().ok())
+        .map(|year| vec![year])
+        .unwrap_or_default()
+}
+
+fn year(item: &ZoteroItem) -> Option<String> {
+    date_parts(item).first().map(|year| year.to_string())
+}
+
+/// First and last page of a page range
+fn page_range(pages: &str) -> (&str, Option<&str>) {
+    match pages.split_once(['-', '–']) {
+        Some((first, last)) => (
+            first.trim(),
+            Some(last.trim_start_matches(['-', '–']).trim()),
+        ),
+        None => (pages.trim(), None),
+    }
+}
+
+/// Format a list as "a, b, and c" with a custom last separator
+fn join_list(parts: &[String], separator: &str, last_separator: &str) -> String {
+    match parts {
+        [] => String::new(),
+        [single] => single.clone(),
+        [rest @ .., last] => format!("{}{}{}", rest.join(separator), last_separator, last),
+    }
+}
+
+/// Append a period, unless the text already ends with punctuation
+fn with_period(text: &str) -> String {
+    if text.ends_with(['.', '?', '!']) {
+        text.to_string()
+    } else {
+        format!("{}.", text)
+    }
+}
+
+// BibTeX and BibLaTeX
+
+fn bibtex_type(item_type: &str, biblatex: bool) -> &'static str {
+    match item_type {
+        "journalArticle" | "magazineArticle" | "newspaperArticle" => "article",
+        "book" => "book",
+        "bookSection" => "incollection",
+        "conferencePaper" => "inproceedings",
+        "thesis" if biblatex => "thesis",
+        "thesis" => "phdthesis",
+        "report" if biblatex => "report",
+        "report" => "techreport",
+        "webpage" | "blogPost" if biblatex => "online",
+        "manuscript" => "unpublished",
+        _ => "misc",
+    }
+}
+
+/// Escape the characters with a special meaning in LaTeX
+fn escape_latex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn bibtex_names(creators: &[&Creator]) -> String {
+    creators
+        .iter()
+        .map(|creator| match given_name(creator) {
+            Some(given) => format!("{}, {}", family_name(creator), given),
+            // Braces keep institutions from being split into first and last name
+            None => format!("{{{}}}", family_name(creator)),
+        })
+        .collect::<Vec<_>>()
+        .join(" and ")
+}
+
+/// Lowercase ASCII letters and digits of a word, without accents
+fn key_part(word: &str) -> String {
+    word.to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            'a'..='z' | '0'..='9' => Some(c),
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => Some('a'),
+            'ç' | 'ć' | 'č' => Some('c'),
+            'è' | 'é' | 'ê' | 'ë' | 'ě' => Some('e'),
+            'ì' | 'í' | 'î' | 'ï' => Some('i'),
+            'ñ' | 'ń' | 'ň' => Some('n'),
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => Some('o'),
+            'ř' => Some('r'),
+            'ś' | 'š' | 'ß' => Some('s'),
+            'ù' | 'ú' | 'û' | 'ü' | 'ů' => Some('u'),
+            'ý' | 'ÿ' => Some('y'),
+            'ź' | 'ż' | 'ž' => Some('z'),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Citation key such as "turing1937computable", unique among the exported items
+fn cite_key(item: &ZoteroItem, used: &mut HashMap<String, usize>) -> String {
+    let name = key_part(
+        authors(item)
+            .first()
+            .map(|creator| family_name(creator))
+            .unwrap_or_default(),
+    );
+    let word: String = title(item)
+        .split_whitespace()
+        .map(key_part)
+        .find(|word| word.len() > 3 && !["with", "from", "into", "about"].contains(&word.as_str()))
+        .unwrap_or_default();
+
+    let mut key = format!("{}{}{}", name, year(item).unwrap_or_default(), word);
+    if key.is_empty() {
+        key = item.key.to_lowercase();
+    }
+
+    // Later duplicates get a suffix, "turing1937computablea", skipping the
+    // keys that are taken already. `used` maps every key to the next suffix
+    // to try for its duplicates.
+    if let Some(&next) = used.get(&key) {
+        let mut n = next;
+        let unique = loop {
+            let candidate = format!("{}{}", key, key_suffix(n));
+            n += 1;
+            if !used.contains_key(&candidate) {
+                break candidate;
+            }
+        };
+        used.insert(key, n);
+        key = unique;
+    }
+    used.insert(key.clone(), 0);
+    key
+}
+
+/// Suffix of the n-th duplicate of a citation key: "a" to "z", then "aa", "ab", ...
+fn key_suffix(mut n: usize) -> String {
+    let mut suffix = Vec::new();
+    loop {
+        suffix.push(b'a' + (n % 26) as u8);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    suffix.reverse();
+    String::from_utf8(suffix).unwrap()
+}
+
+fn to_bibtex(items: &[&ZoteroItem], biblatex: bool) -> String {
+    let mut used_keys = HashMap::new();
+    let mut entries = Vec::new();
+
+    for item in items {
+        let entry_type = bibtex_type(&item.item_type, biblatex);
+        let mut fields: Vec<(&str, String)> = Vec::new();
+
+        let authors = creators(item, "author");
+        if !authors.is_empty() {
+            fields.push(("author", bibtex_names(&authors)));
+        }
+        let editors = creators(item, "editor");
+        if !editors.is_empty() {
+            fields.push(("editor", bibtex_names(&editors)));
+        }
+        fields.push(("title", escape_latex(title(item))));
+
+        if let Some(publication) = non_empty(&item.publication_title) {
+            let field = match (entry_type, biblatex) {
+                ("article", true) => "journaltitle",
+                ("article", false) => "journal",
+                ("incollection" | "inproceedings", _) => "booktitle",
+                _ => "howpublished",
+            };
+            fields.push((field, escape_latex(publication)));
+        }
+
+        if biblatex {
+            let date = date_parts(item)
+                .iter()
+                .enumerate()
+                .map(|(index, part)| match index {
+                    0 => format!("{:04}", part),
+                    _ => format!("{:02}", part),
+                })
+                .collect::<Vec<_>>()
+                .join("-");
+            if !date.is_empty() {
+                fields.push(("date", date));
+            }
+        } else if let Some(year) = year(item) {
+            fields.push(("year", year));
+        }
+
+        if let Some(volume) = non_empty(&item.volume) {
+            fields.push(("volume", escape_latex(volume)));
+        }
+        if let Some(issue) = non_empty(&item.issue) {
+            fields.push(("number", escape_latex(issue)));
+        }
+        if let Some(pages) = non_empty(&item.pages) {
+            let pages = match page_range(pages) {
+                (first, Some(last)) => format!("{}--{}", first, last),
+                (first, None) => first.to_string(),
+            };
+            fields.push(("pages", escape_latex(&pages)));
+        }
+        if let Some(publisher) = non_empty(&item.publisher) {
+            let field = match entry_type {
+                "phdthesis" | "thesis" => "school",
+                "techreport" | "report" => "institution",
+                _ => "publisher",
+            };
+            fields.push((field, escape_latex(publisher)));
+        }
+        if let Some(doi) = non_empty(&item.doi) {
+            fields.push(("doi", doi.to_string()));
+        }
+        if let Some(isbn) = non_empty(&item.isbn) {
+            fields.push(("isbn", isbn.to_string()));
+        }
+        if let Some(url) = non_empty(&item.url) {
+            fields.push(("url", url.to_string()));
+        }
+        if let Some(language) = non_empty(&item.language) {
+            fields.push(("language", escape_latex(language)));
+        }
+        if !item.tags.is_empty() {
+            let keywords = item
+                .tags
+                .iter()
+                .map(|tag| tag.tag.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            fields.push(("keywords", escape_latex(&keywords)));
+        }
+        if let Some(abstract_note) = non_empty(&item.abstract_note) {
+            fields.push(("abstract", escape_latex(abstract_note)));
+        }
+
+        let fields = fields
+            .iter()
+            .map(|(name, value)| format!("  {} = {{{}}}", name, value))
+            .collect::<Vec<_>>()
+            .join(",\n");
+        entries.push(format!(
+            "@{}{{{},\n{}\n}}\n",
+            entry_type,
+            cite_key(item, &mut used_keys),
+            fields
+        ));
+    }
+
+    entries.join("\n")
+}
+
+// RIS
+
+fn ris_type(item_type: &str) -> &'static str {
+    match item_type {
+        "journalArticle" => "JOUR",
+        "magazineArticle" => "MGZN",
+        "newspaperArticle" => "NEWS",
+        "book" => "BOOK",
+        "bookSection" => "CHAP",
+        "conferencePaper" => "CPAPER",
+        "thesis" => "THES",
+        "report" => "RPRT",
+        "webpage" | "blogPost" => "ELEC",
+        "manuscript" => "UNPB",
+        _ => "GEN",
+    }
+}
+
+fn to_ris(items: &[&ZoteroItem]) -> String {
+    let mut lines = Vec::new();
+
+    for item in items {
+        let mut tag = |name: &str, value: &str| lines.push(format!("{}  - {}", name, value));
+
+        tag("TY", ris_type(&item.item_type));
+        for (creator_type, ris_tag) in [("author", "AU"), ("editor", "ED")] {
+            for creator in creators(item, creator_type) {
+                match given_name(creator) {
+                    Some(given) => tag(ris_tag, &format!("{}, {}", family_name(creator), given)),
+                    None => tag(ris_tag, family_name(creator)),
+                }
+            }
+        }
+        tag("TI", title(item));
+        if let Some(publication) = non_empty(&item.publication_title) {
+            tag("T2", publication);
+        }
+        if let Some(year) = year(item) {
+            tag("PY", &year);
+        }
+        if let Some(date) = non_empty(&item.date) {
+            tag("DA", date);
+        }
+        if let Some(volume) = non_empty(&item.volume) {
+            tag("VL", volume);
+        }
+        if let Some(issue) = non_empty(&item.issue) {
+            tag("IS", issue);
+        }
+        if let Some(pages) = non_empty(&item.pages) {
+            let (first, last) = page_range(pages);
+            tag("SP", first);
+            if let Some(last) = last {
+                tag("EP", last);
+            }
+        }
+        if let Some(publisher) = non_empty(&item.publisher) {
+            tag("PB", publisher);
+        }
+        if let Some(doi) = non_empty(&item.doi) {
+            tag("DO", doi);
+        }
+        if let Some(isbn) = non_empty(&item.isbn) {
+            tag("SN", isbn);
+        }
+        if let Some(url) = non_empty(&item.url) {
+            tag("UR", url);
+        }
+        if let Some(language) = non_empty(&item.language) {
+            tag("LA", language);
+        }
+        for keyword in &item.tags {
+            tag("KW", &keyword.tag);
+        }
+        if let Some(abstract_note) = non_empty(&item.abstract_note) {
+            // Values can't span lines
+            tag(
+                "AB",
+                &abstract_note
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+        }
+        tag("ER", "");
+        lines.push(String::new());
+    }
+
+    // The RIS specification asks for CRLF line endings
+    lines.join("\r\n")
+}
+
+// CSL-JSON
+
+fn csl_type(item_type: &str) -> &'static str {
+    match item_type {
+        "journalArticle" => "article-journal",
+        "magazineArticle" => "article-magazine",
+        "newspaperArticle" => "article-newspaper",
+        "book" => "book",
+        "bookSection" => "chapter",
+        "conferencePaper" => "paper-conference",
+        "thesis" => "thesis",
+        "report" => "report",
+        "webpage" => "webpage",
+        "blogPost" => "post-weblog",
+        "manuscript" => "manuscript",
+        _ => "document",
+    }
+}
+
+fn csl_names(creators: &[&Creator]) -> Value {
+    creators
+        .iter()
+        .map(|creator| match given_name(creator) {
+            Some(given) => json!({ "family": family_name(creator), "given": given }),
+            None => json!({ "literal": family_name(creator) }),
+        })
+        .collect()
+}
+
+fn to_csl_json(items: &[&ZoteroItem]) -> String {
+    let entries: Vec<Value> = items
+        .iter()
+        .map(|item| {
+            let mut entry = Map::new();
+            entry.insert("id".to_string(), json!(item.key));
+            entry.insert("type".to_string(), json!(csl_type(&item.item_type)));
+            entry.insert("title".to_string(), json!(title(item)));
+
+            for (creator_type, field) in [("author", "author"), ("editor", "editor")] {
+                let names = creators(item, creator_type);
+                if !names.is_empty() {
+                    entry.insert(field.to_string(), csl_names(&names));
+                }
+            }
+
+            let date = date_parts(item);
+            if !date.is_empty() {
+                entry.insert("issued".to_string(), json!({ "date-parts": [date] }));
+            }
+
+            let keywords = item
+                .tags
+                .iter()
+                .map(|tag| tag.tag.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let fields = [
+                ("container-title", non_empty(&item.publication_title)),
+                ("volume", non_empty(&item.volume)),
+                ("issue", non_empty(&item.issue)),
+                ("page", non_empty(&item.pages)),
+                ("publisher", non_empty(&item.publisher)),
+                ("DOI", non_empty(&item.doi)),
+                ("ISBN", non_empty(&item.isbn)),
+                ("URL", non_empty(&item.url)),
+                ("language", non_empty(&item.language)),
+                ("abstract", non_empty(&item.abstract_note)),
+                ("keyword", Some(keywords.as_str()).filter(|k| !k.is_empty())),
+            ];
+            for (field, value) in fields {
+                if let Some(value) = value {
+                    entry.insert(field.to_string(), json!(value));
+                }
+            }
+
+            Value::Object(entry)
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&entries).unwrap_or_else(|_| "[]".to_string())
+}
+
+// Citation styles
+
+fn doi_url(doi: &str) -> String {
+    if doi.starts_with("http") {
+        doi.to_string()
+    } else {
+        format!("https://doi.org/{}", doi)
+    }
+}
+
+/// Whether the item is published as part of a larger work, with its own title
+fn is_part(item: &ZoteroItem) -> bool {
+    !matches!(item.item_type.as_str(), "book" | "thesis" | "report")
+}
+
+fn apa_name(creator: &Creator) -> String {
+    match given_name(creator) {
+        Some(given) => format!("{}, {}", family_name(creator), initials(given)),
+        None => family_name(creator).to_string(),
+    }
+}
+
+/// "(Turing, 1937)", "(Turing & Church, 1937)" or "(Turing et al., 1937)"
+fn apa_in_text(item: &ZoteroItem) -> String {
+    let year = year(item).unwrap_or_else(|| "n.d.".to_string());
+    format!("({}, {})", short_authors(item, " & "), year)
+}
+
+fn short_authors(item: &ZoteroItem, and: &str) -> String {
+    match authors(item).as_slice() {
+        [] => title(item).to_string(),
+        [single] => family_name(single).to_string(),
+        [first, second] => format!("{}{}{}", family_name(first), and, family_name(second)),
+        [first, ..] => format!("{} et al.", family_name(first)),
+    }
+}
+
+fn apa_entry(item: &ZoteroItem) -> String {
+    let names: Vec<String> = authors(item).into_iter().map(apa_name).collect();
+    // Up to 20 authors are listed, then an ellipsis and the last one
+    let names = if names.len() > 20 {
+        format!(
+            "{}, . . . {}",
+            names[..19].join(", "),
+            names[names.len() - 1]
+        )
+    } else {
+        join_list(&names, ", ", ", & ")
+    };
+    let year = year(item).unwrap_or_else(|| "n.d.".to_string());
+
+    let mut entry = if names.is_empty() {
+        format!("{} ({}).", with_period(title(item)), year)
+    } else {
+        format!(
+            "{} ({}). {}",
+            with_period(&names),
+            year,
+            with_period(title(item))
+        )
+    };
+
+    if is_part(item) {
+        if let Some(publication) = non_empty(&item.publication_title) {
+            let prefix = if item.item_type == "bookSection" {
+                "In "
+            } else {
+                ""
+            };
+            let mut source = format!("{}{}", prefix, publication);
+            if let Some(volume) = non_empty(&item.volume) {
+                source.push_str(&format!(", {}", volume));
+                if let Some(issue) = non_empty(&item.issue) {
+                    source.push_str(&format!("({})", issue));
+                }
+            }
+            if let Some(pages) = non_empty(&item.pages) {
+                source.push_str(&format!(", {}", pages.replace('-', "–")));
+            }
+            entry.push_str(&format!(" {}", with_period(&source)));
+        }
+    }
+    if let Some(publisher) = non_empty(&item.publisher) {
+        if !is_part(item) || item.item_type == "bookSection" {
+            entry.push_str(&format!(" {}", with_period(publisher)));
+        }
+    }
+
+    if let Some(doi) = non_empty(&item.doi) {
+        entry.push_str(&format!(" {}", doi_url(doi)));
+    } else if let Some(url) = non_empty(&item.url) {
+        entry.push_str(&format!(" {}", url));
+    }
+
+    entry
+}
+
+fn ieee_name(creator: &Creator) -> String {
+    match given_name(creator) {
+        Some(given) => format!("{} {}", initials(given), family_name(creator)),
+        None => family_name(creator).to_string(),
+    }
+}
+
+fn ieee_entry(item: &ZoteroItem) -> String {
+    let names: Vec<String> = authors(item).into_iter().map(ieee_name).collect();
+    // More than six authors are shortened to the first one
+    let names = if names.len() > 6 {
+        format!("{} et al.", names[0])
+    } else {
+        join_list(
+            &names,
+            ", ",
+            if names.len() > 2 { ", and " } else { " and " },
+        )
+    };
+
+    let mut parts = Vec::new();
+    let mut entry = if names.is_empty() {
+        String::new()
+    } else {
+        format!("{}, ", names)
+    };
+
+    if is_part(item) {
+        entry.push_str(&format!("“{},”", title(item)));
+        if let Some(publication) = non_empty(&item.publication_title) {
+            let prefix = match item.item_type.as_str() {
+                "bookSection" | "conferencePaper" => "in ",
+                _ => "",
+            };
+            parts.push(format!("{}{}", prefix, publication));
+        }
+        if let Some(volume) = non_empty(&item.volume) {
+            parts.push(format!("vol. {}", volume));
+        }
+        if let Some(issue) = non_empty(&item.issue) {
+            parts.push(format!("no. {}", issue));
+        }
+        if let Some(pages) = non_empty(&item.pages) {
+            let prefix = if page_range(pages).1.is_some() {
+                "pp."
+            } else {
+                "p."
+            };
+            parts.push(format!("{} {}", prefix, pages.replace('-', "–")));
+        }
+        if let Some(year) = year(item) {
+            parts.push(year);
+        }
+    } else {
+        entry.push_str(title(item));
+        let mut publication = Vec::new();
+        if let Some(publisher) = non_empty(&item.publisher) {
+            publication.push(publisher.to_string());
+        }
+        if let Some(year) = year(item) {
+            publication.push(year);
+        }
+        if !publication.is_empty() {
+            entry.push('.');
+            parts.push(publication.join(", "));
+        }
+    }
+
+    if let Some(doi) = non_empty(&item.doi) {
+        parts.push(format!("doi: {}", doi));
+    }
+
+    let mut entry = if parts.is_empty() {
+        entry
+    } else {
+        format!("{} {}", entry, parts.join(", "))
+    };
+    entry = with_period(entry.trim_end_matches(','));
+    if non_empty(&item.doi).is_none() {
+        if let Some(url) = non_empty(&item.url) {
+            entry.push_str(&format!(" [Online]. Available: {}", url));
+        }
+    }
+    entry
+}
+
+/// "(Turing, 1937)", "(Turing and Church, 1937)" or "(Turing et al., 1937)"
+fn harvard_in_text(item: &ZoteroItem) -> String {
+    let year = year(item).unwrap_or_else(|| "no date".to_string());
+    format!("({}, {})", short_authors(item, " and "), year)
+}
+
+fn harvard_name(creator: &Creator) -> String {
+    match given_name(creator) {
+        Some(given) => format!("{}, {}", family_name(creator), initials(given)),
+        None => family_name(creator).to_string(),
+    }
+}
+
+fn harvard_entry(item: &ZoteroItem) -> String {
+    let names: Vec<String> = authors(item).into_iter().map(harvard_name).collect();
+    let names = join_list(&names, ", ", " and ");
+    let year = year(item).unwrap_or_else(|| "no date".to_string());
+
+    let mut entry = if names.is_empty() {
+        format!("{} ({})", title(item), year)
+    } else {
+        format!("{} ({})", names, year)
+    };
+
+    if is_part(item) {
+        if !names.is_empty() {
+            entry.push_str(&format!(" ‘{}’", title(item)));
+        }
+        if let Some(publication) = non_empty(&item.publication_title) {
+            let prefix = if item.item_type == "bookSection" {
+                "in "
+            } else {
+                ""
+            };
+            let mut source = format!("{}{}", prefix, publication);
+            if let Some(volume) = non_empty(&item.volume) {
+                source.push_str(&format!(", {}", volume));
+                if let Some(issue) = non_empty(&item.issue) {
+                    source.push_str(&format!("({})", issue));
+                }
+            }
+            if let Some(pages) = non_empty(&item.pages) {
+                let prefix = if page_range(pages).1.is_some() {
+                    "pp."
+                } else {
+                    "p."
+                };
+                source.push_str(&format!(", {} {}", prefix, pages.replace('-', "–")));
+            }
+            entry.push_str(&format!(", {}", source));
+        }
+        entry = with_period(&entry);
+    } else {
+        if !names.is_empty() {
+            entry.push_str(&format!(" {}", title(item)));
+        }
+        entry = with_period(&entry);
+        if let Some(publisher) = non_empty(&item.publisher) {
+            entry.push_str(&format!(" {}", with_period(publisher)));
+        }
+    }
+
+    if let Some(doi) = non_empty(&item.doi) {
+        entry.push_str(&format!(" doi:{}.", doi));
+    } else if let Some(url) = non_empty(&item.url) {
+        entry.push_str(&format!(" Available at: {}.", url));
+    }
+
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tauri::zotero_complete::Tag;
+
+    fn person(last_name: &str, first_name: &str) -> Creator {
+        Creator {
+            creator_type: "author".to_string(),
+            first_name: Some(first_name.to_string()),
+            last_name: Some(last_name.to_string()),
+        }
+    }
+
+    fn article() -> ZoteroItem {
+        ZoteroItem {
+            key: "ITEM1111".to_string(),
+            version: Some(1),
+            item_type: "journalArticle".to_string(),
+            title: Some(
+                "On Computable Numbers, with an Application to the Entscheidungsproblem"
+                    .to_string(),
+            ),
+            creators: vec![person("Turing", "Alan Mathison")],
+            tags: vec![Tag {
+                tag: "computability".to_string(),
+                type_: None,
+            }],
+            date_added: None,
+            date_modified: None,
+            abstract_note: None,
+            url: None,
+            doi: Some("10.1112/plms/s2-42.1.230".to_string()),
+            isbn: None,
+            publication_title: Some("Proceedings of the London Mathematical Society".to_string()),
+            volume: Some("s2-42".to_string()),
+            issue: Some("1".to_string()),
+            pages: Some("230-265".to_string()),
+            publisher: None,
+            date: Some("1937-01-01".to_string()),
+            language: Some("en".to_string()),
+            notes: vec![],
+            attachments: vec![],
+            collections: vec![],
+        }
+    }
+
+    fn book() -> ZoteroItem {
+        ZoteroItem {
+            key: "ITEM2222".to_string(),
+            item_type: "book".to_string(),
+            title: Some("Gödel, Escher, Bach: An Eternal Golden Braid".to_string()),
+            creators: vec![person("Hofstadter", "Douglas R.")],
+            tags: vec![],
+            doi: None,
+            isbn: Some("978-0-465-02656-2".to_string()),
+            publication_title: None,
+            volume: None,
+            issue: None,
+            pages: None,
+            publisher: Some("Basic Books".to_string()),
+            date: Some("1979".to_string()),
+            language: None,
+            ..article()
+        }
+    }
+
+    #[test]
+    fn test_bibtex_export() {
+        let bibtex = export(&[article(), book()], BibliographyFormat::Bibtex);
+
+        assert!(bibtex.starts_with("@article{turing1937computable,\n"));
+        assert!(bibtex.contains("  author = {Turing, Alan Mathison},\n"));
+        assert!(bibtex.contains("  journal = {Proceedings of the London Mathematical Society},\n"));
+        assert!(bibtex.contains("  year = {1937},\n"));
+        assert!(bibtex.contains("  pages = {230--265},\n"));
+        assert!(bibtex.contains("@book{hofstadter1979godel,\n"));
+        assert!(bibtex.contains("  publisher = {Basic Books},\n"));
+    }
+
+    #[test]
+    fn test_biblatex_export() {
+        let mut item = article();
+        item.title = Some("Costs & Benefits of 100% Coverage".to_string());
+        let biblatex = export(&[item], BibliographyFormat::Biblatex);
+
+        assert!(biblatex
+            .contains("  journaltitle = {Proceedings of the London Mathematical Society},\n"));
+        assert!(biblatex.contains("  date = {1937-01-01},\n"));
+        assert!(biblatex.contains("  title = {Costs \\& Benefits of 100\\% Coverage},\n"));
+    }
+
+    #[test]
+    fn test_duplicate_cite_keys() {
+        let bibtex = export(&[article(), article()], BibliographyFormat::Bibtex);
+
+        assert!(bibtex.contains("@article{turing1937computable,"));
+        assert!(bibtex.contains("@article{turing1937computablea,"));
+    }
+
+    #[test]
+    fn test_cite_keys_skip_taken_keys() {
+        let mut suffixed = article();
+        suffixed.title = Some("Computablea".to_string());
+
+        // a key with the suffix of a duplicate is taken already
+        let mut used = HashMap::new();
+        let keys: Vec<String> = [article(), suffixed.clone(), article()]
+            .iter()
+            .map(|item| cite_key(item, &mut used))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "turing1937computable",
+                "turing1937computablea",
+                "turing1937computableb"
+            ]
+        );
+
+        // the duplicate took the key of a later item
+        let mut used = HashMap::new();
+        let keys: Vec<String> = [article(), article(), suffixed]
+            .iter()
+            .map(|item| cite_key(item, &mut used))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "turing1937computable",
+                "turing1937computablea",
+                "turing1937computableaa"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_key_suffix() {
+        assert_eq!(key_suffix(0), "a");
+        assert_eq!(key_suffix(25), "z");
+        assert_eq!(key_suffix(26), "aa");
+        assert_eq!(key_suffix(27), "ab");
+        assert_eq!(key_suffix(52), "ba");
+    }
+
+    #[test]
+    fn test_ris_export() {
+        let ris = export(&[article()], BibliographyFormat::Ris);
+        let lines: Vec<&str> = ris.split("\r\n").collect();
+
+        assert_eq!(lines[0], "TY  - JOUR");
+        assert!(lines.contains(&"AU  - Turing, Alan Mathison"));
+        assert!(lines.contains(&"PY  - 1937"));
+        assert!(lines.contains(&"SP  - 230"));
+        assert!(lines.contains(&"EP  - 265"));
+        assert!(lines.contains(&"KW  - computability"));
+        assert!(lines.contains(&"ER  - "));
+    }
+
+    #[test]
+    fn test_csl_json_export() {
+        let mut item = article();
+        item.creators.push(Creator {
+            creator_type: "author".to_string(),
+            first_name: None,
+            last_name: Some("London Mathematical Society".to_string()),
+        });
+        let json: Value =
+            serde_json::from_str(&export(&[item], BibliographyFormat::CslJson)).unwrap();
+
+        assert_eq!(json[0]["id"], "ITEM1111");
+        assert_eq!(json[0]["type"], "article-journal");
+        assert_eq!(
+            json[0]["author"][0],
+            json!({ "family": "Turing", "given": "Alan Mathison" })
+        );
+        assert_eq!(
+            json[0]["author"][1],
+            json!({ "literal": "London Mathematical Society" })
+        );
+        assert_eq!(json[0]["issued"], json!({ "date-parts": [[1937, 1, 1]] }));
+        assert_eq!(json[0]["DOI"], "10.1112/plms/s2-42.1.230");
+    }
+
+    #[test]
+    fn test_notes_are_not_exported() {
+        let mut note = article();
+        note.item_type = "note".to_string();
+
+        assert_eq!(export(&[note.clone()], BibliographyFormat::CslJson), "[]");
+        assert!(format_citations(&[note], CitationStyle::Apa).is_empty());
+    }
+
+    #[test]
+    fn test_apa_citations() {
+        let citations = format_citations(&[article(), book()], CitationStyle::Apa);
+
+        // Sorted by author
+        assert_eq!(citations[0].key, "ITEM2222");
+        assert_eq!(citations[0].in_text, "(Hofstadter, 1979)");
+        assert_eq!(
+            citations[0].bibliography,
+            "Hofstadter, D. R. (1979). Gödel, Escher, Bach: An Eternal Golden Braid. Basic Books."
+        );
+        assert_eq!(citations[1].in_text, "(Turing, 1937)");
+        assert_eq!(
+            citations[1].bibliography,
+            "Turing, A. M. (1937). On Computable Numbers, with an Application to the Entscheidungsproblem. \
+             Proceedings of the London Mathematical Society, s2-42(1), 230–265. \
+             https://doi.org/10.1112/plms/s2-42.1.230"
+        );
+
+        let mut coauthored = article();
+        coauthored.creators.push(person("Church", "Alonzo"));
+        let citation = &format_citations(&[coauthored.clone()], CitationStyle::Apa)[0];
+        assert_eq!(citation.in_text, "(Turing & Church, 1937)");
+        assert!(citation
+            .bibliography
+            .starts_with("Turing, A. M., & Church, A. (1937)."));
+
+        coauthored.creators.push(person("Kleene", "Stephen Cole"));
+        let citation = &format_citations(&[coauthored], CitationStyle::Apa)[0];
+        assert_eq!(citation.in_text, "(Turing et al., 1937)");
+    }
+
+    #[test]
+    fn test_ieee_citations() {
+        let citations = format_citations(&[article(), book()], CitationStyle::Ieee);
+
+        // Numbered in the given order
+        assert_eq!(citations[0].in_text, "[1]");
+        assert_eq!(
+            citations[0].bibliography,
+            "[1] A. M. Turing, “On Computable Numbers, with an Application to the Entscheidungsproblem,” \
+             Proceedings of the London Mathematical Society, vol. s2-42, no. 1, pp. 230–265, 1937, \
+             doi: 10.1112/plms/s2-42.1.230."
+        );
+        assert_eq!(
+            citations[1].bibliography,
+            "[2] D. R. Hofstadter, Gödel, Escher, Bach: An Eternal Golden Braid. Basic Books, 1979."
+        );
+    }
+
+    #[test]
+    fn test_harvard_citations() {
+        let citations = format_citations(&[article(), book()], CitationStyle::Harvard);
+
+        assert_eq!(citations[1].in_text, "(Turing, 1937)");
+        assert_eq!(
+            citations[1].bibliography,
+            "Turing, A. M. (1937) ‘On Computable Numbers, with an Application to the Entscheidungsproblem’, \
+             Proceedings of the London Mathematical Society, s2-42(1), pp. 230–265. \
+             doi:10.1112/plms/s2-42.1.230."
+        );
+        assert_eq!(
+            citations[0].bibliography,
+            "Hofstadter, D. R. (1979) Gödel, Escher, Bach: An Eternal Golden Braid. Basic Books."
+        );
+    }
+}
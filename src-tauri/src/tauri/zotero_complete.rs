@@ -1,23 +1,44 @@
-use zotero::{Zotero, ZoteroInit};
 use sled::{Db, Tree};
 use serde::{Deserialize, Serialize};
-use anyhow::Result;
-use std::path::PathBuf;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use tokio::sync::RwLock;
 use std::sync::Arc;
-use tracing::{info, error, warn};
-use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use tracing::{info, warn};
+use chrono::Utc;
+use rand::Rng;
+
+use super::zotero_api::{ApiObject, ZoteroApi, WRITE_LIMIT};
 
 /// Enhanced Zotero client with sled storage and full async support
+///
+/// The library is cached locally and synced with the Zotero Web API by [`ZoteroManager::sync`].
+/// Local changes are queued while offline and written back on the next sync.
 pub struct ZoteroManager {
-    client: Option<Zotero<'static>>,
+    api: Option<ZoteroApi>,
     db: Arc<Db>,
     items_tree: Arc<Tree>,
     collections_tree: Arc<Tree>,
+    /// Child notes and attachments, by key
+    children_tree: Arc<Tree>,
+    /// Local changes not yet written to Zotero, by "items/<key>" or "collections/<key>"
+    pending_tree: Arc<Tree>,
+    /// Items changed both locally and remotely, by key
+    conflicts_tree: Arc<Tree>,
     config: Arc<RwLock<ZoteroConfig>>,
 }
 
+pub type Result<T> = std::result::Result<T, ZoteroError>;
+
+/// How often to retry a sync when the library changes while writing to it
+const MAX_SYNC_ATTEMPTS: usize = 3;
+const LIBRARY_VERSION_KEY: &str = "library_version";
+const ITEMS: &str = "items";
+const COLLECTIONS: &str = "collections";
+/// Characters of Zotero object keys
+const KEY_CHARS: &[u8] = b"23456789ABCDEFGHIJKLMNPQRSTUVWXYZ";
+
 /// Basic item structure for Zotero items
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZoteroItem {
@@ -42,6 +63,9 @@ pub struct ZoteroItem {
     pub language: Option<String>,
     pub notes: Vec<String>,
     pub attachments: Vec<Attachment>,
+    /// Keys of the collections the item is in
+    #[serde(default)]
+    pub collections: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -76,6 +100,31 @@ pub struct ZoteroCollection {
     pub date_modified: Option<String>,
 }
 
+/// An item changed both locally and in Zotero since the last sync
+///
+/// The remote state wins when syncing, the local one is kept here until the conflict
+/// is resolved. `None` stands for a deleted item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoteroConflict {
+    pub key: String,
+    pub local: Option<ZoteroItem>,
+    pub remote: Option<ZoteroItem>,
+}
+
+/// Outcome of a sync
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub library_version: u64,
+    pub pulled_items: usize,
+    pub pulled_collections: usize,
+    pub deleted: usize,
+    pub pushed: usize,
+    /// Keys of the objects changed both locally and remotely
+    pub conflicts: Vec<String>,
+    /// Objects Zotero refused to save, with the reason
+    pub failed: Vec<String>,
+}
+
 /// Configuration for Zotero integration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZoteroConfig {
@@ -84,9 +133,11 @@ pub struct ZoteroConfig {
     pub api_key: String,
     pub library_type: LibraryType,
     pub cache_duration_minutes: u64,
+    /// Base URL of the Web API, defaults to https://api.zotero.org
+    pub api_url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LibraryType {
     User,
     Group,
@@ -107,6 +158,12 @@ pub enum ZoteroError {
     #[error("Serialization error: {0}")]
     SerializationError(#[from] bincode::Error),
     
+    #[error("Invalid API data: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("File error: {0}")]
+    IoError(#[from] std::io::Error),
+
     #[error("Item not found: {0}")]
     ItemNotFound(String),
     
@@ -115,6 +172,9 @@ pub enum ZoteroError {
     
     #[error("Invalid item data: {0}")]
     InvalidItemData(String),
+
+    #[error("Library changed since last sync: {0}")]
+    PreconditionFailed(String),
     
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
@@ -134,6 +194,7 @@ impl ZoteroConfig {
             api_key,
             library_type: LibraryType::User,
             cache_duration_minutes: 60,
+            api_url: None,
         }
     }
 
@@ -144,30 +205,307 @@ impl ZoteroConfig {
             api_key,
             library_type: LibraryType::Group,
             cache_duration_minutes: 60,
+            api_url: None,
+        }
+    }
+
+    /// Use another server than api.zotero.org, such as a mock server
+    pub fn with_api_url(mut self, api_url: String) -> Self {
+        self.api_url = Some(api_url);
+        self
+    }
+
+    /// Whether both configurations point to the same library
+    fn same_library(&self, other: &ZoteroConfig) -> bool {
+        self.library_type == other.library_type
+            && self.user_id == other.user_id
+            && self.group_id == other.group_id
+            && self.api_url == other.api_url
+    }
+}
+
+/// Local change waiting to be written to Zotero
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum PendingChange {
+    Upsert,
+    /// Deletion of an object, at the version it was last synced at
+    Delete { version: u64 },
+}
+
+/// Child note or attachment of an item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ChildItem {
+    key: String,
+    parent_item: String,
+    item_type: String,
+    note: Option<String>,
+    title: Option<String>,
+    content_type: Option<String>,
+    url: Option<String>,
+    filename: Option<String>,
+}
+
+/// Item data as exchanged with the Web API
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItemData {
+    key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<u64>,
+    item_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent_item: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default)]
+    creators: Vec<CreatorData>,
+    #[serde(default)]
+    tags: Vec<TagData>,
+    #[serde(default)]
+    collections: Vec<String>,
+    #[serde(default, skip_serializing)]
+    date_added: Option<String>,
+    #[serde(default, skip_serializing)]
+    date_modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    abstract_note: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(default, rename = "DOI", skip_serializing_if = "Option::is_none")]
+    doi: Option<String>,
+    #[serde(default, rename = "ISBN", skip_serializing_if = "Option::is_none")]
+    isbn: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    publication_title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    volume: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    issue: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pages: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    publisher: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    #[serde(default, skip_serializing)]
+    note: Option<String>,
+    #[serde(default, skip_serializing)]
+    content_type: Option<String>,
+    #[serde(default, skip_serializing)]
+    filename: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreatorData {
+    creator_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    first_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_name: Option<String>,
+    /// Single-field name, used for institutions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TagData {
+    tag: String,
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    type_: Option<i32>,
+}
+
+impl ItemData {
+    fn from_item(item: &ZoteroItem) -> Self {
+        Self {
+            key: item.key.clone(),
+            version: item.version.map(|version| version as u64),
+            item_type: item.item_type.clone(),
+            parent_item: None,
+            title: item.title.clone(),
+            creators: item
+                .creators
+                .iter()
+                .map(|creator| match creator.first_name {
+                    Some(_) => CreatorData {
+                        creator_type: creator.creator_type.clone(),
+                        first_name: creator.first_name.clone(),
+                        last_name: Some(creator.last_name.clone().unwrap_or_default()),
+                        name: None,
+                    },
+                    None => CreatorData {
+                        creator_type: creator.creator_type.clone(),
+                        first_name: None,
+                        last_name: None,
+                        name: Some(creator.last_name.clone().unwrap_or_default()),
+                    },
+                })
+                .collect(),
+            tags: item
+                .tags
+                .iter()
+                .map(|tag| TagData {
+                    tag: tag.tag.clone(),
+                    type_: tag.type_,
+                })
+                .collect(),
+            collections: item.collections.clone(),
+            date_added: None,
+            date_modified: None,
+            abstract_note: item.abstract_note.clone(),
+            url: item.url.clone(),
+            doi: item.doi.clone(),
+            isbn: item.isbn.clone(),
+            publication_title: item.publication_title.clone(),
+            volume: item.volume.clone(),
+            issue: item.issue.clone(),
+            pages: item.pages.clone(),
+            publisher: item.publisher.clone(),
+            date: item.date.clone(),
+            language: item.language.clone(),
+            note: None,
+            content_type: None,
+            filename: None,
+        }
+    }
+
+    fn into_item(self, version: u64) -> ZoteroItem {
+        ZoteroItem {
+            key: self.key,
+            version: Some(version as i32),
+            item_type: self.item_type,
+            title: self.title,
+            creators: self
+                .creators
+                .into_iter()
+                .map(|creator| Creator {
+                    creator_type: creator.creator_type,
+                    first_name: creator.first_name,
+                    last_name: creator.last_name.or(creator.name),
+                })
+                .collect(),
+            tags: self
+                .tags
+                .into_iter()
+                .map(|tag| Tag {
+                    tag: tag.tag,
+                    type_: tag.type_,
+                })
+                .collect(),
+            date_added: self.date_added,
+            date_modified: self.date_modified,
+            abstract_note: self.abstract_note,
+            url: self.url,
+            doi: self.doi,
+            isbn: self.isbn,
+            publication_title: self.publication_title,
+            volume: self.volume,
+            issue: self.issue,
+            pages: self.pages,
+            publisher: self.publisher,
+            date: self.date,
+            language: self.language,
+            notes: vec![],
+            attachments: vec![],
+            collections: self.collections,
+        }
+    }
+
+    fn into_child(self, parent_item: String) -> ChildItem {
+        ChildItem {
+            key: self.key,
+            parent_item,
+            item_type: self.item_type,
+            note: self.note,
+            title: self.title,
+            content_type: self.content_type,
+            url: self.url,
+            filename: self.filename,
+        }
+    }
+}
+
+impl ZoteroCollection {
+    fn to_api(&self) -> Value {
+        let mut data = json!({
+            "key": self.key,
+            "name": self.name,
+            // The API uses `false` for top-level collections
+            "parentCollection": self.parent_collection.clone().map(Value::String).unwrap_or(Value::Bool(false)),
+        });
+        if let Some(version) = self.version {
+            data["version"] = json!(version);
         }
+        data
+    }
+
+    fn from_api(object: &ApiObject) -> Result<Self> {
+        let name = object.data["name"]
+            .as_str()
+            .ok_or_else(|| ZoteroError::InvalidItemData(format!("Collection {} has no name", object.key)))?;
+
+        Ok(Self {
+            key: object.key.clone(),
+            name: name.to_string(),
+            parent_collection: object.data["parentCollection"].as_str().map(|s| s.to_string()),
+            version: Some(object.version as i32),
+            date_added: None,
+            date_modified: None,
+        })
     }
 }
 
+/// Generate a key in Zotero's format, so items created offline can keep it
+fn generate_key() -> String {
+    let mut rng = rand::rng();
+    (0..8)
+        .map(|_| KEY_CHARS[rng.random_range(0..KEY_CHARS.len())] as char)
+        .collect()
+}
+
+fn pending_key(kind: &str, key: &str) -> String {
+    format!("{}/{}", kind, key)
+}
+
 impl ZoteroManager {
     /// Create a new Zotero manager with sled database
     pub async fn new(db_path: PathBuf) -> Result<Self> {
         let db = Arc::new(sled::open(&db_path)?);
         let items_tree = Arc::new(db.open_tree("items")?);
         let collections_tree = Arc::new(db.open_tree("collections")?);
+        let children_tree = Arc::new(db.open_tree("children")?);
+        let pending_tree = Arc::new(db.open_tree("pending")?);
+        let conflicts_tree = Arc::new(db.open_tree("conflicts")?);
         
         info!("Zotero manager initialized with database at: {:?}", db_path);
         
         Ok(Self {
-            client: None,
+            api: None,
             db,
             items_tree,
             collections_tree,
+            children_tree,
+            pending_tree,
+            conflicts_tree,
             config: Arc::new(RwLock::new(ZoteroConfig::new_user("".to_string(), "".to_string()))),
         })
     }
 
     /// Configure the Zotero manager with API credentials
+    ///
+    /// Switching to another library clears the cache of the previous one.
     pub async fn configure(&mut self, config: ZoteroConfig) -> Result<()> {
+        let api = ZoteroApi::new(&config)?;
+
+        if let Some(config_bytes) = self.db.get("zotero_config")? {
+            let previous: ZoteroConfig = bincode::deserialize(&config_bytes)?;
+            if !previous.same_library(&config) {
+                self.clear_cache().await?;
+            }
+        }
+
         // Store configuration
         {
             let mut config_guard = self.config.write().await;
@@ -178,17 +516,7 @@ impl ZoteroManager {
         let config_bytes = bincode::serialize(&config)?;
         self.db.insert("zotero_config", config_bytes)?;
         
-        // Create Zotero client
-        self.client = Some(match config.library_type {
-            LibraryType::User => {
-                let user_id = config.user_id.as_ref().ok_or_else(|| ZoteroError::InvalidItemData("User ID required for user library".to_string()))?;
-                ZoteroInit::set_user(user_id, &config.api_key)
-            }
-            LibraryType::Group => {
-                let group_id = config.group_id.as_ref().ok_or_else(|| ZoteroError::InvalidItemData("Group ID required for group library".to_string()))?;
-                ZoteroInit::set_group(group_id, Some(&config.api_key))
-            }
-        });
+        self.api = Some(api);
         
         info!("Zotero manager configured with {} library", 
               match config.library_type {
@@ -210,60 +538,35 @@ impl ZoteroManager {
         }
     }
 
-    /// Get all items from cache or API
+    /// Get all items from the local cache, see [`ZoteroManager::sync`] to update it
     pub async fn get_items(&self) -> Result<Vec<ZoteroItem>> {
-        let config = self.config.read().await;
-        
-        // Try to get from cache first
-        if let Ok(cached_items) = self.get_cached_items().await {
-            if !cached_items.is_empty() {
-                info!("Returning {} items from cache", cached_items.len());
-                return Ok(cached_items);
-            }
+        if self.api.is_none() {
+            return Err(ZoteroError::NotConfigured);
         }
         
-        // Fetch from API if client is configured
-        if let Some(ref _client) = self.client {
-            // This would use the actual zotero crate API
-            // For now, return empty vec as placeholder
-            warn!("API calls not yet implemented, returning empty result");
-            Ok(vec![])
-        } else {
-            Err(ZoteroError::NotConfigured)
-        }
+        let items = self.get_cached_items().await?;
+        info!("Returning {} items from cache", items.len());
+        Ok(items)
     }
 
     /// Get items from a specific collection
     pub async fn get_collection_items(&self, collection_key: &str) -> Result<Vec<ZoteroItem>> {
         let all_items = self.get_items().await?;
         let filtered_items = all_items.into_iter()
-            .filter(|item| {
-                // This would need to check if item belongs to collection
-                // For now, return all items
-                true
-            })
+            .filter(|item| item.collections.iter().any(|key| key == collection_key))
             .collect();
         Ok(filtered_items)
     }
 
-    /// Get all collections
+    /// Get all collections from the local cache
     pub async fn get_collections(&self) -> Result<Vec<ZoteroCollection>> {
-        // Try to get from cache first
-        if let Ok(cached_collections) = self.get_cached_collections().await {
-            if !cached_collections.is_empty() {
-                info!("Returning {} collections from cache", cached_collections.len());
-                return Ok(cached_collections);
-            }
+        if self.api.is_none() {
+            return Err(ZoteroError::NotConfigured);
         }
         
-        // Fetch from API if client is configured
-        if let Some(ref _client) = self.client {
-            // This would use the actual zotero crate API
-            warn!("API calls not yet implemented, returning empty result");
-            Ok(vec![])
-        } else {
-            Err(ZoteroError::NotConfigured)
-        }
+        let collections = self.get_cached_collections().await?;
+        info!("Returning {} collections from cache", collections.len());
+        Ok(collections)
     }
 
     /// Search items by query
@@ -293,134 +596,525 @@ impl ZoteroManager {
         Ok(filtered_items)
     }
 
-    /// Create a new item
+    /// Create a new item, written to Zotero on the next sync
     pub async fn create_item(&self, mut item: ZoteroItem) -> Result<ZoteroItem> {
         if item.key.is_empty() {
-            item.key = Uuid::new_v4().to_string();
+            item.key = generate_key();
         }
         
+        item.version = None;
         item.date_added = Some(Utc::now().to_rfc3339());
         item.date_modified = Some(Utc::now().to_rfc3339());
         
         // Store in local cache
         self.cache_item(&item).await?;
-        
-        // If API client is available, sync with Zotero
-        if let Some(ref _client) = self.client {
-            // This would create the item via the Zotero API
-            info!("Item {} created locally, API sync pending", item.key);
-        }
+        self.set_pending(ITEMS, &item.key, &PendingChange::Upsert)?;
         
         info!("Created new item: {}", item.title.as_ref().unwrap_or(&item.key));
         Ok(item)
     }
 
-    /// Update an existing item
+    /// Update an existing item, written to Zotero on the next sync
+    ///
+    /// Notes and attachments are read-only, they are synced from Zotero.
     pub async fn update_item(&self, mut item: ZoteroItem) -> Result<ZoteroItem> {
-        // Check if item exists
-        if !self.item_exists(&item.key).await? {
-            return Err(ZoteroError::ItemNotFound(item.key));
-        }
+        // The synced version is the base of the update, whatever the caller sent
+        let cached = self
+            .get_cached_item(&item.key)?
+            .ok_or_else(|| ZoteroError::ItemNotFound(item.key.clone()))?;
         
+        item.version = cached.version;
+        item.date_added = cached.date_added;
         item.date_modified = Some(Utc::now().to_rfc3339());
         
         // Update in local cache
         self.cache_item(&item).await?;
-        
-        // If API client is available, sync with Zotero
-        if let Some(ref _client) = self.client {
-            // This would update the item via the Zotero API
-            info!("Item {} updated locally, API sync pending", item.key);
-        }
+        self.set_pending(ITEMS, &item.key, &PendingChange::Upsert)?;
         
         info!("Updated item: {}", item.title.as_ref().unwrap_or(&item.key));
         Ok(item)
     }
 
-    /// Delete an item
+    /// Delete an item, deleted from Zotero on the next sync
     pub async fn delete_item(&self, item_key: &str) -> Result<()> {
-        // Check if item exists
-        if !self.item_exists(item_key).await? {
-            return Err(ZoteroError::ItemNotFound(item_key.to_string()));
-        }
+        let item = self
+            .get_cached_item(item_key)?
+            .ok_or_else(|| ZoteroError::ItemNotFound(item_key.to_string()))?;
         
         // Remove from local cache
         self.items_tree.remove(item_key.as_bytes())?;
         
-        // If API client is available, delete from Zotero
-        if let Some(ref _client) = self.client {
-            // This would delete the item via the Zotero API
-            info!("Item {} deleted locally, API sync pending", item_key);
+        // Items never synced only existed locally
+        match item.version {
+            Some(version) => self.set_pending(
+                ITEMS,
+                item_key,
+                &PendingChange::Delete { version: version as u64 },
+            )?,
+            None => self.remove_pending(ITEMS, item_key)?,
         }
+        self.db.flush_async().await?;
         
         info!("Deleted item: {}", item_key);
         Ok(())
     }
 
-    /// Create a new collection
+    /// Create a new collection, written to Zotero on the next sync
     pub async fn create_collection(&self, mut collection: ZoteroCollection) -> Result<ZoteroCollection> {
         if collection.key.is_empty() {
-            collection.key = Uuid::new_v4().to_string();
+            collection.key = generate_key();
         }
         
+        collection.version = None;
         collection.date_added = Some(Utc::now().to_rfc3339());
         collection.date_modified = Some(Utc::now().to_rfc3339());
         
         // Store in local cache
         self.cache_collection(&collection).await?;
-        
-        // If API client is available, sync with Zotero
-        if let Some(ref _client) = self.client {
-            // This would create the collection via the Zotero API
-            info!("Collection {} created locally, API sync pending", collection.name);
-        }
+        self.set_pending(COLLECTIONS, &collection.key, &PendingChange::Upsert)?;
         
         info!("Created new collection: {}", collection.name);
         Ok(collection)
     }
 
-    /// Update a collection
+    /// Update a collection, written to Zotero on the next sync
     pub async fn update_collection(&self, mut collection: ZoteroCollection) -> Result<ZoteroCollection> {
-        // Check if collection exists
-        if !self.collection_exists(&collection.key).await? {
-            return Err(ZoteroError::CollectionNotFound(collection.key));
-        }
+        let cached = self
+            .get_cached_collection(&collection.key)?
+            .ok_or_else(|| ZoteroError::CollectionNotFound(collection.key.clone()))?;
         
+        collection.version = cached.version;
+        collection.date_added = cached.date_added;
         collection.date_modified = Some(Utc::now().to_rfc3339());
         
         // Update in local cache
         self.cache_collection(&collection).await?;
-        
-        // If API client is available, sync with Zotero
-        if let Some(ref _client) = self.client {
-            // This would update the collection via the Zotero API
-            info!("Collection {} updated locally, API sync pending", collection.name);
-        }
+        self.set_pending(COLLECTIONS, &collection.key, &PendingChange::Upsert)?;
         
         info!("Updated collection: {}", collection.name);
         Ok(collection)
     }
 
-    /// Delete a collection
+    /// Delete a collection, deleted from Zotero on the next sync
     pub async fn delete_collection(&self, collection_key: &str) -> Result<()> {
-        // Check if collection exists
-        if !self.collection_exists(collection_key).await? {
-            return Err(ZoteroError::CollectionNotFound(collection_key.to_string()));
-        }
+        let collection = self
+            .get_cached_collection(collection_key)?
+            .ok_or_else(|| ZoteroError::CollectionNotFound(collection_key.to_string()))?;
         
         // Remove from local cache
         self.collections_tree.remove(collection_key.as_bytes())?;
         
-        // If API client is available, delete from Zotero
-        if let Some(ref _client) = self.client {
-            // This would delete the collection via the Zotero API
-            info!("Collection {} deleted locally, API sync pending", collection_key);
+        match collection.version {
+            Some(version) => self.set_pending(
+                COLLECTIONS,
+                collection_key,
+                &PendingChange::Delete { version: version as u64 },
+            )?,
+            None => self.remove_pending(COLLECTIONS, collection_key)?,
         }
-        
+        self.db.flush_async().await?;
+
         info!("Deleted collection: {}", collection_key);
         Ok(())
     }
 
+    /// Sync the local cache with Zotero
+    ///
+    /// Pulls the changes made since the last sync, then writes the local changes back.
+    /// Items changed on both sides take the remote state and are kept as
+    /// [`ZoteroConflict`] until resolved with [`ZoteroManager::resolve_conflict`].
+    pub async fn sync(&self) -> Result<SyncReport> {
+        let api = self.api.as_ref().ok_or(ZoteroError::NotConfigured)?;
+        let mut report = SyncReport::default();
+
+        for _ in 0..MAX_SYNC_ATTEMPTS {
+            self.pull(api, &mut report).await?;
+
+            // The library changed while writing, pull again and retry
+            match self.push(api, &mut report).await {
+                Err(ZoteroError::PreconditionFailed(message)) => {
+                    warn!("Zotero library changed during sync: {}", message);
+                    continue;
+                }
+                result => result?,
+            }
+
+            self.db.flush_async().await?;
+            report.library_version = self.library_version()?;
+            info!(
+                "Synced Zotero library at version {}: {} items and {} collections pulled, {} deleted, {} pushed",
+                report.library_version,
+                report.pulled_items,
+                report.pulled_collections,
+                report.deleted,
+                report.pushed
+            );
+            return Ok(report);
+        }
+        
+        Err(ZoteroError::PreconditionFailed(
+            "Library kept changing while syncing".to_string(),
+        ))
+    }
+
+    /// Get the items changed both locally and remotely
+    pub async fn get_conflicts(&self) -> Result<Vec<ZoteroConflict>> {
+        let mut conflicts = Vec::new();
+        for conflict_result in self.conflicts_tree.iter() {
+            let (_, conflict_bytes) = conflict_result?;
+            conflicts.push(bincode::deserialize(&conflict_bytes)?);
+        }
+        Ok(conflicts)
+    }
+
+    /// Resolve a conflict, keeping either the local or the remote state of the item
+    ///
+    /// Keeping the local state writes it to Zotero on the next sync.
+    pub async fn resolve_conflict(&self, item_key: &str, keep_local: bool) -> Result<()> {
+        let conflict_bytes = self
+            .conflicts_tree
+            .remove(item_key.as_bytes())?
+            .ok_or_else(|| ZoteroError::ItemNotFound(item_key.to_string()))?;
+        let conflict: ZoteroConflict = bincode::deserialize(&conflict_bytes)?;
+
+        if keep_local {
+            let remote_version = conflict.remote.as_ref().and_then(|remote| remote.version);
+            match (conflict.local, remote_version) {
+                (Some(mut local), _) => {
+                    // Based on the remote version, so it overwrites it
+                    local.version = remote_version;
+                    self.cache_item(&local).await?;
+                    self.set_pending(ITEMS, item_key, &PendingChange::Upsert)?;
+                }
+                (None, Some(version)) => {
+                    self.items_tree.remove(item_key.as_bytes())?;
+                    self.set_pending(
+                        ITEMS,
+                        item_key,
+                        &PendingChange::Delete { version: version as u64 },
+                    )?;
+                }
+                (None, None) => {}
+            }
+        }
+
+        self.db.flush_async().await?;
+        info!("Resolved conflict of item {}", item_key);
+        Ok(())
+    }
+
+    /// Download the file of an attachment into a directory
+    pub async fn download_attachment(&self, item_key: &str, dir: &Path) -> Result<PathBuf> {
+        let api = self.api.as_ref().ok_or(ZoteroError::NotConfigured)?;
+
+        let file_name = match self.children_tree.get(item_key.as_bytes())? {
+            Some(child_bytes) => {
+                let child: ChildItem = bincode::deserialize(&child_bytes)?;
+                child.filename.or(child.title)
+            }
+            // Standalone attachments are top-level items
+            None => self.get_cached_item(item_key)?.and_then(|item| item.title),
+        };
+        // Only keep the file name, so it can't point outside of the directory
+        let file_name = file_name
+            .as_deref()
+            .and_then(|name| Path::new(name).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| item_key.to_string());
+
+        let content = api.download_file(item_key).await?;
+
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(file_name);
+        std::fs::write(&path, content)?;
+
+        info!("Downloaded attachment {} to {:?}", item_key, path);
+        Ok(path)
+    }
+
+    // Sync helpers
+
+    async fn pull(&self, api: &ZoteroApi, report: &mut SyncReport) -> Result<()> {
+        let since = self.library_version()?;
+        let mut version = since;
+
+        if let Some(collections) = api.get_changed("/collections", since).await? {
+            for object in &collections.data {
+                self.apply_remote_collection(object, report).await?;
+            }
+            version = version.max(collections.library_version);
+        }
+
+        if let Some(items) = api.get_changed("/items", since).await? {
+            for object in items.data {
+                self.apply_remote_item(object, report).await?;
+            }
+            version = version.max(items.library_version);
+        }
+
+        // Nothing cached before the first sync could have been deleted
+        if since > 0 {
+            let deleted = api.get_deleted(since).await?;
+            for key in &deleted.data.collections {
+                self.apply_remote_collection_deletion(key, report)?;
+            }
+            for key in &deleted.data.items {
+                self.apply_remote_item_deletion(key, report)?;
+            }
+            version = version.max(deleted.library_version);
+        }
+
+        self.set_library_version(version)
+    }
+
+    async fn apply_remote_item(&self, object: ApiObject, report: &mut SyncReport) -> Result<()> {
+        let data: ItemData = serde_json::from_value(object.data)?;
+        report.pulled_items += 1;
+
+        if let Some(parent_item) = data.parent_item.clone() {
+            let child = data.into_child(parent_item);
+            self.items_tree.remove(child.key.as_bytes())?;
+            self.children_tree
+                .insert(child.key.as_bytes(), bincode::serialize(&child)?)?;
+            return Ok(());
+        }
+
+        let remote = data.into_item(object.version);
+        self.children_tree.remove(remote.key.as_bytes())?;
+
+        match self.get_pending(ITEMS, &remote.key)? {
+            Some(PendingChange::Upsert) => {
+                let local = self.get_cached_item(&remote.key)?;
+                self.store_conflict(ZoteroConflict {
+                    key: remote.key.clone(),
+                    local,
+                    remote: Some(remote.clone()),
+                }, report)?;
+            }
+            Some(PendingChange::Delete { .. }) => {
+                self.store_conflict(ZoteroConflict {
+                    key: remote.key.clone(),
+                    local: None,
+                    remote: Some(remote.clone()),
+                }, report)?;
+            }
+            None => {}
+        }
+
+        self.cache_item(&remote).await
+    }
+
+    fn apply_remote_item_deletion(&self, key: &str, report: &mut SyncReport) -> Result<()> {
+        if self.children_tree.remove(key.as_bytes())?.is_some() {
+            report.deleted += 1;
+            return Ok(());
+        }
+
+        if let Some(PendingChange::Upsert) = self.get_pending(ITEMS, key)? {
+            let local = self.get_cached_item(key)?;
+            self.store_conflict(ZoteroConflict {
+                key: key.to_string(),
+                local,
+                remote: None,
+            }, report)?;
+        }
+        self.remove_pending(ITEMS, key)?;
+
+        if self.items_tree.remove(key.as_bytes())?.is_some() {
+            report.deleted += 1;
+        }
+        Ok(())
+    }
+
+    async fn apply_remote_collection(&self, object: &ApiObject, report: &mut SyncReport) -> Result<()> {
+        let remote = ZoteroCollection::from_api(object)?;
+        report.pulled_collections += 1;
+
+        // Collections only have a name and a parent, the remote state simply wins
+        if self.get_pending(COLLECTIONS, &remote.key)?.is_some() {
+            self.remove_pending(COLLECTIONS, &remote.key)?;
+            report.conflicts.push(remote.key.clone());
+        }
+
+        self.cache_collection(&remote).await
+    }
+
+    fn apply_remote_collection_deletion(&self, key: &str, report: &mut SyncReport) -> Result<()> {
+        self.remove_pending(COLLECTIONS, key)?;
+        if self.collections_tree.remove(key.as_bytes())?.is_some() {
+            report.deleted += 1;
+        }
+        Ok(())
+    }
+
+    fn store_conflict(&self, conflict: ZoteroConflict, report: &mut SyncReport) -> Result<()> {
+        warn!("Zotero item {} changed both locally and remotely", conflict.key);
+        self.remove_pending(ITEMS, &conflict.key)?;
+        self.conflicts_tree
+            .insert(conflict.key.as_bytes(), bincode::serialize(&conflict)?)?;
+        report.conflicts.push(conflict.key);
+        Ok(())
+    }
+
+    async fn push(&self, api: &ZoteroApi, report: &mut SyncReport) -> Result<()> {
+        let mut upserts: HashMap<&str, Vec<String>> = HashMap::new();
+        let mut deletes: HashMap<&str, Vec<String>> = HashMap::new();
+        for pending_result in self.pending_tree.iter() {
+            let (key, change_bytes) = pending_result?;
+            let key = String::from_utf8_lossy(&key).to_string();
+            let Some((kind, key)) = key.split_once('/') else {
+                continue;
+            };
+            let kind = if kind == ITEMS { ITEMS } else { COLLECTIONS };
+            match bincode::deserialize(&change_bytes)? {
+                PendingChange::Upsert => upserts.entry(kind).or_default().push(key.to_string()),
+                PendingChange::Delete { .. } => deletes.entry(kind).or_default().push(key.to_string()),
+            }
+        }
+
+        // Collections first, so new items can be added to new collections
+        for kind in [COLLECTIONS, ITEMS] {
+            if let Some(keys) = upserts.get(kind) {
+                self.push_upserts(api, kind, keys, report).await?;
+            }
+        }
+        for (kind, key_param) in [(ITEMS, "itemKey"), (COLLECTIONS, "collectionKey")] {
+            if let Some(keys) = deletes.get(kind) {
+                for chunk in keys.chunks(WRITE_LIMIT) {
+                    let version = api
+                        .delete(&format!("/{}", kind), key_param, chunk, self.library_version()?)
+                        .await?;
+                    for key in chunk {
+                        self.remove_pending(kind, key)?;
+                    }
+                    report.pushed += chunk.len();
+                    self.set_library_version(version)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn push_upserts(
+        &self,
+        api: &ZoteroApi,
+        kind: &str,
+        keys: &[String],
+        report: &mut SyncReport,
+    ) -> Result<()> {
+        let mut modified_remotely = false;
+
+        for chunk in keys.chunks(WRITE_LIMIT) {
+            let mut objects = Vec::new();
+            let mut chunk_keys = Vec::new();
+            for key in chunk {
+                let object = if kind == ITEMS {
+                    self.get_cached_item(key)?
+                        .map(|item| serde_json::to_value(ItemData::from_item(&item)))
+                        .transpose()?
+                } else {
+                    self.get_cached_collection(key)?
+                        .map(|collection| collection.to_api())
+                };
+                match object {
+                    Some(object) => {
+                        objects.push(object);
+                        chunk_keys.push(key.clone());
+                    }
+                    None => self.remove_pending(kind, key)?,
+                }
+            }
+            if objects.is_empty() {
+                continue;
+            }
+
+            let result = api
+                .write(&format!("/{}", kind), &objects, self.library_version()?)
+                .await?;
+
+            for (index, key) in chunk_keys.iter().enumerate() {
+                let index = index.to_string();
+                if let Some(object) = result.data.successful.get(&index) {
+                    self.apply_written(kind, object).await?;
+                    self.remove_pending(kind, key)?;
+                    report.pushed += 1;
+                } else if result.data.unchanged.contains_key(&index) {
+                    self.remove_pending(kind, key)?;
+                } else if let Some(failure) = result.data.failed.get(&index) {
+                    if failure.code == 412 {
+                        modified_remotely = true;
+                    } else {
+                        warn!("Zotero refused to save {}: {}", key, failure.message);
+                        report.failed.push(format!("{}: {}", key, failure.message));
+                    }
+                }
+            }
+            self.set_library_version(result.library_version)?;
+        }
+
+        // Pulling again turns these into conflicts
+        if modified_remotely {
+            return Err(ZoteroError::PreconditionFailed(format!(
+                "{} modified remotely",
+                kind
+            )));
+        }
+        Ok(())
+    }
+
+    /// Store the version and dates Zotero assigned to a written object
+    async fn apply_written(&self, kind: &str, object: &ApiObject) -> Result<()> {
+        if kind == ITEMS {
+            if let Some(mut item) = self.get_cached_item(&object.key)? {
+                item.version = Some(object.version as i32);
+                if let Some(date_added) = object.data["dateAdded"].as_str() {
+                    item.date_added = Some(date_added.to_string());
+                }
+                if let Some(date_modified) = object.data["dateModified"].as_str() {
+                    item.date_modified = Some(date_modified.to_string());
+                }
+                self.cache_item(&item).await?;
+            }
+        } else if let Some(mut collection) = self.get_cached_collection(&object.key)? {
+            collection.version = Some(object.version as i32);
+            self.cache_collection(&collection).await?;
+        }
+        Ok(())
+    }
+
+    fn library_version(&self) -> Result<u64> {
+        Ok(self
+            .db
+            .get(LIBRARY_VERSION_KEY)?
+            .and_then(|bytes| bytes[..].try_into().ok())
+            .map(u64::from_be_bytes)
+            .unwrap_or(0))
+    }
+
+    fn set_library_version(&self, version: u64) -> Result<()> {
+        self.db.insert(LIBRARY_VERSION_KEY, version.to_be_bytes().to_vec())?;
+        Ok(())
+    }
+
+    fn get_pending(&self, kind: &str, key: &str) -> Result<Option<PendingChange>> {
+        match self.pending_tree.get(pending_key(kind, key).as_bytes())? {
+            Some(change_bytes) => Ok(Some(bincode::deserialize(&change_bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn set_pending(&self, kind: &str, key: &str, change: &PendingChange) -> Result<()> {
+        self.pending_tree
+            .insert(pending_key(kind, key).as_bytes(), bincode::serialize(change)?)?;
+        Ok(())
+    }
+
+    fn remove_pending(&self, kind: &str, key: &str) -> Result<()> {
+        self.pending_tree.remove(pending_key(kind, key).as_bytes())?;
+        Ok(())
+    }
+
     // Helper methods for caching
     
     async fn cache_item(&self, item: &ZoteroItem) -> Result<()> {
@@ -437,12 +1131,50 @@ impl ZoteroManager {
         Ok(())
     }
 
+    fn get_cached_item(&self, item_key: &str) -> Result<Option<ZoteroItem>> {
+        match self.items_tree.get(item_key.as_bytes())? {
+            Some(item_bytes) => Ok(Some(bincode::deserialize(&item_bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn get_cached_collection(&self, collection_key: &str) -> Result<Option<ZoteroCollection>> {
+        match self.collections_tree.get(collection_key.as_bytes())? {
+            Some(collection_bytes) => Ok(Some(bincode::deserialize(&collection_bytes)?)),
+            None => Ok(None),
+        }
+    }
+
     async fn get_cached_items(&self) -> Result<Vec<ZoteroItem>> {
+        let mut children: HashMap<String, Vec<ChildItem>> = HashMap::new();
+        for child_result in self.children_tree.iter() {
+            let (_, child_bytes) = child_result?;
+            let child: ChildItem = bincode::deserialize(&child_bytes)?;
+            children.entry(child.parent_item.clone()).or_default().push(child);
+        }
+
         let mut items = Vec::new();
         
         for item_result in self.items_tree.iter() {
             let (_, item_bytes) = item_result?;
-            let item: ZoteroItem = bincode::deserialize(&item_bytes)?;
+            let mut item: ZoteroItem = bincode::deserialize(&item_bytes)?;
+
+            // Notes and attachments are separate items in Zotero
+            item.notes.clear();
+            item.attachments.clear();
+            for child in children.remove(&item.key).unwrap_or_default() {
+                match child.item_type.as_str() {
+                    "note" => item.notes.extend(child.note),
+                    "attachment" => item.attachments.push(Attachment {
+                        title: child.title.or(child.filename).unwrap_or_default(),
+                        mime_type: child.content_type.unwrap_or_default(),
+                        url: child.url,
+                        key: child.key,
+                    }),
+                    _ => {}
+                }
+            }
+
             items.push(item);
         }
         
@@ -461,14 +1193,6 @@ impl ZoteroManager {
         Ok(collections)
     }
 
-    async fn item_exists(&self, item_key: &str) -> Result<bool> {
-        Ok(self.items_tree.contains_key(item_key.as_bytes())?)
-    }
-
-    async fn collection_exists(&self, collection_key: &str) -> Result<bool> {
-        Ok(self.collections_tree.contains_key(collection_key.as_bytes())?)
-    }
-
     /// Get current configuration
     pub async fn get_config(&self) -> ZoteroConfig {
         self.config.read().await.clone()
@@ -481,10 +1205,14 @@ impl ZoteroManager {
         (config.user_id.is_some() || config.group_id.is_some())
     }
 
-    /// Clear all cached data
+    /// Clear all cached data, including local changes not synced yet
     pub async fn clear_cache(&self) -> Result<()> {
         self.items_tree.clear()?;
         self.collections_tree.clear()?;
+        self.children_tree.clear()?;
+        self.pending_tree.clear()?;
+        self.conflicts_tree.clear()?;
+        self.db.remove(LIBRARY_VERSION_KEY)?;
         self.db.flush_async().await?;
         info!("Cleared all cached data");
         Ok(())
@@ -501,6 +1229,8 @@ impl ZoteroManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tauri::zotero_mock::MockZotero;
+    use serde_json::json;
     use tempfile::tempdir;
 
     #[tokio::test]
@@ -533,7 +1263,7 @@ mod tests {
         let temp_dir = tempdir().unwrap();
         let db_path = temp_dir.path().join("test_zotero");
         
-        let manager = ZoteroManager::new(db_path).await.unwrap();
+        let mut manager = ZoteroManager::new(db_path).await.unwrap();
         let config = ZoteroConfig::new_user("12345".to_string(), "api_key".to_string());
         manager.configure(config).await.unwrap();
         
@@ -560,6 +1290,7 @@ mod tests {
             language: None,
             notes: vec![],
             attachments: vec![],
+            collections: vec![],
         };
         
         let created_item = manager.create_item(item.clone()).await.unwrap();
@@ -587,7 +1318,7 @@ mod tests {
         let temp_dir = tempdir().unwrap();
         let db_path = temp_dir.path().join("test_zotero");
         
-        let manager = ZoteroManager::new(db_path).await.unwrap();
+        let mut manager = ZoteroManager::new(db_path).await.unwrap();
         let config = ZoteroConfig::new_user("12345".to_string(), "api_key".to_string());
         manager.configure(config).await.unwrap();
         
@@ -626,7 +1357,7 @@ mod tests {
         let temp_dir = tempdir().unwrap();
         let db_path = temp_dir.path().join("test_zotero");
         
-        let manager = ZoteroManager::new(db_path).await.unwrap();
+        let mut manager = ZoteroManager::new(db_path).await.unwrap();
         let config = ZoteroConfig::new_user("12345".to_string(), "api_key".to_string());
         manager.configure(config).await.unwrap();
         
@@ -657,6 +1388,7 @@ mod tests {
             language: None,
             notes: vec![],
             attachments: vec![],
+            collections: vec![],
         };
         
         let item2 = ZoteroItem {
@@ -681,6 +1413,7 @@ mod tests {
             language: None,
             notes: vec![],
             attachments: vec![],
+            collections: vec![],
         };
         
         manager.create_item(item1).await.unwrap();
@@ -700,4 +1433,237 @@ mod tests {
         assert_eq!(tag_results.len(), 1);
         assert_eq!(tag_results[0].title, Some("Python Programming".to_string()));
     }
+
+    fn test_item(title: &str) -> ZoteroItem {
+        ZoteroItem {
+            key: "".to_string(),
+            version: None,
+            item_type: "book".to_string(),
+            title: Some(title.to_string()),
+            creators: vec![Creator {
+                creator_type: "author".to_string(),
+                first_name: Some("Ada".to_string()),
+                last_name: Some("Lovelace".to_string()),
+            }],
+            tags: vec![],
+            date_added: None,
+            date_modified: None,
+            abstract_note: None,
+            url: None,
+            doi: None,
+            isbn: None,
+            publication_title: None,
+            volume: None,
+            issue: None,
+            pages: None,
+            publisher: None,
+            date: None,
+            language: None,
+            notes: vec![],
+            attachments: vec![],
+            collections: vec![],
+        }
+    }
+
+    async fn synced_manager(mock: &MockZotero, db_path: PathBuf) -> ZoteroManager {
+        let mut manager = ZoteroManager::new(db_path).await.unwrap();
+        let config = ZoteroConfig::new_user("12345".to_string(), "api_key".to_string())
+            .with_api_url(mock.url());
+        manager.configure(config).await.unwrap();
+        manager
+    }
+
+    #[tokio::test]
+    async fn test_sync_pulls_library() {
+        let temp_dir = tempdir().unwrap();
+        let mock = MockZotero::start().await;
+        mock.put_collection("COLL1111", json!({ "name": "Papers", "parentCollection": false }));
+        mock.put_item("ITEM1111", json!({
+            "itemType": "journalArticle",
+            "title": "On Computable Numbers",
+            "creators": [
+                { "creatorType": "author", "firstName": "Alan", "lastName": "Turing" },
+                { "creatorType": "editor", "name": "London Mathematical Society" }
+            ],
+            "tags": [{ "tag": "computability" }],
+            "collections": ["COLL1111"],
+            "DOI": "10.1112/plms/s2-42.1.230"
+        }));
+        mock.put_item("NOTE1111", json!({
+            "itemType": "note",
+            "parentItem": "ITEM1111",
+            "note": "<p>Classic</p>"
+        }));
+        mock.put_item("ATTA1111", json!({
+            "itemType": "attachment",
+            "parentItem": "ITEM1111",
+            "title": "Full Text PDF",
+            "contentType": "application/pdf",
+            "filename": "turing.pdf"
+        }));
+
+        let manager = synced_manager(&mock, temp_dir.path().join("test_zotero")).await;
+        let report = manager.sync().await.unwrap();
+        assert_eq!(report.library_version, mock.version());
+        assert_eq!(report.pulled_collections, 1);
+        assert!(report.conflicts.is_empty());
+
+        let items = manager.get_items().await.unwrap();
+        assert_eq!(items.len(), 1);
+        let item = &items[0];
+        assert_eq!(item.title, Some("On Computable Numbers".to_string()));
+        assert_eq!(item.doi, Some("10.1112/plms/s2-42.1.230".to_string()));
+        assert_eq!(item.creators[1].last_name, Some("London Mathematical Society".to_string()));
+        assert_eq!(item.notes, vec!["<p>Classic</p>".to_string()]);
+        assert_eq!(item.attachments.len(), 1);
+        assert_eq!(item.attachments[0].mime_type, "application/pdf");
+
+        assert_eq!(manager.get_collections().await.unwrap()[0].name, "Papers");
+        assert_eq!(manager.get_collection_items("COLL1111").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_sync() {
+        let temp_dir = tempdir().unwrap();
+        let mock = MockZotero::start().await;
+        mock.put_item("ITEM1111", json!({ "itemType": "book", "title": "First" }));
+        mock.put_item("ITEM2222", json!({ "itemType": "book", "title": "Second" }));
+
+        let manager = synced_manager(&mock, temp_dir.path().join("test_zotero")).await;
+        manager.sync().await.unwrap();
+
+        // Nothing changed
+        let report = manager.sync().await.unwrap();
+        assert_eq!(report.pulled_items, 0);
+
+        mock.put_item("ITEM1111", json!({ "itemType": "book", "title": "First, revised" }));
+        mock.delete_item("ITEM2222");
+        let report = manager.sync().await.unwrap();
+        assert_eq!(report.pulled_items, 1);
+        assert_eq!(report.deleted, 1);
+
+        let items = manager.get_items().await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, Some("First, revised".to_string()));
+        assert!(mock
+            .requests()
+            .iter()
+            .any(|request| request.starts_with("GET /users/12345/deleted?since=")));
+    }
+
+    #[tokio::test]
+    async fn test_sync_pushes_local_changes() {
+        let temp_dir = tempdir().unwrap();
+        let mock = MockZotero::start().await;
+        let manager = synced_manager(&mock, temp_dir.path().join("test_zotero")).await;
+
+        let collection = manager
+            .create_collection(ZoteroCollection {
+                key: "".to_string(),
+                name: "Reading list".to_string(),
+                parent_collection: None,
+                version: None,
+                date_added: None,
+                date_modified: None,
+            })
+            .await
+            .unwrap();
+        let mut item = test_item("Notes on the Analytical Engine");
+        item.collections = vec![collection.key.clone()];
+        let item = manager.create_item(item).await.unwrap();
+
+        let report = manager.sync().await.unwrap();
+        assert_eq!(report.pushed, 2);
+        assert_eq!(mock.collection(&collection.key).unwrap()["name"], "Reading list");
+        let remote = mock.item(&item.key).unwrap();
+        assert_eq!(remote["title"], "Notes on the Analytical Engine");
+        assert_eq!(remote["collections"], json!([collection.key]));
+        assert_eq!(remote["creators"][0]["lastName"], "Lovelace");
+
+        let mut updated = manager.get_items().await.unwrap().remove(0);
+        assert!(updated.version.is_some());
+        updated.title = Some("Sketch of the Analytical Engine".to_string());
+        manager.update_item(updated).await.unwrap();
+        manager.sync().await.unwrap();
+        assert_eq!(mock.item(&item.key).unwrap()["title"], "Sketch of the Analytical Engine");
+
+        manager.delete_item(&item.key).await.unwrap();
+        manager.delete_collection(&collection.key).await.unwrap();
+        manager.sync().await.unwrap();
+        assert!(mock.item(&item.key).is_none());
+        assert!(mock.collection(&collection.key).is_none());
+        assert_eq!(manager.sync().await.unwrap().library_version, mock.version());
+    }
+
+    #[tokio::test]
+    async fn test_sync_conflict() {
+        let temp_dir = tempdir().unwrap();
+        let mock = MockZotero::start().await;
+        mock.put_item("ITEM1111", json!({ "itemType": "book", "title": "Original" }));
+
+        let manager = synced_manager(&mock, temp_dir.path().join("test_zotero")).await;
+        manager.sync().await.unwrap();
+
+        let mut local = manager.get_items().await.unwrap().remove(0);
+        local.title = Some("Local".to_string());
+        manager.update_item(local).await.unwrap();
+        mock.put_item("ITEM1111", json!({ "itemType": "book", "title": "Remote" }));
+
+        // The remote state wins until the conflict is resolved
+        let report = manager.sync().await.unwrap();
+        assert_eq!(report.conflicts, vec!["ITEM1111".to_string()]);
+        assert_eq!(manager.get_items().await.unwrap()[0].title, Some("Remote".to_string()));
+
+        let conflicts = manager.get_conflicts().await.unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].local.as_ref().unwrap().title, Some("Local".to_string()));
+        assert_eq!(conflicts[0].remote.as_ref().unwrap().title, Some("Remote".to_string()));
+
+        manager.resolve_conflict("ITEM1111", true).await.unwrap();
+        manager.sync().await.unwrap();
+        assert_eq!(mock.item("ITEM1111").unwrap()["title"], "Local");
+        assert!(manager.get_conflicts().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sync_group_library() {
+        let temp_dir = tempdir().unwrap();
+        let mock = MockZotero::start().await;
+        mock.put_item("ITEM1111", json!({ "itemType": "book", "title": "Shared" }));
+
+        let mut manager = ZoteroManager::new(temp_dir.path().join("test_zotero")).await.unwrap();
+        let config = ZoteroConfig::new_group("42".to_string(), "api_key".to_string())
+            .with_api_url(mock.url());
+        manager.configure(config).await.unwrap();
+        manager.sync().await.unwrap();
+
+        assert_eq!(manager.get_items().await.unwrap().len(), 1);
+        assert!(mock
+            .requests()
+            .iter()
+            .all(|request| request.split(' ').nth(1).unwrap().starts_with("/groups/42/")));
+    }
+
+    #[tokio::test]
+    async fn test_download_attachment() {
+        let temp_dir = tempdir().unwrap();
+        let mock = MockZotero::start().await;
+        mock.put_item("ITEM1111", json!({ "itemType": "book", "title": "Paper" }));
+        mock.put_item("ATTA1111", json!({
+            "itemType": "attachment",
+            "parentItem": "ITEM1111",
+            "title": "Full Text PDF",
+            "contentType": "application/pdf",
+            "filename": "paper.pdf"
+        }));
+        mock.set_file("ATTA1111", b"%PDF-1.7");
+
+        let manager = synced_manager(&mock, temp_dir.path().join("test_zotero")).await;
+        manager.sync().await.unwrap();
+
+        let dir = temp_dir.path().join("attachments");
+        let path = manager.download_attachment("ATTA1111", &dir).await.unwrap();
+        assert_eq!(path, dir.join("paper.pdf"));
+        assert_eq!(std::fs::read(path).unwrap(), b"%PDF-1.7");
+    }
 }
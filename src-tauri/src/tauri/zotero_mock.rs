@@ -0,0 +1,431 @@
+//! # Mock Zotero Web API
+//!
+//! Serves the endpoints used by the Zotero sync on a local port,
+//! so the sync can be tested without an account on zotero.org.
+//!
+//! The library lives in memory and is versioned like the real one:
+//! every write increments the library version, reads honor
+//! `If-Modified-Since-Version` and writes honor
+//! `If-Unmodified-Since-Version`. Remote changes are simulated with
+//! `put_item`, `put_collection` and `delete_item`, and `requests`
+//! returns the requests the client made.
+
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+/// Mock Zotero Web API server
+pub struct MockZotero {
+    url: String,
+    state: Arc<Mutex<MockState>>,
+    server: JoinHandle<()>,
+}
+
+#[derive(Default)]
+struct MockState {
+    version: u64,
+    /// data of the objects, by key
+    items: BTreeMap<String, Value>,
+    collections: BTreeMap<String, Value>,
+    /// library version the objects were deleted at, by key
+    deleted_items: HashMap<String, u64>,
+    deleted_collections: HashMap<String, u64>,
+    /// attachment files, by item key
+    files: HashMap<String, Vec<u8>>,
+    /// method, path and query of the received requests
+    requests: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Items,
+    Collections,
+}
+
+impl MockState {
+    fn objects(&mut self, kind: Kind) -> &mut BTreeMap<String, Value> {
+        match kind {
+            Kind::Items => &mut self.items,
+            Kind::Collections => &mut self.collections,
+        }
+    }
+
+    fn deleted(&mut self, kind: Kind) -> &mut HashMap<String, u64> {
+        match kind {
+            Kind::Items => &mut self.deleted_items,
+            Kind::Collections => &mut self.deleted_collections,
+        }
+    }
+
+    /// Store an object at the next library version
+    fn put(&mut self, kind: Kind, key: &str, mut data: Value) {
+        self.version += 1;
+        data["key"] = json!(key);
+        data["version"] = json!(self.version);
+        self.deleted(kind).remove(key);
+        self.objects(kind).insert(key.to_string(), data);
+    }
+
+    fn remove(&mut self, kind: Kind, keys: &[&str]) {
+        self.version += 1;
+        let version = self.version;
+        for key in keys {
+            if self.objects(kind).remove(*key).is_some() {
+                self.deleted(kind).insert(key.to_string(), version);
+            }
+        }
+    }
+}
+
+impl MockZotero {
+    /// Start the mock server on a free local port
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let app = Router::new()
+            .route(
+                "/{library}/{id}/items",
+                get(get_items).post(post_items).delete(delete_items),
+            )
+            .route(
+                "/{library}/{id}/collections",
+                get(get_collections)
+                    .post(post_collections)
+                    .delete(delete_collections),
+            )
+            .route("/{library}/{id}/deleted", get(get_deleted))
+            .route("/{library}/{id}/items/{key}/file", get(get_file))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                record_request,
+            ))
+            .with_state(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        Self { url, state, server }
+    }
+
+    /// Base URL to configure the client with
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// Create or replace an item, as if changed by another client
+    pub fn put_item(&self, key: &str, data: Value) {
+        self.state.lock().unwrap().put(Kind::Items, key, data);
+    }
+
+    /// Create or replace a collection, as if changed by another client
+    pub fn put_collection(&self, key: &str, data: Value) {
+        self.state.lock().unwrap().put(Kind::Collections, key, data);
+    }
+
+    /// Delete an item, as if deleted by another client
+    pub fn delete_item(&self, key: &str) {
+        self.state.lock().unwrap().remove(Kind::Items, &[key]);
+    }
+
+    pub fn item(&self, key: &str) -> Option<Value> {
+        self.state.lock().unwrap().items.get(key).cloned()
+    }
+
+    pub fn collection(&self, key: &str) -> Option<Value> {
+        self.state.lock().unwrap().collections.get(key).cloned()
+    }
+
+    /// Set the file of an attachment item
+    pub fn set_file(&self, key: &str, content: &[u8]) {
+        self.state
+            .lock()
+            .unwrap()
+            .files
+            .insert(key.to_string(), content.to_vec());
+    }
+
+    /// Requests received so far, as "METHOD /path?query"
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Current version of the library
+    pub fn version(&self) -> u64 {
+        self.state.lock().unwrap().version
+    }
+}
+
+impl Drop for MockZotero {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+type SharedState = State<Arc<Mutex<MockState>>>;
+
+async fn record_request(
+    State(state): SharedState,
+    method: Method,
+    uri: Uri,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    state
+        .lock()
+        .unwrap()
+        .requests
+        .push(format!("{} {}", method, uri));
+    next.run(request).await
+}
+
+fn header_version(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+/// Attach the Last-Modified-Version header to a response
+fn versioned(version: u64, response: impl IntoResponse) -> Response {
+    let mut response = response.into_response();
+    response.headers_mut().insert(
+        "Last-Modified-Version",
+        HeaderValue::from_str(&version.to_string()).unwrap(),
+    );
+    response
+}
+
+fn list(
+    state: &Mutex<MockState>,
+    kind: Kind,
+    headers: &HeaderMap,
+    query: &HashMap<String, String>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+    let version = state.version;
+
+    if header_version(headers, "If-Modified-Since-Version").is_some_and(|since| since >= version) {
+        return versioned(version, StatusCode::NOT_MODIFIED);
+    }
+
+    let param = |name: &str| {
+        query
+            .get(name)
+            .and_then(|value| value.parse::<usize>().ok())
+    };
+    let since = param("since").unwrap_or(0) as u64;
+    let start = param("start").unwrap_or(0);
+    let limit = param("limit").unwrap_or(25);
+
+    let changed: Vec<Value> = state
+        .objects(kind)
+        .values()
+        .filter(|data| data["version"].as_u64().unwrap_or(0) > since)
+        .map(|data| {
+            json!({
+                "key": data["key"],
+                "version": data["version"],
+                "data": data,
+            })
+        })
+        .collect();
+    let total = changed.len();
+    let page: Vec<Value> = changed.into_iter().skip(start).take(limit).collect();
+
+    let mut response = versioned(version, Json(page));
+    response.headers_mut().insert(
+        "Total-Results",
+        HeaderValue::from_str(&total.to_string()).unwrap(),
+    );
+    response
+}
+
+fn write(state: &Mutex<MockState>, kind: Kind, headers: &HeaderMap, body: &[u8]) -> Response {
+    let mut state = state.lock().unwrap();
+
+    if header_version(headers, "If-Unmodified-Since-Version")
+        .is_some_and(|version| version < state.version)
+    {
+        return versioned(state.version, StatusCode::PRECONDITION_FAILED);
+    }
+    let Ok(objects) = serde_json::from_slice::<Vec<Value>>(body) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    let new_version = state.version + 1;
+    let mut successful = Map::new();
+    let mut unchanged = Map::new();
+    let mut failed = Map::new();
+
+    for (index, object) in objects.into_iter().enumerate() {
+        let index = index.to_string();
+        let key = object["key"].as_str().unwrap_or_default().to_string();
+        let existing = state.objects(kind).get(&key).cloned();
+
+        let current_version = existing
+            .as_ref()
+            .and_then(|data| data["version"].as_u64())
+            .unwrap_or(0);
+        if object["version"]
+            .as_u64()
+            .is_some_and(|version| version < current_version)
+        {
+            failed.insert(
+                index,
+                json!({ "key": key, "code": 412, "message": "Object has been modified since the specified version" }),
+            );
+            continue;
+        }
+
+        // Fields left out of the object keep their value
+        let mut data = existing.clone().unwrap_or_else(|| json!({}));
+        for (field, value) in object.as_object().into_iter().flatten() {
+            data[field] = value.clone();
+        }
+        data["version"] = existing
+            .as_ref()
+            .map(|data| data["version"].clone())
+            .unwrap_or(Value::Null);
+        if existing.as_ref() == Some(&data) {
+            unchanged.insert(index, json!(key));
+            continue;
+        }
+
+        data["version"] = json!(new_version);
+        if kind == Kind::Items {
+            data["dateModified"] = json!("2024-01-01T00:00:00Z");
+        }
+        successful.insert(
+            index,
+            json!({ "key": key, "version": new_version, "data": data }),
+        );
+        state.objects(kind).insert(key.clone(), data);
+        state.deleted(kind).remove(&key);
+    }
+
+    if !successful.is_empty() {
+        state.version = new_version;
+    }
+
+    versioned(
+        state.version,
+        Json(json!({
+            "successful": successful,
+            "unchanged": unchanged,
+            "failed": failed,
+        })),
+    )
+}
+
+fn delete(
+    state: &Mutex<MockState>,
+    kind: Kind,
+    headers: &HeaderMap,
+    query: &HashMap<String, String>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+
+    if header_version(headers, "If-Unmodified-Since-Version")
+        .is_some_and(|version| version < state.version)
+    {
+        return versioned(state.version, StatusCode::PRECONDITION_FAILED);
+    }
+
+    let param = match kind {
+        Kind::Items => "itemKey",
+        Kind::Collections => "collectionKey",
+    };
+    let keys: Vec<&str> = query
+        .get(param)
+        .map(|keys| keys.split(',').collect())
+        .unwrap_or_default();
+    state.remove(kind, &keys);
+
+    versioned(state.version, StatusCode::NO_CONTENT)
+}
+
+async fn get_items(
+    State(state): SharedState,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    list(&state, Kind::Items, &headers, &query)
+}
+
+async fn get_collections(
+    State(state): SharedState,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    list(&state, Kind::Collections, &headers, &query)
+}
+
+async fn post_items(State(state): SharedState, headers: HeaderMap, body: Bytes) -> Response {
+    write(&state, Kind::Items, &headers, &body)
+}
+
+async fn post_collections(State(state): SharedState, headers: HeaderMap, body: Bytes) -> Response {
+    write(&state, Kind::Collections, &headers, &body)
+}
+
+async fn delete_items(
+    State(state): SharedState,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    delete(&state, Kind::Items, &headers, &query)
+}
+
+async fn delete_collections(
+    State(state): SharedState,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    delete(&state, Kind::Collections, &headers, &query)
+}
+
+async fn get_deleted(
+    State(state): SharedState,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let state = state.lock().unwrap();
+    let since = query
+        .get("since")
+        .and_then(|since| since.parse::<u64>().ok())
+        .unwrap_or(0);
+    let deleted_since = |deleted: &HashMap<String, u64>| -> Vec<String> {
+        deleted
+            .iter()
+            .filter(|(_, version)| **version > since)
+            .map(|(key, _)| key.clone())
+            .collect()
+    };
+
+    versioned(
+        state.version,
+        Json(json!({
+            "items": deleted_since(&state.deleted_items),
+            "collections": deleted_since(&state.deleted_collections),
+        })),
+    )
+}
+
+async fn get_file(
+    State(state): SharedState,
+    Path((_library, _id, key)): Path<(String, String, String)>,
+) -> Response {
+    match state.lock().unwrap().files.get(&key) {
+        Some(content) => content.clone().into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
@@ -0,0 +1,186 @@
+//! Audio helpers for recordings
+//!
+//! Recordings are stored as 16 bit PCM mono WAV files.
+
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+/// size of the WAV header written by `WavWriter`
+const HEADER_SIZE: u32 = 44;
+
+/// Writes 16 bit PCM mono WAV files incrementally
+///
+/// The sizes in the header are written by `finalize`.
+pub struct WavWriter {
+    writer: BufWriter<File>,
+    sample_rate: u32,
+    samples: u32,
+}
+
+impl WavWriter {
+    pub fn create(path: &Path, sample_rate: u32) -> Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_header(&mut writer, sample_rate, 0)?;
+
+        Ok(Self {
+            writer,
+            sample_rate,
+            samples: 0,
+        })
+    }
+
+    /// Append samples in the range -1.0 to 1.0
+    pub fn write(&mut self, samples: &[f32]) -> Result<()> {
+        for sample in samples {
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.writer.write_all(&sample.to_le_bytes())?;
+        }
+        self.samples += samples.len() as u32;
+        Ok(())
+    }
+
+    /// Duration of the written audio in seconds
+    pub fn duration(&self) -> f32 {
+        self.samples as f32 / self.sample_rate as f32
+    }
+
+    /// Write the sizes to the header and close the file
+    pub fn finalize(mut self) -> Result<()> {
+        self.writer.seek(SeekFrom::Start(0))?;
+        write_header(&mut self.writer, self.sample_rate, self.samples * 2)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn write_header(writer: &mut impl Write, sample_rate: u32, data_size: u32) -> Result<()> {
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(HEADER_SIZE - 8 + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    // PCM, mono
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * 2).to_le_bytes())?;
+    writer.write_all(&2u16.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    Ok(())
+}
+
+/// Read a 16 bit PCM WAV file as mono samples with its sample rate
+///
+/// Channels are mixed down to mono.
+pub fn read_wav(path: &Path) -> Result<(Vec<f32>, u32)> {
+    let mut data = Vec::new();
+    File::open(path)?.read_to_end(&mut data)?;
+
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err(anyhow!("Not a WAV file"));
+    }
+
+    let mut format = None;
+    let mut offset = 12;
+    while offset + 8 <= data.len() {
+        let id = &data[offset..offset + 4];
+        let size = u32::from_le_bytes(data[offset + 4..offset + 8].try_into()?) as usize;
+        let body = &data[offset + 8..(offset + 8 + size).min(data.len())];
+
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let audio_format = u16::from_le_bytes([body[0], body[1]]);
+                let channels = u16::from_le_bytes([body[2], body[3]]) as usize;
+                let sample_rate = u32::from_le_bytes(body[4..8].try_into()?);
+                let bits = u16::from_le_bytes([body[14], body[15]]);
+                if audio_format != 1 || bits != 16 || channels == 0 {
+                    return Err(anyhow!("Only 16 bit PCM WAV files are supported"));
+                }
+                format = Some((channels, sample_rate));
+            }
+            b"data" => {
+                let (channels, sample_rate) =
+                    format.ok_or_else(|| anyhow!("WAV format missing"))?;
+                let samples = body
+                    .chunks_exact(2 * channels)
+                    .map(|frame| {
+                        let sum: f32 = frame
+                            .chunks_exact(2)
+                            .map(|sample| i16::from_le_bytes([sample[0], sample[1]]) as f32)
+                            .sum();
+                        sum / channels as f32 / i16::MAX as f32
+                    })
+                    .collect();
+                return Ok((samples, sample_rate));
+            }
+            _ => {}
+        }
+
+        // chunks are padded to an even size
+        offset += 8 + size + size % 2;
+    }
+
+    Err(anyhow!("WAV data missing"))
+}
+
+/// Resample audio with linear interpolation
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let len = (samples.len() as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let step = from_rate as f64 / to_rate as f64;
+    (0..len)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            let current = samples[index.min(samples.len() - 1)];
+            let next = samples[(index + 1).min(samples.len() - 1)];
+            current + (next - current) * fraction
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_roundtrip() {
+        let path = std::env::temp_dir().join(format!("wav-test-{}.wav", uuid::Uuid::new_v4()));
+        let samples = vec![0.0, 0.5, -0.5, 1.0, -1.0];
+
+        let mut writer = WavWriter::create(&path, 16000).unwrap();
+        writer.write(&samples[..2]).unwrap();
+        writer.write(&samples[2..]).unwrap();
+        writer.finalize().unwrap();
+
+        let (read, sample_rate) = read_wav(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(sample_rate, 16000);
+        assert_eq!(read.len(), samples.len());
+        for (a, b) in read.iter().zip(samples.iter()) {
+            assert!((a - b).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_resample() {
+        let samples: Vec<f32> = (0..480).map(|i| i as f32).collect();
+
+        let down = resample(&samples, 48000, 16000);
+        assert_eq!(down.len(), 160);
+        assert_eq!(down[1], 3.0);
+
+        let up = resample(&[0.0, 1.0], 8000, 16000);
+        assert_eq!(up, vec![0.0, 0.5, 1.0, 1.0]);
+    }
+}
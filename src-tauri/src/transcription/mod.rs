@@ -0,0 +1,26 @@
+pub mod audio;
+pub mod service;
+
+use service::{TranscriptionManager, TranscriptionServiceInfo};
+use tauri::AppHandle;
+
+#[tauri_crate::command]
+pub async fn init_transcription_service(
+    app_handle: AppHandle,
+    manager: tauri::State<'_, TranscriptionManager>,
+) -> Result<(), String> {
+    manager
+        .initialize(app_handle)
+        .await
+        .map_err(|e| format!("Failed to initialize transcription service: {}", e))
+}
+
+#[tauri_crate::command]
+pub async fn transcription_service_info(
+    manager: tauri::State<'_, TranscriptionManager>,
+) -> Result<TranscriptionServiceInfo, String> {
+    manager
+        .get_model_info()
+        .await
+        .map_err(|e| format!("Failed to get transcription service info: {}", e))
+}
@@ -0,0 +1,522 @@
+use anyhow::{Error as E, Result};
+use candle_core::{Device, IndexOp, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::whisper::{self as m, audio, model::Whisper, Config};
+use hf_hub::{api::tokio::ApiBuilder, Cache, Repo, RepoType};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tokenizers::Tokenizer;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::constants::{TRANSCRIPTION_MODEL_ID, TRANSCRIPTION_MODEL_REVISION};
+
+/// Sample rate of the audio the model expects
+pub const SAMPLE_RATE: u32 = m::SAMPLE_RATE as u32;
+
+/// Transcribed part of the audio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    /// start in seconds from the beginning of the audio
+    pub start: f32,
+    /// end in seconds from the beginning of the audio
+    pub end: f32,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionServiceInfo {
+    pub model_id: String,
+    pub revision: String,
+    pub device: String,
+    pub is_ready: bool,
+}
+
+/// Offline speech to text with a Whisper model
+pub struct TranscriptionService {
+    model: Option<Whisper>,
+    tokenizer: Option<Tokenizer>,
+    config: Option<Config>,
+    mel_filters: Vec<f32>,
+    device: Device,
+    model_id: String,
+    revision: String,
+    is_ready: bool,
+}
+
+struct ProgressState {
+    current: usize,
+    total: usize,
+}
+
+#[derive(Clone)]
+struct ModelDownloadProgress {
+    app_handle: AppHandle,
+    state: Arc<Mutex<ProgressState>>,
+}
+
+impl hf_hub::api::tokio::Progress for ModelDownloadProgress {
+    async fn init(&mut self, size: usize, _filename: &str) {
+        let mut state = self.state.lock().await;
+        state.total = size;
+        state.current = 0;
+    }
+
+    async fn update(&mut self, current_chunk_size: usize) {
+        let mut state = self.state.lock().await;
+        if state.total > 0 {
+            state.current += current_chunk_size;
+            let progress = state.current as f32 / state.total as f32;
+            let _ = self
+                .app_handle
+                .emit("transcription-status", progress * 95.0);
+        }
+    }
+
+    async fn finish(&mut self) {
+        let _ = self.app_handle.emit("transcription-status", 95.0);
+    }
+}
+
+impl TranscriptionService {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            model: None,
+            tokenizer: None,
+            config: None,
+            mel_filters: Vec::new(),
+            device: Device::Cpu,
+            model_id: TRANSCRIPTION_MODEL_ID.to_string(),
+            revision: TRANSCRIPTION_MODEL_REVISION.to_string(),
+            is_ready: false,
+        })
+    }
+
+    pub async fn initialize(&mut self, app_handle: AppHandle) -> Result<()> {
+        if self.is_ready {
+            app_handle.emit("transcription-status", 100.0)?;
+            return Ok(());
+        }
+
+        app_handle.emit("transcription-status", 0.0)?;
+
+        let result = self.download_and_load_model(app_handle.clone()).await;
+        self.is_ready = result.is_ok();
+
+        let progress = if self.is_ready { 100.0 } else { -1.0 };
+        app_handle.emit("transcription-status", progress)?;
+
+        result
+    }
+
+    async fn download_and_load_model(&mut self, app_handle: AppHandle) -> Result<()> {
+        let repo = Repo::with_revision(
+            self.model_id.clone(),
+            RepoType::Model,
+            self.revision.clone(),
+        );
+
+        let cache = Cache::default();
+        let api = ApiBuilder::from_cache(cache.clone())
+            .build()?
+            .repo(repo.clone());
+
+        let config_filename = api.get("config.json").await?;
+        let tokenizer_filename = api.get("tokenizer.json").await?;
+
+        let progress_tracker = ModelDownloadProgress {
+            app_handle: app_handle.clone(),
+            state: Arc::new(Mutex::new(ProgressState {
+                total: 0,
+                current: 0,
+            })),
+        };
+
+        let weights_filename = if let Some(path) = cache.repo(repo.clone()).get("model.safetensors")
+        {
+            tracing::info!("Transcription - Using cached weights file");
+            path
+        } else {
+            api.download_with_progress("model.safetensors", progress_tracker)
+                .await?
+        };
+
+        app_handle.emit("transcription-status", 96.0)?;
+        let config_str = tokio::fs::read_to_string(&config_filename)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!("Failed to read config file at {:?}: {}", config_filename, e)
+            })?;
+        let config: Config = serde_json::from_str(&config_str)
+            .map_err(|e| anyhow::anyhow!("Failed to parse config.json: {}", e))?;
+
+        app_handle.emit("transcription-status", 97.0)?;
+        let tokenizer = tokio::task::spawn_blocking(move || {
+            Tokenizer::from_file(tokenizer_filename).map_err(E::msg)
+        })
+        .await??;
+
+        app_handle.emit("transcription-status", 98.0)?;
+        let device = self.device.clone();
+        let model_config = config.clone();
+        let model = tokio::task::spawn_blocking(move || -> Result<Whisper> {
+            let vb = unsafe {
+                VarBuilder::from_mmaped_safetensors(&[weights_filename], m::DTYPE, &device)
+                    .map_err(|e| anyhow::anyhow!("Failed to load weights: {}", e))?
+            };
+            Ok(Whisper::load(&vb, model_config)?)
+        })
+        .await??;
+
+        self.mel_filters = mel_filters(SAMPLE_RATE, m::N_FFT, config.num_mel_bins);
+        self.model = Some(model);
+        self.tokenizer = Some(tokenizer);
+        self.config = Some(config);
+
+        Ok(())
+    }
+
+    /// Transcribe mono audio with `SAMPLE_RATE` samples per second
+    ///
+    /// The audio is decoded greedily in windows of 30 seconds.
+    /// `language` is a language code like `en`. Without it,
+    /// multilingual models detect the language of the first window.
+    /// English only models only accept `en`.
+    pub fn transcribe(
+        &mut self,
+        pcm: &[f32],
+        language: Option<&str>,
+    ) -> Result<Vec<TranscriptSegment>> {
+        let (Some(model), Some(tokenizer), Some(config)) = (
+            self.model.as_mut(),
+            self.tokenizer.as_ref(),
+            self.config.as_ref(),
+        ) else {
+            return Err(anyhow::anyhow!("Service not initialized"));
+        };
+
+        let token = |token: &str| {
+            tokenizer
+                .token_to_id(token)
+                .ok_or_else(|| anyhow::anyhow!("Token {} not found", token))
+        };
+        let sot_token = token(m::SOT_TOKEN)?;
+        let transcribe_token = token(m::TRANSCRIBE_TOKEN)?;
+        let eot_token = token(m::EOT_TOKEN)?;
+        let no_timestamps_token = token(m::NO_TIMESTAMPS_TOKEN)?;
+        let language_tokens = language_tokens(tokenizer);
+        let mut language_token = match language {
+            Some(language) if language_tokens.is_empty() => {
+                if language != "en" {
+                    return Err(anyhow::anyhow!("The model only transcribes English"));
+                }
+                None
+            }
+            Some(language) => Some(
+                language_tokens
+                    .iter()
+                    .find(|(code, _)| code == language)
+                    .map(|(_, token)| *token)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown language code: {}", language))?,
+            ),
+            None => None,
+        };
+
+        let mel = audio::pcm_to_mel(config, pcm, &self.mel_filters);
+        let mel_len = mel.len() / config.num_mel_bins;
+        let mel = Tensor::from_vec(mel, (1, config.num_mel_bins, mel_len), &self.device)?;
+
+        // frames of the audio itself, without the padding of the last window
+        let content_frames = (pcm.len() / m::HOP_LENGTH).min(mel_len);
+        let frame_secs = m::HOP_LENGTH as f32 / m::SAMPLE_RATE as f32;
+        let sample_len = config.max_target_positions / 2;
+
+        let mut segments = Vec::new();
+        let mut seek = 0;
+        while seek < content_frames {
+            let segment_size = usize::min(content_frames - seek, m::N_FRAMES);
+            let mel_segment = mel.narrow(2, seek, segment_size)?;
+            let audio_features = model.encoder.forward(&mel_segment, true)?;
+
+            if language_token.is_none() && !language_tokens.is_empty() {
+                let detected = detect_language(
+                    model,
+                    &audio_features,
+                    sot_token,
+                    &language_tokens,
+                    &self.device,
+                )?;
+                log::info!(
+                    "Detected language: {}",
+                    tokenizer.id_to_token(detected).unwrap_or_default()
+                );
+                language_token = Some(detected);
+            }
+
+            let mut tokens = vec![sot_token];
+            if let Some(language_token) = language_token {
+                tokens.push(language_token);
+            }
+            tokens.push(transcribe_token);
+            tokens.push(no_timestamps_token);
+            let prompt_len = tokens.len();
+
+            for i in 0..sample_len {
+                let tokens_t = Tensor::new(tokens.as_slice(), &self.device)?.unsqueeze(0)?;
+                let ys = model.decoder.forward(&tokens_t, &audio_features, i == 0)?;
+                let (_, seq_len, _) = ys.dims3()?;
+                let logits = model
+                    .decoder
+                    .final_linear(&ys.i((..1, seq_len - 1..))?)?
+                    .i(0)?
+                    .i(0)?;
+                let logits: Vec<f32> = logits.to_vec1()?;
+                let next_token = logits
+                    .iter()
+                    .enumerate()
+                    .max_by(|a, b| a.1.total_cmp(b.1))
+                    .map(|(token, _)| token as u32)
+                    .unwrap_or(eot_token);
+                if next_token == eot_token {
+                    break;
+                }
+                tokens.push(next_token);
+            }
+
+            let text = tokenizer
+                .decode(&tokens[prompt_len..], true)
+                .map_err(E::msg)?;
+            let text = text.trim();
+            if !text.is_empty() {
+                segments.push(TranscriptSegment {
+                    start: seek as f32 * frame_secs,
+                    end: (seek + segment_size) as f32 * frame_secs,
+                    text: text.to_string(),
+                });
+            }
+
+            seek += segment_size;
+        }
+
+        Ok(segments)
+    }
+}
+
+/// Language codes and tokens of a multilingual model
+///
+/// English only models have no language tokens.
+fn language_tokens(tokenizer: &Tokenizer) -> Vec<(String, u32)> {
+    let mut tokens: Vec<(String, u32)> = tokenizer
+        .get_vocab(true)
+        .into_iter()
+        .filter_map(|(token, id)| {
+            let code = token.strip_prefix("<|")?.strip_suffix("|>")?;
+            let is_language = (2..=3).contains(&code.len())
+                && code.chars().all(|c| c.is_ascii_lowercase());
+            is_language.then(|| (code.to_string(), id))
+        })
+        .collect();
+    tokens.sort_by_key(|(_, id)| *id);
+    tokens
+}
+
+/// Detect the spoken language of a window
+///
+/// Returns the language token with the highest probability
+/// after the start of transcript token.
+fn detect_language(
+    model: &mut Whisper,
+    audio_features: &Tensor,
+    sot_token: u32,
+    language_tokens: &[(String, u32)],
+    device: &Device,
+) -> Result<u32> {
+    let tokens = Tensor::new(&[sot_token], device)?.unsqueeze(0)?;
+    let ys = model.decoder.forward(&tokens, audio_features, true)?;
+    let logits: Vec<f32> = model
+        .decoder
+        .final_linear(&ys.i((..1, ..1))?)?
+        .i(0)?
+        .i(0)?
+        .to_vec1()?;
+
+    language_tokens
+        .iter()
+        .map(|(_, token)| *token)
+        .max_by(|a, b| logits[*a as usize].total_cmp(&logits[*b as usize]))
+        .ok_or_else(|| anyhow::anyhow!("Model has no language tokens"))
+}
+
+/// Mel filterbank as used by Whisper
+///
+/// Slaney style mel scale and area normalization, as in
+/// `librosa.filters.mel`, in row major order
+/// `[n_mels][n_fft / 2 + 1]` as `audio::pcm_to_mel` expects it.
+pub fn mel_filters(sample_rate: u32, n_fft: usize, n_mels: usize) -> Vec<f32> {
+    const F_SP: f64 = 200.0 / 3.0;
+    const MIN_LOG_HZ: f64 = 1000.0;
+    const MIN_LOG_MEL: f64 = MIN_LOG_HZ / F_SP;
+    let log_step = 6.4f64.ln() / 27.0;
+
+    let hz_to_mel = |hz: f64| match hz < MIN_LOG_HZ {
+        true => hz / F_SP,
+        false => MIN_LOG_MEL + (hz / MIN_LOG_HZ).ln() / log_step,
+    };
+    let mel_to_hz = |mel: f64| match mel < MIN_LOG_MEL {
+        true => mel * F_SP,
+        false => MIN_LOG_HZ * (log_step * (mel - MIN_LOG_MEL)).exp(),
+    };
+
+    let n_bins = n_fft / 2 + 1;
+    let nyquist = sample_rate as f64 / 2.0;
+    let fft_freqs: Vec<f64> = (0..n_bins)
+        .map(|bin| bin as f64 * nyquist / (n_bins - 1) as f64)
+        .collect();
+
+    let max_mel = hz_to_mel(nyquist);
+    let mel_freqs: Vec<f64> = (0..n_mels + 2)
+        .map(|i| mel_to_hz(max_mel * i as f64 / (n_mels + 1) as f64))
+        .collect();
+
+    let mut filters = vec![0f32; n_mels * n_bins];
+    for mel in 0..n_mels {
+        let (lower, center, upper) = (mel_freqs[mel], mel_freqs[mel + 1], mel_freqs[mel + 2]);
+        let norm = 2.0 / (upper - lower);
+        for (bin, freq) in fft_freqs.iter().enumerate() {
+            let rising = (freq - lower) / (center - lower);
+            let falling = (upper - freq) / (upper - center);
+            let weight = rising.min(falling).max(0.0);
+            filters[mel * n_bins + bin] = (weight * norm) as f32;
+        }
+    }
+
+    filters
+}
+
+pub struct TranscriptionManager {
+    service: Arc<RwLock<Option<TranscriptionService>>>,
+}
+
+impl TranscriptionManager {
+    pub fn new() -> Self {
+        Self {
+            service: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Download and load the model, if it is not loaded yet
+    pub async fn initialize(&self, app_handle: AppHandle) -> Result<()> {
+        let mut service_lock = self.service.write().await;
+
+        match service_lock.as_mut() {
+            Some(service) => service.initialize(app_handle).await,
+            None => {
+                let mut service = TranscriptionService::new()?;
+                service.initialize(app_handle).await?;
+                *service_lock = Some(service);
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn get_model_info(&self) -> Result<TranscriptionServiceInfo> {
+        let service_lock = self.service.read().await;
+
+        let service = service_lock
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Service not initialized"))?;
+
+        Ok(TranscriptionServiceInfo {
+            model_id: service.model_id.clone(),
+            revision: service.revision.clone(),
+            device: if service.device.is_cpu() {
+                "CPU".to_string()
+            } else {
+                "GPU".to_string()
+            },
+            is_ready: service.is_ready,
+        })
+    }
+
+    /// Transcribe mono audio with `SAMPLE_RATE` samples per second
+    pub async fn transcribe(
+        &self,
+        pcm: Vec<f32>,
+        language: Option<String>,
+    ) -> Result<Vec<TranscriptSegment>> {
+        let mut service_lock = self.service.write().await;
+
+        let service = service_lock
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Service not initialized"))?;
+
+        tokio::task::block_in_place(|| service.transcribe(&pcm, language.as_deref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mel_filters_shape() {
+        let filters = mel_filters(16000, 400, 80);
+        assert_eq!(filters.len(), 80 * 201);
+        assert!(filters.iter().all(|weight| *weight >= 0.0));
+
+        // every filter covers some frequencies
+        for mel in 0..80 {
+            assert!(filters[mel * 201..(mel + 1) * 201]
+                .iter()
+                .any(|weight| *weight > 0.0));
+        }
+    }
+
+    #[test]
+    fn test_mel_filters_match_whisper() {
+        // first filter of Whisper's 80 bin filterbank
+        let filters = mel_filters(16000, 400, 80);
+        assert_eq!(filters[0], 0.0);
+        assert!((filters[1] - 0.024_862_59).abs() < 1e-5);
+        assert_eq!(filters[3], 0.0);
+    }
+
+    #[test]
+    fn test_language_tokens() {
+        use tokenizers::models::wordlevel::WordLevel;
+        use tokenizers::AddedToken;
+
+        let vocab = [("[UNK]".to_string(), 0), ("hello".to_string(), 1)].into();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".to_string())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        let special: Vec<AddedToken> = [
+            "<|endoftext|>",
+            "<|startoftranscript|>",
+            "<|en|>",
+            "<|de|>",
+            "<|haw|>",
+            "<|transcribe|>",
+            "<|notimestamps|>",
+        ]
+        .iter()
+        .map(|token| AddedToken::from(*token, true))
+        .collect();
+        tokenizer.add_special_tokens(&special);
+
+        let codes: Vec<String> = language_tokens(&tokenizer)
+            .into_iter()
+            .map(|(code, _)| code)
+            .collect();
+        assert_eq!(codes, vec!["en", "de", "haw"]);
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_without_model() {
+        let manager = TranscriptionManager::new();
+        assert!(manager.transcribe(vec![0.0; 16000], None).await.is_err());
+    }
+}
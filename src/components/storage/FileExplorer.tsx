@@ -97,7 +97,8 @@ const FileExplorer: React.FC<FileExplorerProps> = ({ viewMode = 'grid' }) => {
     try {
       await invoke('download_drive_file', {
         fileId: file.data_id,
-        fileName: file.name
+        fileName: file.name,
+        mimeType: file.mime_type
       });
     } catch (err) {
       console.error('Failed to download file:', err);
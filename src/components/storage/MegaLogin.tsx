@@ -1,4 +1,4 @@
-import React, { useState } from 'react';
+import React, { useEffect, useState } from 'react';
 import { Cloud, Eye, EyeOff } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
 
@@ -14,6 +14,17 @@ const MegaLogin: React.FC<MegaLoginProps> = ({ onLoginSuccess }) => {
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState('');
 
+  // Resume the session persisted by a previous login, if any
+  useEffect(() => {
+    invoke<boolean>('mega_resume_session')
+      .then((resumed) => {
+        if (resumed) {
+          onLoginSuccess();
+        }
+      })
+      .catch((err) => console.error('Failed to resume MEGA session:', err));
+  }, [onLoginSuccess]);
+
   const handleLogin = async (e: React.FormEvent) => {
     e.preventDefault();
     
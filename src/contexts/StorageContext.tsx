@@ -11,6 +11,13 @@ interface GDriveItem {
   url?: string;
   parent_id?: string;
   children?: GDriveItem[];
+  mime_type?: string;
+}
+
+interface DriveFolderPage {
+  items: GDriveItem[];
+  next_page_token?: string;
+  cached: boolean;
 }
 
 interface DownloadRecord {
@@ -43,7 +50,7 @@ interface StorageContextType {
   setDownloadsPath: (path: GDriveItem[]) => void;
   downloadTasks: DownloadTask[];
   setDownloadTasks: (tasks: DownloadTask[]) => void;
-  navigateToFolder: (folder: GDriveItem) => void;
+  navigateToFolder: (folder: GDriveItem) => Promise<void>;
   navigateUp: () => void;
   navigateToRoot: () => void;
   loadRootFolder: () => Promise<void>;
@@ -131,9 +138,33 @@ const loadRootFolder = useCallback(async () => {
     }
   }, []);
 
-  const navigateToFolder = useCallback((folder: GDriveItem) => {
-    if (folder.is_folder) {
+  const navigateToFolder = useCallback(async (folder: GDriveItem) => {
+    if (!folder.is_folder) return;
+    if (!folder.data_id) {
       setCurrentPath(prev => [...prev, folder]);
+      return;
+    }
+
+    try {
+      setLoading(true);
+      // List the folder live from Drive, page by page
+      const items: GDriveItem[] = [];
+      let pageToken: string | undefined;
+      do {
+        const page: DriveFolderPage = await invoke<DriveFolderPage>('list_drive_folder', {
+          folderId: folder.data_id,
+          pageToken,
+        });
+        items.push(...page.items);
+        pageToken = page.next_page_token ?? undefined;
+      } while (pageToken);
+      setCurrentPath(prev => [...prev, { ...folder, children: items }]);
+      setError(null);
+    } catch (err) {
+      console.error('Error listing folder:', err);
+      setError('Failed to load folder');
+    } finally {
+      setLoading(false);
     }
   }, []);
 
@@ -187,14 +218,13 @@ const loadRootFolder = useCallback(async () => {
     const setupDownloadListener = async () => {
       await listen('download-progress', (event) => {
         const progress = event.payload as {
+          id: string;
           status: string;
-          file_id?: string;
-          fileName?: string;
-          file_name?: string;
+          file_name: string;
           percentage: number;
         };
         
-        const fileId = progress.file_id || progress.fileName || 'unknown';
+        const fileId = progress.id;
         
         if (progress.status === 'downloading') {
           setDownloadTasks(prev => {
@@ -228,7 +258,7 @@ const loadRootFolder = useCallback(async () => {
           }, 3000);
           loadDownloadedFiles();
           loadRecordedDownloads();
-        } else if (typeof progress.status === 'string' && progress.status.includes('error')) {
+        } else if (progress.status === 'failed') {
           setDownloadTasks(prev => 
             prev.map(task => 
               task.id === fileId 